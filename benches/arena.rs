@@ -0,0 +1,93 @@
+// arena分配节点的链表和每节点一次Box的链表对比
+// cargo bench --bench arena
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use too_many_linked_list_rs::arena_list::ArenaList;
+use too_many_linked_list_rs::simple_deque_2;
+use too_many_linked_list_rs::simple_deque_3::List;
+
+const SIZES: [usize; 3] = [1_000, 100_000, 1_000_000];
+
+// 建表再整体丢弃: arena只按块申请/释放，Box版本每个节点各一次
+fn build_and_drop(c: &mut Criterion) {
+    let mut group = c.benchmark_group("build_and_drop");
+    for n in SIZES {
+        group.bench_with_input(BenchmarkId::new("arena_list", n), &n, |b, &n| {
+            b.iter(|| {
+                let mut list = ArenaList::new();
+                for i in 0..n {
+                    list.push_back(i);
+                }
+                black_box(&list);
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("simple_deque_3", n), &n, |b, &n| {
+            b.iter(|| {
+                let mut list = List::new();
+                for i in 0..n {
+                    list.push_back(i);
+                }
+                black_box(&list);
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("simple_deque_2", n), &n, |b, &n| {
+            b.iter(|| {
+                let mut list = simple_deque_2::List::new();
+                for i in 0..n {
+                    list.push_back(i);
+                }
+                black_box(&list);
+            })
+        });
+    }
+    group.finish();
+}
+
+// clear之后复用已有的块，稳态下完全不碰分配器
+fn refill_after_clear(c: &mut Criterion) {
+    let mut group = c.benchmark_group("refill_after_clear");
+    for n in SIZES {
+        let mut arena = ArenaList::new();
+        arena.extend(0..n);
+        group.bench_with_input(BenchmarkId::new("arena_list", n), &n, |b, &n| {
+            b.iter(|| {
+                arena.clear();
+                for i in 0..n {
+                    arena.push_back(i);
+                }
+                black_box(arena.len());
+            })
+        });
+        let mut boxed = List::new();
+        group.bench_with_input(BenchmarkId::new("simple_deque_3", n), &n, |b, &n| {
+            b.iter(|| {
+                boxed.clear();
+                for i in 0..n {
+                    boxed.push_back(i);
+                }
+                black_box(boxed.len());
+            })
+        });
+    }
+    group.finish();
+}
+
+// 节点基本连续，遍历时的缓存表现也更好
+fn iterate(c: &mut Criterion) {
+    let mut group = c.benchmark_group("iterate_u64");
+    for n in SIZES {
+        let arena: ArenaList<u64> = (0..n as u64).collect();
+        let mut boxed = List::new();
+        boxed.extend(0..n as u64);
+        group.bench_with_input(BenchmarkId::new("arena_list", n), &arena, |b, list| {
+            b.iter(|| black_box(list.iter().sum::<u64>()))
+        });
+        group.bench_with_input(BenchmarkId::new("simple_deque_3", n), &boxed, |b, list| {
+            b.iter(|| black_box(list.iter().sum::<u64>()))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, build_and_drop, refill_after_clear, iterate);
+criterion_main!(benches);
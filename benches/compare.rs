@@ -0,0 +1,248 @@
+// 把crate里几种基础链表和标准库容器放在同一组负载下对比，看链表到底在哪里占便宜、在哪里吃亏
+// - push_pop: 一端进一端出(栈)，每个元素一次分配，Vec/VecDeque靠摊还的扩容几乎不分配
+// - queue: back进front出，simple_deque_1每一步都要动Rc和RefCell
+// - iterate: 顺序遍历求和，连续内存和指针追逐的差别最明显；simple_deque_1拿不到借用迭代器，不参加
+// - splice: 从中间切成两段再接回去，链表要先走到中间但拼接本身O(1)，Vec要搬一半元素
+// 不支持某个负载的实现在表里记为"—"
+// criterion的结果之外，最后再用Instant粗测一遍，打印n = TABLE_SIZE时每个元素的纳秒数的markdown表格
+// cargo bench --bench compare
+
+use std::collections::{LinkedList, VecDeque};
+use std::time::{Duration, Instant};
+
+use criterion::{black_box, BenchmarkId, Criterion, Throughput};
+use too_many_linked_list_rs::traits::{Queue, Stack};
+use too_many_linked_list_rs::unrolled_list::UnrolledList;
+use too_many_linked_list_rs::{simple_deque_1, simple_deque_2, simple_deque_3, simple_stack_2};
+
+const SIZES: [usize; 2] = [1_000, 100_000];
+const TABLE_SIZE: usize = 100_000;
+const TABLE_RUNS: usize = 5;
+
+// 准备函数按规模建好输入，返回真正被计时的闭包；闭包要能反复调用，返回值防止被优化掉
+type Prepare = fn(usize) -> Box<dyn FnMut() -> u64>;
+
+struct Workload {
+    name: &'static str,
+    cases: &'static [(&'static str, Prepare)],
+}
+
+const IMPLS: [&str; 8] = [
+    "simple_stack_2",
+    "simple_deque_1",
+    "simple_deque_2",
+    "simple_deque_3",
+    "unrolled_16",
+    "Vec",
+    "VecDeque",
+    "LinkedList",
+];
+
+fn push_pop<S: Stack<u64> + Default>(n: usize) -> Box<dyn FnMut() -> u64> {
+    Box::new(move || {
+        let mut stack = S::default();
+        for i in 0..n as u64 {
+            stack.push(i);
+        }
+        std::iter::from_fn(|| stack.pop()).sum()
+    })
+}
+
+fn queue<Q: Queue<u64> + Default>(n: usize) -> Box<dyn FnMut() -> u64> {
+    Box::new(move || {
+        let mut queue = Q::default();
+        for i in 0..n as u64 {
+            queue.enqueue(i);
+        }
+        std::iter::from_fn(|| queue.dequeue()).sum()
+    })
+}
+
+// 遍历用的表先用Stack建好，只计时求和
+fn filled<S: Stack<u64> + Default>(n: usize) -> S {
+    let mut stack = S::default();
+    for i in 0..n as u64 {
+        stack.push(i);
+    }
+    stack
+}
+
+fn iterate_stack_2(n: usize) -> Box<dyn FnMut() -> u64> {
+    let list: simple_stack_2::List<u64> = filled(n);
+    Box::new(move || list.iter().sum())
+}
+
+fn iterate_deque_2(n: usize) -> Box<dyn FnMut() -> u64> {
+    let mut list = simple_deque_2::List::new();
+    for i in 0..n as u64 {
+        list.push_back(i);
+    }
+    Box::new(move || list.iter().sum())
+}
+
+fn iterate_deque_3(n: usize) -> Box<dyn FnMut() -> u64> {
+    let list: simple_deque_3::List<u64> = filled(n);
+    Box::new(move || list.iter().sum())
+}
+
+fn iterate_unrolled(n: usize) -> Box<dyn FnMut() -> u64> {
+    let list: UnrolledList<u64> = filled(n);
+    Box::new(move || list.iter().sum())
+}
+
+fn iterate_vec(n: usize) -> Box<dyn FnMut() -> u64> {
+    let list: Vec<u64> = filled(n);
+    Box::new(move || list.iter().sum())
+}
+
+fn iterate_vec_deque(n: usize) -> Box<dyn FnMut() -> u64> {
+    let list: VecDeque<u64> = filled(n);
+    Box::new(move || list.iter().sum())
+}
+
+fn iterate_linked_list(n: usize) -> Box<dyn FnMut() -> u64> {
+    let list: LinkedList<u64> = filled(n);
+    Box::new(move || list.iter().sum())
+}
+
+// 游标走到中间，切下前半段，再把后半段接回它后面
+fn splice_deque_3(n: usize) -> Box<dyn FnMut() -> u64> {
+    let mut list: simple_deque_3::List<u64> = filled(n);
+    Box::new(move || {
+        let mut cursor = list.cursor_mut();
+        for _ in 0..=n / 2 {
+            cursor.move_next();
+        }
+        let mut front = cursor.split_before();
+        front.append(&mut list);
+        list = front;
+        list.len() as u64
+    })
+}
+
+fn splice_vec(n: usize) -> Box<dyn FnMut() -> u64> {
+    let mut list: Vec<u64> = filled(n);
+    Box::new(move || {
+        let back = list.split_off(n / 2);
+        list.extend(back);
+        list.len() as u64
+    })
+}
+
+fn splice_vec_deque(n: usize) -> Box<dyn FnMut() -> u64> {
+    let mut list: VecDeque<u64> = filled(n);
+    Box::new(move || {
+        let mut back = list.split_off(n / 2);
+        list.append(&mut back);
+        list.len() as u64
+    })
+}
+
+fn splice_linked_list(n: usize) -> Box<dyn FnMut() -> u64> {
+    let mut list: LinkedList<u64> = filled(n);
+    Box::new(move || {
+        let mut back = list.split_off(n / 2);
+        list.append(&mut back);
+        list.len() as u64
+    })
+}
+
+const WORKLOADS: [Workload; 4] = [
+    Workload {
+        name: "push_pop",
+        cases: &[
+            ("simple_stack_2", push_pop::<simple_stack_2::List<u64>>),
+            ("simple_deque_1", push_pop::<simple_deque_1::List<u64>>),
+            ("simple_deque_3", push_pop::<simple_deque_3::List<u64>>),
+            ("unrolled_16", push_pop::<UnrolledList<u64>>),
+            ("Vec", push_pop::<Vec<u64>>),
+            ("VecDeque", push_pop::<VecDeque<u64>>),
+            ("LinkedList", push_pop::<LinkedList<u64>>),
+        ],
+    },
+    Workload {
+        name: "queue",
+        cases: &[
+            ("simple_deque_1", queue::<simple_deque_1::List<u64>>),
+            ("simple_deque_2", queue::<simple_deque_2::List<u64>>),
+            ("simple_deque_3", queue::<simple_deque_3::List<u64>>),
+            ("unrolled_16", queue::<UnrolledList<u64>>),
+            ("VecDeque", queue::<VecDeque<u64>>),
+            ("LinkedList", queue::<LinkedList<u64>>),
+        ],
+    },
+    Workload {
+        name: "iterate",
+        cases: &[
+            ("simple_stack_2", iterate_stack_2),
+            ("simple_deque_2", iterate_deque_2),
+            ("simple_deque_3", iterate_deque_3),
+            ("unrolled_16", iterate_unrolled),
+            ("Vec", iterate_vec),
+            ("VecDeque", iterate_vec_deque),
+            ("LinkedList", iterate_linked_list),
+        ],
+    },
+    Workload {
+        name: "splice",
+        cases: &[
+            ("simple_deque_3", splice_deque_3),
+            ("Vec", splice_vec),
+            ("VecDeque", splice_vec_deque),
+            ("LinkedList", splice_linked_list),
+        ],
+    },
+];
+
+fn bench_workloads(c: &mut Criterion) {
+    for workload in &WORKLOADS {
+        let mut group = c.benchmark_group(format!("compare_{}", workload.name));
+        group.sample_size(20);
+        for n in SIZES {
+            group.throughput(Throughput::Elements(n as u64));
+            for &(name, prepare) in workload.cases {
+                let mut work = prepare(n);
+                group.bench_function(BenchmarkId::new(name, n), |b| b.iter(|| black_box(work())));
+            }
+        }
+        group.finish();
+    }
+}
+
+// 取TABLE_RUNS次里最快的一次，换算成每个元素的纳秒数
+fn ns_per_elem(prepare: Prepare) -> f64 {
+    let mut work = prepare(TABLE_SIZE);
+    let mut best = Duration::MAX;
+    for _ in 0..TABLE_RUNS {
+        let start = Instant::now();
+        black_box(work());
+        best = best.min(start.elapsed());
+    }
+    best.as_nanos() as f64 / TABLE_SIZE as f64
+}
+
+fn print_table() {
+    println!("\nns per element, n = {TABLE_SIZE}\n");
+    println!("| workload | {} |", IMPLS.join(" | "));
+    println!("|---|{}", "---:|".repeat(IMPLS.len()));
+    for workload in &WORKLOADS {
+        let cells: Vec<String> = IMPLS
+            .iter()
+            .map(|name| match workload.cases.iter().find(|(case, _)| case == name) {
+                Some(&(_, prepare)) => format!("{:.2}", ns_per_elem(prepare)),
+                None => "—".to_string(),
+            })
+            .collect();
+        println!("| {} | {} |", workload.name, cells.join(" | "));
+    }
+}
+
+fn main() {
+    let mut criterion = Criterion::default().configure_from_args();
+    bench_workloads(&mut criterion);
+    criterion.final_summary();
+    // cargo test --benches和--list只是检查基准能不能跑，不打印表格
+    if !std::env::args().any(|arg| arg == "--test" || arg == "--list") {
+        print_table();
+    }
+}
@@ -0,0 +1,59 @@
+// 高竞争下对比elimination_stack和普通的treiber_stack
+// 每个线程交替push/pop，线程数越多栈顶的CAS冲突越多，消除数组配对的机会也越多
+// 消除要求配对的两个线程同时在跑，核数少于线程数时等待配对的自旋是浪费，收益要在多核上才看得到
+// cargo bench --bench elimination_stack
+
+use std::sync::{Arc, Barrier};
+use std::thread;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use too_many_linked_list_rs::elimination_stack::EliminationStack;
+use too_many_linked_list_rs::treiber_stack::TreiberStack;
+
+const OPS_PER_THREAD: u64 = 10_000;
+const THREADS: [usize; 4] = [2, 4, 8, 16];
+
+// 所有线程就位之后一起开始，返回所有pop到的元素之和防止被优化掉
+fn run<S: Send + Sync + 'static>(threads: usize, stack: S, op: fn(&S, u64) -> u64) -> u64 {
+    let stack = Arc::new(stack);
+    let barrier = Arc::new(Barrier::new(threads));
+    let handles: Vec<_> = (0..threads)
+        .map(|_| {
+            let stack = Arc::clone(&stack);
+            let barrier = Arc::clone(&barrier);
+            thread::spawn(move || {
+                barrier.wait();
+                (0..OPS_PER_THREAD).map(|i| op(&stack, i)).sum::<u64>()
+            })
+        })
+        .collect();
+    handles.into_iter().map(|h| h.join().unwrap()).sum()
+}
+
+fn elimination_op(stack: &EliminationStack<u64>, i: u64) -> u64 {
+    stack.push(i);
+    stack.pop().unwrap_or(0)
+}
+
+fn treiber_op(stack: &TreiberStack<u64>, i: u64) -> u64 {
+    stack.push(i);
+    stack.pop().unwrap_or(0)
+}
+
+fn contention(c: &mut Criterion) {
+    let mut group = c.benchmark_group("stack_contention");
+    group.sample_size(20);
+    for threads in THREADS {
+        group.throughput(Throughput::Elements(2 * OPS_PER_THREAD * threads as u64));
+        group.bench_with_input(BenchmarkId::new("elimination", threads), &threads, |b, &t| {
+            b.iter(|| run(t, EliminationStack::new(), elimination_op))
+        });
+        group.bench_with_input(BenchmarkId::new("treiber", threads), &threads, |b, &t| {
+            b.iter(|| run(t, TreiberStack::new(), treiber_op))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, contention);
+criterion_main!(benches);
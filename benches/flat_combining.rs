@@ -0,0 +1,59 @@
+// 高竞争下对比flat_combining::FcQueue和Mutex<simple_deque_3::List>
+// 每个线程交替push/pop，线程数越多锁竞争越激烈
+// 合并的收益来自多核之间少搬缓存行；核数少于线程数时等待者的自旋纯属浪费，Mutex反而更快
+// cargo bench --bench flat_combining
+
+use std::sync::{Arc, Barrier, Mutex};
+use std::thread;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use too_many_linked_list_rs::flat_combining::FcQueue;
+use too_many_linked_list_rs::simple_deque_3::List;
+
+const OPS_PER_THREAD: u64 = 10_000;
+const THREADS: [usize; 3] = [2, 4, 8];
+
+// 所有线程就位之后一起开始，返回所有pop到的元素之和防止被优化掉
+fn run<Q: Send + Sync + 'static>(threads: usize, queue: Q, op: fn(&Q, u64) -> u64) -> u64 {
+    let queue = Arc::new(queue);
+    let barrier = Arc::new(Barrier::new(threads));
+    let handles: Vec<_> = (0..threads)
+        .map(|_| {
+            let queue = Arc::clone(&queue);
+            let barrier = Arc::clone(&barrier);
+            thread::spawn(move || {
+                barrier.wait();
+                (0..OPS_PER_THREAD).map(|i| op(&queue, i)).sum::<u64>()
+            })
+        })
+        .collect();
+    handles.into_iter().map(|h| h.join().unwrap()).sum()
+}
+
+fn fc_op(queue: &FcQueue<u64>, i: u64) -> u64 {
+    queue.push(i);
+    queue.pop().unwrap_or(0)
+}
+
+fn mutex_op(queue: &Mutex<List<u64>>, i: u64) -> u64 {
+    queue.lock().unwrap().push_back(i);
+    queue.lock().unwrap().pop_front().unwrap_or(0)
+}
+
+fn contention(c: &mut Criterion) {
+    let mut group = c.benchmark_group("queue_contention");
+    group.sample_size(20);
+    for threads in THREADS {
+        group.throughput(Throughput::Elements(2 * OPS_PER_THREAD * threads as u64));
+        group.bench_with_input(BenchmarkId::new("flat_combining", threads), &threads, |b, &t| {
+            b.iter(|| run(t, FcQueue::new(), fc_op))
+        });
+        group.bench_with_input(BenchmarkId::new("mutex_deque", threads), &threads, |b, &t| {
+            b.iter(|| run(t, Mutex::new(List::new()), mutex_op))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, contention);
+criterion_main!(benches);
@@ -0,0 +1,50 @@
+// 对比simple_deque_3两种节点布局的遍历开销
+// cargo bench --bench node_layout
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use too_many_linked_list_rs::simple_deque_3::{CacheAligned, Compact, List, NodeLayout};
+
+const SIZES: [usize; 3] = [1_000, 100_000, 1_000_000];
+
+fn build<E: Copy, L: NodeLayout>(layout: L, n: usize, elem: E) -> List<E, L> {
+    let mut list = List::with_layout(layout);
+    for _ in 0..n {
+        list.push_back(elem);
+    }
+    list
+}
+
+// 元素很小时CacheAligned每个节点独占一条缓存行，遍历需要读更多的行
+fn iterate_small(c: &mut Criterion) {
+    let mut group = c.benchmark_group("iterate_u64");
+    for n in SIZES {
+        let compact = build(Compact, n, 1u64);
+        let aligned = build(CacheAligned, n, 1u64);
+        group.bench_with_input(BenchmarkId::new("compact", n), &compact, |b, list| {
+            b.iter(|| black_box(list.iter().sum::<u64>()))
+        });
+        group.bench_with_input(BenchmarkId::new("cache_aligned", n), &aligned, |b, list| {
+            b.iter(|| black_box(list.iter().sum::<u64>()))
+        });
+    }
+    group.finish();
+}
+
+// 元素接近一条缓存行时，Compact节点会跨行，CacheAligned每个节点正好一行
+fn iterate_line_sized(c: &mut Criterion) {
+    let mut group = c.benchmark_group("iterate_48_bytes");
+    for n in SIZES {
+        let compact = build(Compact, n, [1u64; 6]);
+        let aligned = build(CacheAligned, n, [1u64; 6]);
+        group.bench_with_input(BenchmarkId::new("compact", n), &compact, |b, list| {
+            b.iter(|| black_box(list.iter().map(|e| e[0] + e[5]).sum::<u64>()))
+        });
+        group.bench_with_input(BenchmarkId::new("cache_aligned", n), &aligned, |b, list| {
+            b.iter(|| black_box(list.iter().map(|e| e[0] + e[5]).sum::<u64>()))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, iterate_small, iterate_line_sized);
+criterion_main!(benches);
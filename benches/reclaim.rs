@@ -0,0 +1,73 @@
+// 对比epoch和危险指针两种回收策略下无锁栈/队列的吞吐，以及运行结束时积压的垃圾量
+// cargo bench --bench reclaim
+
+use std::sync::Arc;
+use std::thread;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use too_many_linked_list_rs::ms_queue::MsQueue;
+use too_many_linked_list_rs::reclaim::{Epoch, Hazard, Reclaim};
+use too_many_linked_list_rs::treiber_stack::TreiberStack;
+
+const OPS_PER_THREAD: usize = 10_000;
+const THREADS: [usize; 3] = [1, 2, 4];
+
+// 每个线程交替push/pop
+fn stack_round<R: Reclaim>(reclaim: R, threads: usize) {
+    let stack = Arc::new(TreiberStack::with_reclaim(reclaim));
+    let handles: Vec<_> = (0..threads)
+        .map(|_| {
+            let stack = Arc::clone(&stack);
+            thread::spawn(move || {
+                for i in 0..OPS_PER_THREAD {
+                    stack.push(i);
+                    stack.pop();
+                }
+            })
+        })
+        .collect();
+    for h in handles {
+        h.join().unwrap();
+    }
+}
+
+fn queue_round<R: Reclaim>(reclaim: R, threads: usize) {
+    let queue = Arc::new(MsQueue::with_reclaim(reclaim));
+    let handles: Vec<_> = (0..threads)
+        .map(|_| {
+            let queue = Arc::clone(&queue);
+            thread::spawn(move || {
+                for i in 0..OPS_PER_THREAD {
+                    queue.push(i);
+                    queue.pop();
+                }
+            })
+        })
+        .collect();
+    for h in handles {
+        h.join().unwrap();
+    }
+}
+
+fn treiber_stack(c: &mut Criterion) {
+    let mut group = c.benchmark_group("treiber_push_pop");
+    for n in THREADS {
+        group.bench_with_input(BenchmarkId::new("epoch", n), &n, |b, &n| b.iter(|| stack_round(Epoch, n)));
+        group.bench_with_input(BenchmarkId::new("hazard", n), &n, |b, &n| b.iter(|| stack_round(Hazard, n)));
+    }
+    group.finish();
+    eprintln!("pending after treiber: epoch={} hazard={}", Epoch::pending(), Hazard::pending());
+}
+
+fn ms_queue(c: &mut Criterion) {
+    let mut group = c.benchmark_group("ms_queue_push_pop");
+    for n in THREADS {
+        group.bench_with_input(BenchmarkId::new("epoch", n), &n, |b, &n| b.iter(|| queue_round(Epoch, n)));
+        group.bench_with_input(BenchmarkId::new("hazard", n), &n, |b, &n| b.iter(|| queue_round(Hazard, n)));
+    }
+    group.finish();
+    eprintln!("pending after ms_queue: epoch={} hazard={}", Epoch::pending(), Hazard::pending());
+}
+
+criterion_group!(benches, treiber_stack, ms_queue);
+criterion_main!(benches);
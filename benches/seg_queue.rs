@@ -0,0 +1,96 @@
+// 对比分段的seg_queue和每个元素一个节点的ms_queue在多生产者多消费者下的吞吐
+// cargo bench --bench seg_queue
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use too_many_linked_list_rs::ms_queue::MsQueue;
+use too_many_linked_list_rs::seg_queue::SegQueue;
+
+const PER_PRODUCER: usize = 10_000;
+const THREADS: [usize; 3] = [1, 2, 4];
+
+// 两种队列的公共接口，让同一段压测代码跑在两种队列上
+trait Queue: Send + Sync + 'static {
+    fn new() -> Self;
+    fn push(&self, value: usize);
+    fn pop(&self) -> Option<usize>;
+}
+
+impl Queue for SegQueue<usize> {
+    fn new() -> Self {
+        SegQueue::new()
+    }
+
+    fn push(&self, value: usize) {
+        SegQueue::push(self, value)
+    }
+
+    fn pop(&self) -> Option<usize> {
+        SegQueue::pop(self)
+    }
+}
+
+impl Queue for MsQueue<usize> {
+    fn new() -> Self {
+        MsQueue::new()
+    }
+
+    fn push(&self, value: usize) {
+        MsQueue::push(self, value)
+    }
+
+    fn pop(&self) -> Option<usize> {
+        MsQueue::pop(self)
+    }
+}
+
+// n个生产者和n个消费者，消费者一直取到所有元素都被取走
+fn mpmc_round<Q: Queue>(n: usize) {
+    let queue = Arc::new(Q::new());
+    let remaining = Arc::new(AtomicUsize::new(n * PER_PRODUCER));
+    let mut handles = Vec::new();
+    for _ in 0..n {
+        let queue = Arc::clone(&queue);
+        handles.push(thread::spawn(move || {
+            for i in 0..PER_PRODUCER {
+                queue.push(i);
+            }
+        }));
+    }
+    for _ in 0..n {
+        let queue = Arc::clone(&queue);
+        let remaining = Arc::clone(&remaining);
+        handles.push(thread::spawn(move || {
+            while remaining.load(Ordering::Relaxed) > 0 {
+                if queue.pop().is_some() {
+                    remaining.fetch_sub(1, Ordering::Relaxed);
+                } else {
+                    thread::yield_now();
+                }
+            }
+        }));
+    }
+    for h in handles {
+        h.join().unwrap();
+    }
+}
+
+fn mpmc(c: &mut Criterion) {
+    let mut group = c.benchmark_group("mpmc_queue");
+    for n in THREADS {
+        group.throughput(Throughput::Elements((n * PER_PRODUCER) as u64));
+        group.bench_with_input(BenchmarkId::new("seg_queue", n), &n, |b, &n| {
+            b.iter(|| mpmc_round::<SegQueue<usize>>(n))
+        });
+        group.bench_with_input(BenchmarkId::new("ms_queue", n), &n, |b, &n| {
+            b.iter(|| mpmc_round::<MsQueue<usize>>(n))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, mpmc);
+criterion_main!(benches);
@@ -0,0 +1,47 @@
+// 对比spsc_queue和std::sync::mpsc在一对一传输下的吞吐
+// cargo bench --bench spsc
+
+use std::sync::mpsc;
+use std::thread;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use too_many_linked_list_rs::spsc_queue;
+
+const SIZES: [u64; 2] = [10_000, 100_000];
+
+fn spsc_round(n: u64) -> u64 {
+    let (tx, rx) = spsc_queue::channel();
+    let producer = thread::spawn(move || {
+        for i in 0..n {
+            tx.send(i).unwrap();
+        }
+    });
+    let sum = rx.iter().sum();
+    producer.join().unwrap();
+    sum
+}
+
+fn std_mpsc_round(n: u64) -> u64 {
+    let (tx, rx) = mpsc::channel();
+    let producer = thread::spawn(move || {
+        for i in 0..n {
+            tx.send(i).unwrap();
+        }
+    });
+    let sum = rx.iter().sum();
+    producer.join().unwrap();
+    sum
+}
+
+fn throughput(c: &mut Criterion) {
+    let mut group = c.benchmark_group("spsc_throughput");
+    for n in SIZES {
+        group.throughput(Throughput::Elements(n));
+        group.bench_with_input(BenchmarkId::new("spsc_queue", n), &n, |b, &n| b.iter(|| spsc_round(n)));
+        group.bench_with_input(BenchmarkId::new("std_mpsc", n), &n, |b, &n| b.iter(|| std_mpsc_round(n)));
+    }
+    group.finish();
+}
+
+criterion_group!(benches, throughput);
+criterion_main!(benches);
@@ -0,0 +1,58 @@
+// 展开链表和每节点一个元素的链表对比
+// cargo bench --bench unrolled
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use too_many_linked_list_rs::simple_deque_3::List;
+use too_many_linked_list_rs::unrolled_list::UnrolledList;
+
+const SIZES: [usize; 3] = [1_000, 100_000, 1_000_000];
+
+// 顺序遍历: 展开链表大部分时间在连续内存里读，另外两种每个元素都要跳一次指针
+fn iterate(c: &mut Criterion) {
+    let mut group = c.benchmark_group("iterate_u64");
+    for n in SIZES {
+        let unrolled: UnrolledList<u64> = (0..n as u64).collect();
+        let mut deque = List::new();
+        deque.extend(0..n as u64);
+        group.bench_with_input(BenchmarkId::new("unrolled_16", n), &unrolled, |b, list| {
+            b.iter(|| black_box(list.iter().sum::<u64>()))
+        });
+        group.bench_with_input(BenchmarkId::new("simple_deque_3", n), &deque, |b, list| {
+            b.iter(|| black_box(list.iter().sum::<u64>()))
+        });
+    }
+    group.finish();
+}
+
+// 从一端建表再从另一端取空，展开链表每K个元素才分配一次
+fn push_pop(c: &mut Criterion) {
+    let mut group = c.benchmark_group("push_back_pop_front");
+    for n in SIZES {
+        group.bench_with_input(BenchmarkId::new("unrolled_16", n), &n, |b, &n| {
+            b.iter(|| {
+                let mut list = UnrolledList::new();
+                for i in 0..n {
+                    list.push_back(i);
+                }
+                while let Some(x) = list.pop_front() {
+                    black_box(x);
+                }
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("simple_deque_3", n), &n, |b, &n| {
+            b.iter(|| {
+                let mut list = List::new();
+                for i in 0..n {
+                    list.push_back(i);
+                }
+                while let Some(x) = list.pop_front() {
+                    black_box(x);
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, iterate, push_pop);
+criterion_main!(benches);
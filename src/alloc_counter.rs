@@ -0,0 +1,250 @@
+// 测试用的计数分配器和计数元素，给各个模块的测试断言分配次数和有没有泄漏
+// 链表最容易出的错就在Drop上: 忘了释放节点(simple_deque_1的Rc环)、元素析构两次、pop出去的元素又被析构一次，
+// 功能测试看不出来，只有数分配和析构才能发现
+// - 测试构建下把它注册成全局分配器，转发给System，顺便按线程记下分配/释放的次数和字节数
+//   计数放在线程局部变量里，cargo test并行跑的其他测试不会干扰当前测试的数字
+// - measure(f)返回f运行期间当前线程的分配统计；assert_no_leaks(f)断言f里分配的内存都已经释放
+// - DropCounter发出Counted<T>元素，统计创建和析构了多少个，析构次数超过创建次数时直接panic
+// 只统计当前线程: 在别的线程分配或释放的内存不算(并发结构要在测试线程里完成全部操作)；
+// epoch第一次pin会分配线程局部的Local，到线程退出才释放，用epoch的结构测量前先pin一次预热
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+pub(crate) struct CountingAlloc;
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct AllocStats {
+    pub allocations: usize,
+    pub deallocations: usize,
+    // realloc不改变块的个数，单独记
+    pub reallocations: usize,
+    pub bytes_allocated: usize,
+    pub bytes_freed: usize,
+}
+
+impl AllocStats {
+    // 这段时间里净增的内存块数，负数表示释放了之前分配的块
+    pub fn net_blocks(&self) -> isize {
+        self.allocations as isize - self.deallocations as isize
+    }
+
+    pub fn net_bytes(&self) -> isize {
+        self.bytes_allocated as isize - self.bytes_freed as isize
+    }
+
+    fn since(&self, start: &AllocStats) -> AllocStats {
+        AllocStats {
+            allocations: self.allocations - start.allocations,
+            deallocations: self.deallocations - start.deallocations,
+            reallocations: self.reallocations - start.reallocations,
+            bytes_allocated: self.bytes_allocated - start.bytes_allocated,
+            bytes_freed: self.bytes_freed - start.bytes_freed,
+        }
+    }
+}
+
+// const初始化、没有析构的线程局部变量访问时不会分配，可以在分配器里用
+thread_local! {
+    static STATS: Cell<AllocStats> = const {
+        Cell::new(AllocStats {
+            allocations: 0,
+            deallocations: 0,
+            reallocations: 0,
+            bytes_allocated: 0,
+            bytes_freed: 0,
+        })
+    };
+}
+
+fn record(f: impl FnOnce(&mut AllocStats)) {
+    // 线程退出的最后阶段可能已经访问不到，这时就不记了
+    let _ = STATS.try_with(|cell| {
+        let mut stats = cell.get();
+        f(&mut stats);
+        cell.set(stats);
+    });
+}
+
+fn current() -> AllocStats {
+    STATS.with(Cell::get)
+}
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            record(|s| {
+                s.allocations += 1;
+                s.bytes_allocated += layout.size();
+            });
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            record(|s| {
+                s.allocations += 1;
+                s.bytes_allocated += layout.size();
+            });
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        record(|s| {
+            s.deallocations += 1;
+            s.bytes_freed += layout.size();
+        });
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new = System.realloc(ptr, layout, new_size);
+        if !new.is_null() {
+            record(|s| {
+                s.reallocations += 1;
+                s.bytes_freed += layout.size();
+                s.bytes_allocated += new_size;
+            });
+        }
+        new
+    }
+}
+
+// 运行f并返回期间当前线程的分配统计
+pub(crate) fn measure<R>(f: impl FnOnce() -> R) -> (R, AllocStats) {
+    let start = current();
+    let result = f();
+    (result, current().since(&start))
+}
+
+// f里分配的内存块和字节数都必须在f结束前释放
+pub(crate) fn assert_no_leaks(f: impl FnOnce()) {
+    let ((), stats) = measure(f);
+    assert!(stats.net_blocks() == 0 && stats.net_bytes() == 0, "leaked: {stats:?}");
+}
+
+#[derive(Debug, Default)]
+struct Counts {
+    created: AtomicUsize,
+    dropped: AtomicUsize,
+}
+
+// 发出计数元素，可以Clone后带到别的线程
+#[derive(Debug, Clone, Default)]
+pub(crate) struct DropCounter(Arc<Counts>);
+
+impl DropCounter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn track<T>(&self, value: T) -> Counted<T> {
+        self.0.created.fetch_add(1, Ordering::Relaxed);
+        Counted {
+            value,
+            counts: Arc::clone(&self.0),
+        }
+    }
+
+    pub fn created(&self) -> usize {
+        self.0.created.load(Ordering::Relaxed)
+    }
+
+    pub fn dropped(&self) -> usize {
+        self.0.dropped.load(Ordering::Relaxed)
+    }
+
+    // 还活着的元素个数
+    pub fn live(&self) -> usize {
+        self.created() - self.dropped()
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Counted<T> {
+    pub value: T,
+    counts: Arc<Counts>,
+}
+
+impl<T: Clone> Clone for Counted<T> {
+    fn clone(&self) -> Self {
+        DropCounter(Arc::clone(&self.counts)).track(self.value.clone())
+    }
+}
+
+impl<T> Drop for Counted<T> {
+    fn drop(&mut self) {
+        let dropped = self.counts.dropped.fetch_add(1, Ordering::Relaxed) + 1;
+        assert!(
+            dropped <= self.counts.created.load(Ordering::Relaxed),
+            "element dropped more times than created"
+        );
+    }
+}
+
+impl<T: PartialEq> PartialEq for Counted<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn counts_boxes_on_this_thread() {
+        let (b, stats) = measure(|| Box::new(7u64));
+        assert_eq!((stats.allocations, stats.deallocations), (1, 0));
+        assert_eq!(stats.bytes_allocated, 8);
+        let ((), stats) = measure(|| drop(b));
+        assert_eq!((stats.net_blocks(), stats.net_bytes()), (-1, -8));
+    }
+
+    #[test]
+    fn realloc_keeps_block_count() {
+        let mut v: Vec<u8> = Vec::with_capacity(1);
+        let ((), stats) = measure(|| v.extend(0..100));
+        assert_eq!(stats.net_blocks(), 0);
+        assert!(stats.reallocations > 0);
+        assert_eq!(stats.net_bytes() as usize, v.capacity() - 1);
+    }
+
+    #[test]
+    fn other_threads_not_counted() {
+        let ((), stats) = measure(|| {
+            thread::spawn(|| drop(vec![0u8; 1 << 20])).join().unwrap();
+        });
+        // 只有spawn本身在当前线程上的几次小分配，1MB的Vec不在里面
+        assert!(stats.bytes_allocated < 1 << 20);
+        assert_no_leaks(|| drop(vec![1u8; 16]));
+    }
+
+    #[test]
+    #[should_panic(expected = "leaked")]
+    fn forgotten_box_is_a_leak() {
+        assert_no_leaks(|| std::mem::forget(Box::new(1u8)));
+    }
+
+    #[test]
+    fn drop_counter_tracks_clones_and_drops() {
+        let counter = DropCounter::new();
+        let a = counter.track(String::from("a"));
+        let b = a.clone();
+        assert_eq!((counter.created(), counter.live()), (2, 2));
+        drop(a);
+        assert_eq!(b.value, "a");
+        drop(b);
+        assert_eq!((counter.created(), counter.dropped()), (2, 2));
+    }
+}
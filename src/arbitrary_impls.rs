@@ -0,0 +1,269 @@
+// arbitrary::Arbitrary实现，开启arbitrary特性后cargo-fuzz、proptest-arbitrary-interop之类的工具
+// 可以直接生成链表，不用先生成Vec再一个个push
+// 元素序列和Vec<T>::arbitrary从同一段字节里读出来的完全一样，再用下面列出的方法依次放进去:
+// 用push_back的链表元素顺序和Vec一致，只有push/push_front的栈顺序相反，sorted_list按大小排好
+// 没有实现的:
+// - ghost_list/static_rc_list需要GhostToken，intrusive_list的元素借用自外部，都没法凭空造出来
+// - 堆、映射、缓存不是序列，history/scheduler这类结构的状态也不只是一串元素
+
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+#[cfg(feature = "unsafe-impls")]
+use crate::arena_list::ArenaList;
+#[cfg(feature = "unsafe-impls")]
+use crate::byte_chain::ByteChain;
+#[cfg(feature = "concurrent")]
+use crate::chase_lev_deque::Worker;
+#[cfg(feature = "persistent")]
+use crate::cow_list::CowList;
+#[cfg(feature = "concurrent")]
+use crate::elimination_stack::EliminationStack;
+#[cfg(feature = "concurrent")]
+use crate::flat_combining::FcQueue;
+#[cfg(feature = "safe")]
+use crate::gap_buffer::GapBuffer;
+#[cfg(feature = "unsafe-impls")]
+use crate::indexable_skip_list::IndexableSkipList;
+#[cfg(feature = "safe")]
+use crate::min_stack::{MinQueue, MinStack};
+#[cfg(feature = "concurrent")]
+use crate::ms_queue::MsQueue;
+#[cfg(feature = "unsafe-impls")]
+use crate::random_list::RandomList;
+#[cfg(feature = "concurrent")]
+use crate::rcu_list::RcuList;
+#[cfg(feature = "concurrent")]
+use crate::reclaim::Reclaim;
+#[cfg(feature = "unsafe-impls")]
+use crate::ring::Ring;
+#[cfg(feature = "unsafe-impls")]
+use crate::rope::Rope;
+#[cfg(feature = "concurrent")]
+use crate::seg_queue::SegQueue;
+#[cfg(feature = "concurrent")]
+use crate::shared_stack::SharedStack;
+#[cfg(feature = "unsafe-impls")]
+use crate::simple_deque_3::pinned::PinnedList;
+#[cfg(feature = "unsafe-impls")]
+use crate::simple_deque_3::NodeLayout;
+#[cfg(feature = "unsafe-impls")]
+use crate::slab_list::SlabList;
+#[cfg(feature = "unsafe-impls")]
+use crate::small_list::SmallList;
+#[cfg(feature = "unsafe-impls")]
+use crate::sorted_list::SortedList;
+#[cfg(feature = "unsafe-impls")]
+use crate::static_list::StaticList;
+#[cfg(feature = "concurrent")]
+use crate::sync_deque::SyncDeque;
+#[cfg(feature = "unsafe-impls")]
+use crate::tombstone_list::TombstoneList;
+#[cfg(feature = "concurrent")]
+use crate::treiber_stack::TreiberStack;
+#[cfg(feature = "unsafe-impls")]
+use crate::unrolled_list::UnrolledList;
+#[cfg(feature = "persistent")]
+use crate::versioned_list::VersionedList;
+#[cfg(feature = "unsafe-impls")]
+use crate::xor_list::XorList;
+#[cfg(feature = "safe")]
+use crate::{simple_deque_1, simple_stack_1, simple_stack_2};
+#[cfg(feature = "persistent")]
+use crate::simple_stack_3;
+#[cfg(feature = "unsafe-impls")]
+use crate::{sentinel_list, simple_deque_2, simple_deque_3};
+
+// 写法和traits里的宏一样: [泛型参数] 类型 => 放入元素的方法;
+// 先default()出空容器，再把元素逐个交给这个方法；取&self的并发结构也能这样调用
+macro_rules! impl_arbitrary {
+    ($($(#[$attr:meta])* [$($gen:tt)*] $ty:ty => $push:ident;)*) => {$(
+        $(#[$attr])*
+        impl<'a, $($gen)*> Arbitrary<'a> for $ty
+        where
+            T: Arbitrary<'a>,
+        {
+            fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+                let mut list = <$ty>::default();
+                for elem in u.arbitrary_iter()? {
+                    <$ty>::$push(&mut list, elem?);
+                }
+                Ok(list)
+            }
+
+            fn arbitrary_take_rest(u: Unstructured<'a>) -> Result<Self> {
+                let mut list = <$ty>::default();
+                for elem in u.arbitrary_take_rest_iter()? {
+                    <$ty>::$push(&mut list, elem?);
+                }
+                Ok(list)
+            }
+        }
+    )*};
+}
+
+impl_arbitrary! {
+    #[cfg(feature = "safe")]
+    [T] simple_stack_2::List<T> => push;
+    #[cfg(feature = "safe")]
+    [T] simple_deque_1::List<T> => push_back;
+    #[cfg(feature = "safe")]
+    [T: Ord + Clone] MinStack<T> => push;
+    #[cfg(feature = "safe")]
+    [T: Ord + Clone] MinQueue<T> => push;
+    #[cfg(feature = "safe")]
+    [T] GapBuffer<T> => insert;
+    #[cfg(feature = "persistent")]
+    [T] CowList<T> => push_front;
+    #[cfg(feature = "persistent")]
+    [T] VersionedList<T> => push_front;
+    #[cfg(feature = "unsafe-impls")]
+    [T] simple_deque_2::List<T> => push_back;
+    #[cfg(feature = "unsafe-impls")]
+    [T, L: NodeLayout] simple_deque_3::List<T, L> => push_back;
+    #[cfg(feature = "unsafe-impls")]
+    [T] PinnedList<T> => push_back;
+    #[cfg(feature = "unsafe-impls")]
+    [T] sentinel_list::List<T> => push_back;
+    #[cfg(feature = "unsafe-impls")]
+    [T] XorList<T> => push_back;
+    #[cfg(feature = "unsafe-impls")]
+    [T] ArenaList<T> => push_back;
+    #[cfg(feature = "unsafe-impls")]
+    [T] SlabList<T> => push_back;
+    #[cfg(feature = "unsafe-impls")]
+    [T, const N: usize] SmallList<T, N> => push_back;
+    #[cfg(feature = "unsafe-impls")]
+    [T, const K: usize] UnrolledList<T, K> => push_back;
+    #[cfg(feature = "unsafe-impls")]
+    [T] IndexableSkipList<T> => push_back;
+    #[cfg(feature = "unsafe-impls")]
+    [T] TombstoneList<T> => push_back;
+    #[cfg(feature = "unsafe-impls")]
+    [T] RandomList<T> => push_front;
+    #[cfg(feature = "unsafe-impls")]
+    [T] Ring<T> => push;
+    #[cfg(feature = "unsafe-impls")]
+    [T: Ord] SortedList<T> => insert;
+    #[cfg(feature = "concurrent")]
+    [T] SyncDeque<T> => push_back;
+    #[cfg(feature = "concurrent")]
+    [T, R: Reclaim] TreiberStack<T, R> => push;
+    #[cfg(feature = "concurrent")]
+    [T, R: Reclaim] EliminationStack<T, R> => push;
+    #[cfg(feature = "concurrent")]
+    [T, R: Reclaim] MsQueue<T, R> => push;
+    #[cfg(feature = "concurrent")]
+    [T, R: Reclaim] SegQueue<T, R> => push;
+    #[cfg(feature = "concurrent")]
+    [T] FcQueue<T> => push;
+    #[cfg(feature = "concurrent")]
+    [T: Send + Sync + 'static] SharedStack<T> => push;
+    #[cfg(feature = "concurrent")]
+    [T: Clone + Send + Sync + 'static] RcuList<T> => push_front;
+    #[cfg(feature = "concurrent")]
+    [T] Worker<T> => push;
+}
+
+// push返回Result，不能放进上面的宏；生成的栈不限容量，push不会失败
+#[cfg(feature = "safe")]
+impl<'a, T: Arbitrary<'a>> Arbitrary<'a> for simple_stack_1::List<T> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let mut list = simple_stack_1::List::new();
+        for elem in u.arbitrary_iter()? {
+            let _ = list.push(elem?);
+        }
+        Ok(list)
+    }
+}
+
+// 持久化的栈每次prepend得到一个新链表；倒着prepend，让表头是第一个生成的元素
+#[cfg(feature = "persistent")]
+impl<'a, T: Arbitrary<'a>> Arbitrary<'a> for simple_stack_3::List<T> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let elems: Vec<T> = u.arbitrary()?;
+        Ok(elems
+            .into_iter()
+            .rev()
+            .fold(simple_stack_3::List::new(), |list, elem| list.prepend(elem)))
+    }
+}
+
+// 容量固定，装满之后剩下的元素不再读取
+#[cfg(feature = "unsafe-impls")]
+impl<'a, T: Arbitrary<'a>, const N: usize> Arbitrary<'a> for StaticList<T, N> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let mut list = StaticList::default();
+        for elem in u.arbitrary_iter()? {
+            if list.push_back(elem?).is_err() {
+                break;
+            }
+        }
+        Ok(list)
+    }
+}
+
+#[cfg(feature = "unsafe-impls")]
+impl<'a> Arbitrary<'a> for Rope {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Rope::from(String::arbitrary(u)?))
+    }
+
+    fn size_hint(depth: usize) -> (usize, Option<usize>) {
+        String::size_hint(depth)
+    }
+}
+
+#[cfg(feature = "unsafe-impls")]
+impl<'a> Arbitrary<'a> for ByteChain {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(ByteChain::from(Vec::<u8>::arbitrary(u)?))
+    }
+
+    fn size_hint(depth: usize) -> (usize, Option<usize>) {
+        Vec::<u8>::size_hint(depth)
+    }
+}
+
+#[cfg(all(test, feature = "unsafe-impls"))]
+mod tests {
+    use super::*;
+
+    // 固定的一段"随机"字节
+    fn bytes() -> Vec<u8> {
+        (0..=255u8).map(|b| b.wrapping_mul(167).wrapping_add(13)).collect()
+    }
+
+    #[test]
+    fn same_elements_as_vec() {
+        let data = bytes();
+        let expected = Vec::<u16>::arbitrary(&mut Unstructured::new(&data)).unwrap();
+        assert!(!expected.is_empty());
+
+        let list = simple_deque_3::List::<u16>::arbitrary(&mut Unstructured::new(&data)).unwrap();
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), expected);
+        let list = UnrolledList::<u16, 2>::arbitrary(&mut Unstructured::new(&data)).unwrap();
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), expected);
+
+        let expected = Vec::<u16>::arbitrary_take_rest(Unstructured::new(&data)).unwrap();
+        let list = XorList::<u16>::arbitrary_take_rest(Unstructured::new(&data)).unwrap();
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), expected);
+    }
+
+    #[test]
+    fn sorted_and_bounded() {
+        let data = bytes();
+        let sorted = SortedList::<u8>::arbitrary(&mut Unstructured::new(&data)).unwrap();
+        let elems: Vec<u8> = sorted.iter().copied().collect();
+        assert!(elems.windows(2).all(|w| w[0] <= w[1]));
+
+        let list = StaticList::<u8, 4>::arbitrary(&mut Unstructured::new(&data)).unwrap();
+        assert_eq!(list.len(), 4);
+    }
+
+    #[test]
+    fn empty_input() {
+        let list = simple_deque_2::List::<u32>::arbitrary(&mut Unstructured::new(&[])).unwrap();
+        assert!(list.peek().is_none());
+        assert_eq!(Rope::arbitrary(&mut Unstructured::new(&[])).unwrap(), "");
+    }
+}
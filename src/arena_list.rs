@@ -0,0 +1,503 @@
+// 节点从链表自带的arena里分配的双向链表
+// arena按块向系统申请内存(块大小逐次翻倍)，分配一个节点只是把块内的游标往后挪一格，
+// 比每个节点一次Box::new快得多，节点在内存里也基本是连续的
+// 代价是节点不会单独释放: pop出去的节点所占的槽位直到clear或者整个链表drop时才一起回收，
+// 适合"建好之后主要遍历"或者整体丢弃的场景，不适合长期大量进出
+// 块以裸指针保存，分配新节点时不会重新借用整个块，之前发出去的节点指针一直有效
+
+use std::fmt;
+use std::iter::FusedIterator;
+use std::marker::PhantomData;
+use std::mem::MaybeUninit;
+use std::ptr::{self, NonNull};
+
+const FIRST_CHUNK: usize = 32;
+const MAX_CHUNK: usize = 1 << 16;
+
+type Link<T> = Option<NonNull<Node<T>>>;
+
+struct Node<T> {
+    elem: T,
+    prev: Link<T>,
+    next: Link<T>,
+}
+
+struct Chunk<T> {
+    base: NonNull<MaybeUninit<Node<T>>>,
+    cap: usize,
+}
+
+struct Arena<T> {
+    chunks: Vec<Chunk<T>>,
+    // 当前块(chunks[current])里下一个可用槽位
+    current: usize,
+    used: usize,
+}
+
+impl<T> Arena<T> {
+    fn new() -> Self {
+        Arena {
+            chunks: Vec::new(),
+            current: 0,
+            used: 0,
+        }
+    }
+
+    fn alloc(&mut self, node: Node<T>) -> NonNull<Node<T>> {
+        let full = match self.chunks.get(self.current) {
+            Some(chunk) => self.used == chunk.cap,
+            None => true,
+        };
+        if full {
+            self.next_chunk();
+        }
+        let chunk = &self.chunks[self.current];
+        unsafe {
+            let slot = chunk.base.as_ptr().add(self.used);
+            self.used += 1;
+            NonNull::new_unchecked((*slot).write(node))
+        }
+    }
+
+    // 切换到下一个块: clear之后已有的块可以复用，否则新申请一个更大的
+    fn next_chunk(&mut self) {
+        if !self.chunks.is_empty() && self.current + 1 < self.chunks.len() {
+            self.current += 1;
+        } else {
+            let cap = self.chunks.last().map_or(FIRST_CHUNK, |c| (c.cap * 2).min(MAX_CHUNK));
+            let slots: Box<[MaybeUninit<Node<T>>]> = (0..cap).map(|_| MaybeUninit::uninit()).collect();
+            let base = NonNull::new(Box::into_raw(slots) as *mut MaybeUninit<Node<T>>).unwrap();
+            self.chunks.push(Chunk { base, cap });
+            self.current = self.chunks.len() - 1;
+        }
+        self.used = 0;
+    }
+
+    // 所有节点都已经不再使用，游标回到第一个块的开头
+    fn reset(&mut self) {
+        self.current = 0;
+        self.used = 0;
+    }
+
+    fn capacity(&self) -> usize {
+        self.chunks.iter().map(|c| c.cap).sum()
+    }
+}
+
+impl<T> Drop for Arena<T> {
+    fn drop(&mut self) {
+        // 槽位是MaybeUninit，只释放内存，元素由链表负责析构
+        for chunk in &self.chunks {
+            unsafe {
+                drop(Box::from_raw(ptr::slice_from_raw_parts_mut(
+                    chunk.base.as_ptr(),
+                    chunk.cap,
+                )));
+            }
+        }
+    }
+}
+
+pub struct ArenaList<T> {
+    head: Link<T>,
+    tail: Link<T>,
+    len: usize,
+    arena: Arena<T>,
+    _boo: PhantomData<T>,
+}
+
+impl<T> ArenaList<T> {
+    pub fn new() -> Self {
+        ArenaList {
+            head: None,
+            tail: None,
+            len: 0,
+            arena: Arena::new(),
+            _boo: PhantomData,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // arena里一共申请了多少个节点槽位(包括已经pop掉、还没回收的)
+    pub fn arena_capacity(&self) -> usize {
+        self.arena.capacity()
+    }
+
+    pub fn push_front(&mut self, elem: T) {
+        let new = self.arena.alloc(Node {
+            elem,
+            prev: None,
+            next: self.head,
+        });
+        match self.head {
+            Some(old) => unsafe { (*old.as_ptr()).prev = Some(new) },
+            None => self.tail = Some(new),
+        }
+        self.head = Some(new);
+        self.len += 1;
+    }
+
+    pub fn push_back(&mut self, elem: T) {
+        let new = self.arena.alloc(Node {
+            elem,
+            prev: self.tail,
+            next: None,
+        });
+        match self.tail {
+            Some(old) => unsafe { (*old.as_ptr()).next = Some(new) },
+            None => self.head = Some(new),
+        }
+        self.tail = Some(new);
+        self.len += 1;
+    }
+
+    // 只把元素移出来，节点槽位留在arena里
+    pub fn pop_front(&mut self) -> Option<T> {
+        self.head.map(|node| unsafe {
+            let node = node.as_ptr();
+            self.head = (*node).next;
+            match self.head {
+                Some(new) => (*new.as_ptr()).prev = None,
+                None => self.tail = None,
+            }
+            self.len -= 1;
+            ptr::read(&(*node).elem)
+        })
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        self.tail.map(|node| unsafe {
+            let node = node.as_ptr();
+            self.tail = (*node).prev;
+            match self.tail {
+                Some(new) => (*new.as_ptr()).next = None,
+                None => self.head = None,
+            }
+            self.len -= 1;
+            ptr::read(&(*node).elem)
+        })
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        self.head.map(|n| unsafe { &(*n.as_ptr()).elem })
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        self.tail.map(|n| unsafe { &(*n.as_ptr()).elem })
+    }
+
+    pub fn front_mut(&mut self) -> Option<&mut T> {
+        self.head.map(|n| unsafe { &mut (*n.as_ptr()).elem })
+    }
+
+    pub fn back_mut(&mut self) -> Option<&mut T> {
+        self.tail.map(|n| unsafe { &mut (*n.as_ptr()).elem })
+    }
+
+    // 析构所有元素并回收arena里的全部槽位(已申请的块保留下来复用)
+    pub fn clear(&mut self) {
+        while self.pop_front().is_some() {}
+        self.arena.reset();
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            head: self.head,
+            tail: self.tail,
+            len: self.len,
+            _boo: PhantomData,
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut {
+            head: self.head,
+            tail: self.tail,
+            len: self.len,
+            _boo: PhantomData,
+        }
+    }
+}
+
+impl<T> Drop for ArenaList<T> {
+    fn drop(&mut self) {
+        // 只需要析构还在链表里的元素，内存由Arena整体释放
+        while self.pop_front().is_some() {}
+    }
+}
+
+impl<T> Default for ArenaList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for ArenaList<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: Clone> Clone for ArenaList<T> {
+    fn clone(&self) -> Self {
+        self.iter().cloned().collect()
+    }
+}
+
+impl<T: PartialEq> PartialEq for ArenaList<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Eq> Eq for ArenaList<T> {}
+
+impl<T> Extend<T> for ArenaList<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for elem in iter {
+            self.push_back(elem);
+        }
+    }
+}
+
+impl<T> FromIterator<T> for ArenaList<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = Self::new();
+        list.extend(iter);
+        list
+    }
+}
+
+pub struct Iter<'a, T> {
+    head: Link<T>,
+    tail: Link<T>,
+    len: usize,
+    _boo: PhantomData<&'a T>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        if self.len == 0 {
+            return None;
+        }
+        self.head.map(|node| unsafe {
+            self.len -= 1;
+            self.head = (*node.as_ptr()).next;
+            &(*node.as_ptr()).elem
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T> DoubleEndedIterator for Iter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        self.tail.map(|node| unsafe {
+            self.len -= 1;
+            self.tail = (*node.as_ptr()).prev;
+            &(*node.as_ptr()).elem
+        })
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+impl<T> FusedIterator for Iter<'_, T> {}
+
+impl<'a, T> IntoIterator for &'a ArenaList<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+pub struct IterMut<'a, T> {
+    head: Link<T>,
+    tail: Link<T>,
+    len: usize,
+    _boo: PhantomData<&'a mut T>,
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<&'a mut T> {
+        if self.len == 0 {
+            return None;
+        }
+        self.head.map(|node| unsafe {
+            self.len -= 1;
+            self.head = (*node.as_ptr()).next;
+            &mut (*node.as_ptr()).elem
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T> DoubleEndedIterator for IterMut<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        self.tail.map(|node| unsafe {
+            self.len -= 1;
+            self.tail = (*node.as_ptr()).prev;
+            &mut (*node.as_ptr()).elem
+        })
+    }
+}
+
+impl<T> ExactSizeIterator for IterMut<'_, T> {}
+impl<T> FusedIterator for IterMut<'_, T> {}
+
+impl<'a, T> IntoIterator for &'a mut ArenaList<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> IterMut<'a, T> {
+        self.iter_mut()
+    }
+}
+
+pub struct IntoIter<T>(ArenaList<T>);
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.0.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.0.len, Some(self.0.len))
+    }
+}
+
+impl<T> DoubleEndedIterator for IntoIter<T> {
+    fn next_back(&mut self) -> Option<T> {
+        self.0.pop_back()
+    }
+}
+
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
+impl<T> IntoIterator for ArenaList<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter(self)
+    }
+}
+
+unsafe impl<T: Send> Send for ArenaList<T> {}
+unsafe impl<T: Sync> Sync for ArenaList<T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+
+    #[test]
+    fn deque_operations() {
+        let mut list = ArenaList::new();
+        list.push_back(2);
+        list.push_back(3);
+        list.push_front(1);
+        assert_eq!(list.len(), 3);
+        assert_eq!(list.front(), Some(&1));
+        assert_eq!(list.back(), Some(&3));
+        *list.back_mut().unwrap() = 30;
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![1, 2, 30]);
+        assert_eq!(list.iter().rev().copied().collect::<Vec<_>>(), vec![30, 2, 1]);
+        assert_eq!(list.pop_front(), Some(1));
+        assert_eq!(list.pop_back(), Some(30));
+        assert_eq!(list.pop_back(), Some(2));
+        assert_eq!(list.pop_back(), None);
+        assert!(list.is_empty());
+    }
+
+    #[test]
+    fn chunks_grow_geometrically() {
+        let mut list = ArenaList::new();
+        for i in 0..FIRST_CHUNK {
+            list.push_back(i);
+        }
+        assert_eq!(list.arena_capacity(), FIRST_CHUNK);
+        list.push_back(0);
+        assert_eq!(list.arena_capacity(), FIRST_CHUNK * 3);
+        // pop不会回收槽位
+        while list.pop_front().is_some() {}
+        list.push_back(1);
+        assert_eq!(list.arena_capacity(), FIRST_CHUNK * 3);
+    }
+
+    #[test]
+    fn clear_reuses_chunks() {
+        let mut list: ArenaList<usize> = (0..1000).collect();
+        let cap = list.arena_capacity();
+        for _ in 0..5 {
+            list.clear();
+            list.extend(0..1000);
+        }
+        assert_eq!(list.arena_capacity(), cap);
+        assert_eq!(list.iter().sum::<usize>(), 999 * 1000 / 2);
+    }
+
+    #[test]
+    fn node_pointers_survive_new_chunks() {
+        let mut list = ArenaList::new();
+        // 跨越好几个块，前面节点的链接仍然有效
+        for i in 0..500 {
+            if i % 2 == 0 {
+                list.push_back(i);
+            } else {
+                list.push_front(i);
+            }
+        }
+        let refs: Vec<&mut i32> = list.iter_mut().collect();
+        for r in refs {
+            *r += 1;
+        }
+        let v: Vec<i32> = list.into_iter().collect();
+        assert_eq!(v.len(), 500);
+        assert_eq!(v[0], 500);
+        assert_eq!(v[499], 499);
+    }
+
+    #[test]
+    fn drops_live_elements_once() {
+        let marker = Rc::new(());
+        let mut list = ArenaList::new();
+        for _ in 0..100 {
+            list.push_back(Rc::clone(&marker));
+        }
+        for _ in 0..40 {
+            drop(list.pop_back());
+        }
+        assert_eq!(Rc::strong_count(&marker), 61);
+        list.clear();
+        assert_eq!(Rc::strong_count(&marker), 1);
+        list.push_front(Rc::clone(&marker));
+        drop(list);
+        assert_eq!(Rc::strong_count(&marker), 1);
+    }
+}
@@ -0,0 +1,400 @@
+// 异步队列: pop().await在队列为空时挂起，而不是阻塞线程
+// 元素和等待者都放在simple_deque_3::List里，由一把Mutex保护(持锁时间很短，不会跨越await)
+// - pop在没有元素时把自己的Waker登记到等待者链表的末尾，然后返回Pending
+// - push放入元素后从链表头取出最早的等待者唤醒，保证先来先服务
+// - 被唤醒的future如果在取到元素之前被drop，要把这次唤醒转交给下一个等待者，否则会丢失唤醒
+// 开启async特性后，Stream同样实现futures_core::Stream
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::task::{Context, Poll, Waker};
+
+use crate::instrument::mutation;
+use crate::simple_deque_3::List;
+
+struct Waiter {
+    id: u64,
+    waker: Waker,
+}
+
+struct State<T> {
+    items: List<T>,
+    // 按登记顺序排列，表头是等得最久的
+    waiters: List<Waiter>,
+    next_id: u64,
+    closed: bool,
+}
+
+impl<T> State<T> {
+    // 从等待链表里删掉id，返回它是否还在链表里(不在说明已经被push唤醒过)
+    fn remove_waiter(&mut self, id: u64) -> bool {
+        let mut cursor = self.waiters.cursor_mut();
+        cursor.move_next();
+        while let Some(waiter) = cursor.current() {
+            if waiter.id == id {
+                cursor.remove_current();
+                return true;
+            }
+            cursor.move_next();
+        }
+        false
+    }
+
+    fn wake_one(&mut self) {
+        if let Some(waiter) = self.waiters.pop_front() {
+            waiter.waker.wake();
+        }
+    }
+}
+
+pub struct AsyncQueue<T> {
+    state: Mutex<State<T>>,
+}
+
+impl<T> AsyncQueue<T> {
+    pub fn new() -> Self {
+        AsyncQueue {
+            state: Mutex::new(State {
+                items: List::new(),
+                waiters: List::new(),
+                next_id: 0,
+                closed: false,
+            }),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lock().items.is_empty()
+    }
+
+    // 当前挂起等待元素的future数量
+    pub fn waiters(&self) -> usize {
+        self.lock().waiters.len()
+    }
+
+    // 关闭后push失败并把元素还回来
+    pub fn push(&self, elem: T) -> Result<(), T> {
+        let mut state = self.lock();
+        if state.closed {
+            return Err(elem);
+        }
+        state.items.push_back(elem);
+        state.wake_one();
+        mutation!("push", len = state.items.len());
+        Ok(())
+    }
+
+    pub fn try_pop(&self) -> Option<T> {
+        let elem = self.lock().items.pop_front();
+        mutation!("try_pop", found = elem.is_some());
+        elem
+    }
+
+    // 等待下一个元素；队列关闭且已经取空时得到None
+    pub fn pop(&self) -> Pop<'_, T> {
+        Pop {
+            queue: self,
+            waiter: None,
+        }
+    }
+
+    // 以Stream的形式不断pop，直到队列关闭并取空
+    pub fn stream(&self) -> PopStream<'_, T> {
+        PopStream {
+            queue: self,
+            waiter: None,
+        }
+    }
+
+    // 关闭队列并唤醒所有等待者，剩余的元素仍然可以取出
+    pub fn close(&self) {
+        let mut state = self.lock();
+        state.closed = true;
+        mutation!("close", waiters = state.waiters.len());
+        while let Some(waiter) = state.waiters.pop_front() {
+            waiter.waker.wake();
+        }
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.lock().closed
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State<T>> {
+        // 持锁期间不会调用用户代码(Waker::wake除外)，中毒时状态仍然一致
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    // Pop和PopStream共用的轮询逻辑，waiter记录自己在等待链表里的id
+    fn poll_pop(&self, waiter: &mut Option<u64>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        let mut state = self.lock();
+        if let Some(elem) = state.items.pop_front() {
+            if let Some(id) = waiter.take() {
+                state.remove_waiter(id);
+            }
+            mutation!("pop", len = state.items.len());
+            return Poll::Ready(Some(elem));
+        }
+        if state.closed {
+            if let Some(id) = waiter.take() {
+                state.remove_waiter(id);
+            }
+            return Poll::Ready(None);
+        }
+
+        // 还在链表里就只更新Waker，保留原来的排队位置
+        if let Some(id) = *waiter {
+            if let Some(w) = state.waiters.iter_mut().find(|w| w.id == id) {
+                if !w.waker.will_wake(cx.waker()) {
+                    w.waker = cx.waker().clone();
+                }
+                return Poll::Pending;
+            }
+        }
+        // 第一次等待，或者被唤醒后元素又被别人抢走了，重新排到队尾
+        let id = state.next_id;
+        state.next_id += 1;
+        state.waiters.push_back(Waiter {
+            id,
+            waker: cx.waker().clone(),
+        });
+        *waiter = Some(id);
+        mutation!("wait", id);
+        Poll::Pending
+    }
+
+    // future在完成前被drop
+    fn cancel(&self, waiter: Option<u64>) {
+        if let Some(id) = waiter {
+            let mut state = self.lock();
+            if !state.remove_waiter(id) && !state.items.is_empty() {
+                // 已经被唤醒却不会再来取了，把唤醒交给下一个等待者
+                state.wake_one();
+            }
+        }
+    }
+}
+
+impl<T> Default for AsyncQueue<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// pop()返回的future
+pub struct Pop<'a, T> {
+    queue: &'a AsyncQueue<T>,
+    waiter: Option<u64>,
+}
+
+impl<T> Future for Pop<'_, T> {
+    type Output = Option<T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        let this = self.get_mut();
+        this.queue.poll_pop(&mut this.waiter, cx)
+    }
+}
+
+impl<T> Drop for Pop<'_, T> {
+    fn drop(&mut self) {
+        self.queue.cancel(self.waiter.take());
+    }
+}
+
+// stream()返回的接收端
+pub struct PopStream<'a, T> {
+    queue: &'a AsyncQueue<T>,
+    waiter: Option<u64>,
+}
+
+impl<T> PopStream<'_, T> {
+    pub fn poll_next(&mut self, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        self.queue.poll_pop(&mut self.waiter, cx)
+    }
+}
+
+#[cfg(feature = "async")]
+impl<T> futures_core::Stream for PopStream<'_, T> {
+    type Item = T;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        PopStream::poll_next(self.get_mut(), cx)
+    }
+}
+
+impl<T> Drop for PopStream<'_, T> {
+    fn drop(&mut self) {
+        self.queue.cancel(self.waiter.take());
+    }
+}
+
+// 测试依赖tokio，loom构建里没有tokio
+#[cfg(all(test, not(loom)))]
+mod tests {
+    use super::*;
+    use std::future::poll_fn;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::task::Wake;
+    use std::time::Duration;
+
+    // 记录被唤醒次数的Waker
+    struct CountingWaker(AtomicUsize);
+
+    impl Wake for CountingWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn counting_waker() -> (Arc<CountingWaker>, Waker) {
+        let inner = Arc::new(CountingWaker(AtomicUsize::new(0)));
+        (Arc::clone(&inner), Waker::from(inner))
+    }
+
+    fn poll<F: Future + Unpin>(fut: &mut F, waker: &Waker) -> Poll<F::Output> {
+        Pin::new(fut).poll(&mut Context::from_waker(waker))
+    }
+
+    #[test]
+    fn push_wakes_oldest_waiter() {
+        let q = AsyncQueue::new();
+        let (a_count, a_waker) = counting_waker();
+        let (b_count, b_waker) = counting_waker();
+        let mut a = q.pop();
+        let mut b = q.pop();
+        assert_eq!(poll(&mut a, &a_waker), Poll::Pending);
+        assert_eq!(poll(&mut b, &b_waker), Poll::Pending);
+        assert_eq!(q.waiters(), 2);
+
+        q.push(1).unwrap();
+        assert_eq!(a_count.0.load(Ordering::SeqCst), 1);
+        assert_eq!(b_count.0.load(Ordering::SeqCst), 0);
+        assert_eq!(poll(&mut a, &a_waker), Poll::Ready(Some(1)));
+
+        q.push(2).unwrap();
+        assert_eq!(b_count.0.load(Ordering::SeqCst), 1);
+        assert_eq!(poll(&mut b, &b_waker), Poll::Ready(Some(2)));
+        assert_eq!(q.waiters(), 0);
+    }
+
+    #[test]
+    fn dropped_waiter_passes_wakeup_on() {
+        let q = AsyncQueue::new();
+        let (_, a_waker) = counting_waker();
+        let (b_count, b_waker) = counting_waker();
+        let mut a = q.pop();
+        let mut b = q.pop();
+        assert_eq!(poll(&mut a, &a_waker), Poll::Pending);
+        assert_eq!(poll(&mut b, &b_waker), Poll::Pending);
+
+        // a被唤醒后没来取就被drop了，b应该接到这次唤醒
+        q.push(1).unwrap();
+        assert_eq!(b_count.0.load(Ordering::SeqCst), 0);
+        drop(a);
+        assert_eq!(b_count.0.load(Ordering::SeqCst), 1);
+        assert_eq!(poll(&mut b, &b_waker), Poll::Ready(Some(1)));
+
+        // 还没被唤醒就drop的等待者直接从链表里移除
+        let mut c = q.pop();
+        assert_eq!(poll(&mut c, &b_waker), Poll::Pending);
+        drop(c);
+        assert_eq!(q.waiters(), 0);
+    }
+
+    #[tokio::test]
+    async fn pop_waits_for_push() {
+        let q = Arc::new(AsyncQueue::new());
+        let producer = {
+            let q = Arc::clone(&q);
+            tokio::spawn(async move {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                q.push("hello").unwrap();
+            })
+        };
+        assert_eq!(q.pop().await, Some("hello"));
+        producer.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn close_ends_stream() {
+        let q = AsyncQueue::new();
+        for i in 0..3 {
+            q.push(i).unwrap();
+        }
+        q.close();
+        assert_eq!(q.push(9), Err(9));
+        let mut stream = q.stream();
+        let mut got = Vec::new();
+        while let Some(x) = poll_fn(|cx| stream.poll_next(cx)).await {
+            got.push(x);
+        }
+        assert_eq!(got, vec![0, 1, 2]);
+        assert_eq!(q.pop().await, None);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn many_producers_and_consumers() {
+        const PRODUCERS: usize = 4;
+        const CONSUMERS: usize = 4;
+        const PER_PRODUCER: usize = 2000;
+        let q = Arc::new(AsyncQueue::new());
+
+        let consumers: Vec<_> = (0..CONSUMERS)
+            .map(|_| {
+                let q = Arc::clone(&q);
+                tokio::spawn(async move {
+                    let mut got = Vec::new();
+                    while let Some(x) = q.pop().await {
+                        got.push(x);
+                    }
+                    got
+                })
+            })
+            .collect();
+        let producers: Vec<_> = (0..PRODUCERS)
+            .map(|p| {
+                let q = Arc::clone(&q);
+                tokio::spawn(async move {
+                    for i in 0..PER_PRODUCER {
+                        q.push(p * PER_PRODUCER + i).unwrap();
+                        if i % 100 == 0 {
+                            tokio::task::yield_now().await;
+                        }
+                    }
+                })
+            })
+            .collect();
+        for p in producers {
+            p.await.unwrap();
+        }
+        q.close();
+
+        let mut all = Vec::new();
+        for c in consumers {
+            all.extend(c.await.unwrap());
+        }
+        all.sort();
+        assert_eq!(all, (0..PRODUCERS * PER_PRODUCER).collect::<Vec<_>>());
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn implements_stream() {
+        use futures_core::Stream;
+
+        let q = AsyncQueue::new();
+        q.push('a').unwrap();
+        q.close();
+        let mut stream = q.stream();
+        let next = poll_fn(|cx| Pin::new(&mut stream).poll_next(cx)).await;
+        assert_eq!(next, Some('a'));
+        assert_eq!(poll_fn(|cx| Stream::poll_next(Pin::new(&mut stream), cx)).await, None);
+    }
+}
@@ -0,0 +1,362 @@
+// 任意精度无符号整数，32位的limb存在simple_deque_3::List里，头部是最低位
+// 加法、减法、乘小整数都是从头部(低位)往尾部走一遍，进位/借位顺着节点传下去；
+// 除以小整数和比较大小则从尾部(高位)往回走，所以用双向链表
+// 规范形式: 尾部没有值为0的limb，零就是空链表，这样比较时可以先比长度
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, AddAssign, Mul, MulAssign, Sub, SubAssign};
+use std::str::FromStr;
+
+use crate::simple_deque_3::List;
+
+#[derive(Clone, Default, PartialEq, Eq, Hash)]
+pub struct BigUint {
+    limbs: List<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseBigUintError;
+
+impl fmt::Display for ParseBigUintError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid decimal digit string")
+    }
+}
+
+impl std::error::Error for ParseBigUintError {}
+
+impl BigUint {
+    pub fn zero() -> Self {
+        BigUint::default()
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    // limb个数
+    pub fn limb_count(&self) -> usize {
+        self.limbs.len()
+    }
+
+    // 二进制位数，零为0
+    pub fn bits(&self) -> u64 {
+        self.limbs
+            .back()
+            .map_or(0, |&top| 32 * self.limbs.len() as u64 - top.leading_zeros() as u64)
+    }
+
+    // 装得进u64时返回它的值
+    pub fn to_u64(&self) -> Option<u64> {
+        let mut iter = self.limbs.iter();
+        match (iter.next(), iter.next(), iter.next()) {
+            (None, _, _) => Some(0),
+            (Some(&lo), None, _) => Some(lo as u64),
+            (Some(&lo), Some(&hi), None) => Some((hi as u64) << 32 | lo as u64),
+            _ => None,
+        }
+    }
+
+    // self < rhs时返回None
+    pub fn checked_sub(&self, rhs: &BigUint) -> Option<BigUint> {
+        (*self >= *rhs).then(|| {
+            let mut out = self.clone();
+            out.sub_limbs(rhs);
+            out
+        })
+    }
+
+    // 原地除以d，返回余数；d为0时panic
+    pub fn div_rem_small(&mut self, d: u32) -> u32 {
+        assert!(d != 0, "attempt to divide by zero");
+        let mut rem = 0u64;
+        for limb in self.limbs.iter_mut().rev() {
+            let cur = rem << 32 | *limb as u64;
+            *limb = (cur / d as u64) as u32;
+            rem = cur % d as u64;
+        }
+        self.trim();
+        rem as u32
+    }
+
+    // 去掉高位的0，恢复规范形式
+    fn trim(&mut self) {
+        while self.limbs.back() == Some(&0) {
+            self.limbs.pop_back();
+        }
+    }
+
+    // 调用者保证self >= rhs
+    fn sub_limbs(&mut self, rhs: &BigUint) {
+        let mut borrow = 0i64;
+        let mut rhs_iter = rhs.limbs.iter();
+        for limb in self.limbs.iter_mut() {
+            let r = rhs_iter.next().copied();
+            if r.is_none() && borrow == 0 {
+                break;
+            }
+            let diff = *limb as i64 - r.unwrap_or(0) as i64 - borrow;
+            borrow = (diff < 0) as i64;
+            *limb = diff.rem_euclid(1 << 32) as u32;
+        }
+        debug_assert_eq!(borrow, 0);
+        self.trim();
+    }
+}
+
+impl From<u64> for BigUint {
+    fn from(value: u64) -> Self {
+        let mut limbs = List::new();
+        limbs.push_back(value as u32);
+        limbs.push_back((value >> 32) as u32);
+        let mut out = BigUint { limbs };
+        out.trim();
+        out
+    }
+}
+
+impl AddAssign<&BigUint> for BigUint {
+    fn add_assign(&mut self, rhs: &BigUint) {
+        let mut carry = 0u64;
+        let mut rhs_iter = rhs.limbs.iter();
+        for limb in self.limbs.iter_mut() {
+            let r = rhs_iter.next().copied();
+            if r.is_none() && carry == 0 {
+                return;
+            }
+            let sum = *limb as u64 + r.unwrap_or(0) as u64 + carry;
+            *limb = sum as u32;
+            carry = sum >> 32;
+        }
+        // rhs更长的部分和最后的进位接在高位
+        for &r in rhs_iter {
+            let sum = r as u64 + carry;
+            self.limbs.push_back(sum as u32);
+            carry = sum >> 32;
+        }
+        if carry != 0 {
+            self.limbs.push_back(carry as u32);
+        }
+    }
+}
+
+impl Add<&BigUint> for &BigUint {
+    type Output = BigUint;
+
+    fn add(self, rhs: &BigUint) -> BigUint {
+        let mut out = self.clone();
+        out += rhs;
+        out
+    }
+}
+
+impl Add for BigUint {
+    type Output = BigUint;
+
+    fn add(mut self, rhs: BigUint) -> BigUint {
+        self += &rhs;
+        self
+    }
+}
+
+// 结果为负时panic，和内置无符号整数一致；不想panic用checked_sub
+impl SubAssign<&BigUint> for BigUint {
+    fn sub_assign(&mut self, rhs: &BigUint) {
+        assert!(*self >= *rhs, "attempt to subtract with overflow");
+        self.sub_limbs(rhs);
+    }
+}
+
+impl Sub<&BigUint> for &BigUint {
+    type Output = BigUint;
+
+    fn sub(self, rhs: &BigUint) -> BigUint {
+        let mut out = self.clone();
+        out -= rhs;
+        out
+    }
+}
+
+impl Sub for BigUint {
+    type Output = BigUint;
+
+    fn sub(mut self, rhs: BigUint) -> BigUint {
+        self -= &rhs;
+        self
+    }
+}
+
+impl MulAssign<u32> for BigUint {
+    fn mul_assign(&mut self, rhs: u32) {
+        if rhs == 0 {
+            self.limbs.clear();
+            return;
+        }
+        let mut carry = 0u64;
+        for limb in self.limbs.iter_mut() {
+            let prod = *limb as u64 * rhs as u64 + carry;
+            *limb = prod as u32;
+            carry = prod >> 32;
+        }
+        if carry != 0 {
+            self.limbs.push_back(carry as u32);
+        }
+    }
+}
+
+impl Mul<u32> for BigUint {
+    type Output = BigUint;
+
+    fn mul(mut self, rhs: u32) -> BigUint {
+        self *= rhs;
+        self
+    }
+}
+
+impl Ord for BigUint {
+    // 规范形式下limb多的更大；一样多时从高位往低位比
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.limbs
+            .len()
+            .cmp(&other.limbs.len())
+            .then_with(|| self.limbs.iter().rev().cmp(other.limbs.iter().rev()))
+    }
+}
+
+impl PartialOrd for BigUint {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl FromStr for BigUint {
+    type Err = ParseBigUintError;
+
+    // 十进制数字串，不接受空串、符号和分隔符
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() {
+            return Err(ParseBigUintError);
+        }
+        let mut out = BigUint::zero();
+        // 每次吃进最多9位，乘10^k再加上这一段
+        for chunk in s.as_bytes().chunks(9) {
+            let mut value = 0u32;
+            for &b in chunk {
+                if !b.is_ascii_digit() {
+                    return Err(ParseBigUintError);
+                }
+                value = value * 10 + (b - b'0') as u32;
+            }
+            out *= 10u32.pow(chunk.len() as u32);
+            out += &BigUint::from(value as u64);
+        }
+        Ok(out)
+    }
+}
+
+impl fmt::Display for BigUint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_zero() {
+            return f.pad_integral(true, "", "0");
+        }
+        // 反复除以10^9，得到从低到高的9位一段
+        let mut n = self.clone();
+        let mut parts = Vec::new();
+        while !n.is_zero() {
+            parts.push(n.div_rem_small(1_000_000_000));
+        }
+        let mut s = parts.pop().unwrap().to_string();
+        for part in parts.iter().rev() {
+            s.push_str(&format!("{part:09}"));
+        }
+        f.pad_integral(true, "", &s)
+    }
+}
+
+impl fmt::Debug for BigUint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::XorShift;
+
+    fn big(s: &str) -> BigUint {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn carry_runs_through_every_limb() {
+        let mut n = big("340282366920938463463374607431768211455"); // 2^128 - 1
+        assert_eq!(n.limb_count(), 4);
+        n += &BigUint::from(1);
+        assert_eq!(n.limb_count(), 5);
+        assert_eq!(n.bits(), 129);
+        assert_eq!(n.to_string(), "340282366920938463463374607431768211456");
+        // 借位同样要一路传到最高位，结果变短
+        n -= &BigUint::from(1);
+        assert_eq!(n.limb_count(), 4);
+        assert_eq!(n.to_string(), "340282366920938463463374607431768211455");
+    }
+
+    #[test]
+    fn factorial_by_small_multiplication() {
+        let mut n = BigUint::from(1);
+        for i in 1..=30 {
+            n *= i;
+        }
+        assert_eq!(n.to_string(), "265252859812191058636308480000000");
+        assert_eq!(n.clone().div_rem_small(1_000_000), 0);
+        assert_eq!(n.clone() * std::hint::black_box(0), BigUint::zero());
+        assert_eq!(format!("{:>5}", BigUint::zero()), "    0");
+    }
+
+    #[test]
+    fn comparison_and_checked_sub() {
+        let a = big("18446744073709551616"); // 2^64
+        let b = big("18446744073709551615");
+        assert!(a > b);
+        assert!(b < a);
+        assert_eq!(a.checked_sub(&b), Some(BigUint::from(1)));
+        assert_eq!(b.checked_sub(&a), None);
+        assert_eq!(b.to_u64(), Some(u64::MAX));
+        assert_eq!(a.to_u64(), None);
+        assert_eq!(&a - &a, BigUint::zero());
+        assert!(BigUint::zero().is_zero());
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!("".parse::<BigUint>(), Err(ParseBigUintError));
+        assert_eq!("12a".parse::<BigUint>(), Err(ParseBigUintError));
+        assert_eq!("-1".parse::<BigUint>(), Err(ParseBigUintError));
+        assert_eq!(big("000123").to_string(), "123");
+    }
+
+    #[test]
+    #[should_panic(expected = "subtract with overflow")]
+    fn negative_result_panics() {
+        let _ = BigUint::from(1) - BigUint::from(2);
+    }
+
+    #[test]
+    fn randomized_against_u128() {
+        let mut rng = XorShift::new(0xb19);
+        for _ in 0..2000 {
+            let x = (rng.next_u64() >> (rng.next_u64() % 64)) as u128;
+            let y = (rng.next_u64() >> (rng.next_u64() % 64)) as u128;
+            let m = (rng.next_u64() >> 32) as u32;
+            let (a, b) = (BigUint::from(x as u64), BigUint::from(y as u64));
+            assert_eq!((&a + &b).to_string(), (x + y).to_string());
+            assert_eq!((a.clone() * m).to_string(), (x * m as u128).to_string());
+            assert_eq!(a.cmp(&b), x.cmp(&y));
+            assert_eq!(a.checked_sub(&b).map(|d| d.to_string()), x.checked_sub(y).map(|d| d.to_string()));
+            let s = (x * y).to_string();
+            assert_eq!(big(&s).to_string(), s);
+        }
+    }
+}
@@ -0,0 +1,528 @@
+// 二项堆(binomial heap)，由一串二项树组成的可合并最小堆
+// 度为k的二项树B_k恰有2^k个节点，根的孩子依次是B_{k-1}, ..., B_0
+// 根链表按度严格递增，n个元素对应n的二进制表示，最多log2(n)+1棵树
+// - link: 两棵度相同的树，大根挂到小根下面成为第一个孩子，得到度加一的树
+// - merge: 把两条根链表的树按度放进桶里，像二进制加法一样把相同度的树link起来(进位)，O(log n)
+// - pop_min: 在根链表里找最小根，摘掉后把它的孩子反转成一条根链表再merge回来
+// - decrease_key: 沿父指针向上冒泡
+// 节点放在slab里用下标链接(同pairing_heap)。冒泡时交换的是元素而不是节点，
+// 所以Handle指向的是一层间接的槽位，槽位记录元素当前所在的节点，冒泡时一并更新
+// Handle只对创建它的堆有效；merge之后被并入的那个堆的Handle全部失效
+
+use std::fmt;
+
+const NIL: usize = usize::MAX;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Handle {
+    index: usize,
+    generation: u64,
+}
+
+struct Node<T> {
+    elem: T,
+    // 元素对应的句柄槽位
+    slot: usize,
+    degree: usize,
+    parent: usize,
+    // 度最大的孩子
+    child: usize,
+    // 在根链表里是下一棵(度更大的)树，在孩子链表里是下一个(度更小的)兄弟
+    sibling: usize,
+}
+
+struct Slot {
+    generation: u64,
+    // 元素当前所在的节点，空闲时为NIL
+    node: usize,
+}
+
+pub struct BinomialHeap<T> {
+    nodes: Vec<Option<Node<T>>>,
+    free_nodes: Vec<usize>,
+    slots: Vec<Slot>,
+    free_slots: Vec<usize>,
+    // 根链表头，度最小的树
+    head: usize,
+    len: usize,
+}
+
+impl<T: Ord> BinomialHeap<T> {
+    pub fn new() -> Self {
+        BinomialHeap {
+            nodes: Vec::new(),
+            free_nodes: Vec::new(),
+            slots: Vec::new(),
+            free_slots: Vec::new(),
+            head: NIL,
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn node(&self, index: usize) -> &Node<T> {
+        self.nodes[index].as_ref().unwrap()
+    }
+
+    fn node_mut(&mut self, index: usize) -> &mut Node<T> {
+        self.nodes[index].as_mut().unwrap()
+    }
+
+    pub fn push(&mut self, elem: T) -> Handle {
+        let slot = match self.free_slots.pop() {
+            Some(slot) => slot,
+            None => {
+                self.slots.push(Slot {
+                    generation: 0,
+                    node: NIL,
+                });
+                self.slots.len() - 1
+            }
+        };
+        let node = Node {
+            elem,
+            slot,
+            degree: 0,
+            parent: NIL,
+            child: NIL,
+            sibling: NIL,
+        };
+        let index = match self.free_nodes.pop() {
+            Some(index) => {
+                self.nodes[index] = Some(node);
+                index
+            }
+            None => {
+                self.nodes.push(Some(node));
+                self.nodes.len() - 1
+            }
+        };
+        self.slots[slot].node = index;
+        self.head = self.union(self.head, index);
+        self.len += 1;
+        self.check_invariants();
+        Handle {
+            index: slot,
+            generation: self.slots[slot].generation,
+        }
+    }
+
+    // 扫描根链表，O(log n)
+    pub fn peek(&self) -> Option<&T> {
+        self.min_root().map(|(_, root)| &self.node(root).elem)
+    }
+
+    pub fn pop_min(&mut self) -> Option<T> {
+        let (prev, root) = self.min_root()?;
+        let next = self.node(root).sibling;
+        if prev == NIL {
+            self.head = next;
+        } else {
+            self.node_mut(prev).sibling = next;
+        }
+        // 孩子按度递减排列，反转后正好是一条合法的根链表
+        let mut children = NIL;
+        let mut cur = self.node(root).child;
+        while cur != NIL {
+            let node = self.node_mut(cur);
+            let next = node.sibling;
+            node.sibling = children;
+            node.parent = NIL;
+            children = cur;
+            cur = next;
+        }
+        self.head = self.union(self.head, children);
+        self.len -= 1;
+        let node = self.nodes[root].take().unwrap();
+        self.free_nodes.push(root);
+        let slot = &mut self.slots[node.slot];
+        slot.generation += 1;
+        slot.node = NIL;
+        self.free_slots.push(node.slot);
+        self.check_invariants();
+        Some(node.elem)
+    }
+
+    // 还在堆里的元素
+    pub fn get(&self, handle: Handle) -> Option<&T> {
+        self.is_live(handle).then(|| &self.node(self.slots[handle.index].node).elem)
+    }
+
+    pub fn contains(&self, handle: Handle) -> bool {
+        self.is_live(handle)
+    }
+
+    // 把handle对应的元素换成更小(或相等)的new；handle已经失效时返回false
+    // new比原来的值大时panic，那会破坏堆序
+    pub fn decrease_key(&mut self, handle: Handle, new: T) -> bool {
+        if !self.is_live(handle) {
+            return false;
+        }
+        let mut cur = self.slots[handle.index].node;
+        assert!(new <= self.node(cur).elem, "new key is greater than the current key");
+        self.node_mut(cur).elem = new;
+        loop {
+            let parent = self.node(cur).parent;
+            if parent == NIL || self.node(parent).elem <= self.node(cur).elem {
+                break;
+            }
+            self.swap_elems(cur, parent);
+            cur = parent;
+        }
+        self.check_invariants();
+        true
+    }
+
+    // 把other的所有元素并进来，other的Handle随之失效
+    pub fn merge(&mut self, other: BinomialHeap<T>) {
+        // 节点和槽位各自整体平移，所有下标加上对应的偏移
+        let node_offset = self.nodes.len();
+        let slot_offset = self.slots.len();
+        let shift = |i: usize, offset: usize| if i == NIL { NIL } else { i + offset };
+        for mut node in other.nodes {
+            if let Some(node) = node.as_mut() {
+                node.slot += slot_offset;
+                node.parent = shift(node.parent, node_offset);
+                node.child = shift(node.child, node_offset);
+                node.sibling = shift(node.sibling, node_offset);
+            }
+            self.nodes.push(node);
+        }
+        for mut slot in other.slots {
+            slot.node = shift(slot.node, node_offset);
+            self.slots.push(slot);
+        }
+        self.free_nodes.extend(other.free_nodes.into_iter().map(|i| i + node_offset));
+        self.free_slots.extend(other.free_slots.into_iter().map(|i| i + slot_offset));
+        self.head = self.union(self.head, shift(other.head, node_offset));
+        self.len += other.len;
+        self.check_invariants();
+    }
+
+    // 槽位保留下来并增加代数，清空前发出的Handle不会对上之后push的元素
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+        self.free_nodes.clear();
+        for (index, slot) in self.slots.iter_mut().enumerate() {
+            if slot.node != NIL {
+                slot.generation += 1;
+                slot.node = NIL;
+                self.free_slots.push(index);
+            }
+        }
+        self.head = NIL;
+        self.len = 0;
+    }
+
+    // 按升序取出所有元素
+    pub fn into_sorted_vec(mut self) -> Vec<T> {
+        let mut out = Vec::with_capacity(self.len);
+        while let Some(x) = self.pop_min() {
+            out.push(x);
+        }
+        out
+    }
+
+    fn is_live(&self, handle: Handle) -> bool {
+        self.slots
+            .get(handle.index)
+            .is_some_and(|slot| slot.generation == handle.generation && slot.node != NIL)
+    }
+
+    // 最小的根和它在根链表里的前驱(没有前驱时为NIL)
+    fn min_root(&self) -> Option<(usize, usize)> {
+        if self.head == NIL {
+            return None;
+        }
+        let mut best = (NIL, self.head);
+        let (mut prev, mut cur) = (self.head, self.node(self.head).sibling);
+        while cur != NIL {
+            if self.node(cur).elem < self.node(best.1).elem {
+                best = (prev, cur);
+            }
+            prev = cur;
+            cur = self.node(cur).sibling;
+        }
+        Some(best)
+    }
+
+    // 交换两个节点里的元素，并让两边的槽位跟着元素走
+    fn swap_elems(&mut self, a: usize, b: usize) {
+        let (lo, hi) = (a.min(b), a.max(b));
+        let (left, right) = self.nodes.split_at_mut(hi);
+        let x = left[lo].as_mut().unwrap();
+        let y = right[0].as_mut().unwrap();
+        std::mem::swap(&mut x.elem, &mut y.elem);
+        std::mem::swap(&mut x.slot, &mut y.slot);
+        self.slots[x.slot].node = lo;
+        self.slots[y.slot].node = hi;
+    }
+
+    // 两棵度相同的树，大根挂到小根下面，返回新根
+    fn link(&mut self, a: usize, b: usize) -> usize {
+        let (parent, child) = if self.node(b).elem < self.node(a).elem { (b, a) } else { (a, b) };
+        let first = self.node(parent).child;
+        let c = self.node_mut(child);
+        c.parent = parent;
+        c.sibling = first;
+        let p = self.node_mut(parent);
+        p.child = child;
+        p.degree += 1;
+        parent
+    }
+
+    // 合并两条根链表，返回新链表头
+    // 像二进制加法一样按度放进桶里，桶里已经有同度的树就link起来进位到下一个桶
+    fn union(&mut self, a: usize, b: usize) -> usize {
+        let mut by_degree: Vec<usize> = Vec::new();
+        for list in [a, b] {
+            let mut cur = list;
+            while cur != NIL {
+                let next = self.node(cur).sibling;
+                let mut tree = cur;
+                loop {
+                    let degree = self.node(tree).degree;
+                    if by_degree.len() <= degree {
+                        by_degree.resize(degree + 1, NIL);
+                    }
+                    let other = std::mem::replace(&mut by_degree[degree], NIL);
+                    if other == NIL {
+                        by_degree[degree] = tree;
+                        break;
+                    }
+                    tree = self.link(other, tree);
+                }
+                cur = next;
+            }
+        }
+        let mut head = NIL;
+        for &root in by_degree.iter().rev().filter(|&&root| root != NIL) {
+            self.node_mut(root).sibling = head;
+            head = root;
+        }
+        head
+    }
+
+    // 完整检查所有结构不变量，任何一条不满足都会panic:
+    // 根链表按度严格递增；每棵树都是二项树且满足堆序；父指针、槽位映射和len都一致
+    pub fn assert_invariants(&self) {
+        let mut count = 0;
+        let mut last_degree = None;
+        let mut root = self.head;
+        while root != NIL {
+            let node = self.node(root);
+            assert_eq!(node.parent, NIL, "root has a parent");
+            if let Some(last) = last_degree {
+                assert!(node.degree > last, "root degrees are not strictly increasing");
+            }
+            last_degree = Some(node.degree);
+            count += self.check_tree(root);
+            root = node.sibling;
+        }
+        assert_eq!(count, self.len, "cached len does not match node count");
+        let live = self.slots.iter().filter(|slot| slot.node != NIL).count();
+        assert_eq!(live, self.len, "live slot count does not match len");
+    }
+
+    // 检查以index为根的二项树，返回节点数
+    fn check_tree(&self, index: usize) -> usize {
+        let node = self.node(index);
+        assert_eq!(self.slots[node.slot].node, index, "slot does not point back to its node");
+        let mut count = 1;
+        let mut expect = node.degree;
+        let mut cur = node.child;
+        while cur != NIL {
+            let child = self.node(cur);
+            assert_eq!(child.parent, index, "child has a wrong parent pointer");
+            assert!(node.elem <= child.elem, "heap order violated");
+            assert!(expect > 0, "node has more children than its degree");
+            expect -= 1;
+            assert_eq!(child.degree, expect, "children degrees are not k-1, ..., 0");
+            count += self.check_tree(cur);
+            cur = child.sibling;
+        }
+        assert_eq!(expect, 0, "node has fewer children than its degree");
+        count
+    }
+
+    // 打开debug-invariants特性后，每次结构性修改都会做一次完整检查(仅debug构建)
+    #[inline]
+    fn check_invariants(&self) {
+        #[cfg(all(debug_assertions, feature = "debug-invariants"))]
+        self.assert_invariants();
+    }
+}
+
+impl<T: Ord> Default for BinomialHeap<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Ord + fmt::Debug> fmt::Debug for BinomialHeap<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BinomialHeap")
+            .field("len", &self.len)
+            .field("min", &self.peek())
+            .finish()
+    }
+}
+
+impl<T: Ord> Extend<T> for BinomialHeap<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for elem in iter {
+            self.push(elem);
+        }
+    }
+}
+
+impl<T: Ord> FromIterator<T> for BinomialHeap<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut heap = Self::new();
+        heap.extend(iter);
+        heap
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::XorShift;
+    use std::cmp::Reverse;
+    use std::collections::BinaryHeap;
+
+    fn root_degrees<T: Ord>(heap: &BinomialHeap<T>) -> Vec<usize> {
+        let mut out = Vec::new();
+        let mut cur = heap.head;
+        while cur != NIL {
+            out.push(heap.node(cur).degree);
+            cur = heap.node(cur).sibling;
+        }
+        out
+    }
+
+    #[test]
+    fn roots_follow_binary_representation() {
+        let mut heap = BinomialHeap::new();
+        assert_eq!(heap.pop_min(), None);
+        heap.extend([5, 3, 8, 1, 9, 2, 7, 3, 6, 0, 4]);
+        heap.assert_invariants();
+        // 11 = 0b1011
+        assert_eq!(root_degrees(&heap), vec![0, 1, 3]);
+        assert_eq!(heap.peek(), Some(&0));
+        assert_eq!(heap.pop_min(), Some(0));
+        assert_eq!(root_degrees(&heap), vec![1, 3]);
+        heap.assert_invariants();
+        assert_eq!(heap.into_sorted_vec(), vec![1, 2, 3, 3, 4, 5, 6, 7, 8, 9]);
+    }
+
+    #[test]
+    fn decrease_key_bubbles_up() {
+        let mut heap = BinomialHeap::new();
+        let handles: Vec<Handle> = (10..26).map(|x| heap.push(x)).collect();
+        assert_eq!(root_degrees(&heap), vec![4]);
+        // 最深的那个节点一路冒泡到根
+        assert!(heap.decrease_key(handles[15], 1));
+        heap.assert_invariants();
+        assert_eq!(heap.peek(), Some(&1));
+        assert_eq!(heap.get(handles[15]), Some(&1));
+        // 被换下去的元素的handle依旧有效
+        assert_eq!(heap.get(handles[0]), Some(&10));
+        assert!(heap.decrease_key(handles[0], 5));
+        assert_eq!(heap.pop_min(), Some(1));
+        assert!(!heap.decrease_key(handles[15], 0));
+        let reused = heap.push(100);
+        assert_eq!(reused.index, handles[15].index);
+        assert_eq!(heap.get(handles[15]), None);
+        heap.assert_invariants();
+        assert_eq!(heap.pop_min(), Some(5));
+    }
+
+    #[test]
+    #[should_panic(expected = "greater")]
+    fn increase_key_panics() {
+        let mut heap = BinomialHeap::new();
+        let h = heap.push(1);
+        heap.decrease_key(h, 2);
+    }
+
+    #[test]
+    fn merge_and_clear() {
+        let mut a: BinomialHeap<i32> = [4, 8, 1].into_iter().collect();
+        let mut b = BinomialHeap::new();
+        b.push(6);
+        b.push(2);
+        b.pop_min();
+        b.extend([0, 9, 3, 7]);
+        a.merge(b);
+        a.assert_invariants();
+        assert_eq!(a.len(), 8);
+        assert_eq!(root_degrees(&a), vec![3]);
+        let h = a.push(5);
+        a.clear();
+        assert!(a.is_empty() && !a.contains(h));
+        a.push(3);
+        assert_eq!(a.get(h), None);
+        a.assert_invariants();
+        assert_eq!(a.into_sorted_vec(), vec![3]);
+    }
+
+    #[test]
+    fn randomized_against_binary_heap() {
+        let mut rng = XorShift::new(0xb1e);
+        let mut heap = BinomialHeap::new();
+        let mut live: Vec<(Handle, u64)> = Vec::new();
+        for _ in 0..2000 {
+            match rng.next_u64() % 6 {
+                0..=2 => {
+                    let x = rng.next_u64() % 1000;
+                    live.push((heap.push(x), x));
+                }
+                3 => {
+                    let got = heap.pop_min();
+                    let reference: BinaryHeap<_> = live.iter().map(|&(_, v)| Reverse(v)).collect();
+                    assert_eq!(got, reference.peek().map(|Reverse(x)| *x));
+                    live.retain(|&(h, _)| heap.contains(h));
+                }
+                4 => {
+                    if live.is_empty() {
+                        continue;
+                    }
+                    let i = (rng.next_u64() as usize) % live.len();
+                    let (h, old) = live[i];
+                    let new = old.saturating_sub(rng.next_u64() % 50);
+                    assert!(heap.decrease_key(h, new));
+                    live[i].1 = new;
+                }
+                _ => {
+                    // 并入的堆的handle作废，之后只能用元素值核对
+                    let xs: Vec<u64> = (0..rng.next_u64() % 8).map(|_| rng.next_u64() % 1000).collect();
+                    let mut other = BinomialHeap::new();
+                    let hs: Vec<Handle> = xs.iter().map(|&x| other.push(x)).collect();
+                    let offset = heap.slots.len();
+                    heap.merge(other);
+                    for (h, x) in hs.into_iter().zip(xs) {
+                        let h = Handle {
+                            index: h.index + offset,
+                            ..h
+                        };
+                        assert_eq!(heap.get(h), Some(&x));
+                        live.push((h, x));
+                    }
+                }
+            }
+            heap.assert_invariants();
+            assert_eq!(heap.len(), live.len());
+            for &(h, v) in &live {
+                assert_eq!(heap.get(h), Some(&v));
+            }
+        }
+    }
+}
@@ -0,0 +1,393 @@
+// 定长块分配器(fixed-size block allocator / pool allocator)
+// 内核的slab、网络栈的包缓冲池、游戏引擎的对象池里都能看到它: 把一块内存切成大小相同的块，
+// 空闲块用一条侵入式单链表串起来 —— 链表的next指针就存在空闲块自己的前几个字节里，
+// 不需要任何额外的元数据内存。分配就是弹出表头，释放就是压回表头，都是O(1)
+// 内存由使用者提供(&mut [u8])，分配器只借用它，所以可以用在栈上的数组、静态缓冲区等任何地方
+//
+// 定长块之间不存在"合并成大块"的需要，但反复分配释放之后空闲链表的顺序会被打乱，
+// 连续分配出来的块在地址上四处分散。coalesce()把空闲链表按地址原地归并排序，
+// 之后的分配重新变得连续；stats()里的free_runs/largest_free_run用来观察碎片程度
+
+use std::fmt;
+use std::marker::PhantomData;
+use std::mem;
+use std::ptr::{self, NonNull};
+
+// 块的对齐，要能放下一个指针
+const ALIGN: usize = mem::align_of::<*mut u8>();
+
+pub struct BlockAllocator<'a> {
+    // 第一个块的地址，已经按ALIGN对齐
+    start: *mut u8,
+    block_size: usize,
+    capacity: usize,
+    // 空闲链表表头，null表示没有空闲块
+    head: *mut u8,
+    free: usize,
+    peak_in_use: usize,
+    allocs: u64,
+    frees: u64,
+    failed_allocs: u64,
+    _buf: PhantomData<&'a mut [u8]>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stats {
+    pub block_size: usize,
+    pub capacity: usize,
+    pub in_use: usize,
+    pub free: usize,
+    pub peak_in_use: usize,
+    pub allocs: u64,
+    pub frees: u64,
+    pub failed_allocs: u64,
+    // 地址连续的空闲块组成的段数，和其中最长一段的块数
+    pub free_runs: usize,
+    pub largest_free_run: usize,
+}
+
+// 空闲块开头存的是下一个空闲块的地址
+unsafe fn next_free(block: *mut u8) -> *mut u8 {
+    unsafe { block.cast::<*mut u8>().read() }
+}
+
+unsafe fn set_next_free(block: *mut u8, next: *mut u8) {
+    unsafe { block.cast::<*mut u8>().write(next) }
+}
+
+impl<'a> BlockAllocator<'a> {
+    // block_size会向上取整到能放下一个指针且满足对齐；buf开头不对齐的部分和末尾不够一块的部分不用
+    pub fn new(buf: &'a mut [u8], block_size: usize) -> Self {
+        let block_size = block_size.max(mem::size_of::<*mut u8>()).next_multiple_of(ALIGN);
+        let offset = buf.as_mut_ptr().align_offset(ALIGN);
+        let capacity = buf.len().saturating_sub(offset) / block_size;
+        let start = buf.as_mut_ptr().wrapping_add(offset);
+        let mut allocator = BlockAllocator {
+            start,
+            block_size,
+            capacity,
+            head: ptr::null_mut(),
+            free: capacity,
+            peak_in_use: 0,
+            allocs: 0,
+            frees: 0,
+            failed_allocs: 0,
+            _buf: PhantomData,
+        };
+        // 初始时按地址顺序把所有块串起来
+        for i in (0..capacity).rev() {
+            let block = allocator.block(i);
+            // SAFETY: block在buf内且按ALIGN对齐，能放下一个指针
+            unsafe { set_next_free(block, allocator.head) };
+            allocator.head = block;
+        }
+        allocator
+    }
+
+    fn block(&self, index: usize) -> *mut u8 {
+        self.start.wrapping_add(index * self.block_size)
+    }
+
+    pub fn block_size(&self) -> usize {
+        self.block_size
+    }
+
+    // 总块数
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    // 当前空闲块数
+    pub fn available(&self) -> usize {
+        self.free
+    }
+
+    pub fn in_use(&self) -> usize {
+        self.capacity - self.free
+    }
+
+    // 返回block_size字节、按指针对齐的一块内存，内容未初始化；用完时返回None
+    pub fn alloc(&mut self) -> Option<NonNull<u8>> {
+        let Some(block) = NonNull::new(self.head) else {
+            self.failed_allocs += 1;
+            return None;
+        };
+        // SAFETY: 表头是一个空闲块，开头存着下一个空闲块的地址
+        self.head = unsafe { next_free(block.as_ptr()) };
+        self.free -= 1;
+        self.allocs += 1;
+        self.peak_in_use = self.peak_in_use.max(self.in_use());
+        self.check_invariants();
+        Some(block)
+    }
+
+    // ptr是否指向本分配器的某个块的起始地址
+    pub fn owns(&self, ptr: NonNull<u8>) -> bool {
+        let addr = ptr.as_ptr() as usize;
+        let start = self.start as usize;
+        addr >= start
+            && addr < start + self.capacity * self.block_size
+            && (addr - start).is_multiple_of(self.block_size)
+    }
+
+    /// 把一个块还给分配器
+    ///
+    /// # Safety
+    ///
+    /// `ptr`必须是这个分配器的`alloc`返回、还没有被释放过的块，释放之后不能再访问它。
+    /// 不属于这个分配器的地址会被检查出来并panic，但重复释放检查不出来
+    pub unsafe fn free(&mut self, ptr: NonNull<u8>) {
+        assert!(self.owns(ptr), "pointer was not allocated from this allocator");
+        // SAFETY: 调用者保证这个块已经不再使用，可以拿来存next指针
+        unsafe { set_next_free(ptr.as_ptr(), self.head) };
+        self.head = ptr.as_ptr();
+        self.free += 1;
+        self.frees += 1;
+        self.check_invariants();
+    }
+
+    // 把空闲链表按地址升序原地归并排序，O(n log n)，不需要额外内存
+    pub fn coalesce(&mut self) {
+        // SAFETY: 空闲链表上正好有self.free个块，以null结尾
+        self.head = unsafe { merge_sort(self.head, self.free) };
+        self.check_invariants();
+    }
+
+    pub fn stats(&self) -> Stats {
+        let mut indices: Vec<usize> = self
+            .free_blocks()
+            .map(|block| (block as usize - self.start as usize) / self.block_size)
+            .collect();
+        indices.sort_unstable();
+        let (mut free_runs, mut largest_free_run, mut run) = (0, 0, 0);
+        for (i, &index) in indices.iter().enumerate() {
+            if i > 0 && indices[i - 1] + 1 == index {
+                run += 1;
+            } else {
+                free_runs += 1;
+                run = 1;
+            }
+            largest_free_run = largest_free_run.max(run);
+        }
+        Stats {
+            block_size: self.block_size,
+            capacity: self.capacity,
+            in_use: self.in_use(),
+            free: self.free,
+            peak_in_use: self.peak_in_use,
+            allocs: self.allocs,
+            frees: self.frees,
+            failed_allocs: self.failed_allocs,
+            free_runs,
+            largest_free_run,
+        }
+    }
+
+    fn free_blocks(&self) -> impl Iterator<Item = *mut u8> + '_ {
+        let mut cur = self.head;
+        // 最多走capacity步，链表被破坏成环时也能停下
+        (0..self.capacity).map_while(move |_| {
+            (!cur.is_null()).then(|| {
+                let block = cur;
+                // SAFETY: cur是空闲链表上的块
+                cur = unsafe { next_free(block) };
+                block
+            })
+        })
+    }
+
+    // 检查空闲链表: 每个节点都是本分配器的块，没有环，长度等于free
+    pub fn assert_invariants(&self) {
+        let mut count = 0;
+        for block in self.free_blocks() {
+            let block = NonNull::new(block).unwrap();
+            assert!(self.owns(block), "free list entry {count} points outside the buffer");
+            count += 1;
+        }
+        assert_eq!(count, self.free, "free list length does not match free count");
+        let last = self.free_blocks().last();
+        // SAFETY: last是空闲链表上的块
+        assert!(
+            last.is_none_or(|block| unsafe { next_free(block) }.is_null()),
+            "free list is longer than the number of blocks (double free?)"
+        );
+    }
+
+    // 打开debug-invariants特性后，每次结构性修改都会做一次完整检查(仅debug构建)
+    #[inline]
+    fn check_invariants(&self) {
+        #[cfg(all(debug_assertions, feature = "debug-invariants"))]
+        self.assert_invariants();
+    }
+}
+
+// 对以head开头、长度为len、以null结尾的空闲链表排序，返回新的表头
+unsafe fn merge_sort(head: *mut u8, len: usize) -> *mut u8 {
+    if len <= 1 {
+        return head;
+    }
+    let mid = len / 2;
+    unsafe {
+        // 在中间断开
+        let mut last = head;
+        for _ in 1..mid {
+            last = next_free(last);
+        }
+        let right = next_free(last);
+        set_next_free(last, ptr::null_mut());
+        let mut a = merge_sort(head, mid);
+        let mut b = merge_sort(right, len - mid);
+
+        let mut out = ptr::null_mut();
+        let mut tail: *mut u8 = ptr::null_mut();
+        while !a.is_null() && !b.is_null() {
+            let take = if a < b { a } else { b };
+            if take == a {
+                a = next_free(a);
+            } else {
+                b = next_free(b);
+            }
+            if tail.is_null() {
+                out = take;
+            } else {
+                set_next_free(tail, take);
+            }
+            tail = take;
+        }
+        let rest = if a.is_null() { b } else { a };
+        if tail.is_null() {
+            out = rest;
+        } else {
+            set_next_free(tail, rest);
+        }
+        out
+    }
+}
+
+impl fmt::Debug for BlockAllocator<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BlockAllocator")
+            .field("block_size", &self.block_size)
+            .field("capacity", &self.capacity)
+            .field("free", &self.free)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::XorShift;
+
+    // 对齐到8字节的缓冲区，方便算出确定的容量
+    #[repr(align(8))]
+    struct Buf<const N: usize>([u8; N]);
+
+    #[test]
+    fn alloc_until_exhausted_then_reuse() {
+        let mut buf = Buf([0u8; 64]);
+        let mut pool = BlockAllocator::new(&mut buf.0, 16);
+        assert_eq!(pool.capacity(), 4);
+        let blocks: Vec<_> = (0..4).map(|_| pool.alloc().unwrap()).collect();
+        // 初始链表按地址顺序，分配出来的块是连续的
+        for w in blocks.windows(2) {
+            assert_eq!(w[1].as_ptr() as usize - w[0].as_ptr() as usize, 16);
+        }
+        assert_eq!(pool.alloc(), None);
+        unsafe {
+            pool.free(blocks[1]);
+            pool.free(blocks[3]);
+        }
+        // 后进先出
+        assert_eq!(pool.alloc(), Some(blocks[3]));
+        assert_eq!(pool.alloc(), Some(blocks[1]));
+        let stats = pool.stats();
+        assert_eq!((stats.allocs, stats.frees, stats.failed_allocs), (6, 2, 1));
+        assert_eq!((stats.in_use, stats.peak_in_use), (4, 4));
+    }
+
+    #[test]
+    fn block_contents_do_not_clobber_each_other() {
+        let mut buf = Buf([0u8; 256]);
+        let mut pool = BlockAllocator::new(&mut buf.0, 8);
+        let blocks: Vec<_> = (0..32).map(|_| pool.alloc().unwrap().cast::<u64>()).collect();
+        for (i, block) in blocks.iter().enumerate() {
+            unsafe { block.as_ptr().write(i as u64 * 0x0101_0101) };
+        }
+        for &block in blocks.iter().step_by(2) {
+            unsafe { pool.free(block.cast()) };
+        }
+        pool.assert_invariants();
+        for (i, block) in blocks.iter().enumerate().skip(1).step_by(2) {
+            assert_eq!(unsafe { block.as_ptr().read() }, i as u64 * 0x0101_0101);
+        }
+    }
+
+    #[test]
+    fn rounds_block_size_and_skips_unaligned_prefix() {
+        let mut buf = Buf([0u8; 72]);
+        // 从第1个字节开始，前面7个字节因为对齐被跳过，剩64字节
+        let mut pool = BlockAllocator::new(&mut buf.0[1..], 3);
+        assert_eq!(pool.block_size(), mem::size_of::<*mut u8>());
+        assert_eq!(pool.capacity(), 64 / mem::size_of::<*mut u8>());
+        let block = pool.alloc().unwrap();
+        assert!((block.as_ptr() as usize).is_multiple_of(ALIGN));
+        let mut empty: [u8; 0] = [];
+        let mut none = BlockAllocator::new(&mut empty, 16);
+        assert_eq!(none.capacity(), 0);
+        assert_eq!(none.alloc(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "not allocated from this allocator")]
+    fn freeing_foreign_pointer_panics() {
+        let mut buf = Buf([0u8; 64]);
+        let mut pool = BlockAllocator::new(&mut buf.0, 16);
+        let block = pool.alloc().unwrap();
+        // 不在块边界上
+        unsafe { pool.free(NonNull::new(block.as_ptr().wrapping_add(4)).unwrap()) };
+    }
+
+    #[test]
+    fn coalesce_restores_contiguous_allocation() {
+        let mut buf = Buf([0u8; 8 * 40]);
+        let mut pool = BlockAllocator::new(&mut buf.0, 8);
+        let mut rng = XorShift::new(0xb10c);
+        let mut blocks: Vec<_> = (0..40).map(|_| pool.alloc().unwrap()).collect();
+        // 打乱顺序释放一半
+        for i in (1..blocks.len()).rev() {
+            blocks.swap(i, (rng.next_u64() % (i as u64 + 1)) as usize);
+        }
+        let kept = blocks.split_off(20);
+        for &block in &blocks {
+            unsafe { pool.free(block) };
+        }
+        let before = pool.stats();
+        assert_eq!(before.free, 20);
+        pool.coalesce();
+        pool.assert_invariants();
+        // 排序不改变有哪些块空闲
+        let after = pool.stats();
+        assert_eq!((after.free_runs, after.largest_free_run), (before.free_runs, before.largest_free_run));
+        let again: Vec<_> = (0..20).map(|_| pool.alloc().unwrap()).collect();
+        assert!(again.windows(2).all(|w| w[0] < w[1]));
+        blocks.sort();
+        assert_eq!(again, blocks);
+        assert!(kept.iter().all(|b| !again.contains(b)));
+        let stats = pool.stats();
+        assert_eq!((stats.free, stats.free_runs, stats.largest_free_run), (0, 0, 0));
+    }
+
+    #[test]
+    fn free_runs_count_adjacent_blocks() {
+        let mut buf = Buf([0u8; 8 * 10]);
+        let mut pool = BlockAllocator::new(&mut buf.0, 8);
+        let blocks: Vec<_> = (0..10).map(|_| pool.alloc().unwrap()).collect();
+        for i in [7, 1, 2, 8, 3, 9] {
+            unsafe { pool.free(blocks[i]) };
+        }
+        let stats = pool.stats();
+        // 空闲的是1-3和7-9两段
+        assert_eq!((stats.free_runs, stats.largest_free_run), (2, 3));
+    }
+}
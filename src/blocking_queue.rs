@@ -0,0 +1,342 @@
+// 基于simple_deque_2的有界阻塞FIFO队列
+// 和sync_deque一样用一把Mutex加两个Condvar，区别在于:
+// - 只支持一端进一端出，底层用更轻的单向队列simple_deque_2
+// - 支持close: 关闭后不能再push，pop取完剩余元素后返回Closed，所有等待的线程都会被唤醒
+// - 不忽略锁中毒: 持锁期间发生panic(比如元素的析构函数panic)后，所有操作都返回Poisoned
+
+use std::fmt;
+use std::sync::{Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use crate::instrument::mutation;
+use crate::simple_deque_2::List;
+
+struct State<T> {
+    list: List<T>,
+    // simple_deque_2不记录长度，由这里维护
+    len: usize,
+    closed: bool,
+}
+
+pub struct BlockingQueue<T> {
+    state: Mutex<State<T>>,
+    not_empty: Condvar,
+    not_full: Condvar,
+    capacity: usize,
+}
+
+// push失败时把元素原样还给调用者
+#[derive(PartialEq, Eq)]
+pub enum PushError<T> {
+    // 只有try_push会返回
+    Full(T),
+    // 只有push_timeout会返回
+    Timeout(T),
+    Closed(T),
+    Poisoned(T),
+}
+
+impl<T> PushError<T> {
+    pub fn into_inner(self) -> T {
+        match self {
+            PushError::Full(elem)
+            | PushError::Timeout(elem)
+            | PushError::Closed(elem)
+            | PushError::Poisoned(elem) => elem,
+        }
+    }
+}
+
+// 不要求T: Debug，unwrap()失败时也能打印
+impl<T> fmt::Debug for PushError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            PushError::Full(_) => "Full(..)",
+            PushError::Timeout(_) => "Timeout(..)",
+            PushError::Closed(_) => "Closed(..)",
+            PushError::Poisoned(_) => "Poisoned(..)",
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum PopError {
+    // 只有try_pop会返回
+    Empty,
+    // 只有pop_timeout会返回
+    Timeout,
+    // 已经关闭并且元素都取完了
+    Closed,
+    Poisoned,
+}
+
+impl<T> BlockingQueue<T> {
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "capacity must be greater than 0");
+        BlockingQueue {
+            state: Mutex::new(State {
+                list: List::new(),
+                len: 0,
+                closed: false,
+            }),
+            not_empty: Condvar::new(),
+            not_full: Condvar::new(),
+            capacity,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    // 锁中毒时返回0
+    pub fn len(&self) -> usize {
+        self.state.lock().map(|s| s.len).unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.state.lock().map(|s| s.closed).unwrap_or(true)
+    }
+
+    pub fn is_poisoned(&self) -> bool {
+        self.state.is_poisoned()
+    }
+
+    // 队列满时阻塞直到有空位或被关闭
+    pub fn push(&self, elem: T) -> Result<(), PushError<T>> {
+        let Ok(mut state) = self.state.lock() else {
+            return Err(PushError::Poisoned(elem));
+        };
+        while !state.closed && state.len >= self.capacity {
+            state = match self.not_full.wait(state) {
+                Ok(state) => state,
+                Err(_) => return Err(PushError::Poisoned(elem)),
+            };
+        }
+        self.push_locked(state, elem)
+    }
+
+    pub fn try_push(&self, elem: T) -> Result<(), PushError<T>> {
+        let Ok(state) = self.state.lock() else {
+            return Err(PushError::Poisoned(elem));
+        };
+        if !state.closed && state.len >= self.capacity {
+            return Err(PushError::Full(elem));
+        }
+        self.push_locked(state, elem)
+    }
+
+    pub fn push_timeout(&self, elem: T, timeout: Duration) -> Result<(), PushError<T>> {
+        let deadline = Instant::now() + timeout;
+        let Ok(mut state) = self.state.lock() else {
+            return Err(PushError::Poisoned(elem));
+        };
+        while !state.closed && state.len >= self.capacity {
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(PushError::Timeout(elem));
+            }
+            state = match self.not_full.wait_timeout(state, deadline - now) {
+                Ok((state, _)) => state,
+                Err(_) => return Err(PushError::Poisoned(elem)),
+            };
+        }
+        self.push_locked(state, elem)
+    }
+
+    // 队列为空时阻塞直到有元素或被关闭
+    pub fn pop(&self) -> Result<T, PopError> {
+        let mut state = self.state.lock().map_err(|_| PopError::Poisoned)?;
+        while !state.closed && state.len == 0 {
+            state = self.not_empty.wait(state).map_err(|_| PopError::Poisoned)?;
+        }
+        self.pop_locked(state)
+    }
+
+    pub fn try_pop(&self) -> Result<T, PopError> {
+        let state = self.state.lock().map_err(|_| PopError::Poisoned)?;
+        if !state.closed && state.len == 0 {
+            return Err(PopError::Empty);
+        }
+        self.pop_locked(state)
+    }
+
+    pub fn pop_timeout(&self, timeout: Duration) -> Result<T, PopError> {
+        let deadline = Instant::now() + timeout;
+        let mut state = self.state.lock().map_err(|_| PopError::Poisoned)?;
+        while !state.closed && state.len == 0 {
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(PopError::Timeout);
+            }
+            state = self
+                .not_empty
+                .wait_timeout(state, deadline - now)
+                .map_err(|_| PopError::Poisoned)?
+                .0;
+        }
+        self.pop_locked(state)
+    }
+
+    // 关闭队列并唤醒所有等待者；已经在队列里的元素仍然可以pop出来
+    // 返回false表示之前已经关闭过
+    pub fn close(&self) -> bool {
+        let Ok(mut state) = self.state.lock() else {
+            return false;
+        };
+        let was_open = !state.closed;
+        state.closed = true;
+        mutation!("close", len = state.len);
+        drop(state);
+        self.not_empty.notify_all();
+        self.not_full.notify_all();
+        was_open
+    }
+
+    // 丢弃所有元素，元素在持锁状态下析构，析构panic会让队列中毒
+    pub fn clear(&self) {
+        if let Ok(mut state) = self.state.lock() {
+            state.len = 0;
+            while state.list.pop_front().is_some() {}
+            mutation!("clear");
+            drop(state);
+            self.not_full.notify_all();
+        }
+    }
+
+    fn push_locked(&self, mut state: MutexGuard<'_, State<T>>, elem: T) -> Result<(), PushError<T>> {
+        if state.closed {
+            return Err(PushError::Closed(elem));
+        }
+        state.list.push_back(elem);
+        state.len += 1;
+        mutation!("push", len = state.len);
+        drop(state);
+        self.not_empty.notify_one();
+        Ok(())
+    }
+
+    fn pop_locked(&self, mut state: MutexGuard<'_, State<T>>) -> Result<T, PopError> {
+        match state.list.pop_front() {
+            Some(elem) => {
+                state.len -= 1;
+                mutation!("pop", len = state.len);
+                drop(state);
+                self.not_full.notify_one();
+                Ok(elem)
+            }
+            None => Err(PopError::Closed),
+        }
+    }
+}
+
+// simple_deque_2::List内部是裸指针，这里整体放在Mutex后面，只要元素能跨线程移动即可
+unsafe impl<T: Send> Send for BlockingQueue<T> {}
+unsafe impl<T: Send> Sync for BlockingQueue<T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::panic::{self, AssertUnwindSafe};
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn fifo_and_capacity() {
+        let q = BlockingQueue::new(2);
+        assert_eq!(q.try_pop(), Err(PopError::Empty));
+        q.push(1).unwrap();
+        q.try_push(2).unwrap();
+        assert_eq!(q.try_push(3), Err(PushError::Full(3)));
+        assert_eq!(q.push_timeout(3, Duration::from_millis(10)), Err(PushError::Timeout(3)));
+        assert_eq!(q.len(), 2);
+        assert_eq!(q.pop(), Ok(1));
+        assert_eq!(q.try_pop(), Ok(2));
+        assert_eq!(q.pop_timeout(Duration::from_millis(10)), Err(PopError::Timeout));
+        assert!(q.is_empty());
+    }
+
+    #[test]
+    fn blocking_push_and_pop() {
+        let q = Arc::new(BlockingQueue::new(1));
+        let producer = {
+            let q = Arc::clone(&q);
+            // 容量只有1，生产者每次push都要等消费者取走上一个
+            thread::spawn(move || {
+                for i in 0..100 {
+                    q.push(i).unwrap();
+                }
+            })
+        };
+        let received: Vec<i32> = (0..100).map(|_| q.pop().unwrap()).collect();
+        producer.join().unwrap();
+        assert_eq!(received, (0..100).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn close_wakes_waiters_and_drains() {
+        let q = Arc::new(BlockingQueue::<i32>::new(1));
+        let waiter = {
+            let q = Arc::clone(&q);
+            thread::spawn(move || q.pop())
+        };
+        thread::sleep(Duration::from_millis(20));
+        assert!(q.close());
+        assert_eq!(waiter.join().unwrap(), Err(PopError::Closed));
+        assert!(!q.close());
+        assert_eq!(q.push(1), Err(PushError::Closed(1)));
+
+        // 关闭前已经入队的元素仍然可以取出来
+        let q = BlockingQueue::new(2);
+        q.push(1).unwrap();
+        q.push(2).unwrap();
+        q.close();
+        assert!(q.is_closed());
+        assert_eq!(q.try_pop(), Ok(1));
+        assert_eq!(q.pop(), Ok(2));
+        assert_eq!(q.pop(), Err(PopError::Closed));
+        assert_eq!(q.try_push(3).unwrap_err().into_inner(), 3);
+    }
+
+    #[test]
+    fn close_wakes_blocked_producer() {
+        let q = Arc::new(BlockingQueue::new(1));
+        q.push(0).unwrap();
+        let producer = {
+            let q = Arc::clone(&q);
+            thread::spawn(move || q.push(1))
+        };
+        thread::sleep(Duration::from_millis(20));
+        q.close();
+        assert_eq!(producer.join().unwrap(), Err(PushError::Closed(1)));
+    }
+
+    struct PanicOnDrop;
+
+    impl Drop for PanicOnDrop {
+        fn drop(&mut self) {
+            panic!("boom");
+        }
+    }
+
+    #[test]
+    fn panic_while_locked_poisons_queue() {
+        let q = BlockingQueue::new(4);
+        q.push(PanicOnDrop).unwrap();
+        let result = panic::catch_unwind(AssertUnwindSafe(|| q.clear()));
+        assert!(result.is_err());
+        assert!(q.is_poisoned());
+        match q.push(PanicOnDrop) {
+            // 拿回来的元素析构时也会panic，直接忘掉
+            Err(PushError::Poisoned(elem)) => std::mem::forget(elem),
+            _ => panic!("expected Poisoned"),
+        }
+        assert!(matches!(q.try_pop(), Err(PopError::Poisoned)));
+        assert!(matches!(q.pop(), Err(PopError::Poisoned)));
+        assert!(matches!(q.pop_timeout(Duration::from_millis(1)), Err(PopError::Poisoned)));
+    }
+}
@@ -0,0 +1,310 @@
+// 单生产者广播日志: 只追加的分段链表，每个消费者各自持有一个游标，按自己的节奏往后读
+// 和队列不同，元素被读过之后并不移除，每个消费者都能读到游标之后的全部元素
+// - 链表的节点是装SEGMENT_SIZE个元素的段，生产者写好一个槽之后Release发布written，消费者Acquire读
+// - 段写满之后生产者挂上下一个段；next用OnceLock，只写一次
+// - 段之间、游标到段都用Arc相连: 一个段只被前一个段和停在它上面的游标引用，
+//   所有游标都越过它之后引用计数归零，段连同里面的元素自动释放，这就是日志的垃圾回收
+//   生产者只拿着最后一个段，没有消费者时已经写完的段立即被回收
+// 消费者可以Clone，新游标从同一个位置开始；Producer::subscribe从当前末尾开始订阅
+// 消费者读到的是&T，多个线程同时读同一个元素，所以要求T: Sync
+
+use std::cell::UnsafeCell;
+use std::mem::MaybeUninit;
+use std::sync::{Arc, OnceLock};
+
+use crate::instrument::mutation;
+use crate::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+pub const SEGMENT_SIZE: usize = 32;
+
+struct Segment<T> {
+    slots: [UnsafeCell<MaybeUninit<T>>; SEGMENT_SIZE],
+    // 已经写好的槽数，只有生产者写
+    written: AtomicUsize,
+    next: OnceLock<Arc<Segment<T>>>,
+}
+
+impl<T> Segment<T> {
+    fn new() -> Arc<Self> {
+        Arc::new(Segment {
+            slots: std::array::from_fn(|_| UnsafeCell::new(MaybeUninit::uninit())),
+            written: AtomicUsize::new(0),
+            next: OnceLock::new(),
+        })
+    }
+}
+
+impl<T> Drop for Segment<T> {
+    fn drop(&mut self) {
+        let written = self.written.load(Ordering::Relaxed);
+        for slot in &mut self.slots[..written] {
+            // SAFETY: 前written个槽已经写好，之后再没有人能访问这个段
+            unsafe { slot.get_mut().assume_init_drop() };
+        }
+        // 落后很多的游标释放时后面可能跟着一长串只被前一个段引用的段，循环释放避免递归太深
+        let mut next = self.next.take();
+        while let Some(segment) = next {
+            match Arc::try_unwrap(segment) {
+                Ok(mut segment) => next = segment.next.take(),
+                Err(_) => break,
+            }
+        }
+    }
+}
+
+// 槽在发布之后只读，元素会被多个线程同时读到并在任意线程释放
+unsafe impl<T: Send + Sync> Send for Segment<T> {}
+unsafe impl<T: Send + Sync> Sync for Segment<T> {}
+
+struct Shared {
+    // 一共追加了多少个元素
+    appended: AtomicUsize,
+    producer_alive: AtomicBool,
+}
+
+// 创建生产者和一个从头开始读的消费者
+pub fn channel<T>() -> (Producer<T>, Consumer<T>) {
+    let segment = Segment::new();
+    let shared = Arc::new(Shared {
+        appended: AtomicUsize::new(0),
+        producer_alive: AtomicBool::new(true),
+    });
+    let consumer = Consumer {
+        segment: Arc::clone(&segment),
+        index: 0,
+        position: 0,
+        shared: Arc::clone(&shared),
+    };
+    (Producer { tail: segment, shared }, consumer)
+}
+
+// 不能Clone，保证只有一个生产者
+pub struct Producer<T> {
+    tail: Arc<Segment<T>>,
+    shared: Arc<Shared>,
+}
+
+impl<T> Producer<T> {
+    pub fn append(&mut self, elem: T) {
+        let mut written = self.tail.written.load(Ordering::Relaxed);
+        if written == SEGMENT_SIZE {
+            let next = Segment::new();
+            let _ = self.tail.next.set(Arc::clone(&next));
+            self.tail = next;
+            written = 0;
+            mutation!("new_segment");
+        }
+        // SAFETY: 只有生产者写，槽written还没有发布，消费者不会读它
+        unsafe { (*self.tail.slots[written].get()).write(elem) };
+        self.tail.written.store(written + 1, Ordering::Release);
+        self.shared.appended.fetch_add(1, Ordering::Release);
+        mutation!("append", written);
+    }
+
+    // 已经追加的元素个数
+    pub fn len(&self) -> usize {
+        self.shared.appended.load(Ordering::Relaxed)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // 从当前末尾开始订阅，只能读到之后追加的元素
+    pub fn subscribe(&self) -> Consumer<T> {
+        Consumer {
+            segment: Arc::clone(&self.tail),
+            index: self.tail.written.load(Ordering::Relaxed),
+            position: self.len(),
+            shared: Arc::clone(&self.shared),
+        }
+    }
+}
+
+impl<T> Drop for Producer<T> {
+    fn drop(&mut self) {
+        // Release保证之前追加的元素对看到关闭的消费者可见
+        self.shared.producer_alive.store(false, Ordering::Release);
+    }
+}
+
+pub struct Consumer<T> {
+    segment: Arc<Segment<T>>,
+    // 在当前段里的下标
+    index: usize,
+    // 在整个日志里的下标
+    position: usize,
+    shared: Arc<Shared>,
+}
+
+impl<T> Consumer<T> {
+    // 读下一个元素并前进，暂时没有新元素时返回None
+    pub fn read(&mut self) -> Option<&T> {
+        if self.index == SEGMENT_SIZE {
+            // 换到下一个段，旧段如果没有别的游标引用就在这里释放
+            self.segment = Arc::clone(self.segment.next.get()?);
+            self.index = 0;
+            mutation!("advance_segment", position = self.position);
+        }
+        if self.index >= self.segment.written.load(Ordering::Acquire) {
+            return None;
+        }
+        let slot = &self.segment.slots[self.index];
+        self.index += 1;
+        self.position += 1;
+        // SAFETY: 槽已经发布并且之后只读，游标持有段的引用，返回的引用借用了self
+        Some(unsafe { (*slot.get()).assume_init_ref() })
+    }
+
+    // 已经读过的元素个数，也就是游标在日志里的位置
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    // 落后生产者多少个元素
+    pub fn lag(&self) -> usize {
+        self.shared.appended.load(Ordering::Acquire) - self.position
+    }
+
+    // 生产者已经不在并且已经读到末尾
+    pub fn is_closed(&self) -> bool {
+        !self.shared.producer_alive.load(Ordering::Acquire) && self.lag() == 0
+    }
+}
+
+impl<T: Clone> Consumer<T> {
+    pub fn read_cloned(&mut self) -> Option<T> {
+        self.read().cloned()
+    }
+}
+
+// 新游标从同一位置开始，之后和原来的游标互不影响
+impl<T> Clone for Consumer<T> {
+    fn clone(&self) -> Self {
+        Consumer {
+            segment: Arc::clone(&self.segment),
+            index: self.index,
+            position: self.position,
+            shared: Arc::clone(&self.shared),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn every_consumer_sees_every_element() {
+        let (mut producer, mut a) = channel();
+        assert_eq!(a.read(), None);
+        for i in 0..SEGMENT_SIZE + 3 {
+            producer.append(i);
+        }
+        let mut b = a.clone();
+        assert_eq!(a.read(), Some(&0));
+        assert_eq!(a.read(), Some(&1));
+        // b从克隆时的位置开始，不受a影响
+        assert_eq!(b.read(), Some(&0));
+        assert_eq!((a.position(), a.lag(), b.lag()), (2, SEGMENT_SIZE + 1, SEGMENT_SIZE + 2));
+        let rest: Vec<usize> = std::iter::from_fn(|| a.read_cloned()).collect();
+        assert_eq!(rest, (2..SEGMENT_SIZE + 3).collect::<Vec<_>>());
+        assert_eq!(a.lag(), 0);
+        producer.append(99);
+        assert_eq!(a.read(), Some(&99));
+        assert_eq!(producer.len(), SEGMENT_SIZE + 4);
+    }
+
+    #[test]
+    fn subscribe_starts_at_the_end() {
+        let (mut producer, all) = channel::<usize>();
+        for i in 0..SEGMENT_SIZE {
+            producer.append(i);
+        }
+        // 末尾正好是段的边界
+        let mut late = producer.subscribe();
+        assert_eq!((late.position(), late.lag()), (SEGMENT_SIZE, 0));
+        assert_eq!(late.read(), None);
+        producer.append(100);
+        let mut later = producer.subscribe();
+        producer.append(101);
+        assert_eq!(late.read_cloned(), Some(100));
+        assert_eq!(later.read_cloned(), Some(101));
+        assert_eq!(late.read_cloned(), Some(101));
+        assert!(!late.is_closed());
+        drop(producer);
+        assert!(late.is_closed() && !all.is_closed());
+    }
+
+    #[test]
+    fn segments_collected_once_every_cursor_passes() {
+        let marker = Arc::new(());
+        let (mut producer, mut fast) = channel();
+        let mut slow = fast.clone();
+        for _ in 0..3 * SEGMENT_SIZE {
+            producer.append(Arc::clone(&marker));
+        }
+        while fast.read().is_some() {}
+        // 慢游标还停在第一个段，所有元素都还活着
+        assert_eq!(Arc::strong_count(&marker), 3 * SEGMENT_SIZE + 1);
+        for _ in 0..SEGMENT_SIZE + 1 {
+            slow.read();
+        }
+        // 两个游标都离开了第一个段
+        assert_eq!(Arc::strong_count(&marker), 2 * SEGMENT_SIZE + 1);
+        drop(slow);
+        // 只剩fast停在最后一个段(生产者也持有它)
+        assert_eq!(Arc::strong_count(&marker), SEGMENT_SIZE + 1);
+        drop(fast);
+        assert_eq!(Arc::strong_count(&marker), SEGMENT_SIZE + 1);
+        // 没有消费者时，生产者换段就把写满的段丢掉
+        producer.append(Arc::clone(&marker));
+        assert_eq!(Arc::strong_count(&marker), 2);
+        drop(producer);
+        assert_eq!(Arc::strong_count(&marker), 1);
+    }
+
+    #[test]
+    fn long_backlog_dropped_without_recursion() {
+        let (mut producer, consumer) = channel();
+        for i in 0..100_000 * SEGMENT_SIZE {
+            producer.append(i as u8);
+        }
+        drop(producer);
+        assert_eq!(consumer.lag(), 100_000 * SEGMENT_SIZE);
+        drop(consumer);
+    }
+
+    #[test]
+    fn consumers_on_other_threads() {
+        const N: usize = 20_000;
+        let (mut producer, consumer) = channel();
+        let readers: Vec<_> = (0..3)
+            .map(|_| {
+                let mut consumer = consumer.clone();
+                thread::spawn(move || {
+                    let mut expected = 0;
+                    while !consumer.is_closed() {
+                        match consumer.read() {
+                            Some(&x) => {
+                                assert_eq!(x, expected);
+                                expected += 1;
+                            }
+                            None => thread::yield_now(),
+                        }
+                    }
+                    expected
+                })
+            })
+            .collect();
+        drop(consumer);
+        for i in 0..N {
+            producer.append(i);
+        }
+        drop(producer);
+        for reader in readers {
+            assert_eq!(reader.join().unwrap(), N);
+        }
+    }
+}
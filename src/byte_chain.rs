@@ -0,0 +1,377 @@
+// 分块字节缓冲区: 字节存放在链表串起来的若干块里，块的存储用Arc共享，类似bytes::Bytes
+// 每个块是共享存储上的一段视图[start, end)，所以:
+// - split_to(n)把前n个字节切成一条新的ByteChain，整块直接摘过去，分界处的块两边各持有同一份存储的一段，不复制字节
+// - append把另一条链整条接到末尾，O(1)
+// - advance丢掉前n个字节，只移动视图的起点，整块用完才释放
+// 实现了std::io::Read/BufRead/Write，可以直接放进I/O管道:
+// - BufRead::fill_buf返回第一块的剩余字节，consume就是advance
+// - Write把数据追加到最后一块: 最后一块的存储没有被共享且视图延伸到存储末尾时原地扩展，否则新开一块
+// 底层是simple_deque_3::List
+
+use std::fmt;
+use std::io::{self, BufRead, Read, Write};
+use std::sync::Arc;
+
+use crate::error::ListError;
+use crate::simple_deque_3::List;
+
+struct Chunk {
+    data: Arc<Vec<u8>>,
+    start: usize,
+    end: usize,
+}
+
+impl Chunk {
+    fn bytes(&self) -> &[u8] {
+        &self.data[self.start..self.end]
+    }
+
+    fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    // 在at处一分为二，前半段返回，自己留下后半段；两段共享同一份存储
+    fn split_front(&mut self, at: usize) -> Chunk {
+        let front = Chunk {
+            data: Arc::clone(&self.data),
+            start: self.start,
+            end: self.start + at,
+        };
+        self.start += at;
+        front
+    }
+}
+
+pub struct ByteChain {
+    chunks: List<Chunk>,
+    len: usize,
+}
+
+impl ByteChain {
+    pub fn new() -> Self {
+        ByteChain {
+            chunks: List::new(),
+            len: 0,
+        }
+    }
+
+    // 字节数
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn chunk_count(&self) -> usize {
+        self.chunks.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.chunks.clear();
+        self.len = 0;
+    }
+
+    // 把整个Vec作为一块接到末尾，不复制
+    pub fn push_chunk(&mut self, bytes: Vec<u8>) {
+        if bytes.is_empty() {
+            return;
+        }
+        self.len += bytes.len();
+        self.chunks.push_back(Chunk {
+            start: 0,
+            end: bytes.len(),
+            data: Arc::new(bytes),
+        });
+    }
+
+    // 把other的所有块接到末尾，other变为空
+    pub fn append(&mut self, other: &mut ByteChain) {
+        self.chunks.append(&mut other.chunks);
+        self.len += other.len;
+        other.len = 0;
+    }
+
+    // 切下前at个字节作为新链返回，自己留下剩余部分；at超过长度时panic
+    pub fn split_to(&mut self, at: usize) -> ByteChain {
+        assert!(at <= self.len, "split position out of bounds");
+        let mut front = ByteChain::new();
+        let mut remaining = at;
+        while remaining > 0 {
+            let chunk = self.chunks.front_mut().unwrap();
+            let piece = if chunk.len() <= remaining {
+                self.chunks.pop_front().unwrap()
+            } else {
+                chunk.split_front(remaining)
+            };
+            remaining -= piece.len();
+            front.chunks.push_back(piece);
+        }
+        front.len = at;
+        self.len -= at;
+        front
+    }
+
+    // at超过长度时返回OutOfRange，自己保持不变
+    pub fn try_split_to(&mut self, at: usize) -> Result<ByteChain, ListError> {
+        if at > self.len {
+            return Err(ListError::OutOfRange { index: at, len: self.len });
+        }
+        Ok(self.split_to(at))
+    }
+
+    // 丢掉前n个字节；n超过长度时panic
+    pub fn advance(&mut self, n: usize) {
+        assert!(n <= self.len, "cannot advance past the end");
+        let mut remaining = n;
+        while remaining > 0 {
+            let chunk = self.chunks.front_mut().unwrap();
+            if chunk.len() <= remaining {
+                remaining -= chunk.len();
+                self.chunks.pop_front();
+            } else {
+                chunk.start += remaining;
+                remaining = 0;
+            }
+        }
+        self.len -= n;
+    }
+
+    // n超过长度时返回OutOfRange，自己保持不变
+    pub fn try_advance(&mut self, n: usize) -> Result<(), ListError> {
+        if n > self.len {
+            return Err(ListError::OutOfRange { index: n, len: self.len });
+        }
+        self.advance(n);
+        Ok(())
+    }
+
+    // 按顺序访问每一块的字节
+    pub fn chunks(&self) -> impl Iterator<Item = &[u8]> + '_ {
+        self.chunks.iter().map(Chunk::bytes)
+    }
+
+    // 把所有字节复制到一个连续的Vec里
+    pub fn to_vec(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.len);
+        for chunk in self.chunks() {
+            out.extend_from_slice(chunk);
+        }
+        out
+    }
+}
+
+impl Default for ByteChain {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<Vec<u8>> for ByteChain {
+    fn from(bytes: Vec<u8>) -> Self {
+        let mut chain = ByteChain::new();
+        chain.push_chunk(bytes);
+        chain
+    }
+}
+
+impl From<&[u8]> for ByteChain {
+    fn from(bytes: &[u8]) -> Self {
+        ByteChain::from(bytes.to_vec())
+    }
+}
+
+// 按字节内容比较，和分块方式无关
+impl PartialEq for ByteChain {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.chunks().flatten().eq(other.chunks().flatten())
+    }
+}
+
+impl Eq for ByteChain {}
+
+impl fmt::Debug for ByteChain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ByteChain")
+            .field("len", &self.len)
+            .field("chunks", &self.chunks.len())
+            .finish()
+    }
+}
+
+impl Read for ByteChain {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut read = 0;
+        while read < buf.len() {
+            let Some(chunk) = self.chunks.front_mut() else { break };
+            let n = chunk.len().min(buf.len() - read);
+            buf[read..read + n].copy_from_slice(&chunk.bytes()[..n]);
+            read += n;
+            self.advance(n);
+        }
+        Ok(read)
+    }
+}
+
+impl BufRead for ByteChain {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        Ok(self.chunks.front().map_or(&[], Chunk::bytes))
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.advance(amt);
+    }
+}
+
+impl Write for ByteChain {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        // 最后一块独占存储并且视图到存储末尾，可以原地扩展
+        if let Some(chunk) = self.chunks.back_mut() {
+            if let Some(data) = Arc::get_mut(&mut chunk.data) {
+                if chunk.end == data.len() {
+                    data.extend_from_slice(buf);
+                    chunk.end += buf.len();
+                    self.len += buf.len();
+                    return Ok(buf.len());
+                }
+            }
+        }
+        self.push_chunk(buf.to_vec());
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::XorShift;
+
+    #[test]
+    fn split_to_shares_storage() {
+        let mut chain = ByteChain::from(b"hello world".to_vec());
+        chain.push_chunk(b"!!".to_vec());
+        let mut hello = chain.split_to(5);
+        assert_eq!(hello.to_vec(), b"hello");
+        assert_eq!(chain.to_vec(), b" world!!");
+        // 分界处的块被两边共享，没有复制
+        let (a, b) = (hello.chunks().next().unwrap(), chain.chunks().next().unwrap());
+        assert_eq!(a.as_ptr().wrapping_add(5), b.as_ptr());
+        // 共享的块不能原地扩展，写入时新开一块
+        hello.write_all(b", there").unwrap();
+        assert_eq!(hello.chunk_count(), 2);
+        assert_eq!(chain.to_vec(), b" world!!");
+        // 整块切走
+        let rest = chain.split_to(6);
+        assert_eq!((rest.chunk_count(), chain.chunk_count()), (1, 1));
+        assert_eq!(chain.to_vec(), b"!!");
+    }
+
+    #[test]
+    fn append_and_advance() {
+        let mut a = ByteChain::from(&b"abc"[..]);
+        let mut b = ByteChain::from(&b"def"[..]);
+        a.append(&mut b);
+        assert!(b.is_empty());
+        assert_eq!((a.len(), a.chunk_count()), (6, 2));
+        a.advance(4);
+        assert_eq!(a.to_vec(), b"ef");
+        assert_eq!(a.chunk_count(), 1);
+        a.advance(2);
+        assert!(a.is_empty() && a.chunk_count() == 0);
+        assert_eq!(ByteChain::from(&b"ab"[..]), {
+            let mut c = ByteChain::from(&b"a"[..]);
+            c.push_chunk(b"b".to_vec());
+            c
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "split position out of bounds")]
+    fn split_past_end_panics() {
+        ByteChain::from(&b"ab"[..]).split_to(3);
+    }
+
+    #[test]
+    fn try_split_to_and_advance() {
+        let mut chain = ByteChain::from(&b"abc"[..]);
+        chain.push_chunk(b"def".to_vec());
+        assert_eq!(chain.try_split_to(7), Err(ListError::OutOfRange { index: 7, len: 6 }));
+        assert_eq!(chain.try_split_to(2).unwrap().to_vec(), b"ab");
+        assert_eq!(chain.try_advance(5), Err(ListError::OutOfRange { index: 5, len: 4 }));
+        assert_eq!(chain.to_vec(), b"cdef");
+        chain.try_advance(2).unwrap();
+        assert_eq!((chain.to_vec(), chain.chunk_count()), (b"ef".to_vec(), 1));
+    }
+
+    #[test]
+    fn io_adapters() {
+        let mut chain = ByteChain::new();
+        // 连续的小写入合并到同一块里
+        write!(chain, "first line\nsec").unwrap();
+        chain.write_all(b"ond line\n").unwrap();
+        assert_eq!(chain.chunk_count(), 1);
+        chain.push_chunk(b"third".to_vec());
+        let mut line = String::new();
+        chain.read_line(&mut line).unwrap();
+        assert_eq!(line, "first line\n");
+        let lines: Vec<String> = chain.split_to(chain.len()).lines().map(Result::unwrap).collect();
+        assert_eq!(lines, vec!["second line", "third"]);
+
+        // 通过io::copy在两条链之间搬运
+        let mut src = ByteChain::from(vec![7u8; 100]);
+        src.push_chunk(vec![9u8; 50]);
+        let mut dst = ByteChain::new();
+        assert_eq!(io::copy(&mut src, &mut dst).unwrap(), 150);
+        assert!(src.is_empty());
+        let mut out = Vec::new();
+        dst.read_to_end(&mut out).unwrap();
+        assert_eq!(out.len(), 150);
+        assert_eq!(out[99..101], [7, 9]);
+    }
+
+    #[test]
+    fn matches_vec_model() {
+        let mut rng = XorShift::new(699);
+        let mut chain = ByteChain::new();
+        let mut model: Vec<u8> = Vec::new();
+        for i in 0..2000u32 {
+            match rng.next_u64() % 5 {
+                0 => {
+                    let bytes = vec![i as u8; (rng.next_u64() % 8) as usize];
+                    model.extend_from_slice(&bytes);
+                    chain.push_chunk(bytes);
+                }
+                1 => {
+                    let bytes = [i as u8, (i >> 8) as u8];
+                    model.extend_from_slice(&bytes);
+                    chain.write_all(&bytes).unwrap();
+                }
+                2 => {
+                    let n = rng.next_u64() as usize % (model.len() + 1);
+                    let front = chain.split_to(n);
+                    assert_eq!(front.to_vec(), model.drain(..n).collect::<Vec<_>>());
+                }
+                3 => {
+                    let n = rng.next_u64() as usize % (model.len() + 1);
+                    chain.advance(n);
+                    model.drain(..n);
+                }
+                _ => {
+                    let mut buf = [0u8; 5];
+                    let n = chain.read(&mut buf).unwrap();
+                    assert_eq!(&buf[..n], model.drain(..n.min(model.len())).as_slice());
+                }
+            }
+            assert_eq!(chain.len(), model.len());
+        }
+        assert_eq!(chain.to_vec(), model);
+    }
+}
@@ -0,0 +1,294 @@
+// 日历队列(calendar queue, Brown 1988)，离散事件模拟里常用的优先队列
+// 把时间轴切成宽度为width的"天"，n个桶循环地代表一年里的n天: 时间t落在第(t / width) % n个桶里
+// 每个桶是一条按时间有序的sorted_list::SortedList，相同时间的事件保持调度顺序(FIFO)
+// pop_earliest从上次出队的那一天开始往后翻日历: 当天桶头的事件如果就在"今年"的这一天，它就是最早的；
+// 否则翻到下一天。整整翻了一年都没有找到(事件都在很远的将来)，就直接比较所有桶头找最小值跳过去
+// 事件数变成桶数的两倍或者不到一半时，桶数翻倍或减半，并按最早若干个事件的平均间隔重新估计width，
+// 让每个桶里平均只有常数个事件，schedule和pop_earliest都是均摊O(1)
+
+use std::cmp::Ordering;
+use std::fmt;
+
+use crate::sorted_list::SortedList;
+
+const MIN_BUCKETS: usize = 2;
+// 重新估计width时取样的事件个数
+const SAMPLE: usize = 25;
+
+struct Event<T> {
+    time: u64,
+    item: T,
+}
+
+// 只按时间比较，SortedList对相等元素是稳定插入的
+impl<T> PartialEq for Event<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.time == other.time
+    }
+}
+
+impl<T> Eq for Event<T> {}
+
+impl<T> PartialOrd for Event<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for Event<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.time.cmp(&other.time)
+    }
+}
+
+pub struct CalendarQueue<T> {
+    buckets: Vec<SortedList<Event<T>>>,
+    width: u64,
+    len: usize,
+    // 当前翻到的桶，以及它在时间轴上对应的那一天(time / width)
+    current: usize,
+    day: u64,
+}
+
+impl<T> CalendarQueue<T> {
+    pub fn new() -> Self {
+        Self::with_width(1)
+    }
+
+    // 预先知道事件的典型间隔时可以直接给出天的宽度；width为0时panic
+    pub fn with_width(width: u64) -> Self {
+        assert!(width > 0, "bucket width must be positive");
+        CalendarQueue {
+            buckets: (0..MIN_BUCKETS).map(|_| SortedList::new()).collect(),
+            width,
+            len: 0,
+            current: 0,
+            day: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn bucket_count(&self) -> usize {
+        self.buckets.len()
+    }
+
+    pub fn bucket_width(&self) -> u64 {
+        self.width
+    }
+
+    fn bucket_of(&self, time: u64) -> usize {
+        ((time / self.width) % self.buckets.len() as u64) as usize
+    }
+
+    // 把日历翻到time所在的那一天
+    fn seek(&mut self, time: u64) {
+        self.current = self.bucket_of(time);
+        self.day = time / self.width;
+    }
+
+    // 允许调度到比上次出队更早的时间，日历会倒回去
+    pub fn schedule(&mut self, time: u64, item: T) {
+        if self.len == 0 || time / self.width < self.day {
+            self.seek(time);
+        }
+        let bucket = self.bucket_of(time);
+        self.buckets[bucket].insert(Event { time, item });
+        self.len += 1;
+        if self.len > 2 * self.buckets.len() {
+            self.resize(2 * self.buckets.len());
+        }
+    }
+
+    pub fn pop_earliest(&mut self) -> Option<(u64, T)> {
+        if self.len == 0 {
+            return None;
+        }
+        let mut found = None;
+        for _ in 0..self.buckets.len() {
+            if self.buckets[self.current]
+                .first()
+                .is_some_and(|e| e.time / self.width <= self.day)
+            {
+                found = Some(self.current);
+                break;
+            }
+            self.current = (self.current + 1) % self.buckets.len();
+            self.day = self.day.saturating_add(1);
+        }
+        let bucket = match found {
+            Some(bucket) => bucket,
+            None => {
+                // 一年之内都没有事件，直接跳到最早的那个
+                let time = self.peek_earliest().map(|(time, _)| time)?;
+                self.seek(time);
+                self.current
+            }
+        };
+        let event = self.buckets[bucket].pop_first()?;
+        self.len -= 1;
+        if self.buckets.len() > MIN_BUCKETS && self.len < self.buckets.len() / 2 {
+            self.resize(self.buckets.len() / 2);
+        }
+        Some((event.time, event.item))
+    }
+
+    // 比较所有桶头，O(桶数)
+    pub fn peek_earliest(&self) -> Option<(u64, &T)> {
+        self.buckets
+            .iter()
+            .filter_map(|bucket| bucket.first())
+            .min_by_key(|e| e.time)
+            .map(|e| (e.time, &e.item))
+    }
+
+    pub fn clear(&mut self) {
+        for bucket in &mut self.buckets {
+            bucket.clear();
+        }
+        self.len = 0;
+    }
+
+    // 换成n个桶并重新估计width，所有事件按时间顺序重新插入
+    fn resize(&mut self, n: usize) {
+        let mut events = Vec::with_capacity(self.len);
+        for bucket in &mut self.buckets {
+            while let Some(event) = bucket.pop_first() {
+                events.push(event);
+            }
+        }
+        // 稳定排序，相同时间的事件保持原来的顺序
+        events.sort();
+        self.width = Self::estimate_width(&events).unwrap_or(self.width);
+        self.buckets = (0..n).map(|_| SortedList::new()).collect();
+        if let Some(first) = events.first() {
+            self.seek(first.time);
+        }
+        for event in events {
+            let bucket = self.bucket_of(event.time);
+            self.buckets[bucket].insert(event);
+        }
+    }
+
+    // 最早若干个事件平均间隔的3倍；样本里时间全部相同时返回None，保留原来的width
+    fn estimate_width(events: &[Event<T>]) -> Option<u64> {
+        let sample = &events[..events.len().min(SAMPLE)];
+        let (first, last) = (sample.first()?.time, sample.last()?.time);
+        let gaps = (sample.len() - 1) as u64;
+        let span = last - first;
+        (span > 0).then(|| (3 * span / gaps).max(1))
+    }
+}
+
+impl<T> Default for CalendarQueue<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> fmt::Debug for CalendarQueue<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CalendarQueue")
+            .field("len", &self.len)
+            .field("buckets", &self.buckets.len())
+            .field("width", &self.width)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::XorShift;
+    use std::cmp::Reverse;
+    use std::collections::BinaryHeap;
+
+    #[test]
+    fn pops_in_time_order_with_fifo_ties() {
+        let mut queue = CalendarQueue::new();
+        for (time, name) in [(30, "c"), (10, "a"), (20, "b"), (10, "a2"), (5, "z")] {
+            queue.schedule(time, name);
+        }
+        assert_eq!(queue.peek_earliest(), Some((5, &"z")));
+        let order: Vec<_> = std::iter::from_fn(|| queue.pop_earliest()).collect();
+        assert_eq!(order, vec![(5, "z"), (10, "a"), (10, "a2"), (20, "b"), (30, "c")]);
+        assert!(queue.is_empty());
+        assert_eq!(queue.pop_earliest(), None);
+    }
+
+    #[test]
+    fn far_future_and_past_events() {
+        let mut queue = CalendarQueue::with_width(10);
+        queue.schedule(1_000_000, 'f');
+        queue.schedule(3, 'a');
+        assert_eq!(queue.pop_earliest(), Some((3, 'a')));
+        // 一年之内没有事件，直接跳过去
+        queue.schedule(2_000_000, 'g');
+        assert_eq!(queue.pop_earliest(), Some((1_000_000, 'f')));
+        // 调度到过去的时间，日历倒回去
+        queue.schedule(7, 'b');
+        assert_eq!(queue.pop_earliest(), Some((7, 'b')));
+        assert_eq!(queue.pop_earliest(), Some((2_000_000, 'g')));
+    }
+
+    #[test]
+    fn resizes_with_load() {
+        let mut queue = CalendarQueue::new();
+        for i in 0..1000u64 {
+            queue.schedule(i * 7, i);
+        }
+        assert!(queue.bucket_count() >= 500);
+        // 事件间隔是7，宽度按3倍平均间隔估计
+        assert_eq!(queue.bucket_width(), 21);
+        for i in 0..990 {
+            assert_eq!(queue.pop_earliest(), Some((i * 7, i)));
+        }
+        assert!(queue.bucket_count() <= 20);
+        assert_eq!(queue.len(), 10);
+        queue.clear();
+        assert_eq!(queue.peek_earliest(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "bucket width must be positive")]
+    fn zero_width_panics() {
+        CalendarQueue::<()>::with_width(0);
+    }
+
+    #[test]
+    fn discrete_event_simulation_matches_heap() {
+        // 每处理一个事件就按随机延迟调度1到2个后续事件，和BinaryHeap对照
+        let mut rng = XorShift::new(695);
+        let mut queue = CalendarQueue::new();
+        let mut heap = BinaryHeap::new();
+        let mut seq = 0u64;
+        for _ in 0..50 {
+            let time = rng.next_u64() % 100;
+            queue.schedule(time, seq);
+            heap.push(Reverse((time, seq)));
+            seq += 1;
+        }
+        let mut now = 0;
+        for _ in 0..5000 {
+            let (time, id) = queue.pop_earliest().unwrap();
+            let Reverse(expected) = heap.pop().unwrap();
+            assert_eq!((time, id), expected);
+            assert!(time >= now);
+            now = time;
+            let spawn = if queue.len() < 200 { 2 } else { 1 };
+            for _ in 0..spawn {
+                let at = now + rng.next_u64() % 50;
+                queue.schedule(at, seq);
+                heap.push(Reverse((at, seq)));
+                seq += 1;
+            }
+        }
+        assert_eq!(queue.len(), heap.len());
+    }
+}
@@ -0,0 +1,287 @@
+// 拉链法(separate chaining)哈希表，每个桶是一条simple_stack_2::List<(K, V)>
+// - 插入: 先在桶里找同一个key，找到就替换值，否则压到桶的头部
+// - 删除: 用remove_first_by把节点从桶里摘掉
+// - 扩容: 元素个数超过桶数 * MAX_LOAD时桶数翻倍；旧桶里的节点连着Box逐个摘下，
+//   按新的桶下标压进新桶，只搬指针，不为元素重新分配内存
+// 哈希器和std::collections::HashMap一样通过BuildHasher参数替换，测试里用它制造冲突
+
+use std::borrow::Borrow;
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::{BuildHasher, Hash};
+use std::mem;
+
+use crate::simple_stack_2::List;
+
+const INITIAL_BUCKETS: usize = 8;
+// 平均每个桶的元素个数上限
+const MAX_LOAD: usize = 1;
+
+pub struct ChainedHashMap<K, V, S = RandomState> {
+    buckets: Vec<List<(K, V)>>,
+    len: usize,
+    hasher: S,
+}
+
+impl<K, V> ChainedHashMap<K, V, RandomState> {
+    // 不分配桶，第一次插入时才分配
+    pub fn new() -> Self {
+        Self::with_hasher(RandomState::new())
+    }
+}
+
+impl<K, V, S> ChainedHashMap<K, V, S> {
+    pub fn with_hasher(hasher: S) -> Self {
+        ChainedHashMap {
+            buckets: Vec::new(),
+            len: 0,
+            hasher,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn bucket_count(&self) -> usize {
+        self.buckets.len()
+    }
+
+    // 桶的顺序，桶内从头到尾
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> + '_ {
+        self.buckets.iter().flat_map(|bucket| bucket.iter().map(|(k, v)| (k, v)))
+    }
+
+    // 保留已经分配的桶
+    pub fn clear(&mut self) {
+        for bucket in &mut self.buckets {
+            *bucket = List::new();
+        }
+        self.len = 0;
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher> ChainedHashMap<K, V, S> {
+    fn bucket_index<Q: Hash + ?Sized>(&self, key: &Q) -> usize {
+        (self.hasher.hash_one(key) % self.buckets.len() as u64) as usize
+    }
+
+    fn bucket<Q>(&self, key: &Q) -> Option<&List<(K, V)>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        (!self.buckets.is_empty()).then(|| &self.buckets[self.bucket_index(key)])
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.bucket(key)?.iter().find(|(k, _)| k.borrow() == key).map(|(_, v)| v)
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        if self.buckets.is_empty() {
+            return None;
+        }
+        let index = self.bucket_index(key);
+        self.buckets[index]
+            .iter_mut()
+            .find(|(k, _)| k.borrow() == key)
+            .map(|(_, v)| v)
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get(key).is_some()
+    }
+
+    // key已存在时替换值并返回旧值
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        if let Some(slot) = self.get_mut(&key) {
+            return Some(mem::replace(slot, value));
+        }
+        if self.len >= self.buckets.len() * MAX_LOAD {
+            self.grow();
+        }
+        let index = self.bucket_index(&key);
+        self.buckets[index].push((key, value));
+        self.len += 1;
+        None
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        if self.buckets.is_empty() {
+            return None;
+        }
+        let index = self.bucket_index(key);
+        let (_, value) = self.buckets[index].remove_first_by(|(k, _)| k.borrow() == key)?;
+        self.len -= 1;
+        Some(value)
+    }
+
+    // 桶数翻倍，把旧桶的节点原样搬到新桶
+    fn grow(&mut self) {
+        let new_count = (self.buckets.len() * 2).max(INITIAL_BUCKETS);
+        let old = mem::replace(&mut self.buckets, (0..new_count).map(|_| List::new()).collect());
+        for mut bucket in old {
+            while let Some(node) = bucket.pop_boxed_node() {
+                let index = self.bucket_index(&node.elem().0);
+                self.buckets[index].push_boxed_node(node);
+            }
+        }
+    }
+}
+
+impl<K, V, S: Default> Default for ChainedHashMap<K, V, S> {
+    fn default() -> Self {
+        Self::with_hasher(S::default())
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher> Extend<(K, V)> for ChainedHashMap<K, V, S> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (k, v) in iter {
+            self.insert(k, v);
+        }
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher + Default> FromIterator<(K, V)> for ChainedHashMap<K, V, S> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = Self::default();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug, S> fmt::Debug for ChainedHashMap<K, V, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::XorShift;
+    use std::collections::HashMap;
+    use std::hash::{BuildHasherDefault, Hasher};
+
+    // 所有key都哈希到同一个值，整张表退化成一条链
+    #[derive(Default)]
+    struct Collide;
+
+    impl Hasher for Collide {
+        fn finish(&self) -> u64 {
+            42
+        }
+
+        fn write(&mut self, _bytes: &[u8]) {}
+    }
+
+    type Colliding<K, V> = ChainedHashMap<K, V, BuildHasherDefault<Collide>>;
+
+    #[test]
+    fn insert_get_remove() {
+        let mut map = ChainedHashMap::new();
+        assert_eq!(map.bucket_count(), 0);
+        assert_eq!(map.get("a"), None);
+        assert_eq!(map.remove("a"), None);
+        assert_eq!(map.insert("a".to_string(), 1), None);
+        assert_eq!(map.insert("b".to_string(), 2), None);
+        assert_eq!(map.insert("a".to_string(), 3), Some(1));
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get("a"), Some(&3));
+        *map.get_mut("b").unwrap() += 10;
+        assert_eq!(map.get("b"), Some(&12));
+        assert_eq!(map.remove("a"), Some(3));
+        assert!(!map.contains_key("a"));
+        assert_eq!(format!("{map:?}"), r#"{"b": 12}"#);
+        map.clear();
+        assert!(map.is_empty());
+        assert_eq!(map.bucket_count(), INITIAL_BUCKETS);
+    }
+
+    #[test]
+    fn full_collisions_still_work() {
+        let mut map: Colliding<u32, u32> = (0..100).map(|i| (i, i * i)).collect();
+        assert_eq!(map.len(), 100);
+        for i in 0..100 {
+            assert_eq!(map.get(&i), Some(&(i * i)));
+        }
+        // 全部挤在一个桶里
+        let index = map.bucket_index(&0);
+        assert_eq!(map.buckets[index].iter().count(), 100);
+        for i in (0..100).step_by(3) {
+            assert_eq!(map.remove(&i), Some(i * i));
+        }
+        assert_eq!(map.remove(&0), None);
+        assert_eq!(map.len(), 66);
+        assert_eq!(map.insert(1, 0), Some(1));
+        assert!((0..100).all(|i| map.contains_key(&i) == (i % 3 != 0)));
+    }
+
+    #[test]
+    fn grow_keeps_load_and_reuses_nodes() {
+        let mut map = ChainedHashMap::new();
+        let mut addrs = Vec::new();
+        for i in 0..8 {
+            map.insert(i, i);
+            addrs.push(map.get(&i).unwrap() as *const i32);
+        }
+        assert_eq!(map.bucket_count(), 8);
+        // 第9个元素触发扩容，已有元素的地址不变说明节点只是被搬到了新桶
+        map.insert(8, 8);
+        assert_eq!(map.bucket_count(), 16);
+        for i in 0..8 {
+            assert_eq!(map.get(&i).unwrap() as *const i32, addrs[i as usize]);
+        }
+        for i in 9..1000 {
+            map.insert(i, i);
+        }
+        assert!(map.len() <= map.bucket_count() * MAX_LOAD);
+        assert_eq!(map.iter().count(), 1000);
+    }
+
+    #[test]
+    fn randomized_against_std() {
+        let mut rng = XorShift::new(0xc4a1);
+        let mut map = ChainedHashMap::new();
+        let mut model = HashMap::new();
+        for _ in 0..5000 {
+            let key = rng.next_u64() % 300;
+            let value = rng.next_u64();
+            match rng.next_u64() % 3 {
+                0 | 1 => assert_eq!(map.insert(key, value), model.insert(key, value)),
+                _ => assert_eq!(map.remove(&key), model.remove(&key)),
+            }
+            assert_eq!(map.len(), model.len());
+        }
+        for key in 0..300 {
+            assert_eq!(map.get(&key), model.get(&key));
+        }
+        let mut pairs: Vec<_> = map.iter().map(|(&k, &v)| (k, v)).collect();
+        pairs.sort();
+        let mut expect: Vec<_> = model.into_iter().collect();
+        expect.sort();
+        assert_eq!(pairs, expect);
+    }
+}
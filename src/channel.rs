@@ -0,0 +1,423 @@
+// 多生产者多消费者(MPMC)通道，元素放在无锁的ms_queue::MsQueue里
+// Sender和Receiver都可以clone，分别计数:
+// - 所有Sender都drop之后，Receiver取完剩余元素就得到断开错误
+// - 所有Receiver都drop之后，send把元素原样还回来
+// 收发本身走无锁队列，只有接收端要睡眠时才用到Mutex + Condvar:
+// 接收端先短暂自旋，仍然没有数据就登记为等待者再睡；发送端入队之后只在有等待者时才去加锁唤醒，
+// 所以没有人等待时send完全不碰锁
+// 等待者登记和队列检查之间用SeqCst fence和发送端配对: 要么接收端再检查时看到了新元素，
+// 要么发送端看到了等待者并去唤醒，不会出现元素在队列里而接收端一直睡着的情况
+
+use std::fmt;
+use std::sync::atomic::{fence, AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+use crate::instrument::mutation;
+use crate::ms_queue::MsQueue;
+
+// 睡眠之前最多自旋的次数
+const SPIN_LIMIT: u32 = 64;
+
+// 接收端都已经drop，元素原样还回来
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub struct SendError<T>(pub T);
+
+impl<T> fmt::Debug for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SendError { .. }")
+    }
+}
+
+impl<T> fmt::Display for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("sending on a disconnected channel")
+    }
+}
+
+impl<T> std::error::Error for SendError<T> {}
+
+// 发送端都已经drop，并且剩下的元素都已经取完
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct RecvError;
+
+impl fmt::Display for RecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("receiving on an empty and disconnected channel")
+    }
+}
+
+impl std::error::Error for RecvError {}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TryRecvError {
+    // 暂时没有元素
+    Empty,
+    Disconnected,
+}
+
+impl fmt::Display for TryRecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TryRecvError::Empty => f.write_str("receiving on an empty channel"),
+            TryRecvError::Disconnected => RecvError.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for TryRecvError {}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum RecvTimeoutError {
+    Timeout,
+    Disconnected,
+}
+
+impl fmt::Display for RecvTimeoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecvTimeoutError::Timeout => f.write_str("timed out waiting on channel"),
+            RecvTimeoutError::Disconnected => RecvError.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for RecvTimeoutError {}
+
+struct Shared<T> {
+    queue: MsQueue<T>,
+    senders: AtomicUsize,
+    receivers: AtomicUsize,
+    // 正在Condvar上睡眠(或者马上要睡)的接收端个数
+    waiting: AtomicUsize,
+    lock: Mutex<()>,
+    available: Condvar,
+}
+
+impl<T> Shared<T> {
+    fn lock(&self) -> MutexGuard<'_, ()> {
+        self.lock.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn disconnected(&self) -> bool {
+        self.senders.load(Ordering::Acquire) == 0
+    }
+}
+
+pub fn channel<T>() -> (Sender<T>, Receiver<T>) {
+    let shared = Arc::new(Shared {
+        queue: MsQueue::new(),
+        senders: AtomicUsize::new(1),
+        receivers: AtomicUsize::new(1),
+        waiting: AtomicUsize::new(0),
+        lock: Mutex::new(()),
+        available: Condvar::new(),
+    });
+    (
+        Sender {
+            shared: Arc::clone(&shared),
+        },
+        Receiver { shared },
+    )
+}
+
+pub struct Sender<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Sender<T> {
+    pub fn send(&self, elem: T) -> Result<(), SendError<T>> {
+        let shared = &self.shared;
+        if shared.receivers.load(Ordering::Relaxed) == 0 {
+            return Err(SendError(elem));
+        }
+        shared.queue.push(elem);
+        mutation!("send");
+        // 和接收端登记等待之后的fence配对
+        fence(Ordering::SeqCst);
+        if shared.waiting.load(Ordering::Relaxed) > 0 {
+            // 加锁保证接收端要么还没开始检查队列，要么已经在wait里释放了锁，唤醒不会丢
+            drop(shared.lock());
+            shared.available.notify_one();
+        }
+        Ok(())
+    }
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        self.shared.senders.fetch_add(1, Ordering::Relaxed);
+        Sender {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        // 最后一个发送端断开时叫醒所有等待者，让它们看到断开
+        if self.shared.senders.fetch_sub(1, Ordering::AcqRel) == 1 {
+            mutation!("disconnect");
+            drop(self.shared.lock());
+            self.shared.available.notify_all();
+        }
+    }
+}
+
+impl<T> fmt::Debug for Sender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Sender { .. }")
+    }
+}
+
+pub struct Receiver<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Receiver<T> {
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        if let Some(elem) = self.shared.queue.pop() {
+            mutation!("recv");
+            return Ok(elem);
+        }
+        if !self.shared.disconnected() {
+            return Err(TryRecvError::Empty);
+        }
+        // 最后一个发送端在上一次pop之后才断开，它断开前发出的元素需要再取一次
+        let elem = self.shared.queue.pop().ok_or(TryRecvError::Disconnected)?;
+        mutation!("recv");
+        Ok(elem)
+    }
+
+    // 阻塞直到收到元素，或者所有发送端断开且队列已空
+    pub fn recv(&self) -> Result<T, RecvError> {
+        self.recv_until(None).map_err(|_| RecvError)
+    }
+
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<T, RecvTimeoutError> {
+        self.recv_until(Some(Instant::now() + timeout))
+    }
+
+    fn recv_until(&self, deadline: Option<Instant>) -> Result<T, RecvTimeoutError> {
+        let shared = &self.shared;
+        let mut spins = 0;
+        loop {
+            match self.try_recv() {
+                Ok(elem) => return Ok(elem),
+                Err(TryRecvError::Disconnected) => return Err(RecvTimeoutError::Disconnected),
+                Err(TryRecvError::Empty) if spins < SPIN_LIMIT => {
+                    spins += 1;
+                    std::hint::spin_loop();
+                    continue;
+                }
+                Err(TryRecvError::Empty) => {}
+            }
+            let timeout = match deadline {
+                Some(deadline) => match deadline.checked_duration_since(Instant::now()) {
+                    Some(timeout) => Some(timeout),
+                    None => return Err(RecvTimeoutError::Timeout),
+                },
+                None => None,
+            };
+            let guard = shared.lock();
+            shared.waiting.fetch_add(1, Ordering::Relaxed);
+            // 和send里入队之后的fence配对，见文件开头的说明
+            fence(Ordering::SeqCst);
+            if shared.queue.is_empty() && !shared.disconnected() {
+                mutation!("wait");
+                // 醒来之后(包括超时和虚假唤醒)回到循环开头重新检查，锁随结果一起释放
+                match timeout {
+                    Some(timeout) => drop(shared.available.wait_timeout(guard, timeout)),
+                    None => drop(shared.available.wait(guard)),
+                }
+            } else {
+                drop(guard);
+            }
+            shared.waiting.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+
+    // 并发情况下只代表调用那一刻的状态
+    pub fn is_empty(&self) -> bool {
+        self.shared.queue.is_empty()
+    }
+
+    // 阻塞接收直到断开
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter { rx: self }
+    }
+
+    // 只取出当前已经在队列里的元素，不等待
+    pub fn try_iter(&self) -> TryIter<'_, T> {
+        TryIter { rx: self }
+    }
+}
+
+impl<T> Clone for Receiver<T> {
+    fn clone(&self) -> Self {
+        self.shared.receivers.fetch_add(1, Ordering::Relaxed);
+        Receiver {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        self.shared.receivers.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl<T> fmt::Debug for Receiver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Receiver { .. }")
+    }
+}
+
+pub struct Iter<'a, T> {
+    rx: &'a Receiver<T>,
+}
+
+impl<T> Iterator for Iter<'_, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.rx.recv().ok()
+    }
+}
+
+pub struct TryIter<'a, T> {
+    rx: &'a Receiver<T>,
+}
+
+impl<T> Iterator for TryIter<'_, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.rx.try_recv().ok()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Receiver<T> {
+    type Item = T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+    use std::thread;
+
+    // 让出CPU一小段时间，用来制造接收端先睡下的情况
+    fn nap() {
+        thread::sleep(Duration::from_millis(20));
+    }
+
+    #[test]
+    fn basics_and_disconnect() {
+        let (tx, rx) = channel();
+        assert_eq!(rx.try_recv(), Err(TryRecvError::Empty));
+        tx.send(1).unwrap();
+        let tx2 = tx.clone();
+        tx2.send(2).unwrap();
+        assert_eq!(rx.recv(), Ok(1));
+        drop(tx);
+        assert_eq!(rx.try_recv(), Ok(2));
+        assert_eq!(rx.try_recv(), Err(TryRecvError::Empty));
+        tx2.send(3).unwrap();
+        drop(tx2);
+        // 断开前发出的元素仍然可以收到
+        assert_eq!(rx.recv(), Ok(3));
+        assert_eq!(rx.recv(), Err(RecvError));
+        assert_eq!(rx.try_recv(), Err(TryRecvError::Disconnected));
+        assert_eq!(rx.recv_timeout(Duration::from_secs(1)), Err(RecvTimeoutError::Disconnected));
+    }
+
+    #[test]
+    fn send_fails_without_receivers() {
+        let (tx, rx) = channel();
+        let rx2 = rx.clone();
+        drop(rx);
+        tx.send(1).unwrap();
+        drop(rx2);
+        let err = tx.send(5).unwrap_err();
+        assert_eq!(err.0, 5);
+        assert_eq!(err.to_string(), "sending on a disconnected channel");
+    }
+
+    #[test]
+    fn blocking_recv_wakes_up() {
+        let (tx, rx) = channel();
+        let consumer = thread::spawn(move || rx.iter().collect::<Vec<i32>>());
+        nap();
+        tx.send(1).unwrap();
+        nap();
+        tx.send(2).unwrap();
+        nap();
+        // 最后一个发送端断开也要叫醒睡着的接收端
+        drop(tx);
+        assert_eq!(consumer.join().unwrap(), vec![1, 2]);
+    }
+
+    #[test]
+    fn recv_timeout_and_try_iter() {
+        let (tx, rx) = channel::<u8>();
+        let start = Instant::now();
+        assert_eq!(rx.recv_timeout(Duration::from_millis(30)), Err(RecvTimeoutError::Timeout));
+        assert!(start.elapsed() >= Duration::from_millis(30));
+        let sender = thread::spawn(move || {
+            nap();
+            tx.send(7).unwrap();
+            tx
+        });
+        assert_eq!(rx.recv_timeout(Duration::from_secs(10)), Ok(7));
+        let tx = sender.join().unwrap();
+        for i in 0..3 {
+            tx.send(i).unwrap();
+        }
+        assert_eq!(rx.try_iter().collect::<Vec<_>>(), vec![0, 1, 2]);
+        assert!(rx.is_empty());
+    }
+
+    #[test]
+    fn mpmc_delivers_each_element_once() {
+        const PRODUCERS: usize = 4;
+        const CONSUMERS: usize = 4;
+        const PER_PRODUCER: usize = 5000;
+
+        let (tx, rx) = channel();
+        let producers: Vec<_> = (0..PRODUCERS)
+            .map(|p| {
+                let tx = tx.clone();
+                thread::spawn(move || {
+                    for i in 0..PER_PRODUCER {
+                        tx.send(p * PER_PRODUCER + i).unwrap();
+                    }
+                })
+            })
+            .collect();
+        drop(tx);
+        let consumers: Vec<_> = (0..CONSUMERS)
+            .map(|_| {
+                let rx = rx.clone();
+                thread::spawn(move || rx.iter().collect::<Vec<_>>())
+            })
+            .collect();
+        drop(rx);
+        for p in producers {
+            p.join().unwrap();
+        }
+        let mut seen = HashSet::new();
+        for c in consumers {
+            for x in c.join().unwrap() {
+                assert!(seen.insert(x), "{} received twice", x);
+            }
+        }
+        assert_eq!(seen.len(), PRODUCERS * PER_PRODUCER);
+    }
+}
@@ -0,0 +1,386 @@
+// Chase-Lev工作窃取双端队列
+// 线程池里每个工作线程拥有一个这样的队列:
+// - 所有者(Worker)在bottom端push/pop，像栈一样后进先出，热路径上没有CAS
+// - 其他线程(Stealer)从top端steal，先进先出，用CAS抢top
+// 只有队列里剩最后一个元素时，所有者的pop才需要和窃取者CAS竞争top
+// 元素放在可扩容的环形缓冲区里，扩容后旧缓冲区可能还有窃取者在读，交给epoch回收器延迟释放
+// 实现参考Lê等人给出的C11内存序版本
+
+use std::cell::{Cell, UnsafeCell};
+use std::marker::PhantomData;
+use std::mem::MaybeUninit;
+use std::ptr;
+use std::sync::atomic::{fence, AtomicIsize, AtomicPtr, Ordering};
+use std::sync::Arc;
+
+use crate::epoch;
+use crate::instrument::mutation;
+
+// 初始容量，必须是2的幂
+const MIN_CAP: usize = 16;
+
+struct Buffer<T> {
+    // 容量总是2的幂，下标对cap取模只需要按位与
+    cap: usize,
+    slots: Box<[UnsafeCell<MaybeUninit<T>>]>,
+}
+
+impl<T> Buffer<T> {
+    fn alloc(cap: usize) -> *mut Self {
+        let slots = (0..cap).map(|_| UnsafeCell::new(MaybeUninit::uninit())).collect();
+        Box::into_raw(Box::new(Buffer { cap, slots }))
+    }
+
+    fn at(&self, i: isize) -> *mut MaybeUninit<T> {
+        self.slots[i as usize & (self.cap - 1)].get()
+    }
+
+    unsafe fn write(&self, i: isize, elem: T) {
+        ptr::write(self.at(i), MaybeUninit::new(elem));
+    }
+
+    // 按位复制出来，是否真正拥有这个元素由调用方的CAS结果决定
+    unsafe fn read(&self, i: isize) -> MaybeUninit<T> {
+        ptr::read(self.at(i))
+    }
+}
+
+struct Inner<T> {
+    // 窃取者取元素的一端
+    top: AtomicIsize,
+    // 所有者push/pop的一端，只有所有者写
+    bottom: AtomicIsize,
+    buffer: AtomicPtr<Buffer<T>>,
+}
+
+impl<T> Drop for Inner<T> {
+    fn drop(&mut self) {
+        // 所有者和窃取者都已经不在，析构[top, bottom)里剩下的元素
+        let top = *self.top.get_mut();
+        let bottom = *self.bottom.get_mut();
+        unsafe {
+            let buffer = Box::from_raw(*self.buffer.get_mut());
+            for i in top..bottom {
+                (*buffer.at(i)).assume_init_drop();
+            }
+        }
+    }
+}
+
+// 窃取的结果
+#[derive(Debug, PartialEq, Eq)]
+pub enum Steal<T> {
+    Empty,
+    Success(T),
+    // 和别的线程竞争失败，队列里可能还有元素，可以重试
+    Retry,
+}
+
+impl<T> Steal<T> {
+    pub fn success(self) -> Option<T> {
+        match self {
+            Steal::Success(elem) => Some(elem),
+            _ => None,
+        }
+    }
+}
+
+// 队列的所有者，只能在一个线程里使用(可以Send，但不是Sync)
+pub struct Worker<T> {
+    inner: Arc<Inner<T>>,
+    _not_sync: PhantomData<Cell<()>>,
+}
+
+impl<T> Worker<T> {
+    pub fn new() -> Self {
+        Worker {
+            inner: Arc::new(Inner {
+                top: AtomicIsize::new(0),
+                bottom: AtomicIsize::new(0),
+                buffer: AtomicPtr::new(Buffer::alloc(MIN_CAP)),
+            }),
+            _not_sync: PhantomData,
+        }
+    }
+
+    // 创建一个窃取者，可以Clone后分发给其他线程
+    pub fn stealer(&self) -> Stealer<T> {
+        Stealer {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        let bottom = self.inner.bottom.load(Ordering::Relaxed);
+        let top = self.inner.top.load(Ordering::Relaxed);
+        (bottom - top).max(0) as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn push(&self, elem: T) {
+        let inner = &*self.inner;
+        let bottom = inner.bottom.load(Ordering::Relaxed);
+        let top = inner.top.load(Ordering::Acquire);
+        let mut buffer = inner.buffer.load(Ordering::Relaxed);
+
+        // SAFETY: 只有所有者会替换缓冲区，这里读到的就是当前缓冲区
+        unsafe {
+            if bottom - top >= (*buffer).cap as isize {
+                buffer = self.grow(top, bottom, buffer);
+            }
+            (*buffer).write(bottom, elem);
+        }
+        // 元素写入必须在bottom对窃取者可见之前完成
+        fence(Ordering::Release);
+        inner.bottom.store(bottom + 1, Ordering::Relaxed);
+        mutation!("push", len = bottom + 1 - top);
+    }
+
+    pub fn pop(&self) -> Option<T> {
+        let inner = &*self.inner;
+        let bottom = inner.bottom.load(Ordering::Relaxed) - 1;
+        let buffer = inner.buffer.load(Ordering::Relaxed);
+        // 先占住bottom-1，再看top: SeqCst fence保证和steal里"读top -> 读bottom"之间有全序
+        inner.bottom.store(bottom, Ordering::Relaxed);
+        fence(Ordering::SeqCst);
+        let top = inner.top.load(Ordering::Relaxed);
+
+        if top > bottom {
+            // 队列本来就是空的，恢复bottom
+            inner.bottom.store(bottom + 1, Ordering::Relaxed);
+            return None;
+        }
+
+        let elem = unsafe { (*buffer).read(bottom) };
+        if top < bottom {
+            // 至少还剩两个元素，窃取者碰不到bottom这一格
+            mutation!("pop", len = bottom - top);
+            return Some(unsafe { elem.assume_init() });
+        }
+
+        // 只剩最后一个元素，和窃取者抢top
+        let won = inner
+            .top
+            .compare_exchange(top, top + 1, Ordering::SeqCst, Ordering::Relaxed)
+            .is_ok();
+        inner.bottom.store(bottom + 1, Ordering::Relaxed);
+        mutation!("pop_last", won);
+        if won {
+            Some(unsafe { elem.assume_init() })
+        } else {
+            // 元素已经被窃取者拿走，按位复制出来的这份不能析构
+            None
+        }
+    }
+
+    // 容量翻倍，把[top, bottom)搬到新缓冲区，旧缓冲区延迟释放
+    unsafe fn grow(&self, top: isize, bottom: isize, old: *mut Buffer<T>) -> *mut Buffer<T> {
+        let new = Buffer::alloc((*old).cap * 2);
+        for i in top..bottom {
+            ptr::copy_nonoverlapping((*old).at(i), (*new).at(i), 1);
+        }
+        let guard = epoch::pin();
+        self.inner.buffer.store(new, Ordering::Release);
+        mutation!("grow", cap = (*new).cap);
+        // 旧缓冲区里的元素已经按位搬走，Buffer的析构只释放内存，不会析构元素
+        guard.defer_destroy(old);
+        new
+    }
+}
+
+impl<T> Default for Worker<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub struct Stealer<T> {
+    inner: Arc<Inner<T>>,
+}
+
+impl<T> Stealer<T> {
+    pub fn steal(&self) -> Steal<T> {
+        let inner = &*self.inner;
+        let top = inner.top.load(Ordering::Acquire);
+        fence(Ordering::SeqCst);
+        let bottom = inner.bottom.load(Ordering::Acquire);
+        if top >= bottom {
+            return Steal::Empty;
+        }
+
+        // pin住之后读到的缓冲区即使被所有者替换也不会马上释放
+        let _guard = epoch::pin();
+        let buffer = inner.buffer.load(Ordering::Acquire);
+        let elem = unsafe { (*buffer).read(top) };
+        if inner
+            .top
+            .compare_exchange(top, top + 1, Ordering::SeqCst, Ordering::Relaxed)
+            .is_err()
+        {
+            // 别的线程先拿走了top，复制出来的这份不属于我们(MaybeUninit丢弃时不会析构)
+            mutation!("steal_retry");
+            return Steal::Retry;
+        }
+        mutation!("steal", top = top + 1);
+        Steal::Success(unsafe { elem.assume_init() })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        let top = self.inner.top.load(Ordering::Acquire);
+        let bottom = self.inner.bottom.load(Ordering::Acquire);
+        top >= bottom
+    }
+}
+
+impl<T> Clone for Stealer<T> {
+    fn clone(&self) -> Self {
+        Stealer {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+
+unsafe impl<T: Send> Send for Worker<T> {}
+unsafe impl<T: Send> Send for Stealer<T> {}
+unsafe impl<T: Send> Sync for Stealer<T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+    use std::sync::atomic::{AtomicBool, AtomicUsize};
+    use std::thread;
+
+    #[test]
+    fn owner_is_lifo_thief_is_fifo() {
+        let worker = Worker::new();
+        let stealer = worker.stealer();
+        assert_eq!(worker.pop(), None);
+        assert_eq!(stealer.steal(), Steal::Empty);
+        for i in 1..=4 {
+            worker.push(i);
+        }
+        assert_eq!(worker.len(), 4);
+        assert_eq!(worker.pop(), Some(4));
+        assert_eq!(stealer.steal(), Steal::Success(1));
+        assert_eq!(worker.pop(), Some(3));
+        assert_eq!(stealer.steal().success(), Some(2));
+        assert_eq!(worker.pop(), None);
+        assert!(worker.is_empty());
+        assert!(stealer.is_empty());
+    }
+
+    #[test]
+    fn grows_past_initial_capacity() {
+        let worker = Worker::new();
+        let stealer = worker.stealer();
+        for i in 0..MIN_CAP * 10 {
+            worker.push(i);
+            // 边push边steal，让环形缓冲区的下标绕圈之后再扩容
+            if i % 3 == 0 {
+                assert!(stealer.steal().success().is_some());
+            }
+        }
+        let mut rest = Vec::new();
+        while let Some(x) = worker.pop() {
+            rest.push(x);
+        }
+        assert!(rest.windows(2).all(|w| w[0] > w[1]));
+        assert_eq!(rest.len(), MIN_CAP * 10 - (MIN_CAP * 10).div_ceil(3));
+    }
+
+    #[test]
+    fn drop_remaining_elements() {
+        let marker = Arc::new(());
+        let worker = Worker::new();
+        let stealer = worker.stealer();
+        for _ in 0..MIN_CAP * 2 {
+            worker.push(Arc::clone(&marker));
+        }
+        drop(worker.pop());
+        drop(stealer.steal());
+        assert_eq!(Arc::strong_count(&marker), MIN_CAP * 2 - 1);
+        drop(worker);
+        drop(stealer);
+        assert_eq!(Arc::strong_count(&marker), 1);
+    }
+
+    // 经典竞争: 只剩最后一个元素时所有者pop和窃取者steal同时进行，恰好一方拿到
+    #[test]
+    fn last_element_race() {
+        const ROUNDS: usize = 10_000;
+        let worker = Worker::new();
+        let stealer = worker.stealer();
+        let stolen = Arc::new(AtomicUsize::new(0));
+        let done = Arc::new(AtomicBool::new(false));
+        let thief = {
+            let stolen = Arc::clone(&stolen);
+            let done = Arc::clone(&done);
+            thread::spawn(move || {
+                while !done.load(Ordering::Acquire) {
+                    if stealer.steal().success().is_some() {
+                        stolen.fetch_add(1, Ordering::Relaxed);
+                    }
+                }
+            })
+        };
+        let mut popped = 0;
+        for i in 0..ROUNDS {
+            worker.push(i);
+            if worker.pop().is_some() {
+                popped += 1;
+            }
+        }
+        done.store(true, Ordering::Release);
+        thief.join().unwrap();
+        assert_eq!(popped + stolen.load(Ordering::Relaxed), ROUNDS);
+    }
+
+    #[test]
+    fn stress_every_item_taken_exactly_once() {
+        const THIEVES: usize = 3;
+        const ITEMS: usize = 50_000;
+        let worker = Worker::new();
+        let done = Arc::new(AtomicBool::new(false));
+        let thieves: Vec<_> = (0..THIEVES)
+            .map(|_| {
+                let stealer = worker.stealer();
+                let done = Arc::clone(&done);
+                thread::spawn(move || {
+                    let mut got = Vec::new();
+                    loop {
+                        match stealer.steal() {
+                            Steal::Success(x) => got.push(x),
+                            Steal::Retry => {}
+                            Steal::Empty if done.load(Ordering::Acquire) => break,
+                            Steal::Empty => thread::yield_now(),
+                        }
+                    }
+                    got
+                })
+            })
+            .collect();
+
+        let mut mine = Vec::new();
+        for i in 0..ITEMS {
+            worker.push(i);
+            // 所有者也不时取回一些，和窃取者交错
+            if i % 4 == 0 {
+                mine.extend(worker.pop());
+            }
+        }
+        while let Some(x) = worker.pop() {
+            mine.push(x);
+        }
+        done.store(true, Ordering::Release);
+
+        let mut seen = HashSet::new();
+        for x in mine.into_iter().chain(thieves.into_iter().flat_map(|t| t.join().unwrap())) {
+            assert!(seen.insert(x), "{} taken twice", x);
+        }
+        assert_eq!(seen.len(), ITEMS);
+    }
+}
@@ -0,0 +1,546 @@
+// 无锁并发跳表(Herlihy-Shavit算法)，提供SkipMap和基于它的SkipSet
+// - 每一层的next指针最低位是删除标记，被标记的next不会再被修改
+// - 插入: 先用CAS把节点挂到第0层(这一步成功即插入生效)，再逐层往上挂
+// - 删除: 从最高层往下给节点的每个next打标记，第0层标记成功的线程才算删除了它(逻辑删除)，
+//   之后再查找一次，沿途把带标记的节点从各层摘下(物理删除)；任何线程查找时也会顺手摘
+// 内存回收用epoch: 危险指针每个线程只有两个槽，而跳表查找一路上要同时持有前驱、当前和后继，
+// 还要跨过已被标记的节点，逐个protect再校验的代价太高
+// 节点只有在所有层都摘下之后才能retire。插入线程可能在删除者清理完之后又把节点挂到某一层，
+// 所以节点带一个引用计数: 插入线程和删除者各持有一份，各自做完最后一次清理查找后释放，
+// 最后一个释放的线程负责retire
+
+use std::borrow::Borrow;
+use std::cell::RefCell;
+use std::fmt;
+use std::marker::PhantomData;
+use std::ptr;
+use std::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+
+use crate::epoch::{self, Guard};
+use crate::instrument::mutation;
+use crate::rng::XorShift;
+use crate::skip_list_map::MAX_LEVEL;
+
+// 各步之间的正确性依赖对不同地址的读写有统一的先后顺序(插入者读第0层标记和删除者清理查找之间)，
+// 全部使用SeqCst
+const SC: Ordering = Ordering::SeqCst;
+
+struct Node<K, V> {
+    key: K,
+    value: V,
+    next: Box<[AtomicPtr<Node<K, V>>]>,
+    // 插入线程和删除者各一份，归零时retire
+    refs: AtomicUsize,
+}
+
+fn is_marked<T>(p: *mut T) -> bool {
+    p.addr() & 1 == 1
+}
+
+fn marked<T>(p: *mut T) -> *mut T {
+    p.map_addr(|a| a | 1)
+}
+
+fn unmarked<T>(p: *mut T) -> *mut T {
+    p.map_addr(|a| a & !1)
+}
+
+fn random_level() -> usize {
+    thread_local! {
+        static RNG: RefCell<XorShift> = RefCell::new(XorShift::from_counter());
+    }
+    RNG.with(|rng| rng.borrow_mut().random_level())
+}
+
+pub struct SkipMap<K, V> {
+    // 头节点的各层指针
+    head: Box<[AtomicPtr<Node<K, V>>]>,
+    len: AtomicUsize,
+    _boo: PhantomData<Box<Node<K, V>>>,
+}
+
+// 每层的前驱槽(头节点或某个节点的next[i])和后继
+struct Position<K, V> {
+    preds: [*const AtomicPtr<Node<K, V>>; MAX_LEVEL],
+    succs: [*mut Node<K, V>; MAX_LEVEL],
+}
+
+impl<K, V> SkipMap<K, V> {
+    pub fn new() -> Self {
+        SkipMap {
+            head: (0..MAX_LEVEL).map(|_| AtomicPtr::new(ptr::null_mut())).collect(),
+            len: AtomicUsize::new(0),
+            _boo: PhantomData,
+        }
+    }
+
+    // 并发情况下只代表调用那一刻的近似值
+    pub fn len(&self) -> usize {
+        self.len.load(Ordering::Relaxed)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // 按key升序访问当前所有元素，遍历期间其他线程的修改可能看得到也可能看不到
+    pub fn for_each(&self, mut f: impl FnMut(&K, &V)) {
+        let _guard = epoch::pin();
+        let mut curr = unmarked(self.head[0].load(SC));
+        while !curr.is_null() {
+            // SAFETY: pin期间读到的节点不会被释放
+            unsafe {
+                let next = (*curr).next[0].load(SC);
+                if !is_marked(next) {
+                    f(&(*curr).key, &(*curr).value);
+                }
+                curr = unmarked(next);
+            }
+        }
+    }
+
+    unsafe fn release(node: *mut Node<K, V>, guard: &Guard) {
+        if (*node).refs.fetch_sub(1, Ordering::AcqRel) == 1 {
+            guard.defer_destroy(node);
+        }
+    }
+}
+
+impl<K: Ord, V> SkipMap<K, V> {
+    // 找到每层上最后一个key < 目标的节点和它的后继，路过带删除标记的节点时把它摘下
+    // 返回第0层的后继是否就是key
+    unsafe fn find<Q>(&self, key: &Q, pos: &mut Position<K, V>) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        'retry: loop {
+            let mut pred: &[AtomicPtr<Node<K, V>>] = &self.head;
+            for i in (0..MAX_LEVEL).rev() {
+                let mut curr = unmarked(pred[i].load(SC));
+                while !curr.is_null() {
+                    let succ = (*curr).next[i].load(SC);
+                    if is_marked(succ) {
+                        // curr已被删除，从第i层摘下；pred自己也被删除了的话CAS会失败，从头再来
+                        if pred[i].compare_exchange(curr, unmarked(succ), SC, SC).is_err() {
+                            continue 'retry;
+                        }
+                        curr = unmarked(succ);
+                    } else if (*curr).key.borrow() < key {
+                        pred = &(*curr).next;
+                        curr = succ;
+                    } else {
+                        break;
+                    }
+                }
+                pos.preds[i] = &pred[i];
+                pos.succs[i] = curr;
+            }
+            let found = pos.succs[0];
+            return !found.is_null() && (*found).key.borrow() == key;
+        }
+    }
+
+    // 只读查找，跳过带标记的节点但不修改结构
+    unsafe fn search<Q>(&self, key: &Q) -> *mut Node<K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut pred: &[AtomicPtr<Node<K, V>>] = &self.head;
+        let mut curr = ptr::null_mut();
+        for i in (0..MAX_LEVEL).rev() {
+            curr = unmarked(pred[i].load(SC));
+            while !curr.is_null() {
+                let succ = (*curr).next[i].load(SC);
+                if is_marked(succ) {
+                    curr = unmarked(succ);
+                } else if (*curr).key.borrow() < key {
+                    pred = &(*curr).next;
+                    curr = succ;
+                } else {
+                    break;
+                }
+            }
+        }
+        if !curr.is_null() && (*curr).key.borrow() == key {
+            curr
+        } else {
+            ptr::null_mut()
+        }
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let _guard = epoch::pin();
+        unsafe { !self.search(key).is_null() }
+    }
+
+    // 其他线程随时可能删除这个元素，所以返回克隆而不是引用
+    pub fn get<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        V: Clone,
+    {
+        let _guard = epoch::pin();
+        unsafe {
+            let node = self.search(key);
+            (!node.is_null()).then(|| (*node).value.clone())
+        }
+    }
+
+    // key已经存在时不做修改并返回false，传入的键值对被丢弃
+    pub fn insert(&self, key: K, value: V) -> bool {
+        let guard = epoch::pin();
+        let height = random_level();
+        let node = Box::into_raw(Box::new(Node {
+            key,
+            value,
+            next: (0..height).map(|_| AtomicPtr::new(ptr::null_mut())).collect(),
+            refs: AtomicUsize::new(2),
+        }));
+        let mut pos = Position {
+            preds: [ptr::null(); MAX_LEVEL],
+            succs: [ptr::null_mut(); MAX_LEVEL],
+        };
+        unsafe {
+            // 挂到第0层，成功后插入即生效
+            loop {
+                if self.find(&(*node).key, &mut pos) {
+                    drop(Box::from_raw(node));
+                    return false;
+                }
+                // 节点还没有发布，直接写
+                for i in 0..height {
+                    (*node).next[i].store(pos.succs[i], Ordering::Relaxed);
+                }
+                if (*pos.preds[0]).compare_exchange(pos.succs[0], node, SC, SC).is_ok() {
+                    break;
+                }
+            }
+            self.len.fetch_add(1, Ordering::Relaxed);
+            mutation!("insert", height);
+
+            // 逐层往上挂；节点一旦在某层被打了标记就不再继续
+            'levels: for i in 1..height {
+                loop {
+                    let next = (*node).next[i].load(SC);
+                    if is_marked(next)
+                        || (*node).next[i].compare_exchange(next, pos.succs[i], SC, SC).is_err()
+                    {
+                        break 'levels;
+                    }
+                    if (*pos.preds[i]).compare_exchange(pos.succs[i], node, SC, SC).is_ok() {
+                        break;
+                    }
+                    // 前驱在这期间变了，重新查找
+                    self.find(&(*node).key, &mut pos);
+                    if is_marked((*node).next[0].load(SC)) {
+                        break 'levels;
+                    }
+                }
+            }
+
+            // 挂的过程中被删除了: 删除者的清理可能早于我们挂上某一层，再清理一次
+            if is_marked((*node).next[0].load(SC)) {
+                self.find(&(*node).key, &mut pos);
+            }
+            Self::release(node, &guard);
+        }
+        true
+    }
+
+    // 返回是否由这次调用删除了key
+    pub fn remove<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let guard = epoch::pin();
+        let mut pos = Position {
+            preds: [ptr::null(); MAX_LEVEL],
+            succs: [ptr::null_mut(); MAX_LEVEL],
+        };
+        unsafe {
+            if !self.find(key, &mut pos) {
+                return false;
+            }
+            let node = pos.succs[0];
+            let tower = &(*node).next;
+            // 从高层往下打标记，保证查找时不会从一个高层未删除的节点下到已删除的层
+            for i in (1..tower.len()).rev() {
+                let mut next = tower[i].load(SC);
+                while !is_marked(next) {
+                    match tower[i].compare_exchange(next, marked(next), SC, SC) {
+                        Ok(_) => break,
+                        Err(actual) => next = actual,
+                    }
+                }
+            }
+            // 第0层的标记决定谁删除了它
+            let mut next = (*node).next[0].load(SC);
+            loop {
+                if is_marked(next) {
+                    return false;
+                }
+                match (*node).next[0].compare_exchange(next, marked(next), SC, SC) {
+                    Ok(_) => break,
+                    Err(actual) => next = actual,
+                }
+            }
+            self.len.fetch_sub(1, Ordering::Relaxed);
+            mutation!("remove");
+            // 物理删除
+            self.find(key, &mut pos);
+            Self::release(node, &guard);
+        }
+        true
+    }
+}
+
+impl<K, V> Default for SkipMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V> Drop for SkipMap<K, V> {
+    fn drop(&mut self) {
+        // &mut self保证没有并发访问；已删除的节点都已从第0层摘下并交给了epoch，
+        // 第0层上剩下的就是全部存活节点
+        let mut curr = unmarked(*self.head[0].get_mut());
+        while !curr.is_null() {
+            unsafe {
+                let node = Box::from_raw(curr);
+                curr = unmarked(node.next[0].load(Ordering::Relaxed));
+            }
+        }
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for SkipMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut map = f.debug_map();
+        self.for_each(|k, v| {
+            map.entry(k, v);
+        });
+        map.finish()
+    }
+}
+
+// 节点可能在任意线程上被epoch析构，元素也会被多个线程同时读取
+unsafe impl<K: Send + Sync, V: Send + Sync> Send for SkipMap<K, V> {}
+unsafe impl<K: Send + Sync, V: Send + Sync> Sync for SkipMap<K, V> {}
+
+pub struct SkipSet<T> {
+    map: SkipMap<T, ()>,
+}
+
+impl<T> SkipSet<T> {
+    pub fn new() -> Self {
+        SkipSet { map: SkipMap::new() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    pub fn for_each(&self, mut f: impl FnMut(&T)) {
+        self.map.for_each(|k, _| f(k));
+    }
+}
+
+impl<T: Ord> SkipSet<T> {
+    pub fn insert(&self, value: T) -> bool {
+        self.map.insert(value, ())
+    }
+
+    pub fn contains<Q>(&self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.map.contains_key(value)
+    }
+
+    pub fn remove<Q>(&self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.map.remove(value)
+    }
+}
+
+impl<T> Default for SkipSet<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for SkipSet<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut set = f.debug_set();
+        self.for_each(|v| {
+            set.entry(v);
+        });
+        set.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::Arc;
+    use std::thread;
+
+    fn keys<K: Clone, V>(map: &SkipMap<K, V>) -> Vec<K> {
+        let mut out = Vec::new();
+        map.for_each(|k, _| out.push(k.clone()));
+        out
+    }
+
+    #[test]
+    fn single_thread_matches_btreemap() {
+        let map = SkipMap::new();
+        let mut reference = BTreeMap::new();
+        let mut rng = XorShift::new(99);
+        for step in 0..5000u32 {
+            let key = rng.next_u64() % 200;
+            if rng.next_u64().is_multiple_of(2) {
+                let inserted = map.insert(key, step);
+                assert_eq!(inserted, !reference.contains_key(&key));
+                reference.entry(key).or_insert(step);
+            } else {
+                assert_eq!(map.remove(&key), reference.remove(&key).is_some());
+            }
+            assert_eq!(map.get(&key), reference.get(&key).copied());
+        }
+        assert_eq!(map.len(), reference.len());
+        assert_eq!(keys(&map), reference.keys().copied().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn drop_releases_live_values() {
+        let marker = Arc::new(());
+        let map = SkipMap::new();
+        for i in 0..100 {
+            map.insert(i, Arc::clone(&marker));
+        }
+        assert!(!map.insert(5, Arc::clone(&marker)));
+        drop(map);
+        assert_eq!(Arc::strong_count(&marker), 1);
+    }
+
+    #[test]
+    fn concurrent_disjoint_inserts() {
+        const THREADS: usize = 4;
+        const PER_THREAD: usize = 2000;
+        let set = Arc::new(SkipSet::new());
+        let handles: Vec<_> = (0..THREADS)
+            .map(|t| {
+                let set = Arc::clone(&set);
+                // 交错的key，让不同线程的插入位置互相穿插
+                thread::spawn(move || {
+                    for i in 0..PER_THREAD {
+                        assert!(set.insert(i * THREADS + t));
+                    }
+                })
+            })
+            .collect();
+        for h in handles {
+            h.join().unwrap();
+        }
+        let mut got = Vec::new();
+        set.for_each(|&x| got.push(x));
+        assert_eq!(got, (0..THREADS * PER_THREAD).collect::<Vec<_>>());
+        assert_eq!(set.len(), THREADS * PER_THREAD);
+    }
+
+    #[test]
+    fn each_key_inserted_and_removed_exactly_once() {
+        const THREADS: usize = 4;
+        const KEYS: usize = 1000;
+        let set = Arc::new(SkipSet::new());
+        let run = |op: fn(&SkipSet<usize>, usize) -> bool| {
+            let handles: Vec<_> = (0..THREADS)
+                .map(|_| {
+                    let set = Arc::clone(&set);
+                    thread::spawn(move || (0..KEYS).filter(|&k| op(&set, k)).count())
+                })
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).sum::<usize>()
+        };
+        // 所有线程争抢同一批key，每个key只能有一个线程成功
+        assert_eq!(run(|s, k| s.insert(k)), KEYS);
+        assert_eq!(set.len(), KEYS);
+        assert_eq!(run(|s, k| s.remove(&k)), KEYS);
+        assert!(set.is_empty());
+        let mut left = 0;
+        set.for_each(|_| left += 1);
+        assert_eq!(left, 0);
+    }
+
+    #[test]
+    fn mixed_stress_with_readers() {
+        const WRITERS: usize = 3;
+        const OPS: usize = 20_000;
+        const KEYS: u64 = 64;
+        let map = Arc::new(SkipMap::new());
+        let stop = Arc::new(AtomicBool::new(false));
+
+        // 读者不断检查遍历结果严格递增
+        let reader = {
+            let map = Arc::clone(&map);
+            let stop = Arc::clone(&stop);
+            thread::spawn(move || {
+                while !stop.load(Ordering::Relaxed) {
+                    let ks = keys(&map);
+                    assert!(ks.windows(2).all(|w| w[0] < w[1]));
+                    thread::yield_now();
+                }
+            })
+        };
+        // 每个写者只改自己的那组key(key % WRITERS == t)，最后和自己的本地记录比较
+        let writers: Vec<_> = (0..WRITERS as u64)
+            .map(|t| {
+                let map = Arc::clone(&map);
+                thread::spawn(move || {
+                    let mut rng = XorShift::new(t + 1);
+                    let mut mine = BTreeMap::new();
+                    for step in 0..OPS as u64 {
+                        let key = (rng.next_u64() % KEYS) * WRITERS as u64 + t;
+                        if rng.next_u64().is_multiple_of(2) {
+                            assert_eq!(map.insert(key, step), !mine.contains_key(&key));
+                            mine.entry(key).or_insert(step);
+                        } else {
+                            assert_eq!(map.remove(&key), mine.remove(&key).is_some());
+                        }
+                    }
+                    mine
+                })
+            })
+            .collect();
+
+        let mut expected = BTreeMap::new();
+        for w in writers {
+            expected.extend(w.join().unwrap());
+        }
+        stop.store(true, Ordering::Relaxed);
+        reader.join().unwrap();
+
+        let mut got = Vec::new();
+        map.for_each(|&k, &v| got.push((k, v)));
+        assert_eq!(got, expected.into_iter().collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,426 @@
+// 所有栈/队列/双端队列实现共用的一组行为测试
+// 测试函数只依赖traits里的Stack/Queue/Deque，每个实现用宏展开成一个子模块，
+// 例如conformance::treiber_stack::lifo_order；新模块实现了trait之后在下面的列表里加一行就有了全部测试
+// 每个实现都要通过:
+// - 空容器pop返回None，取空之后还能继续用
+// - 不同长度下的出队顺序(长度跨过unrolled/small_list/seg_queue等的块边界)
+// - 随机交错的操作序列和Vec/VecDeque模型一致
+// - 还留在容器里的元素在Drop时都被析构(延迟回收的实现在宽限期之后)，被pop出去的不会被析构第二次
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+#[cfg(feature = "concurrent")]
+use crate::epoch;
+use crate::rng::XorShift;
+use crate::traits::{Deque, Queue, Stack};
+
+const LENGTHS: [usize; 6] = [1, 2, 3, 17, 100, 1000];
+const RANDOM_OPS: usize = 2000;
+
+// 记录存活个数的元素，Clone也算一个新元素
+#[derive(Debug)]
+struct Tracked {
+    value: i32,
+    live: Arc<AtomicUsize>,
+}
+
+impl Tracked {
+    fn new(value: i32, live: &Arc<AtomicUsize>) -> Self {
+        live.fetch_add(1, Ordering::Relaxed);
+        Tracked {
+            value,
+            live: Arc::clone(live),
+        }
+    }
+}
+
+impl Clone for Tracked {
+    fn clone(&self) -> Self {
+        Tracked::new(self.value, &self.live)
+    }
+}
+
+impl Drop for Tracked {
+    fn drop(&mut self) {
+        self.live.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl PartialEq for Tracked {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+impl Eq for Tracked {}
+
+impl PartialOrd for Tracked {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Tracked {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.value.cmp(&other.value)
+    }
+}
+
+// 等存活个数归零；用epoch推迟回收的实现(rcu_list、shared_stack)要等宽限期过去，
+// 别的测试线程可能同时pin着全局epoch，所以反复推进一会儿
+fn assert_all_dropped(live: &AtomicUsize) {
+    for _ in 0..10_000 {
+        if live.load(Ordering::Relaxed) == 0 {
+            return;
+        }
+        #[cfg(feature = "concurrent")]
+        epoch::pin().flush();
+        thread::yield_now();
+    }
+    assert_eq!(live.load(Ordering::Relaxed), 0, "elements leaked");
+}
+
+fn stack_empty<S: Stack<i32>>(mut stack: S) {
+    assert_eq!(stack.pop(), None);
+    assert_eq!(stack.pop(), None);
+    stack.push(1);
+    assert_eq!(stack.pop(), Some(1));
+    assert_eq!(stack.pop(), None);
+    stack.push(2);
+    assert_eq!(stack.pop(), Some(2));
+}
+
+fn stack_lifo<S: Stack<i32>>(make: impl Fn() -> S) {
+    for n in LENGTHS {
+        let mut stack = make();
+        for i in 0..n as i32 {
+            stack.push(i);
+        }
+        for i in (0..n as i32).rev() {
+            assert_eq!(stack.pop(), Some(i), "length {}", n);
+        }
+        assert_eq!(stack.pop(), None);
+    }
+}
+
+fn stack_random<S: Stack<i32>>(mut stack: S) {
+    let mut rng = XorShift::new(707);
+    let mut model = Vec::new();
+    for i in 0..RANDOM_OPS as i32 {
+        // 推入稍多于弹出，长度会慢慢涨起来
+        if rng.next_u64() % 5 < 3 {
+            stack.push(i);
+            model.push(i);
+        } else {
+            assert_eq!(stack.pop(), model.pop(), "step {}", i);
+        }
+    }
+    while let Some(x) = model.pop() {
+        assert_eq!(stack.pop(), Some(x));
+    }
+    assert_eq!(stack.pop(), None);
+}
+
+fn stack_drops<S: Stack<Tracked>>(mut stack: S) {
+    let live = Arc::new(AtomicUsize::new(0));
+    for i in 0..50 {
+        stack.push(Tracked::new(i, &live));
+    }
+    for i in (40..50).rev() {
+        assert_eq!(stack.pop().map(|t| t.value), Some(i));
+    }
+    drop(stack);
+    assert_all_dropped(&live);
+}
+
+fn queue_empty<Q: Queue<i32>>(mut queue: Q) {
+    assert_eq!(queue.dequeue(), None);
+    assert_eq!(queue.dequeue(), None);
+    queue.enqueue(1);
+    assert_eq!(queue.dequeue(), Some(1));
+    assert_eq!(queue.dequeue(), None);
+    queue.enqueue(2);
+    assert_eq!(queue.dequeue(), Some(2));
+}
+
+fn queue_fifo<Q: Queue<i32>>(make: impl Fn() -> Q) {
+    for n in LENGTHS {
+        let mut queue = make();
+        for i in 0..n as i32 {
+            queue.enqueue(i);
+        }
+        for i in 0..n as i32 {
+            assert_eq!(queue.dequeue(), Some(i), "length {}", n);
+        }
+        assert_eq!(queue.dequeue(), None);
+    }
+}
+
+fn queue_random<Q: Queue<i32>>(mut queue: Q) {
+    let mut rng = XorShift::new(707);
+    let mut model = VecDeque::new();
+    for i in 0..RANDOM_OPS as i32 {
+        if rng.next_u64() % 5 < 3 {
+            queue.enqueue(i);
+            model.push_back(i);
+        } else {
+            assert_eq!(queue.dequeue(), model.pop_front(), "step {}", i);
+        }
+    }
+    while let Some(x) = model.pop_front() {
+        assert_eq!(queue.dequeue(), Some(x));
+    }
+    assert_eq!(queue.dequeue(), None);
+}
+
+fn queue_drops<Q: Queue<Tracked>>(mut queue: Q) {
+    let live = Arc::new(AtomicUsize::new(0));
+    for i in 0..50 {
+        queue.enqueue(Tracked::new(i, &live));
+    }
+    for i in 0..10 {
+        assert_eq!(queue.dequeue().map(|t| t.value), Some(i));
+    }
+    drop(queue);
+    assert_all_dropped(&live);
+}
+
+fn deque_both_ends<D: Deque<i32>>(make: impl Fn() -> D) {
+    for n in LENGTHS {
+        // 从两端交替推入，再分别从两端取出
+        let mut deque = make();
+        let mut model = VecDeque::new();
+        for i in 0..n as i32 {
+            if i % 2 == 0 {
+                deque.push_front(i);
+                model.push_front(i);
+            } else {
+                deque.push_back(i);
+                model.push_back(i);
+            }
+        }
+        for i in 0..n {
+            if i % 3 == 0 {
+                assert_eq!(deque.pop_back(), model.pop_back(), "length {}", n);
+            } else {
+                assert_eq!(deque.pop_front(), model.pop_front(), "length {}", n);
+            }
+        }
+        assert_eq!(deque.pop_front(), None);
+        assert_eq!(deque.pop_back(), None);
+    }
+}
+
+fn deque_random<D: Deque<i32>>(mut deque: D) {
+    let mut rng = XorShift::new(707);
+    let mut model = VecDeque::new();
+    for i in 0..RANDOM_OPS as i32 {
+        match rng.next_u64() % 7 {
+            0 | 1 => {
+                deque.push_front(i);
+                model.push_front(i);
+            }
+            2 | 3 => {
+                deque.push_back(i);
+                model.push_back(i);
+            }
+            4 | 5 => assert_eq!(deque.pop_front(), model.pop_front(), "step {}", i),
+            _ => assert_eq!(deque.pop_back(), model.pop_back(), "step {}", i),
+        }
+    }
+    while let Some(x) = model.pop_back() {
+        assert_eq!(deque.pop_back(), Some(x));
+    }
+    assert_eq!(deque.pop_front(), None);
+}
+
+fn deque_drops<D: Deque<Tracked>>(mut deque: D) {
+    let live = Arc::new(AtomicUsize::new(0));
+    for i in 0..50 {
+        deque.push_back(Tracked::new(i, &live));
+    }
+    assert_eq!(deque.pop_front().map(|t| t.value), Some(0));
+    assert_eq!(deque.pop_back().map(|t| t.value), Some(49));
+    drop(deque);
+    assert_all_dropped(&live);
+}
+
+// 每一项: 子模块名 => 创建一个空容器的表达式(元素类型由测试函数推断，所以用crate::开头的完整路径)
+macro_rules! stack_suite {
+    (@behaviour $make:expr) => {
+        #[test]
+        fn empty() {
+            stack_empty($make);
+        }
+
+        #[test]
+        fn lifo_order() {
+            stack_lifo(|| $make);
+        }
+
+        #[test]
+        fn matches_vec() {
+            stack_random($make);
+        }
+    };
+    ($($(#[$attr:meta])* $name:ident => $make:expr;)*) => {$(
+        $(#[$attr])*
+        mod $name {
+            use super::*;
+
+            stack_suite!(@behaviour $make);
+
+            #[test]
+            fn drops_remaining() {
+                stack_drops($make);
+            }
+        }
+    )*};
+}
+
+macro_rules! queue_suite {
+    ($($(#[$attr:meta])* $name:ident => $make:expr;)*) => {$(
+        $(#[$attr])*
+        mod $name {
+            use super::*;
+
+            #[test]
+            fn empty() {
+                queue_empty($make);
+            }
+
+            #[test]
+            fn fifo_order() {
+                queue_fifo(|| $make);
+            }
+
+            #[test]
+            fn matches_vec_deque() {
+                queue_random($make);
+            }
+
+            #[test]
+            fn drops_remaining() {
+                queue_drops($make);
+            }
+        }
+    )*};
+}
+
+// 双端队列另外跑一遍栈和队列的测试
+macro_rules! deque_suite {
+    ($($(#[$attr:meta])* $name:ident => $make:expr;)*) => {$(
+        $(#[$attr])*
+        mod $name {
+            use super::*;
+
+            #[test]
+            fn both_ends() {
+                deque_both_ends(|| $make);
+            }
+
+            #[test]
+            fn matches_vec_deque() {
+                deque_random($make);
+            }
+
+            #[test]
+            fn drops_remaining() {
+                deque_drops($make);
+            }
+
+            #[test]
+            fn as_stack() {
+                stack_lifo(|| $make);
+            }
+
+            #[test]
+            fn as_queue() {
+                queue_fifo(|| $make);
+            }
+        }
+    )*};
+}
+
+stack_suite! {
+    #[cfg(feature = "safe")]
+    simple_stack_1 => crate::simple_stack_1::List::new();
+    #[cfg(feature = "safe")]
+    simple_stack_2 => crate::simple_stack_2::List::new();
+    #[cfg(feature = "safe")]
+    min_stack => crate::min_stack::MinStack::new();
+    #[cfg(feature = "unsafe-impls")]
+    ring => crate::ring::Ring::new();
+    #[cfg(feature = "unsafe-impls")]
+    random_list => crate::random_list::RandomList::new();
+    #[cfg(feature = "persistent")]
+    cow_list => crate::cow_list::CowList::new();
+    #[cfg(feature = "concurrent")]
+    rcu_list => crate::rcu_list::RcuList::new();
+    #[cfg(feature = "concurrent")]
+    shared_stack => crate::shared_stack::SharedStack::new();
+    #[cfg(feature = "concurrent")]
+    treiber_stack => crate::treiber_stack::TreiberStack::new();
+    #[cfg(feature = "concurrent")]
+    treiber_stack_hazard => crate::treiber_stack::TreiberStack::with_reclaim(crate::reclaim::Hazard);
+    #[cfg(feature = "concurrent")]
+    elimination_stack => crate::elimination_stack::EliminationStack::new();
+    #[cfg(feature = "concurrent")]
+    chase_lev_worker => crate::chase_lev_deque::Worker::new();
+    #[cfg(feature = "unsafe-impls")]
+    tombstone_list_stack => crate::tombstone_list::TombstoneList::new();
+}
+
+queue_suite! {
+    #[cfg(feature = "unsafe-impls")]
+    simple_deque_2 => crate::simple_deque_2::List::new();
+    #[cfg(feature = "safe")]
+    min_queue => crate::min_stack::MinQueue::new();
+    #[cfg(feature = "concurrent")]
+    ms_queue => crate::ms_queue::MsQueue::new();
+    #[cfg(feature = "concurrent")]
+    ms_queue_hazard => crate::ms_queue::MsQueue::with_reclaim(crate::reclaim::Hazard);
+    #[cfg(feature = "concurrent")]
+    seg_queue => crate::seg_queue::SegQueue::new();
+    #[cfg(feature = "concurrent")]
+    flat_combining => crate::flat_combining::FcQueue::new();
+    #[cfg(feature = "unsafe-impls")]
+    tombstone_list_queue => crate::tombstone_list::TombstoneList::new();
+}
+
+deque_suite! {
+    #[cfg(feature = "safe")]
+    simple_deque_1 => crate::simple_deque_1::List::new();
+    #[cfg(feature = "unsafe-impls")]
+    simple_deque_3 => crate::simple_deque_3::List::new();
+    #[cfg(feature = "unsafe-impls")]
+    simple_deque_3_cache_aligned => crate::simple_deque_3::List::with_layout(crate::simple_deque_3::CacheAligned);
+    #[cfg(feature = "unsafe-impls")]
+    pinned_list => crate::simple_deque_3::pinned::PinnedList::new();
+    #[cfg(feature = "unsafe-impls")]
+    sentinel_list => crate::sentinel_list::List::new();
+    #[cfg(feature = "unsafe-impls")]
+    xor_list => crate::xor_list::XorList::new();
+    #[cfg(feature = "unsafe-impls")]
+    arena_list => crate::arena_list::ArenaList::new();
+    #[cfg(feature = "unsafe-impls")]
+    slab_list => crate::slab_list::SlabList::new();
+    #[cfg(feature = "unsafe-impls")]
+    small_list => crate::small_list::SmallList::<_, 4>::new();
+    #[cfg(feature = "unsafe-impls")]
+    unrolled_list => crate::unrolled_list::UnrolledList::new();
+    #[cfg(feature = "unsafe-impls")]
+    unrolled_list_k2 => crate::unrolled_list::UnrolledList::<_, 2>::default();
+    #[cfg(feature = "unsafe-impls")]
+    indexable_skip_list => crate::indexable_skip_list::IndexableSkipList::new();
+    #[cfg(feature = "concurrent")]
+    sync_deque => crate::sync_deque::SyncDeque::new();
+}
+
+#[cfg(feature = "trace")]
+deque_suite! {
+    traced_list => crate::simple_deque_3::trace::TracedList::new();
+}
@@ -0,0 +1,335 @@
+// 写时复制(copy-on-write)单链表
+// 和simple_stack_3一样节点用Rc共享，clone整个链表是O(1)的，只是多一个指向头节点的引用
+// 区别是这里保留了可变API: 修改时沿途对每个节点调用Rc::make_mut
+// - 节点只被自己这条链表引用: 直接原地修改
+// - 节点还被别的链表共享: 复制这一个节点(元素clone，next只加一次引用计数)，再修改副本
+// 一次修改最多复制从表头到修改位置的这段前缀，后面的节点继续和别的链表共享；
+// 复制出的节点让它的后继多了一个引用，所以后继被访问到时也会被复制，没访问到的保持共享
+
+use std::fmt;
+use std::rc::Rc;
+
+use crate::error::{check_insert, ListError};
+
+pub struct CowList<T> {
+    head: Link<T>,
+    len: usize,
+}
+
+type Link<T> = Option<Rc<Node<T>>>;
+
+#[derive(Clone)]
+struct Node<T> {
+    elem: T,
+    next: Link<T>,
+}
+
+impl<T> CowList<T> {
+    pub fn new() -> Self {
+        CowList { head: None, len: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // 不需要复制任何节点
+    pub fn push_front(&mut self, elem: T) {
+        let next = self.head.take();
+        self.head = Some(Rc::new(Node { elem, next }));
+        self.len += 1;
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        self.head.as_ref().map(|node| &node.elem)
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.iter().nth(index)
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            next: self.head.as_deref(),
+        }
+    }
+
+    // 两条链表是否从表头开始就共享同一批节点
+    pub fn ptr_eq(&self, other: &CowList<T>) -> bool {
+        match (&self.head, &other.head) {
+            (Some(a), Some(b)) => Rc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+}
+
+impl<T: Clone> CowList<T> {
+    pub fn front_mut(&mut self) -> Option<&mut T> {
+        self.head.as_mut().map(|node| &mut Rc::make_mut(node).elem)
+    }
+
+    // 复制到index为止的共享节点
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        self.iter_mut().nth(index)
+    }
+
+    // 惰性复制: 只有迭代到的共享节点才会被复制
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut {
+            next: Some(&mut self.head),
+        }
+    }
+
+    // 返回指向第index个位置的Link，沿途把共享节点复制成独占的
+    fn link_mut(&mut self, index: usize) -> &mut Link<T> {
+        let mut link = &mut self.head;
+        for _ in 0..index {
+            link = &mut Rc::make_mut(link.as_mut().unwrap()).next;
+        }
+        link
+    }
+
+    // index > len时panic
+    pub fn insert(&mut self, index: usize, elem: T) {
+        assert!(index <= self.len, "insertion index out of bounds");
+        let link = self.link_mut(index);
+        let next = link.take();
+        *link = Some(Rc::new(Node { elem, next }));
+        self.len += 1;
+    }
+
+    // index > len时返回OutOfRange而不是panic
+    pub fn try_insert(&mut self, index: usize, elem: T) -> Result<(), ListError> {
+        check_insert(index, self.len)?;
+        self.insert(index, elem);
+        Ok(())
+    }
+
+    // 被删节点独占时直接取出元素，还被共享时返回元素的拷贝
+    pub fn remove(&mut self, index: usize) -> Option<T> {
+        if index >= self.len {
+            return None;
+        }
+        let link = self.link_mut(index);
+        let node = link.take().unwrap();
+        let elem = match Rc::try_unwrap(node) {
+            Ok(node) => {
+                *link = node.next;
+                node.elem
+            }
+            Err(node) => {
+                *link = node.next.clone();
+                node.elem.clone()
+            }
+        };
+        self.len -= 1;
+        Some(elem)
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        self.remove(0)
+    }
+}
+
+impl<T> Clone for CowList<T> {
+    // O(1)，两条链表共享全部节点，之后谁修改谁复制
+    fn clone(&self) -> Self {
+        CowList {
+            head: self.head.clone(),
+            len: self.len,
+        }
+    }
+}
+
+impl<T> Default for CowList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Drop for CowList<T> {
+    // 和simple_stack_3一样逐个拆开独占的节点，遇到还被共享的节点就停下
+    fn drop(&mut self) {
+        let mut cur_link = self.head.take();
+        while let Some(node) = cur_link {
+            match Rc::try_unwrap(node) {
+                Ok(mut node) => cur_link = node.next.take(),
+                Err(_) => break,
+            }
+        }
+    }
+}
+
+impl<T> FromIterator<T> for CowList<T> {
+    // 保持迭代顺序
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let elems: Vec<T> = iter.into_iter().collect();
+        let mut list = CowList::new();
+        for elem in elems.into_iter().rev() {
+            list.push_front(elem);
+        }
+        list
+    }
+}
+
+impl<T: PartialEq> PartialEq for CowList<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && (self.ptr_eq(other) || self.iter().eq(other.iter()))
+    }
+}
+
+impl<T: Eq> Eq for CowList<T> {}
+
+impl<T: fmt::Debug> fmt::Debug for CowList<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a CowList<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+pub struct Iter<'a, T> {
+    next: Option<&'a Node<T>>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next.map(|node| {
+            self.next = node.next.as_deref();
+            &node.elem
+        })
+    }
+}
+
+// 存的是下一个节点所在的Link而不是节点本身，这样只有真正迭代到时才会复制它
+pub struct IterMut<'a, T> {
+    next: Option<&'a mut Link<T>>,
+}
+
+impl<'a, T: Clone> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = Rc::make_mut(self.next.take()?.as_mut()?);
+        self.next = Some(&mut node.next);
+        Some(&mut node.elem)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 第index个节点的地址，用来判断两条链表是否共享节点
+    fn node_addr<T>(list: &CowList<T>, index: usize) -> *const Node<T> {
+        let mut cur = list.head.as_ref().unwrap();
+        for _ in 0..index {
+            cur = cur.next.as_ref().unwrap();
+        }
+        Rc::as_ptr(cur)
+    }
+
+    #[test]
+    fn basics() {
+        let mut list = CowList::new();
+        assert_eq!(list.pop_front(), None);
+        list.push_front(2);
+        list.push_front(1);
+        list.insert(2, 4);
+        list.insert(2, 3);
+        assert_eq!(list.len(), 4);
+        assert_eq!(format!("{list:?}"), "[1, 2, 3, 4]");
+        *list.front_mut().unwrap() = 10;
+        *list.get_mut(3).unwrap() = 40;
+        assert_eq!(list.get(3), Some(&40));
+        assert_eq!(list.remove(1), Some(2));
+        assert_eq!(list.remove(3), None);
+        assert_eq!(list.pop_front(), Some(10));
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![3, 40]);
+    }
+
+    #[test]
+    fn clone_shares_until_written() {
+        let a: CowList<i32> = (0..5).collect();
+        let mut b = a.clone();
+        assert!(a.ptr_eq(&b));
+        *b.get_mut(2).unwrap() = 20;
+        // 前三个节点被复制，后两个仍然共享
+        for i in 0..3 {
+            assert_ne!(node_addr(&a, i), node_addr(&b, i));
+        }
+        for i in 3..5 {
+            assert_eq!(node_addr(&a, i), node_addr(&b, i));
+        }
+        assert_eq!(a.iter().copied().collect::<Vec<_>>(), vec![0, 1, 2, 3, 4]);
+        assert_eq!(b.iter().copied().collect::<Vec<_>>(), vec![0, 1, 20, 3, 4]);
+        assert_ne!(a, b);
+        assert_eq!(a, a.clone());
+    }
+
+    #[test]
+    fn unique_nodes_are_mutated_in_place() {
+        let mut list: CowList<i32> = (0..4).collect();
+        let before: Vec<_> = (0..4).map(|i| node_addr(&list, i)).collect();
+        for x in list.iter_mut() {
+            *x *= 2;
+        }
+        list.insert(4, 8);
+        let after: Vec<_> = (0..4).map(|i| node_addr(&list, i)).collect();
+        assert_eq!(before, after);
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![0, 2, 4, 6, 8]);
+    }
+
+    #[test]
+    fn iter_mut_copies_only_visited_nodes() {
+        let a: CowList<String> = ["x", "y", "z"].into_iter().map(String::from).collect();
+        let mut b = a.clone();
+        b.iter_mut().next().unwrap().push('!');
+        assert_ne!(node_addr(&a, 0), node_addr(&b, 0));
+        assert_eq!(node_addr(&a, 1), node_addr(&b, 1));
+        // 删除共享节点得到的是拷贝，原链表不受影响
+        assert_eq!(b.remove(1), Some("y".to_string()));
+        assert_eq!(a.get(1).map(String::as_str), Some("y"));
+        assert_eq!(b.iter().map(String::as_str).collect::<Vec<_>>(), vec!["x!", "z"]);
+        assert_eq!(node_addr(&a, 2), node_addr(&b, 1));
+    }
+
+    #[test]
+    #[should_panic(expected = "insertion index out of bounds")]
+    fn insert_past_end_panics() {
+        let mut list: CowList<i32> = (0..2).collect();
+        list.insert(3, 0);
+    }
+
+    #[test]
+    fn long_shared_lists_drop() {
+        let a: CowList<u32> = (0..200_000).collect();
+        let mut b = a.clone();
+        b.pop_front();
+        drop(a);
+        assert_eq!(b.len(), 199_999);
+    }
+
+    #[test]
+    fn try_insert_out_of_range() {
+        let mut list = CowList::new();
+        list.try_insert(0, 1).unwrap();
+        list.try_insert(1, 2).unwrap();
+        assert_eq!(list.try_insert(3, 3), Err(ListError::OutOfRange { index: 3, len: 2 }));
+        assert_eq!(format!("{list:?}"), "[1, 2]");
+    }
+}
@@ -0,0 +1,152 @@
+// 环检测算法，用在裸指针链表上检查next链是否被破坏成了环
+// 链表被看作一个"函数图": 从start出发不断调用next，要么走到None(无环)，要么最终进入一个环
+// 结果用Cycle{start, len}描述: 从起点走start步到达环的入口，环上一共len个位置
+// - floyd: 龟兔赛跑，兔子每次走两步；相遇之后一个回到起点、两者同速前进，再次相遇处就是入口
+// - brent: 兔子一步一步走，乌龟每隔2的幂次步瞬移到兔子的位置；直接得到环长，next调用次数通常比floyd少
+// 两者都只需要O(1)额外空间；P只要求Copy + Eq，可以是裸指针，也可以是下标
+// 算法本身是safe的，解引用裸指针的unsafe留在调用方提供的next里
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cycle {
+    // 从起点到环入口的步数，也就是入口节点的下标
+    pub start: usize,
+    pub len: usize,
+}
+
+pub fn floyd<P, F>(start: Option<P>, mut next: F) -> Option<Cycle>
+where
+    P: Copy + Eq,
+    F: FnMut(P) -> Option<P>,
+{
+    let x0 = start?;
+    let mut tortoise = next(x0)?;
+    let mut hare = next(tortoise)?;
+    while tortoise != hare {
+        tortoise = next(tortoise)?;
+        hare = next(hare)?;
+        hare = next(hare)?;
+    }
+    // 相遇点到入口的距离等于起点到入口的距离(模环长)
+    let mut start = 0;
+    tortoise = x0;
+    while tortoise != hare {
+        tortoise = next(tortoise)?;
+        hare = next(hare)?;
+        start += 1;
+    }
+    let mut len = 1;
+    hare = next(tortoise)?;
+    while tortoise != hare {
+        hare = next(hare)?;
+        len += 1;
+    }
+    Some(Cycle { start, len })
+}
+
+pub fn brent<P, F>(start: Option<P>, mut next: F) -> Option<Cycle>
+where
+    P: Copy + Eq,
+    F: FnMut(P) -> Option<P>,
+{
+    let x0 = start?;
+    let mut power = 1;
+    let mut len = 1;
+    let mut tortoise = x0;
+    let mut hare = next(x0)?;
+    while tortoise != hare {
+        if power == len {
+            tortoise = hare;
+            power *= 2;
+            len = 0;
+        }
+        hare = next(hare)?;
+        len += 1;
+    }
+    // 让兔子先走len步，两者再同速前进，相遇处就是入口
+    tortoise = x0;
+    hare = x0;
+    for _ in 0..len {
+        hare = next(hare)?;
+    }
+    let mut start = 0;
+    while tortoise != hare {
+        tortoise = next(tortoise)?;
+        hare = next(hare)?;
+        start += 1;
+    }
+    Some(Cycle { start, len })
+}
+
+pub fn has_cycle<P, F>(start: Option<P>, next: F) -> bool
+where
+    P: Copy + Eq,
+    F: FnMut(P) -> Option<P>,
+{
+    brent(start, next).is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::XorShift;
+
+    // 用HashMap记录第一次到达每个位置的步数，作为对照
+    fn brute_force(start: usize, next: &[Option<usize>]) -> Option<Cycle> {
+        let mut seen = std::collections::HashMap::new();
+        let mut cur = Some(start);
+        let mut step = 0;
+        while let Some(x) = cur {
+            if let Some(&first) = seen.get(&x) {
+                return Some(Cycle {
+                    start: first,
+                    len: step - first,
+                });
+            }
+            seen.insert(x, step);
+            cur = next[x];
+            step += 1;
+        }
+        None
+    }
+
+    #[test]
+    fn rho_shape() {
+        // 0 -> 1 -> 2 -> 3 -> 4 -> 2
+        let next = [Some(1), Some(2), Some(3), Some(4), Some(2)];
+        let expected = Some(Cycle { start: 2, len: 3 });
+        assert_eq!(floyd(Some(0), |x: usize| next[x]), expected);
+        assert_eq!(brent(Some(0), |x: usize| next[x]), expected);
+        // 从环上出发时入口就是起点
+        assert_eq!(floyd(Some(3), |x: usize| next[x]), Some(Cycle { start: 0, len: 3 }));
+    }
+
+    #[test]
+    fn no_cycle_and_self_loop() {
+        let chain = [Some(1), Some(2), None];
+        assert_eq!(floyd(Some(0), |x: usize| chain[x]), None);
+        assert_eq!(brent(Some(0), |x: usize| chain[x]), None);
+        assert!(!has_cycle(None, |x: usize| chain[x]));
+        let self_loop = [Some(1), Some(1)];
+        assert_eq!(brent(Some(0), |x: usize| self_loop[x]), Some(Cycle { start: 1, len: 1 }));
+        assert_eq!(floyd(Some(0), |x: usize| self_loop[x]), Some(Cycle { start: 1, len: 1 }));
+    }
+
+    #[test]
+    fn random_functional_graphs() {
+        let mut rng = XorShift::new(691);
+        for _ in 0..200 {
+            let n = 1 + rng.next_u64() as usize % 40;
+            // 每个位置随机指向一个位置，或者以一定概率指向None
+            let next: Vec<Option<usize>> = (0..n)
+                .map(|_| {
+                    let r = rng.next_u64() as usize % (n + n / 4 + 1);
+                    (r < n).then_some(r)
+                })
+                .collect();
+            let start = rng.next_u64() as usize % n;
+            let expected = brute_force(start, &next);
+            assert_eq!(floyd(Some(start), |x: usize| next[x]), expected);
+            assert_eq!(brent(Some(start), |x: usize| next[x]), expected);
+        }
+    }
+}
@@ -0,0 +1,319 @@
+// 和VecDeque对照的差分属性测试
+// proptest随机生成操作序列，同时作用在被测链表和VecDeque模型上，每一步的返回值都必须一致；
+// 出错时proptest会把序列收缩成能复现问题的最短序列，失败的种子记在proptest-regressions/里
+// conformance里的测试是固定的几组序列，这里覆盖的是人想不到的组合，是unsafe模块主要的安全网
+// - 所有实现了Stack/Queue/Deque的类型都跑一遍(栈只生成front一端的操作，队列只生成back进front出)
+// - simple_deque_3另外生成游标插入删除、切分拼接、旋转等操作，每一步之后检查长度、正反向遍历和内部不变量
+
+use std::collections::VecDeque;
+
+use proptest::prelude::*;
+
+#[cfg(feature = "unsafe-impls")]
+use crate::simple_deque_3::List;
+use crate::traits::{Deque, Queue, Stack};
+
+const MAX_OPS: usize = 200;
+
+#[derive(Debug, Clone)]
+enum Op {
+    PushFront(i32),
+    PushBack(i32),
+    PopFront,
+    PopBack,
+}
+
+fn deque_ops() -> impl Strategy<Value = Vec<Op>> {
+    let op = prop_oneof![
+        any::<i32>().prop_map(Op::PushFront),
+        any::<i32>().prop_map(Op::PushBack),
+        Just(Op::PopFront),
+        Just(Op::PopBack),
+    ];
+    prop::collection::vec(op, 0..MAX_OPS)
+}
+
+// 栈: push/pop都在front一端
+fn stack_ops() -> impl Strategy<Value = Vec<Op>> {
+    let op = prop_oneof![any::<i32>().prop_map(Op::PushFront), Just(Op::PopFront)];
+    prop::collection::vec(op, 0..MAX_OPS)
+}
+
+// 队列: back进front出
+fn queue_ops() -> impl Strategy<Value = Vec<Op>> {
+    let op = prop_oneof![any::<i32>().prop_map(Op::PushBack), Just(Op::PopFront)];
+    prop::collection::vec(op, 0..MAX_OPS)
+}
+
+// 把一个操作同时作用在模型上，返回模型给出的pop结果
+fn apply_model(model: &mut VecDeque<i32>, op: &Op) -> Option<i32> {
+    match *op {
+        Op::PushFront(x) => {
+            model.push_front(x);
+            None
+        }
+        Op::PushBack(x) => {
+            model.push_back(x);
+            None
+        }
+        Op::PopFront => model.pop_front(),
+        Op::PopBack => model.pop_back(),
+    }
+}
+
+fn check_deque<D: Deque<i32>>(mut list: D, ops: &[Op]) -> Result<(), TestCaseError> {
+    let mut model = VecDeque::new();
+    for (step, op) in ops.iter().enumerate() {
+        let expected = apply_model(&mut model, op);
+        let actual = match *op {
+            Op::PushFront(x) => {
+                list.push_front(x);
+                None
+            }
+            Op::PushBack(x) => {
+                list.push_back(x);
+                None
+            }
+            Op::PopFront => list.pop_front(),
+            Op::PopBack => list.pop_back(),
+        };
+        prop_assert_eq!(actual, expected, "step {}: {:?}", step, op);
+    }
+    // 剩下的元素从back一端取空
+    while let Some(x) = model.pop_back() {
+        prop_assert_eq!(list.pop_back(), Some(x));
+    }
+    prop_assert_eq!(list.pop_front(), None);
+    Ok(())
+}
+
+fn check_stack<S: Stack<i32>>(mut stack: S, ops: &[Op]) -> Result<(), TestCaseError> {
+    let mut model = VecDeque::new();
+    for (step, op) in ops.iter().enumerate() {
+        let expected = apply_model(&mut model, op);
+        let actual = match *op {
+            Op::PushFront(x) => {
+                stack.push(x);
+                None
+            }
+            _ => stack.pop(),
+        };
+        prop_assert_eq!(actual, expected, "step {}: {:?}", step, op);
+    }
+    while let Some(x) = model.pop_front() {
+        prop_assert_eq!(stack.pop(), Some(x));
+    }
+    prop_assert_eq!(stack.pop(), None);
+    Ok(())
+}
+
+fn check_queue<Q: Queue<i32>>(mut queue: Q, ops: &[Op]) -> Result<(), TestCaseError> {
+    let mut model = VecDeque::new();
+    for (step, op) in ops.iter().enumerate() {
+        let expected = apply_model(&mut model, op);
+        let actual = match *op {
+            Op::PushBack(x) => {
+                queue.enqueue(x);
+                None
+            }
+            _ => queue.dequeue(),
+        };
+        prop_assert_eq!(actual, expected, "step {}: {:?}", step, op);
+    }
+    while let Some(x) = model.pop_front() {
+        prop_assert_eq!(queue.dequeue(), Some(x));
+    }
+    prop_assert_eq!(queue.dequeue(), None);
+    Ok(())
+}
+
+// 每一项: 测试名 => 创建空容器的表达式
+macro_rules! differential {
+    ($check:ident, $ops:ident: $($(#[$attr:meta])* $name:ident => $make:expr;)*) => {
+        proptest! {
+            $(
+                $(#[$attr])*
+                #[test]
+                fn $name(ops in $ops()) {
+                    $check($make, &ops)?;
+                }
+            )*
+        }
+    };
+}
+
+differential! { check_deque, deque_ops:
+    #[cfg(feature = "safe")]
+    simple_deque_1 => crate::simple_deque_1::List::new();
+    #[cfg(feature = "unsafe-impls")]
+    simple_deque_3 => List::new();
+    #[cfg(feature = "unsafe-impls")]
+    pinned_list => crate::simple_deque_3::pinned::PinnedList::new();
+    #[cfg(feature = "unsafe-impls")]
+    sentinel_list => crate::sentinel_list::List::new();
+    #[cfg(feature = "unsafe-impls")]
+    xor_list => crate::xor_list::XorList::new();
+    #[cfg(feature = "unsafe-impls")]
+    arena_list => crate::arena_list::ArenaList::new();
+    #[cfg(feature = "unsafe-impls")]
+    slab_list => crate::slab_list::SlabList::new();
+    #[cfg(feature = "unsafe-impls")]
+    small_list => crate::small_list::SmallList::<_, 4>::new();
+    #[cfg(feature = "unsafe-impls")]
+    unrolled_list => crate::unrolled_list::UnrolledList::<_, 4>::default();
+    #[cfg(feature = "unsafe-impls")]
+    indexable_skip_list => crate::indexable_skip_list::IndexableSkipList::new();
+    #[cfg(feature = "concurrent")]
+    sync_deque => crate::sync_deque::SyncDeque::new();
+}
+
+differential! { check_stack, stack_ops:
+    #[cfg(feature = "safe")]
+    simple_stack_1 => crate::simple_stack_1::List::new();
+    #[cfg(feature = "safe")]
+    simple_stack_2 => crate::simple_stack_2::List::new();
+    #[cfg(feature = "safe")]
+    min_stack => crate::min_stack::MinStack::new();
+    #[cfg(feature = "unsafe-impls")]
+    ring => crate::ring::Ring::new();
+    #[cfg(feature = "unsafe-impls")]
+    random_list => crate::random_list::RandomList::new();
+    #[cfg(feature = "persistent")]
+    cow_list => crate::cow_list::CowList::new();
+    #[cfg(feature = "concurrent")]
+    rcu_list => crate::rcu_list::RcuList::new();
+    #[cfg(feature = "concurrent")]
+    shared_stack => crate::shared_stack::SharedStack::new();
+    #[cfg(feature = "concurrent")]
+    treiber_stack => crate::treiber_stack::TreiberStack::new();
+    #[cfg(feature = "concurrent")]
+    elimination_stack => crate::elimination_stack::EliminationStack::new();
+    #[cfg(feature = "concurrent")]
+    chase_lev_worker => crate::chase_lev_deque::Worker::new();
+    #[cfg(feature = "unsafe-impls")]
+    tombstone_list_stack => crate::tombstone_list::TombstoneList::new();
+}
+
+differential! { check_queue, queue_ops:
+    #[cfg(feature = "unsafe-impls")]
+    simple_deque_2 => crate::simple_deque_2::List::new();
+    #[cfg(feature = "safe")]
+    min_queue => crate::min_stack::MinQueue::new();
+    #[cfg(feature = "concurrent")]
+    ms_queue => crate::ms_queue::MsQueue::new();
+    #[cfg(feature = "concurrent")]
+    seg_queue => crate::seg_queue::SegQueue::new();
+    #[cfg(feature = "concurrent")]
+    flat_combining => crate::flat_combining::FcQueue::new();
+    #[cfg(feature = "unsafe-impls")]
+    tombstone_list_queue => crate::tombstone_list::TombstoneList::new();
+}
+
+// simple_deque_3专用的操作: 下标和旋转量都对当前长度取模，保证每个操作都合法
+#[cfg(feature = "unsafe-impls")]
+#[derive(Debug, Clone)]
+enum ListOp {
+    Basic(Op),
+    // 游标停在下标处插入(下标等于长度时是幽灵位置，相当于push_back)
+    InsertAt(usize, i32),
+    RemoveAt(usize),
+    // 在下标处split_before，再把切下来的前半段append到尾部，效果等于rotate_left
+    SplitAppend(usize),
+    RotateLeft(usize),
+    RotateRight(usize),
+    Clear,
+}
+
+#[cfg(feature = "unsafe-impls")]
+fn list_ops() -> impl Strategy<Value = Vec<ListOp>> {
+    let basic = prop_oneof![
+        any::<i32>().prop_map(Op::PushFront),
+        any::<i32>().prop_map(Op::PushBack),
+        Just(Op::PopFront),
+        Just(Op::PopBack),
+    ];
+    let op = prop_oneof![
+        6 => basic.prop_map(ListOp::Basic),
+        2 => (any::<usize>(), any::<i32>()).prop_map(|(i, x)| ListOp::InsertAt(i, x)),
+        2 => any::<usize>().prop_map(ListOp::RemoveAt),
+        1 => any::<usize>().prop_map(ListOp::SplitAppend),
+        1 => any::<usize>().prop_map(ListOp::RotateLeft),
+        1 => any::<usize>().prop_map(ListOp::RotateRight),
+        1 => Just(ListOp::Clear),
+    ];
+    prop::collection::vec(op, 0..MAX_OPS)
+}
+
+#[cfg(feature = "unsafe-impls")]
+fn cursor_at(list: &mut List<i32>, index: usize) -> crate::simple_deque_3::CursorMut<'_, i32> {
+    let mut cursor = list.cursor_mut();
+    for _ in 0..=index {
+        cursor.move_next();
+    }
+    cursor
+}
+
+#[cfg(feature = "unsafe-impls")]
+proptest! {
+    #[test]
+    fn simple_deque_3_structural(ops in list_ops()) {
+        let mut list = List::new();
+        let mut model = VecDeque::new();
+        for (step, op) in ops.iter().enumerate() {
+            let len = model.len();
+            match *op {
+                ListOp::Basic(ref op) => {
+                    let expected = apply_model(&mut model, op);
+                    let actual = match *op {
+                        Op::PushFront(x) => {
+                            list.push_front(x);
+                            None
+                        }
+                        Op::PushBack(x) => {
+                            list.push_back(x);
+                            None
+                        }
+                        Op::PopFront => list.pop_front(),
+                        Op::PopBack => list.pop_back(),
+                    };
+                    prop_assert_eq!(actual, expected, "step {}: {:?}", step, op);
+                }
+                ListOp::InsertAt(i, x) => {
+                    let i = i % (len + 1);
+                    cursor_at(&mut list, i).insert_before(x);
+                    model.insert(i, x);
+                }
+                ListOp::RemoveAt(i) => {
+                    if len > 0 {
+                        let i = i % len;
+                        prop_assert_eq!(cursor_at(&mut list, i).remove_current(), model.remove(i));
+                    }
+                }
+                ListOp::SplitAppend(i) => {
+                    let i = i % (len + 1);
+                    let mut front = cursor_at(&mut list, i).split_before();
+                    list.append(&mut front);
+                    model.rotate_left(i);
+                }
+                ListOp::RotateLeft(k) => {
+                    let k = k % (len + 1);
+                    list.rotate_left(k);
+                    model.rotate_left(k);
+                }
+                ListOp::RotateRight(k) => {
+                    let k = k % (len + 1);
+                    list.rotate_right(k);
+                    model.rotate_right(k);
+                }
+                ListOp::Clear => {
+                    list.clear();
+                    model.clear();
+                }
+            }
+            list.assert_invariants();
+            prop_assert_eq!(list.len(), model.len(), "step {}: {:?}", step, op);
+            prop_assert!(list.iter().eq(model.iter()), "step {}: {:?}", step, op);
+            prop_assert!(list.iter().rev().eq(model.iter().rev()), "step {}: {:?}", step, op);
+        }
+    }
+}
@@ -0,0 +1,314 @@
+// 链表表示的不相交集合(CLRS 21.2)
+// 每个集合是一条单链表，表头就是集合的代表元；每个元素都直接指向自己所在集合的代表元，
+// 表头另外记着表尾和集合大小:
+// - find: 直接读代表元指针，O(1)
+// - union: 把一条链表接到另一条的表尾后面，被接过去的那条上每个元素都要改代表元指针
+// 加权合并(weighted-union)总是把短的接到长的后面: 一个元素每被改一次代表元，它所在集合的大小至少翻倍，
+// 所以n个元素一共最多被改n*log2(n)次，m次操作的总代价是O(m + n log n)
+// 对比森林表示(按秩合并+路径压缩)，find要沿父指针往上走，但union只改一个指针，总代价接近线性；
+// 链表表示的优势是find恒为O(1)，并且可以按顺序列出一个集合的所有成员
+// 节点都放在Vec里，用make_set返回的下标访问
+
+use std::fmt;
+
+struct Node<T> {
+    value: T,
+    // 同一集合中的下一个元素
+    next: Option<usize>,
+    // 代表元(表头)的下标
+    rep: usize,
+    // 只有表头有效
+    set: Option<SetInfo>,
+}
+
+#[derive(Clone, Copy)]
+struct SetInfo {
+    tail: usize,
+    len: usize,
+}
+
+pub struct DisjointSets<T> {
+    nodes: Vec<Node<T>>,
+    set_count: usize,
+    // union时一共改了多少次代表元指针
+    relabels: u64,
+}
+
+impl<T> DisjointSets<T> {
+    pub fn new() -> Self {
+        DisjointSets {
+            nodes: Vec::new(),
+            set_count: 0,
+            relabels: 0,
+        }
+    }
+
+    // 元素总数
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn set_count(&self) -> usize {
+        self.set_count
+    }
+
+    pub fn relabels(&self) -> u64 {
+        self.relabels
+    }
+
+    // 新建一个只含value的集合，返回它的下标
+    pub fn make_set(&mut self, value: T) -> usize {
+        let id = self.nodes.len();
+        self.nodes.push(Node {
+            value,
+            next: None,
+            rep: id,
+            set: Some(SetInfo { tail: id, len: 1 }),
+        });
+        self.set_count += 1;
+        id
+    }
+
+    pub fn get(&self, id: usize) -> Option<&T> {
+        self.nodes.get(id).map(|node| &node.value)
+    }
+
+    pub fn get_mut(&mut self, id: usize) -> Option<&mut T> {
+        self.nodes.get_mut(id).map(|node| &mut node.value)
+    }
+
+    // 所在集合的代表元；下标越界时panic
+    pub fn find(&self, id: usize) -> usize {
+        self.nodes[id].rep
+    }
+
+    pub fn same_set(&self, a: usize, b: usize) -> bool {
+        self.find(a) == self.find(b)
+    }
+
+    // 所在集合的大小
+    pub fn set_len(&self, id: usize) -> usize {
+        self.info(self.find(id)).len
+    }
+
+    fn info(&self, rep: usize) -> SetInfo {
+        self.nodes[rep].set.expect("representative without set info")
+    }
+
+    // 合并两个元素所在的集合，已经在同一集合时返回false
+    // 合并后的代表元是原来较大那个集合的代表元，一样大时取a的
+    pub fn union(&mut self, a: usize, b: usize) -> bool {
+        let (mut big, mut small) = (self.find(a), self.find(b));
+        if big == small {
+            return false;
+        }
+        if self.info(big).len < self.info(small).len {
+            (big, small) = (small, big);
+        }
+        let small_info = self.nodes[small].set.take().unwrap();
+        let mut cur = Some(small);
+        while let Some(id) = cur {
+            self.nodes[id].rep = big;
+            self.relabels += 1;
+            cur = self.nodes[id].next;
+        }
+        // 短链表接到长链表的表尾后面
+        let big_info = self.info(big);
+        self.nodes[big_info.tail].next = Some(small);
+        self.nodes[big].set = Some(SetInfo {
+            tail: small_info.tail,
+            len: big_info.len + small_info.len,
+        });
+        self.set_count -= 1;
+        true
+    }
+
+    // 按链表顺序列出id所在集合的所有成员，代表元在最前面
+    pub fn members(&self, id: usize) -> Members<'_, T> {
+        Members {
+            sets: self,
+            next: Some(self.find(id)),
+        }
+    }
+
+    // 每个集合的代表元，按下标升序
+    pub fn representatives(&self) -> impl Iterator<Item = usize> + '_ {
+        self.nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| node.set.is_some())
+            .map(|(id, _)| id)
+    }
+}
+
+pub struct Members<'a, T> {
+    sets: &'a DisjointSets<T>,
+    next: Option<usize>,
+}
+
+impl<T> Iterator for Members<'_, T> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        let id = self.next?;
+        self.next = self.sets.nodes[id].next;
+        Some(id)
+    }
+}
+
+impl<T> Default for DisjointSets<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for DisjointSets<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut list = f.debug_list();
+        for rep in self.representatives() {
+            let set: Vec<&T> = self.members(rep).map(|id| &self.nodes[id].value).collect();
+            list.entry(&set);
+        }
+        list.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::XorShift;
+
+    // 对照用的森林表示: 按大小合并 + 路径压缩，记录find沿父指针走了多少步
+    struct Forest {
+        parent: Vec<usize>,
+        size: Vec<usize>,
+        hops: u64,
+    }
+
+    impl Forest {
+        fn new(n: usize) -> Self {
+            Forest {
+                parent: (0..n).collect(),
+                size: vec![1; n],
+                hops: 0,
+            }
+        }
+
+        fn find(&mut self, x: usize) -> usize {
+            let mut root = x;
+            while self.parent[root] != root {
+                root = self.parent[root];
+                self.hops += 1;
+            }
+            let mut cur = x;
+            while self.parent[cur] != root {
+                cur = std::mem::replace(&mut self.parent[cur], root);
+            }
+            root
+        }
+
+        fn union(&mut self, a: usize, b: usize) -> bool {
+            let (mut a, mut b) = (self.find(a), self.find(b));
+            if a == b {
+                return false;
+            }
+            if self.size[a] < self.size[b] {
+                (a, b) = (b, a);
+            }
+            self.parent[b] = a;
+            self.size[a] += self.size[b];
+            true
+        }
+    }
+
+    fn sets(n: usize) -> DisjointSets<usize> {
+        let mut sets = DisjointSets::new();
+        for i in 0..n {
+            assert_eq!(sets.make_set(i * 10), i);
+        }
+        sets
+    }
+
+    #[test]
+    fn union_and_find() {
+        let mut sets = sets(6);
+        assert_eq!(sets.set_count(), 6);
+        assert!(sets.union(0, 1));
+        assert!(sets.union(2, 3));
+        assert!(sets.union(3, 4));
+        assert!(!sets.union(2, 4));
+        // 大集合{2, 3, 4}的代表元胜出
+        assert!(sets.union(0, 3));
+        assert_eq!(sets.find(1), 2);
+        assert!(sets.same_set(0, 4) && !sets.same_set(0, 5));
+        assert_eq!(sets.set_len(4), 5);
+        assert_eq!(sets.members(1).collect::<Vec<_>>(), vec![2, 3, 4, 0, 1]);
+        assert_eq!(sets.representatives().collect::<Vec<_>>(), vec![2, 5]);
+        *sets.get_mut(5).unwrap() += 1;
+        assert_eq!(format!("{:?}", sets), "[[20, 30, 40, 0, 10], [51]]");
+        assert_eq!((sets.set_count(), sets.len()), (2, 6));
+    }
+
+    #[test]
+    fn weighted_union_relabels_only_the_smaller_set() {
+        // 不断把一个单元素集合并进大集合，加权合并每次只改一个指针；
+        // 不加权、总是改大集合那一边的话一共要改n(n-1)/2次
+        const N: usize = 1000;
+        let mut sets = sets(N);
+        for i in 1..N {
+            sets.union(i, 0);
+        }
+        assert_eq!(sets.relabels(), (N - 1) as u64);
+        assert_eq!(sets.set_len(0), N);
+    }
+
+    #[test]
+    fn balanced_merges_hit_the_n_log_n_bound() {
+        // 两两合并、四四合并……每一轮所有元素都被改一次，一共log2(n)轮
+        const LOG: usize = 10;
+        const N: usize = 1 << LOG;
+        let mut sets = sets(N);
+        let mut step = 1;
+        while step < N {
+            for i in (0..N).step_by(2 * step) {
+                assert!(sets.union(i, i + step));
+            }
+            step *= 2;
+        }
+        assert_eq!(sets.relabels(), (N * LOG / 2) as u64);
+        assert!(sets.relabels() <= (N * LOG) as u64);
+        assert_eq!(sets.set_count(), 1);
+    }
+
+    #[test]
+    fn contrast_with_forest() {
+        // 同样的操作序列下两种表示给出相同的划分；
+        // 链表表示的find不走任何指针，代价都花在union的改代表元上，森林表示正好相反
+        const N: usize = 2000;
+        let mut rng = XorShift::new(700);
+        let mut sets = sets(N);
+        let mut forest = Forest::new(N);
+        for _ in 0..3 * N {
+            let (a, b) = (rng.next_u64() as usize % N, rng.next_u64() as usize % N);
+            assert_eq!(sets.union(a, b), forest.union(a, b));
+        }
+        let hops_before = forest.hops;
+        for x in 0..N {
+            for y in [(x * 7) % N, (x + 1) % N] {
+                assert_eq!(sets.same_set(x, y), forest.find(x) == forest.find(y));
+            }
+        }
+        assert!(forest.hops > hops_before);
+        let n_log_n = (N as f64 * (N as f64).log2()) as u64;
+        assert!(sets.relabels() <= n_log_n);
+        // 每个集合的成员列表和它的大小一致
+        for rep in sets.representatives() {
+            assert_eq!(sets.members(rep).count(), sets.set_len(rep));
+            assert!(sets.members(rep).all(|id| sets.find(id) == rep));
+        }
+    }
+}
@@ -0,0 +1,376 @@
+// 带消除数组的无锁栈(elimination backoff stack, Hendler/Shavit/Yerushalmi)
+// Treiber栈的栈顶是所有线程争抢的单点，线程越多CAS失败越多
+// 观察: 一次push紧接着一次pop，栈的状态没有变化，这两个操作不必碰栈顶，可以直接把元素交给对方
+// - 先在Treiber栈上试一次CAS，成功就结束
+// - CAS失败说明有竞争，随机挑消除数组里的一个槽，等一小会儿看有没有相反的操作来配对:
+//   push把元素放进槽里等pop来取，或者pop在槽里登记等push送来元素
+// - 等不到就回到栈上重试
+// 配对成功的一对操作在交换的那一刻线性化(push紧接着pop)，对外和普通的栈没有区别
+// 竞争越激烈，配对的机会越多，消除掉的操作根本不碰栈顶，所以高线程数下扩展性比Treiber栈好
+// 槽是栈自己持有的固定数组，不需要回收策略；栈本身的节点回收仍然交给R
+
+use std::cell::{Cell, UnsafeCell};
+use std::mem::MaybeUninit;
+
+use crate::instrument::mutation;
+use crate::reclaim::{Epoch, Reclaim};
+use crate::rng::XorShift;
+use crate::sync::atomic::{AtomicU8, AtomicUsize, Ordering};
+use crate::treiber_stack::TreiberStack;
+
+// 消除数组的槽数
+pub const SLOTS: usize = 8;
+
+// 在槽里等待配对时最多自旋这么多次
+const WAIT_SPINS: u32 = 256;
+
+// 槽的状态
+// EMPTY: 空闲
+// BUSY: 有线程正在独占地读写value
+// PUSH_WAITING: push放好了元素，等pop来取
+// POP_WAITING: pop在等push送元素
+// DONE: 交换完成，由等待的那一方取走结果并把槽还原成EMPTY
+const EMPTY: u8 = 0;
+const BUSY: u8 = 1;
+const PUSH_WAITING: u8 = 2;
+const POP_WAITING: u8 = 3;
+const DONE: u8 = 4;
+
+struct Slot<T> {
+    state: AtomicU8,
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+
+impl<T> Slot<T> {
+    fn new() -> Self {
+        Slot {
+            state: AtomicU8::new(EMPTY),
+            value: UnsafeCell::new(MaybeUninit::uninit()),
+        }
+    }
+
+    // 对方已经接手了交换，等它把DONE写上
+    fn wait_done(&self) {
+        let mut spins = 0;
+        while self.state.load(Ordering::Acquire) != DONE {
+            if spins < WAIT_SPINS {
+                std::hint::spin_loop();
+                spins += 1;
+            } else {
+                std::thread::yield_now();
+            }
+        }
+    }
+
+    // 在有限的时间里等状态变成DONE
+    // 后一半改成让出CPU，否则核数不够时对方根本没机会在我们等待的时候运行
+    fn wait_done_for_a_while(&self) -> bool {
+        for spins in 0..WAIT_SPINS {
+            if self.state.load(Ordering::Acquire) == DONE {
+                return true;
+            }
+            if spins < WAIT_SPINS / 2 {
+                std::hint::spin_loop();
+            } else {
+                std::thread::yield_now();
+            }
+        }
+        false
+    }
+
+    // 把elem交给一个pop，没有配对成功时还回来
+    fn exchange_push(&self, elem: T) -> Result<(), T> {
+        match self.state.load(Ordering::Relaxed) {
+            POP_WAITING => {
+                if self
+                    .state
+                    .compare_exchange(POP_WAITING, BUSY, Ordering::Acquire, Ordering::Relaxed)
+                    .is_err()
+                {
+                    return Err(elem);
+                }
+                // SAFETY: BUSY状态下只有当前线程访问value
+                unsafe { (*self.value.get()).write(elem) };
+                self.state.store(DONE, Ordering::Release);
+                Ok(())
+            }
+            EMPTY => {
+                if self
+                    .state
+                    .compare_exchange(EMPTY, BUSY, Ordering::Acquire, Ordering::Relaxed)
+                    .is_err()
+                {
+                    return Err(elem);
+                }
+                unsafe { (*self.value.get()).write(elem) };
+                self.state.store(PUSH_WAITING, Ordering::Release);
+                if !self.wait_done_for_a_while() {
+                    // 超时，收回元素；CAS失败说明有pop正在取，等它取完
+                    if self
+                        .state
+                        .compare_exchange(PUSH_WAITING, BUSY, Ordering::Acquire, Ordering::Relaxed)
+                        .is_ok()
+                    {
+                        let elem = unsafe { (*self.value.get()).assume_init_read() };
+                        self.state.store(EMPTY, Ordering::Release);
+                        return Err(elem);
+                    }
+                    self.wait_done();
+                }
+                self.state.store(EMPTY, Ordering::Release);
+                Ok(())
+            }
+            _ => Err(elem),
+        }
+    }
+
+    // 从一个push那里取元素
+    fn exchange_pop(&self) -> Option<T> {
+        match self.state.load(Ordering::Relaxed) {
+            PUSH_WAITING => {
+                self.state
+                    .compare_exchange(PUSH_WAITING, BUSY, Ordering::Acquire, Ordering::Relaxed)
+                    .ok()?;
+                // SAFETY: PUSH_WAITING说明value已经写好，BUSY之后只有当前线程访问
+                let elem = unsafe { (*self.value.get()).assume_init_read() };
+                self.state.store(DONE, Ordering::Release);
+                Some(elem)
+            }
+            EMPTY => {
+                self.state
+                    .compare_exchange(EMPTY, POP_WAITING, Ordering::Relaxed, Ordering::Relaxed)
+                    .ok()?;
+                if !self.wait_done_for_a_while() {
+                    // 超时，撤销登记；CAS失败说明有push正在写，等它写完
+                    if self
+                        .state
+                        .compare_exchange(POP_WAITING, EMPTY, Ordering::Relaxed, Ordering::Relaxed)
+                        .is_ok()
+                    {
+                        return None;
+                    }
+                    self.wait_done();
+                }
+                let elem = unsafe { (*self.value.get()).assume_init_read() };
+                self.state.store(EMPTY, Ordering::Release);
+                Some(elem)
+            }
+            _ => None,
+        }
+    }
+}
+
+// value只在状态机约定的独占阶段被访问，元素会在线程之间转移，所以只要求T: Send
+// 槽里的元素只在push/pop调用期间存在，Drop时槽都是EMPTY，不需要析构什么
+unsafe impl<T: Send> Send for Slot<T> {}
+unsafe impl<T: Send> Sync for Slot<T> {}
+
+// 每个线程用自己的随机数挑槽，避免大家都挤在同一个槽上
+fn random_slot() -> usize {
+    thread_local! {
+        static RNG: Cell<Option<XorShift>> = const { Cell::new(None) };
+    }
+    RNG.with(|cell| {
+        let mut rng = cell.take().unwrap_or_else(XorShift::from_counter);
+        let index = rng.next_u64() as usize % SLOTS;
+        cell.set(Some(rng));
+        index
+    })
+}
+
+pub struct EliminationStack<T, R: Reclaim = Epoch> {
+    stack: TreiberStack<T, R>,
+    slots: [Slot<T>; SLOTS],
+    // 通过消除数组配对成功的push/pop对数
+    eliminated: AtomicUsize,
+}
+
+impl<T> EliminationStack<T> {
+    pub fn new() -> Self {
+        Self::empty()
+    }
+}
+
+impl<T, R: Reclaim> EliminationStack<T, R> {
+    // 用指定的回收策略创建，例如EliminationStack::with_reclaim(Hazard)
+    pub fn with_reclaim(_reclaim: R) -> Self {
+        Self::empty()
+    }
+
+    fn empty() -> Self {
+        EliminationStack {
+            stack: TreiberStack::default(),
+            slots: std::array::from_fn(|_| Slot::new()),
+            eliminated: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn push(&self, mut elem: T) {
+        loop {
+            match self.stack.try_push(elem) {
+                Ok(()) => {
+                    mutation!("push");
+                    return;
+                }
+                Err(e) => elem = e,
+            }
+            match self.slots[random_slot()].exchange_push(elem) {
+                Ok(()) => {
+                    mutation!("push_eliminated");
+                    return;
+                }
+                Err(e) => elem = e,
+            }
+        }
+    }
+
+    pub fn pop(&self) -> Option<T> {
+        loop {
+            if let Ok(elem) = self.stack.try_pop() {
+                mutation!("pop", found = elem.is_some());
+                return elem;
+            }
+            if let Some(elem) = self.slots[random_slot()].exchange_pop() {
+                self.eliminated.fetch_add(1, Ordering::Relaxed);
+                mutation!("pop_eliminated");
+                return Some(elem);
+            }
+        }
+    }
+
+    // 并发情况下结果只代表调用那一刻的状态；等在消除数组里的元素不算
+    pub fn is_empty(&self) -> bool {
+        self.stack.is_empty()
+    }
+
+    pub fn eliminated(&self) -> usize {
+        self.eliminated.load(Ordering::Relaxed)
+    }
+}
+
+impl<T, R: Reclaim> Default for EliminationStack<T, R> {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reclaim::Hazard;
+    use std::collections::HashSet;
+    use std::sync::{Arc, Barrier};
+    use std::thread;
+
+    #[test]
+    fn basics() {
+        let stack = EliminationStack::new();
+        assert!(stack.is_empty());
+        assert_eq!(stack.pop(), None);
+        for i in 0..5 {
+            stack.push(i);
+        }
+        assert_eq!(stack.pop(), Some(4));
+        stack.push(9);
+        let popped: Vec<_> = (0..6).map(|_| stack.pop()).collect();
+        assert_eq!(popped, [Some(9), Some(3), Some(2), Some(1), Some(0), None]);
+        // 没有竞争时CAS不会失败，不会走到消除数组
+        assert_eq!(stack.eliminated(), 0);
+    }
+
+    #[test]
+    fn slot_times_out_without_partner() {
+        let slot = Slot::new();
+        assert_eq!(slot.exchange_push(1), Err(1));
+        assert_eq!(slot.exchange_pop(), None);
+        assert_eq!(slot.state.load(Ordering::Relaxed), EMPTY);
+    }
+
+    #[test]
+    fn slot_exchanges_between_threads() {
+        // 一边反复push一边反复pop，直到全部元素都通过同一个槽交换过去
+        const N: usize = 200;
+        let slot = Arc::new(Slot::new());
+        let pusher = {
+            let slot = Arc::clone(&slot);
+            thread::spawn(move || {
+                for i in 0..N {
+                    let mut elem = i;
+                    while let Err(e) = slot.exchange_push(elem) {
+                        elem = e;
+                        thread::yield_now();
+                    }
+                }
+            })
+        };
+        let mut got = Vec::new();
+        while got.len() < N {
+            match slot.exchange_pop() {
+                Some(x) => got.push(x),
+                None => thread::yield_now(),
+            }
+        }
+        pusher.join().unwrap();
+        // 只有一个push方，交换出来的顺序就是push的顺序
+        assert_eq!(got, (0..N).collect::<Vec<_>>());
+        assert_eq!(slot.state.load(Ordering::Relaxed), EMPTY);
+    }
+
+    fn concurrent_push_pop<R: Reclaim>(reclaim: R) {
+        const THREADS: usize = 8;
+        const PER_THREAD: usize = 2000;
+
+        let stack = Arc::new(EliminationStack::with_reclaim(reclaim));
+        let barrier = Arc::new(Barrier::new(THREADS));
+        let handles: Vec<_> = (0..THREADS)
+            .map(|t| {
+                let stack = Arc::clone(&stack);
+                let barrier = Arc::clone(&barrier);
+                thread::spawn(move || {
+                    barrier.wait();
+                    let mut popped = Vec::new();
+                    for i in 0..PER_THREAD {
+                        stack.push(t * PER_THREAD + i);
+                        popped.extend(stack.pop());
+                    }
+                    popped
+                })
+            })
+            .collect();
+
+        let mut seen = HashSet::new();
+        for h in handles {
+            for x in h.join().unwrap() {
+                assert!(seen.insert(x), "{} popped twice", x);
+            }
+        }
+        while let Some(x) = stack.pop() {
+            assert!(seen.insert(x), "{} popped twice", x);
+        }
+        assert_eq!(seen.len(), THREADS * PER_THREAD);
+    }
+
+    #[test]
+    fn concurrent_push_pop_epoch() {
+        concurrent_push_pop(Epoch);
+    }
+
+    #[test]
+    fn concurrent_push_pop_hazard() {
+        concurrent_push_pop(Hazard);
+    }
+
+    #[test]
+    fn remaining_elements_dropped() {
+        let marker = Arc::new(());
+        let stack = EliminationStack::new();
+        for _ in 0..10 {
+            stack.push(Arc::clone(&marker));
+        }
+        drop(stack.pop());
+        assert_eq!(Arc::strong_count(&marker), 10);
+        drop(stack);
+        assert_eq!(Arc::strong_count(&marker), 1);
+    }
+}
@@ -0,0 +1,435 @@
+// 基于epoch的内存回收(EBR)
+// 无锁结构里，一个节点被摘下之后，其他线程手里可能还留着指向它的指针，不能马上释放
+// EBR的思路:
+// 1. 全局有一个单调递增的epoch计数
+// 2. 线程访问共享结构前先pin，把自己看到的全局epoch登记下来；访问结束后unpin
+// 3. 被摘下的节点不立刻释放，而是defer到垃圾袋里，并记下当时的全局epoch e
+// 4. 只有当所有处于pin状态的线程都已经登记到当前epoch时，全局epoch才能前进
+//    所以全局epoch到达e + 2时，所有可能看到该节点的线程都已经unpin过，可以安全释放
+// 这里实现的是一个教学用的小型回收器: 参与者登记表和全局垃圾用Mutex保护，
+// 只有pin/unpin这条热路径是无锁的
+
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::sync::atomic::{fence, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, PoisonError};
+
+// 本地垃圾袋攒够这么多条就交给全局并尝试回收
+const BAG_CAPACITY: usize = 64;
+
+// 延迟执行的回收动作
+enum Deferred {
+    // 释放一个Box::into_raw得到的指针，drop_fn是对应类型的析构函数
+    Destroy {
+        ptr: *mut u8,
+        drop_fn: unsafe fn(*mut u8),
+    },
+    Call(Box<dyn FnOnce() + Send>),
+}
+
+// Destroy里的裸指针由defer_destroy的调用者保证可以在任意线程释放
+unsafe impl Send for Deferred {}
+
+impl Deferred {
+    fn run(self) {
+        match self {
+            Deferred::Destroy { ptr, drop_fn } => unsafe { drop_fn(ptr) },
+            Deferred::Call(f) => f(),
+        }
+    }
+}
+
+unsafe fn drop_box<T>(ptr: *mut u8) {
+    drop(Box::from_raw(ptr as *mut T));
+}
+
+struct Global {
+    epoch: AtomicUsize,
+    participants: Mutex<Vec<Arc<Participant>>>,
+    // (封袋时的全局epoch, 袋子里的回收动作)
+    garbage: Mutex<Vec<(usize, Vec<Deferred>)>>,
+    // 已经defer但还没有执行的回收动作数量
+    pending: AtomicUsize,
+}
+
+// 每个注册线程一份，state为0表示未pin，否则是(epoch << 1) | 1
+struct Participant {
+    state: AtomicUsize,
+}
+
+impl Global {
+    // 所有pin住的参与者都已经在当前epoch时才能前进一步
+    fn try_advance(&self) -> usize {
+        let epoch = self.epoch.load(Ordering::Relaxed);
+        fence(Ordering::SeqCst);
+        let participants = self.participants.lock().unwrap_or_else(PoisonError::into_inner);
+        for p in participants.iter() {
+            let state = p.state.load(Ordering::Relaxed);
+            if state & 1 == 1 && state >> 1 != epoch {
+                return epoch;
+            }
+        }
+        drop(participants);
+        fence(Ordering::Acquire);
+        match self
+            .epoch
+            .compare_exchange(epoch, epoch + 1, Ordering::Release, Ordering::Relaxed)
+        {
+            Ok(_) => epoch + 1,
+            Err(actual) => actual,
+        }
+    }
+
+    fn push_bag(&self, bag: Vec<Deferred>) {
+        if bag.is_empty() {
+            return;
+        }
+        // 袋子里每一条都是在当前epoch或更早被摘下的，用当前epoch封袋是保守且正确的
+        fence(Ordering::SeqCst);
+        let epoch = self.epoch.load(Ordering::Relaxed);
+        self.garbage
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push((epoch, bag));
+    }
+
+    fn collect(&self) {
+        // 一个袋子需要epoch前进两次才能释放，这里最多尝试推进两次
+        self.try_advance();
+        let epoch = self.try_advance();
+
+        let ready: Vec<Vec<Deferred>> = {
+            let mut garbage = self.garbage.lock().unwrap_or_else(PoisonError::into_inner);
+            let mut ready = Vec::new();
+            let mut i = 0;
+            while i < garbage.len() {
+                if garbage[i].0 + 2 <= epoch {
+                    ready.push(garbage.swap_remove(i).1);
+                } else {
+                    i += 1;
+                }
+            }
+            ready
+        };
+        // 在锁外执行回收动作，回收动作里再次pin也不会死锁
+        for bag in ready {
+            let n = bag.len();
+            bag.into_iter().for_each(Deferred::run);
+            self.pending.fetch_sub(n, Ordering::Relaxed);
+        }
+    }
+}
+
+impl Drop for Global {
+    fn drop(&mut self) {
+        // 最后一个引用消失时已经没有任何参与者，剩下的垃圾可以全部释放
+        let garbage = std::mem::take(self.garbage.get_mut().unwrap_or_else(PoisonError::into_inner));
+        for (_, bag) in garbage {
+            bag.into_iter().for_each(Deferred::run);
+        }
+    }
+}
+
+// 一个独立的回收域，不同Collector之间的epoch和垃圾互不影响
+pub struct Collector {
+    global: Arc<Global>,
+}
+
+impl Collector {
+    pub fn new() -> Self {
+        Collector {
+            global: Arc::new(Global {
+                epoch: AtomicUsize::new(0),
+                participants: Mutex::new(Vec::new()),
+                garbage: Mutex::new(Vec::new()),
+                pending: AtomicUsize::new(0),
+            }),
+        }
+    }
+
+    // 当前线程注册为参与者，返回的句柄只能在本线程使用
+    pub fn register(&self) -> LocalHandle {
+        let participant = Arc::new(Participant {
+            state: AtomicUsize::new(0),
+        });
+        self.global
+            .participants
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(Arc::clone(&participant));
+        LocalHandle {
+            local: Rc::new(Local {
+                global: Arc::clone(&self.global),
+                participant,
+                guards: Cell::new(0),
+                bag: RefCell::new(Vec::new()),
+            }),
+        }
+    }
+
+    // 已经defer但还没有真正执行的回收动作数量，用来观察垃圾是否有界
+    pub fn pending(&self) -> usize {
+        self.global.pending.load(Ordering::Relaxed)
+    }
+
+    pub fn epoch(&self) -> usize {
+        self.global.epoch.load(Ordering::Relaxed)
+    }
+}
+
+impl Default for Collector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+struct Local {
+    global: Arc<Global>,
+    participant: Arc<Participant>,
+    // 嵌套pin的层数，只有最外层真正登记/注销
+    guards: Cell<usize>,
+    bag: RefCell<Vec<Deferred>>,
+}
+
+impl Local {
+    fn flush(&self) {
+        let bag = std::mem::take(&mut *self.bag.borrow_mut());
+        self.global.push_bag(bag);
+        self.global.collect();
+    }
+}
+
+impl Drop for Local {
+    fn drop(&mut self) {
+        // 线程退出: 本地垃圾交给全局，并从登记表里注销
+        let bag = std::mem::take(self.bag.get_mut());
+        self.global.push_bag(bag);
+        self.global
+            .participants
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .retain(|p| !Arc::ptr_eq(p, &self.participant));
+        self.global.collect();
+    }
+}
+
+pub struct LocalHandle {
+    local: Rc<Local>,
+}
+
+impl LocalHandle {
+    pub fn pin(&self) -> Guard {
+        let local = &self.local;
+        let n = local.guards.get();
+        local.guards.set(n + 1);
+        if n == 0 {
+            let epoch = local.global.epoch.load(Ordering::Relaxed);
+            local.participant.state.store((epoch << 1) | 1, Ordering::Relaxed);
+            // 登记必须在之后读取任何共享指针之前对其他线程可见
+            fence(Ordering::SeqCst);
+        }
+        Guard {
+            local: Rc::clone(local),
+        }
+    }
+
+    pub fn is_pinned(&self) -> bool {
+        self.local.guards.get() > 0
+    }
+
+    // 把本地垃圾交给全局并尝试回收
+    pub fn flush(&self) {
+        self.local.flush();
+    }
+}
+
+// pin的凭证，存活期间从共享结构里读出的指针都不会被释放
+// 内部持有Rc，因此Guard既不能Send也不能Sync，只能在pin它的线程里使用
+pub struct Guard {
+    local: Rc<Local>,
+}
+
+impl Guard {
+    // 推迟执行f，直到所有当前可能持有旧指针的线程都unpin
+    pub fn defer<F>(&self, f: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        self.push(Deferred::Call(Box::new(f)));
+    }
+
+    /// 推迟释放一个由Box::into_raw得到的指针
+    ///
+    /// # Safety
+    ///
+    /// ptr必须来自Box::into_raw且已经从共享结构中摘下，之后不会再被其他地方释放；
+    /// 析构T必须可以在任意线程、任意时间点进行
+    pub unsafe fn defer_destroy<T>(&self, ptr: *mut T) {
+        self.push(Deferred::Destroy {
+            ptr: ptr as *mut u8,
+            drop_fn: drop_box::<T>,
+        });
+    }
+
+    pub fn flush(&self) {
+        self.local.flush();
+    }
+
+    // 袋子满了也不在这里回收: 当前线程还pin着，自己就会挡住epoch前进，等unpin时再处理
+    fn push(&self, deferred: Deferred) {
+        self.local.global.pending.fetch_add(1, Ordering::Relaxed);
+        self.local.bag.borrow_mut().push(deferred);
+    }
+}
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        let n = self.local.guards.get();
+        self.local.guards.set(n - 1);
+        if n == 1 {
+            self.local.participant.state.store(0, Ordering::Release);
+            let full = self.local.bag.borrow().len() >= BAG_CAPACITY;
+            if full {
+                self.local.flush();
+            }
+        }
+    }
+}
+
+// 整个进程共享的默认回收器，无锁结构默认都使用它
+pub fn default_collector() -> &'static Collector {
+    static COLLECTOR: OnceLock<Collector> = OnceLock::new();
+    COLLECTOR.get_or_init(Collector::new)
+}
+
+thread_local! {
+    static HANDLE: LocalHandle = default_collector().register();
+}
+
+// 在默认回收器上pin当前线程
+pub fn pin() -> Guard {
+    // 线程局部变量已经被销毁(比如在别的线程局部变量的析构函数里)时，临时注册一个句柄
+    HANDLE
+        .try_with(LocalHandle::pin)
+        .unwrap_or_else(|_| default_collector().register().pin())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::Barrier;
+    use std::thread;
+
+    #[test]
+    fn deferred_waits_for_pinned_threads() {
+        let collector = Collector::new();
+        let reader = collector.register();
+        let writer = collector.register();
+        let freed = Arc::new(AtomicBool::new(false));
+
+        let reader_guard = reader.pin();
+        {
+            let guard = writer.pin();
+            let freed = Arc::clone(&freed);
+            guard.defer(move || freed.store(true, Ordering::SeqCst));
+        }
+        // reader还pin着，无论回收多少次都不能执行
+        for _ in 0..10 {
+            writer.flush();
+        }
+        assert!(!freed.load(Ordering::SeqCst));
+        assert_eq!(collector.pending(), 1);
+
+        drop(reader_guard);
+        writer.flush();
+        assert!(freed.load(Ordering::SeqCst));
+        assert_eq!(collector.pending(), 0);
+    }
+
+    #[test]
+    fn nested_pins() {
+        let collector = Collector::new();
+        let handle = collector.register();
+        let outer = handle.pin();
+        let inner = handle.pin();
+        drop(outer);
+        assert!(handle.is_pinned());
+        drop(inner);
+        assert!(!handle.is_pinned());
+    }
+
+    #[test]
+    fn defer_destroy_frees_box() {
+        let collector = Collector::new();
+        let handle = collector.register();
+        let marker = Arc::new(());
+        let ptr = Box::into_raw(Box::new(Arc::clone(&marker)));
+        unsafe { handle.pin().defer_destroy(ptr) };
+        assert_eq!(Arc::strong_count(&marker), 2);
+        handle.flush();
+        assert_eq!(Arc::strong_count(&marker), 1);
+    }
+
+    #[test]
+    fn garbage_stays_bounded_under_load() {
+        const THREADS: usize = 4;
+        const ROUNDS: usize = 20;
+        const BATCH: usize = 1000;
+
+        let collector = Arc::new(Collector::new());
+        let executed = Arc::new(AtomicUsize::new(0));
+        let max_pending = Arc::new(AtomicUsize::new(0));
+        let barrier = Arc::new(Barrier::new(THREADS));
+
+        let handles: Vec<_> = (0..THREADS)
+            .map(|_| {
+                let collector = Arc::clone(&collector);
+                let executed = Arc::clone(&executed);
+                let max_pending = Arc::clone(&max_pending);
+                let barrier = Arc::clone(&barrier);
+                thread::spawn(move || {
+                    let handle = collector.register();
+                    for _ in 0..ROUNDS {
+                        for _ in 0..BATCH {
+                            let guard = handle.pin();
+                            let executed = Arc::clone(&executed);
+                            guard.defer(move || {
+                                executed.fetch_add(1, Ordering::Relaxed);
+                            });
+                            drop(guard);
+                            max_pending.fetch_max(collector.pending(), Ordering::Relaxed);
+                        }
+                        // 一轮结束时所有线程都已经unpin，再各自回收一次，之前的垃圾应该全部释放
+                        barrier.wait();
+                        handle.flush();
+                        barrier.wait();
+                        assert_eq!(collector.pending(), 0);
+                        barrier.wait();
+                    }
+                })
+            })
+            .collect();
+        for h in handles {
+            h.join().unwrap();
+        }
+
+        // 任意时刻积压的垃圾不超过一轮的量，和总轮数无关
+        let max = max_pending.load(Ordering::Relaxed);
+        assert!(max <= THREADS * BATCH, "pending garbage grew to {}", max);
+        assert_eq!(executed.load(Ordering::Relaxed), THREADS * ROUNDS * BATCH);
+    }
+
+    #[test]
+    fn dropping_collector_runs_remaining_garbage() {
+        let marker = Arc::new(());
+        {
+            let collector = Collector::new();
+            let handle = collector.register();
+            let m = Arc::clone(&marker);
+            handle.pin().defer(move || drop(m));
+            drop(handle);
+        }
+        assert_eq!(Arc::strong_count(&marker), 1);
+    }
+}
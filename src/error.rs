@@ -0,0 +1,66 @@
+// 各个链表try_*方法共用的错误类型
+// 原来的insert/remove/split_off之类在下标越界、RefCell借用冲突、容量满的时候panic，
+// 对应的try_*版本把同样的情况作为ListError返回，调用者可以用?往上传，不用catch_unwind
+// 错误里不带被拒绝的元素: 需要把元素拿回来的场合(比如StaticList满了)继续用原来返回Err(elem)的方法
+
+use std::fmt;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ListError {
+    // 下标或者位置超出范围，len是当时的长度
+    OutOfRange { index: usize, len: usize },
+    // 元素已经被RefCell借出，不能再借
+    BorrowConflict,
+    // 固定容量的结构已经装满
+    CapacityExceeded { capacity: usize },
+    // 分配器没能分配出节点
+    AllocError,
+}
+
+impl fmt::Display for ListError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ListError::OutOfRange { index, len } => {
+                write!(f, "index {index} out of range for length {len}")
+            }
+            ListError::BorrowConflict => f.write_str("element is already borrowed"),
+            ListError::CapacityExceeded { capacity } => {
+                write!(f, "capacity of {capacity} elements exceeded")
+            }
+            ListError::AllocError => f.write_str("memory allocation failed"),
+        }
+    }
+}
+
+impl std::error::Error for ListError {}
+
+// index > len时返回OutOfRange，插入类操作用；只开safe特性时没有用到它的模块
+#[cfg_attr(not(any(feature = "persistent", feature = "unsafe-impls")), allow(dead_code))]
+pub(crate) fn check_insert(index: usize, len: usize) -> Result<(), ListError> {
+    if index > len {
+        Err(ListError::OutOfRange { index, len })
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn messages() {
+        assert_eq!(
+            ListError::OutOfRange { index: 5, len: 3 }.to_string(),
+            "index 5 out of range for length 3"
+        );
+        assert_eq!(
+            ListError::CapacityExceeded { capacity: 4 }.to_string(),
+            "capacity of 4 elements exceeded"
+        );
+        let err: Box<dyn std::error::Error> = Box::new(ListError::BorrowConflict);
+        assert_eq!(err.to_string(), "element is already borrowed");
+        assert_eq!(check_insert(3, 3), Ok(()));
+        assert_eq!(check_insert(4, 3), Err(ListError::OutOfRange { index: 4, len: 3 }));
+    }
+}
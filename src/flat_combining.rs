@@ -0,0 +1,301 @@
+// 平铺合并(flat combining)队列
+// 用一把Mutex保护队列时，高竞争下锁在各个CPU之间来回传递，每个操作都要付一次缓存行迁移的代价
+// flat combining的思路: 线程不直接去抢锁改队列，而是把"我要做什么"发布到一条请求链表上，
+// 然后谁抢到了合并者(combiner)的身份，谁就一次性把链表上所有人的请求都做完，再把结果写回各自的请求里
+// - 请求节点放在调用线程自己的栈上，用侵入式next指针挂到一条无锁的单链表上(和treiber_stack的push一样)
+// - 合并者把整条请求链表一次性摘下(swap成null)，反转成先来先处理的顺序，逐个应用到一个普通的simple_deque_3上
+// - 其他线程只是自旋等待自己请求的done标志，不会碰队列本身
+// 队列始终只被合并者这一个线程访问，缓存行留在它的CPU上，一批请求只需要一次锁交接
+
+use std::cell::UnsafeCell;
+use std::fmt;
+use std::hint;
+use std::mem;
+use std::ptr;
+use std::thread;
+
+use crate::instrument::mutation;
+use crate::simple_deque_3::List;
+use crate::sync::atomic::{AtomicBool, AtomicPtr, AtomicU64, Ordering};
+
+// 一次合并最多摘几轮请求链表，防止合并者一直被新请求拖住
+const MAX_PASSES: usize = 4;
+// 等待时先自旋这么多次再让出CPU
+const SPINS: usize = 64;
+
+enum Op<T> {
+    Push(T),
+    Pop,
+    Len,
+    Popped(Option<T>),
+    Counted(usize),
+    // 合并者取走操作之后、写回结果之前的占位
+    Taken,
+}
+
+struct Request<T> {
+    op: UnsafeCell<Op<T>>,
+    done: AtomicBool,
+    next: UnsafeCell<*mut Request<T>>,
+}
+
+pub struct FcQueue<T> {
+    // 只有持有combining的线程可以访问
+    queue: UnsafeCell<List<T>>,
+    combining: AtomicBool,
+    // 待处理请求组成的单链表，后发布的在前
+    requests: AtomicPtr<Request<T>>,
+    batches: AtomicU64,
+    combined: AtomicU64,
+}
+
+impl<T> FcQueue<T> {
+    pub fn new() -> Self {
+        FcQueue {
+            queue: UnsafeCell::new(List::new()),
+            combining: AtomicBool::new(false),
+            requests: AtomicPtr::new(ptr::null_mut()),
+            batches: AtomicU64::new(0),
+            combined: AtomicU64::new(0),
+        }
+    }
+
+    pub fn push(&self, elem: T) {
+        self.execute(Op::Push(elem));
+    }
+
+    pub fn pop(&self) -> Option<T> {
+        match self.execute(Op::Pop) {
+            Op::Popped(elem) => elem,
+            _ => unreachable!(),
+        }
+    }
+
+    // 并发情况下结果只代表调用那一刻的状态
+    pub fn len(&self) -> usize {
+        match self.execute(Op::Len) {
+            Op::Counted(len) => len,
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // 合并者一共摘了多少批请求、处理了多少个请求；两者之比就是平均每次锁交接做了多少操作
+    pub fn stats(&self) -> (u64, u64) {
+        (self.batches.load(Ordering::Relaxed), self.combined.load(Ordering::Relaxed))
+    }
+
+    // 发布请求，然后要么等别人替我做完，要么自己当合并者
+    fn execute(&self, op: Op<T>) -> Op<T> {
+        let request = Request {
+            op: UnsafeCell::new(op),
+            done: AtomicBool::new(false),
+            next: UnsafeCell::new(ptr::null_mut()),
+        };
+        let ptr = &request as *const Request<T> as *mut Request<T>;
+        let mut head = self.requests.load(Ordering::Relaxed);
+        loop {
+            // SAFETY: 请求还没有发布，只有当前线程访问
+            unsafe { *request.next.get() = head };
+            // Release保证合并者通过Acquire摘下链表时能看到op和next
+            match self
+                .requests
+                .compare_exchange_weak(head, ptr, Ordering::Release, Ordering::Relaxed)
+            {
+                Ok(_) => break,
+                Err(actual) => head = actual,
+            }
+        }
+
+        let mut spins = 0;
+        // Acquire和合并者设置done时的Release配对，之后才能读结果
+        while !request.done.load(Ordering::Acquire) {
+            if self
+                .combining
+                .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+                .is_ok()
+            {
+                // 自己的请求在抢到锁之前就发布了，第一轮一定会被处理
+                self.combine();
+                self.combining.store(false, Ordering::Release);
+                continue;
+            }
+            spins += 1;
+            if spins < SPINS {
+                hint::spin_loop();
+            } else {
+                thread::yield_now();
+            }
+        }
+        // done之后合并者不会再访问这个请求，request可以安全地离开作用域
+        request.op.into_inner()
+    }
+
+    // 调用者持有combining
+    fn combine(&self) {
+        // SAFETY: 持有combining的线程独占队列
+        let queue = unsafe { &mut *self.queue.get() };
+        for _ in 0..MAX_PASSES {
+            let mut cur = self.requests.swap(ptr::null_mut(), Ordering::Acquire);
+            if cur.is_null() {
+                break;
+            }
+            // SAFETY: 摘下来的请求在设置done之前都由合并者独占，发布它的线程在等待
+            unsafe {
+                // 链表是后发布的在前，反转成先来先处理
+                let mut prev = ptr::null_mut();
+                while !cur.is_null() {
+                    let next = *(*cur).next.get();
+                    *(*cur).next.get() = prev;
+                    prev = cur;
+                    cur = next;
+                }
+                let mut cur = prev;
+                let mut count = 0;
+                while !cur.is_null() {
+                    let request = &*cur;
+                    // 设置done之后请求随时可能被释放，先把next读出来
+                    cur = *request.next.get();
+                    let op = &mut *request.op.get();
+                    *op = match mem::replace(op, Op::Taken) {
+                        Op::Push(elem) => {
+                            queue.push_back(elem);
+                            Op::Popped(None)
+                        }
+                        Op::Pop => Op::Popped(queue.pop_front()),
+                        Op::Len => Op::Counted(queue.len()),
+                        _ => unreachable!("request already served"),
+                    };
+                    request.done.store(true, Ordering::Release);
+                    count += 1;
+                }
+                self.batches.fetch_add(1, Ordering::Relaxed);
+                self.combined.fetch_add(count, Ordering::Relaxed);
+                // 一批请求处理完，queue里每个操作各自的事件挂在simple_deque_3下面
+                mutation!("combine", count, len = queue.len());
+            }
+        }
+    }
+}
+
+impl<T> Default for FcQueue<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> fmt::Debug for FcQueue<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (batches, combined) = self.stats();
+        f.debug_struct("FcQueue")
+            .field("batches", &batches)
+            .field("combined", &combined)
+            .finish()
+    }
+}
+
+// 元素由合并者在线程之间转移，只要求T: Send
+unsafe impl<T: Send> Send for FcQueue<T> {}
+unsafe impl<T: Send> Sync for FcQueue<T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+    use std::sync::Arc;
+
+    #[test]
+    fn fifo_single_thread() {
+        let queue = FcQueue::new();
+        assert_eq!(queue.pop(), None);
+        assert!(queue.is_empty());
+        for i in 0..5 {
+            queue.push(i);
+        }
+        assert_eq!(queue.len(), 5);
+        assert_eq!(queue.pop(), Some(0));
+        assert_eq!(queue.pop(), Some(1));
+        queue.push(5);
+        assert_eq!((0..4).map(|_| queue.pop().unwrap()).collect::<Vec<_>>(), vec![2, 3, 4, 5]);
+        // 单线程时每个请求自己合并自己
+        assert_eq!(queue.stats(), (15, 15));
+    }
+
+    #[test]
+    fn concurrent_push_pop_exactly_once() {
+        const THREADS: usize = 8;
+        const PER_THREAD: usize = 2000;
+
+        let queue = Arc::new(FcQueue::new());
+        let handles: Vec<_> = (0..THREADS)
+            .map(|t| {
+                let queue = Arc::clone(&queue);
+                thread::spawn(move || {
+                    let mut popped = Vec::new();
+                    for i in 0..PER_THREAD {
+                        queue.push(t * PER_THREAD + i);
+                        if i % 2 == 1 {
+                            popped.extend(queue.pop());
+                        }
+                    }
+                    popped
+                })
+            })
+            .collect();
+        let mut seen = HashSet::new();
+        for h in handles {
+            for x in h.join().unwrap() {
+                assert!(seen.insert(x), "{} popped twice", x);
+            }
+        }
+        while let Some(x) = queue.pop() {
+            assert!(seen.insert(x), "{} popped twice", x);
+        }
+        assert_eq!(seen.len(), THREADS * PER_THREAD);
+        let (batches, combined) = queue.stats();
+        assert!(batches <= combined);
+    }
+
+    #[test]
+    fn per_producer_order_is_preserved() {
+        const THREADS: usize = 4;
+        const PER_THREAD: u64 = 3000;
+
+        let queue = Arc::new(FcQueue::new());
+        let handles: Vec<_> = (0..THREADS as u64)
+            .map(|t| {
+                let queue = Arc::clone(&queue);
+                thread::spawn(move || {
+                    for i in 0..PER_THREAD {
+                        queue.push((t, i));
+                    }
+                })
+            })
+            .collect();
+        for h in handles {
+            h.join().unwrap();
+        }
+        assert_eq!(queue.len(), THREADS * PER_THREAD as usize);
+        let mut next = [0; THREADS];
+        while let Some((t, i)) = queue.pop() {
+            assert_eq!(next[t as usize], i);
+            next[t as usize] += 1;
+        }
+        assert!(next.iter().all(|&n| n == PER_THREAD));
+    }
+
+    #[test]
+    fn leftover_elements_are_dropped() {
+        let elem = Arc::new(());
+        let queue = FcQueue::new();
+        for _ in 0..3 {
+            queue.push(Arc::clone(&elem));
+        }
+        drop(queue.pop());
+        drop(queue);
+        assert_eq!(Arc::strong_count(&elem), 1);
+    }
+}
@@ -0,0 +1,300 @@
+// 用两个栈拼成的间隙缓冲区(gap buffer)，文本编辑器里光标附近的编辑都是O(1)
+// 光标把序列分成两段:
+// - before: 光标左边的元素，栈顶是紧挨着光标的那个
+// - after: 光标右边的元素，栈顶同样是紧挨着光标的那个
+// 插入就是push到before，删除就是从after(或before)pop；
+// 光标左右移动是把一个节点从一个栈搬到另一个栈(pop_boxed_node/push_boxed_node)，不重新分配
+// 代价是跳到远处要一步一步搬，O(距离)；顺序遍历时before要倒过来读，需要O(光标位置)的临时空间
+// 两个栈都是simple_stack_2::List
+
+use std::fmt;
+
+use crate::simple_stack_2::List;
+
+pub struct GapBuffer<T> {
+    before: List<T>,
+    after: List<T>,
+    before_len: usize,
+    after_len: usize,
+}
+
+impl<T> GapBuffer<T> {
+    pub fn new() -> Self {
+        GapBuffer {
+            before: List::new(),
+            after: List::new(),
+            before_len: 0,
+            after_len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.before_len + self.after_len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // 光标前面有几个元素，0表示在最开头
+    pub fn cursor(&self) -> usize {
+        self.before_len
+    }
+
+    // 插入到光标处，光标停在新元素后面
+    pub fn insert(&mut self, elem: T) {
+        self.before.push(elem);
+        self.before_len += 1;
+    }
+
+    // 删除光标后面的元素(Delete键)
+    pub fn delete(&mut self) -> Option<T> {
+        let elem = self.after.pop()?;
+        self.after_len -= 1;
+        Some(elem)
+    }
+
+    // 删除光标前面的元素(Backspace键)
+    pub fn backspace(&mut self) -> Option<T> {
+        let elem = self.before.pop()?;
+        self.before_len -= 1;
+        Some(elem)
+    }
+
+    // 已经在最左边时返回false
+    pub fn move_left(&mut self) -> bool {
+        match self.before.pop_boxed_node() {
+            Some(node) => {
+                self.after.push_boxed_node(node);
+                self.before_len -= 1;
+                self.after_len += 1;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn move_right(&mut self) -> bool {
+        match self.after.pop_boxed_node() {
+            Some(node) => {
+                self.before.push_boxed_node(node);
+                self.after_len -= 1;
+                self.before_len += 1;
+                true
+            }
+            None => false,
+        }
+    }
+
+    // 把光标移到pos(前面有pos个元素)，O(|pos - cursor|)；pos超过长度时panic
+    pub fn move_to(&mut self, pos: usize) {
+        assert!(pos <= self.len(), "cursor position out of bounds");
+        while self.before_len > pos {
+            self.move_left();
+        }
+        while self.before_len < pos {
+            self.move_right();
+        }
+    }
+
+    // 光标两侧紧挨着的元素
+    pub fn before_cursor(&self) -> Option<&T> {
+        self.before.peek()
+    }
+
+    pub fn after_cursor(&self) -> Option<&T> {
+        self.after.peek()
+    }
+
+    pub fn before_cursor_mut(&mut self) -> Option<&mut T> {
+        self.before.peek_mut()
+    }
+
+    pub fn after_cursor_mut(&mut self) -> Option<&mut T> {
+        self.after.peek_mut()
+    }
+
+    pub fn clear(&mut self) {
+        self.before = List::new();
+        self.after = List::new();
+        self.before_len = 0;
+        self.after_len = 0;
+    }
+
+    // 从头到尾顺序遍历；before要倒过来读，先把它的引用收集起来
+    pub fn iter(&self) -> impl Iterator<Item = &T> + '_ {
+        let mut left: Vec<&T> = Vec::with_capacity(self.before_len);
+        left.extend(self.before.iter());
+        left.into_iter().rev().chain(self.after.iter())
+    }
+
+    pub fn into_vec(mut self) -> Vec<T> {
+        let mut out = Vec::with_capacity(self.len());
+        while let Some(elem) = self.before.pop() {
+            out.push(elem);
+        }
+        out.reverse();
+        while let Some(elem) = self.after.pop() {
+            out.push(elem);
+        }
+        out
+    }
+}
+
+impl<T> Default for GapBuffer<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// 逐个插入，光标停在末尾
+impl<T> Extend<T> for GapBuffer<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for elem in iter {
+            self.insert(elem);
+        }
+    }
+}
+
+impl<T> FromIterator<T> for GapBuffer<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut buffer = GapBuffer::new();
+        buffer.extend(iter);
+        buffer
+    }
+}
+
+// 光标位置不参与比较
+impl<T: PartialEq> PartialEq for GapBuffer<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Eq> Eq for GapBuffer<T> {}
+
+impl<T: fmt::Debug> fmt::Debug for GapBuffer<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GapBuffer")
+            .field("cursor", &self.cursor())
+            .field("elems", &self.iter().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+// 文本编辑的常见用法，光标用'|'标出
+impl fmt::Display for GapBuffer<char> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, c) in self.iter().enumerate() {
+            if i == self.before_len {
+                f.write_str("|")?;
+            }
+            write!(f, "{}", c)?;
+        }
+        if self.after_len == 0 {
+            f.write_str("|")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::XorShift;
+    use std::rc::Rc;
+
+    #[test]
+    fn editing_session() {
+        let mut text: GapBuffer<char> = "helo".chars().collect();
+        assert_eq!(text.to_string(), "helo|");
+        text.move_left();
+        text.insert('l');
+        assert_eq!(text.to_string(), "hell|o");
+        text.move_to(0);
+        text.insert('>');
+        text.insert(' ');
+        assert_eq!(text.to_string(), "> |hello");
+        assert_eq!(text.delete(), Some('h'));
+        text.insert('H');
+        assert_eq!(text.backspace(), Some('H'));
+        assert_eq!(text.backspace(), Some(' '));
+        assert_eq!(text.to_string(), ">|ello");
+        assert_eq!((text.cursor(), text.len()), (1, 5));
+    }
+
+    #[test]
+    fn moves_stop_at_the_ends() {
+        let mut buffer: GapBuffer<i32> = (0..3).collect();
+        assert!(!buffer.move_right());
+        assert_eq!(buffer.delete(), None);
+        assert!(buffer.move_left() && buffer.move_left() && buffer.move_left());
+        assert!(!buffer.move_left());
+        assert_eq!(buffer.backspace(), None);
+        assert_eq!(buffer.before_cursor(), None);
+        assert_eq!(buffer.after_cursor(), Some(&0));
+        *buffer.after_cursor_mut().unwrap() = 10;
+        buffer.move_right();
+        *buffer.before_cursor_mut().unwrap() += 1;
+        assert_eq!(buffer.iter().copied().collect::<Vec<_>>(), vec![11, 1, 2]);
+        assert_eq!(buffer.into_vec(), vec![11, 1, 2]);
+    }
+
+    #[test]
+    #[should_panic(expected = "cursor position out of bounds")]
+    fn move_past_end_panics() {
+        let mut buffer: GapBuffer<u8> = GapBuffer::new();
+        buffer.move_to(1);
+    }
+
+    #[test]
+    fn matches_vec_model() {
+        let mut rng = XorShift::new(693);
+        let mut buffer = GapBuffer::new();
+        let mut model = Vec::new();
+        let mut cursor = 0;
+        for i in 0..2000u32 {
+            match rng.next_u64() % 6 {
+                0 | 1 => {
+                    buffer.insert(i);
+                    model.insert(cursor, i);
+                    cursor += 1;
+                }
+                2 => {
+                    let expected = (cursor < model.len()).then(|| model.remove(cursor));
+                    assert_eq!(buffer.delete(), expected);
+                }
+                3 => {
+                    let expected = (cursor > 0).then(|| {
+                        cursor -= 1;
+                        model.remove(cursor)
+                    });
+                    assert_eq!(buffer.backspace(), expected);
+                }
+                _ => {
+                    cursor = rng.next_u64() as usize % (model.len() + 1);
+                    buffer.move_to(cursor);
+                }
+            }
+            assert_eq!(buffer.cursor(), cursor);
+            assert_eq!(buffer.len(), model.len());
+        }
+        assert_eq!(buffer.iter().copied().collect::<Vec<_>>(), model);
+        assert_eq!(buffer, model.into_iter().collect());
+    }
+
+    #[test]
+    fn elements_dropped_exactly_once() {
+        let elem = Rc::new(());
+        let mut buffer: GapBuffer<_> = (0..4).map(|_| Rc::clone(&elem)).collect();
+        buffer.move_to(2);
+        drop(buffer.delete());
+        assert_eq!(Rc::strong_count(&elem), 4);
+        buffer.clear();
+        assert_eq!(Rc::strong_count(&elem), 1);
+        buffer.extend([Rc::clone(&elem), Rc::clone(&elem)]);
+        buffer.move_left();
+        drop(buffer);
+        assert_eq!(Rc::strong_count(&elem), 1);
+    }
+}
@@ -0,0 +1,354 @@
+// 用GhostCell(品牌生命周期)实现的安全双向链表
+// simple_deque_1用Rc<RefCell<Node>>，每次访问节点都要在运行时检查借用标志，还可能panic；
+// simple_deque_2为了性能直接上裸指针和unsafe。GhostCell走第三条路:
+// - 把"谁能访问节点"的权限从节点本身挪到一个独立的GhostToken上
+// - 每个GhostToken带一个独一无二的品牌生命周期'id，只能打开同一品牌的GhostCell
+// - &GhostToken可以同时读所有节点，&mut GhostToken独占地写任意一个节点，
+//   整个借用检查都在编译期完成，没有任何运行时借用标志
+// 链表的公开API里没有unsafe，unsafe只在GhostCell这个原语内部
+// 节点之间: next是Rc(拥有后继)，prev是Weak，没有引用环，drop时不需要token
+
+use std::cell::UnsafeCell;
+use std::fmt;
+use std::marker::PhantomData;
+use std::rc::{Rc, Weak};
+
+// 'id在这里既不能变长也不能变短(不变)，不同品牌之间无法互相转换
+type Brand<'id> = PhantomData<fn(&'id ()) -> &'id ()>;
+
+pub struct GhostToken<'id> {
+    _brand: Brand<'id>,
+}
+
+impl GhostToken<'_> {
+    // 每次调用都在闭包里产生一个全新的品牌，闭包外拿不到这个token
+    // 例如: GhostToken::scope(|mut token| { let list = GhostList::new(); ... })
+    pub fn scope<R>(f: impl for<'new> FnOnce(GhostToken<'new>) -> R) -> R {
+        f(GhostToken {
+            _brand: PhantomData,
+        })
+    }
+}
+
+#[repr(transparent)]
+pub struct GhostCell<'id, T: ?Sized> {
+    _brand: Brand<'id>,
+    value: UnsafeCell<T>,
+}
+
+impl<'id, T> GhostCell<'id, T> {
+    pub const fn new(value: T) -> Self {
+        GhostCell {
+            _brand: PhantomData,
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.value.into_inner()
+    }
+}
+
+impl<'id, T: ?Sized> GhostCell<'id, T> {
+    // 持有&token时可以同时读任意多个同品牌的cell
+    pub fn borrow<'a>(&'a self, _token: &'a GhostToken<'id>) -> &'a T {
+        // SAFETY: 同品牌的token只有一个，&token存在期间不可能有人拿着&mut token去写
+        unsafe { &*self.value.get() }
+    }
+
+    // 持有&mut token时独占地写一个cell，期间token和所有同品牌cell都不能再被访问
+    pub fn borrow_mut<'a>(&'a self, _token: &'a mut GhostToken<'id>) -> &'a mut T {
+        // SAFETY: &mut token保证了同一时刻只有这一个借用
+        unsafe { &mut *self.value.get() }
+    }
+
+    pub fn get_mut(&mut self) -> &mut T {
+        self.value.get_mut()
+    }
+
+    // 不经过token拿到裸指针，解引用由调用者保证没有别的借用(例如独占地析构整条链表时)
+    pub(crate) fn as_ptr(&self) -> *mut T {
+        self.value.get()
+    }
+}
+
+// 和RwLock的条件一样: 通过共享的cell可能在别的线程拿到&T或&mut T
+unsafe impl<T: ?Sized + Send> Send for GhostCell<'_, T> {}
+unsafe impl<T: ?Sized + Send + Sync> Sync for GhostCell<'_, T> {}
+
+type NodeRef<'id, T> = Rc<GhostCell<'id, Node<'id, T>>>;
+
+struct Node<'id, T> {
+    elem: T,
+    prev: Option<Weak<GhostCell<'id, Node<'id, T>>>>,
+    next: Option<NodeRef<'id, T>>,
+}
+
+// 所有读写操作都需要传入同品牌的token
+pub struct GhostList<'id, T> {
+    head: Option<NodeRef<'id, T>>,
+    tail: Option<NodeRef<'id, T>>,
+    len: usize,
+}
+
+impl<'id, T> GhostList<'id, T> {
+    pub fn new() -> Self {
+        GhostList {
+            head: None,
+            tail: None,
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn push_front(&mut self, elem: T, token: &mut GhostToken<'id>) {
+        let old = self.head.take();
+        let new = Rc::new(GhostCell::new(Node {
+            elem,
+            prev: None,
+            next: old.clone(),
+        }));
+        match old {
+            Some(old) => old.borrow_mut(token).prev = Some(Rc::downgrade(&new)),
+            None => self.tail = Some(Rc::clone(&new)),
+        }
+        self.head = Some(new);
+        self.len += 1;
+    }
+
+    pub fn push_back(&mut self, elem: T, token: &mut GhostToken<'id>) {
+        let new = Rc::new(GhostCell::new(Node {
+            elem,
+            prev: self.tail.as_ref().map(Rc::downgrade),
+            next: None,
+        }));
+        match self.tail.take() {
+            Some(old) => old.borrow_mut(token).next = Some(Rc::clone(&new)),
+            None => self.head = Some(Rc::clone(&new)),
+        }
+        self.tail = Some(new);
+        self.len += 1;
+    }
+
+    pub fn pop_front(&mut self, token: &mut GhostToken<'id>) -> Option<T> {
+        let old = self.head.take()?;
+        match old.borrow_mut(token).next.take() {
+            Some(next) => {
+                next.borrow_mut(token).prev = None;
+                self.head = Some(next);
+            }
+            None => self.tail = None,
+        }
+        self.len -= 1;
+        Some(Self::into_elem(old))
+    }
+
+    pub fn pop_back(&mut self, token: &mut GhostToken<'id>) -> Option<T> {
+        let old = self.tail.take()?;
+        match old.borrow_mut(token).prev.take().and_then(|prev| prev.upgrade()) {
+            Some(prev) => {
+                // 前驱的next是old剩下的那个强引用，取出来丢掉
+                drop(prev.borrow_mut(token).next.take());
+                self.tail = Some(prev);
+            }
+            None => self.head = None,
+        }
+        self.len -= 1;
+        Some(Self::into_elem(old))
+    }
+
+    // 节点摘下之后只剩调用者手里这一个强引用
+    fn into_elem(node: NodeRef<'id, T>) -> T {
+        match Rc::try_unwrap(node) {
+            Ok(cell) => cell.into_inner().elem,
+            Err(_) => unreachable!("unlinked node is uniquely owned"),
+        }
+    }
+
+    pub fn front<'a>(&'a self, token: &'a GhostToken<'id>) -> Option<&'a T> {
+        self.head.as_ref().map(|node| &node.borrow(token).elem)
+    }
+
+    pub fn back<'a>(&'a self, token: &'a GhostToken<'id>) -> Option<&'a T> {
+        self.tail.as_ref().map(|node| &node.borrow(token).elem)
+    }
+
+    // 只需要&self: 写权限来自&mut token
+    pub fn front_mut<'a>(&'a self, token: &'a mut GhostToken<'id>) -> Option<&'a mut T> {
+        self.head.as_ref().map(|node| &mut node.borrow_mut(token).elem)
+    }
+
+    pub fn back_mut<'a>(&'a self, token: &'a mut GhostToken<'id>) -> Option<&'a mut T> {
+        self.tail.as_ref().map(|node| &mut node.borrow_mut(token).elem)
+    }
+
+    pub fn iter<'a>(&'a self, token: &'a GhostToken<'id>) -> Iter<'a, 'id, T> {
+        Iter {
+            next: self.head.as_ref(),
+            len: self.len,
+            token,
+        }
+    }
+
+    // &mut token一次只能借出一个节点，所以可变遍历写成内部迭代
+    pub fn for_each_mut(&self, token: &mut GhostToken<'id>, mut f: impl FnMut(&mut T)) {
+        let mut cur = self.head.clone();
+        while let Some(node) = cur {
+            let node = node.borrow_mut(token);
+            f(&mut node.elem);
+            cur = node.next.clone();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        // 先去掉tail多出来的强引用，之后每个节点都只被前驱持有，可以逐个拆开
+        self.tail = None;
+        let mut cur = self.head.take();
+        while let Some(node) = cur {
+            cur = match Rc::try_unwrap(node) {
+                Ok(cell) => cell.into_inner().next,
+                Err(_) => unreachable!("nodes are only owned by their predecessor"),
+            };
+        }
+        self.len = 0;
+    }
+}
+
+impl<T> Drop for GhostList<'_, T> {
+    fn drop(&mut self) {
+        // 默认的递归drop在长链表上会爆栈
+        self.clear();
+    }
+}
+
+impl<T> Default for GhostList<'_, T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> fmt::Debug for GhostList<'_, T> {
+    // 没有token读不到元素，只打印长度
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GhostList").field("len", &self.len).finish()
+    }
+}
+
+pub struct Iter<'a, 'id, T> {
+    next: Option<&'a NodeRef<'id, T>>,
+    len: usize,
+    token: &'a GhostToken<'id>,
+}
+
+impl<'a, T> Iterator for Iter<'a, '_, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        self.next.map(|node| {
+            let node = node.borrow(self.token);
+            self.next = node.next.as_ref();
+            self.len -= 1;
+            &node.elem
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, '_, T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn deque_operations() {
+        GhostToken::scope(|mut token| {
+            let mut list = GhostList::new();
+            assert_eq!(list.pop_front(&mut token), None);
+            list.push_back(2, &mut token);
+            list.push_back(3, &mut token);
+            list.push_front(1, &mut token);
+            assert_eq!(list.len(), 3);
+            assert_eq!(list.iter(&token).copied().collect::<Vec<_>>(), vec![1, 2, 3]);
+            assert_eq!(list.front(&token), Some(&1));
+            assert_eq!(list.back(&token), Some(&3));
+            assert_eq!(list.pop_back(&mut token), Some(3));
+            assert_eq!(list.pop_front(&mut token), Some(1));
+            assert_eq!(list.pop_back(&mut token), Some(2));
+            assert!(list.is_empty());
+            assert_eq!(list.pop_back(&mut token), None);
+        });
+    }
+
+    #[test]
+    fn mutate_through_shared_list() {
+        GhostToken::scope(|mut token| {
+            let mut list = GhostList::new();
+            for i in 0..5 {
+                list.push_back(i, &mut token);
+            }
+            // 两个共享引用指向同一条链表，写权限完全由token决定
+            let a = &list;
+            let b = &list;
+            *a.front_mut(&mut token).unwrap() = 10;
+            *b.back_mut(&mut token).unwrap() = 40;
+            a.for_each_mut(&mut token, |x| *x += 1);
+            let r1 = a.iter(&token);
+            let r2 = b.iter(&token);
+            assert!(r1.eq(r2));
+            assert_eq!(b.iter(&token).copied().collect::<Vec<_>>(), vec![11, 2, 3, 4, 41]);
+        });
+    }
+
+    #[test]
+    fn same_token_many_lists() {
+        GhostToken::scope(|mut token| {
+            let mut evens = GhostList::new();
+            let mut odds = GhostList::new();
+            for i in 0..10 {
+                match i % 2 {
+                    0 => evens.push_back(i, &mut token),
+                    _ => odds.push_front(i, &mut token),
+                }
+            }
+            while let Some(x) = odds.pop_back(&mut token) {
+                evens.push_back(x, &mut token);
+            }
+            let v: Vec<_> = evens.iter(&token).copied().collect();
+            assert_eq!(v, vec![0, 2, 4, 6, 8, 1, 3, 5, 7, 9]);
+        });
+    }
+
+    #[test]
+    fn long_list_drops_iteratively() {
+        GhostToken::scope(|mut token| {
+            let mut list = GhostList::new();
+            for i in 0..100_000 {
+                list.push_back(i, &mut token);
+            }
+            drop(list);
+        });
+    }
+
+    #[test]
+    fn token_moves_across_threads() {
+        GhostToken::scope(|mut token| {
+            let cell = GhostCell::new(vec![1]);
+            thread::scope(|s| {
+                s.spawn(|| cell.borrow_mut(&mut token).push(2));
+            });
+            assert_eq!(cell.borrow(&token), &vec![1, 2]);
+        });
+    }
+}
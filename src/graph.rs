@@ -0,0 +1,356 @@
+// 邻接表表示的有向图，每个顶点的出边存在一条simple_stack_2::List里
+// 顶点放在slab里，VertexId带代数(同slab_list的Handle)，删掉的顶点的id不会被误认成后来复用槽位的顶点
+// - 加边: push到起点的边表头部，O(1)，所以neighbors按加边的逆序给出
+// - 删边: 在起点的边表里找第一条指向终点的边摘掉，O(出度)
+// - 删顶点: 丢掉它自己的边表，再扫一遍所有边表删掉指向它的边，O(V + E)
+// bfs/dfs都是迭代器，按需推进，visited用顶点槽位下标索引
+
+use std::collections::VecDeque;
+use std::fmt;
+
+use crate::simple_stack_2::List;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct VertexId {
+    index: usize,
+    generation: u64,
+}
+
+struct Edge<E> {
+    to: VertexId,
+    weight: E,
+}
+
+struct Vertex<N, E> {
+    value: N,
+    edges: List<Edge<E>>,
+}
+
+struct Slot<N, E> {
+    generation: u64,
+    vertex: Option<Vertex<N, E>>,
+}
+
+pub struct Graph<N, E> {
+    slots: Vec<Slot<N, E>>,
+    free: Vec<usize>,
+    vertices: usize,
+    edges: usize,
+}
+
+impl<N, E> Graph<N, E> {
+    pub fn new() -> Self {
+        Graph {
+            slots: Vec::new(),
+            free: Vec::new(),
+            vertices: 0,
+            edges: 0,
+        }
+    }
+
+    pub fn vertex_count(&self) -> usize {
+        self.vertices
+    }
+
+    pub fn edge_count(&self) -> usize {
+        self.edges
+    }
+
+    pub fn add_vertex(&mut self, value: N) -> VertexId {
+        let vertex = Some(Vertex {
+            value,
+            edges: List::new(),
+        });
+        let index = match self.free.pop() {
+            Some(index) => {
+                self.slots[index].vertex = vertex;
+                index
+            }
+            None => {
+                self.slots.push(Slot { generation: 0, vertex });
+                self.slots.len() - 1
+            }
+        };
+        self.vertices += 1;
+        VertexId {
+            index,
+            generation: self.slots[index].generation,
+        }
+    }
+
+    // 连同所有出边和入边一起删掉
+    pub fn remove_vertex(&mut self, id: VertexId) -> Option<N> {
+        let vertex = self.vertex_entry_mut(id)?;
+        let out_degree = vertex.edges.iter().count();
+        let slot = &mut self.slots[id.index];
+        let vertex = slot.vertex.take().unwrap();
+        slot.generation += 1;
+        self.free.push(id.index);
+        self.vertices -= 1;
+        self.edges -= out_degree;
+        let mut removed = 0;
+        for slot in &mut self.slots {
+            if let Some(other) = slot.vertex.as_mut() {
+                other.edges.retain(|edge| {
+                    let keep = edge.to != id;
+                    removed += !keep as usize;
+                    keep
+                });
+            }
+        }
+        self.edges -= removed;
+        Some(vertex.value)
+    }
+
+    pub fn contains_vertex(&self, id: VertexId) -> bool {
+        self.vertex_entry(id).is_some()
+    }
+
+    pub fn vertex(&self, id: VertexId) -> Option<&N> {
+        self.vertex_entry(id).map(|v| &v.value)
+    }
+
+    pub fn vertex_mut(&mut self, id: VertexId) -> Option<&mut N> {
+        self.vertex_entry_mut(id).map(|v| &mut v.value)
+    }
+
+    // 加一条from到to的边，允许重边和自环；任一顶点不存在时panic
+    pub fn add_edge(&mut self, from: VertexId, to: VertexId, weight: E) {
+        assert!(self.contains_vertex(to), "vertex does not exist");
+        let vertex = self.vertex_entry_mut(from).expect("vertex does not exist");
+        vertex.edges.push(Edge { to, weight });
+        self.edges += 1;
+    }
+
+    // 删掉最近加入的一条from到to的边
+    pub fn remove_edge(&mut self, from: VertexId, to: VertexId) -> Option<E> {
+        let edge = self.vertex_entry_mut(from)?.edges.remove_first_by(|edge| edge.to == to)?;
+        self.edges -= 1;
+        Some(edge.weight)
+    }
+
+    // 最近加入的一条from到to的边
+    pub fn edge(&self, from: VertexId, to: VertexId) -> Option<&E> {
+        self.vertex_entry(from)?
+            .edges
+            .iter()
+            .find(|edge| edge.to == to)
+            .map(|edge| &edge.weight)
+    }
+
+    pub fn contains_edge(&self, from: VertexId, to: VertexId) -> bool {
+        self.edge(from, to).is_some()
+    }
+
+    // 出边的(终点, 权重)，最近加入的在前；顶点不存在时为空
+    pub fn neighbors(&self, id: VertexId) -> impl Iterator<Item = (VertexId, &E)> + '_ {
+        self.vertex_entry(id)
+            .into_iter()
+            .flat_map(|v| v.edges.iter())
+            .map(|edge| (edge.to, &edge.weight))
+    }
+
+    pub fn vertices(&self) -> impl Iterator<Item = (VertexId, &N)> + '_ {
+        self.slots.iter().enumerate().filter_map(|(index, slot)| {
+            let id = VertexId {
+                index,
+                generation: slot.generation,
+            };
+            slot.vertex.as_ref().map(|v| (id, &v.value))
+        })
+    }
+
+    // 从start开始广度优先遍历能到达的顶点，start不存在时为空
+    pub fn bfs(&self, start: VertexId) -> Bfs<'_, N, E> {
+        let mut visited = vec![false; self.slots.len()];
+        let mut queue = VecDeque::new();
+        if self.contains_vertex(start) {
+            visited[start.index] = true;
+            queue.push_back(start);
+        }
+        Bfs {
+            graph: self,
+            visited,
+            queue,
+        }
+    }
+
+    // 从start开始深度优先(先序)遍历能到达的顶点，邻居按neighbors的顺序访问
+    pub fn dfs(&self, start: VertexId) -> Dfs<'_, N, E> {
+        let stack = if self.contains_vertex(start) { vec![start] } else { Vec::new() };
+        Dfs {
+            graph: self,
+            visited: vec![false; self.slots.len()],
+            stack,
+        }
+    }
+
+    fn vertex_entry(&self, id: VertexId) -> Option<&Vertex<N, E>> {
+        let slot = self.slots.get(id.index)?;
+        if slot.generation != id.generation {
+            return None;
+        }
+        slot.vertex.as_ref()
+    }
+
+    fn vertex_entry_mut(&mut self, id: VertexId) -> Option<&mut Vertex<N, E>> {
+        let slot = self.slots.get_mut(id.index)?;
+        if slot.generation != id.generation {
+            return None;
+        }
+        slot.vertex.as_mut()
+    }
+}
+
+impl<N, E> Default for Graph<N, E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<N: fmt::Debug, E: fmt::Debug> fmt::Debug for Graph<N, E> {
+    // 每个顶点一行: 值 -> [(终点下标, 权重), ...]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(self.vertices().map(|(id, value)| {
+                let edges: Vec<_> = self.neighbors(id).map(|(to, w)| (to.index, w)).collect();
+                ((id.index, value), edges)
+            }))
+            .finish()
+    }
+}
+
+pub struct Bfs<'a, N, E> {
+    graph: &'a Graph<N, E>,
+    visited: Vec<bool>,
+    queue: VecDeque<VertexId>,
+}
+
+impl<'a, N, E> Iterator for Bfs<'a, N, E> {
+    type Item = (VertexId, &'a N);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let id = self.queue.pop_front()?;
+        for (to, _) in self.graph.neighbors(id) {
+            if !self.visited[to.index] {
+                self.visited[to.index] = true;
+                self.queue.push_back(to);
+            }
+        }
+        Some((id, self.graph.vertex(id).unwrap()))
+    }
+}
+
+pub struct Dfs<'a, N, E> {
+    graph: &'a Graph<N, E>,
+    visited: Vec<bool>,
+    stack: Vec<VertexId>,
+}
+
+impl<'a, N, E> Iterator for Dfs<'a, N, E> {
+    type Item = (VertexId, &'a N);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // 栈里可能有重复的顶点，出栈时才标记visited
+        while let Some(id) = self.stack.pop() {
+            if self.visited[id.index] {
+                continue;
+            }
+            self.visited[id.index] = true;
+            // 逆序压栈，让第一个邻居最先被访问
+            let neighbors: Vec<VertexId> = self.graph.neighbors(id).map(|(to, _)| to).collect();
+            self.stack
+                .extend(neighbors.into_iter().rev().filter(|to| !self.visited[to.index]));
+            return Some((id, self.graph.vertex(id).unwrap()));
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a -> b -> d, a -> c -> d, d -> a
+    fn diamond() -> (Graph<char, u32>, Vec<VertexId>) {
+        let mut g = Graph::new();
+        let ids: Vec<VertexId> = "abcd".chars().map(|c| g.add_vertex(c)).collect();
+        g.add_edge(ids[0], ids[2], 2);
+        g.add_edge(ids[0], ids[1], 1);
+        g.add_edge(ids[1], ids[3], 3);
+        g.add_edge(ids[2], ids[3], 4);
+        g.add_edge(ids[3], ids[0], 5);
+        (g, ids)
+    }
+
+    fn names<'a>(iter: impl Iterator<Item = (VertexId, &'a char)>) -> String {
+        iter.map(|(_, c)| *c).collect()
+    }
+
+    #[test]
+    fn edges_and_neighbors() {
+        let (mut g, ids) = diamond();
+        assert_eq!((g.vertex_count(), g.edge_count()), (4, 5));
+        assert_eq!(g.neighbors(ids[0]).map(|(to, w)| (to, *w)).collect::<Vec<_>>(), vec![(ids[1], 1), (ids[2], 2)]);
+        assert_eq!(g.edge(ids[2], ids[3]), Some(&4));
+        assert!(!g.contains_edge(ids[3], ids[2]));
+        g.add_edge(ids[0], ids[1], 10);
+        assert_eq!(g.edge(ids[0], ids[1]), Some(&10));
+        assert_eq!(g.remove_edge(ids[0], ids[1]), Some(10));
+        assert_eq!(g.remove_edge(ids[0], ids[1]), Some(1));
+        assert_eq!(g.remove_edge(ids[0], ids[1]), None);
+        assert_eq!(g.edge_count(), 4);
+        *g.vertex_mut(ids[3]).unwrap() = 'D';
+        assert_eq!(names(g.vertices()), "abcD");
+    }
+
+    #[test]
+    fn remove_vertex_drops_incoming_edges() {
+        let (mut g, ids) = diamond();
+        assert_eq!(g.remove_vertex(ids[3]), Some('d'));
+        assert_eq!(g.remove_vertex(ids[3]), None);
+        assert_eq!((g.vertex_count(), g.edge_count()), (3, 2));
+        assert_eq!(g.neighbors(ids[1]).count(), 0);
+        // 复用槽位的新顶点和旧id对不上
+        let e = g.add_vertex('e');
+        assert_eq!(e.index, ids[3].index);
+        assert!(!g.contains_vertex(ids[3]));
+        assert_eq!(g.vertex(ids[3]), None);
+        assert_eq!(g.neighbors(ids[3]).count(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "vertex does not exist")]
+    fn edge_to_removed_vertex_panics() {
+        let (mut g, ids) = diamond();
+        g.remove_vertex(ids[1]);
+        g.add_edge(ids[0], ids[1], 0);
+    }
+
+    #[test]
+    fn bfs_and_dfs() {
+        let (mut g, ids) = diamond();
+        let e = g.add_vertex('e');
+        g.add_edge(ids[1], e, 6);
+        // a的邻居顺序是b, c
+        assert_eq!(names(g.bfs(ids[0])), "abced");
+        assert_eq!(names(g.dfs(ids[0])), "abedc");
+        assert_eq!(names(g.bfs(ids[2])), "cdabe");
+        assert_eq!(names(g.dfs(e)), "e");
+        g.remove_vertex(e);
+        assert_eq!(g.bfs(e).count(), 0);
+        assert_eq!(format!("{:?}", g.bfs(ids[3]).map(|(_, c)| c).collect::<Vec<_>>()), "['d', 'a', 'b', 'c']");
+    }
+
+    #[test]
+    fn long_chain_traversal() {
+        let mut g = Graph::new();
+        let ids: Vec<VertexId> = (0..10_000).map(|i| g.add_vertex(i)).collect();
+        for w in ids.windows(2) {
+            g.add_edge(w[0], w[1], ());
+        }
+        assert_eq!(g.dfs(ids[0]).count(), 10_000);
+        assert!(g.bfs(ids[0]).map(|(_, v)| *v).eq(0..10_000));
+        assert_eq!(g.dfs(ids[5000]).count(), 5000);
+    }
+}
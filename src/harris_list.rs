@@ -0,0 +1,400 @@
+// Harris无锁有序链表(Harris 2001)，按升序保存不重复的元素，提供并发的insert/remove/contains
+// - 节点next指针的最低位是删除标记，被标记的next不会再被修改
+// - 插入: 找到第一个>=目标的节点curr和它的前驱，新节点指向curr后CAS前驱的next
+// - 删除: 先CAS给curr.next打标记(逻辑删除，标记成功的线程才算删除了它)，
+//   再CAS把前驱的next从curr改成curr.next(物理删除)；失败就重新查找一次，查找时会顺手摘掉路过的带标记节点
+// 节点由摘下它的那次CAS成功的线程retire，回收策略R可以选epoch或危险指针，默认用epoch
+// Harris原始算法一次CAS可以摘掉一串连续的带标记节点，但危险指针下这串节点没有被保护，
+// 所以这里按Michael(2002)的做法每次只摘一个，查找时用三个槽轮换保护前驱、当前和后继，
+// 每读到一个新指针都要登记后再确认它仍然挂在链表上，确认失败就从头查找
+
+use std::borrow::Borrow;
+use std::fmt;
+use std::marker::PhantomData;
+use std::ptr;
+
+use crate::instrument::mutation;
+use crate::reclaim::{Epoch, Reclaim, ReclaimGuard};
+use crate::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+
+// 和concurrent_skip_list一样，标记和摘除之间的正确性依赖统一的先后顺序，全部使用SeqCst
+const SC: Ordering = Ordering::SeqCst;
+
+struct Node<T> {
+    elem: T,
+    next: AtomicPtr<Node<T>>,
+}
+
+fn is_marked<T>(p: *mut T) -> bool {
+    p.addr() & 1 == 1
+}
+
+fn marked<T>(p: *mut T) -> *mut T {
+    p.map_addr(|a| a | 1)
+}
+
+fn unmarked<T>(p: *mut T) -> *mut T {
+    p.map_addr(|a| a & !1)
+}
+
+pub struct HarrisList<T, R: Reclaim = Epoch> {
+    head: AtomicPtr<Node<T>>,
+    len: AtomicUsize,
+    _boo: PhantomData<(Box<Node<T>>, R)>,
+}
+
+// 查找结果: prev是头指针或前驱节点的next，curr是第一个>=目标的未删除节点，next是它的后继
+// 三者都受guard保护，直到下一次查找
+struct Window<T> {
+    prev: *const AtomicPtr<Node<T>>,
+    curr: *mut Node<T>,
+    next: *mut Node<T>,
+}
+
+impl<T> HarrisList<T> {
+    pub fn new() -> Self {
+        Self::empty()
+    }
+}
+
+impl<T, R: Reclaim> HarrisList<T, R> {
+    // 用指定的回收策略创建，例如HarrisList::with_reclaim(Hazard)
+    pub fn with_reclaim(_reclaim: R) -> Self {
+        Self::empty()
+    }
+
+    fn empty() -> Self {
+        HarrisList {
+            head: AtomicPtr::new(ptr::null_mut()),
+            len: AtomicUsize::new(0),
+            _boo: PhantomData,
+        }
+    }
+
+    // 并发情况下只代表调用那一刻的近似值
+    pub fn len(&self) -> usize {
+        self.len.load(Ordering::Relaxed)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<T: Ord, R: Reclaim> HarrisList<T, R> {
+    // Michael的查找: 摘掉路过的带标记节点，停在第一个>=key的未删除节点上
+    // 返回它是否等于key
+    unsafe fn find<Q>(&self, key: &Q, guard: &R::Guard) -> (bool, Window<T>)
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        'retry: loop {
+            // 三个槽轮换使用，分别保护前驱、当前和后继
+            let (mut hp_prev, mut hp_curr, mut hp_next) = (2, 1, 0);
+            let mut prev: *const AtomicPtr<Node<T>> = &self.head;
+            // 头指针本身永远不带标记
+            let mut curr = guard.protect(hp_curr, &self.head);
+            loop {
+                if curr.is_null() {
+                    let next = ptr::null_mut();
+                    return (false, Window { prev, curr, next });
+                }
+                let next = (*curr).next.load(SC);
+                guard.protect_raw(hp_next, unmarked(next));
+                // curr.next没变且curr仍然挂在前驱上，说明登记next时它还没有被摘下
+                if (*curr).next.load(SC) != next || (*prev).load(SC) != curr {
+                    continue 'retry;
+                }
+                if !is_marked(next) {
+                    if (*curr).elem.borrow() >= key {
+                        let found = (*curr).elem.borrow() == key;
+                        return (found, Window { prev, curr, next });
+                    }
+                    prev = &(*curr).next;
+                    (hp_prev, hp_curr, hp_next) = (hp_curr, hp_next, hp_prev);
+                } else {
+                    // curr已被逻辑删除，从前驱上摘下；前驱自己被删除或者变了的话CAS会失败
+                    let next = unmarked(next);
+                    if (*prev).compare_exchange(curr, next, SC, SC).is_err() {
+                        continue 'retry;
+                    }
+                    guard.retire(curr);
+                    (hp_curr, hp_next) = (hp_next, hp_curr);
+                }
+                curr = unmarked(next);
+            }
+        }
+    }
+
+    pub fn contains<Q>(&self, key: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let guard = R::pin();
+        unsafe { self.find(key, &guard).0 }
+    }
+
+    // 元素已经存在时不做修改并返回false，传入的元素被丢弃
+    pub fn insert(&self, elem: T) -> bool {
+        let guard = R::pin();
+        let node = Box::into_raw(Box::new(Node {
+            elem,
+            next: AtomicPtr::new(ptr::null_mut()),
+        }));
+        unsafe {
+            loop {
+                let (found, window) = self.find(&(*node).elem, &guard);
+                if found {
+                    drop(Box::from_raw(node));
+                    return false;
+                }
+                // 节点还没有发布，直接写
+                (*node).next.store(window.curr, Ordering::Relaxed);
+                if (*window.prev).compare_exchange(window.curr, node, SC, SC).is_ok() {
+                    self.len.fetch_add(1, Ordering::Relaxed);
+                    mutation!("insert");
+                    return true;
+                }
+            }
+        }
+    }
+
+    // 返回是否由这次调用删除了key；元素可能还有其他线程在读，由回收策略延迟析构
+    pub fn remove<Q>(&self, key: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let guard = R::pin();
+        unsafe {
+            loop {
+                let (found, window) = self.find(key, &guard);
+                if !found {
+                    return false;
+                }
+                let curr = window.curr;
+                // 逻辑删除，失败说明next变了或者别的线程抢先打了标记，重新查找
+                if (*curr).next.compare_exchange(window.next, marked(window.next), SC, SC).is_err() {
+                    continue;
+                }
+                self.len.fetch_sub(1, Ordering::Relaxed);
+                mutation!("remove");
+                // 物理删除，失败就交给查找去摘
+                if (*window.prev).compare_exchange(curr, window.next, SC, SC).is_ok() {
+                    guard.retire(curr);
+                } else {
+                    self.find(key, &guard);
+                }
+                return true;
+            }
+        }
+    }
+
+    // 按升序访问当前所有元素，遍历期间其他线程的修改可能看得到也可能看不到
+    // 当前节点被删除时没法再信任它的next，从头重新走，跳过不大于上一个访问过的元素，
+    // 所以每个元素最多访问一次
+    pub fn for_each(&self, mut f: impl FnMut(&T)) {
+        let guard = R::pin();
+        let (mut hp_curr, mut hp_next) = (0, 1);
+        // 上一个访问过的节点，一直保护在槽2里，重新开始时和它比较
+        let mut last: *mut Node<T> = ptr::null_mut();
+        'restart: loop {
+            let mut curr = guard.protect(hp_curr, &self.head);
+            while !curr.is_null() {
+                unsafe {
+                    let next = (*curr).next.load(SC);
+                    if is_marked(next) {
+                        continue 'restart;
+                    }
+                    guard.protect_raw(hp_next, next);
+                    // 登记之后curr仍未被标记，说明它还挂在链表上，next也就还没有被摘下
+                    if (*curr).next.load(SC) != next {
+                        continue;
+                    }
+                    if last.is_null() || (*curr).elem > (*last).elem {
+                        f(&(*curr).elem);
+                        guard.protect_raw(2, curr);
+                        last = curr;
+                    }
+                    (hp_curr, hp_next) = (hp_next, hp_curr);
+                    curr = next;
+                }
+            }
+            return;
+        }
+    }
+}
+
+impl<T, R: Reclaim> Default for HarrisList<T, R> {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+impl<T, R: Reclaim> Drop for HarrisList<T, R> {
+    fn drop(&mut self) {
+        // &mut self保证没有并发访问；已经摘下的节点都交给了回收策略，
+        // 链表上剩下的(包括标记了但还没来得及摘下的)都归我们释放
+        let mut curr = self.head.load(Ordering::Relaxed);
+        while !curr.is_null() {
+            unsafe {
+                let node = Box::from_raw(curr);
+                curr = unmarked(node.next.load(Ordering::Relaxed));
+            }
+        }
+    }
+}
+
+impl<T: Ord + fmt::Debug, R: Reclaim> fmt::Debug for HarrisList<T, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut set = f.debug_set();
+        self.for_each(|elem| {
+            set.entry(elem);
+        });
+        set.finish()
+    }
+}
+
+// 节点可能在任意线程上被回收策略析构，元素也会被多个线程同时读取
+unsafe impl<T: Send + Sync, R: Reclaim> Send for HarrisList<T, R> {}
+unsafe impl<T: Send + Sync, R: Reclaim> Sync for HarrisList<T, R> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reclaim::Hazard;
+    use crate::rng::XorShift;
+    use std::collections::BTreeSet;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::Arc;
+    use std::thread;
+
+    fn elems<T: Ord + Clone, R: Reclaim>(list: &HarrisList<T, R>) -> Vec<T> {
+        let mut out = Vec::new();
+        list.for_each(|x| out.push(x.clone()));
+        out
+    }
+
+    fn matches_btreeset<R: Reclaim>(reclaim: R) {
+        let list = HarrisList::with_reclaim(reclaim);
+        let mut reference = BTreeSet::new();
+        let mut rng = XorShift::new(697);
+        for _ in 0..3000 {
+            let key = rng.next_u64() % 100;
+            match rng.next_u64() % 3 {
+                0 => assert_eq!(list.insert(key), reference.insert(key)),
+                1 => assert_eq!(list.remove(&key), reference.remove(&key)),
+                _ => assert_eq!(list.contains(&key), reference.contains(&key)),
+            }
+        }
+        assert_eq!(list.len(), reference.len());
+        assert_eq!(elems(&list), reference.into_iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn single_thread_matches_btreeset() {
+        matches_btreeset(Epoch);
+        matches_btreeset(Hazard);
+    }
+
+    #[test]
+    fn drop_releases_live_elements() {
+        let marker = Arc::new(());
+        let list = HarrisList::new();
+        for i in 0..50 {
+            assert!(list.insert((i, Arc::clone(&marker))));
+        }
+        assert!(!list.insert((7, Arc::clone(&marker))));
+        assert_eq!(format!("{:?}", HarrisList::<u8>::new()), "{}");
+        drop(list);
+        assert_eq!(Arc::strong_count(&marker), 1);
+    }
+
+    fn each_key_exactly_once<R: Reclaim>(reclaim: R) {
+        const THREADS: usize = 4;
+        const KEYS: usize = 500;
+        let list = Arc::new(HarrisList::with_reclaim(reclaim));
+        let run = |op: fn(&HarrisList<usize, R>, usize) -> bool| {
+            let handles: Vec<_> = (0..THREADS)
+                .map(|_| {
+                    let list = Arc::clone(&list);
+                    thread::spawn(move || (0..KEYS).filter(|&k| op(&list, k)).count())
+                })
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).sum::<usize>()
+        };
+        // 所有线程争抢同一批key，每个key只能有一个线程成功
+        assert_eq!(run(|l, k| l.insert(k)), KEYS);
+        assert_eq!(elems(&list), (0..KEYS).collect::<Vec<_>>());
+        assert_eq!(run(|l, k| l.remove(&k)), KEYS);
+        assert!(list.is_empty());
+        assert!(elems(&list).is_empty());
+    }
+
+    #[test]
+    fn each_key_inserted_and_removed_exactly_once() {
+        each_key_exactly_once(Epoch);
+        each_key_exactly_once(Hazard);
+    }
+
+    fn mixed_stress<R: Reclaim>(reclaim: R) {
+        const WRITERS: u64 = 3;
+        const OPS: u64 = 10_000;
+        const KEYS: u64 = 32;
+        let list = Arc::new(HarrisList::with_reclaim(reclaim));
+        let stop = Arc::new(AtomicBool::new(false));
+
+        // 读者不断检查遍历结果严格递增，并随机查询
+        let reader = {
+            let list = Arc::clone(&list);
+            let stop = Arc::clone(&stop);
+            thread::spawn(move || {
+                let mut rng = XorShift::new(99);
+                while !stop.load(Ordering::Relaxed) {
+                    let xs = elems(&list);
+                    assert!(xs.windows(2).all(|w| w[0] < w[1]));
+                    list.contains(&(rng.next_u64() % (KEYS * WRITERS)));
+                }
+            })
+        };
+        // 每个写者只改自己的那组key(key % WRITERS == t)，最后和自己的本地记录比较
+        let writers: Vec<_> = (0..WRITERS)
+            .map(|t| {
+                let list = Arc::clone(&list);
+                thread::spawn(move || {
+                    let mut rng = XorShift::new(t + 1);
+                    let mut mine = BTreeSet::new();
+                    for _ in 0..OPS {
+                        let key = (rng.next_u64() % KEYS) * WRITERS + t;
+                        match rng.next_u64() % 3 {
+                            0 => assert_eq!(list.insert(key), mine.insert(key)),
+                            1 => assert_eq!(list.remove(&key), mine.remove(&key)),
+                            _ => assert_eq!(list.contains(&key), mine.contains(&key)),
+                        }
+                    }
+                    mine
+                })
+            })
+            .collect();
+
+        let mut expected = BTreeSet::new();
+        for w in writers {
+            expected.extend(w.join().unwrap());
+        }
+        stop.store(true, Ordering::Relaxed);
+        reader.join().unwrap();
+        assert_eq!(elems(&list), expected.into_iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn mixed_stress_epoch() {
+        mixed_stress(Epoch);
+    }
+
+    #[test]
+    fn mixed_stress_hazard() {
+        mixed_stress(Hazard);
+    }
+}
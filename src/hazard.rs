@@ -0,0 +1,416 @@
+// 危险指针(hazard pointer)内存回收
+// 和epoch一样是为了解决无锁结构"摘下的节点什么时候能释放"的问题，但粒度更细:
+// 1. 每个线程拥有若干个公开的危险指针槽，读共享指针前先把它写进槽里，再确认它还在原处
+// 2. 被摘下的节点先retire到线程本地的列表，攒够一定数量就扫描一遍所有线程的槽
+// 3. 没有出现在任何槽里的节点可以立即释放，出现了的留到下次扫描
+// 和epoch相比: 一个线程卡住只会挡住它正在保护的几个节点，未释放的垃圾总量始终有上界；
+// 代价是每次读指针都要多一次写槽和SeqCst fence，读路径更慢
+// 这里同样是教学用的小实现: 槽的登记表和孤儿垃圾用Mutex保护
+
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::ptr;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex, OnceLock, PoisonError};
+
+use crate::sync::atomic::{fence, AtomicPtr, AtomicUsize, Ordering};
+
+// 每个Guard拥有的危险指针槽数，MS队列出队时需要同时保护head和head.next，
+// Harris链表查找时要同时保护前驱、当前和后继三个节点
+pub const SLOTS: usize = 3;
+
+// 本地retire列表至少攒够这么多才扫描，避免频繁加锁遍历登记表
+const SCAN_THRESHOLD: usize = 64;
+
+// 一组危险指针槽，同一时刻只属于一个Guard
+struct Record {
+    slots: [AtomicPtr<u8>; SLOTS],
+}
+
+impl Record {
+    fn clear(&self) {
+        for slot in &self.slots {
+            slot.store(ptr::null_mut(), Ordering::Release);
+        }
+    }
+}
+
+// 等待释放的节点，drop_fn是对应类型的析构函数
+struct Retired {
+    ptr: *mut u8,
+    drop_fn: unsafe fn(*mut u8),
+}
+
+// 指针由retire的调用者保证可以在任意线程释放
+unsafe impl Send for Retired {}
+
+impl Retired {
+    fn run(self) {
+        unsafe { (self.drop_fn)(self.ptr) }
+    }
+}
+
+unsafe fn drop_box<T>(ptr: *mut u8) {
+    drop(Box::from_raw(ptr as *mut T));
+}
+
+struct Global {
+    records: Mutex<Vec<Arc<Record>>>,
+    // 线程退出时还没能释放的节点，由之后任意一次扫描接手
+    orphans: Mutex<Vec<Retired>>,
+    // 已经retire但还没有释放的节点数量
+    pending: AtomicUsize,
+}
+
+impl Global {
+    // 释放retired里所有没有被任何槽保护的节点，被保护的留在retired里
+    fn scan(&self, retired: &mut Vec<Retired>) {
+        retired.append(&mut self.orphans.lock().unwrap_or_else(PoisonError::into_inner));
+        // 和protect里的fence配对: 要么读者能看到节点已经被摘下而重试，要么这里能看到读者的槽
+        fence(Ordering::SeqCst);
+        let hazards: HashSet<*mut u8> = {
+            let records = self.records.lock().unwrap_or_else(PoisonError::into_inner);
+            records
+                .iter()
+                .flat_map(|r| r.slots.iter())
+                .map(|slot| slot.load(Ordering::Acquire))
+                .filter(|p| !p.is_null())
+                .collect()
+        };
+        let (keep, free): (Vec<Retired>, Vec<Retired>) =
+            retired.drain(..).partition(|r| hazards.contains(&r.ptr));
+        *retired = keep;
+        let n = free.len();
+        free.into_iter().for_each(Retired::run);
+        self.pending.fetch_sub(n, Ordering::Relaxed);
+    }
+
+    // 槽越多，一次扫描能释放的比例越低，阈值跟着槽的总数增长
+    fn threshold(&self) -> usize {
+        let records = self.records.lock().unwrap_or_else(PoisonError::into_inner).len();
+        SCAN_THRESHOLD.max(2 * records * SLOTS)
+    }
+}
+
+impl Drop for Global {
+    fn drop(&mut self) {
+        // 所有线程都已经注销，没有槽还在保护任何节点
+        let orphans = std::mem::take(self.orphans.get_mut().unwrap_or_else(PoisonError::into_inner));
+        orphans.into_iter().for_each(Retired::run);
+    }
+}
+
+// 一个独立的回收域，不同Domain之间的槽和垃圾互不影响
+pub struct Domain {
+    global: Arc<Global>,
+}
+
+impl Domain {
+    pub fn new() -> Self {
+        Domain {
+            global: Arc::new(Global {
+                records: Mutex::new(Vec::new()),
+                orphans: Mutex::new(Vec::new()),
+                pending: AtomicUsize::new(0),
+            }),
+        }
+    }
+
+    // 当前线程注册为参与者，返回的句柄只能在本线程使用
+    pub fn register(&self) -> LocalHandle {
+        LocalHandle {
+            local: Rc::new(Local {
+                global: Arc::clone(&self.global),
+                records: RefCell::new(Vec::new()),
+                free: RefCell::new(Vec::new()),
+                retired: RefCell::new(Vec::new()),
+            }),
+        }
+    }
+
+    // 已经retire但还没有真正释放的节点数量
+    pub fn pending(&self) -> usize {
+        self.global.pending.load(Ordering::Relaxed)
+    }
+}
+
+impl Default for Domain {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+struct Local {
+    global: Arc<Global>,
+    // 本线程创建过的所有Record，注销时从登记表里移除
+    records: RefCell<Vec<Arc<Record>>>,
+    // 当前没有被Guard占用的Record，嵌套的Guard各自占用一个
+    free: RefCell<Vec<Arc<Record>>>,
+    retired: RefCell<Vec<Retired>>,
+}
+
+impl Local {
+    fn acquire(&self) -> Arc<Record> {
+        if let Some(record) = self.free.borrow_mut().pop() {
+            return record;
+        }
+        let record = Arc::new(Record {
+            slots: std::array::from_fn(|_| AtomicPtr::new(ptr::null_mut())),
+        });
+        self.global
+            .records
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(Arc::clone(&record));
+        self.records.borrow_mut().push(Arc::clone(&record));
+        record
+    }
+
+    fn scan(&self) {
+        self.global.scan(&mut self.retired.borrow_mut());
+    }
+}
+
+impl Drop for Local {
+    fn drop(&mut self) {
+        // 线程退出: 注销自己的槽，扫描一次，剩下的交给全局
+        let mine = std::mem::take(self.records.get_mut());
+        self.global
+            .records
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .retain(|r| !mine.iter().any(|m| Arc::ptr_eq(r, m)));
+        let mut retired = std::mem::take(self.retired.get_mut());
+        self.global.scan(&mut retired);
+        self.global
+            .orphans
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .append(&mut retired);
+    }
+}
+
+pub struct LocalHandle {
+    local: Rc<Local>,
+}
+
+impl LocalHandle {
+    // 占用一组槽，Guard存活期间可以用protect保护最多SLOTS个指针
+    pub fn guard(&self) -> Guard {
+        Guard {
+            record: self.local.acquire(),
+            local: Rc::clone(&self.local),
+        }
+    }
+
+    // 不等阈值，立即扫描一次本地retire列表
+    pub fn flush(&self) {
+        self.local.scan();
+    }
+}
+
+// 持有一组危险指针槽，析构时清空并归还
+// 内部持有Rc，因此Guard既不能Send也不能Sync，只能在创建它的线程里使用
+pub struct Guard {
+    local: Rc<Local>,
+    record: Arc<Record>,
+}
+
+impl Guard {
+    // 读出src里的指针并登记到第slot个槽，返回时保证它在登记之后仍然在src里，
+    // 因此在重新protect这个槽或Guard析构之前不会被释放
+    pub fn protect<T>(&self, slot: usize, src: &AtomicPtr<T>) -> *mut T {
+        let hazard = &self.record.slots[slot];
+        let mut ptr = src.load(Ordering::Relaxed);
+        loop {
+            hazard.store(ptr as *mut u8, Ordering::Relaxed);
+            fence(Ordering::SeqCst);
+            let again = src.load(Ordering::Acquire);
+            if again == ptr {
+                return ptr;
+            }
+            ptr = again;
+        }
+    }
+
+    // 直接登记一个已经读出的指针(例如去掉了删除标记的next)，不会重新读来源确认；
+    // 调用者之后要自己检查它仍然可达，确认之前不能解引用
+    pub fn protect_raw<T>(&self, slot: usize, ptr: *mut T) {
+        self.record.slots[slot].store(ptr as *mut u8, Ordering::Relaxed);
+        fence(Ordering::SeqCst);
+    }
+
+    pub fn clear(&self, slot: usize) {
+        self.record.slots[slot].store(ptr::null_mut(), Ordering::Release);
+    }
+
+    /// 节点已经从共享结构中摘下，等没有槽保护它时释放
+    ///
+    /// # Safety
+    ///
+    /// ptr必须来自Box::into_raw且已经从共享结构中摘下，之后不会再被其他地方释放；
+    /// 析构T必须可以在任意线程、任意时间点进行
+    pub unsafe fn retire<T>(&self, ptr: *mut T) {
+        self.local.global.pending.fetch_add(1, Ordering::Relaxed);
+        let len = {
+            let mut retired = self.local.retired.borrow_mut();
+            retired.push(Retired {
+                ptr: ptr as *mut u8,
+                drop_fn: drop_box::<T>,
+            });
+            retired.len()
+        };
+        if len >= self.local.global.threshold() {
+            self.local.scan();
+        }
+    }
+}
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        self.record.clear();
+        self.local.free.borrow_mut().push(Arc::clone(&self.record));
+    }
+}
+
+// 整个进程共享的默认回收域，无锁结构选择Hazard策略时使用它
+pub fn default_domain() -> &'static Domain {
+    static DOMAIN: OnceLock<Domain> = OnceLock::new();
+    DOMAIN.get_or_init(Domain::new)
+}
+
+thread_local! {
+    static HANDLE: LocalHandle = default_domain().register();
+}
+
+// 在默认回收域上占用一组槽
+pub fn guard() -> Guard {
+    HANDLE
+        .try_with(LocalHandle::guard)
+        .unwrap_or_else(|_| default_domain().register().guard())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicBool;
+    use std::thread;
+
+    struct Flag(Arc<AtomicBool>);
+
+    impl Drop for Flag {
+        fn drop(&mut self) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn protected_pointer_is_not_freed() {
+        let domain = Domain::new();
+        let reader = domain.register();
+        let writer = domain.register();
+        let freed = Arc::new(AtomicBool::new(false));
+        let shared = AtomicPtr::new(Box::into_raw(Box::new(Flag(Arc::clone(&freed)))));
+
+        let reader_guard = reader.guard();
+        let p = reader_guard.protect(0, &shared);
+
+        // writer摘下节点并retire，reader的槽还指着它
+        let old = shared.swap(ptr::null_mut(), Ordering::AcqRel);
+        assert_eq!(old, p);
+        unsafe { writer.guard().retire(old) };
+        writer.flush();
+        assert!(!freed.load(Ordering::SeqCst));
+        assert_eq!(domain.pending(), 1);
+
+        // 清掉槽之后下一次扫描就能释放
+        reader_guard.clear(0);
+        writer.flush();
+        assert!(freed.load(Ordering::SeqCst));
+        assert_eq!(domain.pending(), 0);
+        drop(reader_guard);
+    }
+
+    #[test]
+    fn protect_follows_concurrent_updates() {
+        let domain = Domain::new();
+        let handle = domain.register();
+        let a = Box::into_raw(Box::new(1));
+        let shared = AtomicPtr::new(a);
+        let guard = handle.guard();
+        assert_eq!(guard.protect(1, &shared), a);
+        let b = Box::into_raw(Box::new(2));
+        shared.store(b, Ordering::Release);
+        // 重新protect同一个槽会换成新的指针，旧节点不再受保护
+        assert_eq!(guard.protect(1, &shared), b);
+        unsafe { guard.retire(a) };
+        handle.flush();
+        assert_eq!(domain.pending(), 0);
+        drop(guard);
+        drop(unsafe { Box::from_raw(b) });
+    }
+
+    #[test]
+    fn nested_guards_use_separate_slots() {
+        let domain = Domain::new();
+        let handle = domain.register();
+        let shared = AtomicPtr::new(Box::into_raw(Box::new(7)));
+        let outer = handle.guard();
+        let p = outer.protect(0, &shared);
+        {
+            // 内层Guard析构清空的是自己的槽，不影响外层的保护
+            let inner = handle.guard();
+            inner.protect(0, &shared);
+        }
+        let old = shared.swap(ptr::null_mut(), Ordering::AcqRel);
+        unsafe { handle.guard().retire(old) };
+        handle.flush();
+        assert_eq!(domain.pending(), 1);
+        assert_eq!(unsafe { *p }, 7);
+        drop(outer);
+        handle.flush();
+        assert_eq!(domain.pending(), 0);
+    }
+
+    #[test]
+    fn garbage_bounded_even_if_a_reader_stalls() {
+        const THREADS: usize = 4;
+        const OPS: usize = 20_000;
+
+        let domain = Arc::new(Domain::new());
+        let max_pending = Arc::new(AtomicUsize::new(0));
+        // 一个一直不放手的读者: 对epoch来说它会让垃圾无限增长，这里只影响它保护的那一个节点
+        let stalled = domain.register();
+        let pinned = AtomicPtr::new(Box::into_raw(Box::new(0usize)));
+        let stalled_guard = stalled.guard();
+        let stuck = stalled_guard.protect(0, &pinned);
+
+        let handles: Vec<_> = (0..THREADS)
+            .map(|_| {
+                let domain = Arc::clone(&domain);
+                let max_pending = Arc::clone(&max_pending);
+                thread::spawn(move || {
+                    let handle = domain.register();
+                    for i in 0..OPS {
+                        let guard = handle.guard();
+                        unsafe { guard.retire(Box::into_raw(Box::new(i))) };
+                        max_pending.fetch_max(domain.pending(), Ordering::Relaxed);
+                    }
+                })
+            })
+            .collect();
+        for h in handles {
+            h.join().unwrap();
+        }
+
+        let bound = THREADS * SCAN_THRESHOLD + THREADS * SLOTS;
+        let max = max_pending.load(Ordering::Relaxed);
+        assert!(max <= bound, "pending garbage grew to {}", max);
+
+        pinned.store(ptr::null_mut(), Ordering::Release);
+        unsafe { stalled_guard.retire(stuck) };
+        drop(stalled_guard);
+        stalled.flush();
+        assert_eq!(domain.pending(), 0);
+    }
+}
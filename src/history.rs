@@ -0,0 +1,215 @@
+// 撤销/重做历史，保存的是完整状态(快照)
+// 两条simple_deque_3::List当作有界栈:
+// - undo: 过去的状态，尾部是最近的一个；超过capacity时从头部丢弃最老的
+// - redo: 撤销掉的状态，头部是下一个可以重做的
+// 撤销之后再push_state相当于从历史中间开出一条新分支，原来的redo部分整段丢弃并交还给调用者
+// 所有操作都只在两条链表的两端进行，O(1)
+
+use std::fmt;
+use std::mem;
+
+use crate::simple_deque_3::List;
+
+pub struct History<T> {
+    current: T,
+    undo: List<T>,
+    redo: List<T>,
+    capacity: usize,
+}
+
+impl<T> History<T> {
+    // capacity是最多能撤销的步数，为0时panic
+    pub fn new(initial: T, capacity: usize) -> Self {
+        assert!(capacity > 0, "capacity must be positive");
+        History {
+            current: initial,
+            undo: List::new(),
+            redo: List::new(),
+            capacity,
+        }
+    }
+
+    pub fn current(&self) -> &T {
+        &self.current
+    }
+
+    pub fn into_current(self) -> T {
+        self.current
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn undo_len(&self) -> usize {
+        self.undo.len()
+    }
+
+    pub fn redo_len(&self) -> usize {
+        self.redo.len()
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    // 记录一个新状态，原来的当前状态进入undo
+    // 有可重做的状态时它们构成的分支被截断，按从近到远的顺序返回
+    pub fn push_state(&mut self, state: T) -> Vec<T> {
+        let old = mem::replace(&mut self.current, state);
+        self.undo.push_back(old);
+        if self.undo.len() > self.capacity {
+            self.undo.pop_front();
+        }
+        mem::take(&mut self.redo).into_iter().collect()
+    }
+
+    // 回到上一个状态，没有可撤销的时返回None
+    pub fn undo(&mut self) -> Option<&T> {
+        let prev = self.undo.pop_back()?;
+        let old = mem::replace(&mut self.current, prev);
+        self.redo.push_front(old);
+        Some(&self.current)
+    }
+
+    pub fn redo(&mut self) -> Option<&T> {
+        let next = self.redo.pop_front()?;
+        let old = mem::replace(&mut self.current, next);
+        self.undo.push_back(old);
+        Some(&self.current)
+    }
+
+    // 调整容量，缩小时丢弃最老的状态并按从老到新的顺序返回；capacity为0时panic
+    pub fn set_capacity(&mut self, capacity: usize) -> Vec<T> {
+        assert!(capacity > 0, "capacity must be positive");
+        self.capacity = capacity;
+        let excess = self.undo.len().saturating_sub(capacity);
+        (0..excess).filter_map(|_| self.undo.pop_front()).collect()
+    }
+
+    // 清空undo和redo，只保留当前状态
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+
+    // 从最老到最新列出整条历史，当前状态在中间
+    pub fn iter(&self) -> impl Iterator<Item = &T> + '_ {
+        self.undo.iter().chain(Some(&self.current)).chain(self.redo.iter())
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for History<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("History")
+            .field("undo", &self.undo)
+            .field("current", &self.current)
+            .field("redo", &self.redo)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::XorShift;
+
+    #[test]
+    fn undo_and_redo() {
+        let mut history = History::new("a", 10);
+        assert_eq!(history.undo(), None);
+        history.push_state("b");
+        history.push_state("c");
+        assert_eq!(history.undo(), Some(&"b"));
+        assert_eq!(history.undo(), Some(&"a"));
+        assert_eq!(history.undo(), None);
+        assert_eq!(history.redo(), Some(&"b"));
+        assert_eq!(history.current(), &"b");
+        assert!(history.can_undo() && history.can_redo());
+        assert_eq!(history.iter().copied().collect::<Vec<_>>(), vec!["a", "b", "c"]);
+        assert_eq!(history.redo(), Some(&"c"));
+        assert_eq!(history.redo(), None);
+    }
+
+    #[test]
+    fn new_edit_truncates_redo_branch() {
+        let mut history = History::new(0, 10);
+        for x in 1..=4 {
+            assert!(history.push_state(x).is_empty());
+        }
+        history.undo();
+        history.undo();
+        history.undo();
+        assert_eq!(history.current(), &1);
+        assert_eq!(history.push_state(10), vec![2, 3, 4]);
+        assert!(!history.can_redo());
+        assert_eq!(history.undo(), Some(&1));
+        assert_eq!(format!("{history:?}"), "History { undo: [0], current: 1, redo: [10] }");
+    }
+
+    #[test]
+    fn capacity_trims_oldest() {
+        let mut history = History::new(0, 3);
+        for x in 1..=5 {
+            history.push_state(x);
+        }
+        assert_eq!(history.undo_len(), 3);
+        while history.undo().is_some() {}
+        assert_eq!(history.current(), &2);
+        assert_eq!(history.redo_len(), 3);
+        while history.redo().is_some() {}
+        assert_eq!(history.set_capacity(1), vec![2, 3]);
+        assert_eq!(history.undo(), Some(&4));
+        history.clear();
+        assert_eq!(history.into_current(), 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "capacity must be positive")]
+    fn zero_capacity_panics() {
+        let _ = History::new((), 0);
+    }
+
+    #[test]
+    fn randomized_against_vec_model() {
+        // 模型: 整条历史放在Vec里，pos是当前状态的下标
+        let mut rng = XorShift::new(0x415);
+        let mut history = History::new(0u64, 8);
+        let mut model = vec![0u64];
+        let mut pos = 0;
+        for i in 1..2000 {
+            match rng.next_u64() % 3 {
+                0 => {
+                    let got = history.push_state(i);
+                    let branch = model.split_off(pos + 1);
+                    assert_eq!(got, branch);
+                    model.push(i);
+                    pos += 1;
+                    if pos > 8 {
+                        model.remove(0);
+                        pos -= 1;
+                    }
+                }
+                1 => {
+                    let expect = (pos > 0).then(|| {
+                        pos -= 1;
+                        model[pos]
+                    });
+                    assert_eq!(history.undo().copied(), expect);
+                }
+                _ => {
+                    let expect = (pos + 1 < model.len()).then(|| {
+                        pos += 1;
+                        model[pos]
+                    });
+                    assert_eq!(history.redo().copied(), expect);
+                }
+            }
+            assert!(history.iter().eq(model.iter()));
+        }
+    }
+}
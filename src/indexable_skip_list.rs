@@ -0,0 +1,489 @@
+// 可按下标访问的跳表: 每个前向指针额外记录它的"跨度"(width)，即沿这条指针走一步在第0层上越过了几个位置
+// 把头节点看作位置0，元素依次是位置1..=len，那么从位置p的节点沿第i层走到位置q的节点，width就是q - p
+// - get(index): 从最高层往下走，只要pos + width不超过目标位置就往右走，O(log n)
+// - insert(index): 找到每一层上目标位置之前的最后一个节点，新节点高度以下的层拆分跨度，以上的层跨度加一
+// - remove(index): 被删节点高度以下的层合并跨度，以上的层跨度减一
+// 指向末尾(None)的指针不记录跨度，也就不需要在每次插入删除时维护
+// 元素之间没有顺序要求，这是一个O(log n)随机访问/插入/删除的序列，和skip_list_map共用层数上限和随机数
+// 和skip_list_map一样所有指针都以裸指针保存
+
+use std::fmt;
+use std::iter::FusedIterator;
+use std::marker::PhantomData;
+use std::ops::{Index, IndexMut};
+use std::ptr::{self, NonNull};
+
+use crate::error::{check_insert, ListError};
+use crate::rng::XorShift;
+use crate::skip_list_map::MAX_LEVEL;
+
+struct Link<T> {
+    next: Option<NonNull<Node<T>>>,
+    // next为Some时有效
+    width: usize,
+}
+
+// derive会要求T: Copy
+impl<T> Clone for Link<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Link<T> {}
+
+struct Node<T> {
+    elem: T,
+    height: usize,
+    // 长度为height的前向指针数组
+    links: *mut Link<T>,
+}
+
+fn alloc_links<T>(n: usize) -> *mut Link<T> {
+    let links: Box<[Link<T>]> = vec![Link { next: None, width: 0 }; n].into_boxed_slice();
+    Box::into_raw(links) as *mut Link<T>
+}
+
+unsafe fn free_links<T>(links: *mut Link<T>, n: usize) {
+    drop(Box::from_raw(ptr::slice_from_raw_parts_mut(links, n)));
+}
+
+pub struct IndexableSkipList<T> {
+    // 头节点只有前向指针，长度为MAX_LEVEL
+    head: *mut Link<T>,
+    level: usize,
+    len: usize,
+    rng: XorShift,
+    _boo: PhantomData<Box<Node<T>>>,
+}
+
+impl<T> IndexableSkipList<T> {
+    pub fn new() -> Self {
+        Self::with_rng(XorShift::from_counter())
+    }
+
+    // 固定随机种子，节点高度(进而整个结构)可以复现
+    pub fn with_seed(seed: u64) -> Self {
+        Self::with_rng(XorShift::new(seed))
+    }
+
+    fn with_rng(rng: XorShift) -> Self {
+        IndexableSkipList {
+            head: alloc_links(MAX_LEVEL),
+            level: 1,
+            len: 0,
+            rng,
+            _boo: PhantomData,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn clear(&mut self) {
+        unsafe {
+            let mut cur = (*self.head).next;
+            while let Some(node) = cur {
+                cur = (*(*node.as_ptr()).links).next;
+                Self::free_node(node);
+            }
+            for i in 0..MAX_LEVEL {
+                *self.head.add(i) = Link { next: None, width: 0 };
+            }
+        }
+        self.level = 1;
+        self.len = 0;
+    }
+
+    // 在每一层找到位置小于target的最后一个节点，返回它在该层的指针槽和它的位置
+    unsafe fn predecessors(&self, target: usize) -> [(*mut Link<T>, usize); MAX_LEVEL] {
+        let mut update = [(ptr::null_mut(), 0); MAX_LEVEL];
+        let mut links = self.head;
+        let mut pos = 0;
+        for (i, slot) in update.iter_mut().enumerate().take(self.level).rev() {
+            while let Link { next: Some(n), width } = *links.add(i) {
+                if pos + width >= target {
+                    break;
+                }
+                pos += width;
+                links = (*n.as_ptr()).links;
+            }
+            *slot = (links.add(i), pos);
+        }
+        update
+    }
+
+    fn node(&self, index: usize) -> Option<NonNull<Node<T>>> {
+        if index >= self.len {
+            return None;
+        }
+        unsafe {
+            let mut links = self.head;
+            let mut pos = 0;
+            let target = index + 1;
+            for i in (0..self.level).rev() {
+                while let Link { next: Some(n), width } = *links.add(i) {
+                    if pos + width > target {
+                        break;
+                    }
+                    pos += width;
+                    if pos == target {
+                        return Some(n);
+                    }
+                    links = (*n.as_ptr()).links;
+                }
+            }
+            unreachable!("index within bounds but not found")
+        }
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.node(index).map(|n| unsafe { &(*n.as_ptr()).elem })
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        self.node(index).map(|n| unsafe { &mut (*n.as_ptr()).elem })
+    }
+
+    pub fn first(&self) -> Option<&T> {
+        unsafe { (*self.head).next.map(|n| &(*n.as_ptr()).elem) }
+    }
+
+    pub fn last(&self) -> Option<&T> {
+        self.len.checked_sub(1).and_then(|i| self.get(i))
+    }
+
+    // 插入后elem位于index处，原来index及之后的元素后移；index > len时panic
+    pub fn insert(&mut self, index: usize, elem: T) {
+        assert!(index <= self.len, "insertion index out of bounds");
+        let target = index + 1;
+        unsafe {
+            let mut update = self.predecessors(target);
+            let height = self.rng.random_level();
+            if height > self.level {
+                for (i, slot) in update.iter_mut().enumerate().take(height).skip(self.level) {
+                    *slot = (self.head.add(i), 0);
+                }
+                self.level = height;
+            }
+            let node = NonNull::new_unchecked(Box::into_raw(Box::new(Node {
+                elem,
+                height,
+                links: alloc_links(height),
+            })));
+            for (i, &(slot, pos)) in update.iter().enumerate().take(self.level) {
+                let old = *slot;
+                if i < height {
+                    // 拆分跨度: 前驱 -> 新节点 -> 原来的后继(它的位置因为插入后移了一位)
+                    *(*node.as_ptr()).links.add(i) = Link {
+                        next: old.next,
+                        width: (pos + old.width + 1).saturating_sub(target),
+                    };
+                    *slot = Link {
+                        next: Some(node),
+                        width: target - pos,
+                    };
+                } else if old.next.is_some() {
+                    (*slot).width += 1;
+                }
+            }
+        }
+        self.len += 1;
+        self.check_invariants();
+    }
+
+    // index > len时返回OutOfRange而不是panic
+    pub fn try_insert(&mut self, index: usize, elem: T) -> Result<(), ListError> {
+        check_insert(index, self.len)?;
+        self.insert(index, elem);
+        Ok(())
+    }
+
+    // 移除并返回index处的元素，越界时返回None
+    pub fn remove(&mut self, index: usize) -> Option<T> {
+        if index >= self.len {
+            return None;
+        }
+        unsafe {
+            let update = self.predecessors(index + 1);
+            let node = (*update[0].0).next.unwrap();
+            let height = (*node.as_ptr()).height;
+            for (i, &(slot, _)) in update.iter().enumerate().take(self.level) {
+                if i < height {
+                    // 合并跨度: 前驱直接指向被删节点的后继
+                    let removed = *(*node.as_ptr()).links.add(i);
+                    *slot = Link {
+                        next: removed.next,
+                        width: (*slot).width + removed.width - 1,
+                    };
+                } else if (*slot).next.is_some() {
+                    (*slot).width -= 1;
+                }
+            }
+            while self.level > 1 && (*self.head.add(self.level - 1)).next.is_none() {
+                self.level -= 1;
+            }
+            self.len -= 1;
+            self.check_invariants();
+            Some(Self::free_node(node))
+        }
+    }
+
+    pub fn push_front(&mut self, elem: T) {
+        self.insert(0, elem);
+    }
+
+    pub fn push_back(&mut self, elem: T) {
+        self.insert(self.len, elem);
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        self.remove(0)
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        self.remove(self.len.checked_sub(1)?)
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            next: unsafe { (*self.head).next },
+            remaining: self.len,
+            _boo: PhantomData,
+        }
+    }
+
+    unsafe fn free_node(node: NonNull<Node<T>>) -> T {
+        let node = Box::from_raw(node.as_ptr());
+        free_links(node.links, node.height);
+        node.elem
+    }
+
+    // 每一层上的跨度之和等于实际越过的位置数，高层串起的节点是低层的子序列
+    pub fn assert_invariants(&self) {
+        unsafe {
+            // 第0层上每个节点的位置
+            let mut positions = std::collections::HashMap::new();
+            let mut cur = (*self.head).next;
+            let mut pos = 0;
+            while let Some(n) = cur {
+                pos += 1;
+                positions.insert(n, pos);
+                cur = (*(*n.as_ptr()).links).next;
+            }
+            assert_eq!(pos, self.len, "length mismatch");
+            for i in 0..MAX_LEVEL {
+                let mut links = self.head;
+                let mut pos = 0;
+                if i >= self.level {
+                    assert!((*links.add(i)).next.is_none(), "link above current level");
+                    continue;
+                }
+                while let Link { next: Some(n), width } = *links.add(i) {
+                    let at = positions[&n];
+                    assert_eq!(pos + width, at, "wrong width at level {}", i);
+                    assert!((*n.as_ptr()).height > i, "node linked above its height");
+                    pos = at;
+                    links = (*n.as_ptr()).links;
+                }
+            }
+        }
+    }
+
+    #[inline]
+    fn check_invariants(&self) {
+        #[cfg(all(debug_assertions, feature = "debug-invariants"))]
+        self.assert_invariants();
+    }
+}
+
+impl<T> Drop for IndexableSkipList<T> {
+    fn drop(&mut self) {
+        self.clear();
+        unsafe { free_links(self.head, MAX_LEVEL) };
+    }
+}
+
+impl<T> Default for IndexableSkipList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Index<usize> for IndexableSkipList<T> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &T {
+        self.get(index).expect("index out of bounds")
+    }
+}
+
+impl<T> IndexMut<usize> for IndexableSkipList<T> {
+    fn index_mut(&mut self, index: usize) -> &mut T {
+        self.get_mut(index).expect("index out of bounds")
+    }
+}
+
+impl<T> Extend<T> for IndexableSkipList<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for elem in iter {
+            self.push_back(elem);
+        }
+    }
+}
+
+impl<T> FromIterator<T> for IndexableSkipList<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = IndexableSkipList::new();
+        list.extend(iter);
+        list
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for IndexableSkipList<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: PartialEq> PartialEq for IndexableSkipList<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Eq> Eq for IndexableSkipList<T> {}
+
+pub struct Iter<'a, T> {
+    next: Option<NonNull<Node<T>>>,
+    remaining: usize,
+    _boo: PhantomData<&'a Node<T>>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        self.next.map(|n| unsafe {
+            let node = &*n.as_ptr();
+            self.next = (*node.links).next;
+            self.remaining -= 1;
+            &node.elem
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+impl<T> FusedIterator for Iter<'_, T> {}
+
+impl<'a, T> IntoIterator for &'a IndexableSkipList<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+unsafe impl<T: Send> Send for IndexableSkipList<T> {}
+unsafe impl<T: Sync> Sync for IndexableSkipList<T> {}
+unsafe impl<T: Sync> Send for Iter<'_, T> {}
+unsafe impl<T: Sync> Sync for Iter<'_, T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+
+    #[test]
+    fn positional_insert_and_remove() {
+        let mut list = IndexableSkipList::with_seed(702);
+        list.extend([1, 2, 4]);
+        list.insert(2, 3);
+        list.insert(0, 0);
+        list.insert(5, 5);
+        assert_eq!(format!("{:?}", list), "[0, 1, 2, 3, 4, 5]");
+        assert_eq!((list[3], list.get(6), list.first(), list.last()), (3, None, Some(&0), Some(&5)));
+        list[3] = 30;
+        assert_eq!(list.remove(3), Some(30));
+        assert_eq!(list.remove(5), None);
+        assert_eq!((list.pop_front(), list.pop_back()), (Some(0), Some(5)));
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![1, 2, 4]);
+        list.assert_invariants();
+    }
+
+    #[test]
+    #[should_panic(expected = "insertion index out of bounds")]
+    fn insert_past_end_panics() {
+        let mut list = IndexableSkipList::new();
+        list.insert(1, 'x');
+    }
+
+    #[test]
+    fn matches_vec_model() {
+        let mut rng = XorShift::new(702);
+        let mut list = IndexableSkipList::with_seed(7);
+        let mut model = Vec::new();
+        for i in 0..4000u32 {
+            match rng.next_u64() % 4 {
+                0 | 1 => {
+                    let index = rng.next_u64() as usize % (model.len() + 1);
+                    list.insert(index, i);
+                    model.insert(index, i);
+                }
+                2 => {
+                    let index = rng.next_u64() as usize % (model.len() + 1);
+                    let expected = (index < model.len()).then(|| model.remove(index));
+                    assert_eq!(list.remove(index), expected);
+                }
+                _ => {
+                    let index = rng.next_u64() as usize % (model.len() + 1);
+                    assert_eq!(list.get(index), model.get(index));
+                }
+            }
+            assert_eq!(list.len(), model.len());
+            if i % 500 == 0 {
+                list.assert_invariants();
+            }
+        }
+        list.assert_invariants();
+        assert!(list.iter().eq(model.iter()));
+        list.clear();
+        assert!(list.is_empty());
+        list.assert_invariants();
+    }
+
+    #[test]
+    fn large_middle_inserts_stay_indexable() {
+        // 一直往正中间插入，Vec要搬O(n)个元素，这里每次都是O(log n)
+        const N: usize = 20_000;
+        let mut list = IndexableSkipList::with_seed(1702);
+        for i in 0..N {
+            list.insert(list.len() / 2, i);
+        }
+        list.assert_invariants();
+        // 最后插入的元素在最后一次插入时的正中间
+        assert_eq!(list[(N - 1) / 2], N - 1);
+        assert_eq!(list[0], 1);
+        assert_eq!(list[N - 1], 0);
+    }
+
+    #[test]
+    fn elements_dropped_exactly_once() {
+        let marker = Rc::new(());
+        let mut list: IndexableSkipList<_> = (0..100).map(|_| Rc::clone(&marker)).collect();
+        drop(list.remove(50));
+        assert_eq!(Rc::strong_count(&marker), 100);
+        drop(list);
+        assert_eq!(Rc::strong_count(&marker), 1);
+    }
+}
@@ -0,0 +1,175 @@
+// 结构性修改的tracing埋点
+// 开启tracing特性时mutation!/mutation_span!展开成tracing的TRACE级事件和span，关闭时什么代码都不生成
+// target就是埋点所在的模块路径，订阅者可以只打开关心的模块，例如
+//   RUST_LOG=too_many_linked_list_rs::simple_deque_3=trace
+// 每个事件都带op字段写明是哪个操作，其余字段是操作之后的长度等信息；
+// 字段表达式在特性关闭时不会被求值，所以只能写成本来就有的值(self.len之类)，不要为了埋点专门算一个局部变量
+// 并发结构里事件只说明"这个线程做完了这一步"，不同线程的事件先后不代表线性化顺序
+
+// 一次结构性修改: mutation!("push_back", len = self.len)
+macro_rules! mutation {
+    ($op:literal $(, $($fields:tt)*)?) => {
+        #[cfg(feature = "tracing")]
+        tracing::trace!(op = $op $(, $($fields)*)?);
+    };
+}
+
+// 由多步修改组成的操作，返回的守卫离开作用域时退出span，期间的事件都挂在它下面
+// let _span = mutation_span!("split_into", n);
+// 目前只有unsafe-impls里的模块用到，只开safe特性时不报未使用
+#[cfg_attr(not(feature = "unsafe-impls"), allow(unused_macros))]
+macro_rules! mutation_span {
+    ($name:literal $(, $($fields:tt)*)?) => {{
+        #[cfg(feature = "tracing")]
+        let span = tracing::trace_span!($name $(, $($fields)*)?).entered();
+        #[cfg(not(feature = "tracing"))]
+        let span = $crate::instrument::NoSpan;
+        span
+    }};
+}
+
+pub(crate) use mutation;
+#[cfg(feature = "unsafe-impls")]
+pub(crate) use mutation_span;
+
+// 特性关闭时mutation_span!返回的占位守卫
+#[cfg(all(feature = "unsafe-impls", not(feature = "tracing")))]
+pub(crate) struct NoSpan;
+
+#[cfg(all(test, feature = "tracing", feature = "concurrent"))]
+mod tests {
+    use std::fmt::Debug;
+    use std::sync::{Arc, Mutex};
+
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata, Subscriber};
+
+    use crate::simple_deque_3::List;
+    use crate::treiber_stack::TreiberStack;
+
+    // 把每个事件记成"模块名::op"，span只记名字
+    #[derive(Clone, Default)]
+    struct Recorder {
+        events: Arc<Mutex<Vec<String>>>,
+        spans: Arc<Mutex<Vec<&'static str>>>,
+    }
+
+    struct OpVisitor(Option<String>);
+
+    impl Visit for OpVisitor {
+        fn record_str(&mut self, field: &Field, value: &str) {
+            if field.name() == "op" {
+                self.0 = Some(value.to_string());
+            }
+        }
+
+        fn record_debug(&mut self, _field: &Field, _value: &dyn Debug) {}
+    }
+
+    impl Subscriber for Recorder {
+        fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &Attributes<'_>) -> Id {
+            self.spans.lock().unwrap().push(span.metadata().name());
+            Id::from_u64(1)
+        }
+
+        fn record(&self, _span: &Id, _values: &Record<'_>) {}
+
+        fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            let mut visitor = OpVisitor(None);
+            event.record(&mut visitor);
+            let module = event.metadata().target().rsplit("::").next().unwrap();
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("{}::{}", module, visitor.0.unwrap()));
+        }
+
+        fn enter(&self, _span: &Id) {}
+
+        fn exit(&self, _span: &Id) {}
+    }
+
+    #[test]
+    fn mutations_emit_events() {
+        let recorder = Recorder::default();
+        let whole: List<i32> = (0..3).collect();
+        tracing::subscriber::with_default(recorder.clone(), || {
+            let mut list = List::new();
+            list.push_back(1);
+            list.push_front(0);
+            let mut cursor = list.cursor_mut();
+            cursor.move_next();
+            let _ = cursor.split_after();
+            list.pop_back();
+            let _ = whole.split_into(2);
+
+            let stack = TreiberStack::new();
+            stack.push(1);
+            stack.pop();
+        });
+        let events = recorder.events.lock().unwrap().clone();
+        assert_eq!(
+            events,
+            [
+                "simple_deque_3::push_back",
+                "simple_deque_3::push_front",
+                "simple_deque_3::split_after",
+                "simple_deque_3::pop_back",
+                "simple_deque_3::split_part",
+                "simple_deque_3::split_part",
+                "treiber_stack::push",
+                "treiber_stack::pop",
+            ]
+        );
+        assert_eq!(*recorder.spans.lock().unwrap(), ["split_into"]);
+    }
+
+    #[test]
+    fn concurrent_modules_emit_events() {
+        let recorder = Recorder::default();
+        tracing::subscriber::with_default(recorder.clone(), || {
+            let (tx, rx) = crate::spsc_queue::channel();
+            tx.send(1).unwrap();
+            rx.try_recv().unwrap();
+
+            let (tx, rx) = crate::channel::channel();
+            tx.send(1).unwrap();
+            rx.try_recv().unwrap();
+            drop(tx);
+
+            let list = crate::rcu_list::RcuList::new();
+            list.push_front(1);
+            list.insert(1, 2);
+            list.remove(0);
+
+            let set = crate::harris_list::HarrisList::new();
+            set.insert(1);
+            set.remove(&1);
+        });
+        let events = recorder.events.lock().unwrap().clone();
+        assert_eq!(
+            events,
+            [
+                "spsc_queue::push",
+                "spsc_queue::pop",
+                "ms_queue::push",
+                "channel::send",
+                "ms_queue::pop",
+                "channel::recv",
+                "channel::disconnect",
+                "rcu_list::push_front",
+                "rcu_list::insert",
+                "rcu_list::remove",
+                "harris_list::insert",
+                "harris_list::remove",
+            ]
+        );
+    }
+}
@@ -0,0 +1,313 @@
+// 区间链表(游程编码): 用有序链表保存一组互不相交的半开区间[start, end)，表示被覆盖的点集
+// 链表里的区间始终满足: 非空、按start升序、相邻两个之间至少隔着一个空隙(首尾相接的会被合并)
+// - insert: 找到第一个可能和新区间相交或相接的节点，把它扩展成合并后的区间，再删掉被吞掉的后续节点
+// - remove: 和要删除的区间相交的节点被截短，完全被盖住的删掉，被从中间挖掉一段的拆成两个节点
+// - gaps: 在给定范围内按顺序列出没有被覆盖的空隙，可以用来做简单的地址/ID分配器
+// 底层是simple_deque_3::List，所有修改都在游标上原地完成；查找是O(区间数)
+
+use std::fmt;
+use std::ops::Range;
+
+use crate::simple_deque_3::{self, List};
+
+pub struct IntervalList<T> {
+    list: List<Range<T>>,
+}
+
+impl<T: Ord + Copy> IntervalList<T> {
+    pub fn new() -> Self {
+        IntervalList { list: List::new() }
+    }
+
+    // 区间(节点)的个数，不是被覆盖的点数
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.list.clear();
+    }
+
+    // 覆盖range，和已有区间重叠或相接时合并；返回覆盖的点集是否变化了，空区间什么都不做
+    pub fn insert(&mut self, range: Range<T>) -> bool {
+        if range.is_empty() {
+            return false;
+        }
+        let mut cursor = self.list.cursor_mut();
+        cursor.move_next();
+        // 跳过完全在左边、连相接都够不上的区间
+        while cursor.current().is_some_and(|r| r.end < range.start) {
+            cursor.move_next();
+        }
+        let end = match cursor.current() {
+            Some(r) if r.start <= range.end => {
+                if r.start <= range.start && range.end <= r.end {
+                    return false;
+                }
+                r.start = r.start.min(range.start);
+                r.end = r.end.max(range.end);
+                r.end
+            }
+            _ => {
+                cursor.insert_before(range);
+                self.check_invariants();
+                return true;
+            }
+        };
+        // 删掉被合并进来的后续区间，最后一个可能伸得更远
+        cursor.move_next();
+        let mut merged_end = end;
+        while let Some(r) = cursor.current() {
+            if r.start > merged_end {
+                break;
+            }
+            merged_end = merged_end.max(r.end);
+            cursor.remove_current();
+        }
+        cursor.move_prev();
+        if let Some(r) = cursor.current() {
+            r.end = merged_end;
+        }
+        self.check_invariants();
+        true
+    }
+
+    // 取消覆盖range，返回覆盖的点集是否变化了
+    pub fn remove(&mut self, range: Range<T>) -> bool {
+        if range.is_empty() {
+            return false;
+        }
+        let mut changed = false;
+        let mut cursor = self.list.cursor_mut();
+        cursor.move_next();
+        while cursor.current().is_some_and(|r| r.end <= range.start) {
+            cursor.move_next();
+        }
+        while let Some(r) = cursor.current() {
+            if r.start >= range.end {
+                break;
+            }
+            changed = true;
+            match (r.start < range.start, range.end < r.end) {
+                // 从中间挖掉一段，拆成两个节点
+                (true, true) => {
+                    let right = range.end..r.end;
+                    r.end = range.start;
+                    cursor.insert_after(right);
+                    break;
+                }
+                (true, false) => {
+                    r.end = range.start;
+                    cursor.move_next();
+                }
+                (false, true) => {
+                    r.start = range.end;
+                    break;
+                }
+                (false, false) => {
+                    cursor.remove_current();
+                }
+            }
+        }
+        self.check_invariants();
+        changed
+    }
+
+    pub fn contains(&self, point: T) -> bool {
+        self.find(point).is_some()
+    }
+
+    // 包含point的那个区间
+    pub fn find(&self, point: T) -> Option<&Range<T>> {
+        self.iter().take_while(|r| r.start <= point).find(|r| point < r.end)
+    }
+
+    // range里的每个点是否都被覆盖；区间之间不相接，所以必须落在同一个区间里
+    pub fn contains_range(&self, range: &Range<T>) -> bool {
+        range.is_empty() || self.find(range.start).is_some_and(|r| range.end <= r.end)
+    }
+
+    // 所有区间，按start升序
+    pub fn iter(&self) -> simple_deque_3::Iter<'_, Range<T>> {
+        self.list.iter()
+    }
+
+    // 按顺序列出within里没有被覆盖的部分
+    pub fn gaps(&self, within: Range<T>) -> Gaps<'_, T> {
+        Gaps {
+            iter: self.iter(),
+            pos: within.start,
+            end: within.end,
+        }
+    }
+
+    pub fn assert_invariants(&self) {
+        let mut prev: Option<&Range<T>> = None;
+        for r in self.iter() {
+            assert!(r.start < r.end, "empty interval");
+            if let Some(prev) = prev {
+                assert!(prev.end < r.start, "intervals overlap, touch or are out of order");
+            }
+            prev = Some(r);
+        }
+    }
+
+    #[inline]
+    fn check_invariants(&self) {
+        #[cfg(all(debug_assertions, feature = "debug-invariants"))]
+        self.assert_invariants();
+    }
+}
+
+pub struct Gaps<'a, T> {
+    iter: simple_deque_3::Iter<'a, Range<T>>,
+    // 下一个空隙最早从哪里开始
+    pos: T,
+    end: T,
+}
+
+impl<T: Ord + Copy> Iterator for Gaps<'_, T> {
+    type Item = Range<T>;
+
+    fn next(&mut self) -> Option<Range<T>> {
+        while self.pos < self.end {
+            let Some(r) = self.iter.next() else {
+                let gap = self.pos..self.end;
+                self.pos = self.end;
+                return Some(gap);
+            };
+            if r.end <= self.pos {
+                continue;
+            }
+            let gap = self.pos..r.start.min(self.end);
+            self.pos = r.end;
+            if !gap.is_empty() {
+                return Some(gap);
+            }
+        }
+        None
+    }
+}
+
+impl<T: Ord + Copy> Default for IntervalList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Ord + Copy> Extend<Range<T>> for IntervalList<T> {
+    fn extend<I: IntoIterator<Item = Range<T>>>(&mut self, iter: I) {
+        for range in iter {
+            self.insert(range);
+        }
+    }
+}
+
+impl<T: Ord + Copy> FromIterator<Range<T>> for IntervalList<T> {
+    fn from_iter<I: IntoIterator<Item = Range<T>>>(iter: I) -> Self {
+        let mut list = IntervalList::new();
+        list.extend(iter);
+        list
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for IntervalList<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.list.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::XorShift;
+
+    fn ranges(list: &IntervalList<u32>) -> Vec<Range<u32>> {
+        list.iter().cloned().collect()
+    }
+
+    #[test]
+    fn insert_merges_overlapping_and_touching() {
+        let mut list: IntervalList<u32> = [10..20, 30..40, 50..60].into_iter().collect();
+        assert!(!list.insert(12..18));
+        assert!(!list.insert(5..5));
+        assert!(list.insert(0..5));
+        assert_eq!(ranges(&list), vec![0..5, 10..20, 30..40, 50..60]);
+        // 首尾相接也合并
+        assert!(list.insert(5..10));
+        assert_eq!(ranges(&list), vec![0..20, 30..40, 50..60]);
+        // 一次吞掉后面两个，最后一个伸得更远
+        assert!(list.insert(25..55));
+        assert_eq!(ranges(&list), vec![0..20, 25..60]);
+        assert!(list.insert(70..80));
+        assert_eq!(format!("{:?}", list), "[0..20, 25..60, 70..80]");
+    }
+
+    #[test]
+    fn remove_trims_and_splits() {
+        let mut list: IntervalList<u32> = [0..10, 20..30, 40..50].into_iter().collect();
+        assert!(!list.remove(10..20));
+        assert!(list.remove(3..5));
+        assert_eq!(ranges(&list), vec![0..3, 5..10, 20..30, 40..50]);
+        assert!(list.remove(8..45));
+        assert_eq!(ranges(&list), vec![0..3, 5..8, 45..50]);
+        assert!(list.remove(0..100));
+        assert!(list.is_empty());
+    }
+
+    #[test]
+    fn contains_and_gaps() {
+        let list: IntervalList<u32> = [2..4, 6..9].into_iter().collect();
+        assert!(list.contains(2) && list.contains(8));
+        assert!(!list.contains(4) && !list.contains(9) && !list.contains(0));
+        assert_eq!(list.find(7), Some(&(6..9)));
+        assert!(list.contains_range(&(6..9)) && !list.contains_range(&(3..7)));
+        assert_eq!(list.gaps(0..12).collect::<Vec<_>>(), vec![0..2, 4..6, 9..12]);
+        assert_eq!(list.gaps(3..7).collect::<Vec<_>>(), vec![4..6]);
+        assert_eq!(list.gaps(6..9).count(), 0);
+    }
+
+    #[test]
+    fn id_allocator() {
+        // 已分配的ID记在区间链表里，每次取第一个空隙的开头
+        let mut used = IntervalList::new();
+        let alloc = |used: &mut IntervalList<u32>| {
+            let id = used.gaps(0..u32::MAX).next().unwrap().start;
+            used.insert(id..id + 1);
+            id
+        };
+        assert_eq!((0..5).map(|_| alloc(&mut used)).collect::<Vec<_>>(), vec![0, 1, 2, 3, 4]);
+        assert_eq!(used.len(), 1);
+        used.remove(1..3);
+        assert_eq!(alloc(&mut used), 1);
+        assert_eq!(ranges(&used), vec![0..2, 3..5]);
+    }
+
+    #[test]
+    fn matches_bitmap_model() {
+        const N: u32 = 64;
+        let mut rng = XorShift::new(698);
+        let mut list = IntervalList::new();
+        let mut model = [false; N as usize];
+        for _ in 0..3000 {
+            let a = (rng.next_u64() % N as u64) as u32;
+            let b = (rng.next_u64() % N as u64) as u32;
+            let range = a.min(b)..a.max(b);
+            let insert = rng.next_u64().is_multiple_of(2);
+            let changed = model[range.start as usize..range.end as usize].iter().any(|&x| x != insert);
+            model[range.start as usize..range.end as usize].fill(insert);
+            let result = if insert { list.insert(range) } else { list.remove(range) };
+            assert_eq!(result, changed);
+            list.assert_invariants();
+            for p in 0..N {
+                assert_eq!(list.contains(p), model[p as usize]);
+            }
+        }
+        let gaps: u32 = list.gaps(0..N).map(|g| g.end - g.start).sum();
+        assert_eq!(gaps as usize, model.iter().filter(|&&x| !x).count());
+    }
+}
@@ -0,0 +1,457 @@
+// 侵入式双向链表
+// 用户类型自己内嵌一个ListLink字段，链表只把这些字段串起来，不拥有也不分配节点
+// 操作系统内核、异步运行时里的等待队列、定时器都是这种写法: 一个对象挂进链表不需要额外分配，
+// 拿到对象本身就能O(1)把它从链表里摘掉
+// 安全性的关键:
+// - 元素以Pin<&'a T>挂进链表，链表活着期间元素既不能移动也不能被释放
+// - 链表头放在单独的堆内存里，ListLink记录自己属于哪个链表，
+//   即使链表被mem::forget，元素析构时也能把自己从(泄漏的)链表里摘掉，不会留下悬垂指针
+//   Pin保证了元素在析构之前地址不变，所以这一步总是安全的
+// - 链表正常销毁时会先把所有元素摘下来
+// 所有链接字段都是Cell，只能单线程使用
+
+use std::cell::Cell;
+use std::fmt;
+use std::iter::FusedIterator;
+use std::marker::{PhantomData, PhantomPinned};
+use std::pin::Pin;
+use std::ptr::{self, NonNull};
+
+// 嵌入在用户类型里的链接字段
+pub struct ListLink {
+    prev: Cell<*const ListLink>,
+    next: Cell<*const ListLink>,
+    // 所在链表的表头，为空表示没有挂在任何链表里
+    owner: Cell<*const Head>,
+    // 链表里保存着它的地址，内嵌了ListLink的类型都是!Unpin
+    _pin: PhantomPinned,
+}
+
+impl ListLink {
+    pub const fn new() -> Self {
+        ListLink {
+            prev: Cell::new(ptr::null()),
+            next: Cell::new(ptr::null()),
+            owner: Cell::new(ptr::null()),
+            _pin: PhantomPinned,
+        }
+    }
+
+    pub fn is_linked(&self) -> bool {
+        !self.owner.get().is_null()
+    }
+
+    // 把自己从所在链表中摘下来
+    // SAFETY(调用者): self已经挂在owner指向的链表里，owner和相邻节点都还有效
+    unsafe fn unlink(&self) {
+        unsafe {
+            let owner = &*self.owner.get();
+            let prev = self.prev.get();
+            let next = self.next.get();
+            match prev.is_null() {
+                true => owner.head.set(next),
+                false => (*prev).next.set(next),
+            }
+            match next.is_null() {
+                true => owner.tail.set(prev),
+                false => (*next).prev.set(prev),
+            }
+            owner.len.set(owner.len.get() - 1);
+        }
+        self.prev.set(ptr::null());
+        self.next.set(ptr::null());
+        self.owner.set(ptr::null());
+    }
+}
+
+impl Drop for ListLink {
+    fn drop(&mut self) {
+        // 正常情况下链表还借用着元素时元素不可能被析构，能走到这里说明链表已经被forget了
+        // 表头是泄漏的堆内存，仍然有效，摘下自己保证邻居不会指向已经释放的内存
+        if self.is_linked() {
+            unsafe { self.unlink() };
+        }
+    }
+}
+
+impl Default for ListLink {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for ListLink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ListLink")
+            .field("linked", &self.is_linked())
+            .finish()
+    }
+}
+
+/// 可以放进侵入式链表的类型
+///
+/// # Safety
+///
+/// links必须返回node内嵌的那个ListLink的地址，并且每次返回同一个字段；
+/// from_links必须是它的逆运算
+pub unsafe trait Linked {
+    fn links(node: NonNull<Self>) -> NonNull<ListLink>;
+
+    /// # Safety
+    ///
+    /// link必须是某个Self节点通过links得到的地址
+    unsafe fn from_links(link: NonNull<ListLink>) -> NonNull<Self>;
+}
+
+// 表头单独放在堆上，链表本身移动时ListLink里记录的owner地址不变
+struct Head {
+    head: Cell<*const ListLink>,
+    tail: Cell<*const ListLink>,
+    len: Cell<usize>,
+}
+
+pub struct IntrusiveList<'a, T: Linked> {
+    // 用裸指针而不是Box保存，避免移动链表时对表头产生新的独占借用
+    head: NonNull<Head>,
+    _boo: PhantomData<Pin<&'a T>>,
+}
+
+impl<'a, T: Linked> IntrusiveList<'a, T> {
+    pub fn new() -> Self {
+        let head = Box::new(Head {
+            head: Cell::new(ptr::null()),
+            tail: Cell::new(ptr::null()),
+            len: Cell::new(0),
+        });
+        IntrusiveList {
+            head: NonNull::from(Box::leak(head)),
+            _boo: PhantomData,
+        }
+    }
+
+    fn raw_head(&self) -> &Head {
+        unsafe { self.head.as_ref() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.raw_head().len.get()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // 元素节点 -> 内嵌的链接字段
+    fn link_of(item: Pin<&T>) -> &ListLink {
+        let node = NonNull::from(item.get_ref());
+        unsafe { T::links(node).as_ref() }
+    }
+
+    // 链接字段 -> 元素节点，链表里的元素都被借用了'a
+    unsafe fn item_of(link: *const ListLink) -> Pin<&'a T> {
+        unsafe {
+            let node = T::from_links(NonNull::new_unchecked(link as *mut ListLink));
+            Pin::new_unchecked(&*node.as_ptr())
+        }
+    }
+
+    // 链表里保存的指针必须从整个元素的引用派生(而不是只覆盖ListLink字段的引用)，
+    // 之后才能用from_links换算回整个元素
+    fn claim(&self, item: Pin<&'a T>) -> *const ListLink {
+        let link = T::links(NonNull::from(item.get_ref())).as_ptr() as *const ListLink;
+        let link_ref = unsafe { &*link };
+        assert!(!link_ref.is_linked(), "item is already linked into a list");
+        link_ref.owner.set(self.head.as_ptr());
+        self.raw_head().len.set(self.len() + 1);
+        link
+    }
+
+    // 元素已经在某个链表里时panic
+    pub fn push_front(&mut self, item: Pin<&'a T>) {
+        let link = self.claim(item);
+        let head = self.raw_head();
+        let old = head.head.get();
+        unsafe { (*link).next.set(old) };
+        match old.is_null() {
+            true => head.tail.set(link),
+            false => unsafe { (*old).prev.set(link) },
+        }
+        head.head.set(link);
+    }
+
+    pub fn push_back(&mut self, item: Pin<&'a T>) {
+        let link = self.claim(item);
+        let head = self.raw_head();
+        let old = head.tail.get();
+        unsafe { (*link).prev.set(old) };
+        match old.is_null() {
+            true => head.head.set(link),
+            false => unsafe { (*old).next.set(link) },
+        }
+        head.tail.set(link);
+    }
+
+    pub fn pop_front(&mut self) -> Option<Pin<&'a T>> {
+        let link = self.raw_head().head.get();
+        if link.is_null() {
+            return None;
+        }
+        unsafe {
+            (*link).unlink();
+            Some(Self::item_of(link))
+        }
+    }
+
+    pub fn pop_back(&mut self) -> Option<Pin<&'a T>> {
+        let link = self.raw_head().tail.get();
+        if link.is_null() {
+            return None;
+        }
+        unsafe {
+            (*link).unlink();
+            Some(Self::item_of(link))
+        }
+    }
+
+    pub fn front(&self) -> Option<Pin<&'a T>> {
+        let link = self.raw_head().head.get();
+        (!link.is_null()).then(|| unsafe { Self::item_of(link) })
+    }
+
+    pub fn back(&self) -> Option<Pin<&'a T>> {
+        let link = self.raw_head().tail.get();
+        (!link.is_null()).then(|| unsafe { Self::item_of(link) })
+    }
+
+    // item是否挂在这个链表里，O(1)
+    pub fn contains(&self, item: Pin<&T>) -> bool {
+        ptr::eq(Self::link_of(item).owner.get(), self.head.as_ptr())
+    }
+
+    // 把item从这个链表里摘下来，O(1)；不在这个链表里时返回false
+    pub fn remove(&mut self, item: Pin<&T>) -> bool {
+        if !self.contains(item) {
+            return false;
+        }
+        unsafe { Self::link_of(item).unlink() };
+        true
+    }
+
+    // 摘下所有元素，元素本身不受影响
+    pub fn clear(&mut self) {
+        while self.pop_front().is_some() {}
+    }
+
+    pub fn iter(&self) -> Iter<'_, 'a, T> {
+        let head = self.raw_head();
+        Iter {
+            head: head.head.get(),
+            tail: head.tail.get(),
+            len: head.len.get(),
+            _boo: PhantomData,
+        }
+    }
+}
+
+impl<T: Linked> Drop for IntrusiveList<'_, T> {
+    fn drop(&mut self) {
+        self.clear();
+        unsafe { drop(Box::from_raw(self.head.as_ptr())) };
+    }
+}
+
+impl<T: Linked> Default for IntrusiveList<'_, T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Linked + fmt::Debug> fmt::Debug for IntrusiveList<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+pub struct Iter<'l, 'a, T> {
+    head: *const ListLink,
+    tail: *const ListLink,
+    len: usize,
+    _boo: PhantomData<(&'l (), Pin<&'a T>)>,
+}
+
+impl<'a, T: Linked> Iterator for Iter<'_, 'a, T> {
+    type Item = Pin<&'a T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        self.len -= 1;
+        let link = self.head;
+        unsafe {
+            self.head = (*link).next.get();
+            Some(IntrusiveList::<T>::item_of(link))
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T: Linked> DoubleEndedIterator for Iter<'_, '_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        self.len -= 1;
+        let link = self.tail;
+        unsafe {
+            self.tail = (*link).prev.get();
+            Some(IntrusiveList::<T>::item_of(link))
+        }
+    }
+}
+
+impl<T: Linked> ExactSizeIterator for Iter<'_, '_, T> {}
+impl<T: Linked> FusedIterator for Iter<'_, '_, T> {}
+
+impl<'l, 'a, T: Linked> IntoIterator for &'l IntrusiveList<'a, T> {
+    type Item = Pin<&'a T>;
+    type IntoIter = Iter<'l, 'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::mem::offset_of;
+    use std::pin::pin;
+
+    #[derive(Debug)]
+    struct Task {
+        id: u32,
+        link: ListLink,
+    }
+
+    unsafe impl Linked for Task {
+        fn links(node: NonNull<Self>) -> NonNull<ListLink> {
+            unsafe { NonNull::new_unchecked(ptr::addr_of_mut!((*node.as_ptr()).link)) }
+        }
+
+        unsafe fn from_links(link: NonNull<ListLink>) -> NonNull<Self> {
+            let base = (link.as_ptr() as *mut u8).sub(offset_of!(Task, link));
+            NonNull::new_unchecked(base as *mut Task)
+        }
+    }
+
+    fn task(id: u32) -> Task {
+        Task {
+            id,
+            link: ListLink::new(),
+        }
+    }
+
+    fn ids(list: &IntrusiveList<'_, Task>) -> Vec<u32> {
+        list.iter().map(|t| t.id).collect()
+    }
+
+    #[test]
+    fn push_pop_both_ends() {
+        let a = pin!(task(1));
+        let b = pin!(task(2));
+        let c = pin!(task(3));
+        let mut list = IntrusiveList::new();
+        list.push_back(a.as_ref());
+        list.push_back(b.as_ref());
+        list.push_front(c.as_ref());
+        assert_eq!(list.len(), 3);
+        assert_eq!(ids(&list), vec![3, 1, 2]);
+        assert_eq!(list.iter().rev().map(|t| t.id).collect::<Vec<_>>(), vec![2, 1, 3]);
+        assert_eq!(list.front().map(|t| t.id), Some(3));
+        assert_eq!(list.back().map(|t| t.id), Some(2));
+        assert_eq!(list.pop_front().map(|t| t.id), Some(3));
+        assert_eq!(list.pop_back().map(|t| t.id), Some(2));
+        assert!(!b.link.is_linked() && a.link.is_linked());
+        assert_eq!(list.pop_back().map(|t| t.id), Some(1));
+        assert!(list.pop_front().is_none());
+        assert!(list.is_empty());
+    }
+
+    #[test]
+    fn remove_by_reference() {
+        let tasks: Vec<Pin<Box<Task>>> = (0..4).map(|i| Box::pin(task(i))).collect();
+        let item = |i: usize| tasks[i].as_ref();
+        let mut list = IntrusiveList::new();
+        let mut other = IntrusiveList::new();
+        for i in 0..3 {
+            list.push_back(item(i));
+        }
+        other.push_back(item(3));
+        assert!(list.remove(item(1)));
+        assert!(!list.remove(item(1)));
+        // 别的链表里的元素不会被误删
+        assert!(!list.remove(item(3)));
+        assert!(other.contains(item(3)));
+        assert_eq!(ids(&list), vec![0, 2]);
+        assert!(list.remove(item(2)));
+        assert!(list.remove(item(0)));
+        assert!(list.is_empty());
+        // 摘下来之后可以挂到另一个链表
+        other.push_front(item(1));
+        assert_eq!(ids(&other), vec![1, 3]);
+    }
+
+    #[test]
+    #[should_panic(expected = "already linked")]
+    fn double_insert_panics() {
+        let a = pin!(task(1));
+        let mut first = IntrusiveList::new();
+        let mut second = IntrusiveList::new();
+        first.push_back(a.as_ref());
+        second.push_back(a.as_ref());
+    }
+
+    #[test]
+    fn dropping_list_unlinks_items() {
+        let a = pin!(task(1));
+        let b = pin!(task(2));
+        {
+            let mut list = IntrusiveList::new();
+            list.push_back(a.as_ref());
+            list.push_back(b.as_ref());
+        }
+        assert!(!a.link.is_linked());
+        assert!(!b.link.is_linked());
+        // 可以再放进新的链表
+        let mut list = IntrusiveList::new();
+        list.push_back(b.as_ref());
+        assert_eq!(ids(&list), vec![2]);
+    }
+
+    #[test]
+    fn item_drop_unlinks_from_forgotten_list() {
+        let a = Box::pin(task(1));
+        let b = Box::pin(task(2));
+        let c = Box::pin(task(3));
+        let mut list = IntrusiveList::new();
+        list.push_back(a.as_ref());
+        list.push_back(b.as_ref());
+        list.push_back(c.as_ref());
+        let head = list.head;
+        std::mem::forget(list);
+        // 链表泄漏后元素照样可以析构，析构时把自己从链表里摘掉
+        drop(b);
+        let head = unsafe { head.as_ref() };
+        assert_eq!(head.len.get(), 2);
+        assert!(ptr::eq(a.link.next.get(), &c.link));
+        assert!(ptr::eq(c.link.prev.get(), &a.link));
+        drop(a);
+        drop(c);
+        assert_eq!(head.len.get(), 0);
+        assert!(head.head.get().is_null() && head.tail.get().is_null());
+    }
+}
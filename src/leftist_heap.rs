@@ -0,0 +1,232 @@
+// 左偏堆(leftist heap)，可合并最小堆，节点用Box链接成二叉树
+// 每个节点记一个rank: 到最近的空孩子的距离(右路径长度)，空树rank为0
+// 左偏性质: 左孩子的rank不小于右孩子，所以右路径最多log2(n+1)个节点
+// merge只沿右路径递归，深度O(log n)，递归写法正好展示子树所有权在调用间的移交:
+// 取走较小根的右子树、与另一棵递归merge、结果挂回去，必要时交换左右孩子恢复左偏
+// 左路径可以很长，所以drop仍然要写成循环
+
+use std::fmt;
+use std::mem;
+
+type Link<T> = Option<Box<Node<T>>>;
+
+struct Node<T> {
+    elem: T,
+    rank: usize,
+    left: Link<T>,
+    right: Link<T>,
+}
+
+fn rank<T>(link: &Link<T>) -> usize {
+    link.as_ref().map_or(0, |node| node.rank)
+}
+
+pub struct LeftistHeap<T> {
+    root: Link<T>,
+    len: usize,
+}
+
+impl<T: Ord> LeftistHeap<T> {
+    pub fn new() -> Self {
+        LeftistHeap { root: None, len: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn push(&mut self, elem: T) {
+        let node = Box::new(Node {
+            elem,
+            rank: 1,
+            left: None,
+            right: None,
+        });
+        self.root = merge(self.root.take(), Some(node));
+        self.len += 1;
+    }
+
+    pub fn peek(&self) -> Option<&T> {
+        self.root.as_ref().map(|node| &node.elem)
+    }
+
+    pub fn pop_min(&mut self) -> Option<T> {
+        self.root.take().map(|node| {
+            let node = *node;
+            self.root = merge(node.left, node.right);
+            self.len -= 1;
+            node.elem
+        })
+    }
+
+    // 把other整个并进来，O(log n)
+    pub fn merge(&mut self, mut other: LeftistHeap<T>) {
+        self.root = merge(self.root.take(), other.root.take());
+        self.len += mem::take(&mut other.len);
+    }
+
+    pub fn clear(&mut self) {
+        drop_tree(self.root.take());
+        self.len = 0;
+    }
+
+    // 按升序取出所有元素
+    pub fn into_sorted_vec(mut self) -> Vec<T> {
+        let mut out = Vec::with_capacity(self.len);
+        while let Some(x) = self.pop_min() {
+            out.push(x);
+        }
+        out
+    }
+}
+
+fn merge<T: Ord>(a: Link<T>, b: Link<T>) -> Link<T> {
+    match (a, b) {
+        (None, rest) | (rest, None) => rest,
+        (Some(x), Some(y)) => {
+            let (mut root, other) = if y.elem < x.elem { (y, x) } else { (x, y) };
+            root.right = merge(root.right.take(), Some(other));
+            if rank(&root.left) < rank(&root.right) {
+                mem::swap(&mut root.left, &mut root.right);
+            }
+            root.rank = rank(&root.right) + 1;
+            Some(root)
+        }
+    }
+}
+
+// 逐层拆开，避免长的左路径递归drop爆栈
+fn drop_tree<T>(root: Link<T>) {
+    let mut stack: Vec<Box<Node<T>>> = root.into_iter().collect();
+    while let Some(mut node) = stack.pop() {
+        stack.extend(node.left.take());
+        stack.extend(node.right.take());
+    }
+}
+
+impl<T> Drop for LeftistHeap<T> {
+    fn drop(&mut self) {
+        drop_tree(self.root.take());
+    }
+}
+
+impl<T: Ord> Default for LeftistHeap<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Ord + fmt::Debug> fmt::Debug for LeftistHeap<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LeftistHeap")
+            .field("len", &self.len)
+            .field("min", &self.peek())
+            .finish()
+    }
+}
+
+impl<T: Ord> Extend<T> for LeftistHeap<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for elem in iter {
+            self.push(elem);
+        }
+    }
+}
+
+impl<T: Ord> FromIterator<T> for LeftistHeap<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut heap = Self::new();
+        heap.extend(iter);
+        heap
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::XorShift;
+    use std::cmp::Reverse;
+    use std::collections::BinaryHeap;
+
+    // 检查堆序、rank和左偏性质，返回节点数
+    fn check<T: Ord>(link: &Link<T>) -> usize {
+        let Some(node) = link else { return 0 };
+        for child in [&node.left, &node.right].into_iter().flatten() {
+            assert!(node.elem <= child.elem);
+        }
+        assert!(rank(&node.left) >= rank(&node.right));
+        assert_eq!(node.rank, rank(&node.right) + 1);
+        1 + check(&node.left) + check(&node.right)
+    }
+
+    #[test]
+    fn push_pop_in_order() {
+        let mut heap = LeftistHeap::new();
+        assert_eq!(heap.pop_min(), None);
+        heap.extend([5, 3, 8, 1, 9, 2, 7, 3]);
+        assert_eq!(check(&heap.root), 8);
+        assert_eq!(heap.peek(), Some(&1));
+        assert_eq!(heap.into_sorted_vec(), vec![1, 2, 3, 3, 5, 7, 8, 9]);
+    }
+
+    #[test]
+    fn merge_two_heaps() {
+        let mut a: LeftistHeap<i32> = [4, 8, 1].into_iter().collect();
+        let b: LeftistHeap<i32> = [6, 0, 9].into_iter().collect();
+        a.merge(b);
+        assert_eq!(check(&a.root), 6);
+        assert_eq!(a.pop_min(), Some(0));
+        a.merge(LeftistHeap::new());
+        assert_eq!(a.into_sorted_vec(), vec![1, 4, 6, 8, 9]);
+    }
+
+    #[test]
+    fn right_spine_is_logarithmic() {
+        let mut heap: LeftistHeap<u32> = (0..100_000).rev().collect();
+        // rank就是右路径长度
+        assert!(rank(&heap.root) <= 17);
+        assert_eq!(heap.pop_min(), Some(0));
+        heap.clear();
+        assert!(heap.is_empty());
+        // 连续merge单节点堆让树退化成一条很长的左路径，drop不能递归
+        let mut heap = LeftistHeap::new();
+        for x in (0..100_000).rev() {
+            let mut single = LeftistHeap::new();
+            single.push(x);
+            single.merge(heap);
+            heap = single;
+        }
+        assert_eq!(rank(&heap.root), 1);
+        drop(heap);
+    }
+
+    #[test]
+    fn randomized_against_binary_heap() {
+        let mut rng = XorShift::new(0x1ef7);
+        let mut heap = LeftistHeap::new();
+        let mut reference = BinaryHeap::new();
+        for _ in 0..2000 {
+            match rng.next_u64() % 6 {
+                0..=2 => {
+                    let x = rng.next_u64() % 1000;
+                    heap.push(x);
+                    reference.push(Reverse(x));
+                }
+                3 | 4 => assert_eq!(heap.pop_min(), reference.pop().map(|Reverse(x)| x)),
+                _ => {
+                    let n = rng.next_u64() % 8;
+                    let xs: Vec<u64> = (0..n).map(|_| rng.next_u64() % 1000).collect();
+                    reference.extend(xs.iter().map(|&x| Reverse(x)));
+                    heap.merge(xs.into_iter().collect());
+                }
+            }
+            assert_eq!(check(&heap.root), reference.len());
+            assert_eq!(heap.len(), reference.len());
+            assert_eq!(heap.peek(), reference.peek().map(|Reverse(x)| x));
+        }
+    }
+}
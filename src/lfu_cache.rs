@@ -0,0 +1,470 @@
+// LFU(最不经常使用)缓存，和lru_cache互补: 淘汰的是访问次数最少的项，次数相同时淘汰其中最久没用的
+// 经典的O(1)做法是"链表套链表":
+// - 外层链表按访问次数从小到大串起频率桶，每个桶只存一个访问次数
+// - 每个桶里是一条内层链表，存这个次数下的所有项，头部最近使用
+// - HashMap把键映射到(桶句柄, 项句柄)
+// 访问一次就把项从freq桶挪到紧挨着的freq+1桶(没有就在后面新建一个)，旧桶空了就删掉，
+// 所以外层链表的头部永远是最小的访问次数，淘汰时取头部桶的尾部项即可，全程O(1)
+// 两层链表都用simple_deque_3::List的节点句柄接口
+
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+
+use crate::simple_deque_3::{List, NodeHandle};
+
+struct Bucket<K, V> {
+    freq: usize,
+    // 头部最近使用
+    items: List<(K, V)>,
+}
+
+struct Slot<K, V> {
+    bucket: NodeHandle<Bucket<K, V>>,
+    item: NodeHandle<(K, V)>,
+}
+
+pub struct LfuCache<K, V> {
+    map: HashMap<K, Slot<K, V>>,
+    // 按freq严格递增，不存在空桶
+    buckets: List<Bucket<K, V>>,
+    capacity: usize,
+}
+
+impl<K: Hash + Eq + Clone, V> LfuCache<K, V> {
+    // capacity为0时panic
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "capacity must be positive");
+        LfuCache {
+            map: HashMap::with_capacity(capacity),
+            buckets: List::new(),
+            capacity,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn clear(&mut self) {
+        self.map.clear();
+        self.buckets.clear();
+    }
+
+    // 插入或更新，更新也算一次访问；返回同一个key的旧值
+    // 新key导致超出容量时先淘汰访问次数最少的项，新项从次数1开始
+    pub fn put(&mut self, key: K, value: V) -> Option<V> {
+        if let Some(slot) = self.map.get_mut(&key) {
+            // SAFETY: map里的句柄都指向buckets中仍然存在的桶和项
+            unsafe {
+                touch(&mut self.buckets, slot);
+                let bucket = self.buckets.get_handle_mut(slot.bucket);
+                return Some(std::mem::replace(&mut bucket.items.get_handle_mut(slot.item).1, value));
+            }
+        }
+        if self.len() == self.capacity {
+            self.pop_lfu();
+        }
+        let bucket = match self.buckets.front_handle() {
+            Some(front) if unsafe { self.buckets.get_handle(front).freq } == 1 => front,
+            _ => self.buckets.push_front_handle(Bucket {
+                freq: 1,
+                items: List::new(),
+            }),
+        };
+        let item = unsafe { self.buckets.get_handle_mut(bucket) }
+            .items
+            .push_front_handle((key.clone(), value));
+        self.map.insert(key, Slot { bucket, item });
+        None
+    }
+
+    // 命中时访问次数加一
+    pub fn get<Q>(&mut self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get_mut(key).map(|v| &*v)
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let slot = self.map.get_mut(key)?;
+        unsafe {
+            touch(&mut self.buckets, slot);
+            let bucket = self.buckets.get_handle_mut(slot.bucket);
+            Some(&mut bucket.items.get_handle_mut(slot.item).1)
+        }
+    }
+
+    // 只读，不增加访问次数
+    pub fn peek<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let slot = self.map.get(key)?;
+        unsafe {
+            let bucket = self.buckets.get_handle(slot.bucket);
+            Some(&bucket.items.get_handle(slot.item).1)
+        }
+    }
+
+    // 当前的访问次数
+    pub fn frequency<Q>(&self, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let slot = self.map.get(key)?;
+        Some(unsafe { self.buckets.get_handle(slot.bucket) }.freq)
+    }
+
+    pub fn contains<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map.contains_key(key)
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let slot = self.map.remove(key)?;
+        unsafe {
+            let bucket = self.buckets.get_handle_mut(slot.bucket);
+            let (_, value) = bucket.items.remove_handle(slot.item);
+            if bucket.items.is_empty() {
+                self.buckets.remove_handle(slot.bucket);
+            }
+            Some(value)
+        }
+    }
+
+    // 下一个会被淘汰的项
+    pub fn peek_lfu(&self) -> Option<(&K, &V)> {
+        self.buckets
+            .front()
+            .and_then(|bucket| bucket.items.back())
+            .map(|(k, v)| (k, v))
+    }
+
+    // 淘汰访问次数最少的项，次数相同时淘汰最久没用的
+    pub fn pop_lfu(&mut self) -> Option<(K, V)> {
+        let front = self.buckets.front_handle()?;
+        let bucket = unsafe { self.buckets.get_handle_mut(front) };
+        let (key, value) = bucket.items.pop_back()?;
+        if bucket.items.is_empty() {
+            unsafe { self.buckets.remove_handle(front) };
+        }
+        self.map.remove(&key);
+        Some((key, value))
+    }
+}
+
+// 把项从当前桶挪到freq+1的桶，更新slot里的两个句柄
+unsafe fn touch<K, V>(buckets: &mut List<Bucket<K, V>>, slot: &mut Slot<K, V>) {
+    unsafe {
+        let bucket = buckets.get_handle_mut(slot.bucket);
+        let freq = bucket.freq;
+        let entry = bucket.items.remove_handle(slot.item);
+        let emptied = bucket.items.is_empty();
+        let target = match buckets.next_handle(slot.bucket) {
+            Some(next) if buckets.get_handle(next).freq == freq + 1 => next,
+            _ => buckets.insert_after_handle(
+                slot.bucket,
+                Bucket {
+                    freq: freq + 1,
+                    items: List::new(),
+                },
+            ),
+        };
+        if emptied {
+            buckets.remove_handle(slot.bucket);
+        }
+        slot.bucket = target;
+        slot.item = buckets.get_handle_mut(target).items.push_front_handle(entry);
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for LfuCache<K, V> {
+    // 按淘汰顺序的反方向打印: 访问次数从高到低，同一次数内从新到旧
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(
+                self.buckets
+                    .iter()
+                    .rev()
+                    .flat_map(|bucket| bucket.items.iter().map(|(k, v)| (k, v))),
+            )
+            .finish()
+    }
+}
+
+unsafe impl<K: Send, V: Send> Send for LfuCache<K, V> {}
+unsafe impl<K: Sync, V: Sync> Sync for LfuCache<K, V> {}
+
+// serde: 写成{capacity, entries}，entries按淘汰顺序排列，每项是(键, 值, 访问次数):
+// 访问次数从小到大，同一次数内从最久没用到最近使用
+// 读回时按次数稳定排序后直接重建频率桶，不用把每一项重新访问一遍
+#[cfg(feature = "serde")]
+mod serde_impl {
+    use std::collections::HashMap;
+    use std::hash::Hash;
+
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::{Bucket, LfuCache, Slot};
+    use crate::simple_deque_3::List;
+
+    #[derive(Serialize)]
+    struct CacheRef<'a, K, V> {
+        capacity: usize,
+        entries: Entries<'a, K, V>,
+    }
+
+    #[derive(Deserialize)]
+    struct CacheOwned<K, V> {
+        capacity: usize,
+        entries: Vec<(K, V, usize)>,
+    }
+
+    struct Entries<'a, K, V>(&'a List<Bucket<K, V>>);
+
+    impl<K: Serialize, V: Serialize> Serialize for Entries<'_, K, V> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.collect_seq(
+                self.0
+                    .iter()
+                    .flat_map(|bucket| bucket.items.iter().rev().map(move |(k, v)| (k, v, bucket.freq))),
+            )
+        }
+    }
+
+    impl<K: Serialize, V: Serialize> Serialize for LfuCache<K, V> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            CacheRef {
+                capacity: self.capacity,
+                entries: Entries(&self.buckets),
+            }
+            .serialize(serializer)
+        }
+    }
+
+    impl<'de, K, V> Deserialize<'de> for LfuCache<K, V>
+    where
+        K: Deserialize<'de> + Hash + Eq + Clone,
+        V: Deserialize<'de>,
+    {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let CacheOwned { capacity, mut entries } = CacheOwned::<K, V>::deserialize(deserializer)?;
+            if capacity == 0 {
+                return Err(D::Error::custom("capacity must be positive"));
+            }
+            if entries.len() > capacity {
+                return Err(D::Error::custom("more entries than capacity"));
+            }
+            entries.sort_by_key(|entry| entry.2);
+            // capacity来自输入，只按实际的项数预留
+            let mut map = HashMap::with_capacity(entries.len());
+            let mut buckets = List::new();
+            let mut last = None;
+            for (key, value, freq) in entries {
+                if freq == 0 {
+                    return Err(D::Error::custom("frequency must be positive"));
+                }
+                if map.contains_key(&key) {
+                    return Err(D::Error::custom("duplicate key"));
+                }
+                let bucket = match last {
+                    // 和上一项次数相同就放进同一个桶，排过序所以桶的次数严格递增
+                    Some((handle, f)) if f == freq => handle,
+                    _ => buckets.push_back_handle(Bucket { freq, items: List::new() }),
+                };
+                last = Some((bucket, freq));
+                // SAFETY: bucket指向buckets里仍然存在的桶
+                let item = unsafe { buckets.get_handle_mut(bucket) }
+                    .items
+                    .push_front_handle((key.clone(), value));
+                map.insert(key, Slot { bucket, item });
+            }
+            Ok(LfuCache { map, buckets, capacity })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::XorShift;
+
+    // 外层频率严格递增、没有空桶、项数和map一致
+    fn check<K: Hash + Eq + Clone, V>(cache: &LfuCache<K, V>) {
+        let freqs: Vec<usize> = cache.buckets.iter().map(|b| b.freq).collect();
+        assert!(freqs.windows(2).all(|w| w[0] < w[1]));
+        assert!(cache.buckets.iter().all(|b| !b.items.is_empty()));
+        let total: usize = cache.buckets.iter().map(|b| b.items.len()).sum();
+        assert_eq!(total, cache.map.len());
+    }
+
+    #[test]
+    fn evicts_least_frequent() {
+        let mut cache = LfuCache::new(2);
+        cache.put("a", 1);
+        cache.put("b", 2);
+        assert_eq!(cache.get("a"), Some(&1));
+        assert_eq!(cache.get("a"), Some(&1));
+        assert_eq!(cache.frequency("a"), Some(3));
+        assert_eq!(cache.frequency("b"), Some(1));
+        cache.put("c", 3);
+        assert!(!cache.contains("b"));
+        assert!(cache.contains("a") && cache.contains("c"));
+        check(&cache);
+    }
+
+    #[test]
+    fn ties_broken_by_recency() {
+        let mut cache = LfuCache::new(3);
+        cache.put(1, "one");
+        cache.put(2, "two");
+        cache.put(3, "three");
+        // 都是次数1，1号最久没用
+        assert_eq!(cache.peek_lfu(), Some((&1, &"one")));
+        cache.get(&1);
+        cache.get(&2);
+        // 3号次数最少
+        assert_eq!(cache.pop_lfu(), Some((3, "three")));
+        // 1和2次数相同，1号较早被访问
+        assert_eq!(cache.peek_lfu(), Some((&1, &"one")));
+        check(&cache);
+    }
+
+    #[test]
+    fn update_counts_as_access() {
+        let mut cache = LfuCache::new(2);
+        cache.put("x", 1);
+        assert_eq!(cache.put("x", 2), Some(1));
+        assert_eq!(cache.frequency("x"), Some(2));
+        assert_eq!(cache.peek("x"), Some(&2));
+        assert_eq!(cache.frequency("x"), Some(2));
+        *cache.get_mut("x").unwrap() += 1;
+        assert_eq!(cache.remove("x"), Some(3));
+        assert!(cache.is_empty());
+        assert_eq!(cache.pop_lfu(), None);
+        check(&cache);
+    }
+
+    #[test]
+    fn debug_order() {
+        let mut cache = LfuCache::new(3);
+        cache.put('a', 0);
+        cache.put('b', 0);
+        cache.put('c', 0);
+        cache.get(&'a');
+        assert_eq!(format!("{cache:?}"), "{'a': 0, 'c': 0, 'b': 0}");
+    }
+
+    #[test]
+    fn randomized_against_model() {
+        // 模型: (key, value, freq, last_used)，淘汰freq最小、last_used最早的
+        let mut rng = XorShift::new(0x1f0);
+        let mut cache = LfuCache::new(6);
+        let mut model: Vec<(u64, u64, usize, usize)> = Vec::new();
+        for tick in 0..3000 {
+            let key = rng.next_u64() % 12;
+            let pos = model.iter().position(|e| e.0 == key);
+            match rng.next_u64() % 3 {
+                0 => {
+                    let value = tick as u64;
+                    let old = cache.put(key, value);
+                    match pos {
+                        Some(p) => {
+                            let e = &mut model[p];
+                            assert_eq!(old, Some(e.1));
+                            *e = (key, value, e.2 + 1, tick);
+                        }
+                        None => {
+                            assert_eq!(old, None);
+                            if model.len() == 6 {
+                                let victim = (0..model.len()).min_by_key(|&i| (model[i].2, model[i].3)).unwrap();
+                                model.remove(victim);
+                            }
+                            model.push((key, value, 1, tick));
+                        }
+                    }
+                }
+                1 => {
+                    let got = cache.get(&key).copied();
+                    assert_eq!(got, pos.map(|p| model[p].1));
+                    if let Some(p) = pos {
+                        model[p].2 += 1;
+                        model[p].3 = tick;
+                    }
+                }
+                _ => {
+                    if rng.next_u64().is_multiple_of(4) {
+                        assert_eq!(cache.remove(&key), pos.map(|p| model.remove(p).1));
+                    }
+                }
+            }
+            check(&cache);
+        }
+        for (key, value, freq, _) in &model {
+            assert_eq!(cache.peek(key), Some(value));
+            assert_eq!(cache.frequency(key), Some(*freq));
+        }
+    }
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_keeps_frequencies() {
+        let mut cache = LfuCache::new(3);
+        cache.put('a', 1);
+        cache.put('b', 2);
+        cache.put('c', 3);
+        cache.get(&'a');
+        cache.get(&'c');
+        cache.get(&'a');
+        let json = serde_json::to_string(&cache).unwrap();
+        assert_eq!(json, r#"{"capacity":3,"entries":[["b",2,1],["c",3,2],["a",1,3]]}"#);
+        let mut back: LfuCache<char, i32> = serde_json::from_str(&json).unwrap();
+        check(&back);
+        assert_eq!(back.frequency(&'a'), Some(3));
+        assert_eq!(format!("{back:?}"), format!("{cache:?}"));
+        assert_eq!(back.pop_lfu(), Some(('b', 2)));
+
+        // 输入里的次数不一定有序，同一次数内保持原来的先后
+        let back: LfuCache<char, i32> =
+            serde_json::from_str(r#"{"capacity":3,"entries":[["x",0,2],["y",0,1],["z",0,2]]}"#).unwrap();
+        check(&back);
+        assert_eq!(format!("{back:?}"), "{'z': 0, 'x': 0, 'y': 0}");
+        for bad in [
+            r#"{"capacity":1,"entries":[["x",0,1],["y",0,1]]}"#,
+            r#"{"capacity":2,"entries":[["x",0,1],["x",0,2]]}"#,
+            r#"{"capacity":2,"entries":[["x",0,0]]}"#,
+        ] {
+            assert!(serde_json::from_str::<LfuCache<char, i32>>(bad).is_err());
+        }
+
+        // capacity只是上限，不按它预先分配
+        let json = r#"{"capacity":1000000000000000000,"entries":[[1,2,1]]}"#;
+        let back: LfuCache<u32, u32> = serde_json::from_str(json).unwrap();
+        assert_eq!((back.capacity(), back.len()), (1_000_000_000_000_000_000, 1));
+    }
+}
@@ -1,12 +1,294 @@
+// 模块按特性分成四组，默认全部开启；只想要safe教学链表的用户可以
+// default-features = false, features = ["safe"]，不用编译任何unsafe和原子操作的代码
+// - safe          不含unsafe的链表，以及只建立在它们之上的结构
+// - persistent    不可变、共享后缀的持久化链表
+// - unsafe-impls  裸指针实现的链表，以及建立在它们之上的结构(哪怕自身没有unsafe)
+// - concurrent    原子操作和锁实现的并发结构、内存回收，依赖unsafe-impls
 // 一个不太好的单链表栈实现
+#[cfg(feature = "safe")]
 pub mod simple_stack_1;
 // 一个更好的单链表栈实现
+#[cfg(feature = "safe")]
 pub mod simple_stack_2;
 // 一个持久的单链表栈实现
+#[cfg(feature = "persistent")]
 pub mod simple_stack_3;
 // 一个不好的safe双向链表实现
+#[cfg(feature = "safe")]
 pub mod simple_deque_1;
-// 一个更好的unsafe双向链表实现
+// 一个还OK的unsafe单向队列实现
+#[cfg(feature = "unsafe-impls")]
 pub mod simple_deque_2;
 // 一个产品级的双向链表实现
-pub mod simple_deque_3;
\ No newline at end of file
+#[cfg(feature = "unsafe-impls")]
+pub mod simple_deque_3;
+// 基于simple_deque_3的线程安全阻塞双端队列
+#[cfg(feature = "concurrent")]
+pub mod sync_deque;
+// 原子类型的统一入口，loom测试时替换成模拟实现
+#[cfg(feature = "concurrent")]
+mod sync;
+// 结构性修改的tracing埋点宏，开启tracing特性时才生成代码
+#[cfg(any(feature = "safe", feature = "unsafe-impls"))]
+mod instrument;
+// 基于epoch的内存回收，供无锁结构使用
+#[cfg(feature = "concurrent")]
+pub mod epoch;
+// 基于危险指针的内存回收，epoch之外的另一种选择
+#[cfg(feature = "concurrent")]
+pub mod hazard;
+// 无锁结构可选的内存回收策略
+#[cfg(feature = "concurrent")]
+pub mod reclaim;
+// Treiber无锁栈
+#[cfg(feature = "concurrent")]
+pub mod treiber_stack;
+// Michael-Scott无锁MPMC队列
+#[cfg(feature = "concurrent")]
+pub mod ms_queue;
+// 单生产者单消费者无等待队列
+#[cfg(feature = "concurrent")]
+pub mod spsc_queue;
+// Vyukov风格的侵入式多生产者单消费者队列
+#[cfg(feature = "concurrent")]
+pub mod mpsc_queue;
+// Chase-Lev工作窃取双端队列
+#[cfg(feature = "concurrent")]
+pub mod chase_lev_deque;
+// 基于simple_deque_2的有界阻塞队列，支持关闭
+#[cfg(feature = "concurrent")]
+pub mod blocking_queue;
+// 基于Waker的异步队列，pop可以await
+#[cfg(feature = "concurrent")]
+pub mod async_queue;
+// loom模型检查，RUSTFLAGS="--cfg loom" cargo test --release loom_tests
+#[cfg(all(test, loom, feature = "concurrent"))]
+mod loom_tests;
+// 跳表实现的有序映射
+#[cfg(feature = "unsafe-impls")]
+pub mod skip_list_map;
+// 跳表实现的有序集合
+#[cfg(feature = "unsafe-impls")]
+pub mod skip_list_set;
+// 基于跳表的无锁并发有序映射和集合
+#[cfg(feature = "concurrent")]
+pub mod concurrent_skip_list;
+// 异或链表(unsafe教学示例)
+#[cfg(feature = "unsafe-impls")]
+pub mod xor_list;
+// 每个节点存多个元素的展开链表
+#[cfg(feature = "unsafe-impls")]
+pub mod unrolled_list;
+// 少量元素内联存储、超出后溢出成链表的SmallList
+#[cfg(feature = "unsafe-impls")]
+pub mod small_list;
+// 不需要分配器的固定容量数组链表
+#[cfg(feature = "unsafe-impls")]
+pub mod static_list;
+// 节点放在slab中、用代数句柄访问的双向链表
+#[cfg(feature = "unsafe-impls")]
+pub mod slab_list;
+// 节点从arena批量分配的双向链表
+#[cfg(feature = "unsafe-impls")]
+pub mod arena_list;
+// 多个链表共享的节点内存池
+#[cfg(feature = "unsafe-impls")]
+pub mod node_pool;
+// 元素内嵌链接字段、以Pin挂入的侵入式双向链表
+#[cfg(feature = "unsafe-impls")]
+pub mod intrusive_list;
+// GhostCell品牌生命周期实现的零运行时检查安全双向链表
+#[cfg(feature = "unsafe-impls")]
+pub mod ghost_list;
+// 带哨兵节点的循环双向链表
+#[cfg(feature = "unsafe-impls")]
+pub mod sentinel_list;
+// 循环单向链表，带游标、轮转和约瑟夫问题
+#[cfg(feature = "unsafe-impls")]
+pub mod ring;
+// 保持插入顺序的哈希表
+#[cfg(feature = "unsafe-impls")]
+pub mod linked_hash_map;
+// 保持插入顺序的哈希集合
+#[cfg(feature = "unsafe-impls")]
+pub mod linked_hash_set;
+// 基于双向链表和哈希表的LRU缓存
+#[cfg(feature = "unsafe-impls")]
+pub mod lru_cache;
+// 频率桶链表实现的O(1) LFU缓存
+#[cfg(feature = "unsafe-impls")]
+pub mod lfu_cache;
+// 插入时保持有序的链表
+#[cfg(feature = "unsafe-impls")]
+pub mod sorted_list;
+// 用子节点/兄弟链接实现的配对堆
+#[cfg(feature = "safe")]
+pub mod pairing_heap;
+// 自调整的可合并斜堆
+#[cfg(feature = "safe")]
+pub mod skew_heap;
+// 按rank保持左偏的可合并堆
+#[cfg(feature = "safe")]
+pub mod leftist_heap;
+// 由二项树组成的可合并堆
+#[cfg(feature = "safe")]
+pub mod binomial_heap;
+// 字符串分块挂在链表上的rope
+#[cfg(feature = "unsafe-impls")]
+pub mod rope;
+// 桶为侵入式链表的分层时间轮
+#[cfg(feature = "unsafe-impls")]
+pub mod timer_wheel;
+// 撤销/重做历史
+#[cfg(feature = "unsafe-impls")]
+pub mod history;
+// 有序项链表表示的多项式
+#[cfg(feature = "unsafe-impls")]
+pub mod polynomial;
+// limb存在链表里的任意精度无符号整数
+#[cfg(feature = "unsafe-impls")]
+pub mod big_uint;
+// 出边存在单链表里的邻接表图
+#[cfg(feature = "safe")]
+pub mod graph;
+// 十字链表表示的稀疏矩阵
+#[cfg(feature = "safe")]
+pub mod sparse_matrix;
+// 桶为单链表的拉链法哈希表
+#[cfg(feature = "safe")]
+pub mod chained_hash_map;
+// 空闲块串成侵入式单链表的定长块分配器
+#[cfg(feature = "unsafe-impls")]
+pub mod block_allocator;
+// 读者无锁遍历快照、写者复制前缀的RCU风格链表
+#[cfg(feature = "concurrent")]
+pub mod rcu_list;
+// 节点用Rc共享、修改时才复制的写时复制链表
+#[cfg(feature = "persistent")]
+pub mod cow_list;
+// 每次修改生成一个持久化版本、可以回到任意历史版本的链表
+#[cfg(feature = "persistent")]
+pub mod versioned_list;
+// 栈顶原子替换、节点用Arc共享的无锁持久化栈
+#[cfg(feature = "concurrent")]
+pub mod shared_stack;
+// 请求挂在侵入式链表上、由合并者批量执行的flat combining队列
+#[cfg(feature = "concurrent")]
+pub mod flat_combining;
+// 按哈希分片、每片一把锁的并发LRU缓存
+#[cfg(feature = "concurrent")]
+pub mod sharded_lru;
+// 基于ms_queue的多生产者多消费者通道，支持阻塞和非阻塞接收
+#[cfg(feature = "concurrent")]
+pub mod channel;
+// 空闲对象放在treiber_stack上、借出用RAII守卫自动归还的对象池
+#[cfg(feature = "concurrent")]
+pub mod object_pool;
+// 只保存Weak、通知时自动清理失效订阅者的观察者列表
+#[cfg(feature = "unsafe-impls")]
+pub mod observer_list;
+// 求滑动窗口最大值/最小值的单调双端队列
+#[cfg(feature = "unsafe-impls")]
+pub mod monotonic_deque;
+// 就绪队列为侵入式链表的协作式轮转调度器
+#[cfg(feature = "unsafe-impls")]
+pub mod scheduler;
+// 节点带额外随机指针的链表及其O(n)深拷贝
+#[cfg(feature = "unsafe-impls")]
+pub mod random_list;
+// 裸指针链表的Floyd/Brent环检测
+#[cfg(feature = "unsafe-impls")]
+pub mod cycle;
+// 节点所有权在类型中拆成两半的static-rc风格双向链表
+#[cfg(feature = "unsafe-impls")]
+pub mod static_rc_list;
+// 光标两侧各用一个链式栈的间隙缓冲区
+#[cfg(feature = "safe")]
+pub mod gap_buffer;
+// O(1)取最小值的栈，以及用两个这样的栈拼成的队列
+#[cfg(feature = "safe")]
+pub mod min_stack;
+// 桶为有序链表、按时间片索引的日历队列
+#[cfg(feature = "unsafe-impls")]
+pub mod calendar_queue;
+// 访问后按移到表头/前移一位/计数启发式自动调整顺序的链表
+#[cfg(feature = "unsafe-impls")]
+pub mod self_organizing_list;
+// Harris无锁有序链表，回收策略可选epoch或危险指针
+#[cfg(feature = "concurrent")]
+pub mod harris_list;
+// 有序链表保存互不相交区间的区间链表，支持合并、拆分和空隙遍历
+#[cfg(feature = "unsafe-impls")]
+pub mod interval_list;
+// 字节存在链式共享块里的缓冲区，支持廉价切分并实现Read/Write/BufRead
+#[cfg(feature = "unsafe-impls")]
+pub mod byte_chain;
+// 链表表示、加权合并的不相交集合(并查集)
+#[cfg(feature = "safe")]
+pub mod disjoint_set;
+// 链表节点为定长数组段的无锁MPMC队列
+#[cfg(feature = "concurrent")]
+pub mod seg_queue;
+// 记录指针跨度、支持O(log n)按下标访问/插入/删除的跳表
+#[cfg(feature = "unsafe-impls")]
+pub mod indexable_skip_list;
+// 删除时只留墓碑、之后统一压缩的延迟删除链表
+#[cfg(feature = "unsafe-impls")]
+pub mod tombstone_list;
+// 在treiber_stack上加消除数组、让push/pop在竞争时直接配对交换的无锁栈
+#[cfg(feature = "concurrent")]
+pub mod elimination_stack;
+// 单生产者追加、每个消费者各自一个游标的广播日志，所有游标越过的段自动回收
+#[cfg(feature = "concurrent")]
+pub mod broadcast_log;
+// 栈、队列、双端队列的公共trait，crate里的链表都实现了对应的trait
+pub mod traits;
+// try_*方法共用的错误类型ListError
+pub mod error;
+// 常用类型别名、ListError和公共trait，use too_many_linked_list_rs::prelude::*一次引入
+pub mod prelude;
+// 按链表实例统计push/pop/遍历步数/节点分配，开启metrics特性时才真正计数
+#[cfg(any(feature = "safe", feature = "unsafe-impls"))]
+pub mod metrics;
+// 跳表和随机测试共用的伪随机数生成器；只开部分特性时有些方法用不到
+#[cfg(any(test, feature = "unsafe-impls"))]
+#[allow(dead_code)]
+mod rng;
+// 把链表的指针图输出成Graphviz DOT格式
+#[cfg(feature = "visualize")]
+pub mod visualize;
+// 浏览器演示用的wasm-bindgen绑定，导出simple_deque_3包装成的Deque类
+#[cfg(feature = "wasm")]
+pub mod wasm;
+// PyO3导出的Python类Deque和LruCache
+#[cfg(feature = "python")]
+pub mod python;
+// 开启serde特性时给容器实现Serialize/Deserialize，以及共用的序列读取函数
+#[cfg(all(feature = "serde", any(feature = "safe", feature = "persistent", feature = "unsafe-impls")))]
+mod serde_impls;
+// 开启arbitrary特性时给链表类型实现arbitrary::Arbitrary
+#[cfg(all(feature = "arbitrary", any(feature = "safe", feature = "persistent", feature = "unsafe-impls")))]
+mod arbitrary_impls;
+// 测试用的计数全局分配器和计数元素，断言分配次数和有没有泄漏
+#[cfg(test)]
+mod alloc_counter;
+// 所有栈/队列/双端队列实现共用的行为测试；只开部分特性时有些测试函数用不到
+#[cfg(test)]
+#[allow(dead_code)]
+mod conformance;
+// 操作序列和VecDeque对照的差分属性测试(proptest)
+#[cfg(test)]
+#[allow(dead_code)]
+mod differential;
+
+// 稳定的类型别名，指向每种用途推荐的实现
+// 模块名(simple_deque_3之类)反映的是教程里的先后顺序，下游代码用这几个名字就不用跟着模块改名
+// 单线程栈，safe实现里最完整的一个
+#[cfg(feature = "safe")]
+pub type Stack<T> = simple_stack_2::List<T>;
+// 不可变、共享后缀的持久化单链表
+#[cfg(feature = "persistent")]
+pub type PersistentList<T> = simple_stack_3::List<T>;
+// 双端队列，也可以当队列用
+#[cfg(feature = "unsafe-impls")]
+pub type Deque<T> = simple_deque_3::List<T>;
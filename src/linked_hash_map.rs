@@ -0,0 +1,432 @@
+// 保持插入顺序的哈希表
+// HashMap负责按键O(1)查找，simple_deque_3::List负责记住顺序:
+// 每个键值对存在链表节点里，HashMap里存的是键到节点句柄的映射
+// 查找、插入、删除、调整顺序都是O(1)，遍历按链表顺序进行
+// 节点和HashMap各需要一份键，所以要求K: Clone
+// 插入已经存在的键只更新值，不改变它的位置；需要调整顺序时用move_to_front/move_to_back
+
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+use std::iter::FusedIterator;
+
+use crate::simple_deque_3::{self, List, NodeHandle};
+
+pub struct LinkedHashMap<K, V> {
+    map: HashMap<K, NodeHandle<(K, V)>>,
+    // 不变量: list里的节点和map里的句柄一一对应
+    list: List<(K, V)>,
+}
+
+impl<K: Hash + Eq + Clone, V> LinkedHashMap<K, V> {
+    pub fn new() -> Self {
+        LinkedHashMap {
+            map: HashMap::new(),
+            list: List::new(),
+        }
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        LinkedHashMap {
+            map: HashMap::with_capacity(capacity),
+            list: List::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.map.clear();
+        self.list.clear();
+    }
+
+    // 新键放到末尾返回None；已有的键原地更新值并返回旧值
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        match self.map.get(&key) {
+            // SAFETY: map里的句柄都指向list中的节点
+            Some(&handle) => unsafe {
+                Some(std::mem::replace(&mut self.list.get_handle_mut(handle).1, value))
+            },
+            None => {
+                let handle = self.list.push_back_handle((key.clone(), value));
+                self.map.insert(key, handle);
+                None
+            }
+        }
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get_key_value(key).map(|(_, v)| v)
+    }
+
+    pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let handle = *self.map.get(key)?;
+        let (k, v) = unsafe { self.list.get_handle(handle) };
+        Some((k, v))
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let handle = *self.map.get(key)?;
+        Some(unsafe { &mut self.list.get_handle_mut(handle).1 })
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map.contains_key(key)
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.remove_entry(key).map(|(_, v)| v)
+    }
+
+    pub fn remove_entry<Q>(&mut self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let handle = self.map.remove(key)?;
+        Some(unsafe { self.list.remove_handle(handle) })
+    }
+
+    // 最早插入(或者被move_to_front移到最前)的键值对
+    pub fn front(&self) -> Option<(&K, &V)> {
+        self.list.front().map(|(k, v)| (k, v))
+    }
+
+    pub fn back(&self) -> Option<(&K, &V)> {
+        self.list.back().map(|(k, v)| (k, v))
+    }
+
+    pub fn pop_front(&mut self) -> Option<(K, V)> {
+        let (key, value) = self.list.pop_front()?;
+        self.map.remove(&key);
+        Some((key, value))
+    }
+
+    pub fn pop_back(&mut self) -> Option<(K, V)> {
+        let (key, value) = self.list.pop_back()?;
+        self.map.remove(&key);
+        Some((key, value))
+    }
+
+    // 把key调整到最前/最后，key不存在时返回false
+    pub fn move_to_front<Q>(&mut self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        match self.map.get(key) {
+            Some(&handle) => {
+                unsafe { self.list.move_to_front(handle) };
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn move_to_back<Q>(&mut self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        match self.map.get(key) {
+            Some(&handle) => {
+                unsafe { self.list.move_to_back(handle) };
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl<K, V> LinkedHashMap<K, V> {
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter(self.list.iter())
+    }
+
+    // 只能改值，键改了会破坏HashMap
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        IterMut(self.list.iter_mut())
+    }
+
+    pub fn keys(&self) -> impl DoubleEndedIterator<Item = &K> + ExactSizeIterator + '_ {
+        self.iter().map(|(k, _)| k)
+    }
+
+    pub fn values(&self) -> impl DoubleEndedIterator<Item = &V> + ExactSizeIterator + '_ {
+        self.iter().map(|(_, v)| v)
+    }
+
+    pub fn values_mut(&mut self) -> impl DoubleEndedIterator<Item = &mut V> + ExactSizeIterator + '_ {
+        self.iter_mut().map(|(_, v)| v)
+    }
+}
+
+impl<K: Hash + Eq + Clone, V> Default for LinkedHashMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> Clone for LinkedHashMap<K, V> {
+    fn clone(&self) -> Self {
+        self.iter().map(|(k, v)| (k.clone(), v.clone())).collect()
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for LinkedHashMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+// 顺序也参与比较
+impl<K: PartialEq, V: PartialEq> PartialEq for LinkedHashMap<K, V> {
+    fn eq(&self, other: &Self) -> bool {
+        self.list == other.list
+    }
+}
+
+impl<K: Eq, V: Eq> Eq for LinkedHashMap<K, V> {}
+
+impl<K: Hash + Eq + Clone, V> Extend<(K, V)> for LinkedHashMap<K, V> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (k, v) in iter {
+            self.insert(k, v);
+        }
+    }
+}
+
+impl<K: Hash + Eq + Clone, V> FromIterator<(K, V)> for LinkedHashMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = Self::new();
+        map.extend(iter);
+        map
+    }
+}
+
+// 句柄只是指向list里节点的指针，整个结构在线程间转移和List<(K, V)>一样安全
+unsafe impl<K: Send, V: Send> Send for LinkedHashMap<K, V> {}
+unsafe impl<K: Sync, V: Sync> Sync for LinkedHashMap<K, V> {}
+
+pub struct Iter<'a, K, V>(simple_deque_3::Iter<'a, (K, V)>);
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(k, v)| (k, v))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for Iter<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|(k, v)| (k, v))
+    }
+}
+
+impl<K, V> ExactSizeIterator for Iter<'_, K, V> {}
+impl<K, V> FusedIterator for Iter<'_, K, V> {}
+
+impl<'a, K, V> IntoIterator for &'a LinkedHashMap<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+pub struct IterMut<'a, K, V>(simple_deque_3::IterMut<'a, (K, V)>);
+
+impl<'a, K, V> Iterator for IterMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(k, v)| (&*k, v))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for IterMut<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|(k, v)| (&*k, v))
+    }
+}
+
+impl<K, V> ExactSizeIterator for IterMut<'_, K, V> {}
+impl<K, V> FusedIterator for IterMut<'_, K, V> {}
+
+impl<'a, K, V> IntoIterator for &'a mut LinkedHashMap<K, V> {
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+// 句柄随map一起丢弃，只需要链表本身的IntoIter
+pub struct IntoIter<K, V>(simple_deque_3::IntoIter<(K, V)>);
+
+impl<K, V> Iterator for IntoIter<K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<(K, V)> {
+        self.0.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for IntoIter<K, V> {
+    fn next_back(&mut self) -> Option<(K, V)> {
+        self.0.next_back()
+    }
+}
+
+impl<K, V> ExactSizeIterator for IntoIter<K, V> {}
+
+impl<K, V> IntoIterator for LinkedHashMap<K, V> {
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V>;
+
+    fn into_iter(self) -> IntoIter<K, V> {
+        IntoIter(self.list.into_iter())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::XorShift;
+
+    fn keys(map: &LinkedHashMap<&'static str, i32>) -> Vec<&'static str> {
+        map.keys().copied().collect()
+    }
+
+    #[test]
+    fn preserves_insertion_order() {
+        let mut map = LinkedHashMap::new();
+        assert_eq!(map.insert("c", 3), None);
+        assert_eq!(map.insert("a", 1), None);
+        assert_eq!(map.insert("b", 2), None);
+        // 更新已有的键不改变顺序
+        assert_eq!(map.insert("c", 30), Some(3));
+        assert_eq!(keys(&map), vec!["c", "a", "b"]);
+        assert_eq!(map.get("c"), Some(&30));
+        assert_eq!(map.get_key_value("a"), Some((&"a", &1)));
+        assert_eq!(map.len(), 3);
+        assert_eq!(format!("{map:?}"), r#"{"c": 30, "a": 1, "b": 2}"#);
+    }
+
+    #[test]
+    fn remove_and_pop_keep_index_in_sync() {
+        let mut map: LinkedHashMap<_, _> = [("a", 1), ("b", 2), ("c", 3), ("d", 4)].into_iter().collect();
+        assert_eq!(map.remove("b"), Some(2));
+        assert_eq!(map.remove("b"), None);
+        assert_eq!(map.pop_front(), Some(("a", 1)));
+        assert_eq!(map.pop_back(), Some(("d", 4)));
+        assert!(!map.contains_key("a") && !map.contains_key("d"));
+        assert_eq!(map.front(), Some((&"c", &3)));
+        assert_eq!(map.back(), Some((&"c", &3)));
+        map.insert("a", 10);
+        assert_eq!(keys(&map), vec!["c", "a"]);
+    }
+
+    #[test]
+    fn move_to_either_end() {
+        let mut map: LinkedHashMap<_, _> = [("a", 1), ("b", 2), ("c", 3)].into_iter().collect();
+        assert!(map.move_to_back("a"));
+        assert_eq!(keys(&map), vec!["b", "c", "a"]);
+        assert!(map.move_to_front("c"));
+        assert_eq!(keys(&map), vec!["c", "b", "a"]);
+        assert!(map.move_to_back("a"));
+        assert!(!map.move_to_front("z"));
+        assert_eq!(keys(&map), vec!["c", "b", "a"]);
+        for (_, v) in map.iter_mut() {
+            *v *= 10;
+        }
+        assert_eq!(map.values().copied().collect::<Vec<_>>(), vec![30, 20, 10]);
+        assert_eq!(map.clone().into_iter().next_back(), Some(("a", 10)));
+    }
+
+    #[test]
+    fn owned_keys_and_borrowed_lookup() {
+        let mut map = LinkedHashMap::new();
+        map.insert(String::from("x"), vec![1]);
+        map.get_mut("x").unwrap().push(2);
+        assert_eq!(map.get("x"), Some(&vec![1, 2]));
+        assert_eq!(map.remove_entry("x"), Some((String::from("x"), vec![1, 2])));
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn randomized_against_vec_model() {
+        let mut rng = XorShift::new(0x11);
+        let mut map = LinkedHashMap::new();
+        let mut model: Vec<(u64, u64)> = Vec::new();
+        for i in 0..2000 {
+            let key = rng.next_u64() % 64;
+            match rng.next_u64() % 4 {
+                0 | 1 => {
+                    let old = map.insert(key, i);
+                    match model.iter_mut().find(|(k, _)| *k == key) {
+                        Some(entry) => assert_eq!(old, Some(std::mem::replace(&mut entry.1, i))),
+                        None => {
+                            assert_eq!(old, None);
+                            model.push((key, i));
+                        }
+                    }
+                }
+                2 => {
+                    let pos = model.iter().position(|(k, _)| *k == key);
+                    assert_eq!(map.remove(&key), pos.map(|p| model.remove(p).1));
+                }
+                _ => {
+                    if let Some(pos) = model.iter().position(|(k, _)| *k == key) {
+                        let entry = model.remove(pos);
+                        model.push(entry);
+                    }
+                    map.move_to_back(&key);
+                }
+            }
+        }
+        assert_eq!(map.into_iter().collect::<Vec<_>>(), model);
+    }
+}
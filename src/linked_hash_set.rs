@@ -0,0 +1,316 @@
+// 保持插入顺序的哈希集合，直接包一层LinkedHashMap<T, ()>
+// 除了普通集合操作，还提供从最早/最晚一端淘汰元素的方法，
+// 适合"记住最近见过的N个id"这类去重窗口
+
+use std::borrow::Borrow;
+use std::fmt;
+use std::hash::Hash;
+use std::iter::FusedIterator;
+
+use crate::linked_hash_map::{self, LinkedHashMap};
+
+pub struct LinkedHashSet<T> {
+    map: LinkedHashMap<T, ()>,
+}
+
+impl<T: Hash + Eq + Clone> LinkedHashSet<T> {
+    pub fn new() -> Self {
+        LinkedHashSet {
+            map: LinkedHashMap::new(),
+        }
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        LinkedHashSet {
+            map: LinkedHashMap::with_capacity(capacity),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.map.clear();
+    }
+
+    // 新元素放到末尾返回true；已经存在时不改变位置，返回false
+    pub fn insert(&mut self, value: T) -> bool {
+        if self.map.contains_key(&value) {
+            return false;
+        }
+        self.map.insert(value, ());
+        true
+    }
+
+    pub fn contains<Q>(&self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map.contains_key(value)
+    }
+
+    pub fn get<Q>(&self, value: &Q) -> Option<&T>
+    where
+        T: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map.get_key_value(value).map(|(k, _)| k)
+    }
+
+    pub fn remove<Q>(&mut self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map.remove(value).is_some()
+    }
+
+    pub fn take<Q>(&mut self, value: &Q) -> Option<T>
+    where
+        T: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map.remove_entry(value).map(|(k, _)| k)
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        self.map.front().map(|(k, _)| k)
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        self.map.back().map(|(k, _)| k)
+    }
+
+    // 淘汰最早的元素
+    pub fn pop_front(&mut self) -> Option<T> {
+        self.map.pop_front().map(|(k, _)| k)
+    }
+
+    // 淘汰最晚的元素
+    pub fn pop_back(&mut self) -> Option<T> {
+        self.map.pop_back().map(|(k, _)| k)
+    }
+
+    // 从最早一端淘汰，直到只剩len个元素，返回被淘汰的元素(按淘汰顺序)
+    pub fn truncate_front(&mut self, len: usize) -> Vec<T> {
+        let excess = self.len().saturating_sub(len);
+        (0..excess).filter_map(|_| self.pop_front()).collect()
+    }
+
+    // 从最晚一端淘汰，直到只剩len个元素
+    pub fn truncate_back(&mut self, len: usize) -> Vec<T> {
+        let excess = self.len().saturating_sub(len);
+        (0..excess).filter_map(|_| self.pop_back()).collect()
+    }
+
+    // 插入或者把已有的元素挪到末尾(刷新成"最新")，返回是否是新元素
+    pub fn refresh(&mut self, value: T) -> bool {
+        if self.map.move_to_back(&value) {
+            return false;
+        }
+        self.map.insert(value, ());
+        true
+    }
+
+    pub fn move_to_front<Q>(&mut self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map.move_to_front(value)
+    }
+
+    pub fn move_to_back<Q>(&mut self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map.move_to_back(value)
+    }
+}
+
+impl<T> LinkedHashSet<T> {
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter(self.map.iter())
+    }
+}
+
+impl<T: Hash + Eq + Clone> Default for LinkedHashSet<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Hash + Eq + Clone> Clone for LinkedHashSet<T> {
+    fn clone(&self) -> Self {
+        LinkedHashSet {
+            map: self.map.clone(),
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for LinkedHashSet<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+// 顺序也参与比较
+impl<T: PartialEq> PartialEq for LinkedHashSet<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.map == other.map
+    }
+}
+
+impl<T: Eq> Eq for LinkedHashSet<T> {}
+
+impl<T: Hash + Eq + Clone> Extend<T> for LinkedHashSet<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.insert(value);
+        }
+    }
+}
+
+impl<T: Hash + Eq + Clone> FromIterator<T> for LinkedHashSet<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut set = Self::new();
+        set.extend(iter);
+        set
+    }
+}
+
+pub struct Iter<'a, T>(linked_hash_map::Iter<'a, T, ()>);
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        self.0.next().map(|(k, _)| k)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<T> DoubleEndedIterator for Iter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|(k, _)| k)
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+impl<T> FusedIterator for Iter<'_, T> {}
+
+impl<'a, T> IntoIterator for &'a LinkedHashSet<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+pub struct IntoIter<T>(linked_hash_map::IntoIter<T, ()>);
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.0.next().map(|(k, _)| k)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<T> DoubleEndedIterator for IntoIter<T> {
+    fn next_back(&mut self) -> Option<T> {
+        self.0.next_back().map(|(k, _)| k)
+    }
+}
+
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
+impl<T> IntoIterator for LinkedHashSet<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter(self.map.into_iter())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn items(set: &LinkedHashSet<i32>) -> Vec<i32> {
+        set.iter().copied().collect()
+    }
+
+    #[test]
+    fn insertion_order_and_dedup() {
+        let mut set = LinkedHashSet::new();
+        assert!(set.insert(3));
+        assert!(set.insert(1));
+        assert!(set.insert(2));
+        assert!(!set.insert(3));
+        assert_eq!(items(&set), vec![3, 1, 2]);
+        assert!(set.contains(&1));
+        assert_eq!(set.get(&2), Some(&2));
+        assert!(set.remove(&1));
+        assert!(!set.remove(&1));
+        assert_eq!(items(&set), vec![3, 2]);
+        assert_eq!(format!("{set:?}"), "{3, 2}");
+    }
+
+    #[test]
+    fn evict_from_either_end() {
+        let mut set: LinkedHashSet<i32> = (1..=6).collect();
+        assert_eq!(set.front(), Some(&1));
+        assert_eq!(set.back(), Some(&6));
+        assert_eq!(set.pop_front(), Some(1));
+        assert_eq!(set.pop_back(), Some(6));
+        assert_eq!(set.truncate_front(2), vec![2, 3]);
+        assert_eq!(items(&set), vec![4, 5]);
+        assert_eq!(set.truncate_back(5), Vec::<i32>::new());
+        set.extend([7, 8]);
+        assert_eq!(set.truncate_back(1), vec![8, 7, 5]);
+        assert_eq!(set.into_iter().collect::<Vec<_>>(), vec![4]);
+    }
+
+    #[test]
+    fn dedup_window() {
+        // 只记住最近见过的3个id，重复出现会刷新它的位置
+        let mut seen = LinkedHashSet::new();
+        let mut fresh = Vec::new();
+        for id in [1, 2, 1, 3, 4, 2, 5, 1] {
+            if seen.refresh(id) {
+                fresh.push(id);
+            }
+            seen.truncate_front(3);
+        }
+        assert_eq!(fresh, vec![1, 2, 3, 4, 2, 5, 1]);
+        assert_eq!(items(&seen), vec![2, 5, 1]);
+    }
+
+    #[test]
+    fn take_and_reorder() {
+        let mut set: LinkedHashSet<String> = ["a", "b", "c"].iter().map(|s| s.to_string()).collect();
+        assert!(set.move_to_front("c"));
+        assert!(set.move_to_back("a"));
+        assert!(!set.move_to_back("z"));
+        assert_eq!(set.iter().rev().cloned().collect::<Vec<_>>(), vec!["a", "b", "c"]);
+        assert_eq!(set.take("b"), Some(String::from("b")));
+        assert_eq!(set.clone(), set);
+    }
+}
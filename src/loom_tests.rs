@@ -0,0 +1,197 @@
+// 用loom对无锁结构做模型检查
+// loom会在每个原子操作处切换线程，穷举小场景下所有的交错顺序和允许的内存序结果，
+// 普通压力测试碰运气才能撞到的竞争在这里每次都能稳定复现
+// 运行方式(只跑这个模块，其他测试在loom下没有意义):
+//     RUSTFLAGS="--cfg loom" cargo test --release loom_tests
+// loom的线程是在同一个系统线程上切换的协程，epoch/hazard依赖的thread_local在这里不可用，
+// 因此无锁结构改用Leak策略: 摘下的节点不释放，只检查算法本身的正确性
+
+use std::sync::Arc;
+
+use loom::thread;
+
+use crate::ms_queue::MsQueue;
+use crate::reclaim::{Reclaim, ReclaimGuard};
+use crate::seg_queue::SegQueue;
+use crate::spsc_queue::{self, TryRecvError};
+use crate::sync::atomic::{AtomicPtr, Ordering};
+use crate::treiber_stack::TreiberStack;
+
+struct Leak;
+
+struct LeakGuard;
+
+impl Reclaim for Leak {
+    type Guard = LeakGuard;
+
+    fn pin() -> LeakGuard {
+        LeakGuard
+    }
+
+    fn pending() -> usize {
+        0
+    }
+}
+
+impl ReclaimGuard for LeakGuard {
+    fn protect<T>(&self, _slot: usize, src: &AtomicPtr<T>) -> *mut T {
+        src.load(Ordering::Acquire)
+    }
+
+    fn protect_raw<T>(&self, _slot: usize, _ptr: *mut T) {}
+
+    unsafe fn retire<T>(&self, _ptr: *mut T) {}
+}
+
+#[test]
+fn treiber_concurrent_push_pop() {
+    loom::model(|| {
+        let stack = Arc::new(TreiberStack::with_reclaim(Leak));
+        let handles: Vec<_> = (0..2)
+            .map(|t| {
+                let stack = Arc::clone(&stack);
+                thread::spawn(move || {
+                    stack.push(t);
+                    stack.pop()
+                })
+            })
+            .collect();
+
+        // 每个线程先push再pop，pop一定能拿到某个元素，两个线程拿到的不会重复
+        let mut got: Vec<i32> = handles.into_iter().map(|h| h.join().unwrap().unwrap()).collect();
+        got.sort();
+        assert_eq!(got, vec![0, 1]);
+        assert!(stack.pop().is_none());
+    });
+}
+
+#[test]
+fn treiber_pop_races_with_push() {
+    loom::model(|| {
+        let stack = Arc::new(TreiberStack::with_reclaim(Leak));
+        stack.push(1);
+        let popper = {
+            let stack = Arc::clone(&stack);
+            thread::spawn(move || stack.pop())
+        };
+        stack.push(2);
+        let popped = popper.join().unwrap().unwrap();
+        let rest = stack.pop().unwrap();
+        assert_ne!(popped, rest);
+        assert!(stack.pop().is_none());
+    });
+}
+
+#[test]
+fn ms_queue_producer_consumer_fifo() {
+    loom::model(|| {
+        let q = Arc::new(MsQueue::with_reclaim(Leak));
+        let producer = {
+            let q = Arc::clone(&q);
+            thread::spawn(move || {
+                q.push(1);
+                q.push(2);
+            })
+        };
+        // 消费者和生产者并发，取到的元素必须是[1, 2]的前缀
+        let mut got = Vec::new();
+        for _ in 0..2 {
+            if let Some(x) = q.pop() {
+                got.push(x);
+            }
+        }
+        producer.join().unwrap();
+        while let Some(x) = q.pop() {
+            got.push(x);
+        }
+        assert_eq!(got, vec![1, 2]);
+    });
+}
+
+#[test]
+fn ms_queue_concurrent_producers() {
+    loom::model(|| {
+        let q = Arc::new(MsQueue::with_reclaim(Leak));
+        let handles: Vec<_> = (0..2)
+            .map(|t| {
+                let q = Arc::clone(&q);
+                thread::spawn(move || q.push(t))
+            })
+            .collect();
+        for h in handles {
+            h.join().unwrap();
+        }
+        let mut got = vec![q.pop().unwrap(), q.pop().unwrap()];
+        got.sort();
+        assert_eq!(got, vec![0, 1]);
+        assert!(q.is_empty());
+    });
+}
+
+#[test]
+fn ms_queue_concurrent_consumers() {
+    loom::model(|| {
+        let q = Arc::new(MsQueue::with_reclaim(Leak));
+        q.push(1);
+        q.push(2);
+        let handles: Vec<_> = (0..2)
+            .map(|_| {
+                let q = Arc::clone(&q);
+                thread::spawn(move || q.pop())
+            })
+            .collect();
+        let mut got: Vec<i32> = handles.into_iter().map(|h| h.join().unwrap().unwrap()).collect();
+        got.sort();
+        assert_eq!(got, vec![1, 2]);
+        assert!(q.pop().is_none());
+    });
+}
+
+// 元素都在启动线程前写好，出队者不会进入等待写入的自旋
+#[test]
+fn seg_queue_concurrent_consumers() {
+    loom::model(|| {
+        let q = Arc::new(SegQueue::with_reclaim(Leak));
+        q.push(1);
+        q.push(2);
+        let handles: Vec<_> = (0..2)
+            .map(|_| {
+                let q = Arc::clone(&q);
+                thread::spawn(move || q.pop())
+            })
+            .collect();
+        let mut got: Vec<i32> = handles.into_iter().map(|h| h.join().unwrap().unwrap()).collect();
+        got.sort();
+        assert_eq!(got, vec![1, 2]);
+        assert!(q.is_empty());
+    });
+}
+
+#[test]
+fn spsc_send_recv() {
+    loom::model(|| {
+        let (tx, rx) = spsc_queue::channel();
+        let producer = thread::spawn(move || {
+            tx.send(1).unwrap();
+            tx.send(2).unwrap();
+        });
+        let mut got = Vec::new();
+        for _ in 0..2 {
+            if let Ok(x) = rx.try_recv() {
+                got.push(x);
+            }
+        }
+        producer.join().unwrap();
+        // 发送端已经drop: 先取完剩下的元素，之后才是Disconnected
+        loop {
+            match rx.try_recv() {
+                Ok(x) => got.push(x),
+                Err(e) => {
+                    assert_eq!(e, TryRecvError::Disconnected);
+                    break;
+                }
+            }
+        }
+        assert_eq!(got, vec![1, 2]);
+    });
+}
@@ -0,0 +1,293 @@
+// LRU(最近最少使用)缓存，双向链表最经典的应用
+// simple_deque_3::List按使用时间排序，头部是最近用过的，尾部是最久没用的；
+// HashMap把键映射到链表节点句柄
+// - 命中: 通过句柄O(1)找到节点，move_to_front挪到头部
+// - 插入新键: 放在头部，超出容量时从尾部淘汰
+// peek系列方法只读不刷新使用时间
+
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+use std::iter::FusedIterator;
+
+use crate::simple_deque_3::{self, List, NodeHandle};
+
+pub struct LruCache<K, V> {
+    map: HashMap<K, NodeHandle<(K, V)>>,
+    // 头部最新，尾部最旧
+    list: List<(K, V)>,
+    capacity: usize,
+}
+
+impl<K: Hash + Eq + Clone, V> LruCache<K, V> {
+    // capacity为0时panic
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "capacity must be positive");
+        Self::with_reserved(capacity, capacity)
+    }
+
+    // 容量上限是capacity，哈希表只预留reserved个位置
+    // 反序列化时capacity来自输入，不能照着它分配内存
+    pub(crate) fn with_reserved(capacity: usize, reserved: usize) -> Self {
+        LruCache {
+            map: HashMap::with_capacity(reserved),
+            list: List::new(),
+            capacity,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn clear(&mut self) {
+        self.map.clear();
+        self.list.clear();
+    }
+
+    // 插入或更新，key成为最近使用的；返回同一个key的旧值
+    // 新key导致超出容量时淘汰最久没用的那一项
+    pub fn put(&mut self, key: K, value: V) -> Option<V> {
+        if let Some(&handle) = self.map.get(&key) {
+            // SAFETY: map里的句柄都指向list中的节点
+            unsafe {
+                self.list.move_to_front(handle);
+                return Some(std::mem::replace(&mut self.list.get_handle_mut(handle).1, value));
+            }
+        }
+        if self.len() == self.capacity {
+            self.pop_lru();
+        }
+        let handle = self.list.push_front_handle((key.clone(), value));
+        self.map.insert(key, handle);
+        None
+    }
+
+    // 命中时刷新使用时间
+    pub fn get<Q>(&mut self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get_mut(key).map(|v| &*v)
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let handle = *self.map.get(key)?;
+        unsafe {
+            self.list.move_to_front(handle);
+            Some(&mut self.list.get_handle_mut(handle).1)
+        }
+    }
+
+    // 缺失时用f计算并插入，命中时刷新使用时间
+    pub fn get_or_insert_with<F: FnOnce() -> V>(&mut self, key: K, f: F) -> &mut V {
+        if !self.map.contains_key(&key) {
+            self.put(key.clone(), f());
+        }
+        self.get_mut(&key).unwrap()
+    }
+
+    // 只读，不改变使用顺序
+    pub fn peek<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let handle = *self.map.get(key)?;
+        Some(unsafe { &self.list.get_handle(handle).1 })
+    }
+
+    pub fn contains<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map.contains_key(key)
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let handle = self.map.remove(key)?;
+        Some(unsafe { self.list.remove_handle(handle) }.1)
+    }
+
+    // 下一个会被淘汰的项
+    pub fn peek_lru(&self) -> Option<(&K, &V)> {
+        self.list.back().map(|(k, v)| (k, v))
+    }
+
+    pub fn peek_mru(&self) -> Option<(&K, &V)> {
+        self.list.front().map(|(k, v)| (k, v))
+    }
+
+    // 主动淘汰最久没用的一项
+    pub fn pop_lru(&mut self) -> Option<(K, V)> {
+        let (key, value) = self.list.pop_back()?;
+        self.map.remove(&key);
+        Some((key, value))
+    }
+
+    // 调整容量，缩小时按LRU顺序淘汰，返回被淘汰的项；capacity为0时panic
+    pub fn resize(&mut self, capacity: usize) -> Vec<(K, V)> {
+        assert!(capacity > 0, "capacity must be positive");
+        self.capacity = capacity;
+        let excess = self.len().saturating_sub(capacity);
+        (0..excess).filter_map(|_| self.pop_lru()).collect()
+    }
+}
+
+impl<K, V> LruCache<K, V> {
+    // 从最近使用到最久没用
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter(self.list.iter())
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for LruCache<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+unsafe impl<K: Send, V: Send> Send for LruCache<K, V> {}
+unsafe impl<K: Sync, V: Sync> Sync for LruCache<K, V> {}
+
+pub struct Iter<'a, K, V>(simple_deque_3::Iter<'a, (K, V)>);
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(k, v)| (k, v))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for Iter<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|(k, v)| (k, v))
+    }
+}
+
+impl<K, V> ExactSizeIterator for Iter<'_, K, V> {}
+impl<K, V> FusedIterator for Iter<'_, K, V> {}
+
+impl<'a, K, V> IntoIterator for &'a LruCache<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::XorShift;
+
+    fn order(cache: &LruCache<&'static str, i32>) -> Vec<&'static str> {
+        cache.iter().map(|(k, _)| *k).collect()
+    }
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let mut cache = LruCache::new(2);
+        assert_eq!(cache.put("a", 1), None);
+        assert_eq!(cache.put("b", 2), None);
+        // 访问a之后b变成最久没用的
+        assert_eq!(cache.get("a"), Some(&1));
+        assert_eq!(cache.put("c", 3), None);
+        assert!(!cache.contains("b"));
+        assert_eq!(order(&cache), vec!["c", "a"]);
+        assert_eq!(cache.put("a", 10), Some(1));
+        assert_eq!(order(&cache), vec!["a", "c"]);
+        assert_eq!(cache.len(), 2);
+    }
+
+    #[test]
+    fn peek_does_not_refresh() {
+        let mut cache = LruCache::new(2);
+        cache.put("a", 1);
+        cache.put("b", 2);
+        assert_eq!(cache.peek("a"), Some(&1));
+        assert_eq!(cache.peek_lru(), Some((&"a", &1)));
+        assert_eq!(cache.peek_mru(), Some((&"b", &2)));
+        cache.put("c", 3);
+        assert_eq!(cache.peek("a"), None);
+        assert_eq!(format!("{cache:?}"), r#"{"c": 3, "b": 2}"#);
+    }
+
+    #[test]
+    fn remove_resize_and_pop() {
+        let mut cache = LruCache::new(4);
+        for (i, k) in ["a", "b", "c", "d"].into_iter().enumerate() {
+            cache.put(k, i as i32);
+        }
+        assert_eq!(cache.remove("c"), Some(2));
+        assert_eq!(cache.remove("c"), None);
+        *cache.get_mut("a").unwrap() += 100;
+        assert_eq!(order(&cache), vec!["a", "d", "b"]);
+        assert_eq!(cache.resize(1), vec![("b", 1), ("d", 3)]);
+        assert_eq!(cache.capacity(), 1);
+        assert_eq!(cache.pop_lru(), Some(("a", 100)));
+        assert!(cache.is_empty());
+        assert_eq!(*cache.get_or_insert_with("z", || 26), 26);
+        assert_eq!(*cache.get_or_insert_with("z", || 0), 26);
+    }
+
+    #[test]
+    #[should_panic(expected = "capacity must be positive")]
+    fn zero_capacity_panics() {
+        let _ = LruCache::<i32, i32>::new(0);
+    }
+
+    #[test]
+    fn randomized_against_vec_model() {
+        // 模型: Vec按最近使用排序，下标0最新
+        let mut rng = XorShift::new(0x1ee);
+        let mut cache = LruCache::new(8);
+        let mut model: Vec<(u64, u64)> = Vec::new();
+        for i in 0..3000 {
+            let key = rng.next_u64() % 16;
+            if rng.next_u64().is_multiple_of(2) {
+                cache.put(key, i);
+                if let Some(pos) = model.iter().position(|(k, _)| *k == key) {
+                    model.remove(pos);
+                } else if model.len() == 8 {
+                    model.pop();
+                }
+                model.insert(0, (key, i));
+            } else {
+                let expect = model.iter().position(|(k, _)| *k == key).map(|pos| {
+                    let entry = model.remove(pos);
+                    model.insert(0, entry);
+                    entry.1
+                });
+                assert_eq!(cache.get(&key).copied(), expect);
+            }
+        }
+        let got: Vec<_> = cache.iter().map(|(k, v)| (*k, *v)).collect();
+        assert_eq!(got, model);
+    }
+}
@@ -0,0 +1,95 @@
+// 按链表实例统计的操作计数，用来实际测一测各种操作的复杂度
+// 开启metrics特性后，simple_stack_2、simple_deque_1/2/3的每个链表都带一组计数器，
+// 通过list.metrics()拿到当前的快照，list.reset_metrics()清零
+// - pushes          放进链表的元素个数(push_*、游标插入等)
+// - pops            从链表里取出的元素个数(pop_*、游标删除、clear等)
+// - traversal_steps 遍历经过的节点数(迭代器每产出一个元素、游标每移动一步、查找时每越过一个节点)
+// - allocations     链表自己向分配器申请的节点个数，从NodePool拿的节点不算
+// 特性关闭时Counters是空结构体，所有计数方法都是空函数，链表里不占空间也没有运行时开销
+// 计数器用Relaxed原子操作，&self遍历也能计数，链表的Send/Sync不受影响
+
+#[cfg(feature = "metrics")]
+use std::sync::atomic::{AtomicU64, Ordering::Relaxed};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Metrics {
+    pub pushes: u64,
+    pub pops: u64,
+    pub traversal_steps: u64,
+    pub allocations: u64,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct Counters {
+    #[cfg(feature = "metrics")]
+    pushes: AtomicU64,
+    #[cfg(feature = "metrics")]
+    pops: AtomicU64,
+    #[cfg(feature = "metrics")]
+    steps: AtomicU64,
+    #[cfg(feature = "metrics")]
+    allocations: AtomicU64,
+}
+
+impl Counters {
+    pub(crate) const fn new() -> Self {
+        Counters {
+            #[cfg(feature = "metrics")]
+            pushes: AtomicU64::new(0),
+            #[cfg(feature = "metrics")]
+            pops: AtomicU64::new(0),
+            #[cfg(feature = "metrics")]
+            steps: AtomicU64::new(0),
+            #[cfg(feature = "metrics")]
+            allocations: AtomicU64::new(0),
+        }
+    }
+
+    #[inline]
+    pub(crate) fn push(&self) {
+        #[cfg(feature = "metrics")]
+        self.pushes.fetch_add(1, Relaxed);
+    }
+
+    #[inline]
+    pub(crate) fn pop(&self) {
+        #[cfg(feature = "metrics")]
+        self.pops.fetch_add(1, Relaxed);
+    }
+
+    #[inline]
+    pub(crate) fn step(&self) {
+        #[cfg(feature = "metrics")]
+        self.steps.fetch_add(1, Relaxed);
+    }
+
+    #[inline]
+    pub(crate) fn alloc(&self) {
+        #[cfg(feature = "metrics")]
+        self.allocations.fetch_add(1, Relaxed);
+    }
+
+    // 分配一个新节点并放进链表，最常见的组合
+    #[inline]
+    pub(crate) fn alloc_push(&self) {
+        self.alloc();
+        self.push();
+    }
+
+    #[cfg(feature = "metrics")]
+    pub(crate) fn snapshot(&self) -> Metrics {
+        Metrics {
+            pushes: self.pushes.load(Relaxed),
+            pops: self.pops.load(Relaxed),
+            traversal_steps: self.steps.load(Relaxed),
+            allocations: self.allocations.load(Relaxed),
+        }
+    }
+
+    #[cfg(feature = "metrics")]
+    pub(crate) fn reset(&self) {
+        for counter in [&self.pushes, &self.pops, &self.steps, &self.allocations] {
+            counter.store(0, Relaxed);
+        }
+    }
+}
@@ -0,0 +1,278 @@
+// O(1)取最小值的栈和队列
+// MinStack: 元素栈之外再维护一个"最小值栈"，只在新元素不大于当前最小值时才压入它的副本，
+//   弹出的元素等于最小值栈顶时最小值栈也跟着弹出；最小值栈顶永远是当前所有元素的最小值
+//   相等的值也要压入，否则弹出一个重复的最小值之后另一个就丢了
+// MinQueue: 经典的"两个栈拼成队列"，入队压到inbox，出队从outbox弹，outbox空了再把inbox整体倒过去；
+//   两个栈都是MinStack，队列的最小值就是两个栈最小值中较小的那个
+//   每个元素最多被倒一次，出队均摊O(1)
+// 所有栈都是simple_stack_2::List
+
+use std::fmt;
+
+use crate::simple_stack_2::List;
+
+pub struct MinStack<T> {
+    elems: List<T>,
+    // 非严格递减，栈顶是当前最小值
+    mins: List<T>,
+    len: usize,
+}
+
+impl<T: Ord + Clone> MinStack<T> {
+    pub fn new() -> Self {
+        MinStack {
+            elems: List::new(),
+            mins: List::new(),
+            len: 0,
+        }
+    }
+
+    pub fn push(&mut self, elem: T) {
+        if self.mins.peek().is_none_or(|min| elem <= *min) {
+            self.mins.push(elem.clone());
+        }
+        self.elems.push(elem);
+        self.len += 1;
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        let elem = self.elems.pop()?;
+        if self.mins.peek() == Some(&elem) {
+            self.mins.pop();
+        }
+        self.len -= 1;
+        Some(elem)
+    }
+
+    pub fn peek(&self) -> Option<&T> {
+        self.elems.peek()
+    }
+
+    pub fn min(&self) -> Option<&T> {
+        self.mins.peek()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // 从栈顶到栈底
+    pub fn iter(&self) -> impl Iterator<Item = &T> + '_ {
+        self.elems.iter()
+    }
+}
+
+impl<T: Ord + Clone> Default for MinStack<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Ord + Clone> Extend<T> for MinStack<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for elem in iter {
+            self.push(elem);
+        }
+    }
+}
+
+impl<T: Ord + Clone + fmt::Debug> fmt::Debug for MinStack<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MinStack")
+            .field("min", &self.min())
+            .field("elems", &self.iter().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+pub struct MinQueue<T> {
+    // 新入队的元素，栈顶是最新的
+    inbox: MinStack<T>,
+    // 栈顶是队头
+    outbox: MinStack<T>,
+}
+
+impl<T: Ord + Clone> MinQueue<T> {
+    pub fn new() -> Self {
+        MinQueue {
+            inbox: MinStack::new(),
+            outbox: MinStack::new(),
+        }
+    }
+
+    pub fn push(&mut self, elem: T) {
+        self.inbox.push(elem);
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        self.refill();
+        self.outbox.pop()
+    }
+
+    // 需要&mut self: outbox为空时要先把inbox倒过去
+    pub fn peek(&mut self) -> Option<&T> {
+        self.refill();
+        self.outbox.peek()
+    }
+
+    pub fn min(&self) -> Option<&T> {
+        match (self.inbox.min(), self.outbox.min()) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.inbox.len() + self.outbox.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn refill(&mut self) {
+        if self.outbox.is_empty() {
+            while let Some(elem) = self.inbox.pop() {
+                self.outbox.push(elem);
+            }
+        }
+    }
+}
+
+impl<T: Ord + Clone> Default for MinQueue<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Ord + Clone> Extend<T> for MinQueue<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for elem in iter {
+            self.push(elem);
+        }
+    }
+}
+
+// 没有对外的迭代器，序列化时先写outbox(从队头开始)，再倒着写inbox，得到从队头到队尾的序列；
+// 格式和其他序列一样，见serde_impls
+#[cfg(feature = "serde")]
+impl<T: serde::Serialize> serde::Serialize for MinQueue<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let inbox: Vec<&T> = self.inbox.elems.iter().collect();
+        serializer.collect_seq(self.outbox.elems.iter().chain(inbox.into_iter().rev()))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: serde::Deserialize<'de> + Ord + Clone> serde::Deserialize<'de> for MinQueue<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        crate::serde_impls::deserialize_seq(deserializer, MinQueue::new(), MinQueue::push)
+    }
+}
+
+impl<T: Ord + Clone + fmt::Debug> fmt::Debug for MinQueue<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MinQueue")
+            .field("min", &self.min())
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::XorShift;
+    use std::collections::VecDeque;
+
+    #[test]
+    fn stack_tracks_duplicate_minimums() {
+        let mut stack = MinStack::new();
+        assert_eq!(stack.min(), None);
+        stack.extend([5, 3, 3, 7, 1]);
+        assert_eq!(stack.min(), Some(&1));
+        assert_eq!(stack.pop(), Some(1));
+        assert_eq!(stack.min(), Some(&3));
+        assert_eq!(stack.pop(), Some(7));
+        assert_eq!(stack.pop(), Some(3));
+        // 另一个3还在
+        assert_eq!(stack.min(), Some(&3));
+        assert_eq!(stack.pop(), Some(3));
+        assert_eq!((stack.min(), stack.peek()), (Some(&5), Some(&5)));
+        assert_eq!(stack.len(), 1);
+    }
+
+    #[test]
+    fn queue_is_fifo_with_min() {
+        let mut queue = MinQueue::new();
+        queue.extend([4, 2, 6]);
+        assert_eq!(queue.min(), Some(&2));
+        assert_eq!(queue.peek(), Some(&4));
+        assert_eq!(queue.pop(), Some(4));
+        queue.push(1);
+        assert_eq!(queue.min(), Some(&1));
+        assert_eq!(queue.pop(), Some(2));
+        assert_eq!(queue.min(), Some(&1));
+        assert_eq!(queue.pop(), Some(6));
+        assert_eq!(queue.pop(), Some(1));
+        assert_eq!((queue.pop(), queue.min()), (None, None));
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn stack_matches_brute_force() {
+        let mut rng = XorShift::new(694);
+        let mut stack = MinStack::new();
+        let mut model: Vec<u64> = Vec::new();
+        for _ in 0..3000 {
+            if rng.next_u64().is_multiple_of(3) {
+                assert_eq!(stack.pop(), model.pop());
+            } else {
+                let x = rng.next_u64() % 20;
+                stack.push(x);
+                model.push(x);
+            }
+            assert_eq!(stack.min(), model.iter().min());
+            assert_eq!(stack.peek(), model.last());
+            assert_eq!(stack.len(), model.len());
+        }
+    }
+
+    #[test]
+    fn queue_matches_brute_force() {
+        let mut rng = XorShift::new(1694);
+        let mut queue = MinQueue::new();
+        let mut model: VecDeque<u64> = VecDeque::new();
+        for _ in 0..3000 {
+            if rng.next_u64().is_multiple_of(3) {
+                assert_eq!(queue.pop(), model.pop_front());
+            } else {
+                let x = rng.next_u64() % 20;
+                queue.push(x);
+                model.push_back(x);
+            }
+            assert_eq!(queue.min(), model.iter().min());
+            assert_eq!(queue.len(), model.len());
+        }
+        assert_eq!(queue.peek(), model.front());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_queue_in_fifo_order() {
+        let mut queue = MinQueue::new();
+        queue.extend([3, 1, 2]);
+        // pop之后1和2倒进了outbox，后来的4在inbox
+        assert_eq!(queue.pop(), Some(3));
+        queue.push(4);
+        let json = serde_json::to_string(&queue).unwrap();
+        assert_eq!(json, "[1,2,4]");
+        let mut back: MinQueue<i32> = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.min(), Some(&1));
+        assert_eq!((back.pop(), back.pop(), back.pop(), back.pop()), (Some(1), Some(2), Some(4), None));
+    }
+}
@@ -0,0 +1,236 @@
+// 单调双端队列，用来在O(1)均摊时间内求滑动窗口的最大值/最小值
+// 队列里存(序号, 值)，从头到尾值保持单调:
+// - 求最大值时单调递减: push一个新值时，先从尾部弹出所有不比它大的旧值，它们比新值先过期又不比新值大，
+//   再也不可能成为窗口最大值
+// - 求最小值时单调递增，道理相同
+// 于是队头就是当前窗口的极值；窗口滑动时用pop_expired从头部弹出已经移出窗口的序号
+// 每个元素最多进出队列各一次，n次push总共O(n)
+// 底层是simple_deque_3::List，两端都要push/pop
+
+use std::fmt;
+
+use crate::simple_deque_3::List;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Extreme {
+    Max,
+    Min,
+}
+
+pub struct MonotonicDeque<T> {
+    entries: List<(usize, T)>,
+    extreme: Extreme,
+    // 下一次push分配的序号
+    next_index: usize,
+}
+
+impl<T: Ord> MonotonicDeque<T> {
+    pub fn new(extreme: Extreme) -> Self {
+        MonotonicDeque {
+            entries: List::new(),
+            extreme,
+            next_index: 0,
+        }
+    }
+
+    // 队头是最大值，值从头到尾递减
+    pub fn max() -> Self {
+        Self::new(Extreme::Max)
+    }
+
+    // 队头是最小值，值从头到尾递增
+    pub fn min() -> Self {
+        Self::new(Extreme::Min)
+    }
+
+    pub fn extreme(&self) -> Extreme {
+        self.extreme
+    }
+
+    // 新值的序号是之前push过的元素个数
+    pub fn push(&mut self, value: T) {
+        while let Some((_, back)) = self.entries.back() {
+            let dominated = match self.extreme {
+                Extreme::Max => *back <= value,
+                Extreme::Min => *back >= value,
+            };
+            if !dominated {
+                break;
+            }
+            self.entries.pop_back();
+        }
+        self.entries.push_back((self.next_index, value));
+        self.next_index += 1;
+        self.check_invariants();
+    }
+
+    // 只保留最近window次push的元素，返回弹出的个数
+    pub fn pop_expired(&mut self, window: usize) -> usize {
+        let oldest = self.next_index.saturating_sub(window);
+        let mut popped = 0;
+        while self.entries.front().is_some_and(|&(index, _)| index < oldest) {
+            self.entries.pop_front();
+            popped += 1;
+        }
+        popped
+    }
+
+    // 当前窗口的最大值(或最小值)
+    pub fn current_extreme(&self) -> Option<&T> {
+        self.entries.front().map(|(_, value)| value)
+    }
+
+    // 极值是第几次push进来的
+    pub fn current_extreme_index(&self) -> Option<usize> {
+        self.entries.front().map(|&(index, _)| index)
+    }
+
+    // 一共push过多少个元素(包括已经被弹出的)
+    pub fn pushed(&self) -> usize {
+        self.next_index
+    }
+
+    // 队列里实际保留的元素个数，不是窗口大小
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    // 清空并把序号归零
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.next_index = 0;
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> + '_ {
+        self.entries.iter().map(|(_, value)| value)
+    }
+
+    // 序号严格递增，值严格单调
+    pub fn assert_invariants(&self) {
+        let mut prev: Option<&(usize, T)> = None;
+        for entry in self.entries.iter() {
+            assert!(entry.0 < self.next_index, "index from the future");
+            if let Some(prev) = prev {
+                assert!(prev.0 < entry.0, "indices out of order");
+                let ok = match self.extreme {
+                    Extreme::Max => prev.1 > entry.1,
+                    Extreme::Min => prev.1 < entry.1,
+                };
+                assert!(ok, "values are not monotonic");
+            }
+            prev = Some(entry);
+        }
+    }
+
+    #[inline]
+    fn check_invariants(&self) {
+        #[cfg(all(debug_assertions, feature = "debug-invariants"))]
+        self.assert_invariants();
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for MonotonicDeque<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MonotonicDeque")
+            .field("extreme", &self.extreme)
+            .field("entries", &self.entries.iter().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+// 每个长度为window的窗口的最大值，共values.len() - window + 1个；window为0时panic
+pub fn sliding_window_max<T: Ord + Clone>(values: &[T], window: usize) -> Vec<T> {
+    sliding_window(values, window, Extreme::Max)
+}
+
+pub fn sliding_window_min<T: Ord + Clone>(values: &[T], window: usize) -> Vec<T> {
+    sliding_window(values, window, Extreme::Min)
+}
+
+fn sliding_window<T: Ord + Clone>(values: &[T], window: usize, extreme: Extreme) -> Vec<T> {
+    assert!(window > 0, "window must be positive");
+    let mut deque = MonotonicDeque::new(extreme);
+    let mut out = Vec::with_capacity(values.len().saturating_sub(window - 1));
+    for (i, value) in values.iter().enumerate() {
+        deque.push(value.clone());
+        deque.pop_expired(window);
+        if i + 1 >= window {
+            out.push(deque.current_extreme().unwrap().clone());
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::XorShift;
+
+    #[test]
+    fn max_deque_basics() {
+        let mut deque = MonotonicDeque::max();
+        assert_eq!(deque.current_extreme(), None);
+        for x in [3, 1, 2] {
+            deque.push(x);
+        }
+        // 1被2淘汰
+        assert_eq!(deque.iter().copied().collect::<Vec<_>>(), vec![3, 2]);
+        assert_eq!(deque.current_extreme(), Some(&3));
+        assert_eq!(deque.current_extreme_index(), Some(0));
+        assert_eq!(deque.pop_expired(2), 1);
+        assert_eq!(deque.current_extreme(), Some(&2));
+        deque.push(5);
+        assert_eq!(deque.len(), 1);
+        assert_eq!(deque.pushed(), 4);
+        deque.assert_invariants();
+        deque.clear();
+        assert!(deque.is_empty());
+        assert_eq!(deque.pushed(), 0);
+    }
+
+    #[test]
+    fn min_deque_keeps_increasing_order() {
+        let mut deque = MonotonicDeque::min();
+        for x in [5, 3, 4, 4, 6] {
+            deque.push(x);
+            deque.assert_invariants();
+        }
+        assert_eq!(deque.iter().copied().collect::<Vec<_>>(), vec![3, 4, 6]);
+        assert_eq!(deque.extreme(), Extreme::Min);
+    }
+
+    #[test]
+    fn sliding_window_helpers() {
+        let values = [1, 3, -1, -3, 5, 3, 6, 7];
+        assert_eq!(sliding_window_max(&values, 3), vec![3, 3, 5, 5, 6, 7]);
+        assert_eq!(sliding_window_min(&values, 3), vec![-1, -3, -3, -3, 3, 3]);
+        assert_eq!(sliding_window_max(&values, 1), values.to_vec());
+        assert_eq!(sliding_window_max(&values, 8), vec![7]);
+        assert!(sliding_window_max(&values, 9).is_empty());
+        assert!(sliding_window_min::<i32>(&[], 2).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "window must be positive")]
+    fn zero_window_panics() {
+        sliding_window_max(&[1, 2], 0);
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        let mut rng = XorShift::new(688);
+        let values: Vec<u64> = (0..500).map(|_| rng.next_u64() % 50).collect();
+        for window in [1, 2, 7, 31, 500] {
+            let expected_max: Vec<u64> =
+                values.windows(window).map(|w| *w.iter().max().unwrap()).collect();
+            let expected_min: Vec<u64> =
+                values.windows(window).map(|w| *w.iter().min().unwrap()).collect();
+            assert_eq!(sliding_window_max(&values, window), expected_max);
+            assert_eq!(sliding_window_min(&values, window), expected_min);
+        }
+    }
+}
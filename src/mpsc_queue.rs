@@ -0,0 +1,377 @@
+// Vyukov风格的侵入式多生产者单消费者(MPSC)队列
+// 异步运行时里常用这种队列存放待执行的任务: 节点自己内嵌next指针(Link)，入队不需要额外分配
+// 算法:
+// - 生产者: 把节点的next清空，用一次swap把head换成自己，再把旧head的next指向自己
+//   只有一次原子swap，没有CAS重试，所以入队是wait-free的
+// - 消费者从tail开始沿next往前取；队列里常驻一个stub节点，保证取到最后一个元素时还有节点可以留作tail
+// - swap和"旧head.next = 自己"之间存在一个短暂窗口，此时链表是断开的，
+//   消费者会看到Inconsistent，稍后重试即可
+// Queue只负责串链，不拥有节点；channel()在它之上提供了一个普通的按值收发的包装
+
+use std::marker::PhantomData;
+use std::ptr::{self, NonNull};
+use std::sync::atomic::{AtomicPtr, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+use crate::instrument::mutation;
+
+// 嵌入在节点里的链接字段
+pub struct Link {
+    next: AtomicPtr<Link>,
+}
+
+impl Link {
+    pub const fn new() -> Self {
+        Link {
+            next: AtomicPtr::new(ptr::null_mut()),
+        }
+    }
+}
+
+impl Default for Link {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 可以放进侵入式队列的节点类型
+///
+/// # Safety
+///
+/// links必须返回节点内嵌的那个Link的地址，from_links必须是它的逆运算；
+/// 节点在队列里期间地址不能改变
+pub unsafe trait Linked {
+    fn links(node: NonNull<Self>) -> NonNull<Link>;
+
+    /// # Safety
+    ///
+    /// link必须是某个Self节点通过links得到的地址
+    unsafe fn from_links(link: NonNull<Link>) -> NonNull<Self>;
+}
+
+// 消费者一次pop的结果
+pub enum Pop<T> {
+    Data(NonNull<T>),
+    Empty,
+    // 有生产者正在入队的中途，队列里有数据但暂时取不到，稍后重试
+    Inconsistent,
+}
+
+pub struct Queue<T: Linked> {
+    // 最近入队的节点，所有生产者竞争
+    head: AtomicPtr<Link>,
+    // 下一个要出队的节点，只有消费者访问
+    tail: AtomicPtr<Link>,
+    // 常驻的哑节点，单独分配保证地址稳定
+    stub: *mut Link,
+    _boo: PhantomData<*const T>,
+}
+
+impl<T: Linked> Queue<T> {
+    pub fn new() -> Self {
+        let stub = Box::into_raw(Box::new(Link::new()));
+        Queue {
+            head: AtomicPtr::new(stub),
+            tail: AtomicPtr::new(stub),
+            stub,
+            _boo: PhantomData,
+        }
+    }
+
+    /// 任意线程都可以调用
+    ///
+    /// # Safety
+    ///
+    /// node必须有效且当前不在任何队列里，在被pop出来之前不能移动或释放
+    pub unsafe fn push(&self, node: NonNull<T>) {
+        self.push_link(T::links(node).as_ptr());
+        mutation!("push");
+    }
+
+    unsafe fn push_link(&self, link: *mut Link) {
+        (*link).next.store(ptr::null_mut(), Ordering::Relaxed);
+        // AcqRel: Release发布节点内容，Acquire保证能安全写旧head的next
+        let prev = self.head.swap(link, Ordering::AcqRel);
+        // 这一步之前链表在prev处断开，消费者会看到Inconsistent
+        (*prev).next.store(link, Ordering::Release);
+    }
+
+    /// # Safety
+    ///
+    /// 同一时刻只能有一个线程调用pop
+    pub unsafe fn pop(&self) -> Pop<T> {
+        let mut tail = self.tail.load(Ordering::Relaxed);
+        let mut next = (*tail).next.load(Ordering::Acquire);
+
+        if tail == self.stub {
+            // 跳过stub
+            if next.is_null() {
+                return Pop::Empty;
+            }
+            self.tail.store(next, Ordering::Relaxed);
+            tail = next;
+            next = (*next).next.load(Ordering::Acquire);
+        }
+
+        if !next.is_null() {
+            self.tail.store(next, Ordering::Relaxed);
+            mutation!("pop");
+            return Pop::Data(T::from_links(NonNull::new_unchecked(tail)));
+        }
+
+        // tail后面没有节点了: 要么它是最后一个，要么有生产者正在入队
+        if tail != self.head.load(Ordering::Acquire) {
+            return Pop::Inconsistent;
+        }
+        // tail是最后一个节点，把stub放回队尾，这样取走tail之后队列里还有节点
+        self.push_link(self.stub);
+        next = (*tail).next.load(Ordering::Acquire);
+        if !next.is_null() {
+            self.tail.store(next, Ordering::Relaxed);
+            mutation!("pop");
+            return Pop::Data(T::from_links(NonNull::new_unchecked(tail)));
+        }
+        // 生产者换完head还没接上next，消费者稍后重试
+        mutation!("pop_inconsistent");
+        Pop::Inconsistent
+    }
+}
+
+impl<T: Linked> Default for Queue<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Queue不拥有节点，析构时只释放stub；还在队列里的节点由使用者自己负责
+impl<T: Linked> Drop for Queue<T> {
+    fn drop(&mut self) {
+        unsafe { drop(Box::from_raw(self.stub)) };
+    }
+}
+
+unsafe impl<T: Linked + Send> Send for Queue<T> {}
+unsafe impl<T: Linked + Send> Sync for Queue<T> {}
+
+// 非侵入式包装: 每个元素装进一个内嵌Link的堆节点
+#[repr(C)]
+struct Node<T> {
+    // 必须是第一个字段，Link和Node的地址才相同
+    link: Link,
+    elem: T,
+}
+
+unsafe impl<T> Linked for Node<T> {
+    fn links(node: NonNull<Self>) -> NonNull<Link> {
+        node.cast()
+    }
+
+    unsafe fn from_links(link: NonNull<Link>) -> NonNull<Self> {
+        link.cast()
+    }
+}
+
+struct Inner<T> {
+    queue: Queue<Node<T>>,
+}
+
+impl<T> Drop for Inner<T> {
+    fn drop(&mut self) {
+        // 所有生产者和消费者都已经不在，不会再出现Inconsistent
+        while let Pop::Data(node) = unsafe { self.queue.pop() } {
+            drop(unsafe { Box::from_raw(node.as_ptr()) });
+        }
+    }
+}
+
+// 创建一个按值收发的MPSC队列，Producer可以Clone给多个线程
+pub fn channel<T>() -> (Producer<T>, Consumer<T>) {
+    let inner = Arc::new(Inner { queue: Queue::new() });
+    (
+        Producer {
+            inner: Arc::clone(&inner),
+        },
+        Consumer { inner },
+    )
+}
+
+pub struct Producer<T> {
+    inner: Arc<Inner<T>>,
+}
+
+impl<T> Producer<T> {
+    pub fn push(&self, elem: T) {
+        let node = Box::new(Node {
+            link: Link::new(),
+            elem,
+        });
+        // SAFETY: 新分配的节点不在任何队列里，出队后由Consumer重新装回Box释放
+        unsafe { self.inner.queue.push(NonNull::from(Box::leak(node))) };
+    }
+}
+
+impl<T> Clone for Producer<T> {
+    fn clone(&self) -> Self {
+        Producer {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+
+// 唯一的消费者，pop需要&mut self，从类型上保证了单消费者
+pub struct Consumer<T> {
+    inner: Arc<Inner<T>>,
+}
+
+impl<T> Consumer<T> {
+    // 队列为空时返回None；遇到入队中途的生产者会等它完成
+    pub fn pop(&mut self) -> Option<T> {
+        loop {
+            match unsafe { self.inner.queue.pop() } {
+                Pop::Data(node) => return Some(unsafe { Box::from_raw(node.as_ptr()) }.elem),
+                Pop::Empty => return None,
+                Pop::Inconsistent => thread::yield_now(),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::mem::offset_of;
+
+    // 侵入式用法: Link可以放在任意位置，用offset_of换算
+    struct Task {
+        id: u32,
+        link: Link,
+    }
+
+    unsafe impl Linked for Task {
+        fn links(node: NonNull<Self>) -> NonNull<Link> {
+            unsafe { NonNull::new_unchecked(ptr::addr_of_mut!((*node.as_ptr()).link)) }
+        }
+
+        unsafe fn from_links(link: NonNull<Link>) -> NonNull<Self> {
+            let base = (link.as_ptr() as *mut u8).sub(offset_of!(Task, link));
+            NonNull::new_unchecked(base as *mut Task)
+        }
+    }
+
+    fn task(id: u32) -> Task {
+        Task { id, link: Link::new() }
+    }
+
+    fn pop_id(queue: &Queue<Task>) -> Option<u32> {
+        match unsafe { queue.pop() } {
+            Pop::Data(t) => Some(unsafe { t.as_ref().id }),
+            Pop::Empty => None,
+            Pop::Inconsistent => unreachable!("no concurrent producers"),
+        }
+    }
+
+    #[test]
+    fn intrusive_basics() {
+        // 节点由调用者持有，队列只串起它们
+        let mut tasks: Vec<Task> = (1..=4).map(task).collect();
+        // 所有节点指针都从同一个裸指针派生，避免再次借用Vec让之前的指针失效
+        let base = tasks.as_mut_ptr();
+        let node = |i: usize| unsafe { NonNull::new_unchecked(base.add(i)) };
+        let queue = Queue::new();
+        assert_eq!(pop_id(&queue), None);
+        for i in 0..3 {
+            unsafe { queue.push(node(i)) };
+        }
+        assert_eq!(pop_id(&queue), Some(1));
+        assert_eq!(pop_id(&queue), Some(2));
+        unsafe { queue.push(node(3)) };
+        assert_eq!(pop_id(&queue), Some(3));
+        assert_eq!(pop_id(&queue), Some(4));
+        assert_eq!(pop_id(&queue), None);
+
+        // 出队之后节点可以再次入队
+        unsafe { queue.push(node(0)) };
+        assert_eq!(pop_id(&queue), Some(1));
+        assert_eq!(pop_id(&queue), None);
+        drop(tasks);
+    }
+
+    #[test]
+    fn inconsistent_while_push_in_progress() {
+        let mut a = task(1);
+        let mut b = task(2);
+        let queue = Queue::new();
+        unsafe {
+            queue.push(NonNull::from(&mut a));
+            // 模拟一个生产者swap了head但还没来得及连上next
+            let link = Task::links(NonNull::from(&mut b)).as_ptr();
+            let prev = queue.head.swap(link, Ordering::AcqRel);
+            assert!(matches!(queue.pop(), Pop::Inconsistent));
+            (*prev).next.store(link, Ordering::Release);
+        }
+        assert_eq!(pop_id(&queue), Some(1));
+        assert_eq!(pop_id(&queue), Some(2));
+        assert_eq!(pop_id(&queue), None);
+    }
+
+    #[test]
+    fn channel_basics() {
+        let (tx, mut rx) = channel();
+        assert_eq!(rx.pop(), None);
+        tx.push(1);
+        tx.clone().push(2);
+        assert_eq!(rx.pop(), Some(1));
+        assert_eq!(rx.pop(), Some(2));
+        assert_eq!(rx.pop(), None);
+    }
+
+    #[test]
+    fn multiple_producers() {
+        const PRODUCERS: usize = 4;
+        const PER_PRODUCER: usize = 10_000;
+        let (tx, mut rx) = channel();
+        let handles: Vec<_> = (0..PRODUCERS)
+            .map(|p| {
+                let tx = tx.clone();
+                thread::spawn(move || {
+                    for i in 0..PER_PRODUCER {
+                        tx.push((p, i));
+                    }
+                })
+            })
+            .collect();
+
+        // 每个生产者自己的元素必须按顺序出队
+        let mut next = [0; PRODUCERS];
+        let mut received = 0;
+        while received < PRODUCERS * PER_PRODUCER {
+            match rx.pop() {
+                Some((p, i)) => {
+                    assert_eq!(i, next[p]);
+                    next[p] += 1;
+                    received += 1;
+                }
+                None => thread::yield_now(),
+            }
+        }
+        for h in handles {
+            h.join().unwrap();
+        }
+        assert_eq!(rx.pop(), None);
+    }
+
+    #[test]
+    fn drop_remaining_elements() {
+        let marker = Arc::new(());
+        let (tx, mut rx) = channel();
+        for _ in 0..5 {
+            tx.push(Arc::clone(&marker));
+        }
+        drop(rx.pop());
+        assert_eq!(Arc::strong_count(&marker), 5);
+        drop(tx);
+        drop(rx);
+        assert_eq!(Arc::strong_count(&marker), 1);
+    }
+}
@@ -0,0 +1,269 @@
+// Michael-Scott无锁MPMC队列
+// 经典的双指针队列: head指向一个哑节点(dummy)，真正的队头元素在dummy.next里
+// 有了哑节点，入队只碰tail，出队只碰head，空队列时head和tail指向同一个dummy
+// 入队分两步: 先CAS把新节点挂到tail.next，再CAS推进tail；
+// 第二步可能由任何看到"tail落后"的线程帮忙完成，所以算法是无锁的
+// 和treiber_stack一样，出队后旧的哑节点交给回收策略R延迟释放，默认用epoch
+
+use std::marker::PhantomData;
+use std::mem::MaybeUninit;
+use std::ptr;
+
+use crate::instrument::mutation;
+use crate::reclaim::{Epoch, Reclaim, ReclaimGuard};
+use crate::sync::atomic::{AtomicPtr, Ordering};
+
+pub struct MsQueue<T, R: Reclaim = Epoch> {
+    head: AtomicPtr<Node<T>>,
+    tail: AtomicPtr<Node<T>>,
+    _reclaim: PhantomData<R>,
+}
+
+struct Node<T> {
+    // 哑节点的elem未初始化；普通节点的elem在出队时被取走，之后它成为新的哑节点
+    elem: MaybeUninit<T>,
+    next: AtomicPtr<Node<T>>,
+}
+
+impl<T> Node<T> {
+    fn alloc(elem: MaybeUninit<T>) -> *mut Self {
+        Box::into_raw(Box::new(Node {
+            elem,
+            next: AtomicPtr::new(ptr::null_mut()),
+        }))
+    }
+}
+
+impl<T> MsQueue<T> {
+    pub fn new() -> Self {
+        Self::empty()
+    }
+}
+
+impl<T, R: Reclaim> MsQueue<T, R> {
+    // 用指定的回收策略创建，例如MsQueue::with_reclaim(Hazard)
+    pub fn with_reclaim(_reclaim: R) -> Self {
+        Self::empty()
+    }
+
+    fn empty() -> Self {
+        let dummy = Node::alloc(MaybeUninit::uninit());
+        MsQueue {
+            head: AtomicPtr::new(dummy),
+            tail: AtomicPtr::new(dummy),
+            _reclaim: PhantomData,
+        }
+    }
+
+    pub fn push(&self, elem: T) {
+        let new = Node::alloc(MaybeUninit::new(elem));
+        let guard = R::pin();
+        loop {
+            let tail = guard.protect(0, &self.tail);
+            // SAFETY: tail受guard保护，即使已经出队也还没有被释放
+            let next = unsafe { (*tail).next.load(Ordering::Acquire) };
+            if tail != self.tail.load(Ordering::Acquire) {
+                // tail在读next的过程中变了，重新来
+                continue;
+            }
+            if next.is_null() {
+                // tail确实是最后一个节点，尝试把新节点挂上去
+                let linked = unsafe {
+                    (*tail)
+                        .next
+                        .compare_exchange(ptr::null_mut(), new, Ordering::Release, Ordering::Relaxed)
+                        .is_ok()
+                };
+                if linked {
+                    // 推进tail，失败说明别的线程已经帮忙推进了
+                    let _ = self
+                        .tail
+                        .compare_exchange(tail, new, Ordering::Release, Ordering::Relaxed);
+                    mutation!("push");
+                    return;
+                }
+            } else {
+                // tail落后了，先帮忙推进再重试
+                let _ = self
+                    .tail
+                    .compare_exchange(tail, next, Ordering::Release, Ordering::Relaxed);
+            }
+        }
+    }
+
+    pub fn pop(&self) -> Option<T> {
+        let guard = R::pin();
+        loop {
+            let head = guard.protect(0, &self.head);
+            let tail = self.tail.load(Ordering::Acquire);
+            // next也要保护: CAS成功后还要从它身上读元素，而它可能同时被别的线程出队
+            // 保护next之后再确认head没变，说明next还没有被摘下
+            let next = unsafe { guard.protect(1, &(*head).next) };
+            if head != self.head.load(Ordering::Acquire) {
+                continue;
+            }
+            if next.is_null() {
+                // 只有哑节点，队列为空
+                return None;
+            }
+            if head == tail {
+                // 有元素但tail还没推进，帮忙推进后重试
+                mutation!("help_advance_tail");
+                let _ = self
+                    .tail
+                    .compare_exchange(tail, next, Ordering::Release, Ordering::Relaxed);
+                continue;
+            }
+            if self
+                .head
+                .compare_exchange(head, next, Ordering::AcqRel, Ordering::Acquire)
+                .is_ok()
+            {
+                // next成为新的哑节点，元素归CAS成功的线程所有
+                // 旧的哑节点head可能还有线程在读它的next，延迟释放(MaybeUninit不会重复析构元素)
+                mutation!("pop");
+                unsafe {
+                    let elem = (*next).elem.assume_init_read();
+                    guard.retire(head);
+                    return Some(elem);
+                }
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        let guard = R::pin();
+        let head = guard.protect(0, &self.head);
+        unsafe { (*head).next.load(Ordering::Acquire).is_null() }
+    }
+}
+
+impl<T, R: Reclaim> Default for MsQueue<T, R> {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+impl<T, R: Reclaim> Drop for MsQueue<T, R> {
+    fn drop(&mut self) {
+        // 先释放哑节点(没有元素)，再依次释放带元素的节点
+        unsafe {
+            let dummy = Box::from_raw(self.head.load(Ordering::Relaxed));
+            let mut cur = dummy.next.load(Ordering::Relaxed);
+            while !cur.is_null() {
+                let mut node = Box::from_raw(cur);
+                node.elem.assume_init_drop();
+                cur = node.next.load(Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+unsafe impl<T: Send, R: Reclaim> Send for MsQueue<T, R> {}
+unsafe impl<T: Send, R: Reclaim> Sync for MsQueue<T, R> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reclaim::Hazard;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn basics() {
+        let q = MsQueue::new();
+        assert!(q.is_empty());
+        assert_eq!(q.pop(), None);
+        q.push(1);
+        q.push(2);
+        q.push(3);
+        assert!(!q.is_empty());
+        assert_eq!(q.pop(), Some(1));
+        q.push(4);
+        assert_eq!(q.pop(), Some(2));
+        assert_eq!(q.pop(), Some(3));
+        assert_eq!(q.pop(), Some(4));
+        assert_eq!(q.pop(), None);
+        assert!(q.is_empty());
+    }
+
+    fn mpmc_stress<R: Reclaim>(reclaim: R) {
+        const PRODUCERS: usize = 4;
+        const CONSUMERS: usize = 4;
+        const PER_PRODUCER: usize = 5000;
+
+        let q = Arc::new(MsQueue::with_reclaim(reclaim));
+        let consumed = Arc::new(AtomicUsize::new(0));
+
+        let producers: Vec<_> = (0..PRODUCERS)
+            .map(|p| {
+                let q = Arc::clone(&q);
+                thread::spawn(move || {
+                    for i in 0..PER_PRODUCER {
+                        q.push((p, i));
+                    }
+                })
+            })
+            .collect();
+
+        let consumers: Vec<_> = (0..CONSUMERS)
+            .map(|_| {
+                let q = Arc::clone(&q);
+                let consumed = Arc::clone(&consumed);
+                thread::spawn(move || {
+                    // 同一个生产者的元素在任意消费者看来都必须是递增的(FIFO)
+                    let mut last = [None; PRODUCERS];
+                    let mut got = Vec::new();
+                    while consumed.load(Ordering::SeqCst) < PRODUCERS * PER_PRODUCER {
+                        if let Some((p, i)) = q.pop() {
+                            if let Some(prev) = last[p] {
+                                assert!(i > prev, "producer {} out of order: {} after {}", p, i, prev);
+                            }
+                            last[p] = Some(i);
+                            got.push((p, i));
+                            consumed.fetch_add(1, Ordering::SeqCst);
+                        } else {
+                            thread::yield_now();
+                        }
+                    }
+                    got
+                })
+            })
+            .collect();
+
+        for p in producers {
+            p.join().unwrap();
+        }
+        let mut all: Vec<(usize, usize)> = consumers.into_iter().flat_map(|c| c.join().unwrap()).collect();
+        all.sort();
+        let expected: Vec<(usize, usize)> = (0..PRODUCERS)
+            .flat_map(|p| (0..PER_PRODUCER).map(move |i| (p, i)))
+            .collect();
+        assert_eq!(all, expected);
+        assert!(q.is_empty());
+    }
+
+    #[test]
+    fn mpmc_stress_epoch() {
+        mpmc_stress(Epoch);
+    }
+
+    #[test]
+    fn mpmc_stress_hazard() {
+        mpmc_stress(Hazard);
+    }
+
+    #[test]
+    fn drop_remaining_elements() {
+        let marker = Arc::new(());
+        let q = MsQueue::new();
+        for _ in 0..5 {
+            q.push(Arc::clone(&marker));
+        }
+        drop(q.pop());
+        assert_eq!(Arc::strong_count(&marker), 5);
+        drop(q);
+        assert_eq!(Arc::strong_count(&marker), 1);
+    }
+}
@@ -0,0 +1,217 @@
+// 多个链表共享的节点内存池
+// 高频push/pop的场景里大部分时间花在分配器上，用完的节点内存先还给池子，下次push直接拿来用
+// 不同链表的节点类型不一样(simple_stack_2是elem+next，simple_deque_3是prev+next+elem)，
+// 池子按节点的Layout分桶保存空闲内存块: 布局相同的节点类型可以互相复用，布局不同的各用各的
+// 每个块都是按对应节点类型的Layout从全局分配器申请的，所以从池子里拿出去的节点
+// 即使最后被链表当成普通的Box释放也是合法的，链表不用区分节点从哪来
+
+use std::alloc::{self, Layout};
+use std::fmt;
+use std::marker::PhantomData;
+use std::ptr::NonNull;
+
+struct Bucket {
+    layout: Layout,
+    free: Vec<NonNull<u8>>,
+}
+
+pub struct NodePool<T> {
+    buckets: Vec<Bucket>,
+    // 每个桶最多缓存多少个空闲块，超出的直接还给分配器
+    limit: usize,
+    // 池子里只有未初始化的内存，不持有T
+    _boo: PhantomData<fn(T) -> T>,
+}
+
+impl<T> NodePool<T> {
+    pub fn new() -> Self {
+        Self::with_limit(usize::MAX)
+    }
+
+    pub fn with_limit(limit: usize) -> Self {
+        NodePool {
+            buckets: Vec::new(),
+            limit,
+            _boo: PhantomData,
+        }
+    }
+
+    // 当前缓存的空闲块总数
+    pub fn len(&self) -> usize {
+        self.buckets.iter().map(|b| b.free.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    // 把缓存的空闲块全部还给分配器
+    pub fn clear(&mut self) {
+        for bucket in &mut self.buckets {
+            for block in bucket.free.drain(..) {
+                unsafe { alloc::dealloc(block.as_ptr(), bucket.layout) };
+            }
+        }
+    }
+
+    // 分配一个节点并写入node，池子里有同布局的空闲块就复用
+    pub(crate) fn alloc<N>(&mut self, node: N) -> NonNull<N> {
+        const { assert!(std::mem::size_of::<N>() != 0) };
+        let layout = Layout::new::<N>();
+        let block = self
+            .buckets
+            .iter_mut()
+            .find(|b| b.layout == layout)
+            .and_then(|b| b.free.pop())
+            .unwrap_or_else(|| {
+                // SAFETY: layout大小非零
+                let ptr = unsafe { alloc::alloc(layout) };
+                NonNull::new(ptr).unwrap_or_else(|| alloc::handle_alloc_error(layout))
+            });
+        let ptr = block.cast::<N>();
+        // SAFETY: block按N的布局分配，当前没有被任何人使用
+        unsafe { ptr.as_ptr().write(node) };
+        ptr
+    }
+
+    /// 回收一个节点的内存，不会析构节点内容
+    ///
+    /// # Safety
+    ///
+    /// `ptr`必须是用`Layout::new::<N>()`从全局分配器申请的(来自`alloc`或者`Box<N>`都可以)，
+    /// 节点里需要析构的内容已经被移走或析构，调用之后不能再通过任何指针访问它
+    pub(crate) unsafe fn recycle<N>(&mut self, ptr: NonNull<N>) {
+        let layout = Layout::new::<N>();
+        let index = match self.buckets.iter().position(|b| b.layout == layout) {
+            Some(index) => index,
+            None => {
+                self.buckets.push(Bucket {
+                    layout,
+                    free: Vec::new(),
+                });
+                self.buckets.len() - 1
+            }
+        };
+        let bucket = &mut self.buckets[index];
+        if bucket.free.len() < self.limit {
+            bucket.free.push(ptr.cast());
+        } else {
+            unsafe { alloc::dealloc(ptr.as_ptr().cast(), layout) };
+        }
+    }
+}
+
+impl<T> Drop for NodePool<T> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+impl<T> Default for NodePool<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> fmt::Debug for NodePool<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NodePool")
+            .field("free", &self.len())
+            .field("buckets", &self.buckets.len())
+            .field("limit", &self.limit)
+            .finish()
+    }
+}
+
+// 池子里只有裸内存块，没有T的值，可以在线程间转移
+unsafe impl<T> Send for NodePool<T> {}
+unsafe impl<T> Sync for NodePool<T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simple_deque_3;
+    #[cfg(feature = "safe")]
+    use crate::{simple_deque_2, simple_stack_2};
+    use std::rc::Rc;
+
+    // 和simple_stack_2混用的两个测试要同时开safe特性
+    #[cfg(feature = "safe")]
+    #[test]
+    fn recycles_between_same_layout_lists() {
+        let mut pool = NodePool::new();
+        let mut stack = simple_stack_2::List::new();
+        for i in 0..10 {
+            stack.push_pooled(i, &mut pool);
+        }
+        assert!(pool.is_empty());
+        while stack.pop_pooled(&mut pool).is_some() {}
+        assert_eq!(pool.len(), 10);
+
+        // 单向队列的节点同样是elem+next，直接复用栈还回来的块
+        let mut queue = simple_deque_2::List::new();
+        for i in 0..4 {
+            queue.push_back_pooled(i, &mut pool);
+        }
+        assert_eq!(pool.len(), 6);
+        assert_eq!(queue.pop_front_pooled(&mut pool), Some(0));
+        assert_eq!(pool.len(), 7);
+        // 没有走池子的pop也能正常释放池子分配的节点
+        assert_eq!(queue.pop_front(), Some(1));
+        drop(queue);
+        assert_eq!(pool.len(), 7);
+    }
+
+    #[cfg(feature = "safe")]
+    #[test]
+    fn separate_buckets_for_different_layouts() {
+        let mut pool = NodePool::new();
+        let mut stack = simple_stack_2::List::new();
+        let mut deque = simple_deque_3::List::new();
+        stack.push_pooled(1u64, &mut pool);
+        deque.push_back_pooled(2u64, &mut pool);
+        deque.push_front_pooled(3u64, &mut pool);
+        assert_eq!(stack.pop_pooled(&mut pool), Some(1));
+        assert_eq!(deque.pop_back_pooled(&mut pool), Some(2));
+        assert_eq!(pool.len(), 2);
+        // 双向链表的节点比栈节点大，不会拿到栈还回来的块
+        deque.push_back_pooled(4, &mut pool);
+        assert_eq!(pool.len(), 1);
+        assert_eq!(deque.iter().copied().collect::<Vec<_>>(), vec![3, 4]);
+        deque.clear_pooled(&mut pool);
+        assert!(deque.is_empty());
+        assert_eq!(pool.len(), 3);
+    }
+
+    #[test]
+    fn limit_caps_cached_blocks() {
+        let mut pool = NodePool::with_limit(3);
+        let mut deque = simple_deque_3::List::new();
+        for i in 0..10 {
+            deque.push_back_pooled(i, &mut pool);
+        }
+        deque.clear_pooled(&mut pool);
+        assert_eq!(pool.len(), 3);
+        pool.clear();
+        assert!(pool.is_empty());
+    }
+
+    #[test]
+    fn elements_dropped_exactly_once() {
+        let marker = Rc::new(());
+        let mut pool = NodePool::new();
+        let mut deque = simple_deque_3::List::new();
+        for _ in 0..8 {
+            deque.push_back_pooled(Rc::clone(&marker), &mut pool);
+        }
+        drop(deque.pop_front_pooled(&mut pool));
+        assert_eq!(Rc::strong_count(&marker), 8);
+        deque.clear_pooled(&mut pool);
+        assert_eq!(Rc::strong_count(&marker), 1);
+        assert_eq!(pool.len(), 8);
+    }
+}
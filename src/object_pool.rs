@@ -0,0 +1,275 @@
+// 线程安全的对象池，空闲对象放在无锁的treiber_stack::TreiberStack里
+// - get从栈上取一个空闲对象；栈空并且总数没到上限时调用工厂函数现场创建一个(惰性创建)
+// - 返回的PoolGuard可以像&mut T一样使用，drop时自动把对象放回栈上，不需要手动归还
+// - max_size限制的是池子一共创建过、仍然存活的对象个数；到上限之后try_get返回None，get等待别人归还
+// 后进先出正好让最近用过的对象(缓存还热)最先被复用
+
+use std::fmt;
+use std::mem::ManuallyDrop;
+use std::ops::{Deref, DerefMut};
+use std::thread;
+
+use crate::sync::atomic::{AtomicUsize, Ordering};
+use crate::treiber_stack::TreiberStack;
+
+pub struct ObjectPool<T> {
+    free: TreiberStack<T>,
+    factory: Box<dyn Fn() -> T + Send + Sync>,
+    max_size: usize,
+    // 已经创建、还没有detach的对象个数，包括空闲的和借出去的
+    created: AtomicUsize,
+    idle: AtomicUsize,
+}
+
+impl<T> ObjectPool<T> {
+    // max_size为0时panic
+    pub fn new(max_size: usize, factory: impl Fn() -> T + Send + Sync + 'static) -> Self {
+        assert!(max_size > 0, "max_size must be positive");
+        ObjectPool {
+            free: TreiberStack::new(),
+            factory: Box::new(factory),
+            max_size,
+            created: AtomicUsize::new(0),
+            idle: AtomicUsize::new(0),
+        }
+    }
+
+    // 没有空闲对象并且已经到上限时返回None
+    pub fn try_get(&self) -> Option<PoolGuard<'_, T>> {
+        if let Some(obj) = self.free.pop() {
+            self.idle.fetch_sub(1, Ordering::Relaxed);
+            return Some(PoolGuard::new(self, obj));
+        }
+        // 先占一个名额再创建，保证并发创建也不会超过上限
+        let mut created = self.created.load(Ordering::Relaxed);
+        while created < self.max_size {
+            match self.created.compare_exchange_weak(
+                created,
+                created + 1,
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return Some(PoolGuard::new(self, (self.factory)())),
+                Err(actual) => created = actual,
+            }
+        }
+        // 判断上限的这段时间里可能刚好有对象归还
+        let obj = self.free.pop()?;
+        self.idle.fetch_sub(1, Ordering::Relaxed);
+        Some(PoolGuard::new(self, obj))
+    }
+
+    // 到上限时让出CPU等待别人归还
+    pub fn get(&self) -> PoolGuard<'_, T> {
+        loop {
+            if let Some(guard) = self.try_get() {
+                return guard;
+            }
+            thread::yield_now();
+        }
+    }
+
+    // 预先创建对象直到空闲对象达到n个(不超过上限)，返回实际新建的个数
+    pub fn prefill(&self, n: usize) -> usize {
+        let mut added = 0;
+        while self.idle() < n {
+            let created = self.created.load(Ordering::Relaxed);
+            if created >= self.max_size {
+                break;
+            }
+            if self
+                .created
+                .compare_exchange(created, created + 1, Ordering::Relaxed, Ordering::Relaxed)
+                .is_ok()
+            {
+                self.release((self.factory)());
+                added += 1;
+            }
+        }
+        added
+    }
+
+    fn release(&self, obj: T) {
+        self.free.push(obj);
+        self.idle.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn max_size(&self) -> usize {
+        self.max_size
+    }
+
+    // 以下计数在并发情况下只是近似值
+    pub fn created(&self) -> usize {
+        self.created.load(Ordering::Relaxed)
+    }
+
+    pub fn idle(&self) -> usize {
+        self.idle.load(Ordering::Relaxed)
+    }
+
+    pub fn in_use(&self) -> usize {
+        self.created().saturating_sub(self.idle())
+    }
+}
+
+impl<T> fmt::Debug for ObjectPool<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ObjectPool")
+            .field("max_size", &self.max_size)
+            .field("created", &self.created())
+            .field("idle", &self.idle())
+            .finish()
+    }
+}
+
+// 借出的对象，drop时自动归还
+pub struct PoolGuard<'a, T> {
+    pool: &'a ObjectPool<T>,
+    obj: ManuallyDrop<T>,
+}
+
+impl<'a, T> PoolGuard<'a, T> {
+    fn new(pool: &'a ObjectPool<T>, obj: T) -> Self {
+        PoolGuard {
+            pool,
+            obj: ManuallyDrop::new(obj),
+        }
+    }
+
+    // 把对象从池子里拿走不再归还，空出的名额可以再创建新对象
+    pub fn detach(self) -> T {
+        let mut this = ManuallyDrop::new(self);
+        this.pool.created.fetch_sub(1, Ordering::Relaxed);
+        // SAFETY: this不会再drop，obj只取出这一次
+        unsafe { ManuallyDrop::take(&mut this.obj) }
+    }
+}
+
+impl<T> Deref for PoolGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.obj
+    }
+}
+
+impl<T> DerefMut for PoolGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.obj
+    }
+}
+
+impl<T> Drop for PoolGuard<'_, T> {
+    fn drop(&mut self) {
+        // SAFETY: drop之后不会再访问obj
+        let obj = unsafe { ManuallyDrop::take(&mut self.obj) };
+        self.pool.release(obj);
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for PoolGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("PoolGuard").field(&*self.obj).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+    use std::sync::Arc;
+
+    #[test]
+    fn objects_are_reused() {
+        let pool = ObjectPool::new(4, Vec::<u8>::new);
+        assert_eq!(pool.created(), 0);
+        {
+            let mut buf = pool.get();
+            buf.extend_from_slice(b"hello");
+            assert_eq!(pool.in_use(), 1);
+        }
+        assert_eq!((pool.created(), pool.idle()), (1, 1));
+        // 归还的对象原样复用，内容由使用者自己重置
+        let buf = pool.get();
+        assert_eq!(&buf[..], b"hello");
+        assert_eq!(pool.created(), 1);
+    }
+
+    #[test]
+    fn max_size_caps_creation() {
+        let pool = ObjectPool::new(2, || 0u32);
+        let a = pool.try_get().unwrap();
+        let b = pool.try_get().unwrap();
+        assert!(pool.try_get().is_none());
+        drop(a);
+        let c = pool.try_get().unwrap();
+        // detach空出一个名额
+        assert_eq!(b.detach(), 0);
+        assert_eq!(pool.created(), 1);
+        let d = pool.try_get().unwrap();
+        assert!(pool.try_get().is_none());
+        drop((c, d));
+        assert_eq!(pool.idle(), 2);
+    }
+
+    #[test]
+    fn prefill_respects_cap() {
+        let pool = ObjectPool::new(3, String::new);
+        assert_eq!(pool.prefill(2), 2);
+        assert_eq!(pool.prefill(2), 0);
+        assert_eq!(pool.prefill(10), 1);
+        assert_eq!((pool.created(), pool.idle()), (3, 3));
+    }
+
+    #[test]
+    fn leftover_objects_are_dropped() {
+        let marker = Arc::new(());
+        let pool = {
+            let marker = Arc::clone(&marker);
+            ObjectPool::new(3, move || Arc::clone(&marker))
+        };
+        pool.prefill(3);
+        let detached = pool.get().detach();
+        assert_eq!(Arc::strong_count(&marker), 5);
+        drop(pool);
+        assert_eq!(Arc::strong_count(&marker), 2);
+        drop(detached);
+        assert_eq!(Arc::strong_count(&marker), 1);
+    }
+
+    #[test]
+    fn concurrent_get_never_shares_an_object() {
+        const THREADS: usize = 8;
+        const PER_THREAD: usize = 2000;
+        const MAX: usize = 3;
+
+        let next_id = Arc::new(AtomicUsize::new(0));
+        let pool = {
+            let next_id = Arc::clone(&next_id);
+            Arc::new(ObjectPool::new(MAX, move || {
+                (next_id.fetch_add(1, Ordering::Relaxed), AtomicUsize::new(0))
+            }))
+        };
+        let handles: Vec<_> = (0..THREADS)
+            .map(|_| {
+                let pool = Arc::clone(&pool);
+                thread::spawn(move || {
+                    let mut ids = HashSet::new();
+                    for _ in 0..PER_THREAD {
+                        let obj = pool.get();
+                        // 同一时刻一个对象只借给一个线程
+                        assert_eq!(obj.1.fetch_add(1, Ordering::Relaxed), 0);
+                        ids.insert(obj.0);
+                        obj.1.fetch_sub(1, Ordering::Relaxed);
+                    }
+                    ids
+                })
+            })
+            .collect();
+        for h in handles {
+            assert!(h.join().unwrap().iter().all(|&id| id < MAX));
+        }
+        assert!(next_id.load(Ordering::Relaxed) <= MAX);
+        assert_eq!(pool.idle(), pool.created());
+    }
+}
@@ -0,0 +1,187 @@
+// 观察者(订阅者)列表，用于事件分发
+// 列表只保存订阅者的rc::Weak，不延长订阅者的生命周期: 订阅者drop之后不需要手动退订，
+// 下一次notify遍历到它时升级失败，顺手把这一项从链表里删掉
+// 节点放在slab_list::SlabList里，subscribe返回的Subscription就是槽位的Handle，
+// 主动退订是O(1)的；Handle带代数，重复退订或者槽位被复用之后的旧Subscription都只会返回false
+// S可以是?Sized，比如Rc<dyn Fn(&Event)>或者Rc<dyn Listener>
+
+use std::fmt;
+use std::rc::{Rc, Weak};
+
+use crate::slab_list::{Handle, SlabList};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Subscription(Handle);
+
+pub struct ObserverList<S: ?Sized> {
+    observers: SlabList<Weak<S>>,
+}
+
+impl<S: ?Sized> ObserverList<S> {
+    pub fn new() -> Self {
+        ObserverList {
+            observers: SlabList::new(),
+        }
+    }
+
+    // 新订阅者排在最后，notify按订阅顺序通知
+    pub fn subscribe(&mut self, observer: &Rc<S>) -> Subscription {
+        Subscription(self.observers.push_back(Rc::downgrade(observer)))
+    }
+
+    // 已经退订或者已经被清理掉时返回false
+    pub fn unsubscribe(&mut self, subscription: Subscription) -> bool {
+        self.observers.remove(subscription.0).is_some()
+    }
+
+    pub fn is_subscribed(&self, subscription: Subscription) -> bool {
+        self.observers
+            .get(subscription.0)
+            .is_some_and(|weak| weak.strong_count() > 0)
+    }
+
+    // 依次把每个还活着的订阅者交给f，同时删掉已经drop的；返回通知到的个数
+    // 回调期间持有一个临时的Rc，订阅者不会在回调中途被释放
+    pub fn notify(&mut self, mut f: impl FnMut(&S)) -> usize {
+        let mut notified = 0;
+        let mut cur = self.observers.front_handle();
+        while let Some(handle) = cur {
+            // 删除当前项不影响已经取到的下一项
+            cur = self.observers.next_handle(handle);
+            match self.observers.get(handle).and_then(Weak::upgrade) {
+                Some(observer) => {
+                    f(&observer);
+                    notified += 1;
+                }
+                None => {
+                    self.observers.remove(handle);
+                }
+            }
+        }
+        notified
+    }
+
+    // 不通知，只清理已经drop的订阅者，返回清理掉的个数
+    pub fn prune(&mut self) -> usize {
+        let before = self.observers.len();
+        let mut cur = self.observers.front_handle();
+        while let Some(handle) = cur {
+            cur = self.observers.next_handle(handle);
+            if self.observers.get(handle).is_some_and(|weak| weak.strong_count() == 0) {
+                self.observers.remove(handle);
+            }
+        }
+        before - self.observers.len()
+    }
+
+    // 包括已经drop但还没被清理的项
+    pub fn len(&self) -> usize {
+        self.observers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.observers.is_empty()
+    }
+
+    // 真正还活着的订阅者个数，需要遍历
+    pub fn live_count(&self) -> usize {
+        self.observers.iter().filter(|weak| weak.strong_count() > 0).count()
+    }
+
+    // 退订所有人，旧的Subscription全部失效
+    pub fn clear(&mut self) {
+        self.observers.clear();
+    }
+}
+
+impl<S: ?Sized> Default for ObserverList<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S: ?Sized> fmt::Debug for ObserverList<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ObserverList")
+            .field("len", &self.len())
+            .field("live", &self.live_count())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::{Cell, RefCell};
+
+    #[test]
+    fn notifies_in_subscription_order() {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let mut list: ObserverList<dyn Fn(i32)> = ObserverList::new();
+        let observers: Vec<Rc<dyn Fn(i32)>> = (0..3)
+            .map(|id| {
+                let log = Rc::clone(&log);
+                Rc::new(move |event| log.borrow_mut().push((id, event))) as Rc<dyn Fn(i32)>
+            })
+            .collect();
+        for observer in &observers {
+            list.subscribe(observer);
+        }
+        assert_eq!(list.notify(|f| f(7)), 3);
+        assert_eq!(*log.borrow(), vec![(0, 7), (1, 7), (2, 7)]);
+    }
+
+    #[test]
+    fn dropped_observers_are_pruned_during_notify() {
+        let mut list = ObserverList::new();
+        let a = Rc::new(Cell::new(0));
+        let b = Rc::new(Cell::new(0));
+        let c = Rc::new(Cell::new(0));
+        list.subscribe(&a);
+        let sub_b = list.subscribe(&b);
+        list.subscribe(&c);
+        drop(b);
+        assert!(!list.is_subscribed(sub_b));
+        assert_eq!((list.len(), list.live_count()), (3, 2));
+        assert_eq!(list.notify(|n| n.set(n.get() + 1)), 2);
+        assert_eq!(list.len(), 2);
+        assert_eq!((a.get(), c.get()), (1, 1));
+        // 已经被清理掉的订阅不能再退订
+        assert!(!list.unsubscribe(sub_b));
+    }
+
+    #[test]
+    fn unsubscribe_is_idempotent() {
+        let mut list = ObserverList::new();
+        let a = Rc::new(Cell::new(0));
+        let sub = list.subscribe(&a);
+        assert!(list.is_subscribed(sub));
+        assert!(list.unsubscribe(sub));
+        assert!(!list.unsubscribe(sub));
+        // 槽位被新订阅复用，旧的Subscription仍然无效
+        let sub2 = list.subscribe(&a);
+        assert_ne!(sub, sub2);
+        assert!(!list.unsubscribe(sub));
+        assert!(list.is_subscribed(sub2));
+        assert_eq!(list.notify(|n| n.set(n.get() + 1)), 1);
+        assert_eq!(a.get(), 1);
+    }
+
+    #[test]
+    fn prune_and_clear() {
+        let mut list = ObserverList::new();
+        let keep = Rc::new(());
+        list.subscribe(&keep);
+        for _ in 0..4 {
+            list.subscribe(&Rc::new(()));
+        }
+        assert_eq!(list.len(), 5);
+        assert_eq!(list.prune(), 4);
+        assert_eq!(list.prune(), 0);
+        // 列表只持有Weak
+        assert_eq!(Rc::strong_count(&keep), 1);
+        list.clear();
+        assert!(list.is_empty());
+        assert_eq!(list.notify(|_| unreachable!()), 0);
+    }
+}
@@ -0,0 +1,383 @@
+// 配对堆(pairing heap)，用链接的子节点/兄弟节点表示的最小堆
+// 每个节点只记三个链接: 第一个孩子child、右兄弟sibling、prev(是第一个孩子时指向父节点，否则指向左兄弟)
+// - meld: 两个堆比较根，大的那个根成为小根的第一个孩子，O(1)
+// - push: 新节点当作单节点堆meld进来，O(1)
+// - pop_min: 删掉根，把它的孩子们两两配对meld(从左到右)，再从右到左依次meld成一棵，均摊O(log n)
+// - decrease_key: 把节点连同子树从兄弟链表里剪下来，再和根meld，均摊o(log n)
+// 节点放在slab里用下标链接，push返回带代数的Handle(和slab_list一样)，
+// decrease_key拿到过期的Handle时返回false而不是访问到别的节点
+// Handle只对创建它的堆有效；merge之后被并入的那个堆的Handle全部失效
+
+use std::fmt;
+
+const NIL: usize = usize::MAX;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Handle {
+    index: usize,
+    generation: u64,
+}
+
+struct Node<T> {
+    elem: T,
+    child: usize,
+    sibling: usize,
+    prev: usize,
+}
+
+struct Slot<T> {
+    generation: u64,
+    node: Option<Node<T>>,
+}
+
+pub struct PairingHeap<T> {
+    slots: Vec<Slot<T>>,
+    free: Vec<usize>,
+    root: usize,
+    len: usize,
+}
+
+impl<T: Ord> PairingHeap<T> {
+    pub fn new() -> Self {
+        PairingHeap {
+            slots: Vec::new(),
+            free: Vec::new(),
+            root: NIL,
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn node(&self, index: usize) -> &Node<T> {
+        self.slots[index].node.as_ref().unwrap()
+    }
+
+    fn node_mut(&mut self, index: usize) -> &mut Node<T> {
+        self.slots[index].node.as_mut().unwrap()
+    }
+
+    pub fn push(&mut self, elem: T) -> Handle {
+        let node = Node {
+            elem,
+            child: NIL,
+            sibling: NIL,
+            prev: NIL,
+        };
+        let index = match self.free.pop() {
+            Some(index) => {
+                self.slots[index].node = Some(node);
+                index
+            }
+            None => {
+                self.slots.push(Slot {
+                    generation: 0,
+                    node: Some(node),
+                });
+                self.slots.len() - 1
+            }
+        };
+        self.root = self.meld(self.root, index);
+        self.len += 1;
+        Handle {
+            index,
+            generation: self.slots[index].generation,
+        }
+    }
+
+    pub fn peek(&self) -> Option<&T> {
+        (self.root != NIL).then(|| &self.node(self.root).elem)
+    }
+
+    pub fn pop_min(&mut self) -> Option<T> {
+        if self.root == NIL {
+            return None;
+        }
+        let root = self.root;
+        let first_child = self.node(root).child;
+        self.root = self.combine_siblings(first_child);
+        self.len -= 1;
+        Some(self.release(root))
+    }
+
+    // 还在堆里的元素
+    pub fn get(&self, handle: Handle) -> Option<&T> {
+        self.is_live(handle).then(|| &self.node(handle.index).elem)
+    }
+
+    pub fn contains(&self, handle: Handle) -> bool {
+        self.is_live(handle)
+    }
+
+    // 把handle对应的元素换成更小(或相等)的new；handle已经失效时返回false
+    // new比原来的值大时panic，那会破坏堆序
+    pub fn decrease_key(&mut self, handle: Handle, new: T) -> bool {
+        if !self.is_live(handle) {
+            return false;
+        }
+        let index = handle.index;
+        assert!(new <= self.node(index).elem, "new key is greater than the current key");
+        self.node_mut(index).elem = new;
+        if index != self.root {
+            self.cut(index);
+            self.root = self.meld(self.root, index);
+        }
+        true
+    }
+
+    // 把other的所有元素并进来，other的Handle随之失效
+    pub fn merge(&mut self, other: PairingHeap<T>) {
+        // other的槽位整体平移到self的slab后面，所有链接下标加上同样的偏移
+        let offset = self.slots.len();
+        let shift = |i: usize| if i == NIL { NIL } else { i + offset };
+        for mut slot in other.slots {
+            if let Some(node) = slot.node.as_mut() {
+                node.child = shift(node.child);
+                node.sibling = shift(node.sibling);
+                node.prev = shift(node.prev);
+            }
+            self.slots.push(slot);
+        }
+        self.free.extend(other.free.into_iter().map(shift));
+        self.root = self.meld(self.root, shift(other.root));
+        self.len += other.len;
+    }
+
+    // 槽位保留下来并增加代数，清空前发出的Handle不会对上之后push的元素
+    pub fn clear(&mut self) {
+        for index in 0..self.slots.len() {
+            if self.slots[index].node.is_some() {
+                self.release(index);
+            }
+        }
+        self.root = NIL;
+        self.len = 0;
+    }
+
+    // 按升序取出所有元素
+    pub fn into_sorted_vec(mut self) -> Vec<T> {
+        let mut out = Vec::with_capacity(self.len);
+        while let Some(x) = self.pop_min() {
+            out.push(x);
+        }
+        out
+    }
+
+    fn is_live(&self, handle: Handle) -> bool {
+        self.slots
+            .get(handle.index)
+            .is_some_and(|slot| slot.generation == handle.generation && slot.node.is_some())
+    }
+
+    // 取出节点的元素，槽位代数加一后放回空闲列表
+    fn release(&mut self, index: usize) -> T {
+        let slot = &mut self.slots[index];
+        slot.generation += 1;
+        self.free.push(index);
+        slot.node.take().unwrap().elem
+    }
+
+    // 合并两棵树的根，返回新根；任一方为NIL时直接返回另一方
+    fn meld(&mut self, a: usize, b: usize) -> usize {
+        if a == NIL {
+            return b;
+        }
+        if b == NIL {
+            return a;
+        }
+        let (parent, child) = if self.node(b).elem < self.node(a).elem { (b, a) } else { (a, b) };
+        let first = self.node(parent).child;
+        if first != NIL {
+            self.node_mut(first).prev = child;
+        }
+        let c = self.node_mut(child);
+        c.sibling = first;
+        c.prev = parent;
+        let p = self.node_mut(parent);
+        p.child = child;
+        p.sibling = NIL;
+        p.prev = NIL;
+        parent
+    }
+
+    // 两趟配对: 从左到右两两meld，再从右到左把结果依次meld起来
+    fn combine_siblings(&mut self, first: usize) -> usize {
+        let mut pairs = Vec::new();
+        let mut cur = first;
+        while cur != NIL {
+            let second = self.node(cur).sibling;
+            let next = if second == NIL { NIL } else { self.node(second).sibling };
+            let melded = self.meld(cur, second);
+            pairs.push(melded);
+            cur = next;
+        }
+        let mut root = NIL;
+        while let Some(tree) = pairs.pop() {
+            root = self.meld(tree, root);
+        }
+        root
+    }
+
+    // 把非根节点连同子树从兄弟链表里剪下来
+    fn cut(&mut self, index: usize) {
+        let Node { prev, sibling, .. } = *self.node(index);
+        if self.node(prev).child == index {
+            self.node_mut(prev).child = sibling;
+        } else {
+            self.node_mut(prev).sibling = sibling;
+        }
+        if sibling != NIL {
+            self.node_mut(sibling).prev = prev;
+        }
+        let node = self.node_mut(index);
+        node.sibling = NIL;
+        node.prev = NIL;
+    }
+}
+
+impl<T: Ord> Default for PairingHeap<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Ord + fmt::Debug> fmt::Debug for PairingHeap<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PairingHeap")
+            .field("len", &self.len)
+            .field("min", &self.peek())
+            .finish()
+    }
+}
+
+impl<T: Ord> Extend<T> for PairingHeap<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for elem in iter {
+            self.push(elem);
+        }
+    }
+}
+
+impl<T: Ord> FromIterator<T> for PairingHeap<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut heap = Self::new();
+        heap.extend(iter);
+        heap
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::XorShift;
+    use std::cmp::Reverse;
+    use std::collections::BinaryHeap;
+
+    #[test]
+    fn push_pop_in_order() {
+        let mut heap = PairingHeap::new();
+        assert_eq!(heap.pop_min(), None);
+        for x in [5, 3, 8, 1, 9, 2, 7] {
+            heap.push(x);
+        }
+        assert_eq!(heap.peek(), Some(&1));
+        assert_eq!(heap.len(), 7);
+        assert_eq!(heap.into_sorted_vec(), vec![1, 2, 3, 5, 7, 8, 9]);
+    }
+
+    #[test]
+    fn decrease_key_moves_to_front() {
+        let mut heap = PairingHeap::new();
+        let handles: Vec<Handle> = (10..20).map(|x| heap.push(x)).collect();
+        assert!(heap.decrease_key(handles[7], 3));
+        assert_eq!(heap.peek(), Some(&3));
+        assert_eq!(heap.get(handles[7]), Some(&3));
+        // 根本身也能减小
+        assert!(heap.decrease_key(handles[7], 1));
+        assert_eq!(heap.pop_min(), Some(1));
+        // 已经弹出的handle失效
+        assert!(!heap.contains(handles[7]));
+        assert!(!heap.decrease_key(handles[7], 0));
+        // 槽位被复用后旧handle依旧失效
+        let reused = heap.push(100);
+        assert_eq!(reused.index, handles[7].index);
+        assert_eq!(heap.get(handles[7]), None);
+        assert!(heap.decrease_key(handles[5], 12));
+        assert_eq!(heap.into_sorted_vec(), vec![10, 11, 12, 12, 13, 14, 16, 18, 19, 100]);
+    }
+
+    #[test]
+    #[should_panic(expected = "greater")]
+    fn increase_key_panics() {
+        let mut heap = PairingHeap::new();
+        let h = heap.push(1);
+        heap.decrease_key(h, 2);
+    }
+
+    #[test]
+    fn merge_two_heaps() {
+        let mut a: PairingHeap<i32> = [4, 8, 1].into_iter().collect();
+        let mut b = PairingHeap::new();
+        let h = b.push(6);
+        b.push(2);
+        b.pop_min();
+        b.extend([0, 9]);
+        a.merge(b);
+        assert_eq!(a.len(), 6);
+        assert_eq!(a.peek(), Some(&0));
+        // 并入后新push会复用b留下的空槽位
+        a.push(5);
+        assert_eq!(a.get(h), Some(&4));
+        let h = a.push(7);
+        a.clear();
+        assert!(a.is_empty() && !a.contains(h));
+        a.push(3);
+        assert_eq!(a.get(h), None);
+        assert_eq!(a.into_sorted_vec(), vec![3]);
+    }
+
+    #[test]
+    fn randomized_against_binary_heap() {
+        let mut rng = XorShift::new(0xbee);
+        let mut heap = PairingHeap::new();
+        let mut reference = BinaryHeap::new();
+        let mut live: Vec<(Handle, u64)> = Vec::new();
+        for _ in 0..3000 {
+            match rng.next_u64() % 5 {
+                0 | 1 => {
+                    let x = rng.next_u64() % 1000;
+                    live.push((heap.push(x), x));
+                    reference.push(Reverse(x));
+                }
+                2 => {
+                    let got = heap.pop_min();
+                    assert_eq!(got, reference.pop().map(|Reverse(x)| x));
+                    if let Some(x) = got {
+                        let pos = live.iter().position(|&(h, v)| v == x && !heap.contains(h)).unwrap();
+                        live.swap_remove(pos);
+                    }
+                }
+                _ => {
+                    if live.is_empty() {
+                        continue;
+                    }
+                    // decrease_key之后在参照堆里重建一份
+                    let i = (rng.next_u64() as usize) % live.len();
+                    let (h, old) = live[i];
+                    let new = old.saturating_sub(rng.next_u64() % 50);
+                    assert!(heap.decrease_key(h, new));
+                    live[i].1 = new;
+                    reference = live.iter().map(|&(_, v)| Reverse(v)).collect();
+                }
+            }
+            assert_eq!(heap.len(), reference.len());
+            assert_eq!(heap.peek(), reference.peek().map(|Reverse(x)| x));
+        }
+    }
+}
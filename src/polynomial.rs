@@ -0,0 +1,306 @@
+// 一元多项式，按指数升序存成一条有序的项链表，只存非零项
+// 这是链表最经典的练习之一，这里直接复用sorted_list:
+// - 加法: 两条项链表merge(只改指针)，再用dedup_by把相邻的同次项系数相加，最后去掉系数为0的项
+// - 乘法: 对左边的每一项，把右边整条链表乘上这一项(指数整体平移，顺序不变)，依次merge进结果
+// - 求值: 从最高次项开始做Horner，跳过的空缺次数用幂补上
+// 系数是i64，溢出按普通整数运算的规则处理
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, Mul, Neg, Sub};
+
+use crate::sorted_list::SortedList;
+
+// 按指数排序的项，指数相同时再比较系数只是为了满足Ord，规范化之后同一个指数只有一项
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Term {
+    exp: u32,
+    coeff: i64,
+}
+
+impl Ord for Term {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.exp, self.coeff).cmp(&(other.exp, other.coeff))
+    }
+}
+
+impl PartialOrd for Term {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct Polynomial {
+    terms: SortedList<Term>,
+}
+
+impl Polynomial {
+    pub fn zero() -> Self {
+        Polynomial::default()
+    }
+
+    // 单项式coeff * x^exp
+    pub fn monomial(coeff: i64, exp: u32) -> Self {
+        [(coeff, exp)].into_iter().collect()
+    }
+
+    fn from_terms(mut terms: SortedList<Term>) -> Self {
+        // 合并同次项；dedup_by只改前一项的系数，不影响它的位置
+        terms.dedup_by(|cur, prev| {
+            let same = cur.exp == prev.exp;
+            if same {
+                prev.coeff += cur.coeff;
+            }
+            same
+        });
+        terms.retain(|term| term.coeff != 0);
+        Polynomial { terms }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    // 零多项式没有次数
+    pub fn degree(&self) -> Option<u32> {
+        self.terms.last().map(|term| term.exp)
+    }
+
+    // x^exp的系数
+    pub fn coeff(&self, exp: u32) -> i64 {
+        self.terms
+            .iter()
+            .take_while(|term| term.exp <= exp)
+            .find(|term| term.exp == exp)
+            .map_or(0, |term| term.coeff)
+    }
+
+    // 非零项(coeff, exp)，从高次到低次
+    pub fn terms(&self) -> impl Iterator<Item = (i64, u32)> + '_ {
+        self.terms.iter().rev().map(|term| (term.coeff, term.exp))
+    }
+
+    pub fn eval(&self, x: i64) -> i64 {
+        let mut acc = 0;
+        let mut last = None;
+        for term in self.terms.iter().rev() {
+            if let Some(last) = last {
+                acc *= x.pow(last - term.exp);
+            }
+            acc += term.coeff;
+            last = Some(term.exp);
+        }
+        acc * x.pow(last.unwrap_or(0))
+    }
+
+    // 每一项乘上coeff * x^exp，结果仍然有序
+    fn scaled(&self, coeff: i64, exp: u32) -> SortedList<Term> {
+        self.terms
+            .iter()
+            .map(|term| Term {
+                exp: term.exp + exp,
+                coeff: term.coeff * coeff,
+            })
+            .collect()
+    }
+}
+
+impl Add for Polynomial {
+    type Output = Polynomial;
+
+    fn add(mut self, rhs: Polynomial) -> Polynomial {
+        self.terms.merge(rhs.terms);
+        Polynomial::from_terms(self.terms)
+    }
+}
+
+impl Add for &Polynomial {
+    type Output = Polynomial;
+
+    fn add(self, rhs: &Polynomial) -> Polynomial {
+        self.clone() + rhs.clone()
+    }
+}
+
+impl Neg for Polynomial {
+    type Output = Polynomial;
+
+    fn neg(self) -> Polynomial {
+        Polynomial {
+            terms: self.scaled(-1, 0),
+        }
+    }
+}
+
+impl Sub for Polynomial {
+    type Output = Polynomial;
+
+    fn sub(self, rhs: Polynomial) -> Polynomial {
+        self + -rhs
+    }
+}
+
+impl Sub for &Polynomial {
+    type Output = Polynomial;
+
+    fn sub(self, rhs: &Polynomial) -> Polynomial {
+        self.clone() - rhs.clone()
+    }
+}
+
+impl Mul for &Polynomial {
+    type Output = Polynomial;
+
+    fn mul(self, rhs: &Polynomial) -> Polynomial {
+        let mut terms = SortedList::new();
+        for term in self.terms.iter() {
+            terms.merge(rhs.scaled(term.coeff, term.exp));
+        }
+        Polynomial::from_terms(terms)
+    }
+}
+
+impl Mul for Polynomial {
+    type Output = Polynomial;
+
+    fn mul(self, rhs: Polynomial) -> Polynomial {
+        &self * &rhs
+    }
+}
+
+// 由(coeff, exp)构造，顺序任意，同次项会合并
+impl FromIterator<(i64, u32)> for Polynomial {
+    fn from_iter<I: IntoIterator<Item = (i64, u32)>>(iter: I) -> Self {
+        let terms: SortedList<Term> = iter.into_iter().map(|(coeff, exp)| Term { exp, coeff }).collect();
+        Polynomial::from_terms(terms)
+    }
+}
+
+// 按习惯从高次到低次打印，比如3x^2 - x + 5
+impl fmt::Display for Polynomial {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_zero() {
+            return f.write_str("0");
+        }
+        for (i, (coeff, exp)) in self.terms().enumerate() {
+            let abs = coeff.unsigned_abs();
+            match (i, coeff < 0) {
+                (0, true) => f.write_str("-")?,
+                (0, false) => {}
+                (_, true) => f.write_str(" - ")?,
+                (_, false) => f.write_str(" + ")?,
+            }
+            if abs != 1 || exp == 0 {
+                write!(f, "{abs}")?;
+            }
+            match exp {
+                0 => {}
+                1 => f.write_str("x")?,
+                _ => write!(f, "x^{exp}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Polynomial {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Polynomial({self})")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::XorShift;
+
+    fn poly(terms: &[(i64, u32)]) -> Polynomial {
+        terms.iter().copied().collect()
+    }
+
+    // 稠密系数表示，下标是指数
+    fn dense(p: &Polynomial) -> Vec<i64> {
+        let mut out = vec![0; p.degree().map_or(0, |d| d as usize + 1)];
+        for (coeff, exp) in p.terms() {
+            out[exp as usize] = coeff;
+        }
+        out
+    }
+
+    #[test]
+    fn construction_normalizes_terms() {
+        let p = poly(&[(2, 1), (3, 2), (-2, 1), (5, 0), (1, 2)]);
+        assert_eq!(p.terms().collect::<Vec<_>>(), vec![(4, 2), (5, 0)]);
+        assert_eq!(p.degree(), Some(2));
+        assert_eq!(p.coeff(1), 0);
+        assert_eq!(p.coeff(2), 4);
+        assert!(poly(&[(1, 3), (-1, 3)]).is_zero());
+        assert_eq!(Polynomial::zero().degree(), None);
+    }
+
+    #[test]
+    fn add_sub_mul() {
+        let a = poly(&[(1, 1), (1, 0)]);
+        let b = poly(&[(1, 1), (-1, 0)]);
+        assert_eq!(&a * &b, poly(&[(1, 2), (-1, 0)]));
+        assert_eq!(&a + &b, Polynomial::monomial(2, 1));
+        assert_eq!(&a - &b, Polynomial::monomial(2, 0));
+        assert!((&a - &a).is_zero());
+        assert!((&a * &Polynomial::zero()).is_zero());
+        let cube = &(&a * &a) * &a;
+        assert_eq!(dense(&cube), vec![1, 3, 3, 1]);
+    }
+
+    #[test]
+    fn eval_with_gaps() {
+        let p = poly(&[(2, 5), (-3, 2), (7, 0)]);
+        assert_eq!(p.eval(2), 2 * 32 - 3 * 4 + 7);
+        assert_eq!(p.eval(-1), -2 - 3 + 7);
+        assert_eq!(Polynomial::monomial(1, 3).eval(3), 27);
+        assert_eq!(Polynomial::zero().eval(5), 0);
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(poly(&[(3, 2), (-1, 1), (5, 0)]).to_string(), "3x^2 - x + 5");
+        assert_eq!(poly(&[(-1, 3), (1, 0)]).to_string(), "-x^3 + 1");
+        assert_eq!(poly(&[(-4, 0)]).to_string(), "-4");
+        assert_eq!(format!("{:?}", Polynomial::zero()), "Polynomial(0)");
+    }
+
+    #[test]
+    fn randomized_against_dense() {
+        let mut rng = XorShift::new(0x9017);
+        let random = |rng: &mut XorShift| -> Polynomial {
+            let n = rng.next_u64() % 6;
+            (0..n)
+                .map(|_| ((rng.next_u64() % 7) as i64 - 3, (rng.next_u64() % 8) as u32))
+                .collect()
+        };
+        for _ in 0..300 {
+            let (a, b) = (random(&mut rng), random(&mut rng));
+            let (da, db) = (dense(&a), dense(&b));
+            let mut sum = vec![0; da.len().max(db.len())];
+            let mut product = vec![0; da.len() + db.len()];
+            for (i, &x) in da.iter().enumerate() {
+                sum[i] += x;
+                for (j, &y) in db.iter().enumerate() {
+                    product[i + j] += x * y;
+                }
+            }
+            for (i, &y) in db.iter().enumerate() {
+                sum[i] += y;
+            }
+            for v in [&mut sum, &mut product] {
+                while v.last() == Some(&0) {
+                    v.pop();
+                }
+            }
+            assert_eq!(dense(&(&a + &b)), sum);
+            assert_eq!(dense(&(&a * &b)), product);
+            let x = (rng.next_u64() % 5) as i64 - 2;
+            assert_eq!((&a * &b).eval(x), a.eval(x) * b.eval(x));
+        }
+    }
+}
@@ -0,0 +1,38 @@
+// use too_many_linked_list_rs::prelude::*;
+// 引入lib.rs里的稳定类型别名、ListError，以及traits里的Stack/Queue/Deque
+// trait只引入方法不引入名字(as _)，不然会和同名的类型别名冲突；写泛型约束时用crate::traits::Stack之类的完整路径
+
+#[cfg(feature = "unsafe-impls")]
+pub use crate::Deque;
+#[cfg(feature = "persistent")]
+pub use crate::PersistentList;
+#[cfg(feature = "safe")]
+pub use crate::Stack;
+
+pub use crate::error::ListError;
+pub use crate::traits::{Deque as _, Queue as _, Stack as _};
+
+#[cfg(all(test, feature = "safe", feature = "persistent", feature = "unsafe-impls"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aliases_and_trait_methods() {
+        let mut stack = Stack::new();
+        stack.push(1);
+        stack.push(2);
+        assert_eq!(stack.pop(), Some(2));
+
+        let list = PersistentList::new().prepend(2).prepend(1);
+        assert_eq!(list.head(), Some(&1));
+        assert_eq!(list.tail().head(), Some(&2));
+
+        // enqueue/dequeue只有Queue trait里有
+        let mut deque: Deque<i32> = Deque::new();
+        deque.enqueue(1);
+        deque.enqueue(2);
+        deque.push_front(0);
+        assert_eq!(deque.dequeue(), Some(0));
+        assert_eq!(deque.try_split_off(3), Err(ListError::OutOfRange { index: 3, len: 2 }));
+    }
+}
@@ -0,0 +1,338 @@
+// PyO3导出的Python类，想在Python里先试验、底下用Rust结构的时候用
+// - Deque: simple_deque_3::List包着Python对象，支持len()、迭代、下标(含负数下标)
+// - LruCache: lru_cache::LruCache，键可以是任意可哈希的Python对象，支持len()、in、[]、del
+// 编译成扩展模块(不需要maturin):
+//   cargo rustc --lib --release --features python --crate-type cdylib
+//   cp target/release/libtoo_many_linked_list_rs.so too_many_linked_list_rs.so
+// 之后在Python里 from too_many_linked_list_rs import Deque, LruCache
+// 迭代拿到的是调用__iter__那一刻的快照，迭代期间修改容器不会影响已经开始的迭代
+
+use std::hash::{Hash, Hasher};
+
+use pyo3::exceptions::{PyIndexError, PyKeyError};
+use pyo3::prelude::*;
+use pyo3::types::PyIterator;
+
+use crate::lru_cache::LruCache;
+use crate::simple_deque_3::List;
+
+#[pyclass(name = "Deque", module = "too_many_linked_list_rs", sequence)]
+pub struct PyDeque {
+    list: List<Py<PyAny>>,
+}
+
+// 把负数下标换算成从头数的位置，越界时抛IndexError
+fn normalize(index: isize, len: usize) -> PyResult<usize> {
+    let real = if index < 0 { index + len as isize } else { index };
+    if real < 0 || real as usize >= len {
+        return Err(PyIndexError::new_err("deque index out of range"));
+    }
+    Ok(real as usize)
+}
+
+#[pymethods]
+impl PyDeque {
+    // Deque()或者Deque(iterable)
+    #[new]
+    #[pyo3(signature = (iterable = None))]
+    fn new(iterable: Option<&Bound<'_, PyAny>>) -> PyResult<Self> {
+        let mut list = List::new();
+        if let Some(iterable) = iterable {
+            for item in iterable.try_iter()? {
+                list.push_back(item?.unbind());
+            }
+        }
+        Ok(PyDeque { list })
+    }
+
+    fn push_front(&mut self, value: Py<PyAny>) {
+        self.list.push_front(value);
+    }
+
+    fn push_back(&mut self, value: Py<PyAny>) {
+        self.list.push_back(value);
+    }
+
+    // 和collections.deque一样，空的时候抛IndexError
+    fn pop_front(&mut self) -> PyResult<Py<PyAny>> {
+        self.list
+            .pop_front()
+            .ok_or_else(|| PyIndexError::new_err("pop from an empty deque"))
+    }
+
+    fn pop_back(&mut self) -> PyResult<Py<PyAny>> {
+        self.list
+            .pop_back()
+            .ok_or_else(|| PyIndexError::new_err("pop from an empty deque"))
+    }
+
+    // 空的时候返回None
+    fn front(&self, py: Python<'_>) -> Option<Py<PyAny>> {
+        self.list.front().map(|value| value.clone_ref(py))
+    }
+
+    fn back(&self, py: Python<'_>) -> Option<Py<PyAny>> {
+        self.list.back().map(|value| value.clone_ref(py))
+    }
+
+    fn clear(&mut self) {
+        self.list.clear();
+    }
+
+    fn __len__(&self) -> usize {
+        self.list.len()
+    }
+
+    // 链表没有随机访问，下标访问是O(n)，从离下标近的一端走过去
+    fn __getitem__(&self, py: Python<'_>, index: isize) -> PyResult<Py<PyAny>> {
+        let len = self.list.len();
+        let i = normalize(index, len)?;
+        let value = if i < len / 2 {
+            self.list.iter().nth(i)
+        } else {
+            self.list.iter().nth_back(len - 1 - i)
+        };
+        Ok(value.unwrap().clone_ref(py))
+    }
+
+    fn __setitem__(&mut self, index: isize, value: Py<PyAny>) -> PyResult<()> {
+        let len = self.list.len();
+        let i = normalize(index, len)?;
+        let slot = if i < len / 2 {
+            self.list.iter_mut().nth(i)
+        } else {
+            self.list.iter_mut().nth_back(len - 1 - i)
+        };
+        *slot.unwrap() = value;
+        Ok(())
+    }
+
+    fn __iter__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyIterator>> {
+        let snapshot: Vec<Py<PyAny>> = self.list.iter().map(|value| value.clone_ref(py)).collect();
+        snapshot.into_pyobject(py)?.try_iter()
+    }
+
+    fn __repr__(&self, py: Python<'_>) -> PyResult<String> {
+        let items = self
+            .list
+            .iter()
+            .map(|value| Ok(value.bind(py).repr()?.to_string()))
+            .collect::<PyResult<Vec<_>>>()?;
+        Ok(format!("Deque([{}])", items.join(", ")))
+    }
+}
+
+// Python对象做LruCache的键: 创建时算好hash，比较时调用Python的__eq__，比较抛异常按不相等处理
+struct PyKey {
+    obj: Py<PyAny>,
+    hash: isize,
+}
+
+impl PyKey {
+    fn new(obj: &Bound<'_, PyAny>) -> PyResult<Self> {
+        Ok(PyKey {
+            hash: obj.hash()?,
+            obj: obj.clone().unbind(),
+        })
+    }
+}
+
+impl Hash for PyKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.hash.hash(state);
+    }
+}
+
+impl PartialEq for PyKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.obj.is(&other.obj)
+            || Python::attach(|py| self.obj.bind(py).eq(other.obj.bind(py)).unwrap_or(false))
+    }
+}
+
+impl Eq for PyKey {}
+
+impl Clone for PyKey {
+    fn clone(&self) -> Self {
+        PyKey {
+            obj: Python::attach(|py| self.obj.clone_ref(py)),
+            hash: self.hash,
+        }
+    }
+}
+
+#[pyclass(name = "LruCache", module = "too_many_linked_list_rs", mapping)]
+pub struct PyLruCache {
+    cache: LruCache<PyKey, Py<PyAny>>,
+}
+
+#[pymethods]
+impl PyLruCache {
+    // capacity为0时抛ValueError
+    #[new]
+    fn new(capacity: usize) -> PyResult<Self> {
+        if capacity == 0 {
+            return Err(pyo3::exceptions::PyValueError::new_err("capacity must be positive"));
+        }
+        Ok(PyLruCache {
+            cache: LruCache::new(capacity),
+        })
+    }
+
+    #[getter]
+    fn capacity(&self) -> usize {
+        self.cache.capacity()
+    }
+
+    // 返回同一个键的旧值，没有就是None
+    fn put(&mut self, key: &Bound<'_, PyAny>, value: Py<PyAny>) -> PyResult<Option<Py<PyAny>>> {
+        Ok(self.cache.put(PyKey::new(key)?, value))
+    }
+
+    // 命中时刷新使用时间；没有就返回default
+    #[pyo3(signature = (key, default = None))]
+    fn get(&mut self, key: &Bound<'_, PyAny>, default: Option<Py<PyAny>>) -> PyResult<Option<Py<PyAny>>> {
+        let py = key.py();
+        Ok(self
+            .cache
+            .get(&PyKey::new(key)?)
+            .map(|value| value.clone_ref(py))
+            .or(default))
+    }
+
+    // 只读，不刷新使用时间
+    #[pyo3(signature = (key, default = None))]
+    fn peek(&self, key: &Bound<'_, PyAny>, default: Option<Py<PyAny>>) -> PyResult<Option<Py<PyAny>>> {
+        let py = key.py();
+        Ok(self
+            .cache
+            .peek(&PyKey::new(key)?)
+            .map(|value| value.clone_ref(py))
+            .or(default))
+    }
+
+    // 淘汰并返回最久没用的(键, 值)，空的时候返回None
+    fn pop_lru(&mut self) -> Option<(Py<PyAny>, Py<PyAny>)> {
+        self.cache.pop_lru().map(|(key, value)| (key.obj, value))
+    }
+
+    fn clear(&mut self) {
+        self.cache.clear();
+    }
+
+    fn __len__(&self) -> usize {
+        self.cache.len()
+    }
+
+    fn __contains__(&self, key: &Bound<'_, PyAny>) -> PyResult<bool> {
+        Ok(self.cache.contains(&PyKey::new(key)?))
+    }
+
+    // cache[key]和get一样会刷新使用时间，键不存在时抛KeyError
+    fn __getitem__(&mut self, key: &Bound<'_, PyAny>) -> PyResult<Py<PyAny>> {
+        let py = key.py();
+        match self.cache.get(&PyKey::new(key)?) {
+            Some(value) => Ok(value.clone_ref(py)),
+            None => Err(PyKeyError::new_err(key.clone().unbind())),
+        }
+    }
+
+    fn __setitem__(&mut self, key: &Bound<'_, PyAny>, value: Py<PyAny>) -> PyResult<()> {
+        self.cache.put(PyKey::new(key)?, value);
+        Ok(())
+    }
+
+    fn __delitem__(&mut self, key: &Bound<'_, PyAny>) -> PyResult<()> {
+        match self.cache.remove(&PyKey::new(key)?) {
+            Some(_) => Ok(()),
+            None => Err(PyKeyError::new_err(key.clone().unbind())),
+        }
+    }
+
+    // 按键迭代，从最近使用到最久没用
+    fn __iter__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyIterator>> {
+        let keys: Vec<Py<PyAny>> = self.cache.iter().map(|(key, _)| key.obj.clone_ref(py)).collect();
+        keys.into_pyobject(py)?.try_iter()
+    }
+}
+
+#[pymodule]
+fn too_many_linked_list_rs(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyDeque>()?;
+    m.add_class::<PyLruCache>()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use pyo3::py_run;
+
+    use super::*;
+
+    #[test]
+    fn deque_protocols() {
+        Python::initialize();
+        Python::attach(|py| {
+            let deque = Py::new(py, PyDeque::new(None).unwrap()).unwrap();
+            py_run!(
+                py,
+                deque,
+                r#"
+deque.push_back(2)
+deque.push_front(1)
+deque.push_back("three")
+assert len(deque) == 3
+assert list(deque) == [1, 2, "three"]
+assert deque[0] == 1 and deque[-1] == "three" and deque[1] == 2
+deque[-2] = 20
+assert repr(deque) == "Deque([1, 20, 'three'])"
+try:
+    deque[3]
+    assert False
+except IndexError:
+    pass
+assert deque.pop_back() == "three"
+assert deque.front() == 1 and deque.back() == 20
+deque.clear()
+assert deque.back() is None
+try:
+    deque.pop_front()
+    assert False
+except IndexError:
+    pass
+"#
+            );
+        });
+    }
+
+    #[test]
+    fn lru_cache_protocols() {
+        Python::initialize();
+        Python::attach(|py| {
+            let cache = Py::new(py, PyLruCache::new(2).unwrap()).unwrap();
+            py_run!(
+                py,
+                cache,
+                r#"
+cache["a"] = 1
+cache[(1, 2)] = "tuple"
+assert cache["a"] == 1
+cache["c"] = 3
+assert "a" in cache and (1, 2) not in cache
+assert list(cache) == ["c", "a"]
+assert cache.put("a", 10) == 1
+assert cache.get("missing", "default") == "default"
+assert cache.peek("c") == 3
+assert cache.pop_lru() == ("c", 3)
+del cache["a"]
+assert len(cache) == 0
+try:
+    cache[[1]] = 1
+    assert False
+except TypeError:
+    pass
+"#
+            );
+        });
+    }
+}
@@ -0,0 +1,393 @@
+// 带随机指针的单链表(LeetCode 138 "复制带随机指针的链表")
+// 每个节点除了next之外还有一个extra指针，可以指向链表里任意一个节点(包括自己)或者为空
+// 这样一个节点可能同时被前驱的next和任意多个extra指向，是一张图而不是一棵树，
+// 所以链表的所有权只沿next链走: extra只是不拥有的裸指针，释放时只顺着next释放
+// 深拷贝的难点在于复制extra时目标节点的副本可能还没建出来，有两种O(n)做法:
+// - clone_with_map: 先复制整条next链，同时用HashMap记下"原节点地址 -> 副本地址"，再查表补extra，额外O(n)空间
+// - clone(交织拆分): 把每个副本插到原节点后面，形成 a a' b b' c c'，
+//   于是原节点x的extra副本正好是x.extra.next；补完extra之后再把两条链拆开，只需要O(1)额外空间
+// 交织期间原链表是被改动过的，所以元素的clone要先全部做完(可能panic)，之后的步骤只有分配和指针操作
+// 和simple_deque_2一样，节点之间只用裸指针互相引用
+
+use std::collections::HashMap;
+use std::fmt;
+use std::marker::PhantomData;
+use std::ptr;
+
+pub struct RandomList<T> {
+    head: Link<T>,
+    len: usize,
+    _boo: PhantomData<Box<Node<T>>>,
+}
+
+type Link<T> = *mut Node<T>;
+
+struct Node<T> {
+    elem: T,
+    next: Link<T>,
+    // 不拥有的指针，指向本链表里的某个节点
+    extra: Link<T>,
+}
+
+impl<T> RandomList<T> {
+    pub fn new() -> Self {
+        RandomList {
+            head: ptr::null_mut(),
+            len: 0,
+            _boo: PhantomData,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn push_front(&mut self, elem: T) {
+        self.head = Box::into_raw(Box::new(Node {
+            elem,
+            next: self.head,
+            extra: ptr::null_mut(),
+        }));
+        self.len += 1;
+    }
+
+    // 弹出头节点，其他节点指向它的extra都被清空
+    pub fn pop_front(&mut self) -> Option<T> {
+        if self.head.is_null() {
+            return None;
+        }
+        let old = self.head;
+        // SAFETY: head非空时指向本链表独占的节点
+        unsafe {
+            self.head = (*old).next;
+            let mut cur = self.head;
+            while !cur.is_null() {
+                if (*cur).extra == old {
+                    (*cur).extra = ptr::null_mut();
+                }
+                cur = (*cur).next;
+            }
+            self.len -= 1;
+            Some(Box::from_raw(old).elem)
+        }
+    }
+
+    // 第index个节点，越界时panic
+    fn node_at(&self, index: usize) -> Link<T> {
+        assert!(index < self.len, "index out of bounds");
+        let mut cur = self.head;
+        for _ in 0..index {
+            // SAFETY: index < len，路径上的节点都非空
+            cur = unsafe { (*cur).next };
+        }
+        cur
+    }
+
+    // 让第from个节点的extra指向第to个节点(None表示清空)，O(n)；越界时panic
+    pub fn set_extra(&mut self, from: usize, to: Option<usize>) {
+        let target = to.map_or(ptr::null_mut(), |to| self.node_at(to));
+        let node = self.node_at(from);
+        // SAFETY: node是本链表的节点
+        unsafe { (*node).extra = target };
+    }
+
+    // 第index个节点的extra指向第几个节点
+    pub fn extra_of(&self, index: usize) -> Option<usize> {
+        // SAFETY: node是本链表的节点
+        let target = unsafe { (*self.node_at(index)).extra };
+        self.index_of(target)
+    }
+
+    fn index_of(&self, target: Link<T>) -> Option<usize> {
+        if target.is_null() {
+            return None;
+        }
+        let mut cur = self.head;
+        let mut index = 0;
+        while cur != target {
+            // SAFETY: extra总是指向本链表里的节点，一定能在next链上找到
+            cur = unsafe { (*cur).next };
+            index += 1;
+        }
+        Some(index)
+    }
+
+    // 所有节点的extra对应的下标，O(n)
+    pub fn extra_indices(&self) -> Vec<Option<usize>> {
+        let mut index_of = HashMap::with_capacity(self.len);
+        let mut cur = self.head;
+        let mut index = 0;
+        while !cur.is_null() {
+            index_of.insert(cur as *const Node<T>, index);
+            // SAFETY: cur是本链表的节点
+            cur = unsafe { (*cur).next };
+            index += 1;
+        }
+        self.iter_nodes()
+            .map(|node| index_of.get(&(node.extra as *const Node<T>)).copied())
+            .collect()
+    }
+
+    fn iter_nodes(&self) -> impl Iterator<Item = &Node<T>> + '_ {
+        let mut cur = self.head;
+        std::iter::from_fn(move || {
+            // SAFETY: 节点在&self期间有效
+            let node = unsafe { cur.as_ref()? };
+            cur = node.next;
+            Some(node)
+        })
+    }
+
+    // 按next顺序给出(元素, extra指向的元素)
+    pub fn iter(&self) -> impl Iterator<Item = (&T, Option<&T>)> + '_ {
+        self.iter_nodes().map(|node| {
+            // SAFETY: extra为空或者指向本链表的节点
+            let extra = unsafe { node.extra.as_ref() }.map(|target| &target.elem);
+            (&node.elem, extra)
+        })
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> + '_ {
+        let mut cur = self.head;
+        std::iter::from_fn(move || {
+            // SAFETY: 每个节点只交出一次&mut elem，extra不经过引用访问
+            unsafe {
+                if cur.is_null() {
+                    return None;
+                }
+                let elem = &mut (*cur).elem;
+                cur = (*cur).next;
+                Some(elem)
+            }
+        })
+    }
+
+    // 先复制next链并记下新旧节点的对应关系，再查表复制extra
+    pub fn clone_with_map(&self) -> Self
+    where
+        T: Clone,
+    {
+        let mut copy = RandomList::new();
+        let mut map: HashMap<*const Node<T>, Link<T>> = HashMap::with_capacity(self.len);
+        let mut tail: *mut Link<T> = &mut copy.head;
+        for node in self.iter_nodes() {
+            let new = Box::into_raw(Box::new(Node {
+                elem: node.elem.clone(),
+                next: ptr::null_mut(),
+                extra: ptr::null_mut(),
+            }));
+            // SAFETY: tail指向copy.head或者副本最后一个节点的next；先挂上再计数，clone中途panic时copy仍然完整
+            unsafe {
+                *tail = new;
+                tail = &mut (*new).next;
+            }
+            copy.len += 1;
+            map.insert(node, new);
+        }
+        let mut cur = copy.head;
+        for node in self.iter_nodes() {
+            // SAFETY: 两条链长度相同，cur是与node对应的副本
+            unsafe {
+                if !node.extra.is_null() {
+                    (*cur).extra = map[&(node.extra as *const Node<T>)];
+                }
+                cur = (*cur).next;
+            }
+        }
+        copy
+    }
+}
+
+// 交织拆分，O(n)时间、除了元素本身之外O(1)额外空间
+impl<T: Clone> Clone for RandomList<T> {
+    fn clone(&self) -> Self {
+        // 先做完所有可能panic的元素clone，之后改动原链表的步骤都不会中途退出
+        let mut elems: Vec<T> = self.iter_nodes().map(|node| node.elem.clone()).collect();
+        let mut copy = RandomList::new();
+        if self.head.is_null() {
+            return copy;
+        }
+        // SAFETY: 以下只在&self期间临时改动next，返回前恢复原状；extra都指向本链表的节点
+        unsafe {
+            // 1. 在每个原节点后面插入它的副本: a a' b b' c c'
+            let mut cur = self.head;
+            for elem in elems.drain(..) {
+                let next = (*cur).next;
+                let new = Box::into_raw(Box::new(Node {
+                    elem,
+                    next,
+                    extra: ptr::null_mut(),
+                }));
+                (*cur).next = new;
+                cur = next;
+            }
+            // 2. x'.extra = x.extra的副本 = x.extra.next
+            let mut cur = self.head;
+            while !cur.is_null() {
+                let new = (*cur).next;
+                let extra = (*cur).extra;
+                if !extra.is_null() {
+                    (*new).extra = (*extra).next;
+                }
+                cur = (*new).next;
+            }
+            // 3. 拆成两条链
+            copy.head = (*self.head).next;
+            let mut cur = self.head;
+            while !cur.is_null() {
+                let new = (*cur).next;
+                let next = (*new).next;
+                (*cur).next = next;
+                (*new).next = if next.is_null() { ptr::null_mut() } else { (*next).next };
+                cur = next;
+            }
+        }
+        copy.len = self.len;
+        copy
+    }
+}
+
+impl<T> Drop for RandomList<T> {
+    fn drop(&mut self) {
+        // 只沿next释放，extra不拥有节点
+        let mut cur = self.head;
+        while !cur.is_null() {
+            // SAFETY: 每个节点只被next链拥有一次
+            let node = unsafe { Box::from_raw(cur) };
+            cur = node.next;
+        }
+    }
+}
+
+impl<T> Default for RandomList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// 元素相同并且extra的拓扑相同
+impl<T: PartialEq> PartialEq for RandomList<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len
+            && self.iter_nodes().zip(other.iter_nodes()).all(|(a, b)| a.elem == b.elem)
+            && self.extra_indices() == other.extra_indices()
+    }
+}
+
+impl<T: Eq> Eq for RandomList<T> {}
+
+impl<T: fmt::Debug> fmt::Debug for RandomList<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.iter_nodes().map(|node| &node.elem).zip(self.extra_indices()))
+            .finish()
+    }
+}
+
+// 保持迭代顺序，extra全部为空
+impl<T> FromIterator<T> for RandomList<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut elems: Vec<T> = iter.into_iter().collect();
+        let mut list = RandomList::new();
+        while let Some(elem) = elems.pop() {
+            list.push_front(elem);
+        }
+        list
+    }
+}
+
+// 元素的所有权只沿next链，和Box<Node<T>>一样
+// 不实现Sync: clone通过&self临时改动next，两个线程同时clone会产生数据竞争
+unsafe impl<T: Send> Send for RandomList<T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::XorShift;
+    use std::rc::Rc;
+
+    fn sample() -> RandomList<char> {
+        let mut list: RandomList<char> = "abcd".chars().collect();
+        list.set_extra(0, Some(2));
+        list.set_extra(1, Some(0));
+        list.set_extra(2, Some(2));
+        list
+    }
+
+    #[test]
+    fn extra_pointers() {
+        let list = sample();
+        assert_eq!(list.len(), 4);
+        assert_eq!(list.extra_indices(), vec![Some(2), Some(0), Some(2), None]);
+        assert_eq!(list.extra_of(1), Some(0));
+        let pairs: Vec<_> = list.iter().map(|(e, x)| (*e, x.copied())).collect();
+        assert_eq!(pairs, vec![('a', Some('c')), ('b', Some('a')), ('c', Some('c')), ('d', None)]);
+    }
+
+    #[test]
+    fn both_clones_copy_the_topology() {
+        let list = sample();
+        for copy in [list.clone(), list.clone_with_map()] {
+            assert_eq!(copy, list);
+            // 副本的extra指向副本自己的节点，而不是原链表
+            let originals: Vec<_> = list.iter_nodes().map(|n| n as *const Node<char>).collect();
+            assert!(copy.iter_nodes().all(|n| !originals.contains(&(n.extra as *const _))));
+        }
+        // 交织拆分之后原链表恢复原状
+        assert_eq!(list.extra_indices(), vec![Some(2), Some(0), Some(2), None]);
+        assert_eq!(list.iter().map(|(e, _)| *e).collect::<String>(), "abcd");
+        assert_eq!(RandomList::<u8>::new().clone(), RandomList::new());
+    }
+
+    #[test]
+    fn copies_are_independent() {
+        let list = sample();
+        let mut copy = list.clone();
+        for elem in copy.iter_mut() {
+            *elem = elem.to_ascii_uppercase();
+        }
+        copy.set_extra(3, Some(3));
+        assert_eq!(copy.pop_front(), Some('A'));
+        // 指向被弹出节点的extra被清空
+        assert_eq!(copy.extra_indices(), vec![None, Some(1), Some(2)]);
+        assert_eq!(list.iter().map(|(e, _)| *e).collect::<String>(), "abcd");
+        assert_eq!(list.extra_of(3), None);
+    }
+
+    #[test]
+    fn random_topologies_match() {
+        let mut rng = XorShift::new(690);
+        for len in [1, 2, 17, 100] {
+            let mut list: RandomList<u64> = (0..len as u64).collect();
+            for i in 0..len {
+                let r = rng.next_u64() as usize % (len + 1);
+                list.set_extra(i, (r < len).then_some(r));
+            }
+            let expected = list.extra_indices();
+            let a = list.clone();
+            let b = list.clone_with_map();
+            assert_eq!(a.extra_indices(), expected);
+            assert_eq!(b.extra_indices(), expected);
+            assert_eq!(a, b);
+            assert_eq!(list.extra_indices(), expected);
+        }
+    }
+
+    #[test]
+    fn elements_dropped_exactly_once() {
+        let elem = Rc::new(());
+        let mut list: RandomList<Rc<()>> = (0..5).map(|_| Rc::clone(&elem)).collect();
+        for i in 0..5 {
+            list.set_extra(i, Some(4 - i));
+        }
+        let copy = list.clone();
+        let copy2 = copy.clone_with_map();
+        assert_eq!(Rc::strong_count(&elem), 16);
+        drop((list, copy, copy2));
+        assert_eq!(Rc::strong_count(&elem), 1);
+    }
+}
@@ -0,0 +1,500 @@
+// RCU(read-copy-update)风格的读多写少单链表
+// 链表本身是不可变的持久化结构: 节点是Arc<Node>，一旦发布就不再修改
+// - 读: 只读一次head，之后遍历的是那一刻的完整版本，不加锁、不和写者竞争，写者发布新版本也不影响正在进行的遍历
+// - 写: 写者之间用Mutex串行。修改第i个位置时复制前i个节点(copy-on-write前缀)，后面的节点原样共享，
+//   构造好新版本后用一次原子swap替换head
+// - 回收: 被换下的旧head可能还有读者在遍历，不能马上释放。旧版本交给epoch延迟释放，
+//   等所有在替换之前开始的读者都结束(一个宽限期grace period)之后才减掉它的引用计数；
+//   和新版本共享的后缀节点还被新版本引用，只有被复制掉的前缀会真正释放
+// 两种读法:
+// - read(f): pin住epoch后直接借用节点遍历，不碰引用计数，对应rcu_read_lock/rcu_read_unlock
+// - snapshot(): 给head加一次引用计数，得到可以长期持有、跨线程传递的版本
+// synchronize()等待本链表已经换下的旧版本全部回收完，对应synchronize_rcu
+
+use std::fmt;
+use std::ptr;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread;
+
+use crate::epoch;
+use crate::error::ListError;
+use crate::instrument::mutation;
+use crate::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+
+type Link<T> = Option<Arc<Node<T>>>;
+
+struct Node<T> {
+    elem: T,
+    next: Link<T>,
+}
+
+// 很长的链表一次性释放时逐个拆开，避免递归析构爆栈；遇到还被别的版本共享的节点就停下
+impl<T> Drop for Node<T> {
+    fn drop(&mut self) {
+        let mut next = self.next.take();
+        while let Some(node) = next {
+            match Arc::try_unwrap(node) {
+                Ok(mut node) => next = node.next.take(),
+                Err(_) => break,
+            }
+        }
+    }
+}
+
+pub struct RcuList<T> {
+    // 由Arc::into_raw得到，持有一个引用计数；null表示空链表
+    head: AtomicPtr<Node<T>>,
+    writer: Mutex<()>,
+    // 已经换下但还没有回收的旧版本数
+    retired: Arc<AtomicUsize>,
+}
+
+fn into_raw<T>(link: Link<T>) -> *mut Node<T> {
+    link.map_or(ptr::null_mut(), |node| Arc::into_raw(node) as *mut Node<T>)
+}
+
+// 由若干个复制出来的元素和一段共享的后缀拼出新链表
+fn build<T>(prefix: Vec<T>, suffix: Link<T>) -> Link<T> {
+    prefix
+        .into_iter()
+        .rev()
+        .fold(suffix, |next, elem| Some(Arc::new(Node { elem, next })))
+}
+
+impl<T> RcuList<T> {
+    pub fn new() -> Self {
+        RcuList {
+            head: AtomicPtr::new(ptr::null_mut()),
+            writer: Mutex::new(()),
+            retired: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    // 并发情况下结果只代表调用那一刻的状态
+    pub fn is_empty(&self) -> bool {
+        self.head.load(Ordering::Acquire).is_null()
+    }
+
+    // 读侧临界区: f里拿到的迭代器不能逃出闭包，闭包执行期间看到的版本不会被回收
+    pub fn read<R>(&self, f: impl FnOnce(Iter<'_, T>) -> R) -> R {
+        let _guard = epoch::pin();
+        let head = self.head.load(Ordering::Acquire);
+        // SAFETY: head要么是当前版本(由self持有)，要么已经被换下但回收被推迟到_guard释放之后
+        f(Iter {
+            next: unsafe { head.as_ref() },
+        })
+    }
+
+    // 当前版本的快照，持有期间不受后续写入影响
+    pub fn snapshot(&self) -> Snapshot<T> {
+        let _guard = epoch::pin();
+        let head = self.head.load(Ordering::Acquire);
+        if head.is_null() {
+            return Snapshot { head: None };
+        }
+        // SAFETY: 同read，head在_guard期间有效，加一次计数后就由快照自己持有
+        unsafe {
+            Arc::increment_strong_count(head);
+            Snapshot {
+                head: Some(Arc::from_raw(head)),
+            }
+        }
+    }
+
+    // 已经换下但还没有回收的旧版本数
+    pub fn retired_pending(&self) -> usize {
+        self.retired.load(Ordering::Acquire)
+    }
+
+    // 等待一个宽限期: 调用之前换下的旧版本全部回收之后才返回
+    // 当前线程不能在read的闭包里调用，否则自己挡住epoch前进，永远等不到
+    pub fn synchronize(&self) {
+        while self.retired_pending() > 0 {
+            epoch::pin().flush();
+            thread::yield_now();
+        }
+    }
+}
+
+impl<T: Clone + Send + Sync + 'static> RcuList<T> {
+    // 持有写锁，把当前版本交给edit；edit返回Some(new_head)时发布新版本
+    fn update<R>(&self, edit: impl FnOnce(&Link<T>) -> (Option<Link<T>>, R)) -> R {
+        let _lock = self.writer.lock().unwrap_or_else(PoisonError::into_inner);
+        let head = self.head.load(Ordering::Acquire);
+        // SAFETY: 写锁保证head不会被别的写者换下，借用它不需要额外计数
+        let current = (!head.is_null()).then(|| unsafe {
+            Arc::increment_strong_count(head);
+            Arc::from_raw(head)
+        });
+        let (new_head, out) = edit(&current);
+        drop(current);
+        if let Some(new_head) = new_head {
+            // Release保证读者通过Acquire读到新head时，新节点的内容都已经可见
+            let old = self.head.swap(into_raw(new_head), Ordering::Release);
+            self.retire(old);
+        }
+        out
+    }
+
+    // 旧版本的那一个引用计数等宽限期之后再释放
+    fn retire(&self, old: *mut Node<T>) {
+        if old.is_null() {
+            return;
+        }
+        // SAFETY: old来自into_raw，swap之后这一个计数归当前线程所有
+        let old = unsafe { Arc::from_raw(old) };
+        self.retired.fetch_add(1, Ordering::Relaxed);
+        let retired = Arc::clone(&self.retired);
+        epoch::pin().defer(move || {
+            drop(old);
+            retired.fetch_sub(1, Ordering::Release);
+        });
+    }
+
+    // 不复制任何节点
+    pub fn push_front(&self, elem: T) {
+        self.update(|head| (Some(Some(Arc::new(Node { elem, next: head.clone() }))), ()));
+        mutation!("push_front");
+    }
+
+    // 复制前index个节点；index > len时panic
+    pub fn insert(&self, index: usize, elem: T) {
+        if let Err(err) = self.try_insert(index, elem) {
+            panic!("insertion index out of bounds: {err}");
+        }
+    }
+
+    // index > len时返回OutOfRange，不发布新版本；len是写锁下看到的长度
+    pub fn try_insert(&self, index: usize, elem: T) -> Result<(), ListError> {
+        self.update(|head| {
+            let mut prefix = Vec::with_capacity(index);
+            let mut cur = head;
+            for len in 0..index {
+                let Some(node) = cur else {
+                    return (None, Err(ListError::OutOfRange { index, len }));
+                };
+                prefix.push(node.elem.clone());
+                cur = &node.next;
+            }
+            let node = Some(Arc::new(Node {
+                elem,
+                next: cur.clone(),
+            }));
+            mutation!("insert", index);
+            (Some(build(prefix, node)), Ok(()))
+        })
+    }
+
+    // 复制前index个节点，返回被删元素的拷贝(旧版本里可能还有读者在用原件)
+    pub fn remove(&self, index: usize) -> Option<T> {
+        self.update(|head| {
+            let mut prefix = Vec::with_capacity(index);
+            let mut cur = head;
+            for _ in 0..index {
+                let Some(node) = cur else {
+                    return (None, None);
+                };
+                prefix.push(node.elem.clone());
+                cur = &node.next;
+            }
+            match cur {
+                Some(node) => {
+                    mutation!("remove", index);
+                    (Some(build(prefix, node.next.clone())), Some(node.elem.clone()))
+                }
+                None => (None, None),
+            }
+        })
+    }
+
+    pub fn pop_front(&self) -> Option<T> {
+        self.remove(0)
+    }
+
+    // 只复制到最后一个被删节点为止，之后的节点继续共享；返回删掉的个数
+    pub fn retain(&self, mut keep: impl FnMut(&T) -> bool) -> usize {
+        self.update(|head| {
+            let mut kept = Vec::new();
+            let mut removed = 0;
+            // 最后一个被删节点之前保留下来的元素个数，以及它之后的后缀
+            let mut cut = None;
+            let mut cur = head;
+            while let Some(node) = cur {
+                if keep(&node.elem) {
+                    kept.push(node.elem.clone());
+                } else {
+                    removed += 1;
+                    cut = Some((kept.len(), &node.next));
+                }
+                cur = &node.next;
+            }
+            match cut {
+                Some((len, suffix)) => {
+                    mutation!("retain", removed);
+                    kept.truncate(len);
+                    (Some(build(kept, suffix.clone())), removed)
+                }
+                None => (None, 0),
+            }
+        })
+    }
+
+    pub fn clear(&self) {
+        self.update(|head| (head.is_some().then_some(None), ()));
+        mutation!("clear");
+    }
+}
+
+impl<T> Default for RcuList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Drop for RcuList<T> {
+    // &mut self保证没有读者借用当前版本，直接释放；已经换下的旧版本由epoch负责
+    fn drop(&mut self) {
+        let head = self.head.load(Ordering::Relaxed);
+        if !head.is_null() {
+            // SAFETY: head来自into_raw，这是self持有的那一个计数
+            drop(unsafe { Arc::from_raw(head) });
+        }
+    }
+}
+
+impl<T> FromIterator<T> for RcuList<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let list = RcuList::new();
+        let head = build(iter.into_iter().collect(), None);
+        list.head.store(into_raw(head), Ordering::Relaxed);
+        list
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for RcuList<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.read(|iter| f.debug_list().entries(iter).finish())
+    }
+}
+
+// 节点是不可变的，元素只会被共享读取，跨线程时要求T: Send + Sync
+unsafe impl<T: Send + Sync> Send for RcuList<T> {}
+unsafe impl<T: Send + Sync> Sync for RcuList<T> {}
+
+// 某一时刻的完整版本
+pub struct Snapshot<T> {
+    head: Link<T>,
+}
+
+impl<T> Snapshot<T> {
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            next: self.head.as_deref(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.head.is_none()
+    }
+
+    // 两个快照是否是同一个版本
+    pub fn ptr_eq(&self, other: &Snapshot<T>) -> bool {
+        match (&self.head, &other.head) {
+            (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+}
+
+impl<T> Clone for Snapshot<T> {
+    fn clone(&self) -> Self {
+        Snapshot {
+            head: self.head.clone(),
+        }
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Snapshot<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Snapshot<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+pub struct Iter<'a, T> {
+    next: Option<&'a Node<T>>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next.map(|node| {
+            self.next = node.next.as_deref();
+            &node.elem
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicBool;
+
+    fn contents<T: Clone>(list: &RcuList<T>) -> Vec<T> {
+        list.read(|iter| iter.cloned().collect())
+    }
+
+    #[test]
+    fn basic_updates() {
+        let list = RcuList::new();
+        assert!(list.is_empty());
+        assert_eq!(list.pop_front(), None);
+        list.push_front(3);
+        list.push_front(1);
+        list.insert(1, 2);
+        list.insert(3, 4);
+        assert_eq!(contents(&list), vec![1, 2, 3, 4]);
+        assert_eq!(list.remove(2), Some(3));
+        assert_eq!(list.remove(9), None);
+        assert_eq!(list.retain(|&x| x != 1), 1);
+        assert_eq!(list.retain(|_| true), 0);
+        assert_eq!(format!("{list:?}"), "[2, 4]");
+        list.clear();
+        assert!(list.is_empty());
+        list.synchronize();
+        assert_eq!(list.retired_pending(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "insertion index out of bounds")]
+    fn insert_past_end_panics() {
+        let list: RcuList<i32> = [1, 2].into_iter().collect();
+        list.insert(3, 0);
+    }
+
+    #[test]
+    fn writes_copy_only_the_prefix() {
+        let list: RcuList<i32> = (0..6).collect();
+        let before = list.snapshot();
+        list.insert(2, 100);
+        let after = list.snapshot();
+        // 旧快照不受影响
+        assert_eq!(before.iter().copied().collect::<Vec<_>>(), vec![0, 1, 2, 3, 4, 5]);
+        assert_eq!(after.iter().copied().collect::<Vec<_>>(), vec![0, 1, 100, 2, 3, 4, 5]);
+        assert!(!before.ptr_eq(&after));
+        // 插入点之后的节点是同一批
+        let nth = |s: &Snapshot<i32>, n: usize| {
+            let mut cur = s.head.as_ref().unwrap();
+            for _ in 0..n {
+                cur = cur.next.as_ref().unwrap();
+            }
+            Arc::as_ptr(cur)
+        };
+        assert_ne!(nth(&before, 1), nth(&after, 1));
+        assert_eq!(nth(&before, 2), nth(&after, 3));
+        assert_eq!(nth(&before, 5), nth(&after, 6));
+        assert!(list.snapshot().ptr_eq(&after));
+    }
+
+    #[test]
+    fn old_versions_reclaimed_after_grace_period() {
+        let removed = Arc::new(7);
+        let list: RcuList<Arc<i32>> = [Arc::new(1), Arc::clone(&removed), Arc::new(9)].into_iter().collect();
+        let taken = list.remove(1).unwrap();
+        drop(taken);
+        // 旧版本还在等宽限期，里面的元素没有释放
+        assert_eq!(Arc::strong_count(&removed), 2);
+        assert_eq!(list.retired_pending(), 1);
+        list.synchronize();
+        assert_eq!(list.retired_pending(), 0);
+        assert_eq!(Arc::strong_count(&removed), 1);
+        // 快照会让旧版本活得更久，和宽限期无关
+        let snap = list.snapshot();
+        list.clear();
+        list.synchronize();
+        assert_eq!(snap.iter().map(|x| **x).collect::<Vec<_>>(), vec![1, 9]);
+    }
+
+    #[test]
+    fn long_list_drops_without_recursion() {
+        let list: RcuList<u32> = (0..200_000).collect();
+        let snap = list.snapshot();
+        drop(list);
+        assert_eq!(snap.iter().count(), 200_000);
+    }
+
+    #[test]
+    fn concurrent_readers_see_consistent_versions() {
+        const READERS: usize = 4;
+        const WRITES: u64 = 2000;
+
+        // 写者始终保持链表严格递减，读者在任何版本里都应该看到递减序列
+        let list = Arc::new(RcuList::new());
+        let done = Arc::new(AtomicBool::new(false));
+        let readers: Vec<_> = (0..READERS)
+            .map(|r| {
+                let list = Arc::clone(&list);
+                let done = Arc::clone(&done);
+                thread::spawn(move || {
+                    let mut reads = 0;
+                    while !done.load(Ordering::Acquire) {
+                        let decreasing = if r % 2 == 0 {
+                            list.read(|iter| {
+                                let v: Vec<u64> = iter.copied().collect();
+                                v.windows(2).all(|w| w[0] > w[1])
+                            })
+                        } else {
+                            let snap = list.snapshot();
+                            let v: Vec<u64> = snap.iter().copied().collect();
+                            v.windows(2).all(|w| w[0] > w[1])
+                        };
+                        assert!(decreasing);
+                        reads += 1;
+                    }
+                    reads
+                })
+            })
+            .collect();
+
+        for i in 0..WRITES {
+            list.push_front(i);
+            match i % 4 {
+                1 => {
+                    list.remove((i % 7) as usize);
+                }
+                3 => {
+                    list.retain(|&x| x % 5 != 0);
+                }
+                _ => {}
+            }
+        }
+        done.store(true, Ordering::Release);
+        for r in readers {
+            r.join().unwrap();
+        }
+        list.synchronize();
+        assert_eq!(list.retired_pending(), 0);
+        let v = contents(&list);
+        assert!(v.windows(2).all(|w| w[0] > w[1]));
+        assert!(v.iter().all(|&x| x % 5 != 0));
+    }
+
+    #[test]
+    fn try_insert_leaves_list_unchanged() {
+        let list = RcuList::new();
+        list.try_insert(0, 1).unwrap();
+        list.try_insert(1, 2).unwrap();
+        assert_eq!(list.try_insert(4, 3), Err(ListError::OutOfRange { index: 4, len: 2 }));
+        assert_eq!(contents(&list), vec![1, 2]);
+    }
+}
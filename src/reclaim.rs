@@ -0,0 +1,92 @@
+// 无锁结构的内存回收策略
+// TreiberStack和MsQueue通过Reclaim参数选择用epoch还是危险指针回收摘下的节点，
+// 两种策略的取舍见epoch.rs和hazard.rs开头的说明:
+// Epoch读路径几乎没有开销，但一个线程卡在pin状态会让所有垃圾都无法释放；
+// Hazard每次读指针都要写槽加fence，但未释放的垃圾总量始终有上界
+
+use crate::sync::atomic::{AtomicPtr, Ordering};
+use crate::{epoch, hazard};
+
+pub trait Reclaim: 'static {
+    type Guard: ReclaimGuard;
+
+    // 进入一次对共享结构的访问，Guard析构时结束
+    fn pin() -> Self::Guard;
+
+    // 已经retire但还没有释放的节点数量，用来比较两种策略的内存表现
+    fn pending() -> usize;
+}
+
+pub trait ReclaimGuard {
+    // 读出src里的指针，并保证在Guard析构(或同一个slot被再次protect)之前它不会被释放
+    // slot取值范围是0..hazard::SLOTS
+    fn protect<T>(&self, slot: usize, src: &AtomicPtr<T>) -> *mut T;
+
+    // 登记一个已经读出的指针，调用者登记之后要自己确认它仍然可达才能解引用
+    fn protect_raw<T>(&self, slot: usize, ptr: *mut T);
+
+    /// 节点已经从共享结构中摘下，等不再有线程可能访问它时释放
+    ///
+    /// # Safety
+    ///
+    /// ptr必须来自Box::into_raw且已经从共享结构中摘下，之后不会再被其他地方释放；
+    /// 析构T必须可以在任意线程、任意时间点进行
+    unsafe fn retire<T>(&self, ptr: *mut T);
+}
+
+// 基于epoch的回收，使用epoch::default_collector
+pub struct Epoch;
+
+impl Reclaim for Epoch {
+    type Guard = epoch::Guard;
+
+    fn pin() -> epoch::Guard {
+        epoch::pin()
+    }
+
+    fn pending() -> usize {
+        epoch::default_collector().pending()
+    }
+}
+
+impl ReclaimGuard for epoch::Guard {
+    // pin住之后读到的指针在unpin之前都不会被释放，直接读即可
+    fn protect<T>(&self, _slot: usize, src: &AtomicPtr<T>) -> *mut T {
+        src.load(Ordering::Acquire)
+    }
+
+    fn protect_raw<T>(&self, _slot: usize, _ptr: *mut T) {}
+
+    unsafe fn retire<T>(&self, ptr: *mut T) {
+        self.defer_destroy(ptr);
+    }
+}
+
+// 基于危险指针的回收，使用hazard::default_domain
+pub struct Hazard;
+
+impl Reclaim for Hazard {
+    type Guard = hazard::Guard;
+
+    fn pin() -> hazard::Guard {
+        hazard::guard()
+    }
+
+    fn pending() -> usize {
+        hazard::default_domain().pending()
+    }
+}
+
+impl ReclaimGuard for hazard::Guard {
+    fn protect<T>(&self, slot: usize, src: &AtomicPtr<T>) -> *mut T {
+        hazard::Guard::protect(self, slot, src)
+    }
+
+    fn protect_raw<T>(&self, slot: usize, ptr: *mut T) {
+        hazard::Guard::protect_raw(self, slot, ptr);
+    }
+
+    unsafe fn retire<T>(&self, ptr: *mut T) {
+        hazard::Guard::retire(self, ptr);
+    }
+}
@@ -0,0 +1,403 @@
+// 循环单向链表(环)
+// 最后一个节点的next指回第一个节点，没有头尾，只有一个游标指向"当前"元素
+// 轮转调度(round-robin)、约瑟夫问题这类场景里，元素本来就围成一圈，游标转一步就是O(1)
+// 单向链表删除节点需要前驱，所以环里保存的是游标前一个节点prev，当前节点就是prev.next:
+// - push把新元素插在prev和当前节点之间，新元素成为当前元素
+// - pop删掉当前元素，游标落到它的下一个
+// - 只有一个节点时prev和当前节点是同一个，它的next指向自己
+
+use std::fmt;
+use std::iter::FusedIterator;
+use std::marker::PhantomData;
+use std::ptr;
+
+struct Node<T> {
+    elem: T,
+    next: *mut Node<T>,
+}
+
+pub struct Ring<T> {
+    // 游标的前一个节点，环为空时为null
+    prev: *mut Node<T>,
+    len: usize,
+    _boo: PhantomData<T>,
+}
+
+impl<T> Ring<T> {
+    pub fn new() -> Self {
+        Ring {
+            prev: ptr::null_mut(),
+            len: 0,
+            _boo: PhantomData,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn cursor(&self) -> *mut Node<T> {
+        unsafe { (*self.prev).next }
+    }
+
+    // 在游标处插入，新元素成为当前元素，原来的当前元素变成它的下一个
+    pub fn push(&mut self, elem: T) {
+        let node = Box::into_raw(Box::new(Node {
+            elem,
+            next: ptr::null_mut(),
+        }));
+        unsafe {
+            if self.prev.is_null() {
+                (*node).next = node;
+                self.prev = node;
+            } else {
+                (*node).next = self.cursor();
+                (*self.prev).next = node;
+            }
+        }
+        self.len += 1;
+    }
+
+    // 删除当前元素，游标移动到下一个
+    pub fn pop(&mut self) -> Option<T> {
+        if self.prev.is_null() {
+            return None;
+        }
+        unsafe {
+            let cur = self.cursor();
+            if cur == self.prev {
+                self.prev = ptr::null_mut();
+            } else {
+                (*self.prev).next = (*cur).next;
+            }
+            self.len -= 1;
+            Some(Box::from_raw(cur).elem)
+        }
+    }
+
+    pub fn current(&self) -> Option<&T> {
+        (!self.prev.is_null()).then(|| unsafe { &(*self.cursor()).elem })
+    }
+
+    pub fn current_mut(&mut self) -> Option<&mut T> {
+        (!self.prev.is_null()).then(|| unsafe { &mut (*self.cursor()).elem })
+    }
+
+    // 游标往前转n步，转满一圈的部分直接跳过
+    pub fn rotate(&mut self, n: usize) {
+        if self.len == 0 {
+            return;
+        }
+        for _ in 0..n % self.len {
+            self.prev = unsafe { (*self.prev).next };
+        }
+    }
+
+    // 单向链表没法往回走，往回转n步等于往前转len - n步
+    pub fn rotate_back(&mut self, n: usize) {
+        if self.len == 0 {
+            return;
+        }
+        self.rotate(self.len - n % self.len);
+    }
+
+    // 沿next实际走一圈数出来的环长度，正常情况下总是等于len()，用来校验环没有断
+    pub fn cycle_len(&self) -> usize {
+        if self.prev.is_null() {
+            return 0;
+        }
+        let mut count = 1;
+        let mut node = self.cursor();
+        unsafe {
+            while node != self.prev {
+                node = (*node).next;
+                count += 1;
+            }
+        }
+        count
+    }
+
+    // 从当前元素开始往前数，第一个满足条件的元素离游标几步
+    pub fn position<P: FnMut(&T) -> bool>(&self, pred: P) -> Option<usize> {
+        self.iter().position(pred)
+    }
+
+    // 每次往前数k个，删掉第k个，直到环为空；k为0时panic
+    pub fn remove_every(&mut self, k: usize) -> RemoveEvery<'_, T> {
+        assert!(k > 0, "step must be at least 1");
+        RemoveEvery { ring: self, k }
+    }
+
+    pub fn clear(&mut self) {
+        while self.pop().is_some() {}
+    }
+
+    // 从当前元素开始转一圈
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            next: if self.prev.is_null() { ptr::null_mut() } else { self.cursor() },
+            len: self.len,
+            _boo: PhantomData,
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut {
+            next: if self.prev.is_null() { ptr::null_mut() } else { self.cursor() },
+            len: self.len,
+            _boo: PhantomData,
+        }
+    }
+}
+
+// 约瑟夫问题: n个人(编号1..=n)围成一圈，从1开始报数，数到k的人出列
+// 返回出列顺序，最后一个就是幸存者
+pub fn josephus(n: usize, k: usize) -> Vec<usize> {
+    let mut ring = Ring::new();
+    // push插在游标处，倒着插进去之后游标正好停在1号
+    for i in (1..=n).rev() {
+        ring.push(i);
+    }
+    ring.remove_every(k).collect()
+}
+
+impl<T> Drop for Ring<T> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+impl<T> Default for Ring<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Ring<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+// 按迭代顺序把元素依次排在游标之后，收集完游标停在第一个元素上
+impl<T> Extend<T> for Ring<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for elem in iter {
+            self.push(elem);
+            self.rotate(1);
+        }
+    }
+}
+
+impl<T> FromIterator<T> for Ring<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut ring = Self::new();
+        ring.extend(iter);
+        ring
+    }
+}
+
+unsafe impl<T: Send> Send for Ring<T> {}
+unsafe impl<T: Sync> Sync for Ring<T> {}
+
+pub struct RemoveEvery<'a, T> {
+    ring: &'a mut Ring<T>,
+    k: usize,
+}
+
+impl<T> Iterator for RemoveEvery<'_, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.ring.rotate(self.k - 1);
+        self.ring.pop()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.ring.len, Some(self.ring.len))
+    }
+}
+
+impl<T> ExactSizeIterator for RemoveEvery<'_, T> {}
+
+pub struct Iter<'a, T> {
+    next: *mut Node<T>,
+    len: usize,
+    _boo: PhantomData<&'a T>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        if self.len == 0 {
+            return None;
+        }
+        self.len -= 1;
+        unsafe {
+            let node = self.next;
+            self.next = (*node).next;
+            Some(&(*node).elem)
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+impl<T> FusedIterator for Iter<'_, T> {}
+
+impl<'a, T> IntoIterator for &'a Ring<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+pub struct IterMut<'a, T> {
+    next: *mut Node<T>,
+    len: usize,
+    _boo: PhantomData<&'a mut T>,
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<&'a mut T> {
+        if self.len == 0 {
+            return None;
+        }
+        self.len -= 1;
+        unsafe {
+            let node = self.next;
+            self.next = (*node).next;
+            Some(&mut (*node).elem)
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T> ExactSizeIterator for IterMut<'_, T> {}
+impl<T> FusedIterator for IterMut<'_, T> {}
+
+pub struct IntoIter<T>(Ring<T>);
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.0.pop()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.0.len, Some(self.0.len))
+    }
+}
+
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
+impl<T> IntoIterator for Ring<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn items(ring: &Ring<i32>) -> Vec<i32> {
+        ring.iter().copied().collect()
+    }
+
+    #[test]
+    fn push_pop_at_cursor() {
+        let mut ring = Ring::new();
+        assert_eq!(ring.pop(), None);
+        ring.push(1);
+        assert_eq!(ring.current(), Some(&1));
+        ring.push(2);
+        ring.push(3);
+        // 每次push都插在游标处
+        assert_eq!(items(&ring), vec![3, 2, 1]);
+        assert_eq!(ring.pop(), Some(3));
+        assert_eq!(ring.current(), Some(&2));
+        *ring.current_mut().unwrap() = 20;
+        assert_eq!(items(&ring), vec![20, 1]);
+        assert_eq!(ring.pop(), Some(20));
+        assert_eq!(ring.pop(), Some(1));
+        assert!(ring.is_empty());
+        assert_eq!(ring.current(), None);
+    }
+
+    #[test]
+    fn rotate_both_ways() {
+        let mut ring: Ring<i32> = (0..5).collect();
+        assert_eq!(items(&ring), vec![0, 1, 2, 3, 4]);
+        ring.rotate(2);
+        assert_eq!(items(&ring), vec![2, 3, 4, 0, 1]);
+        ring.rotate(13);
+        assert_eq!(ring.current(), Some(&0));
+        ring.rotate_back(1);
+        assert_eq!(ring.current(), Some(&4));
+        ring.rotate_back(10);
+        assert_eq!(ring.current(), Some(&4));
+        ring.rotate(0);
+        assert_eq!(ring.current(), Some(&4));
+    }
+
+    #[test]
+    fn cycle_queries() {
+        let mut ring: Ring<i32> = (0..7).collect();
+        assert_eq!(ring.cycle_len(), 7);
+        assert_eq!(ring.position(|&x| x == 5), Some(5));
+        ring.rotate(6);
+        assert_eq!(ring.position(|&x| x == 5), Some(6));
+        assert_eq!(ring.position(|&x| x == 9), None);
+        for x in ring.iter_mut() {
+            *x *= 10;
+        }
+        ring.pop();
+        assert_eq!(ring.cycle_len(), ring.len());
+        assert_eq!(Ring::<i32>::new().cycle_len(), 0);
+    }
+
+    #[test]
+    fn josephus_order() {
+        assert_eq!(josephus(7, 3), vec![3, 6, 2, 7, 5, 1, 4]);
+        assert_eq!(josephus(41, 3).last(), Some(&31));
+        assert_eq!(josephus(5, 1), vec![1, 2, 3, 4, 5]);
+        assert!(josephus(0, 2).is_empty());
+    }
+
+    #[test]
+    fn round_robin_scheduling() {
+        // 每个任务剩余的时间片，轮到的任务跑一片，跑完就出环
+        let mut ring: Ring<(char, u32)> = [('a', 2), ('b', 1), ('c', 3)].into_iter().collect();
+        let mut trace = String::new();
+        while let Some((name, left)) = ring.current_mut() {
+            trace.push(*name);
+            *left -= 1;
+            if *left == 0 {
+                ring.pop();
+            } else {
+                ring.rotate(1);
+            }
+        }
+        assert_eq!(trace, "abcacc");
+    }
+}
@@ -0,0 +1,29 @@
+// xorshift64*伪随机数，跳表决定节点高度、消除数组选槽位、各模块的随机测试都用它
+// 不需要密码学强度，只要快、可以用种子复现
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+#[derive(Clone)]
+pub(crate) struct XorShift(u64);
+
+impl XorShift {
+    pub(crate) fn new(seed: u64) -> Self {
+        // 状态不能为0
+        XorShift(seed | 1)
+    }
+
+    // 每个新建的结构都拿到不同的种子
+    pub(crate) fn from_counter() -> Self {
+        static COUNTER: AtomicU64 = AtomicU64::new(0x9E37_79B9_7F4A_7C15);
+        Self::new(COUNTER.fetch_add(0x9E37_79B9_7F4A_7C15, Ordering::Relaxed))
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        self.0 = x;
+        x.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+}
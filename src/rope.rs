@@ -0,0 +1,463 @@
+// 绳(rope)，把长字符串切成一串小块挂在链表上，适合编辑器那种频繁在中间插入删除的场景
+// 每块是一个不超过MAX_CHUNK字节的String，并缓存自己的字符数
+// 所有编辑都归结为两个链表操作:
+// - 在字符下标处切开: 游标走到目标块，必要时把块一分为二，再split_before
+// - 拼接: 链表append，接缝两侧的小块能装进一块时顺手合并，避免块越切越碎
+// 定位要从头逐块走，O(块数)；块内的插入删除只移动一块里的字节
+// 下标一律按char计，另提供char和byte下标的互相转换
+
+use std::fmt;
+use std::mem;
+use std::ops::{Bound, RangeBounds};
+
+use crate::error::{check_insert, ListError};
+use crate::simple_deque_3::{self, List};
+
+// 每块最多这么多字节，一个char最多4字节，所以切块总能前进
+const MAX_CHUNK: usize = 64;
+
+#[derive(Clone)]
+struct Chunk {
+    text: String,
+    chars: usize,
+}
+
+impl Chunk {
+    fn new(text: String) -> Self {
+        let chars = text.chars().count();
+        Chunk { text, chars }
+    }
+
+    // 在块内第at个字符处切开，self保留前半段，返回后半段
+    fn split_off(&mut self, at: usize) -> Chunk {
+        let byte = char_to_byte(&self.text, at);
+        let tail = Chunk {
+            text: self.text.split_off(byte),
+            chars: self.chars - at,
+        };
+        self.chars = at;
+        tail
+    }
+}
+
+fn char_to_byte(s: &str, char_idx: usize) -> usize {
+    s.char_indices().nth(char_idx).map_or(s.len(), |(byte, _)| byte)
+}
+
+#[derive(Clone, Default)]
+pub struct Rope {
+    chunks: List<Chunk>,
+    chars: usize,
+    bytes: usize,
+}
+
+impl Rope {
+    pub fn new() -> Self {
+        Rope::default()
+    }
+
+    fn from_chunks(chunks: List<Chunk>) -> Self {
+        let chars = chunks.iter().map(|c| c.chars).sum();
+        let bytes = chunks.iter().map(|c| c.text.len()).sum();
+        Rope { chunks, chars, bytes }
+    }
+
+    pub fn len_chars(&self) -> usize {
+        self.chars
+    }
+
+    pub fn len_bytes(&self) -> usize {
+        self.bytes
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bytes == 0
+    }
+
+    // 在第char_idx个字符前插入s；char_idx大于字符数时panic
+    pub fn insert(&mut self, char_idx: usize, s: &str) {
+        if s.is_empty() {
+            assert!(char_idx <= self.chars, "char index out of bounds");
+            return;
+        }
+        let right = self.split_chunks(char_idx);
+        self.append_chunks(chunks_of(s));
+        self.append_chunks(right);
+    }
+
+    // char_idx大于字符数时返回OutOfRange
+    pub fn try_insert(&mut self, char_idx: usize, s: &str) -> Result<(), ListError> {
+        check_insert(char_idx, self.chars)?;
+        self.insert(char_idx, s);
+        Ok(())
+    }
+
+    // 删除一段字符下标范围；范围越界时panic
+    pub fn remove<R: RangeBounds<usize>>(&mut self, range: R) {
+        let (start, end) = self.char_range(range);
+        assert!(start <= end && end <= self.chars, "char range out of bounds");
+        self.remove_chars(start, end);
+    }
+
+    // 范围越界时返回OutOfRange，自己保持不变:
+    // end超过字符数时报告end和字符数，start大于end时报告start和end
+    pub fn try_remove<R: RangeBounds<usize>>(&mut self, range: R) -> Result<(), ListError> {
+        let (start, end) = self.char_range(range);
+        check_insert(end, self.chars)?;
+        check_insert(start, end)?;
+        self.remove_chars(start, end);
+        Ok(())
+    }
+
+    // 把范围换算成[start, end)
+    fn char_range<R: RangeBounds<usize>>(&self, range: R) -> (usize, usize) {
+        let start = match range.start_bound() {
+            Bound::Included(&i) => i,
+            Bound::Excluded(&i) => i.saturating_add(1),
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&i) => i.saturating_add(1),
+            Bound::Excluded(&i) => i,
+            Bound::Unbounded => self.chars,
+        };
+        (start, end)
+    }
+
+    fn remove_chars(&mut self, start: usize, end: usize) {
+        let right = self.split_chunks(end);
+        drop(self.split_chunks(start));
+        self.append_chunks(right);
+    }
+
+    // 把other接在后面
+    pub fn concat(&mut self, other: Rope) {
+        self.append_chunks(other.chunks);
+    }
+
+    // 在第char_idx个字符处切成两段；char_idx大于字符数时panic
+    pub fn split(mut self, char_idx: usize) -> (Rope, Rope) {
+        let right = self.split_chunks(char_idx);
+        (self, Rope::from_chunks(right))
+    }
+
+    // 切下第char_idx个字符之后的部分返回，自己留下前面；char_idx大于字符数时返回OutOfRange
+    pub fn try_split_off(&mut self, char_idx: usize) -> Result<Rope, ListError> {
+        check_insert(char_idx, self.chars)?;
+        Ok(Rope::from_chunks(self.split_chunks(char_idx)))
+    }
+
+    pub fn char_at(&self, char_idx: usize) -> Option<char> {
+        let mut start = 0;
+        for chunk in &self.chunks {
+            if char_idx < start + chunk.chars {
+                return chunk.text.chars().nth(char_idx - start);
+            }
+            start += chunk.chars;
+        }
+        None
+    }
+
+    // 第char_idx个字符的字节偏移，char_idx等于字符数时返回总字节数；更大时panic
+    pub fn char_to_byte(&self, char_idx: usize) -> usize {
+        assert!(char_idx <= self.chars, "char index out of bounds");
+        let (mut chars, mut bytes) = (0, 0);
+        for chunk in &self.chunks {
+            if char_idx < chars + chunk.chars {
+                return bytes + char_to_byte(&chunk.text, char_idx - chars);
+            }
+            chars += chunk.chars;
+            bytes += chunk.text.len();
+        }
+        bytes
+    }
+
+    // 字节偏移byte_idx之前有多少个字符；越界或者不在字符边界上时panic
+    pub fn byte_to_char(&self, byte_idx: usize) -> usize {
+        assert!(byte_idx <= self.bytes, "byte index out of bounds");
+        let (mut chars, mut bytes) = (0, 0);
+        for chunk in &self.chunks {
+            if byte_idx < bytes + chunk.text.len() {
+                let local = byte_idx - bytes;
+                assert!(chunk.text.is_char_boundary(local), "byte index is not a char boundary");
+                return chars + chunk.text[..local].chars().count();
+            }
+            chars += chunk.chars;
+            bytes += chunk.text.len();
+        }
+        chars
+    }
+
+    pub fn chars(&self) -> impl Iterator<Item = char> + '_ {
+        self.chunks().flat_map(str::chars)
+    }
+
+    // 按存储的块依次给出字符串片段
+    pub fn chunks(&self) -> Chunks<'_> {
+        Chunks(self.chunks.iter())
+    }
+
+    pub fn chunk_count(&self) -> usize {
+        self.chunks.len()
+    }
+
+    // 在第at个字符处切开，self保留前半段，返回后半段的块
+    fn split_chunks(&mut self, at: usize) -> List<Chunk> {
+        assert!(at <= self.chars, "char index out of bounds");
+        let mut cursor = self.chunks.cursor_mut();
+        cursor.move_next();
+        let mut start = 0;
+        while let Some(chunk) = cursor.current() {
+            if at < start + chunk.chars {
+                // 切点落在块中间时把块一分为二，游标停在后半块上
+                if at > start {
+                    let tail = chunk.split_off(at - start);
+                    cursor.insert_after(tail);
+                    cursor.move_next();
+                }
+                break;
+            }
+            start += chunk.chars;
+            cursor.move_next();
+        }
+        let left = cursor.split_before();
+        let right = mem::replace(&mut self.chunks, left);
+        let right_bytes: usize = right.iter().map(|c| c.text.len()).sum();
+        self.chars = at;
+        self.bytes -= right_bytes;
+        right
+    }
+
+    fn append_chunks(&mut self, mut other: List<Chunk>) {
+        for chunk in &other {
+            self.chars += chunk.chars;
+            self.bytes += chunk.text.len();
+        }
+        // 接缝两侧的块装得进一块时合并成一块
+        if let (Some(last), Some(first)) = (self.chunks.back_mut(), other.front()) {
+            if last.text.len() + first.text.len() <= MAX_CHUNK {
+                let first = other.pop_front().unwrap();
+                last.text.push_str(&first.text);
+                last.chars += first.chars;
+            }
+        }
+        self.chunks.append(&mut other);
+    }
+}
+
+// 把s按字符边界切成不超过MAX_CHUNK字节的块
+fn chunks_of(mut s: &str) -> List<Chunk> {
+    let mut out = List::new();
+    while !s.is_empty() {
+        let mut end = s.len().min(MAX_CHUNK);
+        while !s.is_char_boundary(end) {
+            end -= 1;
+        }
+        out.push_back(Chunk::new(s[..end].to_string()));
+        s = &s[end..];
+    }
+    out
+}
+
+impl From<&str> for Rope {
+    fn from(s: &str) -> Self {
+        Rope::from_chunks(chunks_of(s))
+    }
+}
+
+impl From<String> for Rope {
+    fn from(s: String) -> Self {
+        Rope::from(s.as_str())
+    }
+}
+
+impl fmt::Display for Rope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.chunks().try_for_each(|chunk| f.write_str(chunk))
+    }
+}
+
+impl fmt::Debug for Rope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.to_string(), f)
+    }
+}
+
+// 按内容比较，和块怎么切分无关
+impl PartialEq for Rope {
+    fn eq(&self, other: &Self) -> bool {
+        self.bytes == other.bytes && self.chunks().flat_map(str::bytes).eq(other.chunks().flat_map(str::bytes))
+    }
+}
+
+impl Eq for Rope {}
+
+impl PartialEq<str> for Rope {
+    fn eq(&self, other: &str) -> bool {
+        self.bytes == other.len() && self.chunks().flat_map(str::bytes).eq(other.bytes())
+    }
+}
+
+impl PartialEq<&str> for Rope {
+    fn eq(&self, other: &&str) -> bool {
+        *self == **other
+    }
+}
+
+pub struct Chunks<'a>(simple_deque_3::Iter<'a, Chunk>);
+
+impl<'a> Iterator for Chunks<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|chunk| chunk.text.as_str())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for Chunks<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|chunk| chunk.text.as_str())
+    }
+}
+
+impl ExactSizeIterator for Chunks<'_> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::XorShift;
+
+    // 缓存的字符数、字节数和各块一致，没有空块，块都不超过MAX_CHUNK
+    fn check(rope: &Rope) {
+        let mut chars = 0;
+        for chunk in &rope.chunks {
+            assert!(!chunk.text.is_empty() && chunk.text.len() <= MAX_CHUNK);
+            assert_eq!(chunk.chars, chunk.text.chars().count());
+            chars += chunk.chars;
+        }
+        assert_eq!(chars, rope.len_chars());
+        assert_eq!(rope.chunks().map(str::len).sum::<usize>(), rope.len_bytes());
+    }
+
+    #[test]
+    fn insert_and_remove() {
+        let mut rope = Rope::from("hello world");
+        rope.insert(5, ",");
+        rope.insert(12, "!");
+        rope.insert(0, ">> ");
+        assert_eq!(rope, ">> hello, world!");
+        rope.remove(0..3);
+        rope.remove(5..=5);
+        assert_eq!(rope, "hello world!");
+        rope.remove(5..);
+        assert_eq!(rope.to_string(), "hello");
+        rope.remove(..);
+        assert!(rope.is_empty());
+        check(&rope);
+    }
+
+    #[test]
+    fn large_text_is_chunked() {
+        let text = "日本語のテキスト".repeat(40);
+        let mut rope = Rope::from(text.as_str());
+        check(&rope);
+        assert!(rope.chunk_count() > 1);
+        assert_eq!(rope.len_chars(), 320);
+        assert_eq!(rope.len_bytes(), text.len());
+        rope.insert(100, "abc");
+        assert_eq!(rope.char_at(100), Some('a'));
+        assert_eq!(rope.char_at(103), text.chars().nth(100));
+        assert_eq!(rope.char_at(323), None);
+        check(&rope);
+    }
+
+    #[test]
+    fn concat_and_split() {
+        let mut a = Rope::from("foo");
+        a.concat(Rope::from("bar"));
+        // 两个小块在接缝处合并
+        assert_eq!(a.chunk_count(), 1);
+        let (left, right) = a.split(2);
+        assert_eq!(left, "fo");
+        assert_eq!(right, "obar");
+        let (empty, all) = right.split(0);
+        assert!(empty.is_empty());
+        assert_eq!(format!("{all:?}"), "\"obar\"");
+        check(&left);
+        check(&all);
+    }
+
+    #[test]
+    fn index_conversion() {
+        let rope = Rope::from("aé日🙂b".repeat(20).as_str());
+        let text = rope.to_string();
+        for (char_idx, (byte_idx, _)) in text.char_indices().enumerate() {
+            assert_eq!(rope.char_to_byte(char_idx), byte_idx);
+            assert_eq!(rope.byte_to_char(byte_idx), char_idx);
+        }
+        assert_eq!(rope.char_to_byte(rope.len_chars()), rope.len_bytes());
+        assert_eq!(rope.byte_to_char(rope.len_bytes()), rope.len_chars());
+    }
+
+    #[test]
+    #[should_panic(expected = "char boundary")]
+    fn byte_inside_char_panics() {
+        Rope::from("日本").byte_to_char(1);
+    }
+
+    #[test]
+    fn randomized_against_string() {
+        let mut rng = XorShift::new(0x20e);
+        let pieces = ["a", "xyz", "é", "日本語", "🙂🙂", &"long-chunk-".repeat(9)];
+        let mut rope = Rope::new();
+        let mut model: Vec<char> = Vec::new();
+        for _ in 0..1500 {
+            let len = model.len();
+            if !rng.next_u64().is_multiple_of(3) || len == 0 {
+                let at = (rng.next_u64() as usize) % (len + 1);
+                let piece = pieces[(rng.next_u64() as usize) % pieces.len()];
+                rope.insert(at, piece);
+                model.splice(at..at, piece.chars());
+            } else {
+                let start = (rng.next_u64() as usize) % len;
+                let end = start + (rng.next_u64() as usize) % (len - start).min(20) + 1;
+                rope.remove(start..end);
+                model.drain(start..end);
+            }
+            check(&rope);
+            assert!(rope.chars().eq(model.iter().copied()));
+        }
+    }
+
+    #[test]
+    fn try_insert_and_split_off() {
+        let mut rope = Rope::from("héllo");
+        rope.try_insert(5, " wörld").unwrap();
+        assert_eq!(rope.try_insert(12, "!"), Err(ListError::OutOfRange { index: 12, len: 11 }));
+        let right = rope.try_split_off(5).unwrap();
+        check(&rope);
+        check(&right);
+        assert_eq!((rope.to_string(), right.to_string()), ("héllo".to_string(), " wörld".to_string()));
+        assert!(rope.try_split_off(6).is_err());
+        assert_eq!(rope, "héllo");
+    }
+
+    #[test]
+    fn try_remove_out_of_range() {
+        let mut rope = Rope::from("héllo wörld");
+        assert_eq!(rope.try_remove(6..12), Err(ListError::OutOfRange { index: 12, len: 11 }));
+        assert_eq!(rope.try_remove(..=11), Err(ListError::OutOfRange { index: 12, len: 11 }));
+        #[allow(clippy::reversed_empty_ranges)]
+        let reversed = rope.try_remove(4..2);
+        assert_eq!(reversed, Err(ListError::OutOfRange { index: 4, len: 2 }));
+        assert_eq!(rope, "héllo wörld");
+        rope.try_remove(5..).unwrap();
+        check(&rope);
+        rope.try_remove(..1).unwrap();
+        assert_eq!(rope, "éllo");
+    }
+}
@@ -0,0 +1,326 @@
+// 协作式轮转(round-robin)调度器的小例子，就绪队列是intrusive_list::IntrusiveList
+// 每个Task内嵌自己的ListLink，调度器不为任务分配任何内存:
+// - schedule: 挂到就绪队列尾部，O(1)
+// - pick_next: 从队头取出一个任务作为当前任务，O(1)
+// - yield_current: 当前任务让出CPU，重新挂到队尾，O(1)
+// - remove: 凭任务本身(就是它的"句柄")直接从队列中间摘掉，O(1)
+// 任务体是一个返回Step的闭包，每次被调度执行一步: Yield表示还要继续跑，
+// Block表示等待外部事件，需要别人再schedule它；Done表示结束，之后不能再被调度
+// 整个调度器是单线程、确定性的，执行顺序只取决于schedule的顺序和各任务返回的Step
+
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::mem::offset_of;
+use std::pin::Pin;
+use std::ptr::{self, NonNull};
+
+use crate::intrusive_list::{IntrusiveList, Linked, ListLink};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step {
+    Yield,
+    Block,
+    Done,
+}
+
+pub struct Task {
+    id: u32,
+    step: RefCell<Box<dyn FnMut() -> Step>>,
+    runs: Cell<u64>,
+    done: Cell<bool>,
+    link: ListLink,
+}
+
+impl Task {
+    pub fn new(id: u32, step: impl FnMut() -> Step + 'static) -> Self {
+        Task {
+            id,
+            step: RefCell::new(Box::new(step)),
+            runs: Cell::new(0),
+            done: Cell::new(false),
+            link: ListLink::new(),
+        }
+    }
+
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    // 被执行过多少步
+    pub fn runs(&self) -> u64 {
+        self.runs.get()
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.done.get()
+    }
+
+    // 是否在某个调度器的就绪队列里
+    pub fn is_queued(&self) -> bool {
+        self.link.is_linked()
+    }
+}
+
+impl fmt::Debug for Task {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Task")
+            .field("id", &self.id)
+            .field("runs", &self.runs())
+            .field("done", &self.is_done())
+            .field("queued", &self.is_queued())
+            .finish()
+    }
+}
+
+unsafe impl Linked for Task {
+    fn links(node: NonNull<Self>) -> NonNull<ListLink> {
+        unsafe { NonNull::new_unchecked(ptr::addr_of_mut!((*node.as_ptr()).link)) }
+    }
+
+    unsafe fn from_links(link: NonNull<ListLink>) -> NonNull<Self> {
+        unsafe {
+            let base = (link.as_ptr() as *mut u8).sub(offset_of!(Task, link));
+            NonNull::new_unchecked(base as *mut Task)
+        }
+    }
+}
+
+pub struct Scheduler<'a> {
+    run_queue: IntrusiveList<'a, Task>,
+    // 正在执行的任务，不在就绪队列里
+    current: Option<Pin<&'a Task>>,
+    ticks: u64,
+}
+
+impl<'a> Scheduler<'a> {
+    pub fn new() -> Self {
+        Scheduler {
+            run_queue: IntrusiveList::new(),
+            current: None,
+            ticks: 0,
+        }
+    }
+
+    // 挂到队尾；已经在队列里或者已经结束时返回false
+    // 当前任务可以在让出之前调度自己，yield_current时就不会再挂一次
+    pub fn schedule(&mut self, task: Pin<&'a Task>) -> bool {
+        if task.is_queued() || task.is_done() {
+            return false;
+        }
+        self.run_queue.push_back(task);
+        true
+    }
+
+    // 从就绪队列里摘掉，不在这个队列里时返回false
+    pub fn remove(&mut self, task: Pin<&Task>) -> bool {
+        self.run_queue.remove(task)
+    }
+
+    // 取出队头作为当前任务；上一个当前任务还没有让出时panic
+    pub fn pick_next(&mut self) -> Option<Pin<&'a Task>> {
+        assert!(self.current.is_none(), "current task has not yielded");
+        self.current = self.run_queue.pop_front();
+        self.current
+    }
+
+    pub fn current(&self) -> Option<Pin<&'a Task>> {
+        self.current
+    }
+
+    // 当前任务回到队尾
+    pub fn yield_current(&mut self) {
+        if let Some(task) = self.current.take() {
+            self.schedule(task);
+        }
+    }
+
+    // 当前任务离开CPU但不回到队列，等别人再schedule它
+    pub fn block_current(&mut self) -> Option<Pin<&'a Task>> {
+        self.current.take()
+    }
+
+    // 执行一步，没有就绪任务时返回false
+    pub fn run_once(&mut self) -> bool {
+        let Some(task) = self.pick_next() else {
+            return false;
+        };
+        let step = (task.step.borrow_mut())();
+        task.runs.set(task.runs.get() + 1);
+        self.ticks += 1;
+        match step {
+            Step::Yield => self.yield_current(),
+            Step::Block => {
+                self.block_current();
+            }
+            Step::Done => {
+                task.done.set(true);
+                self.block_current();
+            }
+        }
+        true
+    }
+
+    // 一直执行直到没有就绪任务或者执行了max_ticks步，返回执行的步数
+    pub fn run_until_idle(&mut self, max_ticks: usize) -> usize {
+        let mut ticks = 0;
+        while ticks < max_ticks && self.run_once() {
+            ticks += 1;
+        }
+        ticks
+    }
+
+    // 就绪队列的长度，不包括当前任务
+    pub fn len(&self) -> usize {
+        self.run_queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.run_queue.is_empty()
+    }
+
+    // 累计执行的步数
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    // 按执行顺序列出就绪任务
+    pub fn queued_ids(&self) -> Vec<u32> {
+        self.run_queue.iter().map(|task| task.id).collect()
+    }
+}
+
+impl Default for Scheduler<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for Scheduler<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Scheduler")
+            .field("current", &self.current.map(|task| task.id))
+            .field("queued", &self.queued_ids())
+            .field("ticks", &self.ticks)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+
+    type Trace = Rc<RefCell<Vec<u32>>>;
+
+    // 确定性测试用的任务: 每执行一步在trace里记下自己的id，执行steps步后结束
+    fn worker(id: u32, steps: u32, trace: &Trace) -> Pin<Box<Task>> {
+        let trace = Rc::clone(trace);
+        let mut left = steps;
+        Box::pin(Task::new(id, move || {
+            trace.borrow_mut().push(id);
+            left -= 1;
+            if left == 0 {
+                Step::Done
+            } else {
+                Step::Yield
+            }
+        }))
+    }
+
+    #[test]
+    fn round_robin_order() {
+        let trace = Trace::default();
+        let tasks = [worker(1, 3, &trace), worker(2, 1, &trace), worker(3, 2, &trace)];
+        let mut sched = Scheduler::new();
+        for task in &tasks {
+            assert!(sched.schedule(task.as_ref()));
+        }
+        assert_eq!(sched.run_until_idle(100), 6);
+        assert_eq!(*trace.borrow(), vec![1, 2, 3, 1, 3, 1]);
+        assert!(tasks.iter().all(|t| t.is_done() && !t.is_queued()));
+        assert_eq!(tasks[0].runs(), 3);
+        // 结束的任务不能再调度
+        assert!(!sched.schedule(tasks[1].as_ref()));
+        assert_eq!(sched.ticks(), 6);
+    }
+
+    #[test]
+    fn remove_from_middle() {
+        let trace = Trace::default();
+        let tasks: Vec<_> = (0..4).map(|id| worker(id, 2, &trace)).collect();
+        let mut sched = Scheduler::new();
+        for task in &tasks {
+            sched.schedule(task.as_ref());
+        }
+        assert!(!sched.schedule(tasks[0].as_ref()));
+        assert!(sched.remove(tasks[2].as_ref()));
+        assert!(!sched.remove(tasks[2].as_ref()));
+        assert_eq!(sched.queued_ids(), vec![0, 1, 3]);
+        sched.run_until_idle(100);
+        assert_eq!(*trace.borrow(), vec![0, 1, 3, 0, 1, 3]);
+        assert_eq!(tasks[2].runs(), 0);
+    }
+
+    #[test]
+    fn blocked_task_waits_for_wakeup() {
+        let trace = Trace::default();
+        let ready = Rc::new(Cell::new(false));
+        let waiter = {
+            let trace = Rc::clone(&trace);
+            let ready = Rc::clone(&ready);
+            Box::pin(Task::new(9, move || {
+                trace.borrow_mut().push(9);
+                if ready.get() {
+                    Step::Done
+                } else {
+                    Step::Block
+                }
+            }))
+        };
+        let other = worker(1, 3, &trace);
+        let mut sched = Scheduler::new();
+        sched.schedule(waiter.as_ref());
+        sched.schedule(other.as_ref());
+        assert_eq!(sched.run_until_idle(100), 4);
+        assert!(!waiter.is_done() && !waiter.is_queued());
+        // 外部事件到来，重新调度被阻塞的任务
+        ready.set(true);
+        assert!(sched.schedule(waiter.as_ref()));
+        sched.run_until_idle(100);
+        assert!(waiter.is_done());
+        assert_eq!(*trace.borrow(), vec![9, 1, 1, 1, 9]);
+    }
+
+    #[test]
+    fn manual_pick_and_yield() {
+        let a = Box::pin(Task::new(1, || Step::Yield));
+        let b = Box::pin(Task::new(2, || Step::Yield));
+        let mut sched = Scheduler::new();
+        sched.schedule(a.as_ref());
+        sched.schedule(b.as_ref());
+        assert_eq!(sched.pick_next().map(|t| t.id()), Some(1));
+        assert_eq!(sched.current().map(|t| t.id()), Some(1));
+        assert_eq!(sched.len(), 1);
+        // 当前任务先调度自己，yield时不会重复入队
+        assert!(sched.schedule(a.as_ref()));
+        sched.yield_current();
+        assert_eq!(sched.queued_ids(), vec![2, 1]);
+        assert_eq!(sched.pick_next().map(|t| t.id()), Some(2));
+        assert_eq!(sched.block_current().map(|t| t.id()), Some(2));
+        assert_eq!(sched.queued_ids(), vec![1]);
+        drop(sched);
+        assert!(!a.is_queued());
+    }
+
+    #[test]
+    #[should_panic(expected = "current task has not yielded")]
+    fn pick_without_yield_panics() {
+        let a = Box::pin(Task::new(1, || Step::Done));
+        let b = Box::pin(Task::new(2, || Step::Done));
+        let mut sched = Scheduler::new();
+        sched.schedule(a.as_ref());
+        sched.schedule(b.as_ref());
+        sched.pick_next();
+        sched.pick_next();
+    }
+}
@@ -0,0 +1,318 @@
+// 分段无锁MPMC队列(类似crossbeam的SegQueue)
+// 链表的每个节点是一个装SEGMENT_SIZE个槽的段，而不是一个元素，分配和回收的次数都摊薄到1/SEGMENT_SIZE:
+// - 每个段有两个计数器enq/deq，入队用fetch_add领一个槽位，写完元素后把槽标记为ready；
+//   出队用CAS把deq加一来领槽位，领到的槽如果写入者还没写完就自旋等一会儿
+// - 段写满时(领到的槽位>=SEGMENT_SIZE)，入队者负责挂上下一个段并推进tail
+// - 段里的槽全部被领走之后，出队者把head推进到下一个段，并把旧段交给回收策略R
+//   推进head之前先确保tail不再指向旧段，否则之后的入队者会读到已经释放的段
+// 和MsQueue一样，回收策略可以选epoch或危险指针，默认用epoch；同一时刻只需要保护一个段
+
+use std::cell::UnsafeCell;
+use std::marker::PhantomData;
+use std::mem::MaybeUninit;
+use std::ptr;
+
+use crate::instrument::mutation;
+use crate::reclaim::{Epoch, Reclaim, ReclaimGuard};
+use crate::sync::atomic::{AtomicBool, AtomicPtr, AtomicUsize, Ordering};
+
+pub const SEGMENT_SIZE: usize = 32;
+
+// 出队者等待槽被写完时先自旋这么多次
+const SPIN_LIMIT: u32 = 64;
+
+struct Slot<T> {
+    ready: AtomicBool,
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+
+struct Segment<T> {
+    slots: [Slot<T>; SEGMENT_SIZE],
+    // 已经领走的入队/出队槽位数；enq会因为段满后的重试超过SEGMENT_SIZE
+    enq: AtomicUsize,
+    deq: AtomicUsize,
+    next: AtomicPtr<Segment<T>>,
+}
+
+impl<T> Segment<T> {
+    fn alloc() -> *mut Self {
+        Box::into_raw(Box::new(Segment {
+            slots: std::array::from_fn(|_| Slot {
+                ready: AtomicBool::new(false),
+                value: UnsafeCell::new(MaybeUninit::uninit()),
+            }),
+            enq: AtomicUsize::new(0),
+            deq: AtomicUsize::new(0),
+            next: AtomicPtr::new(ptr::null_mut()),
+        }))
+    }
+}
+
+pub struct SegQueue<T, R: Reclaim = Epoch> {
+    head: AtomicPtr<Segment<T>>,
+    tail: AtomicPtr<Segment<T>>,
+    _boo: PhantomData<(Box<Segment<T>>, R)>,
+}
+
+impl<T> SegQueue<T> {
+    pub fn new() -> Self {
+        Self::empty()
+    }
+}
+
+impl<T, R: Reclaim> SegQueue<T, R> {
+    // 用指定的回收策略创建，例如SegQueue::with_reclaim(Hazard)
+    pub fn with_reclaim(_reclaim: R) -> Self {
+        Self::empty()
+    }
+
+    fn empty() -> Self {
+        let segment = Segment::alloc();
+        SegQueue {
+            head: AtomicPtr::new(segment),
+            tail: AtomicPtr::new(segment),
+            _boo: PhantomData,
+        }
+    }
+
+    pub fn push(&self, elem: T) {
+        let guard = R::pin();
+        loop {
+            let tail = guard.protect(0, &self.tail);
+            // SAFETY: tail受guard保护，段在被摘下之前tail已经离开了它
+            unsafe {
+                let index = (*tail).enq.fetch_add(1, Ordering::AcqRel);
+                if index < SEGMENT_SIZE {
+                    let slot = &(*tail).slots[index];
+                    (*slot.value.get()).write(elem);
+                    slot.ready.store(true, Ordering::Release);
+                    mutation!("push", index);
+                    return;
+                }
+                // 段满了，挂上下一个段(可能已经有别人挂好了)再推进tail
+                let mut next = (*tail).next.load(Ordering::Acquire);
+                if next.is_null() {
+                    let new = Segment::alloc();
+                    match (*tail).next.compare_exchange(ptr::null_mut(), new, Ordering::AcqRel, Ordering::Acquire) {
+                        Ok(_) => {
+                            mutation!("link_segment");
+                            next = new;
+                        }
+                        Err(actual) => {
+                            drop(Box::from_raw(new));
+                            next = actual;
+                        }
+                    }
+                }
+                let _ = self.tail.compare_exchange(tail, next, Ordering::AcqRel, Ordering::Relaxed);
+            }
+        }
+    }
+
+    pub fn pop(&self) -> Option<T> {
+        let guard = R::pin();
+        loop {
+            let head = guard.protect(0, &self.head);
+            unsafe {
+                let index = (*head).deq.load(Ordering::Acquire);
+                if index >= SEGMENT_SIZE {
+                    // 这个段的槽都被领走了，换到下一个段
+                    let next = (*head).next.load(Ordering::Acquire);
+                    if next.is_null() {
+                        return None;
+                    }
+                    let _ = self.tail.compare_exchange(head, next, Ordering::AcqRel, Ordering::Relaxed);
+                    if self
+                        .head
+                        .compare_exchange(head, next, Ordering::AcqRel, Ordering::Relaxed)
+                        .is_ok()
+                    {
+                        // 还在读这个段里的槽的出队者都受各自的guard保护
+                        mutation!("retire_segment");
+                        guard.retire(head);
+                    }
+                    continue;
+                }
+                // 入队者还没有领到这个槽位，队列为空
+                if index >= (*head).enq.load(Ordering::Acquire) {
+                    return None;
+                }
+                if (*head)
+                    .deq
+                    .compare_exchange(index, index + 1, Ordering::AcqRel, Ordering::Relaxed)
+                    .is_err()
+                {
+                    continue;
+                }
+                // 槽位已经归我们，入队者可能还没写完；等久了就让出CPU，入队者可能正好被换下去了
+                let slot = &(*head).slots[index];
+                let mut spins = 0;
+                while !slot.ready.load(Ordering::Acquire) {
+                    if spins < SPIN_LIMIT {
+                        std::hint::spin_loop();
+                        spins += 1;
+                    } else {
+                        std::thread::yield_now();
+                    }
+                }
+                mutation!("pop", index);
+                return Some((*slot.value.get()).assume_init_read());
+            }
+        }
+    }
+
+    // 并发情况下只代表调用那一刻的状态
+    pub fn is_empty(&self) -> bool {
+        let guard = R::pin();
+        let head = guard.protect(0, &self.head);
+        unsafe {
+            let index = (*head).deq.load(Ordering::Acquire);
+            if index >= SEGMENT_SIZE {
+                // 下一个段挂上去之前不会有新元素
+                (*head).next.load(Ordering::Acquire).is_null()
+            } else {
+                index >= (*head).enq.load(Ordering::Acquire)
+            }
+        }
+    }
+}
+
+impl<T, R: Reclaim> Default for SegQueue<T, R> {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+impl<T, R: Reclaim> Drop for SegQueue<T, R> {
+    fn drop(&mut self) {
+        // &mut self保证没有并发访问，领走的入队槽位都已经写完；
+        // 每个段里[deq, enq)之间的槽还有元素
+        let mut cur = self.head.load(Ordering::Relaxed);
+        while !cur.is_null() {
+            unsafe {
+                let mut segment = Box::from_raw(cur);
+                let start = segment.deq.load(Ordering::Relaxed);
+                let end = segment.enq.load(Ordering::Relaxed).min(SEGMENT_SIZE);
+                for slot in &mut segment.slots[start.min(end)..end] {
+                    slot.value.get_mut().assume_init_drop();
+                }
+                cur = segment.next.load(Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+unsafe impl<T: Send, R: Reclaim> Send for SegQueue<T, R> {}
+unsafe impl<T: Send, R: Reclaim> Sync for SegQueue<T, R> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reclaim::Hazard;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn fifo_across_segments() {
+        let q = SegQueue::new();
+        assert!(q.is_empty());
+        assert_eq!(q.pop(), None);
+        let n = 3 * SEGMENT_SIZE + 5;
+        for i in 0..n {
+            q.push(i);
+        }
+        assert!(!q.is_empty());
+        for i in 0..n {
+            assert_eq!(q.pop(), Some(i));
+        }
+        assert_eq!(q.pop(), None);
+        assert!(q.is_empty());
+        // 正好用完一个段之后继续使用
+        for i in 0..SEGMENT_SIZE {
+            q.push(i);
+        }
+        for i in 0..SEGMENT_SIZE {
+            assert_eq!(q.pop(), Some(i));
+        }
+        assert!(q.is_empty());
+        q.push(7);
+        assert_eq!(q.pop(), Some(7));
+    }
+
+    fn mpmc_stress<R: Reclaim>(reclaim: R) {
+        const PRODUCERS: usize = 4;
+        const CONSUMERS: usize = 4;
+        const PER_PRODUCER: usize = 5000;
+
+        let q = Arc::new(SegQueue::with_reclaim(reclaim));
+        let consumed = Arc::new(AtomicUsize::new(0));
+        let producers: Vec<_> = (0..PRODUCERS)
+            .map(|p| {
+                let q = Arc::clone(&q);
+                thread::spawn(move || {
+                    for i in 0..PER_PRODUCER {
+                        q.push((p, i));
+                    }
+                })
+            })
+            .collect();
+        let consumers: Vec<_> = (0..CONSUMERS)
+            .map(|_| {
+                let q = Arc::clone(&q);
+                let consumed = Arc::clone(&consumed);
+                thread::spawn(move || {
+                    // 同一个生产者的元素在任意消费者看来都必须是递增的(FIFO)
+                    let mut last = [None; PRODUCERS];
+                    let mut got = Vec::new();
+                    while consumed.load(Ordering::SeqCst) < PRODUCERS * PER_PRODUCER {
+                        if let Some((p, i)) = q.pop() {
+                            assert!(last[p].is_none_or(|prev| i > prev));
+                            last[p] = Some(i);
+                            got.push((p, i));
+                            consumed.fetch_add(1, Ordering::SeqCst);
+                        } else {
+                            thread::yield_now();
+                        }
+                    }
+                    got
+                })
+            })
+            .collect();
+
+        for p in producers {
+            p.join().unwrap();
+        }
+        let mut all: Vec<(usize, usize)> = consumers.into_iter().flat_map(|c| c.join().unwrap()).collect();
+        all.sort();
+        let expected: Vec<(usize, usize)> = (0..PRODUCERS)
+            .flat_map(|p| (0..PER_PRODUCER).map(move |i| (p, i)))
+            .collect();
+        assert_eq!(all, expected);
+        assert!(q.is_empty());
+    }
+
+    #[test]
+    fn mpmc_stress_epoch() {
+        mpmc_stress(Epoch);
+    }
+
+    #[test]
+    fn mpmc_stress_hazard() {
+        mpmc_stress(Hazard);
+    }
+
+    #[test]
+    fn drop_remaining_elements() {
+        let marker = Arc::new(());
+        let q = SegQueue::new();
+        for _ in 0..SEGMENT_SIZE + 10 {
+            q.push(Arc::clone(&marker));
+        }
+        for _ in 0..5 {
+            drop(q.pop());
+        }
+        assert_eq!(Arc::strong_count(&marker), SEGMENT_SIZE + 6);
+        drop(q);
+        assert_eq!(Arc::strong_count(&marker), 1);
+    }
+}
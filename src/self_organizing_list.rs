@@ -0,0 +1,262 @@
+// 自组织链表: 每次访问之后按启发式规则调整元素顺序，让常被访问的元素靠近表头，缩短之后的顺序查找
+// - MoveToFront: 命中的元素直接移到表头，对访问模式的变化反应最快，但偶尔访问一次冷门元素也会把它顶到最前面
+// - Transpose: 命中的元素和前一个元素交换，调整得慢但稳定
+// - Count: 每个元素记访问次数，链表始终按次数从高到低排列(次数相同的保持原来的先后)
+// 底层是simple_deque_3::List，调整顺序都通过节点句柄只改指针，不移动元素也不重新分配
+// 同时统计命中位置，用平均查找长度比较不同启发式的效果
+
+use std::fmt;
+
+use crate::simple_deque_3::{List, NodeHandle};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Heuristic {
+    MoveToFront,
+    Transpose,
+    Count,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AccessStats {
+    pub hits: u64,
+    pub misses: u64,
+    // 所有命中的位置(从0开始)之和
+    pub hit_positions: u64,
+}
+
+impl AccessStats {
+    // 命中时平均比较了几次(位置 + 1)，没有命中过时为0
+    pub fn average_cost(&self) -> f64 {
+        if self.hits == 0 {
+            0.0
+        } else {
+            (self.hit_positions + self.hits) as f64 / self.hits as f64
+        }
+    }
+}
+
+struct Entry<T> {
+    elem: T,
+    count: u64,
+}
+
+pub struct SelfOrganizingList<T> {
+    list: List<Entry<T>>,
+    heuristic: Heuristic,
+    stats: AccessStats,
+}
+
+impl<T> SelfOrganizingList<T> {
+    pub fn new(heuristic: Heuristic) -> Self {
+        SelfOrganizingList {
+            list: List::new(),
+            heuristic,
+            stats: AccessStats::default(),
+        }
+    }
+
+    pub fn heuristic(&self) -> Heuristic {
+        self.heuristic
+    }
+
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    // 新元素放在表尾，访问次数为0
+    pub fn push(&mut self, elem: T) {
+        self.list.push_back(Entry { elem, count: 0 });
+    }
+
+    // 顺序查找第一个满足pred的元素，命中后按启发式调整位置，返回调整之后的元素
+    pub fn access<P: FnMut(&T) -> bool>(&mut self, mut pred: P) -> Option<&mut T> {
+        let mut position = 0;
+        let mut cur = self.list.front_handle();
+        while let Some(handle) = cur {
+            // SAFETY: 句柄都是从这条链表遍历得到的
+            if pred(unsafe { &self.list.get_handle(handle).elem }) {
+                self.stats.hits += 1;
+                self.stats.hit_positions += position;
+                unsafe {
+                    self.promote(handle);
+                    return Some(&mut self.list.get_handle_mut(handle).elem);
+                }
+            }
+            cur = unsafe { self.list.next_handle(handle) };
+            position += 1;
+        }
+        self.stats.misses += 1;
+        None
+    }
+
+    /// # Safety
+    ///
+    /// handle必须指向这条链表里的节点
+    unsafe fn promote(&mut self, handle: NodeHandle<Entry<T>>) {
+        unsafe {
+            match self.heuristic {
+                Heuristic::MoveToFront => self.list.move_to_front(handle),
+                Heuristic::Transpose => {
+                    if let Some(prev) = self.list.prev_handle(handle) {
+                        self.list.move_before(handle, prev);
+                    }
+                }
+                Heuristic::Count => {
+                    let entry = self.list.get_handle_mut(handle);
+                    entry.count += 1;
+                    let count = entry.count;
+                    // 越过前面所有次数比它少的元素
+                    let mut target = handle;
+                    while let Some(prev) = self.list.prev_handle(target) {
+                        if self.list.get_handle(prev).count >= count {
+                            break;
+                        }
+                        target = prev;
+                    }
+                    self.list.move_before(handle, target);
+                }
+            }
+        }
+    }
+
+    // 查找但不调整顺序，也不计入统计
+    pub fn position<P: FnMut(&T) -> bool>(&self, pred: P) -> Option<usize> {
+        self.iter().position(pred)
+    }
+
+    pub fn remove<P: FnMut(&T) -> bool>(&mut self, mut pred: P) -> Option<T> {
+        let mut cur = self.list.front_handle();
+        while let Some(handle) = cur {
+            // SAFETY: 句柄都是从这条链表遍历得到的
+            unsafe {
+                if pred(&self.list.get_handle(handle).elem) {
+                    return Some(self.list.remove_handle(handle).elem);
+                }
+                cur = self.list.next_handle(handle);
+            }
+        }
+        None
+    }
+
+    pub fn stats(&self) -> AccessStats {
+        self.stats
+    }
+
+    pub fn reset_stats(&mut self) {
+        self.stats = AccessStats::default();
+    }
+
+    // 按当前顺序遍历
+    pub fn iter(&self) -> impl Iterator<Item = &T> + '_ {
+        self.list.iter().map(|entry| &entry.elem)
+    }
+
+    // Count启发式下记录的访问次数，按当前顺序
+    pub fn counts(&self) -> impl Iterator<Item = (&T, u64)> + '_ {
+        self.list.iter().map(|entry| (&entry.elem, entry.count))
+    }
+}
+
+impl<T> Extend<T> for SelfOrganizingList<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for elem in iter {
+            self.push(elem);
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for SelfOrganizingList<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SelfOrganizingList")
+            .field("heuristic", &self.heuristic)
+            .field("elems", &self.iter().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::XorShift;
+
+    fn list(heuristic: Heuristic) -> SelfOrganizingList<char> {
+        let mut list = SelfOrganizingList::new(heuristic);
+        list.extend("abcde".chars());
+        list
+    }
+
+    fn order(list: &SelfOrganizingList<char>) -> String {
+        list.iter().collect()
+    }
+
+    #[test]
+    fn move_to_front() {
+        let mut list = list(Heuristic::MoveToFront);
+        assert_eq!(list.access(|&c| c == 'd'), Some(&mut 'd'));
+        assert_eq!(order(&list), "dabce");
+        list.access(|&c| c == 'e');
+        list.access(|&c| c == 'd');
+        assert_eq!(order(&list), "deabc");
+        assert_eq!(list.access(|&c| c == 'z'), None);
+        let stats = list.stats();
+        assert_eq!((stats.hits, stats.misses, stats.hit_positions), (3, 1, 3 + 4 + 1));
+    }
+
+    #[test]
+    fn transpose() {
+        let mut list = list(Heuristic::Transpose);
+        list.access(|&c| c == 'd');
+        assert_eq!(order(&list), "abdce");
+        list.access(|&c| c == 'd');
+        list.access(|&c| c == 'd');
+        list.access(|&c| c == 'd');
+        assert_eq!(order(&list), "dabce");
+        // 已经在表头，不再移动
+        *list.access(|&c| c == 'd').unwrap() = 'D';
+        assert_eq!(order(&list), "Dabce");
+    }
+
+    #[test]
+    fn count_keeps_descending_frequency() {
+        let mut list = list(Heuristic::Count);
+        for c in "eeecccb".chars() {
+            list.access(|&x| x == c);
+        }
+        assert_eq!(order(&list), "ecbad");
+        // c追平e之后不会越过它，再多一次才越过
+        list.access(|&x| x == 'c');
+        assert_eq!(order(&list), "cebad");
+        let counts: Vec<_> = list.counts().map(|(c, n)| (*c, n)).collect();
+        assert_eq!(counts, vec![('c', 4), ('e', 3), ('b', 1), ('a', 0), ('d', 0)]);
+        assert_eq!(list.position(|&x| x == 'a'), Some(3));
+        assert_eq!(list.remove(|&x| x == 'e'), Some('e'));
+        assert_eq!(order(&list), "cbad");
+    }
+
+    #[test]
+    fn skewed_access_gets_cheaper() {
+        // 一小部分元素占了大部分访问，三种启发式的平均查找长度都应该远小于不调整时的(n + 1) / 2
+        const N: u64 = 100;
+        for heuristic in [Heuristic::MoveToFront, Heuristic::Transpose, Heuristic::Count] {
+            let mut rng = XorShift::new(696);
+            let mut list = SelfOrganizingList::new(heuristic);
+            list.extend(0..N);
+            for _ in 0..5000 {
+                let key = match rng.next_u64() % 10 {
+                    0 => rng.next_u64() % N,
+                    _ => N - 1 - rng.next_u64() % 5,
+                };
+                assert!(list.access(|&x| x == key).is_some());
+            }
+            let cost = list.stats().average_cost();
+            assert!(cost < 20.0, "{:?} average cost {}", heuristic, cost);
+            assert_eq!(list.len(), N as usize);
+            list.reset_stats();
+            assert_eq!(list.stats().average_cost(), 0.0);
+        }
+    }
+}
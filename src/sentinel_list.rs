@@ -0,0 +1,438 @@
+// 带哨兵节点的循环双向链表(教学用)
+// simple_deque_2/simple_deque_3里head、tail都可能为空，每个push/pop都要分"空链表"和"非空链表"两种情况，
+// 头尾两端的操作还各写一遍
+// 这里在堆上放一个不存元素的哨兵节点，所有节点连同哨兵首尾相连成一个环:
+// - 空链表就是哨兵自己指向自己，head = sentinel.next，tail = sentinel.prev
+// - 任何节点的prev/next永远非空，插入只有"插到a和b之间"一种情况，删除只有"把x从环里摘掉"一种情况
+// - push_front/push_back只是插入位置不同，没有任何关于空链表的分支
+// 哨兵只有链接字段，节点用repr(C)把同样的链接字段放在最前面，节点指针可以直接当链接指针用
+
+use std::fmt;
+use std::iter::FusedIterator;
+use std::marker::PhantomData;
+use std::ptr::{self, addr_of_mut};
+
+struct Links {
+    prev: *mut Links,
+    next: *mut Links,
+}
+
+#[repr(C)]
+struct Node<T> {
+    links: Links,
+    elem: T,
+}
+
+pub struct List<T> {
+    // 哨兵单独分配在堆上，链表移动时环里的指针不受影响
+    sentinel: *mut Links,
+    len: usize,
+    _boo: PhantomData<T>,
+}
+
+impl<T> List<T> {
+    pub fn new() -> Self {
+        let sentinel = Box::into_raw(Box::new(Links {
+            prev: ptr::null_mut(),
+            next: ptr::null_mut(),
+        }));
+        // 空环: 哨兵自己指向自己
+        unsafe {
+            (*sentinel).prev = sentinel;
+            (*sentinel).next = sentinel;
+        }
+        List {
+            sentinel,
+            len: 0,
+            _boo: PhantomData,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // 把新节点插到prev和next之间，整个链表唯一的插入逻辑
+    unsafe fn insert_between(&mut self, elem: T, prev: *mut Links, next: *mut Links) {
+        let node = Box::into_raw(Box::new(Node {
+            links: Links { prev, next },
+            elem,
+        })) as *mut Links;
+        unsafe {
+            (*prev).next = node;
+            (*next).prev = node;
+        }
+        self.len += 1;
+    }
+
+    // 把节点从环里摘下来并取出元素，整个链表唯一的删除逻辑
+    unsafe fn unlink(&mut self, link: *mut Links) -> T {
+        unsafe {
+            (*(*link).prev).next = (*link).next;
+            (*(*link).next).prev = (*link).prev;
+            self.len -= 1;
+            Box::from_raw(link as *mut Node<T>).elem
+        }
+    }
+
+    // link必须是元素节点(不能是哨兵)
+    unsafe fn elem<'a>(link: *mut Links) -> &'a mut T {
+        unsafe { &mut *addr_of_mut!((*(link as *mut Node<T>)).elem) }
+    }
+
+    fn head(&self) -> *mut Links {
+        unsafe { (*self.sentinel).next }
+    }
+
+    fn tail(&self) -> *mut Links {
+        unsafe { (*self.sentinel).prev }
+    }
+
+    pub fn push_front(&mut self, elem: T) {
+        unsafe { self.insert_between(elem, self.sentinel, self.head()) }
+    }
+
+    pub fn push_back(&mut self, elem: T) {
+        unsafe { self.insert_between(elem, self.tail(), self.sentinel) }
+    }
+
+    // 唯一需要的判断是"环里除了哨兵还有没有节点"
+    pub fn pop_front(&mut self) -> Option<T> {
+        (!self.is_empty()).then(|| unsafe { self.unlink(self.head()) })
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        (!self.is_empty()).then(|| unsafe { self.unlink(self.tail()) })
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        (!self.is_empty()).then(|| unsafe { &*Self::elem(self.head()) })
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        (!self.is_empty()).then(|| unsafe { &*Self::elem(self.tail()) })
+    }
+
+    pub fn front_mut(&mut self) -> Option<&mut T> {
+        (!self.is_empty()).then(|| unsafe { Self::elem(self.head()) })
+    }
+
+    pub fn back_mut(&mut self) -> Option<&mut T> {
+        (!self.is_empty()).then(|| unsafe { Self::elem(self.tail()) })
+    }
+
+    // 把other整个接到self尾部，O(1)；有哨兵之后同样不用考虑任何一方为空
+    pub fn append(&mut self, other: &mut Self) {
+        if other.is_empty() {
+            return;
+        }
+        unsafe {
+            let (first, last) = (other.head(), other.tail());
+            let tail = self.tail();
+            (*tail).next = first;
+            (*first).prev = tail;
+            (*last).next = self.sentinel;
+            (*self.sentinel).prev = last;
+            (*other.sentinel).next = other.sentinel;
+            (*other.sentinel).prev = other.sentinel;
+        }
+        self.len += std::mem::take(&mut other.len);
+    }
+
+    pub fn clear(&mut self) {
+        while self.pop_front().is_some() {}
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            head: self.head(),
+            tail: self.tail(),
+            len: self.len,
+            _boo: PhantomData,
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut {
+            head: self.head(),
+            tail: self.tail(),
+            len: self.len,
+            _boo: PhantomData,
+        }
+    }
+}
+
+impl<T> Drop for List<T> {
+    fn drop(&mut self) {
+        self.clear();
+        unsafe { drop(Box::from_raw(self.sentinel)) };
+    }
+}
+
+impl<T> Default for List<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for List<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: PartialEq> PartialEq for List<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Eq> Eq for List<T> {}
+
+impl<T: Clone> Clone for List<T> {
+    fn clone(&self) -> Self {
+        self.iter().cloned().collect()
+    }
+}
+
+impl<T> Extend<T> for List<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for elem in iter {
+            self.push_back(elem);
+        }
+    }
+}
+
+impl<T> FromIterator<T> for List<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = Self::new();
+        list.extend(iter);
+        list
+    }
+}
+
+unsafe impl<T: Send> Send for List<T> {}
+unsafe impl<T: Sync> Sync for List<T> {}
+
+// 迭代器用剩余长度判断结束，不会走到哨兵上
+pub struct Iter<'a, T> {
+    head: *mut Links,
+    tail: *mut Links,
+    len: usize,
+    _boo: PhantomData<&'a T>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        if self.len == 0 {
+            return None;
+        }
+        self.len -= 1;
+        let link = self.head;
+        unsafe {
+            self.head = (*link).next;
+            Some(&*List::<T>::elem(link))
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T> DoubleEndedIterator for Iter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        self.len -= 1;
+        let link = self.tail;
+        unsafe {
+            self.tail = (*link).prev;
+            Some(&*List::<T>::elem(link))
+        }
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+impl<T> FusedIterator for Iter<'_, T> {}
+
+impl<'a, T> IntoIterator for &'a List<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+pub struct IterMut<'a, T> {
+    head: *mut Links,
+    tail: *mut Links,
+    len: usize,
+    _boo: PhantomData<&'a mut T>,
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<&'a mut T> {
+        if self.len == 0 {
+            return None;
+        }
+        self.len -= 1;
+        let link = self.head;
+        unsafe {
+            self.head = (*link).next;
+            Some(List::<T>::elem(link))
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T> DoubleEndedIterator for IterMut<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        self.len -= 1;
+        let link = self.tail;
+        unsafe {
+            self.tail = (*link).prev;
+            Some(List::<T>::elem(link))
+        }
+    }
+}
+
+impl<T> ExactSizeIterator for IterMut<'_, T> {}
+impl<T> FusedIterator for IterMut<'_, T> {}
+
+impl<'a, T> IntoIterator for &'a mut List<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> IterMut<'a, T> {
+        self.iter_mut()
+    }
+}
+
+pub struct IntoIter<T>(List<T>);
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.0.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.0.len, Some(self.0.len))
+    }
+}
+
+impl<T> DoubleEndedIterator for IntoIter<T> {
+    fn next_back(&mut self) -> Option<T> {
+        self.0.pop_back()
+    }
+}
+
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
+impl<T> IntoIterator for List<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 有哨兵之后整个结构只剩一条不变量: 从哨兵出发沿next走len+1步回到哨兵，且每一步都满足 x.next.prev == x
+    // 对比simple_deque_3::check_invariants要分别检查head/tail为空、head.prev和tail.next为None等情况
+    fn check_ring<T>(list: &List<T>) {
+        let mut link = list.sentinel;
+        for _ in 0..=list.len {
+            unsafe {
+                let next = (*link).next;
+                assert!(!next.is_null());
+                assert_eq!((*next).prev, link);
+                link = next;
+            }
+        }
+        assert_eq!(link, list.sentinel);
+    }
+
+    #[test]
+    fn empty_ring_points_to_itself() {
+        let list: List<i32> = List::new();
+        unsafe {
+            assert_eq!((*list.sentinel).next, list.sentinel);
+            assert_eq!((*list.sentinel).prev, list.sentinel);
+        }
+        check_ring(&list);
+        assert_eq!(list.front(), None);
+    }
+
+    #[test]
+    fn both_ends_share_one_code_path() {
+        let mut list = List::new();
+        // 空链表上的第一次push和之后的push走的是同一段代码
+        list.push_back(2);
+        check_ring(&list);
+        list.push_front(1);
+        list.push_back(3);
+        check_ring(&list);
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert_eq!(list.iter().rev().copied().collect::<Vec<_>>(), vec![3, 2, 1]);
+        *list.front_mut().unwrap() = 10;
+        *list.back_mut().unwrap() = 30;
+        assert_eq!(list.pop_front(), Some(10));
+        assert_eq!(list.pop_back(), Some(30));
+        // 删掉最后一个元素后自然回到空环，不需要特意重置head/tail
+        assert_eq!(list.pop_back(), Some(2));
+        check_ring(&list);
+        assert_eq!(list.pop_front(), None);
+        assert!(list.is_empty());
+    }
+
+    #[test]
+    fn append_with_empty_sides() {
+        let mut a: List<i32> = List::new();
+        let mut b: List<i32> = (1..=3).collect();
+        a.append(&mut b);
+        check_ring(&a);
+        check_ring(&b);
+        assert!(b.is_empty());
+        let mut c: List<i32> = (4..=5).collect();
+        a.append(&mut c);
+        a.append(&mut List::new());
+        check_ring(&a);
+        assert_eq!(a.into_iter().collect::<Vec<_>>(), vec![1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn iter_mut_and_drop() {
+        let mut list: List<String> = ["a", "b", "c"].iter().map(|s| s.to_string()).collect();
+        let refs: Vec<&mut String> = list.iter_mut().collect();
+        for r in refs {
+            r.push('!');
+        }
+        assert_eq!(format!("{list:?}"), r#"["a!", "b!", "c!"]"#);
+        assert_eq!(list.clone(), list);
+        let mut it = list.into_iter();
+        assert_eq!(it.next_back().as_deref(), Some("c!"));
+    }
+}
@@ -0,0 +1,596 @@
+// serde序列化，开启serde特性后crate里单线程的链表、栈、队列、映射、集合，以及所有缓存(包括sharded_lru)
+// 都能序列化再原样读回来；无锁/并发的队列和栈(写出时内容还在变)、各种堆、graph/big_uint这类建在链表上的专用结构不在此列
+// 链表的指针结构不会写出去，只写内容:
+// - 序列类型写成元素序列，顺序就是iter()的顺序；读回时按这个顺序重新放进去，得到的容器和原来相等
+//   栈的iter()从栈顶开始，读回时倒着push；ring从游标处开始，读回后游标还在第一个元素上
+// - 映射写成map，集合写成序列，interval_list写成区间序列
+// - static_list的容量是类型的一部分，读回时元素比容量多就报错
+// - rope写成字符串，byte_chain写成字节串，分块方式不保留
+// - versioned_list只写当前版本的内容，历史版本不保留
+// - lru_cache写成{capacity, entries}，entries从最久没用到最近使用，依次put回去就恢复了使用顺序；
+//   lfu_cache还要保留访问次数，放在lfu_cache.rs里；sharded_lru写成{capacity, shards, entries}，放在sharded_lru.rs里
+// - simple_deque_1、simple_stack_1和min_stack::MinQueue没有对外的迭代器，实现写在各自的文件里
+//   simple_stack_1写成{capacity, elems}，保留可选的容量上限
+// 这里读回时只走公开的插入方法，不信任输入里的任何结构信息，重复的键按插入语义覆盖
+
+use std::fmt;
+use std::marker::PhantomData;
+
+use serde::de::{Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::ser::{Serialize, Serializer};
+
+// 共用的读取逻辑: 从序列里逐个读出元素，交给push放进init
+// 只开persistent特性时用不到这两个函数
+#[cfg_attr(not(any(feature = "safe", feature = "unsafe-impls")), allow(dead_code))]
+pub(crate) fn deserialize_seq<'de, D, T, C>(
+    deserializer: D,
+    init: C,
+    push: impl FnMut(&mut C, T),
+) -> Result<C, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    struct SeqVisitor<T, C, F> {
+        init: C,
+        push: F,
+        marker: PhantomData<fn() -> T>,
+    }
+
+    impl<'de, T: Deserialize<'de>, C, F: FnMut(&mut C, T)> Visitor<'de> for SeqVisitor<T, C, F> {
+        type Value = C;
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("a sequence")
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(mut self, mut seq: A) -> Result<C, A::Error> {
+            while let Some(elem) = seq.next_element()? {
+                (self.push)(&mut self.init, elem);
+            }
+            Ok(self.init)
+        }
+    }
+
+    deserializer.deserialize_seq(SeqVisitor {
+        init,
+        push,
+        marker: PhantomData,
+    })
+}
+
+// 映射版本，insert收到的是一对键值
+#[cfg_attr(not(any(feature = "safe", feature = "unsafe-impls")), allow(dead_code))]
+pub(crate) fn deserialize_map<'de, D, K, V, C>(
+    deserializer: D,
+    init: C,
+    insert: impl FnMut(&mut C, K, V),
+) -> Result<C, D::Error>
+where
+    D: Deserializer<'de>,
+    K: Deserialize<'de>,
+    V: Deserialize<'de>,
+{
+    struct MapVisitor<K, V, C, F> {
+        init: C,
+        insert: F,
+        marker: PhantomData<fn() -> (K, V)>,
+    }
+
+    impl<'de, K, V, C, F> Visitor<'de> for MapVisitor<K, V, C, F>
+    where
+        K: Deserialize<'de>,
+        V: Deserialize<'de>,
+        F: FnMut(&mut C, K, V),
+    {
+        type Value = C;
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("a map")
+        }
+
+        fn visit_map<A: MapAccess<'de>>(mut self, mut map: A) -> Result<C, A::Error> {
+            while let Some((key, value)) = map.next_entry()? {
+                (self.insert)(&mut self.init, key, value);
+            }
+            Ok(self.init)
+        }
+    }
+
+    deserializer.deserialize_map(MapVisitor {
+        init,
+        insert,
+        marker: PhantomData,
+    })
+}
+
+// 下面的宏和traits里的写法一样: [泛型参数] 类型 => 放入元素的方法;
+// 序列化用iter()，反序列化从default()出发逐个调用这个方法
+macro_rules! impl_serde_seq {
+    ($($(#[$attr:meta])* [$($gen:tt)*] $ty:ty => $push:ident;)*) => {$(
+        $(#[$attr])*
+        impl<$($gen)*> Serialize for $ty
+        where
+            T: Serialize,
+        {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.collect_seq(self.iter())
+            }
+        }
+
+        $(#[$attr])*
+        impl<'de, $($gen)*> Deserialize<'de> for $ty
+        where
+            T: Deserialize<'de>,
+        {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                deserialize_seq(deserializer, <$ty>::default(), |list, elem| {
+                    <$ty>::$push(list, elem);
+                })
+            }
+        }
+    )*};
+}
+
+macro_rules! impl_serde_map {
+    ($($(#[$attr:meta])* [$($gen:tt)*] $ty:ty => $insert:ident;)*) => {$(
+        $(#[$attr])*
+        impl<$($gen)*> Serialize for $ty
+        where
+            K: Serialize,
+            V: Serialize,
+        {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.collect_map(self.iter())
+            }
+        }
+
+        $(#[$attr])*
+        impl<'de, $($gen)*> Deserialize<'de> for $ty
+        where
+            K: Deserialize<'de>,
+            V: Deserialize<'de>,
+        {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                deserialize_map(deserializer, <$ty>::default(), |map, key, value| {
+                    <$ty>::$insert(map, key, value);
+                })
+            }
+        }
+    )*};
+}
+
+// 栈: 写出去是从栈顶到栈底，读回来先收集再倒着压栈
+macro_rules! impl_serde_stack {
+    ($($(#[$attr:meta])* [$($gen:tt)*] $ty:ty => $push:ident;)*) => {$(
+        $(#[$attr])*
+        impl<$($gen)*> Serialize for $ty
+        where
+            T: Serialize,
+        {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.collect_seq(self.iter())
+            }
+        }
+
+        $(#[$attr])*
+        impl<'de, $($gen)*> Deserialize<'de> for $ty
+        where
+            T: Deserialize<'de>,
+        {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let elems = Vec::<T>::deserialize(deserializer)?;
+                let mut stack = <$ty>::default();
+                for elem in elems.into_iter().rev() {
+                    <$ty>::$push(&mut stack, elem);
+                }
+                Ok(stack)
+            }
+        }
+    )*};
+}
+
+impl_serde_seq! {
+    #[cfg(feature = "safe")]
+    [T] crate::gap_buffer::GapBuffer<T> => insert;
+    #[cfg(feature = "unsafe-impls")]
+    [T] crate::simple_deque_2::List<T> => push_back;
+    #[cfg(feature = "unsafe-impls")]
+    [T, L: crate::simple_deque_3::NodeLayout] crate::simple_deque_3::List<T, L> => push_back;
+    #[cfg(feature = "unsafe-impls")]
+    [T] crate::sentinel_list::List<T> => push_back;
+    #[cfg(feature = "unsafe-impls")]
+    [T] crate::xor_list::XorList<T> => push_back;
+    #[cfg(feature = "unsafe-impls")]
+    [T] crate::arena_list::ArenaList<T> => push_back;
+    #[cfg(feature = "unsafe-impls")]
+    [T] crate::slab_list::SlabList<T> => push_back;
+    #[cfg(feature = "unsafe-impls")]
+    [T, const N: usize] crate::small_list::SmallList<T, N> => push_back;
+    #[cfg(feature = "unsafe-impls")]
+    [T, const K: usize] crate::unrolled_list::UnrolledList<T, K> => push_back;
+    #[cfg(feature = "unsafe-impls")]
+    [T] crate::indexable_skip_list::IndexableSkipList<T> => push_back;
+    #[cfg(feature = "unsafe-impls")]
+    [T] crate::tombstone_list::TombstoneList<T> => push_back;
+    #[cfg(feature = "unsafe-impls")]
+    [T: Ord] crate::sorted_list::SortedList<T> => insert;
+    #[cfg(feature = "unsafe-impls")]
+    [T: Ord] crate::skip_list_set::SkipListSet<T> => insert;
+    #[cfg(feature = "unsafe-impls")]
+    [T: std::hash::Hash + Eq + Clone] crate::linked_hash_set::LinkedHashSet<T> => insert;
+    #[cfg(feature = "unsafe-impls")]
+    [T: Ord + Copy] crate::interval_list::IntervalList<T> => insert;
+}
+
+impl_serde_map! {
+    #[cfg(feature = "safe")]
+    [K: std::hash::Hash + Eq, V, H: std::hash::BuildHasher + Default]
+        crate::chained_hash_map::ChainedHashMap<K, V, H> => insert;
+    #[cfg(feature = "unsafe-impls")]
+    [K: Ord, V] crate::skip_list_map::SkipListMap<K, V> => insert;
+    #[cfg(feature = "unsafe-impls")]
+    [K: std::hash::Hash + Eq + Clone, V] crate::linked_hash_map::LinkedHashMap<K, V> => insert;
+}
+
+impl_serde_stack! {
+    #[cfg(feature = "safe")]
+    [T] crate::simple_stack_2::List<T> => push;
+    #[cfg(feature = "safe")]
+    [T: Ord + Clone] crate::min_stack::MinStack<T> => push;
+}
+
+// 持久化链表: prepend返回新链表，cow_list的FromIterator本身就保持顺序
+#[cfg(feature = "persistent")]
+mod persistent {
+    use super::*;
+    use crate::cow_list::CowList;
+    use crate::simple_stack_3;
+    use crate::versioned_list::VersionedList;
+
+    impl<T: Serialize> Serialize for simple_stack_3::List<T> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.collect_seq(self.iter())
+        }
+    }
+
+    impl<'de, T: Deserialize<'de>> Deserialize<'de> for simple_stack_3::List<T> {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let elems = Vec::<T>::deserialize(deserializer)?;
+            Ok(elems
+                .into_iter()
+                .rev()
+                .fold(simple_stack_3::List::new(), |list, elem| list.prepend(elem)))
+        }
+    }
+
+    impl<T: Serialize> Serialize for CowList<T> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.collect_seq(self.iter())
+        }
+    }
+
+    impl<'de, T: Deserialize<'de>> Deserialize<'de> for CowList<T> {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            Ok(Vec::<T>::deserialize(deserializer)?.into_iter().collect())
+        }
+    }
+
+    // 读回来的只有一个版本
+    impl<T: Serialize> Serialize for VersionedList<T> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.collect_seq(self.iter())
+        }
+    }
+
+    impl<'de, T: Deserialize<'de>> Deserialize<'de> for VersionedList<T> {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let elems = Vec::<T>::deserialize(deserializer)?;
+            let mut list = VersionedList::new();
+            for elem in elems.into_iter().rev() {
+                list.push_front(elem);
+            }
+            Ok(list)
+        }
+    }
+}
+
+// 这几个放入元素的方法签名不统一，不走上面的宏
+#[cfg(feature = "unsafe-impls")]
+mod unsafe_impls {
+    use serde::de::Error;
+
+    use super::*;
+    use crate::byte_chain::ByteChain;
+    use crate::ring::Ring;
+    use crate::rope::Rope;
+    use crate::static_list::StaticList;
+
+    impl<T: Serialize> Serialize for Ring<T> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.collect_seq(self.iter())
+        }
+    }
+
+    // extend把元素依次排在游标之后，游标停在第一个元素上
+    impl<'de, T: Deserialize<'de>> Deserialize<'de> for Ring<T> {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            deserialize_seq(deserializer, Ring::new(), |ring, elem| ring.extend(Some(elem)))
+        }
+    }
+
+    impl<T: Serialize, const N: usize> Serialize for StaticList<T, N> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.collect_seq(self.iter())
+        }
+    }
+
+    // 装满之后多出来的元素不能丢掉，读完整个序列后报错
+    impl<'de, T: Deserialize<'de>, const N: usize> Deserialize<'de> for StaticList<T, N> {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let (list, overflow) = deserialize_seq(deserializer, (StaticList::new(), false), |(list, overflow), elem| {
+                *overflow |= list.push_back(elem).is_err();
+            })?;
+            if overflow {
+                return Err(D::Error::custom(format_args!("more than {N} elements")));
+            }
+            Ok(list)
+        }
+    }
+
+    impl Serialize for Rope {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.collect_str(self)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Rope {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            Ok(Rope::from(String::deserialize(deserializer)?))
+        }
+    }
+
+    impl Serialize for ByteChain {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_bytes(&self.to_vec())
+        }
+    }
+
+    // 二进制格式给的是字节串，json之类给的是数字序列，两种都接受
+    impl<'de> Deserialize<'de> for ByteChain {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            struct BytesVisitor;
+
+            impl<'de> Visitor<'de> for BytesVisitor {
+                type Value = ByteChain;
+
+                fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                    f.write_str("a byte string")
+                }
+
+                fn visit_bytes<E: Error>(self, bytes: &[u8]) -> Result<ByteChain, E> {
+                    Ok(ByteChain::from(bytes))
+                }
+
+                fn visit_byte_buf<E: Error>(self, bytes: Vec<u8>) -> Result<ByteChain, E> {
+                    Ok(ByteChain::from(bytes))
+                }
+
+                fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<ByteChain, A::Error> {
+                    let mut bytes = Vec::new();
+                    while let Some(byte) = seq.next_element()? {
+                        bytes.push(byte);
+                    }
+                    Ok(ByteChain::from(bytes))
+                }
+            }
+
+            deserializer.deserialize_byte_buf(BytesVisitor)
+        }
+    }
+}
+
+#[cfg(feature = "unsafe-impls")]
+mod lru {
+    use std::hash::Hash;
+
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use crate::lru_cache::LruCache;
+
+    #[derive(Serialize)]
+    struct CacheRef<E> {
+        capacity: usize,
+        entries: E,
+    }
+
+    #[derive(Deserialize)]
+    struct CacheOwned<K, V> {
+        capacity: usize,
+        entries: Vec<(K, V)>,
+    }
+
+    // 从最久没用到最近使用
+    struct Entries<'a, K, V>(&'a LruCache<K, V>);
+
+    impl<K: Serialize, V: Serialize> Serialize for Entries<'_, K, V> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.collect_seq(self.0.iter().rev())
+        }
+    }
+
+    impl<K: Serialize + Hash + Eq + Clone, V: Serialize> Serialize for LruCache<K, V> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            CacheRef {
+                capacity: self.capacity(),
+                entries: Entries(self),
+            }
+            .serialize(serializer)
+        }
+    }
+
+    impl<'de, K, V> Deserialize<'de> for LruCache<K, V>
+    where
+        K: Deserialize<'de> + Hash + Eq + Clone,
+        V: Deserialize<'de>,
+    {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let owned = CacheOwned::<K, V>::deserialize(deserializer)?;
+            if owned.capacity == 0 {
+                return Err(D::Error::custom("capacity must be positive"));
+            }
+            if owned.entries.len() > owned.capacity {
+                return Err(D::Error::custom("more entries than capacity"));
+            }
+            let mut cache = LruCache::with_reserved(owned.capacity, owned.entries.len());
+            for (key, value) in owned.entries {
+                cache.put(key, value);
+            }
+            Ok(cache)
+        }
+    }
+}
+
+#[cfg(all(test, feature = "safe", feature = "persistent", feature = "unsafe-impls"))]
+mod tests {
+    use std::fmt::Debug;
+
+    use serde::de::DeserializeOwned;
+
+    use super::*;
+    use crate::byte_chain::ByteChain;
+    use crate::interval_list::IntervalList;
+    use crate::lru_cache::LruCache;
+    use crate::ring::Ring;
+    use crate::rope::Rope;
+    use crate::skip_list_map::SkipListMap;
+    use crate::static_list::StaticList;
+    use crate::unrolled_list::UnrolledList;
+    use crate::versioned_list::VersionedList;
+    use crate::{cow_list, simple_deque_3, simple_stack_2, simple_stack_3};
+
+    fn round_trip<C: Serialize + DeserializeOwned>(value: &C) -> (String, C) {
+        let json = serde_json::to_string(value).unwrap();
+        let back = serde_json::from_str(&json).unwrap();
+        (json, back)
+    }
+
+    fn check<C: Serialize + DeserializeOwned + PartialEq + Debug>(value: C, expected: &str) {
+        let (json, back) = round_trip(&value);
+        assert_eq!(json, expected);
+        assert_eq!(back, value);
+    }
+
+    #[test]
+    fn sequences() {
+        check((1..=3).collect::<simple_deque_3::List<_>>(), "[1,2,3]");
+        check((1..=5).collect::<UnrolledList<_, 2>>(), "[1,2,3,4,5]");
+        check(simple_deque_3::List::<u8>::new(), "[]");
+        check((1..=3).collect::<cow_list::CowList<_>>(), "[1,2,3]");
+        let mut list = StaticList::<_, 4>::new();
+        list.push_back(2).unwrap();
+        list.push_front(1).unwrap();
+        check(list, "[1,2]");
+    }
+
+    #[test]
+    fn static_list_rejects_overflow() {
+        assert!(serde_json::from_str::<StaticList<i32, 2>>("[1,2]").is_ok());
+        let err = serde_json::from_str::<StaticList<i32, 2>>("[1,2,3]").unwrap_err();
+        assert!(err.to_string().contains("more than 2 elements"));
+    }
+
+    #[test]
+    fn ring_keeps_cursor() {
+        let mut ring: Ring<_> = (1..=4).collect();
+        ring.rotate(2);
+        let (json, back) = round_trip(&ring);
+        assert_eq!(json, "[3,4,1,2]");
+        assert_eq!(back.current(), Some(&3));
+        assert!(back.iter().eq(ring.iter()));
+    }
+
+    #[test]
+    fn intervals() {
+        let set: IntervalList<u32> = [5..8, 1..3, 3..4].into_iter().collect();
+        let (json, back) = round_trip(&set);
+        assert_eq!(json, r#"[{"start":1,"end":4},{"start":5,"end":8}]"#);
+        assert!(back.iter().eq(set.iter()));
+        // 输入不一定满足不变式，按insert的语义合并、丢掉空区间
+        let back: IntervalList<u32> =
+            serde_json::from_str(r#"[{"start":5,"end":8},{"start":6,"end":9},{"start":2,"end":2}]"#).unwrap();
+        back.assert_invariants();
+        assert_eq!(back.iter().collect::<Vec<_>>(), [&(5..9)]);
+    }
+
+    #[test]
+    fn text_and_bytes() {
+        let mut rope = Rope::from("héllo");
+        rope.insert(5, " wörld");
+        check(rope, r#""héllo wörld""#);
+        let mut chain = ByteChain::from(&b"ab"[..]);
+        chain.push_chunk(b"c".to_vec());
+        check(chain, "[97,98,99]");
+    }
+
+    #[test]
+    fn versioned_list_keeps_current() {
+        let mut list = VersionedList::new();
+        list.push_front(2);
+        let old = list.push_front(1);
+        list.pop_front();
+        list.checkout(old);
+        let (json, back) = round_trip(&list);
+        assert_eq!(json, "[1,2]");
+        assert!(back.iter().eq(list.iter()));
+        assert_eq!(back.version_count(), 3);
+    }
+
+    #[test]
+    fn stacks_keep_top() {
+        let mut stack = simple_stack_2::List::new();
+        stack.push(1);
+        stack.push(2);
+        let (json, mut back) = round_trip(&stack);
+        assert_eq!(json, "[2,1]");
+        assert_eq!(back.pop(), Some(2));
+        assert_eq!(back.pop(), Some(1));
+
+        let stack = simple_stack_3::List::new().prepend(1).prepend(2);
+        let (json, back) = round_trip(&stack);
+        assert_eq!(json, "[2,1]");
+        assert!(back.iter().eq(stack.iter()));
+    }
+
+    #[test]
+    fn maps() {
+        let map: SkipListMap<String, i32> = [("b".to_string(), 2), ("a".to_string(), 1)].into_iter().collect();
+        check(map, r#"{"a":1,"b":2}"#);
+    }
+
+    #[test]
+    fn lru_keeps_recency() {
+        let mut cache = LruCache::new(3);
+        cache.put("a", 1);
+        cache.put("b", 2);
+        cache.put("c", 3);
+        cache.get("a");
+        let json = serde_json::to_string(&cache).unwrap();
+        assert_eq!(json, r#"{"capacity":3,"entries":[["b",2],["c",3],["a",1]]}"#);
+        let mut back: LruCache<String, i32> = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.capacity(), 3);
+        assert_eq!(back.pop_lru(), Some(("b".to_string(), 2)));
+        assert!(serde_json::from_str::<LruCache<String, i32>>(r#"{"capacity":0,"entries":[]}"#).is_err());
+        let overfull = r#"{"capacity":1,"entries":[["a",1],["b",2]]}"#;
+        assert!(serde_json::from_str::<LruCache<String, i32>>(overfull).is_err());
+    }
+
+    // 输入里的capacity只是上限，不能拿来预先分配
+    #[test]
+    fn lru_huge_capacity() {
+        let json = r#"{"capacity":1000000000000000000,"entries":[[1,2]]}"#;
+        let cache: LruCache<u32, u32> = serde_json::from_str(json).unwrap();
+        assert_eq!((cache.capacity(), cache.len()), (1_000_000_000_000_000_000, 1));
+    }
+}
@@ -0,0 +1,437 @@
+// 分片的并发LRU缓存
+// 一把锁保护整个LruCache时，所有线程的get都要排队(LRU的get也要改链表，不能用读写锁并发读)
+// 这里按key的哈希把缓存切成N个互不相干的分片，每个分片是一个Mutex<LruCache>，
+// 不同分片上的操作完全并行，竞争降为原来的1/N
+// 代价是LRU只在分片内部精确: 淘汰的是"该分片里"最久没用的项，总容量按分片均分
+// 每个分片单独统计命中、未命中、插入和淘汰次数，方便观察热点是否集中在少数分片上
+
+use std::borrow::Borrow;
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::{BuildHasher, Hash};
+use std::ops::AddAssign;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use crate::lru_cache::LruCache;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ShardStats {
+    pub len: usize,
+    pub capacity: usize,
+    pub hits: u64,
+    pub misses: u64,
+    pub inserts: u64,
+    // 因为容量不够被挤出去的项，不包括主动evict的
+    pub evictions: u64,
+}
+
+impl AddAssign for ShardStats {
+    fn add_assign(&mut self, rhs: ShardStats) {
+        self.len += rhs.len;
+        self.capacity += rhs.capacity;
+        self.hits += rhs.hits;
+        self.misses += rhs.misses;
+        self.inserts += rhs.inserts;
+        self.evictions += rhs.evictions;
+    }
+}
+
+struct Shard<K, V> {
+    lru: LruCache<K, V>,
+    stats: ShardStats,
+}
+
+pub struct ShardedLru<K, V, S = RandomState> {
+    shards: Box<[Mutex<Shard<K, V>>]>,
+    hasher: S,
+}
+
+impl<K: Hash + Eq + Clone, V> ShardedLru<K, V, RandomState> {
+    // 总容量capacity均分到shards个分片上(向上取整)；任一参数为0时panic
+    pub fn new(capacity: usize, shards: usize) -> Self {
+        Self::with_hasher(capacity, shards, RandomState::new())
+    }
+}
+
+impl<K: Hash + Eq + Clone, V, S: BuildHasher> ShardedLru<K, V, S> {
+    pub fn with_hasher(capacity: usize, shards: usize, hasher: S) -> Self {
+        assert!(capacity > 0, "capacity must be positive");
+        assert!(shards > 0, "shard count must be positive");
+        let per_shard = capacity.div_ceil(shards);
+        ShardedLru {
+            shards: (0..shards)
+                .map(|_| {
+                    Mutex::new(Shard {
+                        lru: LruCache::new(per_shard),
+                        stats: ShardStats::default(),
+                    })
+                })
+                .collect(),
+            hasher,
+        }
+    }
+
+    fn shard<Q: Hash + ?Sized>(&self, key: &Q) -> MutexGuard<'_, Shard<K, V>> {
+        let index = (self.hasher.hash_one(key) % self.shards.len() as u64) as usize;
+        lock(&self.shards[index])
+    }
+
+    // 命中时刷新使用时间，把值交给f处理，避免在锁外持有引用
+    pub fn get_with<Q, R>(&self, key: &Q, f: impl FnOnce(&V) -> R) -> Option<R>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let mut shard = self.shard(key);
+        match shard.lru.get(key).map(f) {
+            Some(out) => {
+                shard.stats.hits += 1;
+                Some(out)
+            }
+            None => {
+                shard.stats.misses += 1;
+                None
+            }
+        }
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+        V: Clone,
+    {
+        self.get_with(key, V::clone)
+    }
+
+    // 不刷新使用时间，也不计入命中统计
+    pub fn contains<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.shard(key).lru.contains(key)
+    }
+
+    // 插入或更新，返回同一个key的旧值；分片满时淘汰该分片里最久没用的项
+    pub fn insert(&self, key: K, value: V) -> Option<V> {
+        let mut shard = self.shard(&key);
+        let shard = &mut *shard;
+        if !shard.lru.contains(&key) && shard.lru.len() == shard.lru.capacity() {
+            shard.lru.pop_lru();
+            shard.stats.evictions += 1;
+        }
+        shard.stats.inserts += 1;
+        shard.lru.put(key, value)
+    }
+
+    // 主动移除一项
+    pub fn evict<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.shard(key).lru.remove(key)
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+impl<K: Hash + Eq + Clone, V, S> ShardedLru<K, V, S> {
+    pub fn shard_count(&self) -> usize {
+        self.shards.len()
+    }
+
+    // 并发情况下各分片不是在同一时刻读到的
+    pub fn len(&self) -> usize {
+        self.shards.iter().map(|s| lock(s).lru.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.shards.iter().map(|s| lock(s).lru.capacity()).sum()
+    }
+
+    // 清空所有分片，统计数据保留
+    pub fn clear(&self) {
+        for shard in self.shards.iter() {
+            lock(shard).lru.clear();
+        }
+    }
+
+    pub fn shard_stats(&self) -> Vec<ShardStats> {
+        self.shards
+            .iter()
+            .map(|shard| {
+                let shard = lock(shard);
+                ShardStats {
+                    len: shard.lru.len(),
+                    capacity: shard.lru.capacity(),
+                    ..shard.stats
+                }
+            })
+            .collect()
+    }
+
+    // 所有分片的合计
+    pub fn stats(&self) -> ShardStats {
+        let mut total = ShardStats::default();
+        for stats in self.shard_stats() {
+            total += stats;
+        }
+        total
+    }
+}
+
+impl<K: Hash + Eq + Clone, V, S> fmt::Debug for ShardedLru<K, V, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let stats = self.stats();
+        f.debug_struct("ShardedLru")
+            .field("shards", &self.shard_count())
+            .field("len", &stats.len)
+            .field("capacity", &stats.capacity)
+            .finish()
+    }
+}
+
+// serde: 写成{capacity, shards, entries}，capacity是各分片容量的合计，
+// entries逐个分片写出，每个分片内部从最久没用到最近使用，和lru_cache的格式一致
+// 读回时用新的哈希器重新分片，所以只有原来同一分片里的项之间保持使用顺序；
+// 某个分片分到的项超过它的容量时按LRU淘汰，读回的项数可能比写出时少
+// 统计数据不写出去，读回后从零开始
+#[cfg(feature = "serde")]
+mod serde_impl {
+    use std::hash::{BuildHasher, Hash};
+    use std::sync::Mutex;
+
+    use serde::de::Error;
+    use serde::ser::SerializeSeq;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::{lock, Shard, ShardStats, ShardedLru};
+    use crate::lru_cache::LruCache;
+
+    // 分片是一次性分配好的，输入里的分片数要有个上限
+    const MAX_SHARDS: usize = 1 << 16;
+
+    #[derive(Serialize)]
+    struct CacheRef<E> {
+        capacity: usize,
+        shards: usize,
+        entries: E,
+    }
+
+    #[derive(Deserialize)]
+    struct CacheOwned<K, V> {
+        capacity: usize,
+        shards: usize,
+        entries: Vec<(K, V)>,
+    }
+
+    struct Entries<'a, K, V, S>(&'a ShardedLru<K, V, S>);
+
+    // 每次只锁一个分片，并发修改时各分片不是在同一时刻写出的
+    impl<K: Serialize, V: Serialize, S> Serialize for Entries<'_, K, V, S> {
+        fn serialize<Ser: Serializer>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error> {
+            let mut seq = serializer.serialize_seq(None)?;
+            for shard in self.0.shards.iter() {
+                for entry in lock(shard).lru.iter().rev() {
+                    seq.serialize_element(&entry)?;
+                }
+            }
+            seq.end()
+        }
+    }
+
+    impl<K: Serialize + Hash + Eq + Clone, V: Serialize, S> Serialize for ShardedLru<K, V, S> {
+        fn serialize<Ser: Serializer>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error> {
+            CacheRef {
+                capacity: self.capacity(),
+                shards: self.shard_count(),
+                entries: Entries(self),
+            }
+            .serialize(serializer)
+        }
+    }
+
+    impl<'de, K, V, S> Deserialize<'de> for ShardedLru<K, V, S>
+    where
+        K: Deserialize<'de> + Hash + Eq + Clone,
+        V: Deserialize<'de>,
+        S: BuildHasher + Default,
+    {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let CacheOwned { capacity, shards, entries } = CacheOwned::<K, V>::deserialize(deserializer)?;
+            if capacity == 0 {
+                return Err(D::Error::custom("capacity must be positive"));
+            }
+            if shards == 0 || shards > capacity.min(MAX_SHARDS) {
+                return Err(D::Error::custom("invalid shard count"));
+            }
+            if entries.len() > capacity {
+                return Err(D::Error::custom("more entries than capacity"));
+            }
+            // capacity来自输入，每个分片只按平均分到的项数预留
+            let per_shard = capacity.div_ceil(shards);
+            let reserved = entries.len().div_ceil(shards);
+            let cache = ShardedLru {
+                shards: (0..shards)
+                    .map(|_| {
+                        Mutex::new(Shard {
+                            lru: LruCache::with_reserved(per_shard, reserved),
+                            stats: ShardStats::default(),
+                        })
+                    })
+                    .collect(),
+                hasher: S::default(),
+            };
+            for (key, value) in entries {
+                cache.shard(&key).lru.put(key, value);
+            }
+            Ok(cache)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn single_shard_is_a_plain_lru() {
+        let cache = ShardedLru::new(2, 1);
+        assert_eq!(cache.insert("a", 1), None);
+        assert_eq!(cache.insert("b", 2), None);
+        assert_eq!(cache.get("a"), Some(1));
+        // b最久没用，被挤出去
+        assert_eq!(cache.insert("c", 3), None);
+        assert!(!cache.contains("b"));
+        assert_eq!(cache.insert("a", 10), Some(1));
+        assert_eq!(cache.evict("c"), Some(3));
+        assert_eq!(cache.evict("c"), None);
+        assert_eq!(cache.get_with("a", |v| v * 2), Some(20));
+        assert_eq!(cache.get("b"), None);
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses, stats.inserts, stats.evictions), (2, 1, 4, 1));
+        assert_eq!((stats.len, stats.capacity), (1, 2));
+    }
+
+    #[test]
+    fn capacity_is_split_across_shards() {
+        let cache: ShardedLru<u32, u32> = ShardedLru::new(10, 4);
+        assert_eq!(cache.shard_count(), 4);
+        assert_eq!(cache.capacity(), 12);
+        for i in 0..1000 {
+            cache.insert(i, i);
+        }
+        let shards = cache.shard_stats();
+        assert!(shards.iter().all(|s| s.len == 3 && s.capacity == 3));
+        assert_eq!(shards.iter().map(|s| s.evictions).sum::<u64>(), 1000 - 12);
+        assert_eq!(cache.len(), 12);
+        // 每个分片里留下的都是最后插入的那几个
+        for i in 0..1000 - 100 {
+            assert!(!cache.contains(&i));
+        }
+        cache.clear();
+        assert!(cache.is_empty());
+        assert_eq!(cache.stats().inserts, 1000);
+    }
+
+    #[test]
+    #[should_panic(expected = "shard count must be positive")]
+    fn zero_shards_panics() {
+        let _: ShardedLru<u8, u8> = ShardedLru::new(4, 0);
+    }
+
+    #[test]
+    fn concurrent_access() {
+        const THREADS: u64 = 8;
+        const OPS: u64 = 5000;
+
+        let cache = Arc::new(ShardedLru::new(256, 16));
+        let handles: Vec<_> = (0..THREADS)
+            .map(|t| {
+                let cache = Arc::clone(&cache);
+                thread::spawn(move || {
+                    for i in 0..OPS {
+                        let key = (i * 7 + t) % 512;
+                        match cache.get(&key) {
+                            // 值永远是key的两倍，不会读到别的key的值
+                            Some(v) => assert_eq!(v, key * 2),
+                            None => {
+                                cache.insert(key, key * 2);
+                            }
+                        }
+                        if i % 97 == 0 {
+                            cache.evict(&key);
+                        }
+                    }
+                })
+            })
+            .collect();
+        for h in handles {
+            h.join().unwrap();
+        }
+        let stats = cache.stats();
+        assert_eq!(stats.hits + stats.misses, THREADS * OPS);
+        assert_eq!(stats.inserts, stats.misses);
+        assert!(stats.len <= stats.capacity);
+        assert!(cache.shard_stats().iter().all(|s| s.len <= s.capacity));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let cache = ShardedLru::new(8, 1);
+        cache.insert("a", 1);
+        cache.insert("b", 2);
+        cache.insert("c", 3);
+        cache.get("a");
+        let json = serde_json::to_string(&cache).unwrap();
+        assert_eq!(json, r#"{"capacity":8,"shards":1,"entries":[["b",2],["c",3],["a",1]]}"#);
+        let back: ShardedLru<String, i32> = serde_json::from_str(&json).unwrap();
+        assert_eq!((back.capacity(), back.shard_count(), back.len()), (8, 1, 3));
+        assert_eq!(back.stats().inserts, 0);
+        // 使用顺序保留了下来，b最先被挤出去
+        for i in 0..6 {
+            back.insert(i.to_string(), i);
+        }
+        assert!(!back.contains("b"));
+        assert!(back.contains("c") && back.contains("a"));
+
+        // 多个分片时读回的每一项都还在，容量按原来的分片数均分
+        let cache: ShardedLru<u32, u32> = ShardedLru::new(40, 4);
+        for i in 0..8 {
+            cache.insert(i, i * 10);
+        }
+        let json = serde_json::to_string(&cache).unwrap();
+        let back: ShardedLru<u32, u32> = serde_json::from_str(&json).unwrap();
+        assert_eq!((back.capacity(), back.shard_count(), back.len()), (40, 4, 8));
+        assert!((0..8).all(|i| back.get(&i) == Some(i * 10)));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_rejects_bad_input() {
+        for bad in [
+            r#"{"capacity":0,"shards":1,"entries":[]}"#,
+            r#"{"capacity":4,"shards":0,"entries":[]}"#,
+            r#"{"capacity":2,"shards":3,"entries":[]}"#,
+            r#"{"capacity":1,"shards":1,"entries":[[1,1],[2,2]]}"#,
+            r#"{"capacity":1000000000000000000,"shards":1000000000000000000,"entries":[]}"#,
+        ] {
+            assert!(serde_json::from_str::<ShardedLru<u32, u32>>(bad).is_err(), "{bad}");
+        }
+        // 输入里的capacity只是上限，不能拿来预先分配
+        let json = r#"{"capacity":1000000000000000000,"shards":4,"entries":[[1,2]]}"#;
+        let cache: ShardedLru<u32, u32> = serde_json::from_str(json).unwrap();
+        assert_eq!((cache.capacity(), cache.get(&1)), (1_000_000_000_000_000_000, Some(2)));
+    }
+}
@@ -0,0 +1,396 @@
+// 多线程共享的持久化栈: simple_stack_3的不可变节点 + treiber_stack的CAS循环
+// 节点是Arc<Node>，发布之后不再修改；栈顶是一个AtomicPtr，持有栈顶节点的一个引用计数
+// - push/pop: 读栈顶 -> 构造新栈顶(push是新节点，pop是旧栈顶的next) -> CAS替换
+// - snapshot: 给当前栈顶加一次引用计数，O(1)得到一个不可变的栈，之后的push/pop都不影响它
+// 读栈顶和给它加引用计数之间，别的线程可能已经把它pop掉并减掉了计数，所以两步之间要pin住epoch，
+// 被换下的栈顶的那一个计数也交给epoch推迟到宽限期之后再减。
+// CAS期间当前线程自己持有旧栈顶的一个计数，节点不会被释放，地址也不会被复用，所以没有ABA问题
+// pop返回的是元素的拷贝: 弹出的节点可能还被快照共享着
+
+use std::fmt;
+use std::ptr;
+use std::sync::Arc;
+
+use crate::epoch;
+use crate::instrument::mutation;
+use crate::sync::atomic::{AtomicPtr, Ordering};
+
+type Link<T> = Option<Arc<Node<T>>>;
+
+struct Node<T> {
+    elem: T,
+    next: Link<T>,
+}
+
+// 逐个拆开不再被共享的节点，避免长链递归析构爆栈
+impl<T> Drop for Node<T> {
+    fn drop(&mut self) {
+        let mut next = self.next.take();
+        while let Some(node) = next {
+            match Arc::try_unwrap(node) {
+                Ok(mut node) => next = node.next.take(),
+                Err(_) => break,
+            }
+        }
+    }
+}
+
+fn into_raw<T>(link: Link<T>) -> *mut Node<T> {
+    link.map_or(ptr::null_mut(), |node| Arc::into_raw(node) as *mut Node<T>)
+}
+
+/// 给`ptr`指向的节点加一个引用计数并返回它
+///
+/// # Safety
+///
+/// `ptr`为null，或者来自`Arc::into_raw`且在调用期间不会被释放(比如调用者pin住了epoch)
+unsafe fn acquire<T>(ptr: *mut Node<T>) -> Link<T> {
+    (!ptr.is_null()).then(|| unsafe {
+        Arc::increment_strong_count(ptr);
+        Arc::from_raw(ptr)
+    })
+}
+
+/// 收回`into_raw`交出去的那一个计数
+///
+/// # Safety
+///
+/// `ptr`为null，或者来自`into_raw`且那一个计数还没有被收回过
+unsafe fn acquire_owned<T>(ptr: *mut Node<T>) -> Link<T> {
+    (!ptr.is_null()).then(|| unsafe { Arc::from_raw(ptr) })
+}
+
+pub struct SharedStack<T> {
+    head: AtomicPtr<Node<T>>,
+}
+
+impl<T> SharedStack<T> {
+    pub fn new() -> Self {
+        SharedStack {
+            head: AtomicPtr::new(ptr::null_mut()),
+        }
+    }
+
+    // 并发情况下结果只代表调用那一刻的状态
+    pub fn is_empty(&self) -> bool {
+        self.head.load(Ordering::Acquire).is_null()
+    }
+
+    // O(1)，只增加栈顶的引用计数
+    pub fn snapshot(&self) -> Snapshot<T> {
+        let _guard = epoch::pin();
+        let head = self.head.load(Ordering::Acquire);
+        // SAFETY: pin住期间被换下的栈顶不会减到0
+        Snapshot {
+            head: unsafe { acquire(head) },
+        }
+    }
+}
+
+impl<T: Send + Sync + 'static> SharedStack<T> {
+    pub fn push(&self, elem: T) {
+        let mut new = Arc::new(Node { elem, next: None });
+        let guard = epoch::pin();
+        let mut head = self.head.load(Ordering::Relaxed);
+        loop {
+            // SAFETY: head在guard期间有效；new还没有发布，只有当前线程持有
+            Arc::get_mut(&mut new).unwrap().next = unsafe { acquire(head) };
+            let raw = Arc::into_raw(new) as *mut Node<T>;
+            // Release保证其他线程通过Acquire读到新栈顶时，也能看到节点内容
+            match self
+                .head
+                .compare_exchange_weak(head, raw, Ordering::Release, Ordering::Acquire)
+            {
+                Ok(_) => {
+                    // 栈顶原来持有的那个计数换成了new.next持有的计数，多出的一个等宽限期后再减
+                    Self::retire(&guard, head);
+                    mutation!("push");
+                    return;
+                }
+                Err(actual) => {
+                    // SAFETY: raw没有发布出去，收回所有权；next里的计数是当前线程加的，直接丢掉
+                    new = unsafe { Arc::from_raw(raw) };
+                    Arc::get_mut(&mut new).unwrap().next = None;
+                    head = actual;
+                }
+            }
+        }
+    }
+
+    // 减掉栈顶曾经持有的一个计数，推迟到所有可能还在读它的线程unpin之后
+    fn retire(guard: &epoch::Guard, old: *mut Node<T>) {
+        if old.is_null() {
+            return;
+        }
+        // SAFETY: CAS成功之后栈顶原来持有的计数归当前线程
+        let old = unsafe { Arc::from_raw(old) };
+        guard.defer(move || drop(old));
+    }
+}
+
+impl<T: Clone + Send + Sync + 'static> SharedStack<T> {
+    pub fn pop(&self) -> Option<T> {
+        let guard = epoch::pin();
+        let mut head = self.head.load(Ordering::Acquire);
+        loop {
+            // SAFETY: head在guard期间有效，加一个计数之后由当前线程持有
+            let node = unsafe { acquire(head) }?;
+            let next = into_raw(node.next.clone());
+            match self
+                .head
+                .compare_exchange_weak(head, next, Ordering::AcqRel, Ordering::Acquire)
+            {
+                Ok(_) => {
+                    Self::retire(&guard, head);
+                    mutation!("pop");
+                    return Some(node.elem.clone());
+                }
+                Err(actual) => {
+                    // SAFETY: next没有发布出去，收回那一个计数
+                    drop(unsafe { acquire_owned(next) });
+                    head = actual;
+                }
+            }
+        }
+    }
+
+    pub fn peek(&self) -> Option<T> {
+        self.snapshot().peek().cloned()
+    }
+}
+
+impl<T> Default for SharedStack<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Drop for SharedStack<T> {
+    // &mut self保证没有并发访问，直接减掉栈顶的计数；快照共享的节点由快照自己负责
+    fn drop(&mut self) {
+        let head = self.head.load(Ordering::Relaxed);
+        // SAFETY: head来自into_raw，这是栈持有的那一个计数
+        drop(unsafe { acquire_owned(head) });
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for SharedStack<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.snapshot(), f)
+    }
+}
+
+// 节点不可变，元素会被多个线程共享读取，所以要求T: Send + Sync
+unsafe impl<T: Send + Sync> Send for SharedStack<T> {}
+unsafe impl<T: Send + Sync> Sync for SharedStack<T> {}
+
+// 某一时刻的整个栈，接口和simple_stack_3::List一样
+pub struct Snapshot<T> {
+    head: Link<T>,
+}
+
+impl<T> Snapshot<T> {
+    pub fn peek(&self) -> Option<&T> {
+        self.head.as_ref().map(|node| &node.elem)
+    }
+
+    pub fn tail(&self) -> Snapshot<T> {
+        Snapshot {
+            head: self.head.as_ref().and_then(|node| node.next.clone()),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.head.is_none()
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            next: self.head.as_deref(),
+        }
+    }
+
+    // 两个快照是否从栈顶开始就是同一批节点
+    pub fn ptr_eq(&self, other: &Snapshot<T>) -> bool {
+        match (&self.head, &other.head) {
+            (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+}
+
+impl<T> Clone for Snapshot<T> {
+    fn clone(&self) -> Self {
+        Snapshot {
+            head: self.head.clone(),
+        }
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Snapshot<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Snapshot<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+pub struct Iter<'a, T> {
+    next: Option<&'a Node<T>>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next.map(|node| {
+            self.next = node.next.as_deref();
+            &node.elem
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+    use std::sync::atomic::AtomicBool;
+    use std::thread;
+
+    #[test]
+    fn basics() {
+        let stack = SharedStack::new();
+        assert_eq!(stack.pop(), None);
+        stack.push(1);
+        stack.push(2);
+        stack.push(3);
+        assert_eq!(stack.peek(), Some(3));
+        assert_eq!(format!("{stack:?}"), "[3, 2, 1]");
+        assert_eq!(stack.pop(), Some(3));
+        assert_eq!(stack.pop(), Some(2));
+        stack.push(4);
+        assert_eq!(stack.pop(), Some(4));
+        assert_eq!(stack.pop(), Some(1));
+        assert!(stack.is_empty());
+    }
+
+    #[test]
+    fn snapshots_are_immutable_and_shared() {
+        let stack = SharedStack::new();
+        for i in 0..4 {
+            stack.push(i);
+        }
+        let before = stack.snapshot();
+        stack.pop();
+        stack.push(10);
+        let after = stack.snapshot();
+        assert_eq!(before.iter().copied().collect::<Vec<_>>(), vec![3, 2, 1, 0]);
+        assert_eq!(after.iter().copied().collect::<Vec<_>>(), vec![10, 2, 1, 0]);
+        // pop/push只换掉了栈顶，下面的节点两个快照共享
+        assert!(before.tail().ptr_eq(&after.tail()));
+        assert!(!before.ptr_eq(&after));
+        assert_eq!(before.tail().peek(), Some(&2));
+        // 快照在栈销毁后仍然有效
+        drop(stack);
+        assert_eq!(after.clone().iter().count(), 4);
+        assert!(SharedStack::<i32>::new().snapshot().is_empty());
+    }
+
+    #[test]
+    fn popped_elements_live_on_in_snapshots() {
+        let elem = Arc::new(5);
+        let stack = SharedStack::new();
+        stack.push(Arc::clone(&elem));
+        let snap = stack.snapshot();
+        let popped = stack.pop().unwrap();
+        assert!(Arc::ptr_eq(&popped, &elem));
+        drop(popped);
+        drop(stack);
+        assert_eq!(snap.peek().map(|x| **x), Some(5));
+        drop(snap);
+        // 栈顶原来那个计数在epoch推迟释放，刷几次之后元素只剩这里一个引用
+        while Arc::strong_count(&elem) > 1 {
+            epoch::pin().flush();
+            thread::yield_now();
+        }
+    }
+
+    #[test]
+    fn concurrent_push_pop() {
+        const THREADS: usize = 8;
+        const PER_THREAD: usize = 1000;
+
+        let stack = Arc::new(SharedStack::new());
+        let handles: Vec<_> = (0..THREADS)
+            .map(|t| {
+                let stack = Arc::clone(&stack);
+                thread::spawn(move || {
+                    let mut popped = Vec::new();
+                    for i in 0..PER_THREAD {
+                        stack.push(t * PER_THREAD + i);
+                        if i % 2 == 1 {
+                            popped.extend(stack.pop());
+                        }
+                    }
+                    popped
+                })
+            })
+            .collect();
+
+        let mut seen = HashSet::new();
+        for h in handles {
+            for x in h.join().unwrap() {
+                assert!(seen.insert(x), "{} popped twice", x);
+            }
+        }
+        while let Some(x) = stack.pop() {
+            assert!(seen.insert(x), "{} popped twice", x);
+        }
+        assert_eq!(seen.len(), THREADS * PER_THREAD);
+    }
+
+    #[test]
+    fn concurrent_snapshots_are_consistent() {
+        // 只有一个线程push递增的数，任何快照从栈顶往下都应该严格递减
+        let stack = Arc::new(SharedStack::new());
+        let done = Arc::new(AtomicBool::new(false));
+        let readers: Vec<_> = (0..3)
+            .map(|_| {
+                let stack = Arc::clone(&stack);
+                let done = Arc::clone(&done);
+                thread::spawn(move || {
+                    while !done.load(Ordering::Acquire) {
+                        let snap = stack.snapshot();
+                        let v: Vec<u64> = snap.iter().copied().collect();
+                        assert!(v.windows(2).all(|w| w[0] > w[1]));
+                    }
+                })
+            })
+            .collect();
+        let popper = {
+            let stack = Arc::clone(&stack);
+            let done = Arc::clone(&done);
+            thread::spawn(move || {
+                while !done.load(Ordering::Acquire) {
+                    stack.pop();
+                }
+            })
+        };
+        for i in 0..5000 {
+            stack.push(i);
+        }
+        done.store(true, Ordering::Release);
+        for r in readers {
+            r.join().unwrap();
+        }
+        popper.join().unwrap();
+    }
+}
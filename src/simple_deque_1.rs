@@ -8,10 +8,14 @@
 use std::rc::Rc;
 use std::cell::RefCell;
 
+use crate::error::ListError;
+use crate::instrument::mutation;
+use crate::metrics::Counters;
 
 pub struct List<T> {
     head: Link<T>,
     tail: Link<T>,
+    metrics: Counters,
 }
 
 type Link<T> = Option<Rc<RefCell<Node<T>>>>;
@@ -25,7 +29,7 @@ struct Node<T> {
 impl<T> Node<T> {
     fn new(elem: T) -> Rc<RefCell<Self>> {
         Rc::new(RefCell::new(Node {
-            elem: elem,
+            elem,
             prev: None,
             next: None,
         }))
@@ -34,7 +38,11 @@ impl<T> Node<T> {
 
 impl<T> List<T> {
     pub fn new() -> Self {
-        List { head: None, tail: None }
+        List {
+            head: None,
+            tail: None,
+            metrics: Counters::new(),
+        }
     }
 
     pub fn push_front(&mut self, elem: T) {
@@ -50,10 +58,14 @@ impl<T> List<T> {
                 self.head = Some(new_node);
             }
         }
+        self.metrics.alloc_push();
+        mutation!("push_front");
     }
 
     pub fn pop_front(&mut self) -> Option<T> {
         self.head.take().map(|old_head| {
+            self.metrics.pop();
+            mutation!("pop_front");
             if let Some(next) = old_head.borrow_mut().next.take() {
                 next.borrow_mut().prev.take();
                 self.head = Some(next);
@@ -64,13 +76,13 @@ impl<T> List<T> {
         })
     }
 
-    pub fn peek_front(&self) -> Option<std::cell::Ref<T>> {
+    pub fn peek_front(&self) -> Option<std::cell::Ref<'_, T>> {
         self.head.as_ref().map(|node| {
             std::cell::Ref::map(node.borrow(), |n| &n.elem)
         })
     }
 
-    pub fn peek_front_mut(&self) -> Option<std::cell::RefMut<T>> {
+    pub fn peek_front_mut(&self) -> Option<std::cell::RefMut<'_, T>> {
         self.head.as_ref().map(|node| {
             std::cell::RefMut::map(node.borrow_mut(), |n| &mut n.elem)
         })
@@ -89,10 +101,14 @@ impl<T> List<T> {
                 self.tail = Some(new_node);
             }
         }
+        self.metrics.alloc_push();
+        mutation!("push_back");
     }   
 
     pub fn pop_back(&mut self) -> Option<T> {
         self.tail.take().map(|old_tail| {
+            self.metrics.pop();
+            mutation!("pop_back");
             if let Some(prev) = old_tail.borrow_mut().prev.take() {
                 prev.borrow_mut().next.take();
                 self.tail = Some(prev);
@@ -103,18 +119,118 @@ impl<T> List<T> {
         })
     }
 
-    pub fn peek_back(&self) -> Option<std::cell::Ref<T>> {
+    pub fn peek_back(&self) -> Option<std::cell::Ref<'_, T>> {
         self.tail.as_ref().map(|node| {
             std::cell::Ref::map(node.borrow(), |n| &n.elem)
         })
     }
 
-    pub fn peek_back_mut(&self) -> Option<std::cell::RefMut<T>> {
+    pub fn peek_back_mut(&self) -> Option<std::cell::RefMut<'_, T>> {
         self.tail.as_ref().map(|node| {
             std::cell::RefMut::map(node.borrow_mut(), |n| &mut n.elem)
         })
     }
 
+    // 和peek_*_mut一样，但元素已经被借出(比如还拿着peek_front返回的Ref)时
+    // 返回BorrowConflict，而不是在RefCell::borrow_mut里panic
+    pub fn try_peek_front_mut(&self) -> Result<Option<std::cell::RefMut<'_, T>>, ListError> {
+        Self::try_borrow_elem_mut(self.head.as_ref())
+    }
+
+    pub fn try_peek_back_mut(&self) -> Result<Option<std::cell::RefMut<'_, T>>, ListError> {
+        Self::try_borrow_elem_mut(self.tail.as_ref())
+    }
+
+    fn try_borrow_elem_mut(
+        node: Option<&Rc<RefCell<Node<T>>>>,
+    ) -> Result<Option<std::cell::RefMut<'_, T>>, ListError> {
+        match node {
+            Some(node) => {
+                let node = node.try_borrow_mut().map_err(|_| ListError::BorrowConflict)?;
+                Ok(Some(std::cell::RefMut::map(node, |n| &mut n.elem)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    // 当前的操作计数快照，各项含义见metrics.rs
+    #[cfg(feature = "metrics")]
+    pub fn metrics(&self) -> crate::metrics::Metrics {
+        self.metrics.snapshot()
+    }
+
+    #[cfg(feature = "metrics")]
+    pub fn reset_metrics(&self) {
+        self.metrics.reset();
+    }
+}
+
+impl<T> Default for List<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// next和prev都是强引用，相邻节点之间是一对方向相反的Rc，所以每个节点(首尾另算表头)的rc都是2
+#[cfg(feature = "visualize")]
+impl<T: std::fmt::Debug> List<T> {
+    pub fn to_dot(&self) -> String {
+        use crate::visualize::{Dot, Edge};
+        let mut dot = Dot::new();
+        let root = dot.root("List");
+        let mut cur = self.head.clone();
+        let mut prev_name: Option<String> = None;
+        while let Some(rc) = cur {
+            let node = rc.borrow();
+            // 计数里要扣掉cur自己临时持有的这一份
+            let note = format!("rc={}", Rc::strong_count(&rc) - 1);
+            let (name, _) = dot.node(Rc::as_ptr(&rc), &node.elem, &note);
+            match &prev_name {
+                None => dot.edge(&root, &name, "head", Edge::Shared),
+                Some(prev) => dot.edge(prev, &name, "next", Edge::Shared),
+            }
+            if let Some(prev) = &node.prev {
+                let (to, _) = dot.node(Rc::as_ptr(prev), &prev.borrow().elem, "");
+                dot.edge(&name, &to, "prev", Edge::Shared);
+            }
+            prev_name = Some(name);
+            cur = node.next.clone();
+        }
+        if let Some(last) = &prev_name {
+            dot.edge(&root, last, "tail", Edge::Shared);
+        }
+        dot.finish()
+    }
+}
+
+// 没有迭代器(元素都在RefCell里)，序列化时沿next逐个借出来；格式和其他序列一样，见serde_impls
+#[cfg(feature = "serde")]
+impl<T: serde::Serialize> serde::Serialize for List<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeSeq;
+        let mut seq = serializer.serialize_seq(None)?;
+        let mut cur = self.head.clone();
+        while let Some(rc) = cur {
+            let node = rc.borrow();
+            seq.serialize_element(&node.elem)?;
+            cur = node.next.clone();
+        }
+        seq.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: serde::Deserialize<'de>> serde::Deserialize<'de> for List<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        crate::serde_impls::deserialize_seq(deserializer, List::new(), List::push_back)
+    }
+}
+
+// 相邻节点通过next/prev互相持有Rc，形成引用环，不手动拆开的话节点永远不会被释放
+impl<T> Drop for List<T> {
+    fn drop(&mut self) {
+        while self.pop_front().is_some() {}
+    }
 }
 
 pub struct IntoIter<T>(List<T>);
@@ -132,8 +248,11 @@ impl<T> DoubleEndedIterator for IntoIter<T> {
     }
 }
 
-impl<T> List<T> {
-    pub fn into_iter(self) -> IntoIter<T> {
+impl<T> IntoIterator for List<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
         IntoIter(self)
     }
 }
@@ -178,4 +297,84 @@ mod test {
         assert_eq!(iter.next_back(), None);
         assert_eq!(iter.next(), None);
     }
-}
\ No newline at end of file
+
+    // 相邻节点的Rc互相指着，List的Drop不拆开的话整条链都会泄漏
+    #[test]
+    fn drop_frees_every_node() {
+        use crate::alloc_counter::{assert_no_leaks, DropCounter};
+        let counter = DropCounter::new();
+        assert_no_leaks(|| {
+            let mut list = List::new();
+            for i in 0..10 {
+                list.push_back(counter.track(i));
+                list.push_front(counter.track(i));
+            }
+            assert_eq!(list.pop_back().map(|x| x.value), Some(9));
+        });
+        assert_eq!((counter.created(), counter.live()), (20, 0));
+    }
+
+    #[test]
+    fn try_peek_mut_reports_borrow_conflict() {
+        let mut list = List::new();
+        assert!(matches!(list.try_peek_front_mut(), Ok(None)));
+        list.push_back(1);
+        list.push_back(2);
+        {
+            let front = list.peek_front().unwrap();
+            assert!(matches!(list.try_peek_front_mut(), Err(ListError::BorrowConflict)));
+            *list.try_peek_back_mut().unwrap().unwrap() = 20;
+            assert_eq!(*front, 1);
+        }
+        *list.try_peek_front_mut().unwrap().unwrap() = 10;
+        assert_eq!(list.pop_front(), Some(10));
+        assert_eq!(list.pop_front(), Some(20));
+    }
+
+    #[cfg(feature = "visualize")]
+    #[test]
+    fn to_dot_draws_both_directions() {
+        let mut list = List::new();
+        for i in 1..=3 {
+            list.push_back(i);
+        }
+        let dot = list.to_dot();
+        for i in 1..=3 {
+            assert!(dot.contains(&format!("[label=\"{i}\\nrc=2\"]")));
+        }
+        assert_eq!(dot.matches("[label=\"next\"").count(), 2);
+        assert_eq!(dot.matches("[label=\"prev\"").count(), 2);
+        assert!(dot.contains("root0 -> n2 [label=\"tail\""));
+    }
+
+    #[cfg(feature = "metrics")]
+    #[test]
+    fn metrics_count_operations() {
+        use crate::metrics::Metrics;
+
+        let mut list = List::new();
+        list.push_front(1);
+        list.push_back(2);
+        list.push_back(3);
+        assert_eq!(list.pop_back(), Some(3));
+        assert_eq!(
+            list.metrics(),
+            Metrics { pushes: 3, pops: 1, traversal_steps: 0, allocations: 3 }
+        );
+    }
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let mut list = List::new();
+        list.push_back(2);
+        list.push_front(1);
+        list.push_back(3);
+        let json = serde_json::to_string(&list).unwrap();
+        assert_eq!(json, "[1,2,3]");
+        let mut back: List<i32> = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.pop_front(), Some(1));
+        assert_eq!(back.pop_back(), Some(3));
+        assert_eq!(back.pop_back(), Some(2));
+        assert_eq!(back.pop_back(), None);
+    }
+}
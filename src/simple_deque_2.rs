@@ -1,4 +1,531 @@
 // 一个还OK的unsafe单向队列
+// 在simple_stack_2的基础上多维护一个指向尾节点的裸指针，push_back和pop_front都是O(1)
+// 为了通过Miri的Stacked Borrows检查，所有节点只用裸指针互相引用:
+// 一旦把Box转成裸指针，就不再混用&mut和Box，全程裸指针操作直到重新装回Box释放
 
+use std::ptr::{self, NonNull};
 
+use crate::cycle;
+use crate::instrument::mutation;
+use crate::metrics::Counters;
+use crate::node_pool::NodePool;
 
+pub struct List<T> {
+    head: Link<T>,
+    tail: *mut Node<T>,
+    metrics: Counters,
+}
+
+type Link<T> = *mut Node<T>;
+
+struct Node<T> {
+    elem: T,
+    next: Link<T>,
+}
+
+impl<T> List<T> {
+    pub fn new() -> Self {
+        List {
+            head: ptr::null_mut(),
+            tail: ptr::null_mut(),
+            metrics: Counters::new(),
+        }
+    }
+
+    pub fn push_back(&mut self, elem: T) {
+        // SAFETY: new_tail来自Box::into_raw，tail为空或指向本链表的最后一个节点
+        unsafe {
+            let new_tail = Box::into_raw(Box::new(Node {
+                elem,
+                next: ptr::null_mut(),
+            }));
+
+            if !self.tail.is_null() {
+                (*self.tail).next = new_tail;
+            } else {
+                // 空队列，新节点同时也是头节点
+                self.head = new_tail;
+            }
+            self.tail = new_tail;
+        }
+        self.metrics.alloc_push();
+        mutation!("push_back");
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        // SAFETY: head非空时指向本链表独占的节点
+        unsafe {
+            if self.head.is_null() {
+                None
+            } else {
+                let head = Box::from_raw(self.head);
+                self.head = head.next;
+                if self.head.is_null() {
+                    // 取空了，tail不能继续指向已经释放的节点
+                    self.tail = ptr::null_mut();
+                }
+                self.metrics.pop();
+                mutation!("pop_front");
+                Some(head.elem)
+            }
+        }
+    }
+
+    // 从共享节点池里拿节点入队
+    pub fn push_back_pooled(&mut self, elem: T, pool: &mut NodePool<T>) {
+        let new_tail = pool
+            .alloc(Node {
+                elem,
+                next: ptr::null_mut(),
+            })
+            .as_ptr();
+        // SAFETY: 与push_back相同，池子里的块按Node<T>的布局分配，之后也能当作Box释放
+        unsafe {
+            if !self.tail.is_null() {
+                (*self.tail).next = new_tail;
+            } else {
+                self.head = new_tail;
+            }
+        }
+        self.tail = new_tail;
+        self.metrics.push();
+        mutation!("push_back_pooled");
+    }
+
+    // 出队后把节点内存还给池子
+    pub fn pop_front_pooled(&mut self, pool: &mut NodePool<T>) -> Option<T> {
+        if self.head.is_null() {
+            return None;
+        }
+        // SAFETY: head非空时指向本链表独占的节点，字段移出后只剩内存交给池子
+        unsafe {
+            let head = self.head;
+            self.head = (*head).next;
+            if self.head.is_null() {
+                self.tail = ptr::null_mut();
+            }
+            let elem = ptr::read(&(*head).elem);
+            pool.recycle(NonNull::new_unchecked(head));
+            self.metrics.pop();
+            mutation!("pop_front_pooled");
+            Some(elem)
+        }
+    }
+
+    pub fn peek(&self) -> Option<&T> {
+        unsafe { self.head.as_ref().map(|node| &node.elem) }
+    }
+
+    pub fn peek_mut(&mut self) -> Option<&mut T> {
+        unsafe { self.head.as_mut().map(|node| &mut node.elem) }
+    }
+
+    pub fn peek_back(&self) -> Option<&T> {
+        unsafe { self.tail.as_ref().map(|node| &node.elem) }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.head.is_null()
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        unsafe {
+            Iter {
+                next: self.head.as_ref(),
+                metrics: &self.metrics,
+            }
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        unsafe {
+            IterMut {
+                next: self.head.as_mut(),
+                metrics: &self.metrics,
+            }
+        }
+    }
+
+    // 当前的操作计数快照，各项含义见metrics.rs
+    #[cfg(feature = "metrics")]
+    pub fn metrics(&self) -> crate::metrics::Metrics {
+        self.metrics.snapshot()
+    }
+
+    #[cfg(feature = "metrics")]
+    pub fn reset_metrics(&self) {
+        self.metrics.reset();
+    }
+
+    // 只用于排查unsafe代码把next链改坏的情况，正常使用下永远是false
+    pub fn has_cycle(&self) -> bool {
+        cycle::has_cycle(self.first(), Self::successor)
+    }
+
+    // 环入口节点的下标
+    pub fn cycle_start(&self) -> Option<usize> {
+        cycle::floyd(self.first(), Self::successor).map(|c| c.start)
+    }
+
+    // 断开环上最后一个节点的next，让它成为新的尾节点；没有环时返回false
+    pub fn remove_cycle(&mut self) -> bool {
+        let Some(found) = cycle::brent(self.first(), Self::successor) else {
+            return false;
+        };
+        // SAFETY: 环上的节点都由本队列拥有，next都非空
+        unsafe {
+            let mut last = self.head;
+            for _ in 1..found.start + found.len {
+                last = (*last).next;
+            }
+            (*last).next = ptr::null_mut();
+            self.tail = last;
+        }
+        true
+    }
+
+    fn first(&self) -> Option<*const Node<T>> {
+        (!self.head.is_null()).then_some(self.head as *const Node<T>)
+    }
+
+    fn successor(node: *const Node<T>) -> Option<*const Node<T>> {
+        // SAFETY: 只在本队列的节点上调用
+        let next = unsafe { (*node).next };
+        (!next.is_null()).then_some(next as *const Node<T>)
+    }
+
+    // 测试用: 故意把尾节点的next指回第index个节点，制造一个环；越界时panic
+    #[cfg(test)]
+    pub(crate) fn link_tail_to(&mut self, index: usize) {
+        let mut target = self.head;
+        for _ in 0..index {
+            assert!(!target.is_null(), "index out of bounds");
+            target = unsafe { (*target).next };
+        }
+        assert!(!target.is_null(), "index out of bounds");
+        unsafe { (*self.tail).next = target };
+    }
+}
+
+impl<T> Default for List<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// 节点之间全是裸指针，画成虚线；tail从表头直接指向最后一个节点
+#[cfg(feature = "visualize")]
+impl<T: std::fmt::Debug> List<T> {
+    pub fn to_dot(&self) -> String {
+        use crate::visualize::{Dot, Edge};
+        let mut dot = Dot::new();
+        let root = dot.root("List");
+        let mut from = root.clone();
+        let mut label = "head";
+        let mut cur = self.head;
+        while !cur.is_null() {
+            // SAFETY: 从head沿next能走到的节点都归本链表所有，&self期间不会被释放
+            let node = unsafe { &*cur };
+            let (to, new) = dot.node(cur, &node.elem, "");
+            dot.edge(&from, &to, label, Edge::Raw);
+            // link_tail_to造出来的环，走回已经画过的节点就停
+            if !new {
+                break;
+            }
+            (from, label, cur) = (to, "next", node.next);
+        }
+        if !self.tail.is_null() {
+            // SAFETY: tail非空时指向本链表的最后一个节点
+            let (to, _) = dot.node(self.tail, unsafe { &(*self.tail).elem }, "");
+            dot.edge(&root, &to, "tail", Edge::Raw);
+        }
+        dot.finish()
+    }
+}
+
+impl<T> Drop for List<T> {
+    fn drop(&mut self) {
+        while self.pop_front().is_some() {}
+    }
+}
+
+pub struct IntoIter<T>(List<T>);
+
+impl<T> IntoIterator for List<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter(self)
+    }
+}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.pop_front()
+    }
+}
+
+pub struct Iter<'a, T> {
+    next: Option<&'a Node<T>>,
+    metrics: &'a Counters,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        unsafe {
+            self.next.map(|node| {
+                self.metrics.step();
+                self.next = node.next.as_ref();
+                &node.elem
+            })
+        }
+    }
+}
+
+pub struct IterMut<'a, T> {
+    next: Option<&'a mut Node<T>>,
+    metrics: &'a Counters,
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        unsafe {
+            self.next.take().map(|node| {
+                self.metrics.step();
+                self.next = node.next.as_mut();
+                &mut node.elem
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::List;
+
+    #[test]
+    fn basics() {
+        let mut list = List::new();
+
+        // 空队列
+        assert_eq!(list.pop_front(), None);
+
+        // 填充队列
+        list.push_back(1);
+        list.push_back(2);
+        list.push_back(3);
+
+        // 先进先出
+        assert_eq!(list.pop_front(), Some(1));
+        assert_eq!(list.pop_front(), Some(2));
+
+        // 再推入一些元素，确认tail没有被破坏
+        list.push_back(4);
+        list.push_back(5);
+
+        assert_eq!(list.pop_front(), Some(3));
+        assert_eq!(list.pop_front(), Some(4));
+
+        // 取空后tail需要重置
+        assert_eq!(list.pop_front(), Some(5));
+        assert_eq!(list.pop_front(), None);
+
+        list.push_back(6);
+        list.push_back(7);
+        assert_eq!(list.pop_front(), Some(6));
+        assert_eq!(list.pop_front(), Some(7));
+        assert_eq!(list.pop_front(), None);
+        assert!(list.is_empty());
+    }
+
+    #[test]
+    fn into_iter() {
+        let mut list = List::new();
+        list.push_back(1);
+        list.push_back(2);
+        list.push_back(3);
+
+        let mut iter = list.into_iter();
+        assert_eq!(iter.next(), Some(1));
+        assert_eq!(iter.next(), Some(2));
+        assert_eq!(iter.next(), Some(3));
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn iter() {
+        let mut list = List::new();
+        list.push_back(1);
+        list.push_back(2);
+        list.push_back(3);
+
+        let mut iter = list.iter();
+        assert_eq!(iter.next(), Some(&1));
+        assert_eq!(iter.next(), Some(&2));
+        assert_eq!(iter.next(), Some(&3));
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn iter_mut() {
+        let mut list = List::new();
+        list.push_back(1);
+        list.push_back(2);
+        list.push_back(3);
+
+        let mut iter = list.iter_mut();
+        assert_eq!(iter.next(), Some(&mut 1));
+        assert_eq!(iter.next(), Some(&mut 2));
+        assert_eq!(iter.next(), Some(&mut 3));
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn peek() {
+        let mut list = List::new();
+        assert!(list.peek().is_none());
+        assert!(list.peek_back().is_none());
+        list.push_back(1);
+        list.push_back(2);
+        assert_eq!(list.peek(), Some(&1));
+        assert_eq!(list.peek_back(), Some(&2));
+        *list.peek_mut().unwrap() = 10;
+        assert_eq!(list.pop_front(), Some(10));
+        assert_eq!(list.peek(), Some(&2));
+    }
+
+    // 交替使用各种借用方式，专门给Miri检查别名规则
+    #[test]
+    fn miri_food() {
+        let mut list = List::new();
+
+        list.push_back(1);
+        list.push_back(2);
+        list.push_back(3);
+
+        assert!(list.pop_front() == Some(1));
+        list.push_back(4);
+        assert!(list.pop_front() == Some(2));
+        list.push_back(5);
+
+        assert!(list.peek() == Some(&3));
+        list.push_back(6);
+        if let Some(x) = list.peek_mut() {
+            *x *= 10;
+        }
+        assert!(list.peek() == Some(&30));
+        assert!(list.pop_front() == Some(30));
+
+        for elem in list.iter_mut() {
+            *elem *= 100;
+        }
+
+        let mut iter = list.iter();
+        assert_eq!(iter.next(), Some(&400));
+        assert_eq!(iter.next(), Some(&500));
+        assert_eq!(iter.next(), Some(&600));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next(), None);
+
+        assert!(list.pop_front() == Some(400));
+        if let Some(x) = list.peek_mut() {
+            *x *= 10;
+        }
+        assert!(list.peek() == Some(&5000));
+        list.push_back(7);
+
+        // 剩下的元素交给Drop释放
+    }
+
+    #[test]
+    fn cycle_detection() {
+        let mut list = List::new();
+        assert!(!list.has_cycle());
+        assert!(!list.remove_cycle());
+        for i in 0..5 {
+            list.push_back(i);
+        }
+        assert!(!list.has_cycle());
+        assert_eq!(list.cycle_start(), None);
+
+        // 故意破坏: 4 -> 2
+        list.link_tail_to(2);
+        assert!(list.has_cycle());
+        assert_eq!(list.cycle_start(), Some(2));
+        assert!(list.remove_cycle());
+        assert!(!list.has_cycle());
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![0, 1, 2, 3, 4]);
+
+        // 自环，修复之后队列照常使用
+        list.link_tail_to(4);
+        assert_eq!(list.cycle_start(), Some(4));
+        assert!(list.remove_cycle());
+        list.push_back(5);
+        assert_eq!(list.peek_back(), Some(&5));
+        assert_eq!(list.into_iter().collect::<Vec<_>>(), vec![0, 1, 2, 3, 4, 5]);
+    }
+
+    // 池子预热之后，用池子入队出队不再分配
+    #[test]
+    fn pooled_steady_state_does_not_allocate() {
+        use crate::alloc_counter::{measure, AllocStats};
+        use crate::node_pool::NodePool;
+        let mut pool = NodePool::new();
+        let mut list = List::new();
+        for i in 0..10 {
+            list.push_back_pooled(i, &mut pool);
+        }
+        while list.pop_front_pooled(&mut pool).is_some() {}
+        let ((), stats) = measure(|| {
+            for round in 0..100 {
+                for i in 0..10 {
+                    list.push_back_pooled(round * 10 + i, &mut pool);
+                }
+                while list.pop_front_pooled(&mut pool).is_some() {}
+            }
+        });
+        assert_eq!(stats, AllocStats::default());
+    }
+
+    #[cfg(feature = "visualize")]
+    #[test]
+    fn to_dot_stops_at_cycle() {
+        let mut list = List::new();
+        for i in 0..3 {
+            list.push_back(i);
+        }
+        assert!(list.to_dot().contains("root0 -> n2 [label=\"tail\", style=dashed];"));
+        list.link_tail_to(1);
+        let dot = list.to_dot();
+        // 尾节点的next指回1，画出这条边之后就停下
+        assert!(dot.contains("n2 -> n1 [label=\"next\", style=dashed];"));
+        assert_eq!(dot.matches("[label=\"next\"").count(), 3);
+        assert!(list.remove_cycle());
+    }
+
+    #[cfg(feature = "metrics")]
+    #[test]
+    fn metrics_count_operations() {
+        use crate::metrics::Metrics;
+
+        let mut list = List::new();
+        for i in 0..5 {
+            list.push_back(i);
+        }
+        // 队列只能从头部遍历，访问队尾要走完整条链
+        assert_eq!(list.iter().last(), Some(&4));
+        list.iter_mut().take(2).for_each(|x| *x += 10);
+        assert_eq!(list.pop_front(), Some(10));
+        assert_eq!(
+            list.metrics(),
+            Metrics { pushes: 5, pops: 1, traversal_steps: 7, allocations: 5 }
+        );
+    }
+}
@@ -1,45 +1,2301 @@
 // 一个产品级的unsafe的双端队列
+// 使用NonNull裸指针实现双向链接，配合PhantomData让List<T>对T协变并参与drop检查
+// 数据结构的核心不变量:
+// 1. 空链表时head和tail都是None，len为0
+// 2. head.prev和tail.next永远是None
+// 3. 任意相邻节点a->b满足 a.next == b 且 b.prev == a
+// 4. 从head沿next走len步正好走到tail
 
-use std::mem;
+use std::alloc::{self, Layout};
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::collections::{LinkedList, VecDeque};
+use std::fmt::{self, Debug};
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use std::ptr::NonNull;
 
-pub struct List<T> {
-    head: Link<T>,
-    tail: Link<T>, // NEW!
+use crate::cycle;
+use crate::error::ListError;
+use crate::instrument::{mutation, mutation_span};
+use crate::metrics::Counters;
+use crate::node_pool::NodePool;
+
+// 固定元素地址的PinnedList，见pinned.rs
+pub mod pinned;
+// 操作录制与回放，见trace.rs
+#[cfg(feature = "trace")]
+pub mod trace;
+
+pub struct List<T, L: NodeLayout = Compact> {
+    head: Link<T, L>,
+    tail: Link<T, L>,
+    len: usize,
+    metrics: Counters,
+    // 告诉编译器我们逻辑上拥有T，用于drop检查和协变
+    _boo: PhantomData<T>,
 }
 
-type Link<T> = Option<Box<Node<T>>>;
+type Link<T, L> = Option<NonNull<Node<T, L>>>;
 
-struct Node<T> {
+struct Node<T, L: NodeLayout> {
+    // 长度为0的数组不占空间，只把节点的对齐抬高到L::Align
+    _align: [L::Align; 0],
+    prev: Link<T, L>,
+    next: Link<T, L>,
     elem: T,
-    next: Link<T>,
+}
+
+// 节点布局策略，决定每个堆节点的对齐方式
+// Compact是默认布局，节点按自身字段自然对齐，内存占用最小
+// CacheAligned把每个节点对齐并填充到64字节缓存行: 相邻节点不会共享缓存行(避免伪共享)，
+// 并且元素不超过 64 - 2 * size_of::<usize>() 字节时，elem和prev/next指针一次缓存行读取就能全部拿到
+pub trait NodeLayout {
+    type Align: Copy;
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Compact;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheAligned;
+
+#[doc(hidden)]
+#[derive(Debug, Clone, Copy)]
+#[repr(align(64))]
+pub struct CacheLine;
+
+impl NodeLayout for Compact {
+    type Align = ();
+}
+
+impl NodeLayout for CacheAligned {
+    type Align = CacheLine;
 }
 
 impl<T> List<T> {
     pub fn new() -> Self {
+        Self::empty()
+    }
+}
+
+impl<T, L: NodeLayout> List<T, L> {
+    // 指定节点布局创建空链表，例如 List::with_layout(CacheAligned)
+    pub fn with_layout(_layout: L) -> Self {
+        Self::empty()
+    }
+
+    fn empty() -> Self {
         List {
             head: None,
-            tail: None, // NEW!
+            tail: None,
+            len: 0,
+            metrics: Counters::new(),
+            _boo: PhantomData,
+        }
+    }
+
+    // 每个节点实际占用的字节数和对齐，方便对比不同布局的内存开销
+    pub fn node_size() -> usize {
+        std::mem::size_of::<Node<T, L>>()
+    }
+
+    pub fn node_align() -> usize {
+        std::mem::align_of::<Node<T, L>>()
+    }
+
+    pub fn push_front(&mut self, elem: T) {
+        // SAFETY: 新节点来自Box::into_raw，非空且有效
+        let new = unsafe {
+            NonNull::new_unchecked(Box::into_raw(Box::new(Node {
+                _align: [],
+                prev: None,
+                next: None,
+                elem,
+            })))
+        };
+        self.metrics.alloc_push();
+        self.link_front(new);
+        mutation!("push_front", len = self.len);
+    }
+
+    // 把一个孤立的新节点接到头部
+    fn link_front(&mut self, new: NonNull<Node<T, L>>) {
+        // SAFETY: new是刚分配、尚未链接的节点
+        unsafe {
+            if let Some(old) = self.head {
+                (*old.as_ptr()).prev = Some(new);
+                (*new.as_ptr()).next = Some(old);
+            } else {
+                // 空链表，新节点同时是尾节点
+                self.tail = Some(new);
+            }
+            self.head = Some(new);
+            self.len += 1;
+        }
+        self.check_invariants();
+    }
+
+    pub fn push_back(&mut self, elem: T) {
+        // SAFETY: 与push_front对称
+        let new = unsafe {
+            NonNull::new_unchecked(Box::into_raw(Box::new(Node {
+                _align: [],
+                prev: None,
+                next: None,
+                elem,
+            })))
+        };
+        self.metrics.alloc_push();
+        self.link_back(new);
+        mutation!("push_back", len = self.len);
+    }
+
+    // 和push_front/push_back一样，但分配节点失败时返回AllocError，而不是像Box::new那样直接abort
+    // 失败时elem随错误一起被丢弃
+    pub fn try_push_front(&mut self, elem: T) -> Result<(), ListError> {
+        let new = Self::try_alloc_node(elem)?;
+        self.metrics.alloc_push();
+        self.link_front(new);
+        mutation!("push_front", len = self.len);
+        Ok(())
+    }
+
+    pub fn try_push_back(&mut self, elem: T) -> Result<(), ListError> {
+        let new = Self::try_alloc_node(elem)?;
+        self.metrics.alloc_push();
+        self.link_back(new);
+        mutation!("push_back", len = self.len);
+        Ok(())
+    }
+
+    // 按Box<Node>的布局直接向全局分配器要内存，之后照常用Box::from_raw释放
+    fn try_alloc_node(elem: T) -> Result<NonNull<Node<T, L>>, ListError> {
+        let layout = Layout::new::<Node<T, L>>();
+        // SAFETY: 节点里至少有两个指针，layout大小不为0
+        let ptr = unsafe { alloc::alloc(layout) } as *mut Node<T, L>;
+        let ptr = NonNull::new(ptr).ok_or(ListError::AllocError)?;
+        // SAFETY: ptr刚分配，大小和对齐都符合Node<T, L>
+        unsafe {
+            ptr.as_ptr().write(Node {
+                _align: [],
+                prev: None,
+                next: None,
+                elem,
+            });
+        }
+        Ok(ptr)
+    }
+
+    fn link_back(&mut self, new: NonNull<Node<T, L>>) {
+        // SAFETY: 与link_front对称
+        unsafe {
+            if let Some(old) = self.tail {
+                (*old.as_ptr()).next = Some(new);
+                (*new.as_ptr()).prev = Some(old);
+            } else {
+                self.head = Some(new);
+            }
+            self.tail = Some(new);
+            self.len += 1;
         }
+        self.check_invariants();
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        let node = self.unlink_front()?;
+        mutation!("pop_front", len = self.len);
+        Some(node.elem)
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        let node = self.unlink_back()?;
+        mutation!("pop_back", len = self.len);
+        Some(node.elem)
+    }
+
+    // 从共享节点池里拿节点，池子为空时才向分配器申请
+    // 池子里的块按Node<T, L>的布局分配，之后走普通的pop/drop当作Box释放也没问题
+    pub fn push_front_pooled(&mut self, elem: T, pool: &mut NodePool<T>) {
+        let new = pool.alloc(Node {
+            _align: [],
+            prev: None,
+            next: None,
+            elem,
+        });
+        self.metrics.push();
+        self.link_front(new);
     }
-    pub fn push(&mut self, elem:T){
-        let new_tail = Box::new(Node{
+
+    pub fn push_back_pooled(&mut self, elem: T, pool: &mut NodePool<T>) {
+        let new = pool.alloc(Node {
+            _align: [],
+            prev: None,
+            next: None,
             elem,
-            next:None,
         });
+        self.metrics.push();
+        self.link_back(new);
+    }
+
+    // pop之后把节点内存还给池子而不是释放
+    pub fn pop_front_pooled(&mut self, pool: &mut NodePool<T>) -> Option<T> {
+        self.unlink_front().map(|node| Self::recycle(node, pool))
+    }
+
+    pub fn pop_back_pooled(&mut self, pool: &mut NodePool<T>) -> Option<T> {
+        self.unlink_back().map(|node| Self::recycle(node, pool))
+    }
+
+    // 析构所有元素，节点内存全部还给池子
+    pub fn clear_pooled(&mut self, pool: &mut NodePool<T>) {
+        while let Some(node) = self.unlink_front() {
+            drop(Self::recycle(node, pool));
+        }
+    }
+
+    fn recycle(node: Box<Node<T, L>>, pool: &mut NodePool<T>) -> T {
+        let node = Box::into_raw(node);
+        // SAFETY: 节点来自Box，elem移出后只剩内存还给池子
+        unsafe {
+            let elem = std::ptr::read(&(*node).elem);
+            pool.recycle(NonNull::new_unchecked(node));
+            elem
+        }
+    }
+
+    // 摘下头节点但不把元素移出来，直接drop返回的Box会在堆上原地析构元素
+    // Drop/clear和PinnedList都依赖这一点: 被Pin住的元素直到析构都不能移动
+    fn unlink_front(&mut self) -> Option<Box<Node<T, L>>> {
+        // SAFETY: head指向的节点由本链表独占，重新装回Box后交给调用者
+        let node = unsafe {
+            self.head.map(|node| {
+                let boxed_node = Box::from_raw(node.as_ptr());
+                self.metrics.pop();
+                self.head = boxed_node.next;
+                if let Some(new) = self.head {
+                    (*new.as_ptr()).prev = None;
+                } else {
+                    // 链表被取空，tail也要清掉
+                    self.tail = None;
+                }
+                self.len -= 1;
+                boxed_node
+            })
+        };
+        self.check_invariants();
+        node
+    }
+
+    fn unlink_back(&mut self) -> Option<Box<Node<T, L>>> {
+        // SAFETY: 与unlink_front对称
+        let node = unsafe {
+            self.tail.map(|node| {
+                let boxed_node = Box::from_raw(node.as_ptr());
+                self.metrics.pop();
+                self.tail = boxed_node.prev;
+                if let Some(new) = self.tail {
+                    (*new.as_ptr()).next = None;
+                } else {
+                    self.head = None;
+                }
+                self.len -= 1;
+                boxed_node
+            })
+        };
+        self.check_invariants();
+        node
+    }
+
+    // 异步版本的pop_front，链表为空时返回Ready(None)而不是挂起:
+    // 持有&mut self期间不可能有别人push，挂起只会永远等下去
+    // 需要等待其他线程生产数据的场景请使用带唤醒机制的队列
+    #[cfg(feature = "async")]
+    pub fn poll_pop_front(&mut self, _cx: &mut std::task::Context<'_>) -> std::task::Poll<Option<T>> {
+        std::task::Poll::Ready(self.pop_front())
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        unsafe { self.head.map(|node| &(*node.as_ptr()).elem) }
+    }
+
+    pub fn front_mut(&mut self) -> Option<&mut T> {
+        unsafe { self.head.map(|node| &mut (*node.as_ptr()).elem) }
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        unsafe { self.tail.map(|node| &(*node.as_ptr()).elem) }
+    }
+
+    pub fn back_mut(&mut self) -> Option<&mut T> {
+        unsafe { self.tail.map(|node| &mut (*node.as_ptr()).elem) }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn clear(&mut self) {
+        mutation!("clear", len = self.len);
+        while self.unlink_front().is_some() {}
+    }
+
+    // 把other的所有节点接到self尾部，O(1)，other变为空
+    pub fn append(&mut self, other: &mut Self) {
+        let (Some(other_head), Some(other_tail)) = (other.head.take(), other.tail.take()) else {
+            return;
+        };
+        // SAFETY: 两个链表各自的节点互不重叠，只修改端点指针
+        unsafe {
+            match self.tail {
+                Some(tail) => {
+                    (*tail.as_ptr()).next = Some(other_head);
+                    (*other_head.as_ptr()).prev = Some(tail);
+                }
+                None => self.head = Some(other_head),
+            }
+        }
+        self.tail = Some(other_tail);
+        self.len += std::mem::take(&mut other.len);
+        self.check_invariants();
+        mutation!("append", len = self.len);
+    }
+
+    // 把切片里的元素依次clone到尾部
+    pub fn extend_from_slice(&mut self, other: &[T])
+    where
+        T: Clone,
+    {
+        self.extend(other.iter().cloned());
+    }
+
+    // 把链表切成n段长度尽量相等的子链表(前len % n段各多一个元素)，只做指针手术不移动元素
+    // 适合把一批任务分给n个工作线程，n大于元素个数时多出来的子链表为空
+    pub fn split_into(mut self, n: usize) -> Vec<Self> {
+        assert!(n > 0, "split_into requires at least one part");
+        let _span = mutation_span!("split_into", n, len = self.len);
+        let base = self.len / n;
+        let extra = self.len % n;
+        let mut parts = Vec::with_capacity(n);
+        let mut cur = self.head.take();
+        self.tail = None;
+        self.len = 0;
+
+        for i in 0..n {
+            let part_len = base + usize::from(i < extra);
+            let mut part = Self::empty();
+            if part_len > 0 {
+                // SAFETY: 所有节点原本都属于self，这里逐段断开并交给新链表
+                unsafe {
+                    let head = cur.unwrap();
+                    let mut tail = head;
+                    for _ in 1..part_len {
+                        tail = (*tail.as_ptr()).next.unwrap();
+                    }
+                    cur = (*tail.as_ptr()).next.take();
+                    (*head.as_ptr()).prev = None;
+                    part.head = Some(head);
+                    part.tail = Some(tail);
+                    part.len = part_len;
+                }
+            }
+            part.check_invariants();
+            mutation!("split_part", len = part.len);
+            parts.push(part);
+        }
+        parts
+    }
+
+    // 切下[at, len)作为新链表返回，自己留下前at个元素(LinkedList::split_off的语义)
+    // at > len时panic
+    pub fn split_off(&mut self, at: usize) -> Self {
+        match self.try_split_off(at) {
+            Ok(list) => list,
+            Err(err) => panic!("split_off: {err}"),
+        }
+    }
+
+    // 和split_off一样，at > len时返回OutOfRange
+    pub fn try_split_off(&mut self, at: usize) -> Result<Self, ListError> {
+        crate::error::check_insert(at, self.len)?;
+        if at == 0 {
+            return Ok(std::mem::take(self));
+        }
+        // 游标从幽灵位置出发，走at步停在第at - 1个元素上
+        let mut cursor = self.cursor_mut();
+        for _ in 0..at {
+            cursor.move_next();
+        }
+        Ok(cursor.split_after())
+    }
+
+    pub fn iter(&self) -> Iter<'_, T, L> {
+        Iter {
+            head: self.head,
+            tail: self.tail,
+            len: self.len,
+            metrics: &self.metrics,
+            _boo: PhantomData,
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T, L> {
+        IterMut {
+            head: self.head,
+            tail: self.tail,
+            len: self.len,
+            metrics: &self.metrics,
+            _boo: PhantomData,
+        }
+    }
+
+    pub fn cursor_mut(&mut self) -> CursorMut<'_, T, L> {
+        CursorMut {
+            list: self,
+            cur: None,
+            index: None,
+        }
+    }
+
+    // 把前k个元素整体挪到尾部(VecDeque::rotate_left的语义)
+    // 从离目标更近的一端走到切分点，然后只改四个指针
+    pub fn rotate_left(&mut self, k: usize) {
+        assert!(k <= self.len, "rotate amount exceeds length");
+        if k == 0 || k == self.len {
+            return;
+        }
+        // SAFETY: 0 < k < len，目标节点一定存在
+        unsafe {
+            let new_head = if k <= self.len / 2 {
+                let mut node = self.head.unwrap();
+                for _ in 0..k {
+                    node = (*node.as_ptr()).next.unwrap();
+                    self.metrics.step();
+                }
+                node
+            } else {
+                let mut node = self.tail.unwrap();
+                for _ in 0..self.len - k - 1 {
+                    node = (*node.as_ptr()).prev.unwrap();
+                    self.metrics.step();
+                }
+                node
+            };
+            self.rotate_to(new_head);
+        }
+        mutation!("rotate_left", k);
+    }
+
+    // 把后k个元素整体挪到头部
+    pub fn rotate_right(&mut self, k: usize) {
+        assert!(k <= self.len, "rotate amount exceeds length");
+        self.rotate_left(self.len - k);
+    }
+
+    // 把队头元素依次挪到队尾，直到队头满足pred为止，返回挪动的元素个数
+    // 整个过程只在找到位置后做一次O(1)的重新链接，而不是k次pop+push
+    // 没有任何元素满足pred时链表保持原样并返回None
+    pub fn rotate_until<P>(&mut self, mut pred: P) -> Option<usize>
+    where
+        P: FnMut(&T) -> bool,
+    {
+        let mut k = 0;
+        let mut cur = self.head;
+        // SAFETY: 只读遍历
+        unsafe {
+            while let Some(node) = cur {
+                if pred(&(*node.as_ptr()).elem) {
+                    if k > 0 {
+                        self.rotate_to(node);
+                    }
+                    return Some(k);
+                }
+                k += 1;
+                cur = (*node.as_ptr()).next;
+                self.metrics.step();
+            }
+        }
+        None
+    }
+
+    // 只要队头满足pred就继续往后挪，语义上等于rotate_until(!pred)
+    pub fn rotate_while<P>(&mut self, mut pred: P) -> Option<usize>
+    where
+        P: FnMut(&T) -> bool,
+    {
+        self.rotate_until(|elem| !pred(elem))
+    }
+
+    // 加权轮转调度，运行队列的常见写法:
+    // 队头元素连续获得weight(elem)次服务(每次调用visit)，配额用完后挪到队尾，一共服务ticks次
+    // weight为0的元素直接跳过；ticks在某个元素配额中途用完时，该元素留在队头，下次调用重新计算配额
+    // 返回本次被挪到队尾的元素个数
+    pub fn round_robin_weighted<W, V>(&mut self, ticks: usize, mut weight: W, mut visit: V) -> usize
+    where
+        W: FnMut(&T) -> usize,
+        V: FnMut(&mut T),
+    {
+        let mut rotated = 0;
+        let mut served = 0;
+        // 连续跳过了多少个权重为0的元素，转满一圈说明没有人可以服务
+        let mut idle = 0;
+        while served < ticks && idle < self.len {
+            let quantum = match self.front() {
+                Some(front) => weight(front),
+                None => break,
+            };
+            if quantum == 0 {
+                idle += 1;
+            } else {
+                idle = 0;
+                let turns = quantum.min(ticks - served);
+                for _ in 0..turns {
+                    visit(self.front_mut().unwrap());
+                }
+                served += turns;
+                if turns < quantum {
+                    break;
+                }
+            }
+            self.rotate_left(1);
+            rotated += 1;
+        }
+        rotated
+    }
+
+    // 让new_head成为新的队头，原来在它前面的节点整体接到尾部
+    // SAFETY: new_head必须属于本链表且不是当前head
+    unsafe fn rotate_to(&mut self, new_head: NonNull<Node<T, L>>) {
+        let old_head = self.head.unwrap();
+        let old_tail = self.tail.unwrap();
+        let new_tail = (*new_head.as_ptr()).prev.unwrap();
+
+        (*new_tail.as_ptr()).next = None;
+        (*new_head.as_ptr()).prev = None;
+        (*old_tail.as_ptr()).next = Some(old_head);
+        (*old_head.as_ptr()).prev = Some(old_tail);
+
+        self.head = Some(new_head);
+        self.tail = Some(new_tail);
+        self.check_invariants();
+    }
+
+    // 以下查找方法都接受T借用出来的类型Q，List<String>可以直接用&str查找而不用分配
+    pub fn contains<Q>(&self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: PartialEq + ?Sized,
+    {
+        self.find(value).is_some()
+    }
 
-        let old_tail = mem::replace(&mut self.tail, Some(new_tail));
+    // 返回第一个等于value的元素
+    pub fn find<Q>(&self, value: &Q) -> Option<&T>
+    where
+        T: Borrow<Q>,
+        Q: PartialEq + ?Sized,
+    {
+        self.iter().find(|elem| (*elem).borrow() == value)
+    }
+
+    pub fn find_mut<Q>(&mut self, value: &Q) -> Option<&mut T>
+    where
+        T: Borrow<Q>,
+        Q: PartialEq + ?Sized,
+    {
+        self.iter_mut().find(|elem| (**elem).borrow() == value)
+    }
+
+    // 删除并返回第一个等于value的元素，O(n)查找 + O(1)摘除
+    pub fn remove_first<Q>(&mut self, value: &Q) -> Option<T>
+    where
+        T: Borrow<Q>,
+        Q: PartialEq + ?Sized,
+    {
+        let mut cursor = self.cursor_mut();
+        cursor.move_next();
+        while let Some(elem) = cursor.current() {
+            if (*elem).borrow() == value {
+                return cursor.remove_current();
+            }
+            cursor.move_next();
+        }
+        None
+    }
+
+    // 链表版本的Entry API，适用于按顺序维护的双端队列
+    // cmp返回元素相对目标的大小(与slice::binary_search_by一致)，从头开始查找:
+    // - 遇到Equal，游标停在该元素上
+    // - 遇到第一个Greater，说明目标不存在，在它前面插入default()，游标停在新元素上
+    // - 走到末尾也没找到，把default()追加到尾部
+    pub fn find_or_insert_by<F, D>(&mut self, mut cmp: F, default: D) -> CursorMut<'_, T, L>
+    where
+        F: FnMut(&T) -> Ordering,
+        D: FnOnce() -> T,
+    {
+        let mut cursor = self.cursor_mut();
+        cursor.move_next();
+        loop {
+            let ord = match cursor.current() {
+                Some(elem) => cmp(elem),
+                None => Ordering::Greater,
+            };
+            match ord {
+                Ordering::Less => cursor.move_next(),
+                Ordering::Equal => return cursor,
+                Ordering::Greater => {
+                    cursor.insert_before(default());
+                    cursor.move_prev();
+                    return cursor;
+                }
+            }
+        }
+    }
+
+    // 下面是给同crate里"外部索引 + 链表"结构(LinkedHashMap、LruCache等)用的节点句柄接口
+    // 句柄就是节点指针，不做任何校验，调用者保证句柄指向的节点仍在这条链表里
+    pub(crate) fn push_front_handle(&mut self, elem: T) -> NodeHandle<T, L> {
+        self.push_front(elem);
+        NodeHandle(self.head.unwrap())
+    }
+
+    pub(crate) fn push_back_handle(&mut self, elem: T) -> NodeHandle<T, L> {
+        self.push_back(elem);
+        NodeHandle(self.tail.unwrap())
+    }
+
+    pub(crate) fn front_handle(&self) -> Option<NodeHandle<T, L>> {
+        self.head.map(NodeHandle)
+    }
+
+    /// # Safety
+    ///
+    /// handle指向的节点必须在这条链表里
+    pub(crate) unsafe fn next_handle(&self, handle: NodeHandle<T, L>) -> Option<NodeHandle<T, L>> {
+        unsafe { (*handle.0.as_ptr()).next.map(NodeHandle) }
+    }
+
+    /// # Safety
+    ///
+    /// 同next_handle
+    pub(crate) unsafe fn insert_after_handle(&mut self, handle: NodeHandle<T, L>, elem: T) -> NodeHandle<T, L> {
+        unsafe {
+            let new = NonNull::new_unchecked(Box::into_raw(Box::new(Node {
+                _align: [],
+                prev: Some(handle.0),
+                next: (*handle.0.as_ptr()).next,
+                elem,
+            })));
+            self.metrics.alloc_push();
+            match (*handle.0.as_ptr()).next.replace(new) {
+                Some(next) => (*next.as_ptr()).prev = Some(new),
+                None => self.tail = Some(new),
+            }
+            self.len += 1;
+            self.check_invariants();
+            NodeHandle(new)
+        }
+    }
+
+    /// # Safety
+    ///
+    /// handle指向的节点必须在这条链表里
+    pub(crate) unsafe fn get_handle(&self, handle: NodeHandle<T, L>) -> &T {
+        unsafe { &(*handle.0.as_ptr()).elem }
+    }
+
+    /// # Safety
+    ///
+    /// 同get_handle
+    pub(crate) unsafe fn get_handle_mut(&mut self, handle: NodeHandle<T, L>) -> &mut T {
+        unsafe { &mut (*handle.0.as_ptr()).elem }
+    }
+
+    /// # Safety
+    ///
+    /// 同get_handle，移除之后handle失效
+    pub(crate) unsafe fn remove_handle(&mut self, handle: NodeHandle<T, L>) -> T {
+        unsafe {
+            self.detach(handle.0);
+            self.metrics.pop();
+            Box::from_raw(handle.0.as_ptr()).elem
+        }
+    }
+
+    /// # Safety
+    ///
+    /// 同get_handle
+    pub(crate) unsafe fn move_to_front(&mut self, handle: NodeHandle<T, L>) {
+        if self.head == Some(handle.0) {
+            return;
+        }
+        unsafe {
+            self.detach(handle.0);
+            self.link_front(handle.0);
+        }
+    }
+
+    /// # Safety
+    ///
+    /// 同get_handle
+    pub(crate) unsafe fn move_to_back(&mut self, handle: NodeHandle<T, L>) {
+        if self.tail == Some(handle.0) {
+            return;
+        }
+        unsafe {
+            self.detach(handle.0);
+            self.link_back(handle.0);
+        }
+    }
+
+    /// # Safety
+    ///
+    /// 同next_handle
+    pub(crate) unsafe fn prev_handle(&self, handle: NodeHandle<T, L>) -> Option<NodeHandle<T, L>> {
+        unsafe { (*handle.0.as_ptr()).prev.map(NodeHandle) }
+    }
+
+    /// # Safety
+    ///
+    /// handle和before指向的节点都必须在这条链表里
+    pub(crate) unsafe fn move_before(&mut self, handle: NodeHandle<T, L>, before: NodeHandle<T, L>) {
+        if handle == before {
+            return;
+        }
+        unsafe {
+            self.detach(handle.0);
+            let (node, before) = (handle.0.as_ptr(), before.0);
+            let prev = (*before.as_ptr()).prev.replace(handle.0);
+            (*node).prev = prev;
+            (*node).next = Some(before);
+            match prev {
+                Some(prev) => (*prev.as_ptr()).next = Some(handle.0),
+                None => self.head = Some(handle.0),
+            }
+        }
+        self.len += 1;
+        self.check_invariants();
+    }
+
+    // 把节点从链表中摘下来但不释放，摘下后prev/next清空，可以再用link_front/link_back接回去
+    unsafe fn detach(&mut self, node: NonNull<Node<T, L>>) {
+        unsafe {
+            let node = node.as_ptr();
+            let prev = (*node).prev.take();
+            let next = (*node).next.take();
+            match prev {
+                Some(prev) => (*prev.as_ptr()).next = next,
+                None => self.head = next,
+            }
+            match next {
+                Some(next) => (*next.as_ptr()).prev = prev,
+                None => self.tail = prev,
+            }
+        }
+        self.len -= 1;
+        self.check_invariants();
+    }
+
+    // 只沿next检查是否成环；正常使用下永远是false，用于排查unsafe代码把链表改坏的情况
+    pub fn has_cycle(&self) -> bool {
+        cycle::has_cycle(self.head, Self::successor)
+    }
+
+    // 环入口节点的下标
+    pub fn cycle_start(&self) -> Option<usize> {
+        cycle::floyd(self.head, Self::successor).map(|c| c.start)
+    }
+
+    // 断开环上最后一个节点的next，让它成为新的尾节点，并按实际节点数修正len；没有环时返回false
+    // 入口节点的prev不受影响，仍然指向它在next链上的前驱
+    pub fn remove_cycle(&mut self) -> bool {
+        let Some(found) = cycle::brent(self.head, Self::successor) else {
+            return false;
+        };
+        let mut last = self.head.unwrap();
+        for _ in 1..found.start + found.len {
+            // SAFETY: 环上的节点都由本链表拥有，next都非空
+            last = unsafe { (*last.as_ptr()).next.unwrap() };
+        }
+        // SAFETY: 同上
+        unsafe { (*last.as_ptr()).next = None };
+        self.tail = Some(last);
+        self.len = found.start + found.len;
+        self.check_invariants();
+        true
+    }
+
+    fn successor(node: NonNull<Node<T, L>>) -> Link<T, L> {
+        // SAFETY: 只在本链表的节点上调用
+        unsafe { (*node.as_ptr()).next }
+    }
+
+    // 测试用: 故意把尾节点的next指回第index个节点，制造一个环
+    #[cfg(test)]
+    pub(crate) fn link_tail_to(&mut self, index: usize) {
+        assert!(index < self.len, "index out of bounds");
+        let mut target = self.head.unwrap();
+        for _ in 0..index {
+            target = unsafe { (*target.as_ptr()).next.unwrap() };
+        }
+        unsafe { (*self.tail.unwrap().as_ptr()).next = Some(target) };
+    }
+
+    // 完整遍历一次链表检查所有结构不变量，任何一条不满足都会panic
+    // 用于单元测试和重构unsafe内部实现时快速定位问题
+    pub fn assert_invariants(&self) {
+        match (self.head, self.tail) {
+            (None, None) => {
+                assert_eq!(self.len, 0, "empty list must have len 0");
+                return;
+            }
+            (Some(_), None) | (None, Some(_)) => {
+                panic!("head and tail must be both Some or both None");
+            }
+            (Some(_), Some(_)) => {}
+        }
+
+        // SAFETY: 只读遍历，所有节点都由本链表拥有
+        unsafe {
+            let head = self.head.unwrap();
+            assert!((*head.as_ptr()).prev.is_none(), "head.prev must be None");
+            let tail = self.tail.unwrap();
+            assert!((*tail.as_ptr()).next.is_none(), "tail.next must be None");
+
+            let mut count = 1;
+            let mut cur = head;
+            while let Some(next) = (*cur.as_ptr()).next {
+                assert_eq!(
+                    (*next.as_ptr()).prev,
+                    Some(cur),
+                    "node {} has a prev pointer that does not point back",
+                    count
+                );
+                assert!(count < self.len, "list has more nodes than len {}", self.len);
+                cur = next;
+                count += 1;
+            }
+            assert_eq!(cur, tail, "walking from head does not end at tail");
+            assert_eq!(count, self.len, "cached len does not match node count");
+        }
+    }
+
+    // 当前的操作计数快照，各项含义见metrics.rs
+    #[cfg(feature = "metrics")]
+    pub fn metrics(&self) -> crate::metrics::Metrics {
+        self.metrics.snapshot()
+    }
+
+    #[cfg(feature = "metrics")]
+    pub fn reset_metrics(&self) {
+        self.metrics.reset();
+    }
+
+    // 打开debug-invariants特性后，每次结构性修改都会做一次完整检查(仅debug构建)
+    #[inline]
+    fn check_invariants(&self) {
+        #[cfg(all(debug_assertions, feature = "debug-invariants"))]
+        self.assert_invariants();
+    }
+}
+
+// 节点句柄，见List::push_back_handle
+pub(crate) struct NodeHandle<T, L: NodeLayout = Compact>(NonNull<Node<T, L>>);
+
+impl<T, L: NodeLayout> Clone for NodeHandle<T, L> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T, L: NodeLayout> Copy for NodeHandle<T, L> {}
+
+impl<T, L: NodeLayout> PartialEq for NodeHandle<T, L> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<T, L: NodeLayout> Eq for NodeHandle<T, L> {}
+
+impl<T, L: NodeLayout> Drop for List<T, L> {
+    fn drop(&mut self) {
+        // 元素在节点里原地析构，不会被移动到栈上
+        while self.unlink_front().is_some() {}
+    }
+}
+
+impl<T, L: NodeLayout> Default for List<T, L> {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
 
-        match old_tail {
-            Some(mut old_tail) => {
-                old_tail.next = Some(new_tail)
+// next/prev都是NonNull，画成虚线；表头上写着len，head/tail分别指向首尾节点
+#[cfg(feature = "visualize")]
+impl<T: Debug, L: NodeLayout> List<T, L> {
+    pub fn to_dot(&self) -> String {
+        use crate::visualize::{Dot, Edge};
+        let mut dot = Dot::new();
+        let root = dot.root(&format!("List len={}", self.len));
+        let mut cur = self.head;
+        let mut prev: Option<String> = None;
+        while let Some(ptr) = cur {
+            // SAFETY: 从head沿next能走到的节点都归本链表所有，&self期间不会被释放
+            let node = unsafe { &*ptr.as_ptr() };
+            let (name, new) = dot.node(ptr.as_ptr(), &node.elem, "");
+            match &prev {
+                None => dot.edge(&root, &name, "head", Edge::Raw),
+                Some(p) => dot.edge(p, &name, "next", Edge::Raw),
+            }
+            if !new {
+                break;
             }
-            None => {
-                self.head = Some(new_tail)
+            // 画节点里实际存的prev，链表正常时指回上一个节点
+            if let Some(back) = node.prev {
+                // SAFETY: prev指向的也是本链表的节点
+                let (to, _) = dot.node(back.as_ptr(), unsafe { &(*back.as_ptr()).elem }, "");
+                dot.edge(&name, &to, "prev", Edge::Raw);
             }
+            prev = Some(name);
+            cur = node.next;
+        }
+        if let Some(tail) = self.tail {
+            // SAFETY: tail非空时指向本链表的最后一个节点
+            let (to, _) = dot.node(tail.as_ptr(), unsafe { &(*tail.as_ptr()).elem }, "");
+            dot.edge(&root, &to, "tail", Edge::Raw);
+        }
+        dot.finish()
+    }
+}
+
+impl<T: Clone, L: NodeLayout> Clone for List<T, L> {
+    fn clone(&self) -> Self {
+        let mut new_list = Self::empty();
+        for item in self {
+            new_list.push_back(item.clone());
+        }
+        new_list
+    }
+}
+
+impl<T, L: NodeLayout> Extend<T> for List<T, L> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.push_back(item);
         }
     }
+}
+
+// 和Vec一样，Copy元素可以直接从借用的数据批量追加
+impl<'a, T: Copy + 'a, L: NodeLayout> Extend<&'a T> for List<T, L> {
+    fn extend<I: IntoIterator<Item = &'a T>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().copied());
+    }
+}
+
+impl<T, L: NodeLayout> FromIterator<T> for List<T, L> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = Self::empty();
+        list.extend(iter);
+        list
+    }
+}
+
+// 与std集合互相转换，方便从LinkedList/VecDeque逐步迁移过来
+impl<T, L: NodeLayout> From<LinkedList<T>> for List<T, L> {
+    fn from(list: LinkedList<T>) -> Self {
+        list.into_iter().collect()
+    }
+}
+
+impl<T, L: NodeLayout> From<List<T, L>> for LinkedList<T> {
+    fn from(list: List<T, L>) -> Self {
+        list.into_iter().collect()
+    }
+}
+
+impl<T, L: NodeLayout> From<VecDeque<T>> for List<T, L> {
+    fn from(deque: VecDeque<T>) -> Self {
+        deque.into_iter().collect()
+    }
+}
+
+impl<T, L: NodeLayout> From<List<T, L>> for VecDeque<T> {
+    fn from(list: List<T, L>) -> Self {
+        // IntoIter是ExactSizeIterator，collect会一次性分配好容量
+        list.into_iter().collect()
+    }
+}
 
+impl<T: Debug, L: NodeLayout> Debug for List<T, L> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self).finish()
+    }
+}
 
+impl<T: PartialEq, L: NodeLayout> PartialEq for List<T, L> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other)
+    }
 }
 
+impl<T: Eq, L: NodeLayout> Eq for List<T, L> {}
+
+impl<T: PartialOrd, L: NodeLayout> PartialOrd for List<T, L> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.iter().partial_cmp(other)
+    }
+}
 
+impl<T: Ord, L: NodeLayout> Ord for List<T, L> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.iter().cmp(other)
+    }
+}
+
+impl<T: Hash, L: NodeLayout> Hash for List<T, L> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.len().hash(state);
+        for item in self {
+            item.hash(state);
+        }
+    }
+}
+
+// List<T>本身不包含任何共享可变状态，和Box<T>一样按T决定Send/Sync
+unsafe impl<T: Send, L: NodeLayout> Send for List<T, L> {}
+unsafe impl<T: Sync, L: NodeLayout> Sync for List<T, L> {}
+
+// 元素都在堆上的节点里，移动List本身不会移动任何元素，和Box<T>一样总是Unpin
+impl<T, L: NodeLayout> Unpin for List<T, L> {}
+
+// 三种迭代器都从两端同时收缩，用len判断何时相遇
+pub struct Iter<'a, T, L: NodeLayout = Compact> {
+    head: Link<T, L>,
+    tail: Link<T, L>,
+    len: usize,
+    metrics: &'a Counters,
+    _boo: PhantomData<&'a T>,
+}
+
+impl<'a, T, L: NodeLayout> Iterator for Iter<'a, T, L> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.len > 0 {
+            self.head.map(|node| unsafe {
+                self.len -= 1;
+                self.metrics.step();
+                self.head = (*node.as_ptr()).next;
+                &(*node.as_ptr()).elem
+            })
+        } else {
+            None
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T, L: NodeLayout> DoubleEndedIterator for Iter<'_, T, L> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.len > 0 {
+            self.tail.map(|node| unsafe {
+                self.len -= 1;
+                self.metrics.step();
+                self.tail = (*node.as_ptr()).prev;
+                &(*node.as_ptr()).elem
+            })
+        } else {
+            None
+        }
+    }
+}
+
+// 手写Clone，避免derive带上多余的T: Clone约束
+impl<T, L: NodeLayout> Clone for Iter<'_, T, L> {
+    fn clone(&self) -> Self {
+        Iter { ..*self }
+    }
+}
+
+impl<T, L: NodeLayout> ExactSizeIterator for Iter<'_, T, L> {
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+
+impl<'a, T, L: NodeLayout> IntoIterator for &'a List<T, L> {
+    type IntoIter = Iter<'a, T, L>;
+    type Item = &'a T;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+pub struct IterMut<'a, T, L: NodeLayout = Compact> {
+    head: Link<T, L>,
+    tail: Link<T, L>,
+    len: usize,
+    metrics: &'a Counters,
+    _boo: PhantomData<&'a mut T>,
+}
+
+impl<'a, T, L: NodeLayout> Iterator for IterMut<'a, T, L> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.len > 0 {
+            self.head.map(|node| unsafe {
+                self.len -= 1;
+                self.metrics.step();
+                self.head = (*node.as_ptr()).next;
+                &mut (*node.as_ptr()).elem
+            })
+        } else {
+            None
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T, L: NodeLayout> DoubleEndedIterator for IterMut<'_, T, L> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.len > 0 {
+            self.tail.map(|node| unsafe {
+                self.len -= 1;
+                self.metrics.step();
+                self.tail = (*node.as_ptr()).prev;
+                &mut (*node.as_ptr()).elem
+            })
+        } else {
+            None
+        }
+    }
+}
+
+impl<T, L: NodeLayout> ExactSizeIterator for IterMut<'_, T, L> {
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+
+impl<'a, T, L: NodeLayout> IntoIterator for &'a mut List<T, L> {
+    type IntoIter = IterMut<'a, T, L>;
+    type Item = &'a mut T;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+pub struct IntoIter<T, L: NodeLayout = Compact> {
+    list: List<T, L>,
+}
+
+impl<T, L: NodeLayout> Iterator for IntoIter<T, L> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.list.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.list.len, Some(self.list.len))
+    }
+}
+
+impl<T, L: NodeLayout> DoubleEndedIterator for IntoIter<T, L> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.list.pop_back()
+    }
+}
+
+impl<T, L: NodeLayout> ExactSizeIterator for IntoIter<T, L> {
+    fn len(&self) -> usize {
+        self.list.len
+    }
+}
+
+// 消费型迭代器同时也是一个永远不会Pending的Stream，可以直接喂给异步管道
+#[cfg(feature = "async")]
+impl<T, L: NodeLayout> futures_core::Stream for IntoIter<T, L> {
+    type Item = T;
+
+    fn poll_next(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        self.get_mut().list.poll_pop_front(cx)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.list.len, Some(self.list.len))
+    }
+}
+
+impl<T, L: NodeLayout> IntoIterator for List<T, L> {
+    type IntoIter = IntoIter<T, L>;
+    type Item = T;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter { list: self }
+    }
+}
+
+// 游标在链表的节点之间移动，另外还有一个指向"幽灵"位置的状态(cur为None)
+// 幽灵位置位于tail和head之间，从幽灵位置move_next会到head，move_prev会到tail
+pub struct CursorMut<'a, T, L: NodeLayout = Compact> {
+    list: &'a mut List<T, L>,
+    cur: Link<T, L>,
+    index: Option<usize>,
+}
+
+impl<'a, T, L: NodeLayout> CursorMut<'a, T, L> {
+    pub fn index(&self) -> Option<usize> {
+        self.index
+    }
+
+    pub fn move_next(&mut self) {
+        self.list.metrics.step();
+        if let Some(cur) = self.cur {
+            unsafe {
+                self.cur = (*cur.as_ptr()).next;
+                if self.cur.is_some() {
+                    *self.index.as_mut().unwrap() += 1;
+                } else {
+                    // 走过了tail，回到幽灵位置
+                    self.index = None;
+                }
+            }
+        } else if !self.list.is_empty() {
+            self.cur = self.list.head;
+            self.index = Some(0);
+        }
+    }
+
+    pub fn move_prev(&mut self) {
+        self.list.metrics.step();
+        if let Some(cur) = self.cur {
+            unsafe {
+                self.cur = (*cur.as_ptr()).prev;
+                if self.cur.is_some() {
+                    *self.index.as_mut().unwrap() -= 1;
+                } else {
+                    self.index = None;
+                }
+            }
+        } else if !self.list.is_empty() {
+            self.cur = self.list.tail;
+            self.index = Some(self.list.len - 1);
+        }
+    }
+
+    pub fn current(&mut self) -> Option<&mut T> {
+        unsafe { self.cur.map(|node| &mut (*node.as_ptr()).elem) }
+    }
+
+    pub fn peek_next(&mut self) -> Option<&mut T> {
+        unsafe {
+            let next = if let Some(cur) = self.cur {
+                (*cur.as_ptr()).next
+            } else {
+                self.list.head
+            };
+            next.map(|node| &mut (*node.as_ptr()).elem)
+        }
+    }
+
+    pub fn peek_prev(&mut self) -> Option<&mut T> {
+        unsafe {
+            let prev = if let Some(cur) = self.cur {
+                (*cur.as_ptr()).prev
+            } else {
+                self.list.tail
+            };
+            prev.map(|node| &mut (*node.as_ptr()).elem)
+        }
+    }
+
+    // 在当前位置之前插入元素，游标停在幽灵位置时相当于push_back
+    pub fn insert_before(&mut self, elem: T) {
+        let Some(cur) = self.cur else {
+            self.list.push_back(elem);
+            return;
+        };
+        unsafe {
+            let prev = (*cur.as_ptr()).prev;
+            let new = NonNull::new_unchecked(Box::into_raw(Box::new(Node {
+                _align: [],
+                prev,
+                next: Some(cur),
+                elem,
+            })));
+            self.list.metrics.alloc_push();
+            (*cur.as_ptr()).prev = Some(new);
+            match prev {
+                Some(prev) => (*prev.as_ptr()).next = Some(new),
+                None => self.list.head = Some(new),
+            }
+        }
+        self.list.len += 1;
+        *self.index.as_mut().unwrap() += 1;
+        self.list.check_invariants();
+        mutation!("insert_before", len = self.list.len);
+    }
+
+    // 在当前位置之后插入元素，游标停在幽灵位置时相当于push_front
+    pub fn insert_after(&mut self, elem: T) {
+        let Some(cur) = self.cur else {
+            self.list.push_front(elem);
+            return;
+        };
+        unsafe {
+            let next = (*cur.as_ptr()).next;
+            let new = NonNull::new_unchecked(Box::into_raw(Box::new(Node {
+                _align: [],
+                prev: Some(cur),
+                next,
+                elem,
+            })));
+            self.list.metrics.alloc_push();
+            (*cur.as_ptr()).next = Some(new);
+            match next {
+                Some(next) => (*next.as_ptr()).prev = Some(new),
+                None => self.list.tail = Some(new),
+            }
+        }
+        self.list.len += 1;
+        self.list.check_invariants();
+        mutation!("insert_after", len = self.list.len);
+    }
+
+    // 移除当前元素并返回，游标移动到下一个节点(没有下一个则回到幽灵位置)
+    pub fn remove_current(&mut self) -> Option<T> {
+        self.unlink_current().map(|node| node.elem)
+    }
+
+    // 摘下当前节点但不移出元素，游标的移动规则与remove_current相同
+    fn unlink_current(&mut self) -> Option<Box<Node<T, L>>> {
+        let cur = self.cur?;
+        unsafe {
+            let boxed_node = Box::from_raw(cur.as_ptr());
+            self.list.metrics.pop();
+            let prev = boxed_node.prev;
+            let next = boxed_node.next;
+            match prev {
+                Some(prev) => (*prev.as_ptr()).next = next,
+                None => self.list.head = next,
+            }
+            match next {
+                Some(next) => (*next.as_ptr()).prev = prev,
+                None => self.list.tail = prev,
+            }
+            self.list.len -= 1;
+            self.cur = next;
+            if next.is_none() {
+                self.index = None;
+            }
+            self.list.check_invariants();
+            mutation!("remove_current", len = self.list.len);
+            Some(boxed_node)
+        }
+    }
+
+    // 把当前位置之前的所有元素切下来作为新链表返回
+    // list: [A, B, C, D] cursor在C => 返回[A, B]，原链表剩下[C, D]
+    pub fn split_before(&mut self) -> List<T, L> {
+        if let Some(cur) = self.cur {
+            unsafe {
+                let old_len = self.list.len;
+                let old_idx = self.index.unwrap();
+                let prev = (*cur.as_ptr()).prev;
+
+                let new_len = old_len - old_idx;
+                let new_head = self.cur;
+                let new_idx = Some(0);
+
+                let output_len = old_len - new_len;
+                let output_head = self.list.head;
+                let output_tail = prev;
+
+                if let Some(prev) = prev {
+                    (*cur.as_ptr()).prev = None;
+                    (*prev.as_ptr()).next = None;
+                }
+
+                self.list.len = new_len;
+                self.list.head = new_head;
+                self.index = new_idx;
+                self.list.check_invariants();
+                mutation!("split_before", len = self.list.len, split_len = output_len);
+
+                List {
+                    head: if output_len == 0 { None } else { output_head },
+                    tail: output_tail,
+                    len: output_len,
+                    metrics: Counters::new(),
+                    _boo: PhantomData,
+                }
+            }
+        } else {
+            // 幽灵位置，整个链表都在它之前
+            std::mem::take(self.list)
+        }
+    }
+
+    // 把当前位置之后的所有元素切下来作为新链表返回
+    // list: [A, B, C, D] cursor在B => 返回[C, D]，原链表剩下[A, B]
+    pub fn split_after(&mut self) -> List<T, L> {
+        if let Some(cur) = self.cur {
+            unsafe {
+                let old_len = self.list.len;
+                let old_idx = self.index.unwrap();
+                let next = (*cur.as_ptr()).next;
+
+                let new_len = old_idx + 1;
+                let new_tail = self.cur;
+
+                let output_len = old_len - new_len;
+                let output_head = next;
+                let output_tail = self.list.tail;
+
+                if let Some(next) = next {
+                    (*cur.as_ptr()).next = None;
+                    (*next.as_ptr()).prev = None;
+                }
+
+                self.list.len = new_len;
+                self.list.tail = new_tail;
+                self.list.check_invariants();
+                mutation!("split_after", len = self.list.len, split_len = output_len);
+
+                List {
+                    head: output_head,
+                    tail: if output_len == 0 { None } else { output_tail },
+                    len: output_len,
+                    metrics: Counters::new(),
+                    _boo: PhantomData,
+                }
+            }
+        } else {
+            std::mem::take(self.list)
+        }
+    }
+
+    // 把input整体插入到当前位置之前，O(1)
+    pub fn splice_before(&mut self, mut input: List<T, L>) {
+        unsafe {
+            if input.is_empty() {
+                // input为空，什么都不用做
+            } else if let Some(cur) = self.cur {
+                let in_head = input.head.take().unwrap();
+                let in_tail = input.tail.take().unwrap();
+
+                if let Some(prev) = (*cur.as_ptr()).prev {
+                    (*prev.as_ptr()).next = Some(in_head);
+                    (*in_head.as_ptr()).prev = Some(prev);
+                } else {
+                    self.list.head = Some(in_head);
+                }
+                (*cur.as_ptr()).prev = Some(in_tail);
+                (*in_tail.as_ptr()).next = Some(cur);
+
+                *self.index.as_mut().unwrap() += input.len;
+                self.list.len += input.len;
+                input.len = 0;
+            } else {
+                // 幽灵位置之前就是链表尾部
+                self.list.append(&mut input);
+            }
+        }
+        self.list.check_invariants();
+        mutation!("splice_before", len = self.list.len);
+    }
+
+    // 把input整体插入到当前位置之后，O(1)
+    pub fn splice_after(&mut self, mut input: List<T, L>) {
+        unsafe {
+            if input.is_empty() {
+            } else if let Some(cur) = self.cur {
+                let in_head = input.head.take().unwrap();
+                let in_tail = input.tail.take().unwrap();
+
+                if let Some(next) = (*cur.as_ptr()).next {
+                    (*next.as_ptr()).prev = Some(in_tail);
+                    (*in_tail.as_ptr()).next = Some(next);
+                } else {
+                    self.list.tail = Some(in_tail);
+                }
+                (*cur.as_ptr()).next = Some(in_head);
+                (*in_head.as_ptr()).prev = Some(cur);
+
+                self.list.len += input.len;
+                input.len = 0;
+            } else {
+                // 幽灵位置之后就是链表头部
+                input.append(self.list);
+                std::mem::swap(self.list, &mut input);
+            }
+        }
+        self.list.check_invariants();
+        mutation!("splice_after", len = self.list.len);
+    }
+}
+
+// rkyv零拷贝序列化: 链表节点散落在堆上，归档时把元素按顺序写成一段连续的ArchivedVec，
+// 读取归档数据不需要反序列化就能当切片直接遍历
+#[cfg(feature = "rkyv")]
+mod rkyv_impl {
+    use super::{List, NodeLayout};
+    use rkyv::rancor::Fallible;
+    use rkyv::ser::{Allocator, Writer};
+    use rkyv::vec::{ArchivedVec, VecResolver};
+    use rkyv::{Archive, Deserialize, Place, Serialize};
+
+    impl<T: Archive, L: NodeLayout> Archive for List<T, L> {
+        type Archived = ArchivedVec<T::Archived>;
+        type Resolver = VecResolver;
+
+        fn resolve(&self, resolver: Self::Resolver, out: Place<Self::Archived>) {
+            ArchivedVec::resolve_from_len(self.len(), resolver, out);
+        }
+    }
+
+    impl<T, L: NodeLayout, S> Serialize<S> for List<T, L>
+    where
+        T: Serialize<S>,
+        S: Fallible + Allocator + Writer + ?Sized,
+    {
+        fn serialize(&self, serializer: &mut S) -> Result<Self::Resolver, S::Error> {
+            ArchivedVec::<T::Archived>::serialize_from_iter::<T, _, _>(self.iter(), serializer)
+        }
+    }
+
+    impl<T, L: NodeLayout, D> Deserialize<List<T, L>, D> for ArchivedVec<T::Archived>
+    where
+        T: Archive,
+        T::Archived: Deserialize<T, D>,
+        D: Fallible + ?Sized,
+    {
+        fn deserialize(&self, deserializer: &mut D) -> Result<List<T, L>, D::Error> {
+            let mut list = List::empty();
+            for elem in self.iter() {
+                list.push_back(elem.deserialize(deserializer)?);
+            }
+            Ok(list)
+        }
+    }
+
+    impl<T: PartialEq<U>, U, L: NodeLayout> PartialEq<List<U, L>> for ArchivedVec<T> {
+        fn eq(&self, other: &List<U, L>) -> bool {
+            self.len() == other.len() && self.iter().zip(other).all(|(a, b)| a == b)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn generate_test() -> List<i32> {
+        list_from(&[0, 1, 2, 3, 4, 5, 6])
+    }
+
+    fn list_from<T: Clone>(v: &[T]) -> List<T> {
+        v.iter().map(|x| (*x).clone()).collect()
+    }
+
+    #[test]
+    fn cycle_detection() {
+        let mut list = generate_test();
+        assert!(!list.has_cycle());
+        assert!(!list.remove_cycle());
+        // 整条链首尾相连
+        list.link_tail_to(0);
+        assert!(list.has_cycle());
+        assert_eq!(list.cycle_start(), Some(0));
+        assert!(list.remove_cycle());
+        list.assert_invariants();
+        assert_eq!(list, generate_test());
+
+        list.link_tail_to(5);
+        assert_eq!(list.cycle_start(), Some(5));
+        assert!(list.remove_cycle());
+        list.assert_invariants();
+        list.push_back(7);
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![0, 1, 2, 3, 4, 5, 6, 7]);
+    }
+
+    #[test]
+    fn test_basic_front() {
+        let mut list = List::new();
+
+        // 空链表
+        assert_eq!(list.len(), 0);
+        assert_eq!(list.pop_front(), None);
+        assert_eq!(list.len(), 0);
+
+        // 单个元素
+        list.push_front(10);
+        assert_eq!(list.len(), 1);
+        assert_eq!(list.pop_front(), Some(10));
+        assert_eq!(list.len(), 0);
+        assert_eq!(list.pop_front(), None);
+
+        // 多个元素
+        list.push_front(10);
+        list.push_front(20);
+        list.push_front(30);
+        assert_eq!(list.len(), 3);
+        assert_eq!(list.pop_front(), Some(30));
+        assert_eq!(list.pop_front(), Some(20));
+        list.push_front(40);
+        assert_eq!(list.pop_front(), Some(40));
+        assert_eq!(list.pop_front(), Some(10));
+        assert_eq!(list.pop_front(), None);
+        assert_eq!(list.len(), 0);
+    }
+
+    #[test]
+    fn test_basic_back() {
+        let mut list = List::new();
+        list.push_back(1);
+        list.push_back(2);
+        list.push_front(0);
+        assert_eq!(list.front(), Some(&0));
+        assert_eq!(list.back(), Some(&2));
+        *list.back_mut().unwrap() = 20;
+        assert_eq!(list.pop_back(), Some(20));
+        assert_eq!(list.pop_back(), Some(1));
+        assert_eq!(list.pop_back(), Some(0));
+        assert_eq!(list.pop_back(), None);
+        assert!(list.is_empty());
+    }
+
+    #[test]
+    fn test_iterator() {
+        let m = generate_test();
+        for (i, elt) in m.iter().enumerate() {
+            assert_eq!(i as i32, *elt);
+        }
+        let mut n = List::new();
+        assert_eq!(n.iter().next(), None);
+        n.push_front(4);
+        let mut it = n.iter();
+        assert_eq!(it.size_hint(), (1, Some(1)));
+        assert_eq!(it.next().unwrap(), &4);
+        assert_eq!(it.size_hint(), (0, Some(0)));
+        assert_eq!(it.next(), None);
+    }
+
+    #[test]
+    fn test_iterator_double_end() {
+        let mut n = List::new();
+        n.push_front(4);
+        n.push_front(5);
+        n.push_front(6);
+        let mut it = n.iter();
+        assert_eq!(it.size_hint(), (3, Some(3)));
+        assert_eq!(it.next().unwrap(), &6);
+        assert_eq!(it.size_hint(), (2, Some(2)));
+        assert_eq!(it.next_back().unwrap(), &4);
+        assert_eq!(it.size_hint(), (1, Some(1)));
+        assert_eq!(it.next_back().unwrap(), &5);
+        assert_eq!(it.next_back(), None);
+        assert_eq!(it.next(), None);
+    }
+
+    #[test]
+    fn test_iter_mut_and_into_iter() {
+        let mut m = generate_test();
+        for elt in m.iter_mut() {
+            *elt *= 10;
+        }
+        assert_eq!(m.iter().rev().copied().collect::<Vec<_>>(), vec![60, 50, 40, 30, 20, 10, 0]);
+
+        let mut it = m.into_iter();
+        assert_eq!(it.next(), Some(0));
+        assert_eq!(it.next_back(), Some(60));
+        assert_eq!(it.len(), 5);
+    }
+
+    #[test]
+    fn test_eq_ord_hash() {
+        use std::collections::hash_map::DefaultHasher;
+
+        let a = list_from(&[1, 2, 3]);
+        let b = a.clone();
+        assert_eq!(a, b);
+        assert!(list_from(&[1, 2]) < a);
+        assert!(list_from(&[1, 3]) > a);
+
+        let hash = |l: &List<i32>| {
+            let mut h = DefaultHasher::new();
+            l.hash(&mut h);
+            h.finish()
+        };
+        assert_eq!(hash(&a), hash(&b));
+        assert_eq!(format!("{:?}", a), "[1, 2, 3]");
+    }
+
+    #[test]
+    fn test_extend_borrowed() {
+        let mut list = list_from(&[1, 2]);
+        let more = vec![3, 4];
+        list.extend(&more);
+        list.extend([5, 6].iter());
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![1, 2, 3, 4, 5, 6]);
+        list.assert_invariants();
+
+        let mut words: List<String> = List::new();
+        words.extend_from_slice(&["a".to_string(), "b".to_string()]);
+        words.extend_from_slice(&[]);
+        assert_eq!(words.len(), 2);
+        assert_eq!(words.back().map(String::as_str), Some("b"));
+    }
+
+    #[test]
+    fn test_find_or_insert_by() {
+        let mut list = list_from(&[10, 20, 30]);
+
+        // 已存在: 游标停在该元素上，不插入
+        let mut cursor = list.find_or_insert_by(|x| x.cmp(&20), || unreachable!());
+        assert_eq!(cursor.index(), Some(1));
+        *cursor.current().unwrap() += 1;
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![10, 21, 30]);
+
+        // 不存在: 插入到第一个更大元素之前
+        let mut cursor = list.find_or_insert_by(|x| x.cmp(&25), || 25);
+        assert_eq!(cursor.index(), Some(2));
+        assert_eq!(cursor.current(), Some(&mut 25));
+        assert_eq!(cursor.peek_next(), Some(&mut 30));
+
+        // 比所有元素都大/小
+        list.find_or_insert_by(|x| x.cmp(&99), || 99);
+        let mut cursor = list.find_or_insert_by(|x| x.cmp(&1), || 1);
+        assert_eq!(cursor.index(), Some(0));
+        assert_eq!(cursor.peek_prev(), None);
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![1, 10, 21, 25, 30, 99]);
+        list.assert_invariants();
+
+        let mut empty: List<(u32, &str)> = List::new();
+        let cursor = empty.find_or_insert_by(|(k, _)| k.cmp(&7), || (7, "seven"));
+        assert_eq!(cursor.index(), Some(0));
+        assert_eq!(empty.front(), Some(&(7, "seven")));
+    }
+
+    #[test]
+    fn test_split_into() {
+        let parts = list_from(&[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]).split_into(3);
+        let lens: Vec<_> = parts.iter().map(List::len).collect();
+        assert_eq!(lens, vec![4, 3, 3]);
+        for part in &parts {
+            part.assert_invariants();
+        }
+        let rejoined: Vec<i32> = parts.into_iter().flatten().collect();
+        assert_eq!(rejoined, (0..10).collect::<Vec<_>>());
+
+        // 份数比元素多
+        let parts = list_from(&[1, 2]).split_into(4);
+        assert_eq!(parts.iter().map(List::len).collect::<Vec<_>>(), vec![1, 1, 0, 0]);
+        parts.iter().for_each(List::assert_invariants);
+
+        let parts = list_from(&[1, 2, 3]).split_into(1);
+        assert_eq!(parts[0], list_from(&[1, 2, 3]));
+    }
+
+    #[test]
+    fn test_try_split_off() {
+        let mut list = list_from(&[0, 1, 2, 3, 4]);
+        let tail = list.try_split_off(3).unwrap();
+        list.assert_invariants();
+        tail.assert_invariants();
+        assert_eq!(list, list_from(&[0, 1, 2]));
+        assert_eq!(tail, list_from(&[3, 4]));
+
+        assert_eq!(list.split_off(3), List::new());
+        assert_eq!(list.split_off(0), list_from(&[0, 1, 2]));
+        assert!(list.is_empty());
+        assert_eq!(list.try_split_off(1), Err(ListError::OutOfRange { index: 1, len: 0 }));
+
+        let mut list = List::new();
+        list.try_push_back(2).unwrap();
+        list.try_push_front(1).unwrap();
+        list.assert_invariants();
+        assert_eq!(list, list_from(&[1, 2]));
+    }
+
+    #[test]
+    fn test_split_into_threads() {
+        let list: List<u64> = (1..=1000).collect();
+        let sums: Vec<u64> = std::thread::scope(|s| {
+            let handles: Vec<_> = list
+                .split_into(4)
+                .into_iter()
+                .map(|part| s.spawn(move || part.iter().sum::<u64>()))
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+        assert_eq!(sums.iter().sum::<u64>(), 500500);
+    }
+
+    #[test]
+    #[should_panic(expected = "at least one part")]
+    fn test_split_into_zero() {
+        list_from(&[1]).split_into(0);
+    }
+
+    #[test]
+    fn test_std_conversions() {
+        let std_list: LinkedList<i32> = (0..5).collect();
+        let list: List<i32> = List::from(std_list.clone());
+        list.assert_invariants();
+        assert!(list.iter().eq(std_list.iter()));
+        assert_eq!(LinkedList::from(list), std_list);
+
+        let mut deque: VecDeque<&str> = VecDeque::new();
+        deque.push_back("b");
+        deque.push_front("a");
+        let list: List<&str, CacheAligned> = deque.clone().into();
+        assert_eq!(list.front(), Some(&"a"));
+        let back: VecDeque<&str> = list.into();
+        assert_eq!(back, deque);
+    }
+
+    #[test]
+    fn test_borrow_search() {
+        let mut list: List<String> = ["apple", "banana", "cherry", "banana"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert!(list.contains("banana"));
+        assert!(!list.contains("durian"));
+        assert_eq!(list.find("cherry").map(String::len), Some(6));
+        list.find_mut("apple").unwrap().push_str(" pie");
+        assert!(list.contains("apple pie"));
+
+        // 只删除第一个匹配项
+        assert_eq!(list.remove_first("banana").as_deref(), Some("banana"));
+        assert_eq!(list.remove_first("durian"), None);
+        assert_eq!(list.len(), 3);
+        assert_eq!(list.back().map(String::as_str), Some("banana"));
+        list.assert_invariants();
+
+        let mut nums = list_from(&[1, 2, 3]);
+        assert_eq!(nums.remove_first(&3), Some(3));
+        assert_eq!(nums.remove_first(&1), Some(1));
+        assert_eq!(nums.front(), Some(&2));
+        nums.assert_invariants();
+    }
+
+    #[test]
+    fn test_rotate() {
+        let mut list = list_from(&[0, 1, 2, 3, 4]);
+        list.rotate_left(2);
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![2, 3, 4, 0, 1]);
+        list.rotate_left(4);
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![1, 2, 3, 4, 0]);
+        list.rotate_right(1);
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![0, 1, 2, 3, 4]);
+        list.rotate_left(0);
+        list.rotate_right(5);
+        assert_eq!(list.front(), Some(&0));
+        list.assert_invariants();
+
+        let mut single = list_from(&[7]);
+        single.rotate_left(1);
+        assert_eq!(single.front(), Some(&7));
+    }
+
+    #[test]
+    fn test_rotate_until_while() {
+        let mut list = list_from(&[1, 3, 5, 6, 7]);
+        assert_eq!(list.rotate_until(|x| x % 2 == 0), Some(3));
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![6, 7, 1, 3, 5]);
+        list.assert_invariants();
+
+        // 队头已经满足条件
+        assert_eq!(list.rotate_until(|x| *x == 6), Some(0));
+        // 没有满足条件的元素，链表不变
+        assert_eq!(list.rotate_until(|x| *x > 100), None);
+        assert_eq!(list.front(), Some(&6));
+
+        assert_eq!(list.rotate_while(|x| *x >= 6), Some(2));
+        assert_eq!(list.front(), Some(&1));
+        list.assert_invariants();
+
+        let mut empty: List<i32> = List::new();
+        assert_eq!(empty.rotate_until(|_| true), None);
+    }
+
+    #[test]
+    fn test_round_robin_weighted() {
+        // (任务名, 权重, 已服务次数)
+        let mut run_queue: List<(char, usize, usize)> =
+            vec![('a', 2, 0), ('b', 1, 0), ('c', 0, 0), ('d', 3, 0)].into_iter().collect();
+        let mut order = String::new();
+        let rotated = run_queue.round_robin_weighted(
+            8,
+            |task| task.1,
+            |task| {
+                task.2 += 1;
+                order.push(task.0);
+            },
+        );
+        // a a b (c跳过) d d d a a
+        assert_eq!(order, "aabdddaa");
+        assert_eq!(rotated, 5);
+        assert_eq!(run_queue.front().map(|t| t.0), Some('b'));
+        run_queue.assert_invariants();
+
+        // 配额中途用完时元素留在队头
+        let mut order = String::new();
+        run_queue.round_robin_weighted(2, |t| t.1, |t| order.push(t.0));
+        assert_eq!(order, "bd");
+        assert_eq!(run_queue.front().map(|t| t.0), Some('d'));
+
+        // 所有权重都是0时不会死循环
+        let mut idle: List<usize> = list_from(&[0, 0]);
+        assert_eq!(idle.round_robin_weighted(10, |w| *w, |_| unreachable!()), 2);
+    }
+
+    #[test]
+    fn test_append() {
+        let mut a = list_from(&[1, 2]);
+        let mut b = list_from(&[3, 4]);
+        a.append(&mut b);
+        assert!(b.is_empty());
+        assert_eq!(a, list_from(&[1, 2, 3, 4]));
+        a.assert_invariants();
+        b.assert_invariants();
+
+        let mut empty = List::new();
+        empty.append(&mut a);
+        assert_eq!(empty.len(), 4);
+        empty.assert_invariants();
+    }
+
+    #[test]
+    fn test_cursor_move_peek() {
+        let mut m = list_from(&[1, 2, 3, 4, 5, 6]);
+        let mut cursor = m.cursor_mut();
+        cursor.move_next();
+        assert_eq!(cursor.current(), Some(&mut 1));
+        assert_eq!(cursor.peek_next(), Some(&mut 2));
+        assert_eq!(cursor.peek_prev(), None);
+        assert_eq!(cursor.index(), Some(0));
+        cursor.move_prev();
+        assert_eq!(cursor.current(), None);
+        assert_eq!(cursor.peek_next(), Some(&mut 1));
+        assert_eq!(cursor.peek_prev(), Some(&mut 6));
+        assert_eq!(cursor.index(), None);
+        cursor.move_next();
+        cursor.move_next();
+        assert_eq!(cursor.current(), Some(&mut 2));
+        assert_eq!(cursor.index(), Some(1));
+
+        let mut cursor = m.cursor_mut();
+        cursor.move_prev();
+        assert_eq!(cursor.current(), Some(&mut 6));
+        assert_eq!(cursor.peek_next(), None);
+        assert_eq!(cursor.index(), Some(5));
+        cursor.move_next();
+        assert_eq!(cursor.current(), None);
+    }
+
+    #[test]
+    fn test_cursor_insert_remove() {
+        let mut m = list_from(&[1, 3]);
+        let mut cursor = m.cursor_mut();
+        cursor.move_next();
+        cursor.insert_after(2);
+        cursor.insert_before(0);
+        assert_eq!(cursor.index(), Some(1));
+        assert_eq!(cursor.remove_current(), Some(1));
+        assert_eq!(cursor.current(), Some(&mut 2));
+        m.assert_invariants();
+        assert_eq!(m, list_from(&[0, 2, 3]));
+
+        let mut cursor = m.cursor_mut();
+        cursor.move_prev();
+        assert_eq!(cursor.remove_current(), Some(3));
+        assert_eq!(cursor.current(), None);
+        cursor.insert_before(4);
+        cursor.insert_after(-1);
+        m.assert_invariants();
+        assert_eq!(m, list_from(&[-1, 0, 2, 4]));
+    }
+
+    #[test]
+    fn test_cursor_mut_insert() {
+        let mut m = list_from(&[1, 2, 3, 4, 5, 6]);
+        let mut cursor = m.cursor_mut();
+        cursor.move_next();
+        cursor.splice_before(Some(7).into_iter().collect());
+        cursor.splice_after(Some(8).into_iter().collect());
+        assert_eq!(m.iter().cloned().collect::<Vec<_>>(), &[7, 1, 8, 2, 3, 4, 5, 6]);
+        let mut cursor = m.cursor_mut();
+        cursor.move_next();
+        cursor.move_prev();
+        cursor.splice_before(Some(9).into_iter().collect());
+        cursor.splice_after(Some(10).into_iter().collect());
+        assert_eq!(m.iter().cloned().collect::<Vec<_>>(), &[10, 7, 1, 8, 2, 3, 4, 5, 6, 9]);
+        m.assert_invariants();
+
+        let mut cursor = m.cursor_mut();
+        cursor.move_next();
+        cursor.move_next();
+        cursor.move_next();
+        let tmp = cursor.split_before();
+        assert_eq!(tmp.into_iter().collect::<Vec<_>>(), &[10, 7]);
+        m.assert_invariants();
+        let mut cursor = m.cursor_mut();
+        cursor.move_next();
+        cursor.move_next();
+        let tmp = cursor.split_after();
+        assert_eq!(tmp.into_iter().collect::<Vec<_>>(), &[2, 3, 4, 5, 6, 9]);
+        assert_eq!(m.iter().cloned().collect::<Vec<_>>(), &[1, 8]);
+        m.assert_invariants();
+    }
+
+    #[test]
+    fn test_invariants_hold_through_mutations() {
+        let mut list = List::new();
+        list.assert_invariants();
+        for i in 0..10 {
+            if i % 2 == 0 {
+                list.push_front(i);
+            } else {
+                list.push_back(i);
+            }
+            list.assert_invariants();
+        }
+        while list.len() > 2 {
+            list.pop_front();
+            list.pop_back();
+            list.assert_invariants();
+        }
+        list.clear();
+        list.assert_invariants();
+    }
+
+    // 人为破坏结构后检查必须报错，检查完再修复，保证链表drop时依然安全
+    fn invariant_panic_message(list: &List<i32>) -> String {
+        let err = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| list.assert_invariants()))
+            .expect_err("corrupted list passed assert_invariants");
+        match err.downcast_ref::<&str>() {
+            Some(msg) => msg.to_string(),
+            None => err.downcast_ref::<String>().cloned().unwrap_or_default(),
+        }
+    }
+
+    #[test]
+    fn test_invariants_detect_bad_len() {
+        let mut list = list_from(&[1, 2, 3]);
+        list.len = 4;
+        assert!(invariant_panic_message(&list).contains("cached len does not match node count"));
+        list.len = 3;
+    }
+
+    #[test]
+    fn test_invariants_detect_broken_prev() {
+        let list = list_from(&[1, 2, 3]);
+        unsafe {
+            let tail = list.tail.unwrap();
+            let old_prev = (*tail.as_ptr()).prev;
+            (*tail.as_ptr()).prev = list.head;
+            assert!(invariant_panic_message(&list).contains("prev pointer that does not point back"));
+            (*tail.as_ptr()).prev = old_prev;
+        }
+        list.assert_invariants();
+    }
+
+    #[test]
+    fn test_invariants_detect_dangling_tail() {
+        let mut list = list_from(&[1, 2]);
+        let old_tail = list.tail;
+        list.tail = list.head;
+        assert!(invariant_panic_message(&list).contains("tail.next must be None"));
+        list.tail = old_tail;
+    }
+
+    #[test]
+    fn test_node_layout_sizes() {
+        // Compact: 两个指针 + u8，对齐到指针
+        assert_eq!(List::<u8>::node_align(), std::mem::align_of::<usize>());
+        assert_eq!(List::<u8>::node_size(), 3 * std::mem::size_of::<usize>());
+        // CacheAligned: 小元素和指针一起塞进一条缓存行
+        assert_eq!(List::<u8, CacheAligned>::node_align(), 64);
+        assert_eq!(List::<u8, CacheAligned>::node_size(), 64);
+        assert_eq!(List::<[u8; 48], CacheAligned>::node_size(), 64);
+        // 放不下时向上取整到两条缓存行
+        assert_eq!(List::<[u8; 49], CacheAligned>::node_size(), 128);
+    }
+
+    #[test]
+    fn test_cache_aligned_list() {
+        let mut list = List::with_layout(CacheAligned);
+        for i in 0..16 {
+            list.push_back(i);
+        }
+        list.push_front(-1);
+        // 每个节点都从缓存行起点开始，所以元素在行内的偏移全部相同
+        let offsets: std::collections::HashSet<usize> =
+            list.iter().map(|elem| elem as *const i32 as usize % 64).collect();
+        assert_eq!(offsets.len(), 1);
+        let mut cursor = list.cursor_mut();
+        cursor.move_next();
+        cursor.move_next();
+        let tail = cursor.split_after();
+        assert_eq!(tail.len(), 15);
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![-1, 0]);
+        list.assert_invariants();
+        tail.assert_invariants();
+
+        let other: List<i32, CacheAligned> = (0..3).collect();
+        assert_eq!(other, other.clone());
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_stream_into_iter() {
+        use futures_core::Stream;
+        use std::pin::Pin;
+        use std::task::{Context, Poll, Waker};
+
+        let mut cx = Context::from_waker(Waker::noop());
+        let list: List<i32> = (1..=3).collect();
+        let mut stream = list.into_iter();
+        assert_eq!(Stream::size_hint(&stream), (3, Some(3)));
+        let mut out = Vec::new();
+        while let Poll::Ready(Some(x)) = Pin::new(&mut stream).poll_next(&mut cx) {
+            out.push(x);
+        }
+        assert_eq!(out, vec![1, 2, 3]);
+        assert_eq!(Pin::new(&mut stream).poll_next(&mut cx), Poll::Ready(None));
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_poll_pop_front() {
+        use std::task::{Context, Poll, Waker};
+
+        let mut cx = Context::from_waker(Waker::noop());
+        let mut list = List::new();
+        assert_eq!(list.poll_pop_front(&mut cx), Poll::Ready(None));
+        list.push_back("a");
+        list.push_back("b");
+        assert_eq!(list.poll_pop_front(&mut cx), Poll::Ready(Some("a")));
+        assert_eq!(list.len(), 1);
+    }
+
+    #[cfg(feature = "rkyv")]
+    #[test]
+    fn test_rkyv_round_trip() {
+        use rkyv::rancor::Error;
+        use rkyv::vec::ArchivedVec;
+        use rkyv::Archived;
+
+        let mut list = List::new();
+        for x in 0..10 {
+            list.push_back(x);
+        }
+        list.push_front(-1);
+
+        let bytes = rkyv::to_bytes::<Error>(&list).unwrap();
+        // 零拷贝访问: 归档数据就是一段连续的切片
+        let archived = rkyv::access::<ArchivedVec<Archived<i32>>, Error>(&bytes).unwrap();
+        assert_eq!(archived.len(), 11);
+        assert_eq!(archived.as_slice()[0], -1);
+        assert!(archived.iter().map(|x| x.to_native()).eq(list.iter().copied()));
+        assert!(*archived == list);
+
+        let back: List<i32> = rkyv::deserialize::<List<i32>, Error>(archived).unwrap();
+        assert_eq!(back, list);
+        back.assert_invariants();
+    }
+
+    #[cfg(feature = "rkyv")]
+    #[test]
+    fn test_rkyv_nested_and_empty() {
+        use rkyv::rancor::Error;
+
+        let empty: List<String> = List::new();
+        let bytes = rkyv::to_bytes::<Error>(&empty).unwrap();
+        let back: List<String> = rkyv::from_bytes::<List<String>, Error>(&bytes).unwrap();
+        assert!(back.is_empty());
+
+        let words: List<String> = ["linked", "lists", "are", "fun"].iter().map(|s| s.to_string()).collect();
+        let bytes = rkyv::to_bytes::<Error>(&words).unwrap();
+        let back: List<String> = rkyv::from_bytes::<List<String>, Error>(&bytes).unwrap();
+        assert_eq!(back, words);
+    }
+
+    // 切分、拼接、旋转只改指针，不分配也不释放节点
+    #[test]
+    fn relinking_does_not_allocate() {
+        use crate::alloc_counter::{measure, AllocStats};
+        let mut list: List<i32> = (0..100).collect();
+        let ((), stats) = measure(|| {
+            let mut cursor = list.cursor_mut();
+            for _ in 0..=50 {
+                cursor.move_next();
+            }
+            let mut front = cursor.split_before();
+            list.append(&mut front);
+            list.rotate_left(30);
+            list.rotate_right(80);
+        });
+        assert_eq!(stats, AllocStats::default());
+        assert!(list.iter().copied().eq(0..100));
+    }
+
+    #[cfg(feature = "visualize")]
+    #[test]
+    fn to_dot_draws_prev_and_tail() {
+        let list = list_from(&[1, 2, 3]);
+        let dot = list.to_dot();
+        assert!(dot.contains("label=\"List len=3\""));
+        assert_eq!(dot.matches("[label=\"next\"").count(), 2);
+        assert!(dot.contains("n2 -> n1 [label=\"prev\", style=dashed];"));
+        assert!(dot.contains("root0 -> n2 [label=\"tail\", style=dashed];"));
+        assert_eq!(List::<i32>::new().to_dot().matches("->").count(), 0);
+    }
+
+    #[cfg(feature = "metrics")]
+    #[test]
+    fn metrics_count_operations() {
+        use crate::metrics::Metrics;
+
+        let mut list: List<i32> = (0..6).collect();
+        assert_eq!(list.metrics().allocations, 6);
+        list.reset_metrics();
+
+        // 切分点离尾部更近，从tail往回走1步
+        list.rotate_left(4);
+        assert_eq!(list.metrics().traversal_steps, 1);
+        // remove_first用游标查找: 4、5、0各一步
+        assert_eq!(list.remove_first(&0), Some(0));
+        let mut cursor = list.cursor_mut();
+        cursor.move_next();
+        cursor.insert_after(7);
+        assert_eq!(list.iter().rev().count(), 6);
+        list.clear();
+        assert_eq!(
+            list.metrics(),
+            Metrics { pushes: 1, pops: 7, traversal_steps: 11, allocations: 1 }
+        );
+    }
+}
@@ -0,0 +1,331 @@
+// 固定地址的双端队列
+// List的每个元素都住在独立的堆节点里，push/pop其他元素、拼接、拆分都只改指针，元素本身从不移动
+// PinnedList利用这一点对外提供Pin<&mut T>，让future、侵入式状态机这类!Unpin的值可以放进链表
+// 为了遵守Pin的约定，PinnedList保证:
+// 1. 不对外暴露&mut T
+// 2. !Unpin的元素不能被pop出来，只能原地析构(drop_front/drop_back/drop_current)
+// 3. 链表销毁时元素也在节点里原地析构
+
+use std::pin::Pin;
+
+use super::{Compact, CursorMut, Iter, IterMut, List, NodeLayout};
+
+pub struct PinnedList<T, L: NodeLayout = Compact> {
+    list: List<T, L>,
+}
+
+impl<T> PinnedList<T> {
+    pub fn new() -> Self {
+        PinnedList { list: List::new() }
+    }
+}
+
+impl<T> Default for PinnedList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// 元素还没有被Pin过，直接接管整条链表是安全的
+impl<T, L: NodeLayout> From<List<T, L>> for PinnedList<T, L> {
+    fn from(list: List<T, L>) -> Self {
+        PinnedList { list }
+    }
+}
+
+impl<T, L: NodeLayout> PinnedList<T, L> {
+    pub fn with_layout(layout: L) -> Self {
+        PinnedList {
+            list: List::with_layout(layout),
+        }
+    }
+
+    // 放进来之前元素还没有被Pin，移动进节点没有问题
+    pub fn push_front(&mut self, elem: T) {
+        self.list.push_front(elem);
+    }
+
+    pub fn push_back(&mut self, elem: T) {
+        self.list.push_back(elem);
+    }
+
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        self.list.front()
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        self.list.back()
+    }
+
+    pub fn front_mut(&mut self) -> Option<Pin<&mut T>> {
+        // SAFETY: 节点地址稳定，且PinnedList不会再移动这个元素
+        self.list.front_mut().map(|elem| unsafe { Pin::new_unchecked(elem) })
+    }
+
+    pub fn back_mut(&mut self) -> Option<Pin<&mut T>> {
+        self.list.back_mut().map(|elem| unsafe { Pin::new_unchecked(elem) })
+    }
+
+    pub fn iter(&self) -> Iter<'_, T, L> {
+        self.list.iter()
+    }
+
+    pub fn pinned_iter_mut(&mut self) -> PinnedIterMut<'_, T, L> {
+        PinnedIterMut {
+            inner: self.list.iter_mut(),
+        }
+    }
+
+    pub fn pinned_cursor_mut(&mut self) -> PinnedCursorMut<'_, T, L> {
+        PinnedCursorMut {
+            inner: self.list.cursor_mut(),
+        }
+    }
+
+    // 在节点里原地析构队头元素，返回是否真的删掉了一个元素
+    pub fn drop_front(&mut self) -> bool {
+        self.list.unlink_front().is_some()
+    }
+
+    pub fn drop_back(&mut self) -> bool {
+        self.list.unlink_back().is_some()
+    }
+
+    pub fn clear(&mut self) {
+        self.list.clear();
+    }
+
+    // 只移动节点指针，元素地址不变
+    pub fn append(&mut self, other: &mut Self) {
+        self.list.append(&mut other.list);
+    }
+}
+
+// Unpin的元素不受Pin约束，可以像普通链表一样取出
+impl<T: Unpin, L: NodeLayout> PinnedList<T, L> {
+    pub fn pop_front(&mut self) -> Option<T> {
+        self.list.pop_front()
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        self.list.pop_back()
+    }
+
+    pub fn into_list(self) -> List<T, L> {
+        self.list
+    }
+}
+
+impl<T, L: NodeLayout> Extend<T> for PinnedList<T, L> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        self.list.extend(iter);
+    }
+}
+
+pub struct PinnedIterMut<'a, T, L: NodeLayout = Compact> {
+    inner: IterMut<'a, T, L>,
+}
+
+impl<'a, T, L: NodeLayout> Iterator for PinnedIterMut<'a, T, L> {
+    type Item = Pin<&'a mut T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // SAFETY: 同front_mut
+        self.inner.next().map(|elem| unsafe { Pin::new_unchecked(elem) })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<T, L: NodeLayout> DoubleEndedIterator for PinnedIterMut<'_, T, L> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|elem| unsafe { Pin::new_unchecked(elem) })
+    }
+}
+
+impl<T, L: NodeLayout> ExactSizeIterator for PinnedIterMut<'_, T, L> {}
+
+// 只暴露不会移动元素的游标操作
+pub struct PinnedCursorMut<'a, T, L: NodeLayout = Compact> {
+    inner: CursorMut<'a, T, L>,
+}
+
+impl<T, L: NodeLayout> PinnedCursorMut<'_, T, L> {
+    pub fn index(&self) -> Option<usize> {
+        self.inner.index()
+    }
+
+    pub fn move_next(&mut self) {
+        self.inner.move_next();
+    }
+
+    pub fn move_prev(&mut self) {
+        self.inner.move_prev();
+    }
+
+    pub fn current(&mut self) -> Option<Pin<&mut T>> {
+        self.inner.current().map(|elem| unsafe { Pin::new_unchecked(elem) })
+    }
+
+    pub fn peek_next(&mut self) -> Option<Pin<&mut T>> {
+        self.inner.peek_next().map(|elem| unsafe { Pin::new_unchecked(elem) })
+    }
+
+    pub fn peek_prev(&mut self) -> Option<Pin<&mut T>> {
+        self.inner.peek_prev().map(|elem| unsafe { Pin::new_unchecked(elem) })
+    }
+
+    pub fn insert_before(&mut self, elem: T) {
+        self.inner.insert_before(elem);
+    }
+
+    pub fn insert_after(&mut self, elem: T) {
+        self.inner.insert_after(elem);
+    }
+
+    // 原地析构当前元素，游标移到下一个节点
+    pub fn drop_current(&mut self) -> bool {
+        self.inner.unlink_current().is_some()
+    }
+}
+
+impl<T: Unpin, L: NodeLayout> PinnedCursorMut<'_, T, L> {
+    pub fn remove_current(&mut self) -> Option<T> {
+        self.inner.remove_current()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::future::Future;
+    use std::marker::PhantomPinned;
+    use std::rc::Rc;
+    use std::task::{Context, Poll, Waker};
+
+    // 第一次被Pin访问时记下自己的地址，之后每次访问以及析构时都检查地址没变
+    struct SelfAware {
+        id: u32,
+        addr: usize,
+        drops: Rc<Cell<u32>>,
+        _pin: PhantomPinned,
+    }
+
+    impl SelfAware {
+        fn new(id: u32, drops: &Rc<Cell<u32>>) -> Self {
+            SelfAware {
+                id,
+                addr: 0,
+                drops: Rc::clone(drops),
+                _pin: PhantomPinned,
+            }
+        }
+
+        fn touch(self: Pin<&mut Self>) -> u32 {
+            let addr = &*self as *const Self as usize;
+            // SAFETY: 只修改普通字段，不移动self
+            let this = unsafe { self.get_unchecked_mut() };
+            if this.addr == 0 {
+                this.addr = addr;
+            }
+            assert_eq!(this.addr, addr, "element {} moved after being pinned", this.id);
+            this.id
+        }
+    }
+
+    impl Drop for SelfAware {
+        fn drop(&mut self) {
+            if self.addr != 0 {
+                assert_eq!(self.addr, self as *const Self as usize, "element {} dropped after moving", self.id);
+            }
+            self.drops.set(self.drops.get() + 1);
+        }
+    }
+
+    #[test]
+    fn elements_never_move() {
+        let drops = Rc::new(Cell::new(0));
+        let mut list = PinnedList::new();
+        for id in 1..=4 {
+            list.push_back(SelfAware::new(id, &drops));
+        }
+        let ids: Vec<u32> = list.pinned_iter_mut().map(SelfAware::touch).collect();
+        assert_eq!(ids, vec![1, 2, 3, 4]);
+
+        // 其他位置的增删不影响已经Pin住的元素
+        list.push_front(SelfAware::new(0, &drops));
+        list.push_back(SelfAware::new(5, &drops));
+        assert!(list.drop_front());
+        assert_eq!(drops.get(), 1);
+        let mut other = PinnedList::new();
+        other.push_back(SelfAware::new(6, &drops));
+        list.append(&mut other);
+
+        let ids: Vec<u32> = list.pinned_iter_mut().rev().map(SelfAware::touch).collect();
+        assert_eq!(ids, vec![6, 5, 4, 3, 2, 1]);
+        assert_eq!(list.front_mut().map(SelfAware::touch), Some(1));
+        assert_eq!(list.back_mut().map(SelfAware::touch), Some(6));
+
+        // 游标删除同样原地析构
+        let mut cursor = list.pinned_cursor_mut();
+        cursor.move_next();
+        cursor.move_next();
+        assert_eq!(cursor.current().map(SelfAware::touch), Some(2));
+        assert!(cursor.drop_current());
+        assert_eq!(cursor.current().map(SelfAware::touch), Some(3));
+        cursor.insert_before(SelfAware::new(7, &drops));
+        assert_eq!(cursor.peek_prev().map(SelfAware::touch), Some(7));
+        assert_eq!(drops.get(), 2);
+
+        assert!(list.drop_back());
+        assert_eq!(list.len(), 5);
+        drop(list);
+        assert_eq!(drops.get(), 8);
+    }
+
+    async fn double(x: u32) -> u32 {
+        x * 2
+    }
+
+    #[test]
+    fn futures_can_be_polled_in_place() {
+        let mut list = PinnedList::new();
+        for x in 1..=3 {
+            list.push_back(double(x));
+        }
+        let mut cx = Context::from_waker(Waker::noop());
+        let results: Vec<u32> = list
+            .pinned_iter_mut()
+            .map(|fut| match fut.poll(&mut cx) {
+                Poll::Ready(v) => v,
+                Poll::Pending => unreachable!(),
+            })
+            .collect();
+        assert_eq!(results, vec![2, 4, 6]);
+        list.clear();
+        assert!(list.is_empty());
+    }
+
+    #[test]
+    fn unpin_elements_can_be_taken_out() {
+        let mut list: PinnedList<i32> = (1..=3).collect::<List<_>>().into();
+        *list.front_mut().unwrap() += 10;
+        assert_eq!(list.pop_front(), Some(11));
+        assert_eq!(list.pop_back(), Some(3));
+        let mut cursor = list.pinned_cursor_mut();
+        cursor.move_next();
+        assert_eq!(cursor.remove_current(), Some(2));
+        assert!(list.into_list().is_empty());
+    }
+}
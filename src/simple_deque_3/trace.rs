@@ -0,0 +1,341 @@
+// 操作录制与回放
+// TracedList包装一条List，每次修改都把操作和元素的Debug表示记到Trace里
+// Trace可以序列化成JSON保存下来，之后在一条全新的链表上回放，用来复现线上的bug序列
+// 回放时pop类操作会和录制时的结果比对，第一次出现分歧就报告是哪一步
+
+use std::fmt::{self, Debug};
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use super::{Compact, List, NodeLayout};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Op {
+    PushFront { elem: String },
+    PushBack { elem: String },
+    // result是pop出来的元素的Debug表示，回放时用来校验
+    PopFront { result: Option<String> },
+    PopBack { result: Option<String> },
+    RotateLeft { k: usize },
+    RotateRight { k: usize },
+    Clear,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Trace {
+    pub ops: Vec<Op>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ReplayError {
+    // 第step步的元素表示无法还原成T
+    Parse { step: usize, repr: String },
+    // 第step步pop出来的结果和录制时不同
+    Diverged {
+        step: usize,
+        expected: Option<String>,
+        actual: Option<String>,
+    },
+    // 第step步的操作在当时的链表上做不了(比如轮转的步数超过长度)，len是当时的长度
+    InvalidOp { step: usize, op: Op, len: usize },
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::Parse { step, repr } => {
+                write!(f, "step {}: cannot parse element {:?}", step, repr)
+            }
+            ReplayError::Diverged { step, expected, actual } => write!(
+                f,
+                "step {}: expected {:?} but replay produced {:?}",
+                step, expected, actual
+            ),
+            ReplayError::InvalidOp { step, op, len } => {
+                write!(f, "step {}: cannot apply {:?} to a list of length {}", step, op, len)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl Trace {
+    pub fn new() -> Self {
+        Trace { ops: Vec::new() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.ops.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+
+    // 用parse把元素的Debug表示还原成T，在新链表上依次回放所有操作
+    pub fn replay_with<T, F>(&self, mut parse: F) -> Result<List<T>, ReplayError>
+    where
+        T: Debug,
+        F: FnMut(&str) -> Option<T>,
+    {
+        let mut list = List::new();
+        for (step, op) in self.ops.iter().enumerate() {
+            let mut parse_elem = |repr: &String| {
+                parse(repr).ok_or_else(|| ReplayError::Parse {
+                    step,
+                    repr: repr.clone(),
+                })
+            };
+            match op {
+                Op::PushFront { elem } => list.push_front(parse_elem(elem)?),
+                Op::PushBack { elem } => list.push_back(parse_elem(elem)?),
+                Op::PopFront { result } => check(step, result, list.pop_front())?,
+                Op::PopBack { result } => check(step, result, list.pop_back())?,
+                // 手改过的trace里k可能超过长度，rotate_*会panic，这里先检查
+                Op::RotateLeft { k } | Op::RotateRight { k } if *k > list.len() => {
+                    return Err(ReplayError::InvalidOp {
+                        step,
+                        op: op.clone(),
+                        len: list.len(),
+                    });
+                }
+                Op::RotateLeft { k } => list.rotate_left(*k),
+                Op::RotateRight { k } => list.rotate_right(*k),
+                Op::Clear => list.clear(),
+            }
+        }
+        Ok(list)
+    }
+
+    // 元素类型实现了FromStr时的便捷版本，适用于整数这类Debug和FromStr格式一致的类型
+    pub fn replay<T>(&self) -> Result<List<T>, ReplayError>
+    where
+        T: Debug + FromStr,
+    {
+        self.replay_with(|repr| repr.parse().ok())
+    }
+}
+
+fn check<T: Debug>(step: usize, expected: &Option<String>, actual: Option<T>) -> Result<(), ReplayError> {
+    let actual = actual.map(|elem| format!("{:?}", elem));
+    if *expected == actual {
+        Ok(())
+    } else {
+        Err(ReplayError::Diverged {
+            step,
+            expected: expected.clone(),
+            actual,
+        })
+    }
+}
+
+// 带录制功能的链表，只暴露会被录制的修改操作，读操作通过list()拿到内部链表
+pub struct TracedList<T, L: NodeLayout = Compact> {
+    list: List<T, L>,
+    trace: Trace,
+}
+
+impl<T: Debug> TracedList<T> {
+    pub fn new() -> Self {
+        TracedList {
+            list: List::new(),
+            trace: Trace::new(),
+        }
+    }
+}
+
+impl<T: Debug> Default for TracedList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Debug, L: NodeLayout> TracedList<T, L> {
+    pub fn push_front(&mut self, elem: T) {
+        self.trace.ops.push(Op::PushFront {
+            elem: format!("{:?}", elem),
+        });
+        self.list.push_front(elem);
+    }
+
+    pub fn push_back(&mut self, elem: T) {
+        self.trace.ops.push(Op::PushBack {
+            elem: format!("{:?}", elem),
+        });
+        self.list.push_back(elem);
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        let elem = self.list.pop_front();
+        self.trace.ops.push(Op::PopFront {
+            result: elem.as_ref().map(|e| format!("{:?}", e)),
+        });
+        elem
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        let elem = self.list.pop_back();
+        self.trace.ops.push(Op::PopBack {
+            result: elem.as_ref().map(|e| format!("{:?}", e)),
+        });
+        elem
+    }
+
+    pub fn rotate_left(&mut self, k: usize) {
+        self.list.rotate_left(k);
+        self.trace.ops.push(Op::RotateLeft { k });
+    }
+
+    pub fn rotate_right(&mut self, k: usize) {
+        self.list.rotate_right(k);
+        self.trace.ops.push(Op::RotateRight { k });
+    }
+
+    pub fn clear(&mut self) {
+        self.list.clear();
+        self.trace.ops.push(Op::Clear);
+    }
+
+    pub fn list(&self) -> &List<T, L> {
+        &self.list
+    }
+
+    pub fn trace(&self) -> &Trace {
+        &self.trace
+    }
+
+    // 取走目前为止的录制结果，之后重新开始录制
+    pub fn take_trace(&mut self) -> Trace {
+        std::mem::take(&mut self.trace)
+    }
+
+    pub fn into_parts(self) -> (List<T, L>, Trace) {
+        (self.list, self.trace)
+    }
+}
+
+impl<T: Debug, L: NodeLayout> Extend<T> for TracedList<T, L> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for elem in iter {
+            self.push_back(elem);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record_and_replay() {
+        let mut traced = TracedList::new();
+        traced.extend([1, 2, 3]);
+        traced.push_front(0);
+        assert_eq!(traced.pop_back(), Some(3));
+        traced.rotate_left(1);
+        assert_eq!(traced.pop_front(), Some(1));
+        traced.push_back(9);
+        assert_eq!(traced.trace().len(), 8);
+
+        let json = traced.trace().to_json().unwrap();
+        let trace = Trace::from_json(&json).unwrap();
+        assert_eq!(&trace, traced.trace());
+
+        let replayed: List<i32> = trace.replay().unwrap();
+        assert_eq!(&replayed, traced.list());
+        replayed.assert_invariants();
+    }
+
+    #[test]
+    fn json_shape() {
+        let mut traced = TracedList::new();
+        traced.push_back("x");
+        traced.pop_front();
+        traced.clear();
+        let value: serde_json::Value = serde_json::from_str(&traced.trace().to_json().unwrap()).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({"ops": [
+                {"op": "push_back", "elem": "\"x\""},
+                {"op": "pop_front", "result": "\"x\""},
+                {"op": "clear"},
+            ]})
+        );
+    }
+
+    #[test]
+    fn replay_with_custom_parser() {
+        let mut traced = TracedList::new();
+        traced.push_back(String::from("hello"));
+        traced.push_front(String::from("world"));
+        let (list, trace) = traced.into_parts();
+        // String的Debug带引号，需要自己去掉
+        let replayed = trace
+            .replay_with(|repr| Some(repr.trim_matches('"').to_string()))
+            .unwrap();
+        assert_eq!(replayed, list);
+    }
+
+    #[test]
+    fn replay_detects_divergence_and_bad_input() {
+        let trace = Trace {
+            ops: vec![
+                Op::PushBack { elem: "1".into() },
+                Op::PopFront { result: Some("2".into()) },
+            ],
+        };
+        assert_eq!(
+            trace.replay::<i32>().unwrap_err(),
+            ReplayError::Diverged {
+                step: 1,
+                expected: Some("2".into()),
+                actual: Some("1".into()),
+            }
+        );
+
+        let trace = Trace {
+            ops: vec![Op::PushBack { elem: "oops".into() }],
+        };
+        assert_eq!(
+            trace.replay::<i32>().unwrap_err(),
+            ReplayError::Parse {
+                step: 0,
+                repr: "oops".into(),
+            }
+        );
+
+        let trace = Trace::from_json(r#"{"ops":[{"op":"rotate_left","k":1}]}"#).unwrap();
+        let err = trace.replay::<i32>().unwrap_err();
+        assert_eq!(
+            err,
+            ReplayError::InvalidOp {
+                step: 0,
+                op: Op::RotateLeft { k: 1 },
+                len: 0,
+            }
+        );
+        assert_eq!(err.to_string(), "step 0: cannot apply RotateLeft { k: 1 } to a list of length 0");
+    }
+
+    #[test]
+    fn take_trace_restarts_recording() {
+        let mut traced = TracedList::new();
+        traced.push_back(1);
+        let first = traced.take_trace();
+        traced.push_back(2);
+        assert_eq!(first.len(), 1);
+        assert_eq!(traced.trace().ops, vec![Op::PushBack { elem: "2".into() }]);
+        assert_eq!(traced.list().len(), 2);
+    }
+}
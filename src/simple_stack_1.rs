@@ -1,12 +1,18 @@
-pub struct List {
-    head: Link,
+// 最简单的单链表栈，作为其它栈的对照基线
+// 可以选择限定容量: List::bounded(n)最多存n个元素，满了push返回Err并把元素还回来
+
+pub struct List<T> {
+    head: Link<T>,
+    len: usize,
+    // None表示不限容量
+    capacity: Option<usize>,
 }
 
-type Link = Option<Box<Node>>;
+type Link<T> = Option<Box<Node<T>>>;
 
-struct Node {
-    elem: i32,
-    next: Link,
+struct Node<T> {
+    elem: T,
+    next: Link<T>,
 }
 
 // 数据布局使用上面的结构有以下几个好处
@@ -14,12 +20,48 @@ struct Node {
 // 2. Link 使用 Option 包裹 Box<Node>，这样可以很方便地表示链表的结束（None）和节点的存在（Some(Box<Node>)）
 
 
-impl List{
+impl<T> List<T> {
     pub fn new() -> Self {
-        List { head: None }
+        List {
+            head: None,
+            len: 0,
+            capacity: None,
+        }
+    }
+
+    // 最多存capacity个元素的栈；capacity为0时什么都放不进去
+    // 注意traits::Stack::push没有失败的余地，通过Stack trait往满栈里push会panic，
+    // 可能装满的场合直接调用本身返回Result的push
+    pub fn bounded(capacity: usize) -> Self {
+        List {
+            head: None,
+            len: 0,
+            capacity: Some(capacity),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
     }
 
-    pub fn push(&mut self, elem: i32) {
+    pub fn is_empty(&self) -> bool {
+        self.head.is_none()
+    }
+
+    // 不限容量时返回None
+    pub fn capacity(&self) -> Option<usize> {
+        self.capacity
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.capacity.is_some_and(|capacity| self.len >= capacity)
+    }
+
+    // 满了返回Err(elem)；不限容量时总是Ok
+    pub fn push(&mut self, elem: T) -> Result<(), T> {
+        if self.is_full() {
+            return Err(elem);
+        }
         let new_node = Box::new(Node {
             elem,
             next: self.head.take(),
@@ -27,18 +69,113 @@ impl List{
             // next: mem::replace(&mut self.head, None), // 另一种实现方式
         });
         self.head = Some(new_node);
+        self.len += 1;
+        Ok(())
     }
 
-    pub fn pop(&mut self) -> Option<i32> {
+    pub fn pop(&mut self) -> Option<T> {
         // 取出节点元素，把self.head 置为下一个节点
         self.head.take().map(|node| {
             self.head = node.next;
+            self.len -= 1;
             node.elem
         })
     }
+
+    pub fn peek(&self) -> Option<&T> {
+        self.head.as_ref().map(|node| &node.elem)
+    }
+
+    pub fn peek_mut(&mut self) -> Option<&mut T> {
+        self.head.as_mut().map(|node| &mut node.elem)
+    }
+}
+
+impl<T> Default for List<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// 每个节点被上一个Box独占，画出来是一条实线链
+#[cfg(feature = "visualize")]
+impl<T: std::fmt::Debug> List<T> {
+    pub fn to_dot(&self) -> String {
+        use crate::visualize::{Dot, Edge};
+        let mut dot = Dot::new();
+        let mut from = dot.root("List");
+        let mut label = "head";
+        let mut link = &self.head;
+        while let Some(node) = link {
+            let (to, _) = dot.node(&**node, &node.elem, "");
+            dot.edge(&from, &to, label, Edge::Box);
+            (from, label, link) = (to, "next", &node.next);
+        }
+        dot.finish()
+    }
+}
+
+// serde: 写成{capacity, elems}，capacity为null表示不限容量，elems和其它栈一样从栈顶到栈底
+// 没有迭代器，序列化时沿next逐个写出；读回时元素比容量多就报错
+#[cfg(feature = "serde")]
+mod serde_impl {
+    use serde::de::Error;
+    use serde::ser::SerializeSeq;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::{Link, List};
+
+    #[derive(Serialize)]
+    struct StackRef<'a, T> {
+        capacity: Option<usize>,
+        elems: Elems<'a, T>,
+    }
+
+    #[derive(Deserialize)]
+    struct StackOwned<T> {
+        capacity: Option<usize>,
+        elems: Vec<T>,
+    }
+
+    struct Elems<'a, T>(&'a Link<T>);
+
+    impl<T: Serialize> Serialize for Elems<'_, T> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let mut seq = serializer.serialize_seq(None)?;
+            let mut link = self.0;
+            while let Some(node) = link {
+                seq.serialize_element(&node.elem)?;
+                link = &node.next;
+            }
+            seq.end()
+        }
+    }
+
+    impl<T: Serialize> Serialize for List<T> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            StackRef {
+                capacity: self.capacity,
+                elems: Elems(&self.head),
+            }
+            .serialize(serializer)
+        }
+    }
+
+    impl<'de, T: Deserialize<'de>> Deserialize<'de> for List<T> {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let StackOwned { capacity, elems } = StackOwned::<T>::deserialize(deserializer)?;
+            let mut list = List { head: None, len: 0, capacity };
+            for elem in elems.into_iter().rev() {
+                if list.push(elem).is_err() {
+                    return Err(D::Error::custom("more elements than capacity"));
+                }
+            }
+            Ok(list)
+        }
+    }
 }
 
-impl Drop for List {
+impl<T> Drop for List<T> {
     fn drop(&mut self) {
         let mut cur_link = self.head.take();
         while let Some(mut boxed_node) = cur_link {
@@ -63,17 +200,17 @@ mod tests {
         assert_eq!(list.pop(), None);
 
         // 填充列表
-        list.push(1);
-        list.push(2);
-        list.push(3);
+        assert_eq!(list.push(1), Ok(()));
+        assert_eq!(list.push(2), Ok(()));
+        assert_eq!(list.push(3), Ok(()));
 
         // 检查正常的弹出操作
         assert_eq!(list.pop(), Some(3));
         assert_eq!(list.pop(), Some(2));
 
         // 推入更多元素
-        list.push(4);
-        list.push(5);
+        assert_eq!(list.push(4), Ok(()));
+        assert_eq!(list.push(5), Ok(()));
 
         // 检查剩余元素的弹出操作
         assert_eq!(list.pop(), Some(5));
@@ -83,4 +220,57 @@ mod tests {
         // 确认列表现在为空
         assert_eq!(list.pop(), None);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn bounded_push_returns_elem() {
+        let mut list = List::bounded(2);
+        assert_eq!(list.capacity(), Some(2));
+        assert_eq!(list.push("a".to_string()), Ok(()));
+        assert_eq!(list.push("b".to_string()), Ok(()));
+        assert!(list.is_full());
+        assert_eq!(list.push("c".to_string()), Err("c".to_string()));
+        assert_eq!(list.len(), 2);
+
+        list.peek_mut().unwrap().push('!');
+        assert_eq!(list.peek().map(String::as_str), Some("b!"));
+        assert_eq!(list.pop().as_deref(), Some("b!"));
+        assert!(!list.is_full());
+        assert_eq!(list.push("c".to_string()), Ok(()));
+
+        let mut empty = List::bounded(0);
+        assert_eq!(empty.push(1), Err(1));
+        assert!(empty.is_empty());
+        assert_eq!(List::<i32>::new().capacity(), None);
+    }
+
+    #[cfg(feature = "visualize")]
+    #[test]
+    fn to_dot_draws_box_chain() {
+        let mut list = List::new();
+        list.push(1).unwrap();
+        list.push(2).unwrap();
+        let dot = list.to_dot();
+        assert!(dot.contains("n0 [label=\"2\"];"));
+        assert!(dot.contains("root0 -> n0 [label=\"head\"];"));
+        assert!(dot.contains("n0 -> n1 [label=\"next\"];"));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_keeps_capacity() {
+        let mut list = List::bounded(3);
+        list.push(1).unwrap();
+        list.push(2).unwrap();
+        let json = serde_json::to_string(&list).unwrap();
+        assert_eq!(json, r#"{"capacity":3,"elems":[2,1]}"#);
+        let mut back: List<i32> = serde_json::from_str(&json).unwrap();
+        assert_eq!((back.capacity(), back.len()), (Some(3), 2));
+        assert_eq!(back.pop(), Some(2));
+        assert_eq!(back.pop(), Some(1));
+
+        let json = serde_json::to_string(&List::<i32>::new()).unwrap();
+        assert_eq!(json, r#"{"capacity":null,"elems":[]}"#);
+        assert_eq!(serde_json::from_str::<List<i32>>(&json).unwrap().capacity(), None);
+        assert!(serde_json::from_str::<List<i32>>(r#"{"capacity":1,"elems":[1,2]}"#).is_err());
+    }
+}
@@ -3,8 +3,16 @@
 // - peek
 // - 支持迭代器
 
+#[cfg(feature = "unsafe-impls")]
+use std::ptr::{self, NonNull};
+
+use crate::metrics::Counters;
+#[cfg(feature = "unsafe-impls")]
+use crate::node_pool::NodePool;
+
 pub struct List<T> {
     head: Link<T>,
+    metrics: Counters,
 }
 
 type Link<T> = Option<Box<Node<T>>>;
@@ -14,10 +22,19 @@ pub struct Node<T> {
     next: Link<T>,
 }
 
+impl<T> Node<T> {
+    pub fn elem(&self) -> &T {
+        &self.elem
+    }
+}
+
 // 实现时需要在impl块上添加泛型参数<T>,List<T>只是类型名
 impl<T> List<T> {
     pub fn new() -> Self {
-        List { head: None }
+        List {
+            head: None,
+            metrics: Counters::new(),
+        }
     }
 
     pub fn push(&mut self, elem: T) {
@@ -26,10 +43,12 @@ impl<T> List<T> {
             next: self.head.take(),
         });
         self.head = Some(new_node);
+        self.metrics.alloc_push();
     }
 
     pub fn pop(&mut self) -> Option<T> {
         self.head.take().map(|boxed_node| {
+            self.metrics.pop();
             // 这里解引用boxed_node，获得Node<T>所有权，可以返回整个Node，比如处理下面的情况
             // 1. Node字段比较多
             // 2. 把节点转移到另一个链表，缓存等地方
@@ -44,6 +63,7 @@ impl<T> List<T> {
     // 整体 move Node<T> 的 pop
     pub fn pop_node(&mut self) -> Option<Node<T>> {
         self.head.take().map(|boxed_node| {
+            self.metrics.pop();
             let mut node = *boxed_node;
             self.head = node.next.take(); // 关键：断开链表，维护 head
             node
@@ -51,6 +71,50 @@ impl<T> List<T> {
         // 如果写成 self.head.take().map(|boxed_node| boxed_node) 会报类型不匹配错误
     }
 
+    // 连着Box一起摘下头节点，配合push_boxed_node在链表之间搬节点，不重新分配
+    pub fn pop_boxed_node(&mut self) -> Option<Box<Node<T>>> {
+        self.head.take().map(|mut boxed_node| {
+            self.metrics.pop();
+            self.head = boxed_node.next.take();
+            boxed_node
+        })
+    }
+
+    pub fn push_boxed_node(&mut self, mut boxed_node: Box<Node<T>>) {
+        boxed_node.next = self.head.take();
+        self.head = Some(boxed_node);
+        self.metrics.push();
+    }
+
+    // 从共享节点池里拿节点，池子为空时才向分配器申请
+    // 节点池是unsafe实现，只开safe特性时没有这两个方法
+    #[cfg(feature = "unsafe-impls")]
+    pub fn push_pooled(&mut self, elem: T, pool: &mut NodePool<T>) {
+        let node = pool.alloc(Node {
+            elem,
+            next: self.head.take(),
+        });
+        // SAFETY: 池子按Node<T>的布局分配，可以直接装成Box
+        self.head = Some(unsafe { Box::from_raw(node.as_ptr()) });
+        self.metrics.push();
+    }
+
+    // pop之后把节点内存还给池子而不是释放
+    #[cfg(feature = "unsafe-impls")]
+    pub fn pop_pooled(&mut self, pool: &mut NodePool<T>) -> Option<T> {
+        self.head.take().map(|boxed_node| {
+            self.metrics.pop();
+            let node = Box::into_raw(boxed_node);
+            // SAFETY: 节点来自Box，字段移出后只剩内存还给池子
+            unsafe {
+                self.head = ptr::read(&(*node).next);
+                let elem = ptr::read(&(*node).elem);
+                pool.recycle(NonNull::new_unchecked(node));
+                elem
+            }
+        })
+    }
+
     pub fn peek(&self) -> Option<&T> {
         // as_ref() 将 Option<Box<Node<T>>> 转换为 Option<&Box<Node<T>>>
         self.head.as_ref().map(|node| &node.elem)
@@ -63,22 +127,85 @@ impl<T> List<T> {
         })
     }
 
-    pub fn into_iter(self) -> IntoIter<T> {
-        IntoIter(self)
+    // 删除从头数第一个满足pred的元素并返回
+    // link始终指向"下一个要检查的节点"所在的那个Link，删除就是把它换成被删节点的next
+    pub fn remove_first_by<F: FnMut(&T) -> bool>(&mut self, mut pred: F) -> Option<T> {
+        let mut link = &mut self.head;
+        while link.is_some() {
+            if link.as_ref().is_some_and(|node| pred(&node.elem)) {
+                let node = link.take().unwrap();
+                *link = node.next;
+                self.metrics.pop();
+                return Some(node.elem);
+            }
+            link = &mut link.as_mut().unwrap().next;
+            self.metrics.step();
+        }
+        None
+    }
+
+    // 只保留keep返回true的元素，顺序不变
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, mut keep: F) {
+        let mut link = &mut self.head;
+        while link.is_some() {
+            if link.as_ref().is_some_and(|node| keep(&node.elem)) {
+                link = &mut link.as_mut().unwrap().next;
+                self.metrics.step();
+            } else {
+                let node = link.take().unwrap();
+                *link = node.next;
+                self.metrics.pop();
+            }
+        }
     }
 
     pub fn iter(&self) -> Iter<'_, T> {
         Iter {
             next: self.head.as_deref(), // as_deref() 将 Option<Box<Node<T>>> 转换为 Option<&Node<T>>
+            metrics: &self.metrics,
         }
     }
 
     pub fn iter_mut(&mut self) -> IterMut<'_, T> {
         IterMut {
             next: self.head.as_deref_mut(), // as_deref_mut() 将 Option<Box<Node<T>>> 转换为 Option<&mut Node<T>>
+            metrics: &self.metrics,
         }
     }
 
+    // 当前的操作计数快照，各项含义见metrics.rs
+    #[cfg(feature = "metrics")]
+    pub fn metrics(&self) -> crate::metrics::Metrics {
+        self.metrics.snapshot()
+    }
+
+    #[cfg(feature = "metrics")]
+    pub fn reset_metrics(&self) {
+        self.metrics.reset();
+    }
+}
+
+impl<T> Default for List<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "visualize")]
+impl<T: std::fmt::Debug> List<T> {
+    pub fn to_dot(&self) -> String {
+        use crate::visualize::{Dot, Edge};
+        let mut dot = Dot::new();
+        let mut from = dot.root("List");
+        let mut label = "head";
+        let mut link = &self.head;
+        while let Some(node) = link {
+            let (to, _) = dot.node(&**node, &node.elem, "");
+            dot.edge(&from, &to, label, Edge::Box);
+            (from, label, link) = (to, "next", &node.next);
+        }
+        dot.finish()
+    }
 }
 
 impl<T> Drop for List<T> {
@@ -91,6 +218,16 @@ impl<T> Drop for List<T> {
 }
 
 pub struct IntoIter<T>(List<T>);
+
+impl<T> IntoIterator for List<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter(self)
+    }
+}
+
 // 实现值迭代器，消耗整个链表每次迭代返回一个元素的所有权
 // for x in list.into_iter() for会自动匹配Some(x) => Some(x), None => None
 impl<T> Iterator for IntoIter<T> {
@@ -106,6 +243,7 @@ impl<T> Iterator for IntoIter<T> {
 pub struct Iter<'a, T> {
     // T是个泛型，有可能是引用类型，所以必须要生命周期标注
     next: Option<&'a Node<T>>,
+    metrics: &'a Counters,
 }
 impl<'a, T> Iterator for Iter<'a, T> {
     type Item = &'a T;
@@ -119,6 +257,7 @@ impl<'a, T> Iterator for Iter<'a, T> {
         //     None => None,
         // }
         self.next.map(|node|{
+            self.metrics.step();
             self.next = node.next.as_deref(); // 更新next为下一个节点
             &node.elem
         })
@@ -130,6 +269,7 @@ impl<'a, T> Iterator for Iter<'a, T> {
 // 可变借用迭代器，返回元素的可变引用，可以原地修改元素
 pub struct IterMut<'a, T> {
     next: Option<&'a mut Node<T>>,
+    metrics: &'a Counters,
 }
 
 impl<'a, T> Iterator for IterMut<'a, T> {
@@ -137,6 +277,7 @@ impl<'a, T> Iterator for IterMut<'a, T> {
 
     fn next(&mut self) -> Option<Self::Item> {
         self.next.take().map(|node|{
+            self.metrics.step();
             self.next = node.next.as_deref_mut(); // 更新next为下一个节点
             &mut node.elem
         })
@@ -283,9 +424,7 @@ mod tests {
         list.push(2);
         list.push(3);
 
-        let mut iter = list.iter_mut();
-
-        while let Some(x) = iter.next() {
+        for x in list.iter_mut() {
             *x *= 10; // 将每个元素乘以10
         }
 
@@ -316,4 +455,55 @@ mod tests {
         println!("ref1: {}, ref2: {}", ref1, ref2);
     }
 
+    // 每个元素一个节点，drop时全部释放
+    #[test]
+    fn one_allocation_per_node() {
+        use crate::alloc_counter::measure;
+        let (list, stats) = measure(|| {
+            let mut list = List::new();
+            for i in 0..100 {
+                list.push(i);
+            }
+            list
+        });
+        assert_eq!(stats.allocations, 100);
+        let ((), stats) = measure(|| drop(list));
+        assert_eq!(stats.deallocations, 100);
+    }
+
+    #[cfg(feature = "visualize")]
+    #[test]
+    fn to_dot_uses_debug_labels() {
+        let mut list = List::new();
+        list.push("a".to_string());
+        list.push("b".to_string());
+        let dot = list.to_dot();
+        assert!(dot.contains(r#"n0 [label="\"b\""];"#));
+        assert!(dot.contains(r#"n1 [label="\"a\""];"#));
+        assert_eq!(dot.matches("->").count(), 2);
+    }
+
+    #[cfg(feature = "metrics")]
+    #[test]
+    fn metrics_count_operations() {
+        use crate::metrics::Metrics;
+
+        let mut list = List::new();
+        for i in 0..4 {
+            list.push(i);
+        }
+        assert_eq!(list.iter().count(), 4);
+        // 找到2之前越过了3这一个节点
+        assert_eq!(list.remove_first_by(|&x| x == 2), Some(2));
+        list.pop();
+        // 整个节点搬走再放回来不重新分配
+        let node = list.pop_boxed_node().unwrap();
+        list.push_boxed_node(node);
+        assert_eq!(
+            list.metrics(),
+            Metrics { pushes: 5, pops: 3, traversal_steps: 5, allocations: 4 }
+        );
+        list.reset_metrics();
+        assert_eq!(list.metrics(), Metrics::default());
+    }
 }
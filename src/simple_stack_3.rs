@@ -33,7 +33,83 @@ impl<T> List<T> {
     pub fn head(&self) -> Option<&T> {
         self.head.as_ref().map(|node| &node.elem)
     }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            next: self.head.as_deref(),
+        }
+    }
+
+    // 两个列表是否从头开始就是同一批节点(都为空也算)
+    pub fn ptr_eq(&self, other: &List<T>) -> bool {
+        match (&self.head, &other.head) {
+            (Some(a), Some(b)) => Rc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+}
+
+// 只增加头节点的引用计数，不复制任何节点
+impl<T> Clone for List<T> {
+    fn clone(&self) -> Self {
+        List {
+            head: self.head.clone(),
+        }
+    }
+}
+
+pub struct Iter<'a, T> {
+    next: Option<&'a Node<T>>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next.map(|node| {
+            self.next = node.next.as_deref();
+            &node.elem
+        })
+    }
 }
+impl<T> Default for List<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// 多个版本画在同一张图里才看得出哪些节点是共享的: 共享的后缀只画一次，
+// 每个节点标出强引用计数，也就是有几个地方(前驱节点或表头)指着它
+#[cfg(feature = "visualize")]
+impl<T: std::fmt::Debug> List<T> {
+    pub fn to_dot(&self) -> String {
+        Self::to_dot_all(&[("list", self)])
+    }
+
+    // 每一项: 表头上显示的名字和链表
+    pub fn to_dot_all(lists: &[(&str, &List<T>)]) -> String {
+        use crate::visualize::{Dot, Edge};
+        let mut dot = Dot::new();
+        for &(name, list) in lists {
+            let mut from = dot.root(name);
+            let mut label = "head";
+            let mut link = &list.head;
+            while let Some(node) = link {
+                let note = format!("rc={}", Rc::strong_count(node));
+                let (to, new) = dot.node(Rc::as_ptr(node), &node.elem, &note);
+                dot.edge(&from, &to, label, Edge::Shared);
+                // 后面的节点已经从别的版本画过了
+                if !new {
+                    break;
+                }
+                (from, label, link) = (to, "next", &node.next);
+            }
+        }
+        dot.finish()
+    }
+}
+
 impl<T> Drop for List<T> {
     fn drop(&mut self) {
         let mut cur_link = self.head.take();
@@ -66,6 +142,32 @@ mod test{
         let tail3 = tail2.tail();
         assert_eq!(tail3.head(), None);
     }
+
+    #[test]
+    fn iter_and_sharing() {
+        let list = List::new().prepend(1).prepend(2);
+        let copy = list.clone();
+        let longer = list.prepend(3);
+        assert!(list.ptr_eq(&copy));
+        assert!(longer.tail().ptr_eq(&list));
+        assert!(!longer.ptr_eq(&list));
+        assert!(List::<i32>::new().ptr_eq(&list.tail().tail()));
+        assert_eq!(longer.iter().copied().collect::<Vec<_>>(), vec![3, 2, 1]);
+    }
    
     
-}
\ No newline at end of file
+
+    #[cfg(feature = "visualize")]
+    #[test]
+    fn to_dot_all_shows_shared_suffix() {
+        let base = List::new().prepend(1).prepend(2);
+        let a = base.prepend(3);
+        let b = base.prepend(4);
+        let dot = List::to_dot_all(&[("a", &a), ("b", &b), ("base", &base)]);
+        // 共享的节点只画一次，2被base、3、4三处指着
+        assert_eq!(dot.matches("label=\"2\\nrc=3\"").count(), 1);
+        assert_eq!(dot.matches("label=\"1\\nrc=1\"").count(), 1);
+        assert_eq!(dot.matches("[label=\"next\"").count(), 3);
+        assert_eq!(dot.matches("[label=\"head\"").count(), 3);
+    }
+}
@@ -0,0 +1,216 @@
+// 斜堆(skew heap)，自调整的可合并最小堆，节点用Box链接成二叉树
+// 所有操作都归结为merge: 沿两棵树的右路径合并，途经的每个节点都交换左右孩子
+// 不记录任何平衡信息，单次merge可能走很长的右路径，但均摊O(log n)
+// - push: 单节点堆与原堆merge
+// - pop_min: 取出根，左右子树merge
+// 右路径在最坏情况下是O(n)长的，所以merge和drop都写成循环，不用递归
+
+use std::fmt;
+use std::mem;
+
+type Link<T> = Option<Box<Node<T>>>;
+
+struct Node<T> {
+    elem: T,
+    left: Link<T>,
+    right: Link<T>,
+}
+
+pub struct SkewHeap<T> {
+    root: Link<T>,
+    len: usize,
+}
+
+impl<T: Ord> SkewHeap<T> {
+    pub fn new() -> Self {
+        SkewHeap { root: None, len: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn push(&mut self, elem: T) {
+        let node = Box::new(Node {
+            elem,
+            left: None,
+            right: None,
+        });
+        self.root = merge(self.root.take(), Some(node));
+        self.len += 1;
+    }
+
+    pub fn peek(&self) -> Option<&T> {
+        self.root.as_ref().map(|node| &node.elem)
+    }
+
+    pub fn pop_min(&mut self) -> Option<T> {
+        self.root.take().map(|node| {
+            let node = *node;
+            self.root = merge(node.left, node.right);
+            self.len -= 1;
+            node.elem
+        })
+    }
+
+    // 把other整个并进来
+    pub fn merge(&mut self, mut other: SkewHeap<T>) {
+        self.root = merge(self.root.take(), other.root.take());
+        self.len += mem::take(&mut other.len);
+    }
+
+    pub fn clear(&mut self) {
+        drop_tree(self.root.take());
+        self.len = 0;
+    }
+
+    // 按升序取出所有元素
+    pub fn into_sorted_vec(mut self) -> Vec<T> {
+        let mut out = Vec::with_capacity(self.len);
+        while let Some(x) = self.pop_min() {
+            out.push(x);
+        }
+        out
+    }
+}
+
+// 自顶向下的斜堆合并:
+// 先沿右路径把两棵树的节点按大小摘下来压栈(每个节点摘走它的右子树继续比较)，
+// 再自底向上把已合并的部分挂到栈中节点上，并交换左右孩子
+fn merge<T: Ord>(mut a: Link<T>, mut b: Link<T>) -> Link<T> {
+    let mut path: Vec<Box<Node<T>>> = Vec::new();
+    loop {
+        match (a, b) {
+            (None, rest) | (rest, None) => {
+                let mut acc = rest;
+                while let Some(mut node) = path.pop() {
+                    node.right = node.left.take();
+                    node.left = acc;
+                    acc = Some(node);
+                }
+                return acc;
+            }
+            (Some(x), Some(y)) => {
+                let (mut smaller, larger) = if y.elem < x.elem { (y, x) } else { (x, y) };
+                a = smaller.right.take();
+                b = Some(larger);
+                path.push(smaller);
+            }
+        }
+    }
+}
+
+// 逐层拆开，避免深树递归drop爆栈
+fn drop_tree<T>(root: Link<T>) {
+    let mut stack: Vec<Box<Node<T>>> = root.into_iter().collect();
+    while let Some(mut node) = stack.pop() {
+        stack.extend(node.left.take());
+        stack.extend(node.right.take());
+    }
+}
+
+impl<T> Drop for SkewHeap<T> {
+    fn drop(&mut self) {
+        drop_tree(self.root.take());
+    }
+}
+
+impl<T: Ord> Default for SkewHeap<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Ord + fmt::Debug> fmt::Debug for SkewHeap<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SkewHeap")
+            .field("len", &self.len)
+            .field("min", &self.peek())
+            .finish()
+    }
+}
+
+impl<T: Ord> Extend<T> for SkewHeap<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for elem in iter {
+            self.push(elem);
+        }
+    }
+}
+
+impl<T: Ord> FromIterator<T> for SkewHeap<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut heap = Self::new();
+        heap.extend(iter);
+        heap
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::XorShift;
+    use std::cmp::Reverse;
+    use std::collections::BinaryHeap;
+
+    #[test]
+    fn push_pop_in_order() {
+        let mut heap = SkewHeap::new();
+        assert_eq!(heap.pop_min(), None);
+        heap.extend([5, 3, 8, 1, 9, 2, 7, 3]);
+        assert_eq!(heap.peek(), Some(&1));
+        assert_eq!(heap.len(), 8);
+        assert_eq!(heap.into_sorted_vec(), vec![1, 2, 3, 3, 5, 7, 8, 9]);
+    }
+
+    #[test]
+    fn merge_two_heaps() {
+        let mut a: SkewHeap<i32> = [4, 8, 1].into_iter().collect();
+        let b: SkewHeap<i32> = [6, 0, 9].into_iter().collect();
+        a.merge(b);
+        assert_eq!(a.len(), 6);
+        assert_eq!(a.pop_min(), Some(0));
+        a.merge(SkewHeap::new());
+        assert_eq!(a.into_sorted_vec(), vec![1, 4, 6, 8, 9]);
+    }
+
+    #[test]
+    fn long_right_spine() {
+        // 降序push让每个新节点都成为根，树退化得很深；merge和drop都不能递归
+        let mut heap: SkewHeap<u32> = (0..100_000).rev().collect();
+        assert_eq!(heap.pop_min(), Some(0));
+        heap.clear();
+        assert!(heap.is_empty());
+        let heap: SkewHeap<u32> = (0..100_000).collect();
+        drop(heap);
+    }
+
+    #[test]
+    fn randomized_against_binary_heap() {
+        let mut rng = XorShift::new(0x5e3);
+        let mut heap = SkewHeap::new();
+        let mut reference = BinaryHeap::new();
+        for _ in 0..3000 {
+            match rng.next_u64() % 6 {
+                0..=2 => {
+                    let x = rng.next_u64() % 1000;
+                    heap.push(x);
+                    reference.push(Reverse(x));
+                }
+                3 | 4 => assert_eq!(heap.pop_min(), reference.pop().map(|Reverse(x)| x)),
+                _ => {
+                    let n = rng.next_u64() % 8;
+                    let xs: Vec<u64> = (0..n).map(|_| rng.next_u64() % 1000).collect();
+                    reference.extend(xs.iter().map(|&x| Reverse(x)));
+                    heap.merge(xs.into_iter().collect());
+                }
+            }
+            assert_eq!(heap.len(), reference.len());
+            assert_eq!(heap.peek(), reference.peek().map(|Reverse(x)| x));
+        }
+    }
+}
@@ -0,0 +1,636 @@
+// 跳表实现的有序映射
+// 链表遇上有序映射: 在有序单链表的基础上给每个节点随机分配若干层"快车道"，
+// 第i层只串起高度大于i的节点，查找时从最高层往下逐层逼近，期望复杂度O(log n)
+// 节点高度按p = 1/4的几何分布随机生成，和平衡树相比不需要任何旋转或重新平衡
+// 所有指针都以裸指针保存，每个节点的前向指针数组单独分配一次，之后只通过裸指针读写，
+// 这样查找路径上记下的"前驱指针槽"在修改时不会因为重新借用而失效(Miri的Stacked Borrows)
+
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::fmt;
+use std::iter::FusedIterator;
+use std::marker::PhantomData;
+use std::ops::{Bound, RangeBounds};
+use std::ptr::{self, NonNull};
+
+use crate::rng::XorShift;
+
+// 最多16层，p = 1/4时足够容纳4^16个元素
+pub(crate) const MAX_LEVEL: usize = 16;
+
+type Link<K, V> = Option<NonNull<Node<K, V>>>;
+
+struct Node<K, V> {
+    key: K,
+    value: V,
+    height: usize,
+    // 长度为height的前向指针数组，next[i]是第i层的下一个节点
+    next: *mut Link<K, V>,
+}
+
+// 分配长度为n、全部为None的指针数组
+fn alloc_links<K, V>(n: usize) -> *mut Link<K, V> {
+    let links: Box<[Link<K, V>]> = vec![None; n].into_boxed_slice();
+    Box::into_raw(links) as *mut Link<K, V>
+}
+
+unsafe fn free_links<K, V>(links: *mut Link<K, V>, n: usize) {
+    drop(Box::from_raw(ptr::slice_from_raw_parts_mut(links, n)));
+}
+
+// 随机数生成器定义在rng.rs，这里只补上跳表需要的随机层数
+impl XorShift {
+    // 每次以1/4的概率多加一层
+    pub(crate) fn random_level(&mut self) -> usize {
+        let mut level = 1;
+        let mut bits = self.next_u64();
+        while level < MAX_LEVEL && bits & 3 == 0 {
+            level += 1;
+            bits >>= 2;
+        }
+        level
+    }
+}
+
+pub struct SkipListMap<K, V> {
+    // 头节点只有前向指针，长度为MAX_LEVEL
+    head: *mut Link<K, V>,
+    // 当前实际使用的层数，至少为1
+    level: usize,
+    len: usize,
+    rng: XorShift,
+    _boo: PhantomData<Box<Node<K, V>>>,
+}
+
+impl<K, V> SkipListMap<K, V> {
+    pub fn new() -> Self {
+        Self::with_rng(XorShift::from_counter())
+    }
+
+    // 固定随机种子，节点高度(进而整个结构)可以复现
+    pub fn with_seed(seed: u64) -> Self {
+        Self::with_rng(XorShift::new(seed))
+    }
+
+    fn with_rng(rng: XorShift) -> Self {
+        SkipListMap {
+            head: alloc_links(MAX_LEVEL),
+            level: 1,
+            len: 0,
+            rng,
+            _boo: PhantomData,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn clear(&mut self) {
+        while self.pop_first().is_some() {}
+    }
+
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            next: unsafe { *self.head },
+            remaining: self.len,
+            _boo: PhantomData,
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        IterMut {
+            next: unsafe { *self.head },
+            remaining: self.len,
+            _boo: PhantomData,
+        }
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &K> + '_ {
+        self.iter().map(|(k, _)| k)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &V> + '_ {
+        self.iter().map(|(_, v)| v)
+    }
+
+    pub fn first_key_value(&self) -> Option<(&K, &V)> {
+        unsafe { (*self.head).map(|n| (&(*n.as_ptr()).key, &(*n.as_ptr()).value)) }
+    }
+
+    // 从最高层一路向右走到底，O(log n)
+    pub fn last_key_value(&self) -> Option<(&K, &V)> {
+        unsafe {
+            let mut links = self.head;
+            let mut last = None;
+            for i in (0..self.level).rev() {
+                while let Some(n) = *links.add(i) {
+                    last = Some(n);
+                    links = (*n.as_ptr()).next;
+                }
+            }
+            last.map(|n| (&(*n.as_ptr()).key, &(*n.as_ptr()).value))
+        }
+    }
+
+    // 第一个节点在它所有的层上都是第一个，直接从头节点摘下即可
+    pub fn pop_first(&mut self) -> Option<(K, V)> {
+        unsafe {
+            let first = (*self.head)?;
+            let node = first.as_ptr();
+            for i in 0..(*node).height {
+                *self.head.add(i) = *(*node).next.add(i);
+            }
+            self.shrink_level();
+            self.len -= 1;
+            Some(Self::free_node(first))
+        }
+    }
+
+    // 最高层空了就降低层数，让之后的查找少走几层
+    unsafe fn shrink_level(&mut self) {
+        while self.level > 1 && (*self.head.add(self.level - 1)).is_none() {
+            self.level -= 1;
+        }
+    }
+
+    unsafe fn free_node(node: NonNull<Node<K, V>>) -> (K, V) {
+        let node = Box::from_raw(node.as_ptr());
+        free_links(node.next, node.height);
+        (node.key, node.value)
+    }
+}
+
+impl<K: Ord, V> SkipListMap<K, V> {
+    // 在每一层找到最后一个"key < 目标"的节点，返回它们在该层的前向指针槽
+    // less(k)表示k是否应该排在目标之前
+    unsafe fn predecessors(&self, less: impl Fn(&K) -> bool) -> [*mut Link<K, V>; MAX_LEVEL] {
+        let mut update = [ptr::null_mut(); MAX_LEVEL];
+        let mut links = self.head;
+        for i in (0..self.level).rev() {
+            while let Some(n) = *links.add(i) {
+                if !less(&(*n.as_ptr()).key) {
+                    break;
+                }
+                links = (*n.as_ptr()).next;
+            }
+            update[i] = links.add(i);
+        }
+        update
+    }
+
+    // 第一个不满足less的节点
+    fn lower_bound(&self, less: impl Fn(&K) -> bool) -> Link<K, V> {
+        unsafe {
+            let mut links = self.head;
+            for i in (0..self.level).rev() {
+                while let Some(n) = *links.add(i) {
+                    if !less(&(*n.as_ptr()).key) {
+                        break;
+                    }
+                    links = (*n.as_ptr()).next;
+                }
+            }
+            *links
+        }
+    }
+
+    fn find<Q>(&self, key: &Q) -> Link<K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let candidate = self.lower_bound(|k| k.borrow() < key);
+        candidate.filter(|n| unsafe { (*n.as_ptr()).key.borrow() == key })
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find(key).map(|n| unsafe { &(*n.as_ptr()).value })
+    }
+
+    pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find(key)
+            .map(|n| unsafe { (&(*n.as_ptr()).key, &(*n.as_ptr()).value) })
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find(key).map(|n| unsafe { &mut (*n.as_ptr()).value })
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find(key).is_some()
+    }
+
+    // key已经存在时替换value并返回旧值，key本身保持不变(和BTreeMap一致)
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        unsafe {
+            let mut update = self.predecessors(|k| *k < key);
+            if let Some(n) = *update[0] {
+                if (*n.as_ptr()).key == key {
+                    return Some(std::mem::replace(&mut (*n.as_ptr()).value, value));
+                }
+            }
+
+            let height = self.rng.random_level();
+            if height > self.level {
+                for (i, slot) in update.iter_mut().enumerate().take(height).skip(self.level) {
+                    *slot = self.head.add(i);
+                }
+                self.level = height;
+            }
+            let node = NonNull::new_unchecked(Box::into_raw(Box::new(Node {
+                key,
+                value,
+                height,
+                next: alloc_links(height),
+            })));
+            for (i, &slot) in update.iter().enumerate().take(height) {
+                *(*node.as_ptr()).next.add(i) = *slot;
+                *slot = Some(node);
+            }
+            self.len += 1;
+            None
+        }
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.remove_entry(key).map(|(_, v)| v)
+    }
+
+    pub fn remove_entry<Q>(&mut self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        unsafe {
+            let update = self.predecessors(|k| k.borrow() < key);
+            let target = (*update[0]).filter(|n| (*n.as_ptr()).key.borrow() == key)?;
+            // target在它的每一层上都紧跟在update[i]之后
+            for (i, &slot) in update.iter().enumerate().take((*target.as_ptr()).height) {
+                *slot = *(*target.as_ptr()).next.add(i);
+            }
+            self.shrink_level();
+            self.len -= 1;
+            Some(Self::free_node(target))
+        }
+    }
+
+    // 按key的范围遍历，和BTreeMap::range一样支持各种区间写法
+    pub fn range<Q, R>(&self, range: R) -> Range<'_, K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        let start = match range.start_bound() {
+            Bound::Included(s) => self.lower_bound(|k| k.borrow() < s),
+            Bound::Excluded(s) => self.lower_bound(|k| k.borrow() <= s),
+            Bound::Unbounded => unsafe { *self.head },
+        };
+        // end是区间之后的第一个节点，遍历到它为止
+        let end = match range.end_bound() {
+            Bound::Included(e) => self.lower_bound(|k| k.borrow() <= e),
+            Bound::Excluded(e) => self.lower_bound(|k| k.borrow() < e),
+            Bound::Unbounded => None,
+        };
+        // start已经越过end说明区间为空(比如start > end)
+        let empty = match (start, end) {
+            (Some(s), Some(e)) => unsafe { (*s.as_ptr()).key.cmp(&(*e.as_ptr()).key) != Ordering::Less },
+            (None, _) => true,
+            (Some(_), None) => false,
+        };
+        Range {
+            next: if empty { None } else { start },
+            end,
+            _boo: PhantomData,
+        }
+    }
+}
+
+impl<K, V> Drop for SkipListMap<K, V> {
+    fn drop(&mut self) {
+        self.clear();
+        unsafe { free_links(self.head, MAX_LEVEL) };
+    }
+}
+
+impl<K, V> Default for SkipListMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for SkipListMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K: Ord, V> Extend<(K, V)> for SkipListMap<K, V> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (k, v) in iter {
+            self.insert(k, v);
+        }
+    }
+}
+
+impl<K: Ord, V> FromIterator<(K, V)> for SkipListMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = Self::new();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<K: PartialEq, V: PartialEq> PartialEq for SkipListMap<K, V> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().eq(other.iter())
+    }
+}
+
+impl<K: Eq, V: Eq> Eq for SkipListMap<K, V> {}
+
+pub struct Iter<'a, K, V> {
+    next: Link<K, V>,
+    remaining: usize,
+    _boo: PhantomData<&'a Node<K, V>>,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next.map(|n| unsafe {
+            let node = &*n.as_ptr();
+            self.next = *node.next;
+            self.remaining -= 1;
+            (&node.key, &node.value)
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K, V> ExactSizeIterator for Iter<'_, K, V> {}
+impl<K, V> FusedIterator for Iter<'_, K, V> {}
+
+impl<K, V> Clone for Iter<'_, K, V> {
+    fn clone(&self) -> Self {
+        Iter {
+            next: self.next,
+            remaining: self.remaining,
+            _boo: PhantomData,
+        }
+    }
+}
+
+pub struct IterMut<'a, K, V> {
+    next: Link<K, V>,
+    remaining: usize,
+    _boo: PhantomData<&'a mut Node<K, V>>,
+}
+
+impl<'a, K, V> Iterator for IterMut<'a, K, V> {
+    // key不能修改，否则会破坏有序性
+    type Item = (&'a K, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next.map(|n| unsafe {
+            let node = n.as_ptr();
+            self.next = *(*node).next;
+            self.remaining -= 1;
+            (&(*node).key, &mut (*node).value)
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K, V> ExactSizeIterator for IterMut<'_, K, V> {}
+impl<K, V> FusedIterator for IterMut<'_, K, V> {}
+
+pub struct Range<'a, K, V> {
+    next: Link<K, V>,
+    // 区间之后的第一个节点，None表示一直到表尾
+    end: Link<K, V>,
+    _boo: PhantomData<&'a Node<K, V>>,
+}
+
+impl<'a, K, V> Iterator for Range<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let n = self.next.filter(|&n| Some(n) != self.end)?;
+        unsafe {
+            let node = &*n.as_ptr();
+            self.next = *node.next;
+            Some((&node.key, &node.value))
+        }
+    }
+}
+
+impl<K, V> FusedIterator for Range<'_, K, V> {}
+
+impl<K, V> Clone for Range<'_, K, V> {
+    fn clone(&self) -> Self {
+        Range {
+            next: self.next,
+            end: self.end,
+            _boo: PhantomData,
+        }
+    }
+}
+
+pub struct IntoIter<K, V>(SkipListMap<K, V>);
+
+impl<K, V> Iterator for IntoIter<K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<(K, V)> {
+        self.0.pop_first()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.0.len, Some(self.0.len))
+    }
+}
+
+impl<K, V> ExactSizeIterator for IntoIter<K, V> {}
+
+impl<K, V> IntoIterator for SkipListMap<K, V> {
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V>;
+
+    fn into_iter(self) -> IntoIter<K, V> {
+        IntoIter(self)
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a SkipListMap<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Iter<'a, K, V> {
+        self.iter()
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a mut SkipListMap<K, V> {
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V>;
+
+    fn into_iter(self) -> IterMut<'a, K, V> {
+        self.iter_mut()
+    }
+}
+
+unsafe impl<K: Send, V: Send> Send for SkipListMap<K, V> {}
+unsafe impl<K: Sync, V: Sync> Sync for SkipListMap<K, V> {}
+unsafe impl<K: Sync, V: Sync> Send for Iter<'_, K, V> {}
+unsafe impl<K: Sync, V: Sync> Sync for Iter<'_, K, V> {}
+unsafe impl<K: Sync, V: Send> Send for IterMut<'_, K, V> {}
+unsafe impl<K: Sync, V: Sync> Sync for IterMut<'_, K, V> {}
+unsafe impl<K: Sync, V: Sync> Send for Range<'_, K, V> {}
+unsafe impl<K: Sync, V: Sync> Sync for Range<'_, K, V> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+    use std::rc::Rc;
+
+    #[test]
+    fn basics() {
+        let mut map = SkipListMap::new();
+        assert!(map.is_empty());
+        assert_eq!(map.insert(3, "c"), None);
+        assert_eq!(map.insert(1, "a"), None);
+        assert_eq!(map.insert(2, "b"), None);
+        assert_eq!(map.insert(2, "B"), Some("b"));
+        assert_eq!(map.len(), 3);
+        assert_eq!(map.get(&2), Some(&"B"));
+        assert_eq!(map.get(&4), None);
+        assert!(map.contains_key(&1));
+        *map.get_mut(&1).unwrap() = "A";
+        assert_eq!(map.first_key_value(), Some((&1, &"A")));
+        assert_eq!(map.last_key_value(), Some((&3, &"c")));
+        assert_eq!(map.iter().collect::<Vec<_>>(), vec![(&1, &"A"), (&2, &"B"), (&3, &"c")]);
+
+        assert_eq!(map.remove(&2), Some("B"));
+        assert_eq!(map.remove(&2), None);
+        assert_eq!(map.pop_first(), Some((1, "A")));
+        assert_eq!(map.into_iter().collect::<Vec<_>>(), vec![(3, "c")]);
+    }
+
+    #[test]
+    fn borrowed_lookup() {
+        let mut map: SkipListMap<String, usize> = ["pear", "apple", "fig"]
+            .iter()
+            .map(|s| (s.to_string(), s.len()))
+            .collect();
+        assert_eq!(map.get("fig"), Some(&3));
+        assert_eq!(map.remove("apple"), Some(5));
+        assert_eq!(map.keys().map(String::as_str).collect::<Vec<_>>(), vec!["fig", "pear"]);
+        let r: Vec<_> = map.range::<str, _>((Bound::Included("g"), Bound::Unbounded)).map(|(k, _)| k.as_str()).collect();
+        assert_eq!(r, vec!["pear"]);
+    }
+
+    #[test]
+    fn range_bounds() {
+        let map: SkipListMap<i32, i32> = (0..20).map(|i| (i * 2, i)).collect();
+        let keys = |r: Range<'_, i32, i32>| r.map(|(k, _)| *k).collect::<Vec<_>>();
+        assert_eq!(keys(map.range(3..9)), vec![4, 6, 8]);
+        assert_eq!(keys(map.range(4..=8)), vec![4, 6, 8]);
+        assert_eq!(keys(map.range((Bound::Excluded(4), Bound::Included(8)))), vec![6, 8]);
+        assert_eq!(keys(map.range(..3)), vec![0, 2]);
+        assert_eq!(keys(map.range(35..)), vec![36, 38]);
+        assert_eq!(keys(map.range(100..)), Vec::<i32>::new());
+        assert_eq!(keys(map.range(5..5)), Vec::<i32>::new());
+        assert_eq!(keys(map.range((Bound::Excluded(6), Bound::Excluded(8)))), Vec::<i32>::new());
+        assert_eq!(map.range(..).count(), 20);
+    }
+
+    #[test]
+    fn iter_mut_and_drop() {
+        let marker = Rc::new(());
+        let mut map = SkipListMap::new();
+        for i in 0..50 {
+            map.insert(i, (i, Rc::clone(&marker)));
+        }
+        for (k, v) in map.iter_mut() {
+            v.0 = k * 10;
+        }
+        assert_eq!(map.get(&7).map(|v| v.0), Some(70));
+        map.remove(&7);
+        assert_eq!(Rc::strong_count(&marker), 50);
+        drop(map);
+        assert_eq!(Rc::strong_count(&marker), 1);
+    }
+
+    #[test]
+    fn levels_shrink_after_removal() {
+        let mut map = SkipListMap::with_seed(42);
+        for i in 0..1000 {
+            map.insert(i, ());
+        }
+        assert!(map.level > 1);
+        for i in 0..1000 {
+            map.remove(&i);
+        }
+        assert_eq!(map.level, 1);
+        assert!(map.is_empty());
+    }
+
+    // 随机操作序列，每一步都和BTreeMap比较结果
+    #[test]
+    fn randomized_against_btreemap() {
+        let mut rng = XorShift::new(0xDEADBEEF);
+        let mut map = SkipListMap::with_seed(7);
+        let mut reference = BTreeMap::new();
+        for step in 0..20_000 {
+            let key = (rng.next_u64() % 500) as u32;
+            match rng.next_u64() % 4 {
+                0 | 1 => assert_eq!(map.insert(key, step), reference.insert(key, step)),
+                2 => assert_eq!(map.remove(&key), reference.remove(&key)),
+                _ => assert_eq!(map.get(&key), reference.get(&key)),
+            }
+            assert_eq!(map.len(), reference.len());
+            if step % 1000 == 0 {
+                assert!(map.iter().eq(reference.iter()));
+                let lo = (rng.next_u64() % 500) as u32;
+                let hi = lo + (rng.next_u64() % 100) as u32;
+                assert!(map.range(lo..hi).eq(reference.range(lo..hi)));
+                assert!(map.range(lo..=hi).eq(reference.range(lo..=hi)));
+                assert_eq!(map.last_key_value(), reference.last_key_value());
+            }
+        }
+    }
+}
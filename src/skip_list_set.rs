@@ -0,0 +1,367 @@
+// 跳表实现的有序集合，是SkipListMap<T, ()>的一层薄包装
+// 并集/交集利用两个集合都有序的特点做归并，不需要额外分配，每个元素只比较一次
+
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::fmt;
+use std::iter::{FusedIterator, Peekable};
+use std::ops::RangeBounds;
+
+use crate::skip_list_map::{self, SkipListMap};
+
+pub struct SkipListSet<T> {
+    map: SkipListMap<T, ()>,
+}
+
+impl<T> SkipListSet<T> {
+    pub fn new() -> Self {
+        SkipListSet {
+            map: SkipListMap::new(),
+        }
+    }
+
+    // 固定随机种子，见SkipListMap::with_seed
+    pub fn with_seed(seed: u64) -> Self {
+        SkipListSet {
+            map: SkipListMap::with_seed(seed),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.map.clear();
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter(self.map.iter())
+    }
+
+    pub fn first(&self) -> Option<&T> {
+        self.map.first_key_value().map(|(k, _)| k)
+    }
+
+    pub fn last(&self) -> Option<&T> {
+        self.map.last_key_value().map(|(k, _)| k)
+    }
+
+    pub fn pop_first(&mut self) -> Option<T> {
+        self.map.pop_first().map(|(k, _)| k)
+    }
+}
+
+impl<T: Ord> SkipListSet<T> {
+    // 新插入返回true；已经存在时集合不变(保留原来的元素)并返回false
+    pub fn insert(&mut self, value: T) -> bool {
+        if self.map.contains_key(&value) {
+            return false;
+        }
+        self.map.insert(value, ());
+        true
+    }
+
+    pub fn contains<Q>(&self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.map.contains_key(value)
+    }
+
+    pub fn get<Q>(&self, value: &Q) -> Option<&T>
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.map.get_key_value(value).map(|(k, _)| k)
+    }
+
+    pub fn remove<Q>(&mut self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.map.remove(value).is_some()
+    }
+
+    pub fn take<Q>(&mut self, value: &Q) -> Option<T>
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.map.remove_entry(value).map(|(k, _)| k)
+    }
+
+    pub fn range<Q, R>(&self, range: R) -> Range<'_, T>
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        Range(self.map.range(range))
+    }
+
+    // 按升序产生出现在任一集合中的元素，两边都有的只产生一次(取self里的那个)
+    pub fn union<'a>(&'a self, other: &'a Self) -> Union<'a, T> {
+        Union {
+            a: self.iter().peekable(),
+            b: other.iter().peekable(),
+        }
+    }
+
+    // 按升序产生两个集合共有的元素
+    pub fn intersection<'a>(&'a self, other: &'a Self) -> Intersection<'a, T> {
+        Intersection {
+            a: self.iter(),
+            b: other.iter(),
+        }
+    }
+
+    pub fn is_subset(&self, other: &Self) -> bool {
+        self.len() <= other.len() && self.intersection(other).count() == self.len()
+    }
+}
+
+impl<T> Default for SkipListSet<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for SkipListSet<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl<T: PartialEq> PartialEq for SkipListSet<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.map == other.map
+    }
+}
+
+impl<T: Eq> Eq for SkipListSet<T> {}
+
+impl<T: Ord> Extend<T> for SkipListSet<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.insert(value);
+        }
+    }
+}
+
+impl<T: Ord> FromIterator<T> for SkipListSet<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut set = Self::new();
+        set.extend(iter);
+        set
+    }
+}
+
+pub struct Iter<'a, T>(skip_list_map::Iter<'a, T, ()>);
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        self.0.next().map(|(k, _)| k)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+impl<T> FusedIterator for Iter<'_, T> {}
+
+impl<T> Clone for Iter<'_, T> {
+    fn clone(&self) -> Self {
+        Iter(self.0.clone())
+    }
+}
+
+pub struct Range<'a, T>(skip_list_map::Range<'a, T, ()>);
+
+impl<'a, T> Iterator for Range<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        self.0.next().map(|(k, _)| k)
+    }
+}
+
+impl<T> FusedIterator for Range<'_, T> {}
+
+impl<T> Clone for Range<'_, T> {
+    fn clone(&self) -> Self {
+        Range(self.0.clone())
+    }
+}
+
+pub struct Union<'a, T> {
+    a: Peekable<Iter<'a, T>>,
+    b: Peekable<Iter<'a, T>>,
+}
+
+impl<'a, T: Ord> Iterator for Union<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        let order = match (self.a.peek(), self.b.peek()) {
+            (Some(x), Some(y)) => x.cmp(y),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => return None,
+        };
+        match order {
+            Ordering::Less => self.a.next(),
+            Ordering::Greater => self.b.next(),
+            Ordering::Equal => {
+                self.b.next();
+                self.a.next()
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (a, _) = self.a.size_hint();
+        let (b, _) = self.b.size_hint();
+        (a.max(b), Some(a + b))
+    }
+}
+
+impl<T: Ord> FusedIterator for Union<'_, T> {}
+
+pub struct Intersection<'a, T> {
+    a: Iter<'a, T>,
+    b: Iter<'a, T>,
+}
+
+impl<'a, T: Ord> Iterator for Intersection<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        let mut x = self.a.next()?;
+        let mut y = self.b.next()?;
+        loop {
+            match x.cmp(y) {
+                Ordering::Less => x = self.a.next()?,
+                Ordering::Greater => y = self.b.next()?,
+                Ordering::Equal => return Some(x),
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.a.len().min(self.b.len())))
+    }
+}
+
+impl<T: Ord> FusedIterator for Intersection<'_, T> {}
+
+pub struct IntoIter<T>(skip_list_map::IntoIter<T, ()>);
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.0.next().map(|(k, _)| k)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
+impl<T> IntoIterator for SkipListSet<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter(self.map.into_iter())
+    }
+}
+
+impl<'a, T> IntoIterator for &'a SkipListSet<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::XorShift;
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn basics() {
+        let mut set = SkipListSet::new();
+        assert!(set.insert(5));
+        assert!(set.insert(1));
+        assert!(!set.insert(5));
+        assert!(set.insert(3));
+        assert_eq!(set.len(), 3);
+        assert!(set.contains(&3));
+        assert_eq!(set.first(), Some(&1));
+        assert_eq!(set.last(), Some(&5));
+        assert!(set.remove(&3));
+        assert!(!set.remove(&3));
+        assert_eq!(set.take(&5), Some(5));
+        assert_eq!(set.into_iter().collect::<Vec<_>>(), vec![1]);
+    }
+
+    #[test]
+    fn union_and_intersection() {
+        let a: SkipListSet<i32> = [1, 3, 5, 7, 9].into_iter().collect();
+        let b: SkipListSet<i32> = [2, 3, 4, 9, 10].into_iter().collect();
+        let empty = SkipListSet::new();
+        assert_eq!(a.union(&b).copied().collect::<Vec<_>>(), vec![1, 2, 3, 4, 5, 7, 9, 10]);
+        assert_eq!(a.intersection(&b).copied().collect::<Vec<_>>(), vec![3, 9]);
+        assert_eq!(a.union(&empty).count(), 5);
+        assert_eq!(empty.intersection(&a).count(), 0);
+        assert!(a.intersection(&b).copied().collect::<SkipListSet<_>>().is_subset(&a));
+        assert!(!a.is_subset(&b));
+    }
+
+    #[test]
+    fn range() {
+        let set: SkipListSet<u32> = (0..100).step_by(5).collect();
+        assert_eq!(set.range(12..=30).copied().collect::<Vec<_>>(), vec![15, 20, 25, 30]);
+        assert_eq!(set.range(..10).count(), 2);
+        assert_eq!(set.range(96..).count(), 0);
+    }
+
+    #[test]
+    fn randomized_against_btreeset() {
+        let mut rng = XorShift::new(12345);
+        for _ in 0..20 {
+            let mut a = SkipListSet::new();
+            let mut b = SkipListSet::new();
+            let mut ra = BTreeSet::new();
+            let mut rb = BTreeSet::new();
+            for _ in 0..200 {
+                let x = rng.next_u64() % 300;
+                let y = rng.next_u64() % 300;
+                assert_eq!(a.insert(x), ra.insert(x));
+                assert_eq!(b.insert(y), rb.insert(y));
+            }
+            assert!(a.union(&b).eq(ra.union(&rb)));
+            assert!(a.intersection(&b).eq(ra.intersection(&rb)));
+            let lo = rng.next_u64() % 300;
+            assert!(a.range(lo..lo + 50).eq(ra.range(lo..lo + 50)));
+        }
+    }
+}
@@ -0,0 +1,580 @@
+// 节点放在Vec(slab)里、用下标互相链接的双向链表
+// 插入时返回一个Handle(下标 + 代数)，之后可以凭它O(1)地访问、删除元素或在它前后插入，
+// 全程不需要裸指针也不需要Rc<RefCell>
+// 槽位被删除后会放进空闲链表复用，同时代数加一: 旧Handle里的代数对不上，访问时得到None，
+// 从而检测出"槽位已经被别的元素复用"的ABA情况，而不会悄悄读到别人的数据
+
+use std::fmt;
+use std::iter::FusedIterator;
+use std::marker::PhantomData;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Handle {
+    index: usize,
+    generation: u64,
+}
+
+enum Entry<T> {
+    Occupied {
+        value: T,
+        generation: u64,
+        prev: Option<usize>,
+        next: Option<usize>,
+    },
+    Vacant {
+        // 下次占用时使用的代数
+        generation: u64,
+        next_free: Option<usize>,
+    },
+}
+
+pub struct SlabList<T> {
+    entries: Vec<Entry<T>>,
+    head: Option<usize>,
+    tail: Option<usize>,
+    free: Option<usize>,
+    len: usize,
+}
+
+impl<T> SlabList<T> {
+    pub fn new() -> Self {
+        SlabList {
+            entries: Vec::new(),
+            head: None,
+            tail: None,
+            free: None,
+            len: 0,
+        }
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        SlabList {
+            entries: Vec::with_capacity(capacity),
+            ..Self::new()
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn push_front(&mut self, value: T) -> Handle {
+        self.link(value, None, self.head)
+    }
+
+    pub fn push_back(&mut self, value: T) -> Handle {
+        self.link(value, self.tail, None)
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        let index = self.head?;
+        Some(self.unlink(index))
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        let index = self.tail?;
+        Some(self.unlink(index))
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        self.value_at(self.head?)
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        self.value_at(self.tail?)
+    }
+
+    pub fn front_handle(&self) -> Option<Handle> {
+        self.handle_at(self.head?)
+    }
+
+    pub fn back_handle(&self) -> Option<Handle> {
+        self.handle_at(self.tail?)
+    }
+
+    // handle已经失效(元素被删除，或者槽位已被复用)时返回None
+    pub fn get(&self, handle: Handle) -> Option<&T> {
+        match self.entries.get(handle.index)? {
+            Entry::Occupied {
+                value, generation, ..
+            } if *generation == handle.generation => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn get_mut(&mut self, handle: Handle) -> Option<&mut T> {
+        match self.entries.get_mut(handle.index)? {
+            Entry::Occupied {
+                value, generation, ..
+            } if *generation == handle.generation => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn contains(&self, handle: Handle) -> bool {
+        self.get(handle).is_some()
+    }
+
+    pub fn remove(&mut self, handle: Handle) -> Option<T> {
+        self.get(handle)?;
+        Some(self.unlink(handle.index))
+    }
+
+    // 在handle之前/之后插入，handle失效时返回None且不插入
+    pub fn insert_before(&mut self, handle: Handle, value: T) -> Option<Handle> {
+        let (prev, _) = self.links(handle)?;
+        Some(self.link(value, prev, Some(handle.index)))
+    }
+
+    pub fn insert_after(&mut self, handle: Handle, value: T) -> Option<Handle> {
+        let (_, next) = self.links(handle)?;
+        Some(self.link(value, Some(handle.index), next))
+    }
+
+    // 链表顺序上的下一个/上一个元素
+    pub fn next_handle(&self, handle: Handle) -> Option<Handle> {
+        self.handle_at(self.links(handle)?.1?)
+    }
+
+    pub fn prev_handle(&self, handle: Handle) -> Option<Handle> {
+        self.handle_at(self.links(handle)?.0?)
+    }
+
+    // 清空元素但保留槽位，所有旧handle都会失效
+    pub fn clear(&mut self) {
+        while self.pop_front().is_some() {}
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            entries: &self.entries,
+            front: self.head,
+            back: self.tail,
+            remaining: self.len,
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut {
+            entries: self.entries.as_mut_ptr(),
+            front: self.head,
+            back: self.tail,
+            remaining: self.len,
+            _boo: PhantomData,
+        }
+    }
+
+    // 按链表顺序同时给出handle和元素
+    pub fn handles(&self) -> impl Iterator<Item = (Handle, &T)> + '_ {
+        let mut cur = self.head;
+        std::iter::from_fn(move || {
+            let index = cur?;
+            match &self.entries[index] {
+                Entry::Occupied {
+                    value,
+                    generation,
+                    next,
+                    ..
+                } => {
+                    cur = *next;
+                    Some((
+                        Handle {
+                            index,
+                            generation: *generation,
+                        },
+                        value,
+                    ))
+                }
+                Entry::Vacant { .. } => unreachable!("list links to a vacant slot"),
+            }
+        })
+    }
+
+    fn value_at(&self, index: usize) -> Option<&T> {
+        match &self.entries[index] {
+            Entry::Occupied { value, .. } => Some(value),
+            Entry::Vacant { .. } => None,
+        }
+    }
+
+    fn handle_at(&self, index: usize) -> Option<Handle> {
+        match &self.entries[index] {
+            Entry::Occupied { generation, .. } => Some(Handle {
+                index,
+                generation: *generation,
+            }),
+            Entry::Vacant { .. } => None,
+        }
+    }
+
+    fn links(&self, handle: Handle) -> Option<(Option<usize>, Option<usize>)> {
+        match self.entries.get(handle.index)? {
+            Entry::Occupied {
+                generation,
+                prev,
+                next,
+                ..
+            } if *generation == handle.generation => Some((*prev, *next)),
+            _ => None,
+        }
+    }
+
+    fn set_next(&mut self, index: Option<usize>, to: Option<usize>) {
+        match index {
+            Some(i) => match &mut self.entries[i] {
+                Entry::Occupied { next, .. } => *next = to,
+                Entry::Vacant { .. } => unreachable!("list links to a vacant slot"),
+            },
+            None => self.head = to,
+        }
+    }
+
+    fn set_prev(&mut self, index: Option<usize>, to: Option<usize>) {
+        match index {
+            Some(i) => match &mut self.entries[i] {
+                Entry::Occupied { prev, .. } => *prev = to,
+                Entry::Vacant { .. } => unreachable!("list links to a vacant slot"),
+            },
+            None => self.tail = to,
+        }
+    }
+
+    // 取一个空闲槽位(没有就在末尾新建)放入value，挂在prev和next之间
+    fn link(&mut self, value: T, prev: Option<usize>, next: Option<usize>) -> Handle {
+        let (index, generation) = match self.free {
+            Some(index) => match self.entries[index] {
+                Entry::Vacant {
+                    generation,
+                    next_free,
+                } => {
+                    self.free = next_free;
+                    (index, generation)
+                }
+                Entry::Occupied { .. } => unreachable!("free list links to an occupied slot"),
+            },
+            None => {
+                self.entries.push(Entry::Vacant {
+                    generation: 0,
+                    next_free: None,
+                });
+                (self.entries.len() - 1, 0)
+            }
+        };
+        self.entries[index] = Entry::Occupied {
+            value,
+            generation,
+            prev,
+            next,
+        };
+        self.set_next(prev, Some(index));
+        self.set_prev(next, Some(index));
+        self.len += 1;
+        Handle { index, generation }
+    }
+
+    // 摘下一个已占用的槽位，代数加一后放回空闲链表
+    fn unlink(&mut self, index: usize) -> T {
+        let (prev, next, generation) = match &self.entries[index] {
+            Entry::Occupied {
+                prev,
+                next,
+                generation,
+                ..
+            } => (*prev, *next, *generation),
+            Entry::Vacant { .. } => unreachable!("list links to a vacant slot"),
+        };
+        let vacant = Entry::Vacant {
+            generation: generation + 1,
+            next_free: self.free,
+        };
+        let Entry::Occupied { value, .. } = std::mem::replace(&mut self.entries[index], vacant) else {
+            unreachable!();
+        };
+        self.free = Some(index);
+        self.set_next(prev, next);
+        self.set_prev(next, prev);
+        self.len -= 1;
+        value
+    }
+}
+
+impl<T> Default for SlabList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for SlabList<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: PartialEq> PartialEq for SlabList<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Eq> Eq for SlabList<T> {}
+
+// 克隆后槽位按链表顺序重新排列，旧handle不能用在克隆出来的链表上
+impl<T: Clone> Clone for SlabList<T> {
+    fn clone(&self) -> Self {
+        self.iter().cloned().collect()
+    }
+}
+
+impl<T> Extend<T> for SlabList<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.push_back(value);
+        }
+    }
+}
+
+impl<T> FromIterator<T> for SlabList<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = Self::new();
+        list.extend(iter);
+        list
+    }
+}
+
+pub struct Iter<'a, T> {
+    entries: &'a [Entry<T>],
+    front: Option<usize>,
+    back: Option<usize>,
+    remaining: usize,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        match &self.entries[self.front?] {
+            Entry::Occupied { value, next, .. } => {
+                self.front = *next;
+                Some(value)
+            }
+            Entry::Vacant { .. } => unreachable!("list links to a vacant slot"),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T> DoubleEndedIterator for Iter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        match &self.entries[self.back?] {
+            Entry::Occupied { value, prev, .. } => {
+                self.back = *prev;
+                Some(value)
+            }
+            Entry::Vacant { .. } => unreachable!("list links to a vacant slot"),
+        }
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+impl<T> FusedIterator for Iter<'_, T> {}
+
+impl<T> Clone for Iter<'_, T> {
+    fn clone(&self) -> Self {
+        Iter { ..*self }
+    }
+}
+
+impl<'a, T> IntoIterator for &'a SlabList<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+// 链表里的槽位互不相同，可以同时交出它们的&mut；借用检查器看不出这一点，只能经由裸指针访问
+pub struct IterMut<'a, T> {
+    entries: *mut Entry<T>,
+    front: Option<usize>,
+    back: Option<usize>,
+    remaining: usize,
+    _boo: PhantomData<&'a mut [Entry<T>]>,
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<&'a mut T> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        match unsafe { &mut *self.entries.add(self.front?) } {
+            Entry::Occupied { value, next, .. } => {
+                self.front = *next;
+                Some(value)
+            }
+            Entry::Vacant { .. } => unreachable!("list links to a vacant slot"),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T> DoubleEndedIterator for IterMut<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        match unsafe { &mut *self.entries.add(self.back?) } {
+            Entry::Occupied { value, prev, .. } => {
+                self.back = *prev;
+                Some(value)
+            }
+            Entry::Vacant { .. } => unreachable!("list links to a vacant slot"),
+        }
+    }
+}
+
+impl<T> ExactSizeIterator for IterMut<'_, T> {}
+impl<T> FusedIterator for IterMut<'_, T> {}
+
+impl<'a, T> IntoIterator for &'a mut SlabList<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> IterMut<'a, T> {
+        self.iter_mut()
+    }
+}
+
+unsafe impl<T: Send> Send for IterMut<'_, T> {}
+unsafe impl<T: Sync> Sync for IterMut<'_, T> {}
+
+pub struct IntoIter<T>(SlabList<T>);
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.0.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.0.len, Some(self.0.len))
+    }
+}
+
+impl<T> DoubleEndedIterator for IntoIter<T> {
+    fn next_back(&mut self) -> Option<T> {
+        self.0.pop_back()
+    }
+}
+
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
+impl<T> IntoIterator for SlabList<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn handles_give_o1_access_and_removal() {
+        let mut list = SlabList::new();
+        let a = list.push_back("a");
+        let c = list.push_back("c");
+        let b = list.insert_after(a, "b").unwrap();
+        let start = list.insert_before(a, "start").unwrap();
+        list.push_front("front");
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec!["front", "start", "a", "b", "c"]);
+
+        assert_eq!(list.get(b), Some(&"b"));
+        *list.get_mut(c).unwrap() = "C";
+        assert_eq!(list.remove(a), Some("a"));
+        assert_eq!(list.next_handle(start), Some(b));
+        assert_eq!(list.prev_handle(b), Some(start));
+        assert_eq!(list.back_handle(), Some(c));
+        assert_eq!(list.iter().rev().copied().collect::<Vec<_>>(), vec!["C", "b", "start", "front"]);
+    }
+
+    // 槽位被复用后，旧handle必须失效，不能读到新元素
+    #[test]
+    fn stale_handles_are_detected() {
+        let mut list = SlabList::new();
+        let old = list.push_back(1);
+        assert_eq!(list.remove(old), Some(1));
+        assert_eq!(list.remove(old), None);
+
+        let new = list.push_back(2);
+        // 复用了同一个槽位，只是代数不同
+        assert_eq!(new.index, old.index);
+        assert_ne!(new.generation, old.generation);
+        assert_eq!(list.get(old), None);
+        assert!(!list.contains(old));
+        assert_eq!(list.insert_after(old, 3), None);
+        assert_eq!(list.remove(old), None);
+        assert_eq!(list.get(new), Some(&2));
+        assert_eq!(list.len(), 1);
+    }
+
+    #[test]
+    fn clear_invalidates_all_handles() {
+        let mut list: SlabList<i32> = SlabList::with_capacity(4);
+        let handles: Vec<_> = (0..4).map(|i| list.push_back(i)).collect();
+        list.clear();
+        assert!(handles.iter().all(|&h| !list.contains(h)));
+        let reused: Vec<_> = (10..14).map(|i| list.push_front(i)).collect();
+        // 没有新分配槽位
+        assert_eq!(list.entries.len(), 4);
+        assert!(handles.iter().all(|&h| list.get(h).is_none()));
+        assert!(reused.iter().all(|&h| list.contains(h)));
+    }
+
+    #[test]
+    fn iterators_follow_list_order() {
+        let mut list: SlabList<i32> = (0..5).collect();
+        // 删除中间元素再从前端插入，槽位顺序和链表顺序不再一致
+        let h = list.handles().nth(2).map(|(h, _)| h).unwrap();
+        list.remove(h);
+        list.push_front(9);
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![9, 0, 1, 3, 4]);
+
+        let refs: Vec<&mut i32> = list.iter_mut().rev().collect();
+        for r in refs {
+            *r += 1;
+        }
+        assert_eq!(
+            list.handles().map(|(_, v)| *v).collect::<Vec<_>>(),
+            vec![10, 1, 2, 4, 5]
+        );
+        assert_eq!(list.clone(), list);
+        assert_eq!(list.into_iter().rev().collect::<Vec<_>>(), vec![5, 4, 2, 1, 10]);
+    }
+}
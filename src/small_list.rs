@@ -0,0 +1,602 @@
+// 带内联存储的小链表: 元素不超过N个时直接放在结构体内部的数组里，完全不分配堆内存；
+// 超过N个时整体"溢出"成simple_deque_3::List，之后的行为和普通链表一样
+// 实际场景里大部分链表都很短(比如哈希桶、邻接表)，内联存储省掉了每个元素一次的分配
+// 溢出后即使元素又变少也保持链表形态，避免在边界附近反复搬移；需要时可以调用shrink_to_fit搬回内联
+// 内联模式下push_front/pop_front需要整体移动，N很小时这点开销可以忽略
+
+use std::cmp::Ordering;
+use std::fmt::{self, Debug};
+use std::hash::{Hash, Hasher};
+use std::mem::{ManuallyDrop, MaybeUninit};
+use std::ptr;
+use std::slice;
+
+use crate::simple_deque_3::{self, List};
+
+// buf[..len]已初始化
+struct Inline<T, const N: usize> {
+    buf: [MaybeUninit<T>; N],
+    len: usize,
+}
+
+impl<T, const N: usize> Inline<T, N> {
+    fn new() -> Self {
+        Inline {
+            buf: [const { MaybeUninit::uninit() }; N],
+            len: 0,
+        }
+    }
+
+    fn as_slice(&self) -> &[T] {
+        unsafe { slice::from_raw_parts(self.buf.as_ptr() as *const T, self.len) }
+    }
+
+    fn as_mut_slice(&mut self) -> &mut [T] {
+        unsafe { slice::from_raw_parts_mut(self.buf.as_mut_ptr() as *mut T, self.len) }
+    }
+
+    fn base(&mut self) -> *mut T {
+        self.buf.as_mut_ptr() as *mut T
+    }
+
+    // 调用者保证len < N
+    unsafe fn push_back(&mut self, elem: T) {
+        self.base().add(self.len).write(elem);
+        self.len += 1;
+    }
+
+    unsafe fn push_front(&mut self, elem: T) {
+        let base = self.base();
+        ptr::copy(base, base.add(1), self.len);
+        base.write(elem);
+        self.len += 1;
+    }
+
+    fn pop_back(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+        self.len -= 1;
+        unsafe { Some(self.base().add(self.len).read()) }
+    }
+
+    fn pop_front(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+        let base = self.base();
+        self.len -= 1;
+        unsafe {
+            let elem = base.read();
+            ptr::copy(base.add(1), base, self.len);
+            Some(elem)
+        }
+    }
+}
+
+impl<T, const N: usize> Drop for Inline<T, N> {
+    fn drop(&mut self) {
+        unsafe { ptr::drop_in_place(self.as_mut_slice()) };
+    }
+}
+
+enum Repr<T, const N: usize> {
+    Inline(Inline<T, N>),
+    Spilled(List<T>),
+}
+
+pub struct SmallList<T, const N: usize> {
+    repr: Repr<T, N>,
+}
+
+impl<T, const N: usize> SmallList<T, N> {
+    pub fn new() -> Self {
+        SmallList {
+            repr: Repr::Inline(Inline::new()),
+        }
+    }
+
+    // 不分配堆内存时最多能放的元素个数
+    pub const fn inline_capacity() -> usize {
+        N
+    }
+
+    pub fn len(&self) -> usize {
+        match &self.repr {
+            Repr::Inline(inline) => inline.len,
+            Repr::Spilled(list) => list.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // 是否已经溢出成堆上的链表
+    pub fn is_spilled(&self) -> bool {
+        matches!(self.repr, Repr::Spilled(_))
+    }
+
+    pub fn push_back(&mut self, elem: T) {
+        match &mut self.repr {
+            Repr::Inline(inline) if inline.len < N => unsafe { inline.push_back(elem) },
+            _ => self.spill().push_back(elem),
+        }
+    }
+
+    pub fn push_front(&mut self, elem: T) {
+        match &mut self.repr {
+            Repr::Inline(inline) if inline.len < N => unsafe { inline.push_front(elem) },
+            _ => self.spill().push_front(elem),
+        }
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        match &mut self.repr {
+            Repr::Inline(inline) => inline.pop_back(),
+            Repr::Spilled(list) => list.pop_back(),
+        }
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        match &mut self.repr {
+            Repr::Inline(inline) => inline.pop_front(),
+            Repr::Spilled(list) => list.pop_front(),
+        }
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        match &self.repr {
+            Repr::Inline(inline) => inline.as_slice().first(),
+            Repr::Spilled(list) => list.front(),
+        }
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        match &self.repr {
+            Repr::Inline(inline) => inline.as_slice().last(),
+            Repr::Spilled(list) => list.back(),
+        }
+    }
+
+    pub fn front_mut(&mut self) -> Option<&mut T> {
+        match &mut self.repr {
+            Repr::Inline(inline) => inline.as_mut_slice().first_mut(),
+            Repr::Spilled(list) => list.front_mut(),
+        }
+    }
+
+    pub fn back_mut(&mut self) -> Option<&mut T> {
+        match &mut self.repr {
+            Repr::Inline(inline) => inline.as_mut_slice().last_mut(),
+            Repr::Spilled(list) => list.back_mut(),
+        }
+    }
+
+    // 清空后回到内联模式，释放链表占用的堆内存
+    pub fn clear(&mut self) {
+        self.repr = Repr::Inline(Inline::new());
+    }
+
+    // 已经溢出但元素个数又降到N以内时，搬回内联存储
+    pub fn shrink_to_fit(&mut self) {
+        if let Repr::Spilled(list) = &mut self.repr {
+            if list.len() <= N {
+                let mut inline = Inline::new();
+                while let Some(elem) = list.pop_front() {
+                    unsafe { inline.push_back(elem) };
+                }
+                self.repr = Repr::Inline(inline);
+            }
+        }
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter(match &self.repr {
+            Repr::Inline(inline) => IterRepr::Inline(inline.as_slice().iter()),
+            Repr::Spilled(list) => IterRepr::Spilled(list.iter()),
+        })
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut(match &mut self.repr {
+            Repr::Inline(inline) => IterMutRepr::Inline(inline.as_mut_slice().iter_mut()),
+            Repr::Spilled(list) => IterMutRepr::Spilled(list.iter_mut()),
+        })
+    }
+
+    // 把内联的元素按顺序搬进新链表，返回链表
+    fn spill(&mut self) -> &mut List<T> {
+        if let Repr::Inline(inline) = &mut self.repr {
+            let mut list = List::new();
+            while let Some(elem) = inline.pop_front() {
+                list.push_back(elem);
+            }
+            self.repr = Repr::Spilled(list);
+        }
+        match &mut self.repr {
+            Repr::Spilled(list) => list,
+            Repr::Inline(_) => unreachable!(),
+        }
+    }
+}
+
+impl<T, const N: usize> Default for SmallList<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Clone, const N: usize> Clone for SmallList<T, N> {
+    fn clone(&self) -> Self {
+        self.iter().cloned().collect()
+    }
+}
+
+impl<T, const N: usize> Extend<T> for SmallList<T, N> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.push_back(item);
+        }
+    }
+}
+
+impl<'a, T: Copy + 'a, const N: usize> Extend<&'a T> for SmallList<T, N> {
+    fn extend<I: IntoIterator<Item = &'a T>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().copied());
+    }
+}
+
+impl<T, const N: usize> FromIterator<T> for SmallList<T, N> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = Self::new();
+        list.extend(iter);
+        list
+    }
+}
+
+impl<T: Debug, const N: usize> Debug for SmallList<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self).finish()
+    }
+}
+
+// 比较和哈希只看元素序列，和当前是否溢出无关
+impl<T: PartialEq, const N: usize> PartialEq for SmallList<T, N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other)
+    }
+}
+
+impl<T: Eq, const N: usize> Eq for SmallList<T, N> {}
+
+impl<T: PartialOrd, const N: usize> PartialOrd for SmallList<T, N> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.iter().partial_cmp(other)
+    }
+}
+
+impl<T: Ord, const N: usize> Ord for SmallList<T, N> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.iter().cmp(other)
+    }
+}
+
+impl<T: Hash, const N: usize> Hash for SmallList<T, N> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.len().hash(state);
+        for item in self {
+            item.hash(state);
+        }
+    }
+}
+
+pub struct Iter<'a, T>(IterRepr<'a, T>);
+
+enum IterRepr<'a, T> {
+    Inline(slice::Iter<'a, T>),
+    Spilled(simple_deque_3::Iter<'a, T>),
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        match &mut self.0 {
+            IterRepr::Inline(it) => it.next(),
+            IterRepr::Spilled(it) => it.next(),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match &self.0 {
+            IterRepr::Inline(it) => it.size_hint(),
+            IterRepr::Spilled(it) => it.size_hint(),
+        }
+    }
+}
+
+impl<T> DoubleEndedIterator for Iter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        match &mut self.0 {
+            IterRepr::Inline(it) => it.next_back(),
+            IterRepr::Spilled(it) => it.next_back(),
+        }
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+impl<T> Clone for Iter<'_, T> {
+    fn clone(&self) -> Self {
+        Iter(match &self.0 {
+            IterRepr::Inline(it) => IterRepr::Inline(it.clone()),
+            IterRepr::Spilled(it) => IterRepr::Spilled(it.clone()),
+        })
+    }
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a SmallList<T, N> {
+    type IntoIter = Iter<'a, T>;
+    type Item = &'a T;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+pub struct IterMut<'a, T>(IterMutRepr<'a, T>);
+
+enum IterMutRepr<'a, T> {
+    Inline(slice::IterMut<'a, T>),
+    Spilled(simple_deque_3::IterMut<'a, T>),
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<&'a mut T> {
+        match &mut self.0 {
+            IterMutRepr::Inline(it) => it.next(),
+            IterMutRepr::Spilled(it) => it.next(),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match &self.0 {
+            IterMutRepr::Inline(it) => it.size_hint(),
+            IterMutRepr::Spilled(it) => it.size_hint(),
+        }
+    }
+}
+
+impl<T> DoubleEndedIterator for IterMut<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        match &mut self.0 {
+            IterMutRepr::Inline(it) => it.next_back(),
+            IterMutRepr::Spilled(it) => it.next_back(),
+        }
+    }
+}
+
+impl<T> ExactSizeIterator for IterMut<'_, T> {}
+
+impl<'a, T, const N: usize> IntoIterator for &'a mut SmallList<T, N> {
+    type IntoIter = IterMut<'a, T>;
+    type Item = &'a mut T;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+// 内联模式下用[start, end)标记还没取走的元素，两端取都是O(1)
+pub struct IntoIter<T, const N: usize>(IntoIterRepr<T, N>);
+
+enum IntoIterRepr<T, const N: usize> {
+    Inline {
+        buf: [MaybeUninit<T>; N],
+        start: usize,
+        end: usize,
+    },
+    Spilled(simple_deque_3::IntoIter<T>),
+}
+
+impl<T, const N: usize> Iterator for IntoIter<T, N> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        match &mut self.0 {
+            IntoIterRepr::Inline { buf, start, end } => {
+                if start == end {
+                    return None;
+                }
+                *start += 1;
+                unsafe { Some(buf[*start - 1].assume_init_read()) }
+            }
+            IntoIterRepr::Spilled(it) => it.next(),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match &self.0 {
+            IntoIterRepr::Inline { start, end, .. } => (end - start, Some(end - start)),
+            IntoIterRepr::Spilled(it) => it.size_hint(),
+        }
+    }
+}
+
+impl<T, const N: usize> DoubleEndedIterator for IntoIter<T, N> {
+    fn next_back(&mut self) -> Option<T> {
+        match &mut self.0 {
+            IntoIterRepr::Inline { buf, start, end } => {
+                if start == end {
+                    return None;
+                }
+                *end -= 1;
+                unsafe { Some(buf[*end].assume_init_read()) }
+            }
+            IntoIterRepr::Spilled(it) => it.next_back(),
+        }
+    }
+}
+
+impl<T, const N: usize> ExactSizeIterator for IntoIter<T, N> {}
+
+impl<T, const N: usize> Drop for IntoIter<T, N> {
+    fn drop(&mut self) {
+        if let IntoIterRepr::Inline { buf, start, end } = &mut self.0 {
+            for slot in &mut buf[*start..*end] {
+                unsafe { slot.assume_init_drop() };
+            }
+        }
+    }
+}
+
+impl<T, const N: usize> IntoIterator for SmallList<T, N> {
+    type IntoIter = IntoIter<T, N>;
+    type Item = T;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter(match self.repr {
+            Repr::Inline(inline) => {
+                // 元素的所有权转给迭代器，Inline自己的Drop不能再运行
+                let inline = ManuallyDrop::new(inline);
+                IntoIterRepr::Inline {
+                    buf: unsafe { ptr::read(&inline.buf) },
+                    start: 0,
+                    end: inline.len,
+                }
+            }
+            Repr::Spilled(list) => IntoIterRepr::Spilled(list.into_iter()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::XorShift;
+    use std::collections::hash_map::DefaultHasher;
+    use std::collections::VecDeque;
+    use std::rc::Rc;
+
+    fn hash_of<T: Hash>(value: &T) -> u64 {
+        let mut h = DefaultHasher::new();
+        value.hash(&mut h);
+        h.finish()
+    }
+
+    #[test]
+    fn spills_past_inline_capacity() {
+        let mut list = SmallList::<i32, 3>::new();
+        assert_eq!(SmallList::<i32, 3>::inline_capacity(), 3);
+        list.push_back(2);
+        list.push_front(1);
+        list.push_back(3);
+        assert!(!list.is_spilled());
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![1, 2, 3]);
+
+        list.push_front(0);
+        assert!(list.is_spilled());
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![0, 1, 2, 3]);
+
+        // 溢出后变少也保持链表，shrink_to_fit才搬回去
+        assert_eq!(list.pop_back(), Some(3));
+        assert!(list.is_spilled());
+        list.shrink_to_fit();
+        assert!(!list.is_spilled());
+        assert_eq!(list.front(), Some(&0));
+        assert_eq!(list.back(), Some(&2));
+
+        list.clear();
+        assert!(list.is_empty());
+        assert!(!list.is_spilled());
+    }
+
+    #[test]
+    fn traits_ignore_representation() {
+        let inline: SmallList<i32, 4> = (1..=3).collect();
+        let mut spilled: SmallList<i32, 4> = (0..=5).collect();
+        spilled.pop_front();
+        spilled.pop_back();
+        spilled.pop_back();
+        assert!(spilled.is_spilled() && !inline.is_spilled());
+        assert_eq!(inline, spilled);
+        assert_eq!(hash_of(&inline), hash_of(&spilled));
+        assert_eq!(inline.cmp(&spilled), Ordering::Equal);
+        spilled.push_back(0);
+        assert!(inline < spilled);
+        assert_eq!(format!("{:?}", inline), "[1, 2, 3]");
+        let cloned = inline.clone();
+        assert_eq!(cloned, inline);
+
+        let mut ext = SmallList::<i32, 2>::new();
+        ext.extend(&[7, 8, 9]);
+        assert_eq!(ext.iter().rev().copied().collect::<Vec<_>>(), vec![9, 8, 7]);
+    }
+
+    #[test]
+    fn iterators_in_both_modes() {
+        for n in [2, 6] {
+            let mut list: SmallList<i32, 4> = (0..n).collect();
+            for x in &mut list {
+                *x *= 10;
+            }
+            *list.front_mut().unwrap() += 1;
+            *list.back_mut().unwrap() += 2;
+            let mut expected: Vec<i32> = (0..n).map(|x| x * 10).collect();
+            expected[0] += 1;
+            *expected.last_mut().unwrap() += 2;
+            assert_eq!(list.iter().copied().collect::<Vec<_>>(), expected);
+            assert_eq!(list.iter().len(), n as usize);
+
+            let mut it = list.into_iter();
+            assert_eq!(it.next_back(), expected.last().copied());
+            assert_eq!(it.next(), Some(expected[0]));
+            assert_eq!(it.len(), n as usize - 2);
+        }
+    }
+
+    #[test]
+    fn drops_across_spill_boundary() {
+        let marker = Rc::new(());
+        for n in 0..8 {
+            let mut list = SmallList::<Rc<()>, 4>::new();
+            for _ in 0..n {
+                list.push_back(Rc::clone(&marker));
+            }
+            assert_eq!(list.is_spilled(), n > 4);
+            assert_eq!(Rc::strong_count(&marker), n + 1);
+            // 取一个元素后丢弃剩下的迭代器
+            let mut it = list.into_iter();
+            drop(it.next());
+            drop(it);
+            assert_eq!(Rc::strong_count(&marker), 1);
+        }
+    }
+
+    #[test]
+    fn randomized_against_vecdeque() {
+        let mut rng = XorShift::new(77);
+        let mut list = SmallList::<u64, 4>::new();
+        let mut reference = VecDeque::new();
+        for step in 0..3000 {
+            match rng.next_u64() % 5 {
+                0 => {
+                    list.push_back(step);
+                    reference.push_back(step);
+                }
+                1 => {
+                    list.push_front(step);
+                    reference.push_front(step);
+                }
+                2 => assert_eq!(list.pop_back(), reference.pop_back()),
+                3 => assert_eq!(list.pop_front(), reference.pop_front()),
+                _ => list.shrink_to_fit(),
+            }
+            assert_eq!(list.len(), reference.len());
+            assert!(list.iter().eq(reference.iter()));
+        }
+    }
+}
@@ -0,0 +1,428 @@
+// 始终保持有序的链表(有序多重集合)
+// 底层就是simple_deque_3::List，插入/删除/合并/拆分都用游标的节点操作完成:
+// - insert沿游标找到第一个比新元素大的位置，insert_before，相等元素保持插入顺序(稳定)
+// - merge把另一条有序链表按段切下来(split_before)接到对应位置(splice_before)，全程只改指针，不重新分配节点
+// - split_off在分界点直接切断链表
+// 链表不能随机访问，查找都是O(n)；按升序追加时insert会走push_back的O(1)快路径
+
+use std::borrow::Borrow;
+use std::fmt;
+use std::iter::{Skip, Take};
+use std::ops::{Bound, RangeBounds};
+
+use crate::simple_deque_3::{self, List};
+
+pub struct SortedList<T> {
+    list: List<T>,
+}
+
+impl<T: Ord> SortedList<T> {
+    pub fn new() -> Self {
+        SortedList { list: List::new() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.list.clear();
+    }
+
+    // 插到所有小于等于value的元素之后
+    pub fn insert(&mut self, value: T) {
+        if self.list.back().is_none_or(|last| *last <= value) {
+            self.list.push_back(value);
+            return;
+        }
+        let mut cursor = self.list.cursor_mut();
+        cursor.move_next();
+        while cursor.current().is_some_and(|cur| *cur <= value) {
+            cursor.move_next();
+        }
+        cursor.insert_before(value);
+    }
+
+    // 已经有相等元素时不插入，返回false，当作有序集合使用
+    pub fn insert_unique(&mut self, value: T) -> bool {
+        if self.list.back().is_none_or(|last| *last < value) {
+            self.list.push_back(value);
+            return true;
+        }
+        let mut cursor = self.list.cursor_mut();
+        cursor.move_next();
+        while let Some(cur) = cursor.current() {
+            if *cur == value {
+                return false;
+            }
+            if *cur > value {
+                break;
+            }
+            cursor.move_next();
+        }
+        cursor.insert_before(value);
+        true
+    }
+
+    pub fn contains<Q>(&self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        // 有序，遇到更大的元素就可以停下
+        self.iter()
+            .map(Borrow::borrow)
+            .take_while(|cur| *cur <= value)
+            .any(|cur| cur == value)
+    }
+
+    // 删除第一个等于value的元素
+    pub fn remove<Q>(&mut self, value: &Q) -> Option<T>
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut cursor = self.list.cursor_mut();
+        cursor.move_next();
+        while let Some(cur) = cursor.current() {
+            match (*cur).borrow().cmp(value) {
+                std::cmp::Ordering::Less => cursor.move_next(),
+                std::cmp::Ordering::Equal => return cursor.remove_current(),
+                std::cmp::Ordering::Greater => return None,
+            }
+        }
+        None
+    }
+
+    pub fn first(&self) -> Option<&T> {
+        self.list.front()
+    }
+
+    pub fn last(&self) -> Option<&T> {
+        self.list.back()
+    }
+
+    pub fn pop_first(&mut self) -> Option<T> {
+        self.list.pop_front()
+    }
+
+    pub fn pop_last(&mut self) -> Option<T> {
+        self.list.pop_back()
+    }
+
+    // 落在range里的元素，升序；先数出起点和个数，再在普通迭代器上skip/take，所以仍然可以双端迭代
+    pub fn range<Q, R>(&self, range: R) -> Take<Skip<simple_deque_3::Iter<'_, T>>>
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        let above_start = |x: &Q| match range.start_bound() {
+            Bound::Included(lo) => x >= lo,
+            Bound::Excluded(lo) => x > lo,
+            Bound::Unbounded => true,
+        };
+        let below_end = |x: &Q| match range.end_bound() {
+            Bound::Included(hi) => x <= hi,
+            Bound::Excluded(hi) => x < hi,
+            Bound::Unbounded => true,
+        };
+        let skip = self.iter().take_while(|x| !above_start((*x).borrow())).count();
+        let take = self
+            .iter()
+            .skip(skip)
+            .take_while(|x| below_end((*x).borrow()))
+            .count();
+        self.iter().skip(skip).take(take)
+    }
+
+    // 把other的所有节点按序并入self，O(n + m)，不分配新节点
+    // 相等元素里self原有的排在前面
+    pub fn merge(&mut self, mut other: SortedList<T>) {
+        let mut cursor = self.list.cursor_mut();
+        cursor.move_next();
+        while !other.is_empty() {
+            let Some(cur) = cursor.current() else {
+                // 走到了self尾部，other剩下的整段接在最后
+                cursor.splice_before(std::mem::take(&mut other.list));
+                break;
+            };
+            // 从other切下严格小于cur的一段，接到cur前面
+            let mut other_cursor = other.list.cursor_mut();
+            other_cursor.move_next();
+            while other_cursor.current().is_some_and(|x| *x < *cur) {
+                other_cursor.move_next();
+            }
+            let run = other_cursor.split_before();
+            cursor.splice_before(run);
+            cursor.move_next();
+        }
+    }
+
+    // 把所有大于等于value的元素切下来作为新的SortedList返回，O(n)找分界点，O(1)切断
+    pub fn split_off<Q>(&mut self, value: &Q) -> SortedList<T>
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut cursor = self.list.cursor_mut();
+        cursor.move_next();
+        while cursor.current().is_some_and(|cur| (*cur).borrow() < value) {
+            cursor.move_next();
+        }
+        let before = cursor.split_before();
+        let after = std::mem::replace(&mut self.list, before);
+        SortedList { list: after }
+    }
+
+    // 去掉重复元素，每组相等元素保留第一个
+    pub fn dedup(&mut self) {
+        self.dedup_by(|cur, prev| *cur == *prev);
+    }
+
+    // 和Vec::dedup_by一样: same(cur, prev)返回true时删掉cur，prev是前面保留下来的那个元素
+    // 闭包可以修改两个元素(比如把cur合并进prev)，但不能改变prev在链表中应有的位置
+    pub fn dedup_by<F>(&mut self, mut same: F)
+    where
+        F: FnMut(&mut T, &mut T) -> bool,
+    {
+        let mut cursor = self.list.cursor_mut();
+        cursor.move_next();
+        loop {
+            let duplicate = match (cursor.peek_prev().map(|p| p as *mut T), cursor.current()) {
+                // SAFETY: prev和current是两个不同的节点
+                (Some(prev), Some(cur)) => same(cur, unsafe { &mut *prev }),
+                (_, None) => break,
+                (None, Some(_)) => false,
+            };
+            if duplicate {
+                cursor.remove_current();
+            } else {
+                cursor.move_next();
+            }
+        }
+    }
+
+    // 只保留keep返回true的元素，删除不影响顺序
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, mut keep: F) {
+        let mut cursor = self.list.cursor_mut();
+        cursor.move_next();
+        while let Some(cur) = cursor.current() {
+            if keep(cur) {
+                cursor.move_next();
+            } else {
+                cursor.remove_current();
+            }
+        }
+    }
+}
+
+impl<T> SortedList<T> {
+    pub fn iter(&self) -> simple_deque_3::Iter<'_, T> {
+        self.list.iter()
+    }
+
+    // 拿出底层链表，之后不再保证有序
+    pub fn into_list(self) -> List<T> {
+        self.list
+    }
+}
+
+impl<T: Ord> Default for SortedList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Ord + Clone> Clone for SortedList<T> {
+    fn clone(&self) -> Self {
+        SortedList {
+            list: self.list.clone(),
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for SortedList<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: PartialEq> PartialEq for SortedList<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.list == other.list
+    }
+}
+
+impl<T: Eq> Eq for SortedList<T> {}
+
+impl<T: Ord> Extend<T> for SortedList<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.insert(value);
+        }
+    }
+}
+
+// 先在Vec里稳定排序再建链表，避免n次O(n)的插入
+impl<T: Ord> FromIterator<T> for SortedList<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut items: Vec<T> = iter.into_iter().collect();
+        items.sort();
+        SortedList {
+            list: items.into_iter().collect(),
+        }
+    }
+}
+
+impl<T: Ord> From<Vec<T>> for SortedList<T> {
+    fn from(items: Vec<T>) -> Self {
+        items.into_iter().collect()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a SortedList<T> {
+    type Item = &'a T;
+    type IntoIter = simple_deque_3::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T> IntoIterator for SortedList<T> {
+    type Item = T;
+    type IntoIter = simple_deque_3::IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.list.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::XorShift;
+
+    fn items(list: &SortedList<i32>) -> Vec<i32> {
+        list.iter().copied().collect()
+    }
+
+    #[test]
+    fn insert_keeps_order() {
+        let mut list = SortedList::new();
+        for x in [5, 1, 4, 1, 5, 9, 2, 6] {
+            list.insert(x);
+        }
+        assert_eq!(items(&list), vec![1, 1, 2, 4, 5, 5, 6, 9]);
+        assert!(list.contains(&4));
+        assert!(!list.contains(&3));
+        assert_eq!(list.remove(&5), Some(5));
+        assert_eq!(list.remove(&3), None);
+        assert_eq!(items(&list), vec![1, 1, 2, 4, 5, 6, 9]);
+        assert_eq!(list.first(), Some(&1));
+        assert_eq!(list.pop_last(), Some(9));
+        list.dedup();
+        assert_eq!(items(&list), vec![1, 2, 4, 5, 6]);
+        assert!(!list.insert_unique(4));
+        assert!(list.insert_unique(3));
+        assert!(list.insert_unique(7));
+        assert_eq!(items(&list), vec![1, 2, 3, 4, 5, 6, 7]);
+    }
+
+    #[test]
+    fn equal_elements_are_stable() {
+        let mut list = SortedList::new();
+        for (k, tag) in [(2, 'a'), (1, 'b'), (2, 'c'), (1, 'd')] {
+            list.insert(Keyed(k, tag));
+        }
+        let tags: String = list.iter().map(|k| k.1).collect();
+        assert_eq!(tags, "bdac");
+    }
+
+    // 只按第一个字段比较，用来观察相等元素的先后
+    #[derive(Debug)]
+    struct Keyed(i32, char);
+
+    impl PartialEq for Keyed {
+        fn eq(&self, other: &Self) -> bool {
+            self.0 == other.0
+        }
+    }
+
+    impl Eq for Keyed {}
+
+    impl PartialOrd for Keyed {
+        fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl Ord for Keyed {
+        fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+            self.0.cmp(&other.0)
+        }
+    }
+
+    #[test]
+    fn range_between_bounds() {
+        let list: SortedList<i32> = (0..10).map(|x| x * 2).collect();
+        assert_eq!(list.range(3..9).copied().collect::<Vec<_>>(), vec![4, 6, 8]);
+        assert_eq!(list.range(4..=8).rev().copied().collect::<Vec<_>>(), vec![8, 6, 4]);
+        assert_eq!(list.range(..3).len(), 2);
+        assert_eq!(list.range(15..).copied().collect::<Vec<_>>(), vec![16, 18]);
+        assert_eq!(list.range(100..).count(), 0);
+        assert_eq!(list.range((Bound::Excluded(4), Bound::Excluded(8))).count(), 1);
+    }
+
+    #[test]
+    fn merge_and_split_move_nodes() {
+        let mut a: SortedList<i32> = vec![1, 4, 4, 7, 10].into();
+        let b: SortedList<i32> = vec![0, 2, 4, 8, 11, 12].into();
+        let first_b = b.first().unwrap() as *const i32;
+        a.merge(b);
+        assert_eq!(items(&a), vec![0, 1, 2, 4, 4, 4, 7, 8, 10, 11, 12]);
+        // 节点是直接搬过来的，元素地址不变
+        assert_eq!(a.first().unwrap() as *const i32, first_b);
+        let high = a.split_off(&7);
+        assert_eq!(items(&a), vec![0, 1, 2, 4, 4, 4]);
+        assert_eq!(items(&high), vec![7, 8, 10, 11, 12]);
+        a.merge(SortedList::new());
+        let mut empty = SortedList::new();
+        empty.merge(high);
+        assert_eq!(empty.len(), 5);
+        assert!(a.split_off(&100).is_empty());
+    }
+
+    #[test]
+    fn randomized_against_sorted_vec() {
+        let mut rng = XorShift::new(0x5011);
+        let mut list = SortedList::new();
+        let mut model: Vec<u64> = Vec::new();
+        for _ in 0..1500 {
+            let x = rng.next_u64() % 50;
+            match rng.next_u64() % 4 {
+                0 | 1 => {
+                    list.insert(x);
+                    let pos = model.partition_point(|&y| y <= x);
+                    model.insert(pos, x);
+                }
+                2 => {
+                    let pos = model.iter().position(|&y| y == x);
+                    assert_eq!(list.remove(&x), pos.map(|p| model.remove(p)));
+                }
+                _ => {
+                    let other: SortedList<u64> = (0..rng.next_u64() % 4).map(|_| rng.next_u64() % 50).collect();
+                    model.extend(other.iter().copied());
+                    model.sort();
+                    list.merge(other);
+                }
+            }
+        }
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), model);
+    }
+}
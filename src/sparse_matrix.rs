@@ -0,0 +1,375 @@
+// 十字链表(orthogonal list)表示的稀疏矩阵
+// 每个非零元素是一个节点，同时挂在两条单链表上:
+// - 行链表: 同一行的元素按列号升序用right串起来
+// - 列链表: 同一列的元素按行号升序用down串起来
+// 按行遍历、按列遍历都只走实际存在的元素；get/set/remove走行链表和列链表定位，O(该行 + 该列的元素数)
+// 转置只需要在每个节点上交换行列号和right/down两个指针，再交换行头和列头数组，不移动任何元素
+// 节点放在slab里用下标链接，删除的节点槽位进空闲列表复用
+
+use std::fmt;
+use std::mem;
+
+const NIL: usize = usize::MAX;
+
+struct Node<T> {
+    row: usize,
+    col: usize,
+    value: T,
+    // 同一行的下一个元素
+    right: usize,
+    // 同一列的下一个元素
+    down: usize,
+}
+
+pub struct SparseMatrix<T> {
+    nodes: Vec<Option<Node<T>>>,
+    free: Vec<usize>,
+    row_heads: Vec<usize>,
+    col_heads: Vec<usize>,
+    nnz: usize,
+}
+
+impl<T> SparseMatrix<T> {
+    pub fn new(rows: usize, cols: usize) -> Self {
+        SparseMatrix {
+            nodes: Vec::new(),
+            free: Vec::new(),
+            row_heads: vec![NIL; rows],
+            col_heads: vec![NIL; cols],
+            nnz: 0,
+        }
+    }
+
+    pub fn rows(&self) -> usize {
+        self.row_heads.len()
+    }
+
+    pub fn cols(&self) -> usize {
+        self.col_heads.len()
+    }
+
+    // 存储的元素个数
+    pub fn nnz(&self) -> usize {
+        self.nnz
+    }
+
+    fn node(&self, index: usize) -> &Node<T> {
+        self.nodes[index].as_ref().unwrap()
+    }
+
+    fn node_mut(&mut self, index: usize) -> &mut Node<T> {
+        self.nodes[index].as_mut().unwrap()
+    }
+
+    fn check_bounds(&self, row: usize, col: usize) {
+        assert!(
+            row < self.rows() && col < self.cols(),
+            "index ({row}, {col}) out of bounds for {}x{} matrix",
+            self.rows(),
+            self.cols()
+        );
+    }
+
+    // 在行链表里找col的位置: (前驱, 自己)，前驱为NIL表示在表头
+    fn find_in_row(&self, row: usize, col: usize) -> (usize, usize) {
+        let mut prev = NIL;
+        let mut cur = self.row_heads[row];
+        while cur != NIL && self.node(cur).col < col {
+            prev = cur;
+            cur = self.node(cur).right;
+        }
+        let found = if cur != NIL && self.node(cur).col == col { cur } else { NIL };
+        (prev, found)
+    }
+
+    // 在列链表里找row的前驱
+    fn prev_in_col(&self, row: usize, col: usize) -> usize {
+        let mut prev = NIL;
+        let mut cur = self.col_heads[col];
+        while cur != NIL && self.node(cur).row < row {
+            prev = cur;
+            cur = self.node(cur).down;
+        }
+        prev
+    }
+
+    // 越界时panic
+    pub fn get(&self, row: usize, col: usize) -> Option<&T> {
+        self.check_bounds(row, col);
+        let (_, found) = self.find_in_row(row, col);
+        (found != NIL).then(|| &self.node(found).value)
+    }
+
+    pub fn get_mut(&mut self, row: usize, col: usize) -> Option<&mut T> {
+        self.check_bounds(row, col);
+        let (_, found) = self.find_in_row(row, col);
+        (found != NIL).then(|| &mut self.node_mut(found).value)
+    }
+
+    // 写入一个元素，返回原来的值；越界时panic
+    pub fn set(&mut self, row: usize, col: usize, value: T) -> Option<T> {
+        self.check_bounds(row, col);
+        let (row_prev, found) = self.find_in_row(row, col);
+        if found != NIL {
+            return Some(mem::replace(&mut self.node_mut(found).value, value));
+        }
+        let col_prev = self.prev_in_col(row, col);
+        let right = match row_prev {
+            NIL => self.row_heads[row],
+            p => self.node(p).right,
+        };
+        let down = match col_prev {
+            NIL => self.col_heads[col],
+            p => self.node(p).down,
+        };
+        let node = Some(Node {
+            row,
+            col,
+            value,
+            right,
+            down,
+        });
+        let index = match self.free.pop() {
+            Some(index) => {
+                self.nodes[index] = node;
+                index
+            }
+            None => {
+                self.nodes.push(node);
+                self.nodes.len() - 1
+            }
+        };
+        match row_prev {
+            NIL => self.row_heads[row] = index,
+            p => self.node_mut(p).right = index,
+        }
+        match col_prev {
+            NIL => self.col_heads[col] = index,
+            p => self.node_mut(p).down = index,
+        }
+        self.nnz += 1;
+        None
+    }
+
+    // 删掉一个元素，同时从行链表和列链表里摘下；越界时panic
+    pub fn remove(&mut self, row: usize, col: usize) -> Option<T> {
+        self.check_bounds(row, col);
+        let (row_prev, found) = self.find_in_row(row, col);
+        if found == NIL {
+            return None;
+        }
+        let col_prev = self.prev_in_col(row, col);
+        let node = self.nodes[found].take().unwrap();
+        self.free.push(found);
+        match row_prev {
+            NIL => self.row_heads[row] = node.right,
+            p => self.node_mut(p).right = node.right,
+        }
+        match col_prev {
+            NIL => self.col_heads[col] = node.down,
+            p => self.node_mut(p).down = node.down,
+        }
+        self.nnz -= 1;
+        Some(node.value)
+    }
+
+    // 第row行的(列号, 值)，列号升序
+    pub fn row(&self, row: usize) -> Line<'_, T> {
+        Line {
+            matrix: self,
+            cur: self.row_heads[row],
+            along_row: true,
+        }
+    }
+
+    // 第col列的(行号, 值)，行号升序
+    pub fn col(&self, col: usize) -> Line<'_, T> {
+        Line {
+            matrix: self,
+            cur: self.col_heads[col],
+            along_row: false,
+        }
+    }
+
+    // 按行优先顺序给出所有(行号, 列号, 值)
+    pub fn iter(&self) -> impl Iterator<Item = (usize, usize, &T)> + '_ {
+        (0..self.rows()).flat_map(move |r| self.row(r).map(move |(c, v)| (r, c, v)))
+    }
+
+    // 原地转置，O(元素数 + 行数 + 列数)，只改指针
+    pub fn transpose(&mut self) {
+        for node in self.nodes.iter_mut().flatten() {
+            mem::swap(&mut node.row, &mut node.col);
+            mem::swap(&mut node.right, &mut node.down);
+        }
+        mem::swap(&mut self.row_heads, &mut self.col_heads);
+    }
+
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+        self.free.clear();
+        self.row_heads.fill(NIL);
+        self.col_heads.fill(NIL);
+        self.nnz = 0;
+    }
+}
+
+impl<T: Clone> Clone for SparseMatrix<T> {
+    // 按行优先重新插入，得到的链表结构和原矩阵一致
+    fn clone(&self) -> Self {
+        let mut out = SparseMatrix::new(self.rows(), self.cols());
+        for (r, c, v) in self.iter() {
+            out.set(r, c, v.clone());
+        }
+        out
+    }
+}
+
+impl<T: PartialEq> PartialEq for SparseMatrix<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.rows() == other.rows() && self.cols() == other.cols() && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Eq> Eq for SparseMatrix<T> {}
+
+impl<T: fmt::Debug> fmt::Debug for SparseMatrix<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SparseMatrix {}x{} ", self.rows(), self.cols())?;
+        f.debug_map().entries(self.iter().map(|(r, c, v)| ((r, c), v))).finish()
+    }
+}
+
+// 沿一行或一列遍历
+pub struct Line<'a, T> {
+    matrix: &'a SparseMatrix<T>,
+    cur: usize,
+    along_row: bool,
+}
+
+impl<'a, T> Iterator for Line<'a, T> {
+    // 行遍历给出列号，列遍历给出行号
+    type Item = (usize, &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.cur == NIL {
+            return None;
+        }
+        let node = self.matrix.node(self.cur);
+        if self.along_row {
+            self.cur = node.right;
+            Some((node.col, &node.value))
+        } else {
+            self.cur = node.down;
+            Some((node.row, &node.value))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::XorShift;
+
+    // 行链表和列链表都有序，且两边看到的元素一致
+    fn check<T: PartialEq + fmt::Debug>(m: &SparseMatrix<T>) {
+        let mut by_row = Vec::new();
+        for r in 0..m.rows() {
+            let cols: Vec<usize> = m.row(r).map(|(c, _)| c).collect();
+            assert!(cols.windows(2).all(|w| w[0] < w[1]));
+            by_row.extend(m.row(r).map(|(c, v)| (r, c, v)));
+        }
+        let mut by_col = Vec::new();
+        for c in 0..m.cols() {
+            let rows: Vec<usize> = m.col(c).map(|(r, _)| r).collect();
+            assert!(rows.windows(2).all(|w| w[0] < w[1]));
+            by_col.extend(m.col(c).map(|(r, v)| (r, c, v)));
+        }
+        by_col.sort_by_key(|&(r, c, _)| (r, c));
+        assert_eq!(by_row, by_col);
+        assert_eq!(by_row.len(), m.nnz());
+    }
+
+    #[test]
+    fn set_get_remove() {
+        let mut m = SparseMatrix::new(3, 4);
+        assert_eq!(m.set(1, 2, 'a'), None);
+        assert_eq!(m.set(1, 0, 'b'), None);
+        assert_eq!(m.set(2, 2, 'c'), None);
+        assert_eq!(m.set(0, 2, 'd'), None);
+        assert_eq!(m.set(1, 2, 'A'), Some('a'));
+        assert_eq!(m.get(1, 2), Some(&'A'));
+        assert_eq!(m.get(0, 0), None);
+        assert_eq!(m.nnz(), 4);
+        assert_eq!(m.row(1).collect::<Vec<_>>(), vec![(0, &'b'), (2, &'A')]);
+        assert_eq!(m.col(2).collect::<Vec<_>>(), vec![(0, &'d'), (1, &'A'), (2, &'c')]);
+        check(&m);
+        assert_eq!(m.remove(1, 2), Some('A'));
+        assert_eq!(m.remove(1, 2), None);
+        assert_eq!(m.col(2).collect::<Vec<_>>(), vec![(0, &'d'), (2, &'c')]);
+        *m.get_mut(2, 2).unwrap() = 'C';
+        check(&m);
+        assert_eq!(format!("{m:?}"), "SparseMatrix 3x4 {(0, 2): 'd', (1, 0): 'b', (2, 2): 'C'}");
+    }
+
+    #[test]
+    #[should_panic(expected = "out of bounds")]
+    fn out_of_bounds_panics() {
+        let mut m = SparseMatrix::new(2, 2);
+        m.set(2, 0, 1);
+    }
+
+    #[test]
+    fn transpose_swaps_links() {
+        let mut m = SparseMatrix::new(2, 3);
+        m.set(0, 1, 1);
+        m.set(0, 2, 2);
+        m.set(1, 0, 3);
+        let original = m.clone();
+        m.transpose();
+        assert_eq!((m.rows(), m.cols()), (3, 2));
+        assert_eq!(m.get(1, 0), Some(&1));
+        assert_eq!(m.get(2, 0), Some(&2));
+        assert_eq!(m.get(0, 1), Some(&3));
+        assert_eq!(m.row(0).collect::<Vec<_>>(), vec![(1, &3)]);
+        check(&m);
+        // 转置之后照样能增删
+        m.set(2, 1, 4);
+        m.remove(1, 0);
+        check(&m);
+        m.transpose();
+        assert_ne!(m, original);
+        m.set(0, 1, 1);
+        m.remove(1, 2);
+        assert_eq!(m, original);
+    }
+
+    #[test]
+    fn randomized_against_dense() {
+        let mut rng = XorShift::new(0x5a7);
+        let (rows, cols) = (7, 9);
+        let mut m = SparseMatrix::new(rows, cols);
+        let mut dense = vec![vec![None; cols]; rows];
+        for step in 0..3000u64 {
+            let r = (rng.next_u64() as usize) % rows;
+            let c = (rng.next_u64() as usize) % cols;
+            match rng.next_u64() % 4 {
+                0 | 1 => assert_eq!(m.set(r, c, step), dense[r][c].replace(step)),
+                2 => assert_eq!(m.remove(r, c), dense[r][c].take()),
+                _ => {
+                    if step % 100 == 3 {
+                        m.transpose();
+                        m.transpose();
+                    }
+                    assert_eq!(m.get(r, c), dense[r][c].as_ref());
+                }
+            }
+        }
+        check(&m);
+        let expect: Vec<(usize, usize, &u64)> = (0..rows)
+            .flat_map(|r| (0..cols).filter_map(move |c| Some(r).zip(Some(c))))
+            .filter_map(|(r, c)| dense[r][c].as_ref().map(|v| (r, c, v)))
+            .collect();
+        assert_eq!(m.iter().collect::<Vec<_>>(), expect);
+    }
+}
@@ -0,0 +1,270 @@
+// 单生产者单消费者(SPSC)无等待链表队列
+// 只有一个线程push、一个线程pop时，完全不需要CAS:
+// - head(哑节点)只有消费者读写，tail只有生产者读写
+// - 两边唯一共享的是节点的next指针: 生产者先写好元素再Release发布next，消费者Acquire读next
+// 消费者只释放已经越过的旧哑节点，而tail至少是它后面的节点，所以生产者手里的节点永远不会被释放
+// push/pop都是固定步数完成，不存在重试循环，因此是wait-free的
+// head和tail各自占一条缓存行，避免两个线程互相写同一行造成伪共享
+
+use std::cell::UnsafeCell;
+use std::mem::MaybeUninit;
+use std::ptr;
+use std::sync::Arc;
+use std::thread;
+
+use crate::instrument::mutation;
+use crate::sync::atomic::{AtomicBool, AtomicPtr, Ordering};
+
+// recv在让出CPU之前最多自旋的次数
+const SPIN_LIMIT: u32 = 64;
+
+// 按缓存行对齐，保证被包装的值独占一行
+#[repr(align(64))]
+struct CachePadded<T>(T);
+
+struct Node<T> {
+    // 哑节点的elem未初始化
+    elem: MaybeUninit<T>,
+    next: AtomicPtr<Node<T>>,
+}
+
+impl<T> Node<T> {
+    fn alloc(elem: MaybeUninit<T>) -> *mut Self {
+        Box::into_raw(Box::new(Node {
+            elem,
+            next: AtomicPtr::new(ptr::null_mut()),
+        }))
+    }
+}
+
+struct Inner<T> {
+    // 只有Receiver访问
+    head: CachePadded<UnsafeCell<*mut Node<T>>>,
+    // 只有Sender访问
+    tail: CachePadded<UnsafeCell<*mut Node<T>>>,
+    sender_alive: AtomicBool,
+    receiver_alive: AtomicBool,
+}
+
+impl<T> Drop for Inner<T> {
+    fn drop(&mut self) {
+        // 两端都已经不在了，先释放哑节点再释放带元素的节点
+        unsafe {
+            let dummy = Box::from_raw(*self.head.0.get_mut());
+            let mut cur = dummy.next.load(Ordering::Relaxed);
+            while !cur.is_null() {
+                let mut node = Box::from_raw(cur);
+                node.elem.assume_init_drop();
+                cur = node.next.load(Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+// 创建一对收发端，Sender和Receiver都只能各有一个，可以分别移动到不同线程
+pub fn channel<T>() -> (Sender<T>, Receiver<T>) {
+    let dummy = Node::alloc(MaybeUninit::uninit());
+    let inner = Arc::new(Inner {
+        head: CachePadded(UnsafeCell::new(dummy)),
+        tail: CachePadded(UnsafeCell::new(dummy)),
+        sender_alive: AtomicBool::new(true),
+        receiver_alive: AtomicBool::new(true),
+    });
+    (
+        Sender {
+            inner: Arc::clone(&inner),
+        },
+        Receiver { inner },
+    )
+}
+
+pub struct Sender<T> {
+    inner: Arc<Inner<T>>,
+}
+
+impl<T> Sender<T> {
+    // 接收端已经被drop时把元素原样还回来
+    pub fn send(&self, elem: T) -> Result<(), T> {
+        if !self.inner.receiver_alive.load(Ordering::Relaxed) {
+            return Err(elem);
+        }
+        let new = Node::alloc(MaybeUninit::new(elem));
+        // SAFETY: Sender不能Clone也不是Sync，tail只有当前线程访问；tail节点不会被消费者释放
+        unsafe {
+            let tail = self.inner.tail.0.get();
+            (**tail).next.store(new, Ordering::Release);
+            *tail = new;
+        }
+        mutation!("push");
+        Ok(())
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        // Release保证之前send的元素对看到断开的接收端可见
+        self.inner.sender_alive.store(false, Ordering::Release);
+    }
+}
+
+pub struct Receiver<T> {
+    inner: Arc<Inner<T>>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TryRecvError {
+    // 暂时没有元素
+    Empty,
+    // 发送端已经drop，并且剩下的元素都已经取完
+    Disconnected,
+}
+
+impl<T> Receiver<T> {
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        if let Some(elem) = self.pop() {
+            return Ok(elem);
+        }
+        if self.inner.sender_alive.load(Ordering::Acquire) {
+            return Err(TryRecvError::Empty);
+        }
+        // 发送端在上一次pop之后才断开，它断开前发出的元素需要再取一次
+        self.pop().ok_or(TryRecvError::Disconnected)
+    }
+
+    // 等待下一个元素，返回None说明发送端已经断开且队列已空
+    // 先短暂自旋，仍然没有数据就让出CPU，避免和生产者抢同一个核
+    pub fn recv(&self) -> Option<T> {
+        let mut spins = 0;
+        loop {
+            match self.try_recv() {
+                Ok(elem) => return Some(elem),
+                Err(TryRecvError::Disconnected) => return None,
+                Err(TryRecvError::Empty) if spins < SPIN_LIMIT => {
+                    spins += 1;
+                    std::hint::spin_loop();
+                }
+                Err(TryRecvError::Empty) => thread::yield_now(),
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        unsafe { (**self.inner.head.0.get()).next.load(Ordering::Acquire).is_null() }
+    }
+
+    // 一直接收直到发送端断开
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter { rx: self }
+    }
+
+    fn pop(&self) -> Option<T> {
+        // SAFETY: Receiver不能Clone也不是Sync，head只有当前线程访问
+        unsafe {
+            let head = self.inner.head.0.get();
+            let next = (**head).next.load(Ordering::Acquire);
+            if next.is_null() {
+                return None;
+            }
+            // next成为新的哑节点，旧哑节点已经不可能被生产者访问(tail至少是next)
+            let elem = (*next).elem.assume_init_read();
+            drop(Box::from_raw(*head));
+            *head = next;
+            mutation!("pop");
+            Some(elem)
+        }
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        self.inner.receiver_alive.store(false, Ordering::Relaxed);
+    }
+}
+
+pub struct Iter<'a, T> {
+    rx: &'a Receiver<T>,
+}
+
+impl<T> Iterator for Iter<'_, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.rx.recv()
+    }
+}
+
+// 收发端各自独占自己那一侧的指针，可以移动到别的线程，但不能被多个线程共享
+unsafe impl<T: Send> Send for Sender<T> {}
+unsafe impl<T: Send> Send for Receiver<T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn basics() {
+        let (tx, rx) = channel();
+        assert!(rx.is_empty());
+        assert_eq!(rx.try_recv(), Err(TryRecvError::Empty));
+        tx.send(1).unwrap();
+        tx.send(2).unwrap();
+        assert!(!rx.is_empty());
+        assert_eq!(rx.try_recv(), Ok(1));
+        tx.send(3).unwrap();
+        assert_eq!(rx.try_recv(), Ok(2));
+        assert_eq!(rx.try_recv(), Ok(3));
+        assert_eq!(rx.try_recv(), Err(TryRecvError::Empty));
+    }
+
+    #[test]
+    fn disconnect() {
+        let (tx, rx) = channel();
+        tx.send(1).unwrap();
+        drop(tx);
+        // 断开前发出的元素仍然可以收到
+        assert_eq!(rx.try_recv(), Ok(1));
+        assert_eq!(rx.try_recv(), Err(TryRecvError::Disconnected));
+        assert_eq!(rx.recv(), None);
+
+        let (tx, rx) = channel();
+        drop(rx);
+        assert_eq!(tx.send(5), Err(5));
+    }
+
+    #[test]
+    fn cross_thread_fifo() {
+        const N: usize = 100_000;
+        let (tx, rx) = channel();
+        let producer = thread::spawn(move || {
+            for i in 0..N {
+                tx.send(i).unwrap();
+            }
+        });
+        let received: Vec<usize> = rx.iter().collect();
+        producer.join().unwrap();
+        assert_eq!(received, (0..N).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn drop_remaining_elements() {
+        let marker = Arc::new(());
+        let (tx, rx) = channel();
+        for _ in 0..5 {
+            tx.send(Arc::clone(&marker)).unwrap();
+        }
+        drop(rx.try_recv());
+        assert_eq!(Arc::strong_count(&marker), 5);
+        drop(tx);
+        drop(rx);
+        assert_eq!(Arc::strong_count(&marker), 1);
+    }
+
+    #[test]
+    fn head_and_tail_on_separate_cache_lines() {
+        assert_eq!(std::mem::align_of::<CachePadded<u8>>(), 64);
+        let (tx, _rx) = channel::<u8>();
+        let head = &tx.inner.head as *const _ as usize;
+        let tail = &tx.inner.tail as *const _ as usize;
+        assert!(head.abs_diff(tail) >= 64);
+    }
+}
@@ -0,0 +1,533 @@
+// 固定容量、不需要分配器的双向链表
+// 所有节点都放在结构体内部的数组里，prev/next存的是数组下标而不是指针；
+// 空闲的槽位通过next串成一条空闲链表，push时从空闲链表取，pop时还回去，都是O(1)
+// 只用到core，不依赖alloc，可以直接放进嵌入式环境的static里(new是const fn)
+// 容量满时push/insert返回Err并把元素还给调用者，而不是panic或者扩容
+
+use core::fmt;
+use core::iter::FusedIterator;
+use core::marker::PhantomData;
+use core::mem::MaybeUninit;
+
+use crate::error::{check_insert, ListError};
+
+// 表示"没有节点"的下标
+const NIL: usize = usize::MAX;
+
+struct Slot<T> {
+    // 只有在链表中的槽位value才是初始化的
+    value: MaybeUninit<T>,
+    prev: usize,
+    next: usize,
+}
+
+pub struct StaticList<T, const N: usize> {
+    slots: [Slot<T>; N],
+    head: usize,
+    tail: usize,
+    // 空闲链表的表头，只使用next
+    free: usize,
+    len: usize,
+}
+
+impl<T, const N: usize> StaticList<T, N> {
+    pub const fn new() -> Self {
+        let mut slots = [const {
+            Slot {
+                value: MaybeUninit::uninit(),
+                prev: NIL,
+                next: NIL,
+            }
+        }; N];
+        // 初始时所有槽位按顺序串成空闲链表
+        let mut i = 0;
+        while i + 1 < N {
+            slots[i].next = i + 1;
+            i += 1;
+        }
+        StaticList {
+            slots,
+            head: NIL,
+            tail: NIL,
+            free: if N == 0 { NIL } else { 0 },
+            len: 0,
+        }
+    }
+
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len == N
+    }
+
+    // 满了返回Err(elem)
+    pub fn push_front(&mut self, elem: T) -> Result<(), T> {
+        let head = self.head;
+        self.link_between(NIL, head, elem)
+    }
+
+    pub fn push_back(&mut self, elem: T) -> Result<(), T> {
+        let tail = self.tail;
+        self.link_between(tail, NIL, elem)
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        (self.head != NIL).then(|| self.unlink(self.head))
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        (self.tail != NIL).then(|| self.unlink(self.tail))
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        self.value(self.head)
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        self.value(self.tail)
+    }
+
+    pub fn front_mut(&mut self) -> Option<&mut T> {
+        self.value_mut(self.head)
+    }
+
+    pub fn back_mut(&mut self) -> Option<&mut T> {
+        self.value_mut(self.tail)
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.value(self.slot_of(index))
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        self.value_mut(self.slot_of(index))
+    }
+
+    // 插到第index个位置(index == len时插到末尾)
+    // 满了返回Err(elem)，index超出范围会panic
+    pub fn insert(&mut self, index: usize, elem: T) -> Result<(), T> {
+        assert!(index <= self.len, "insertion index {index} out of bounds (len {})", self.len);
+        if index == self.len {
+            return self.push_back(elem);
+        }
+        let next = self.slot_of(index);
+        let prev = self.slots[next].prev;
+        self.link_between(prev, next, elem)
+    }
+
+    // 越界返回OutOfRange，满了返回CapacityExceeded；两种情况elem都被丢弃，
+    // 需要拿回元素时用insert
+    pub fn try_insert(&mut self, index: usize, elem: T) -> Result<(), ListError> {
+        check_insert(index, self.len)?;
+        self.insert(index, elem)
+            .map_err(|_| ListError::CapacityExceeded { capacity: N })
+    }
+
+    // 删除并返回第index个元素，超出范围时返回None
+    pub fn remove(&mut self, index: usize) -> Option<T> {
+        let slot = self.slot_of(index);
+        (slot != NIL).then(|| self.unlink(slot))
+    }
+
+    pub fn clear(&mut self) {
+        while self.pop_front().is_some() {}
+    }
+
+    pub fn iter(&self) -> Iter<'_, T, N> {
+        Iter {
+            list: self,
+            front: self.head,
+            back: self.tail,
+            remaining: self.len,
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T, N> {
+        IterMut {
+            front: self.head,
+            back: self.tail,
+            remaining: self.len,
+            slots: self.slots.as_mut_ptr(),
+            _boo: PhantomData,
+        }
+    }
+
+    fn value(&self, slot: usize) -> Option<&T> {
+        (slot != NIL).then(|| unsafe { self.slots[slot].value.assume_init_ref() })
+    }
+
+    fn value_mut(&mut self, slot: usize) -> Option<&mut T> {
+        (slot != NIL).then(|| unsafe { self.slots[slot].value.assume_init_mut() })
+    }
+
+    // 第index个元素所在的槽位，从离得近的一端走过去；超出范围返回NIL
+    fn slot_of(&self, index: usize) -> usize {
+        if index >= self.len {
+            return NIL;
+        }
+        if index < self.len / 2 {
+            let mut slot = self.head;
+            for _ in 0..index {
+                slot = self.slots[slot].next;
+            }
+            slot
+        } else {
+            let mut slot = self.tail;
+            for _ in 0..self.len - 1 - index {
+                slot = self.slots[slot].prev;
+            }
+            slot
+        }
+    }
+
+    // 从空闲链表取一个槽位放入elem，挂在prev和next之间(NIL表示链表端点)
+    fn link_between(&mut self, prev: usize, next: usize, elem: T) -> Result<(), T> {
+        let slot = self.free;
+        if slot == NIL {
+            return Err(elem);
+        }
+        self.free = self.slots[slot].next;
+
+        let s = &mut self.slots[slot];
+        s.value.write(elem);
+        s.prev = prev;
+        s.next = next;
+        match prev {
+            NIL => self.head = slot,
+            p => self.slots[p].next = slot,
+        }
+        match next {
+            NIL => self.tail = slot,
+            n => self.slots[n].prev = slot,
+        }
+        self.len += 1;
+        Ok(())
+    }
+
+    // 摘下一个在链表中的槽位，取出元素并把槽位还给空闲链表
+    fn unlink(&mut self, slot: usize) -> T {
+        let (prev, next) = (self.slots[slot].prev, self.slots[slot].next);
+        match prev {
+            NIL => self.head = next,
+            p => self.slots[p].next = next,
+        }
+        match next {
+            NIL => self.tail = prev,
+            n => self.slots[n].prev = prev,
+        }
+        self.len -= 1;
+
+        let s = &mut self.slots[slot];
+        s.prev = NIL;
+        s.next = self.free;
+        self.free = slot;
+        unsafe { s.value.assume_init_read() }
+    }
+}
+
+impl<T, const N: usize> Drop for StaticList<T, N> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+impl<T, const N: usize> Default for StaticList<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: fmt::Debug, const N: usize> fmt::Debug for StaticList<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+// 克隆后元素按逻辑顺序紧凑地放在前len个槽位里，不保留原来的槽位分布
+impl<T: Clone, const N: usize> Clone for StaticList<T, N> {
+    fn clone(&self) -> Self {
+        let mut list = Self::new();
+        for elem in self.iter() {
+            // 容量相同，不可能放不下
+            let _ = list.push_back(elem.clone());
+        }
+        list
+    }
+}
+
+impl<T: PartialEq, const N: usize> PartialEq for StaticList<T, N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Eq, const N: usize> Eq for StaticList<T, N> {}
+
+pub struct Iter<'a, T, const N: usize> {
+    list: &'a StaticList<T, N>,
+    front: usize,
+    back: usize,
+    remaining: usize,
+}
+
+impl<'a, T, const N: usize> Iterator for Iter<'a, T, N> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let slot = self.front;
+        self.front = self.list.slots[slot].next;
+        self.list.value(slot)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T, const N: usize> DoubleEndedIterator for Iter<'_, T, N> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let slot = self.back;
+        self.back = self.list.slots[slot].prev;
+        self.list.value(slot)
+    }
+}
+
+impl<T, const N: usize> ExactSizeIterator for Iter<'_, T, N> {}
+impl<T, const N: usize> FusedIterator for Iter<'_, T, N> {}
+
+impl<T, const N: usize> Clone for Iter<'_, T, N> {
+    fn clone(&self) -> Self {
+        Iter { ..*self }
+    }
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a StaticList<T, N> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T, N>;
+
+    fn into_iter(self) -> Iter<'a, T, N> {
+        self.iter()
+    }
+}
+
+// 可变迭代要同时交出多个槽位里的&mut T，只能通过裸指针逐个访问
+pub struct IterMut<'a, T, const N: usize> {
+    slots: *mut Slot<T>,
+    front: usize,
+    back: usize,
+    remaining: usize,
+    _boo: PhantomData<&'a mut StaticList<T, N>>,
+}
+
+impl<'a, T, const N: usize> Iterator for IterMut<'a, T, N> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<&'a mut T> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        unsafe {
+            let slot = self.slots.add(self.front);
+            self.front = (*slot).next;
+            Some((*slot).value.assume_init_mut())
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T, const N: usize> DoubleEndedIterator for IterMut<'_, T, N> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        unsafe {
+            let slot = self.slots.add(self.back);
+            self.back = (*slot).prev;
+            Some((*slot).value.assume_init_mut())
+        }
+    }
+}
+
+impl<T, const N: usize> ExactSizeIterator for IterMut<'_, T, N> {}
+impl<T, const N: usize> FusedIterator for IterMut<'_, T, N> {}
+
+impl<'a, T, const N: usize> IntoIterator for &'a mut StaticList<T, N> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T, N>;
+
+    fn into_iter(self) -> IterMut<'a, T, N> {
+        self.iter_mut()
+    }
+}
+
+pub struct IntoIter<T, const N: usize>(StaticList<T, N>);
+
+impl<T, const N: usize> Iterator for IntoIter<T, N> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.0.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.0.len, Some(self.0.len))
+    }
+}
+
+impl<T, const N: usize> DoubleEndedIterator for IntoIter<T, N> {
+    fn next_back(&mut self) -> Option<T> {
+        self.0.pop_back()
+    }
+}
+
+impl<T, const N: usize> ExactSizeIterator for IntoIter<T, N> {}
+
+impl<T, const N: usize> IntoIterator for StaticList<T, N> {
+    type Item = T;
+    type IntoIter = IntoIter<T, N>;
+
+    fn into_iter(self) -> IntoIter<T, N> {
+        IntoIter(self)
+    }
+}
+
+unsafe impl<T: Send, const N: usize> Send for IterMut<'_, T, N> {}
+unsafe impl<T: Sync, const N: usize> Sync for IterMut<'_, T, N> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+    use std::sync::Mutex;
+
+    #[test]
+    fn push_pop_and_overflow() {
+        let mut list = StaticList::<i32, 3>::new();
+        assert_eq!(list.capacity(), 3);
+        assert_eq!(list.push_back(2), Ok(()));
+        assert_eq!(list.push_front(1), Ok(()));
+        assert_eq!(list.push_back(3), Ok(()));
+        assert!(list.is_full());
+        assert_eq!(list.push_back(4), Err(4));
+        assert_eq!(list.push_front(0), Err(0));
+        assert_eq!(list.insert(1, 9), Err(9));
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![1, 2, 3]);
+
+        assert_eq!(list.pop_front(), Some(1));
+        assert_eq!(list.pop_back(), Some(3));
+        // 释放的槽位可以再次使用
+        assert_eq!(list.push_back(5), Ok(()));
+        assert_eq!(list.push_back(6), Ok(()));
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![2, 5, 6]);
+        assert_eq!(list.front(), Some(&2));
+        assert_eq!(list.back(), Some(&6));
+    }
+
+    #[test]
+    fn insert_and_remove_by_index() {
+        let mut list = StaticList::<char, 8>::new();
+        for c in ['a', 'c', 'e'] {
+            list.push_back(c).unwrap();
+        }
+        list.insert(1, 'b').unwrap();
+        list.insert(3, 'd').unwrap();
+        list.insert(5, 'f').unwrap();
+        list.insert(0, '_').unwrap();
+        assert_eq!(list.iter().collect::<String>(), "_abcdef");
+        assert_eq!(list.remove(0), Some('_'));
+        assert_eq!(list.remove(5), Some('f'));
+        assert_eq!(list.remove(2), Some('c'));
+        assert_eq!(list.remove(9), None);
+        assert_eq!(list.get(2), Some(&'d'));
+        *list.get_mut(0).unwrap() = 'A';
+        assert_eq!(list.iter().rev().collect::<String>(), "edbA");
+    }
+
+    #[test]
+    fn iterators() {
+        let mut list = StaticList::<i32, 6>::new();
+        for i in 0..6 {
+            list.push_back(i).unwrap();
+        }
+        let refs: Vec<&mut i32> = list.iter_mut().rev().collect();
+        for r in refs {
+            *r *= 2;
+        }
+        let mut it = list.iter();
+        assert_eq!(it.next(), Some(&0));
+        assert_eq!(it.next_back(), Some(&10));
+        assert_eq!(it.len(), 4);
+        assert_eq!(list.clone(), list);
+        assert_eq!(list.into_iter().rev().collect::<Vec<_>>(), vec![10, 8, 6, 4, 2, 0]);
+    }
+
+    // const fn new可以直接初始化static，不需要任何运行时分配
+    #[test]
+    fn usable_in_static() {
+        static LOG: Mutex<StaticList<u32, 4>> = Mutex::new(StaticList::new());
+        let mut log = LOG.lock().unwrap();
+        for i in 0..5 {
+            if log.push_back(i).is_err() {
+                log.pop_front();
+                log.push_back(i).unwrap();
+            }
+        }
+        assert_eq!(log.iter().copied().collect::<Vec<_>>(), vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn zero_capacity() {
+        let mut list = StaticList::<u8, 0>::new();
+        assert_eq!(list.push_back(1), Err(1));
+        assert!(list.is_empty() && list.is_full());
+        assert_eq!(list.pop_front(), None);
+    }
+
+    #[test]
+    fn drops_remaining_elements() {
+        let marker = Rc::new(());
+        let mut list = StaticList::<Rc<()>, 5>::new();
+        for _ in 0..5 {
+            list.push_back(Rc::clone(&marker)).unwrap();
+        }
+        drop(list.remove(2));
+        assert!(list.push_back(Rc::clone(&marker)).is_ok());
+        assert!(list.push_back(Rc::clone(&marker)).is_err());
+        assert_eq!(Rc::strong_count(&marker), 6);
+        drop(list);
+        assert_eq!(Rc::strong_count(&marker), 1);
+    }
+
+    #[test]
+    fn try_insert_errors() {
+        let mut list = StaticList::<i32, 2>::new();
+        assert_eq!(list.try_insert(1, 1), Err(ListError::OutOfRange { index: 1, len: 0 }));
+        list.try_insert(0, 2).unwrap();
+        list.try_insert(0, 1).unwrap();
+        assert_eq!(list.try_insert(1, 3), Err(ListError::CapacityExceeded { capacity: 2 }));
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![1, 2]);
+    }
+}
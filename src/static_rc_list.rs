@@ -0,0 +1,419 @@
+// 用"编译期拆分的引用计数"(static-rc)实现的双向链表
+// 双向链表里每个节点都被两个地方指着: 前驱的next(或表头head)和后继的prev(或表尾tail)
+// - simple_deque_1用Rc<RefCell>: 引用计数和借用标志都在运行时检查
+// - simple_deque_2/3用裸指针: 没有任何检查，全靠unsafe代码自己小心
+// - 这里走中间路线: 节点的所有权在类型里被拆成两半，StaticRc<T, 1, 2>各持有二分之一，
+//   两个半份合起来(join)才得到完整的StaticRc<T, 2, 2>，才能取出值、释放节点
+//   份额记在类型参数里，没有运行时计数器；"谁持有这个节点"由类型系统保证恰好两处
+// 半份只能共享地访问节点，修改节点的权限和ghost_list一样来自GhostToken
+// 链表的公开API里没有unsafe；unsafe只在StaticRc这个原语和不需要token的clear里
+
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::Deref;
+use std::ptr::NonNull;
+
+use crate::ghost_list::{GhostCell, GhostToken};
+
+// 拥有一个堆分配的NUM/DEN份额
+pub struct StaticRc<T, const NUM: usize, const DEN: usize> {
+    ptr: NonNull<T>,
+    _boo: PhantomData<T>,
+}
+
+pub type Full<T> = StaticRc<T, 2, 2>;
+pub type Half<T> = StaticRc<T, 1, 2>;
+
+impl<T> StaticRc<T, 2, 2> {
+    pub fn new(value: T) -> Self {
+        StaticRc {
+            ptr: NonNull::from(Box::leak(Box::new(value))),
+            _boo: PhantomData,
+        }
+    }
+
+    pub fn split(self) -> (Half<T>, Half<T>) {
+        let ptr = self.ptr;
+        std::mem::forget(self);
+        (
+            StaticRc {
+                ptr,
+                _boo: PhantomData,
+            },
+            StaticRc {
+                ptr,
+                _boo: PhantomData,
+            },
+        )
+    }
+
+    pub fn into_inner(self) -> T {
+        let ptr = self.ptr;
+        std::mem::forget(self);
+        // SAFETY: 完整份额独占这块由Box分配的内存
+        *unsafe { Box::from_raw(ptr.as_ptr()) }
+    }
+}
+
+impl<T> StaticRc<T, 1, 2> {
+    // 两个半份必须来自同一次分配，否则panic
+    pub fn join(self, other: Half<T>) -> Full<T> {
+        assert!(Self::ptr_eq(&self, &other), "halves of different allocations");
+        std::mem::forget(other);
+        let ptr = self.ptr;
+        std::mem::forget(self);
+        StaticRc {
+            ptr,
+            _boo: PhantomData,
+        }
+    }
+}
+
+impl<T, const NUM: usize, const DEN: usize> StaticRc<T, NUM, DEN> {
+    pub fn ptr_eq(a: &Self, b: &Self) -> bool {
+        a.ptr == b.ptr
+    }
+}
+
+// 任何份额都只能共享地读
+impl<T, const NUM: usize, const DEN: usize> Deref for StaticRc<T, NUM, DEN> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // SAFETY: 份额存在期间分配一直有效，可变访问只能通过完整份额或者GhostToken
+        unsafe { self.ptr.as_ref() }
+    }
+}
+
+impl<T, const NUM: usize, const DEN: usize> Drop for StaticRc<T, NUM, DEN> {
+    fn drop(&mut self) {
+        // 不完整的份额不知道另一半在哪，单独drop只能泄漏
+        if NUM == DEN {
+            // SAFETY: 完整份额独占这块内存
+            drop(unsafe { Box::from_raw(self.ptr.as_ptr()) });
+        }
+    }
+}
+
+impl<T: fmt::Debug, const NUM: usize, const DEN: usize> fmt::Debug for StaticRc<T, NUM, DEN> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "StaticRc<{}/{}>(", NUM, DEN)?;
+        (**self).fmt(f)?;
+        f.write_str(")")
+    }
+}
+
+type NodeHalf<'id, T> = Half<GhostCell<'id, Node<'id, T>>>;
+
+struct Node<'id, T> {
+    elem: T,
+    prev: Option<NodeHalf<'id, T>>,
+    next: Option<NodeHalf<'id, T>>,
+}
+
+// 每个节点的两个半份: 一个在前驱的next(或head)，一个在后继的prev(或tail)
+pub struct StaticRcList<'id, T> {
+    head: Option<NodeHalf<'id, T>>,
+    tail: Option<NodeHalf<'id, T>>,
+    len: usize,
+}
+
+impl<'id, T> StaticRcList<'id, T> {
+    pub fn new() -> Self {
+        StaticRcList {
+            head: None,
+            tail: None,
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn new_node(elem: T) -> (NodeHalf<'id, T>, NodeHalf<'id, T>) {
+        Full::new(GhostCell::new(Node {
+            elem,
+            prev: None,
+            next: None,
+        }))
+        .split()
+    }
+
+    pub fn push_front(&mut self, elem: T, token: &mut GhostToken<'id>) {
+        let (one, two) = Self::new_node(elem);
+        match self.head.take() {
+            Some(old) => {
+                old.borrow_mut(token).prev = Some(one);
+                two.borrow_mut(token).next = Some(old);
+            }
+            None => self.tail = Some(one),
+        }
+        self.head = Some(two);
+        self.len += 1;
+    }
+
+    pub fn push_back(&mut self, elem: T, token: &mut GhostToken<'id>) {
+        let (one, two) = Self::new_node(elem);
+        match self.tail.take() {
+            Some(old) => {
+                old.borrow_mut(token).next = Some(one);
+                two.borrow_mut(token).prev = Some(old);
+            }
+            None => self.head = Some(one),
+        }
+        self.tail = Some(two);
+        self.len += 1;
+    }
+
+    pub fn pop_front(&mut self, token: &mut GhostToken<'id>) -> Option<T> {
+        let old = self.head.take()?;
+        // 另一半在后继的prev里，没有后继时在tail里
+        let other = match old.borrow_mut(token).next.take() {
+            Some(next) => {
+                let other = next.borrow_mut(token).prev.take();
+                self.head = Some(next);
+                other
+            }
+            None => self.tail.take(),
+        };
+        self.len -= 1;
+        Some(Self::into_elem(old, other))
+    }
+
+    pub fn pop_back(&mut self, token: &mut GhostToken<'id>) -> Option<T> {
+        let old = self.tail.take()?;
+        let other = match old.borrow_mut(token).prev.take() {
+            Some(prev) => {
+                let other = prev.borrow_mut(token).next.take();
+                self.tail = Some(prev);
+                other
+            }
+            None => self.head.take(),
+        };
+        self.len -= 1;
+        Some(Self::into_elem(old, other))
+    }
+
+    // 两个半份合成完整份额之后才能取出元素
+    fn into_elem(half: NodeHalf<'id, T>, other: Option<NodeHalf<'id, T>>) -> T {
+        let other = other.expect("every linked node has two halves");
+        half.join(other).into_inner().into_inner().elem
+    }
+
+    pub fn front<'a>(&'a self, token: &'a GhostToken<'id>) -> Option<&'a T> {
+        self.head.as_ref().map(|node| &node.borrow(token).elem)
+    }
+
+    pub fn back<'a>(&'a self, token: &'a GhostToken<'id>) -> Option<&'a T> {
+        self.tail.as_ref().map(|node| &node.borrow(token).elem)
+    }
+
+    pub fn front_mut<'a>(&'a self, token: &'a mut GhostToken<'id>) -> Option<&'a mut T> {
+        self.head.as_ref().map(|node| &mut node.borrow_mut(token).elem)
+    }
+
+    pub fn back_mut<'a>(&'a self, token: &'a mut GhostToken<'id>) -> Option<&'a mut T> {
+        self.tail.as_ref().map(|node| &mut node.borrow_mut(token).elem)
+    }
+
+    pub fn iter<'a>(&'a self, token: &'a GhostToken<'id>) -> Iter<'a, 'id, T> {
+        Iter {
+            front: self.head.as_ref(),
+            back: self.tail.as_ref(),
+            len: self.len,
+            token,
+        }
+    }
+
+    // 和ghost_list一样，&mut token一次只能借出一个节点，可变遍历写成内部迭代
+    pub fn for_each_mut(&self, token: &mut GhostToken<'id>, mut f: impl FnMut(&mut T)) {
+        // 借出的&mut Node会连带借住token，取下一个节点时只能先转成裸指针
+        let mut cur: Option<*const GhostCell<'id, Node<'id, T>>> =
+            self.head.as_deref().map(|c| c as *const _);
+        while let Some(cell) = cur {
+            // SAFETY: 节点由&self借用的链表持有，遍历期间链表不会被修改
+            let node = unsafe { &*cell }.borrow_mut(token);
+            f(&mut node.elem);
+            cur = node.next.as_deref().map(|c| c as *const _);
+        }
+    }
+
+    // 不需要token: &mut self保证没有任何经由token借出的引用还活着
+    pub fn clear(&mut self) {
+        while let Some(old) = self.head.take() {
+            // SAFETY: 所有对节点的借用都要经过&self，这里独占链表
+            let node = unsafe { &mut *GhostCell::as_ptr(&old) };
+            let other = match node.next.take() {
+                Some(next) => {
+                    let other = unsafe { &mut *GhostCell::as_ptr(&next) }.prev.take();
+                    self.head = Some(next);
+                    other
+                }
+                None => self.tail.take(),
+            };
+            drop(Self::into_elem(old, other));
+        }
+        self.len = 0;
+    }
+}
+
+impl<T> Drop for StaticRcList<'_, T> {
+    fn drop(&mut self) {
+        // 半份单独drop会泄漏，必须逐个节点合并后释放，顺便避免递归drop爆栈
+        self.clear();
+    }
+}
+
+impl<T> Default for StaticRcList<'_, T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> fmt::Debug for StaticRcList<'_, T> {
+    // 没有token读不到元素，只打印长度
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StaticRcList").field("len", &self.len).finish()
+    }
+}
+
+pub struct Iter<'a, 'id, T> {
+    front: Option<&'a NodeHalf<'id, T>>,
+    back: Option<&'a NodeHalf<'id, T>>,
+    len: usize,
+    token: &'a GhostToken<'id>,
+}
+
+impl<'a, T> Iterator for Iter<'a, '_, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        if self.len == 0 {
+            return None;
+        }
+        self.front.map(|node| {
+            let node = node.borrow(self.token);
+            self.front = node.next.as_ref();
+            self.len -= 1;
+            &node.elem
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for Iter<'a, '_, T> {
+    fn next_back(&mut self) -> Option<&'a T> {
+        if self.len == 0 {
+            return None;
+        }
+        self.back.map(|node| {
+            let node = node.borrow(self.token);
+            self.back = node.prev.as_ref();
+            self.len -= 1;
+            &node.elem
+        })
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, '_, T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+
+    #[test]
+    fn static_rc_split_and_join() {
+        let full = Full::new(String::from("node"));
+        let (a, b) = full.split();
+        assert!(StaticRc::ptr_eq(&a, &b));
+        assert_eq!(a.len(), 4);
+        assert_eq!(format!("{:?}", b), "StaticRc<1/2>(\"node\")");
+        assert_eq!(a.join(b).into_inner(), "node");
+    }
+
+    #[test]
+    #[should_panic(expected = "halves of different allocations")]
+    fn join_mismatched_halves_panics() {
+        let (a, _) = Full::new(1).split();
+        let (_, b) = Full::new(2).split();
+        a.join(b);
+    }
+
+    #[test]
+    fn deque_operations() {
+        GhostToken::scope(|mut token| {
+            let mut list = StaticRcList::new();
+            assert_eq!(list.pop_front(&mut token), None);
+            list.push_back(2, &mut token);
+            list.push_back(3, &mut token);
+            list.push_front(1, &mut token);
+            assert_eq!(list.len(), 3);
+            assert_eq!(list.iter(&token).copied().collect::<Vec<_>>(), vec![1, 2, 3]);
+            assert_eq!(list.iter(&token).rev().copied().collect::<Vec<_>>(), vec![3, 2, 1]);
+            assert_eq!((list.front(&token), list.back(&token)), (Some(&1), Some(&3)));
+            assert_eq!(list.pop_back(&mut token), Some(3));
+            assert_eq!(list.pop_front(&mut token), Some(1));
+            assert_eq!(list.pop_back(&mut token), Some(2));
+            assert!(list.is_empty());
+            assert_eq!(list.pop_back(&mut token), None);
+            list.push_front(4, &mut token);
+            assert_eq!(list.pop_back(&mut token), Some(4));
+        });
+    }
+
+    #[test]
+    fn mutation_and_double_ended_iter() {
+        GhostToken::scope(|mut token| {
+            let mut list = StaticRcList::new();
+            for i in 0..6 {
+                list.push_back(i, &mut token);
+            }
+            *list.front_mut(&mut token).unwrap() = 10;
+            *list.back_mut(&mut token).unwrap() = 50;
+            list.for_each_mut(&mut token, |x| *x *= 2);
+            let mut iter = list.iter(&token);
+            assert_eq!(iter.next(), Some(&20));
+            assert_eq!(iter.next_back(), Some(&100));
+            assert_eq!(iter.len(), 4);
+            assert_eq!(iter.copied().collect::<Vec<_>>(), vec![2, 4, 6, 8]);
+        });
+    }
+
+    #[test]
+    fn elements_are_dropped() {
+        let elem = Rc::new(());
+        GhostToken::scope(|mut token| {
+            let mut list = StaticRcList::new();
+            for _ in 0..4 {
+                list.push_back(Rc::clone(&elem), &mut token);
+            }
+            drop(list.pop_front(&mut token));
+            assert_eq!(Rc::strong_count(&elem), 4);
+            list.clear();
+            assert_eq!(Rc::strong_count(&elem), 1);
+            list.push_front(Rc::clone(&elem), &mut token);
+            drop(list);
+            assert_eq!(Rc::strong_count(&elem), 1);
+        });
+    }
+
+    #[test]
+    fn long_list_drops_iteratively() {
+        GhostToken::scope(|mut token| {
+            let mut list = StaticRcList::new();
+            for i in 0..100_000 {
+                list.push_back(i, &mut token);
+            }
+            drop(list);
+        });
+    }
+}
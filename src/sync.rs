@@ -0,0 +1,9 @@
+// 无锁结构使用的原子类型的统一入口
+// 平时就是std::sync::atomic；用RUSTFLAGS="--cfg loom"编译时换成loom的模拟实现，
+// loom会接管这些原子操作，枚举所有可能的线程交错和内存序结果
+
+#[cfg(not(loom))]
+pub(crate) use std::sync::atomic;
+
+#[cfg(loom)]
+pub(crate) use loom::sync::atomic;
@@ -0,0 +1,282 @@
+// 基于simple_deque_3的线程安全阻塞双端队列
+// 一把Mutex保护整条链表，两个Condvar分别等待"非空"和"未满"
+// 可以直接当作线程之间的工作队列使用
+
+use std::sync::{Condvar, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+use crate::instrument::mutation;
+use crate::simple_deque_3::List;
+
+pub struct SyncDeque<T> {
+    inner: Mutex<List<T>>,
+    not_empty: Condvar,
+    not_full: Condvar,
+    // None表示不限容量
+    capacity: Option<usize>,
+}
+
+impl<T> SyncDeque<T> {
+    // 不限容量，push永远不会阻塞
+    pub fn new() -> Self {
+        SyncDeque {
+            inner: Mutex::new(List::new()),
+            not_empty: Condvar::new(),
+            not_full: Condvar::new(),
+            capacity: None,
+        }
+    }
+
+    // 最多容纳capacity个元素，满了之后push会阻塞
+    pub fn bounded(capacity: usize) -> Self {
+        assert!(capacity > 0, "capacity must be greater than 0");
+        SyncDeque {
+            capacity: Some(capacity),
+            ..Self::new()
+        }
+    }
+
+    pub fn capacity(&self) -> Option<usize> {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    // 队列满时阻塞直到有空位
+    pub fn push_back(&self, elem: T) {
+        let mut list = self.lock();
+        while self.is_full(&list) {
+            list = self.not_full.wait(list).unwrap_or_else(PoisonError::into_inner);
+        }
+        list.push_back(elem);
+        drop(list);
+        self.not_empty.notify_one();
+    }
+
+    // 插队到队头，同样受容量限制
+    pub fn push_front(&self, elem: T) {
+        let mut list = self.lock();
+        while self.is_full(&list) {
+            list = self.not_full.wait(list).unwrap_or_else(PoisonError::into_inner);
+        }
+        list.push_front(elem);
+        drop(list);
+        self.not_empty.notify_one();
+    }
+
+    // 不阻塞，队列已满时把元素原样还给调用者
+    pub fn try_push_back(&self, elem: T) -> Result<(), T> {
+        let mut list = self.lock();
+        if self.is_full(&list) {
+            return Err(elem);
+        }
+        list.push_back(elem);
+        drop(list);
+        self.not_empty.notify_one();
+        Ok(())
+    }
+
+    // 最多等待timeout，超时仍然没有空位则返回Err(elem)
+    pub fn push_back_timeout(&self, elem: T, timeout: Duration) -> Result<(), T> {
+        let deadline = Instant::now() + timeout;
+        let mut list = self.lock();
+        while self.is_full(&list) {
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(elem);
+            }
+            list = self
+                .not_full
+                .wait_timeout(list, deadline - now)
+                .unwrap_or_else(PoisonError::into_inner)
+                .0;
+        }
+        list.push_back(elem);
+        drop(list);
+        self.not_empty.notify_one();
+        Ok(())
+    }
+
+    // 队列为空时阻塞直到有元素
+    pub fn pop_front_wait(&self) -> T {
+        let mut list = self.lock();
+        loop {
+            if let Some(elem) = list.pop_front() {
+                drop(list);
+                self.not_full.notify_one();
+                return elem;
+            }
+            list = self.not_empty.wait(list).unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+
+    // 最多等待timeout，超时返回None
+    pub fn pop_front_timeout(&self, timeout: Duration) -> Option<T> {
+        let deadline = Instant::now() + timeout;
+        let mut list = self.lock();
+        loop {
+            if let Some(elem) = list.pop_front() {
+                drop(list);
+                self.not_full.notify_one();
+                return Some(elem);
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return None;
+            }
+            list = self
+                .not_empty
+                .wait_timeout(list, deadline - now)
+                .unwrap_or_else(PoisonError::into_inner)
+                .0;
+        }
+    }
+
+    pub fn try_pop_front(&self) -> Option<T> {
+        let elem = self.lock().pop_front();
+        if elem.is_some() {
+            self.not_full.notify_one();
+        }
+        elem
+    }
+
+    pub fn try_pop_back(&self) -> Option<T> {
+        let elem = self.lock().pop_back();
+        if elem.is_some() {
+            self.not_full.notify_one();
+        }
+        elem
+    }
+
+    // 取出当前所有元素，一次性唤醒所有等待空位的生产者
+    pub fn drain(&self) -> List<T> {
+        let list = std::mem::take(&mut *self.lock());
+        mutation!("drain", len = list.len());
+        self.not_full.notify_all();
+        list
+    }
+
+    pub fn into_inner(self) -> List<T> {
+        self.inner.into_inner().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    // 链表的每个操作要么完成要么没开始，持锁线程panic不会留下坏掉的结构，所以直接忽略中毒
+    fn lock(&self) -> MutexGuard<'_, List<T>> {
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn is_full(&self, list: &List<T>) -> bool {
+        self.capacity.is_some_and(|cap| list.len() >= cap)
+    }
+}
+
+impl<T> Default for SyncDeque<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> From<List<T>> for SyncDeque<T> {
+    fn from(list: List<T>) -> Self {
+        SyncDeque {
+            inner: Mutex::new(list),
+            ..Self::new()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn basics() {
+        let q = SyncDeque::new();
+        assert!(q.is_empty());
+        q.push_back(1);
+        q.push_back(2);
+        q.push_front(0);
+        assert_eq!(q.len(), 3);
+        assert_eq!(q.try_pop_front(), Some(0));
+        assert_eq!(q.try_pop_back(), Some(2));
+        assert_eq!(q.pop_front_wait(), 1);
+        assert_eq!(q.try_pop_front(), None);
+        assert_eq!(q.capacity(), None);
+    }
+
+    #[test]
+    fn timeouts() {
+        let q: SyncDeque<i32> = SyncDeque::bounded(1);
+        assert_eq!(q.pop_front_timeout(Duration::from_millis(10)), None);
+        assert_eq!(q.try_push_back(1), Ok(()));
+        assert_eq!(q.try_push_back(2), Err(2));
+        assert_eq!(q.push_back_timeout(3, Duration::from_millis(10)), Err(3));
+        assert_eq!(q.pop_front_timeout(Duration::from_millis(10)), Some(1));
+    }
+
+    #[test]
+    fn producer_consumer() {
+        let q = Arc::new(SyncDeque::bounded(4));
+        let producers: Vec<_> = (0..4)
+            .map(|p| {
+                let q = Arc::clone(&q);
+                thread::spawn(move || {
+                    for i in 0..250 {
+                        q.push_back(p * 1000 + i);
+                    }
+                })
+            })
+            .collect();
+
+        let consumer = {
+            let q = Arc::clone(&q);
+            thread::spawn(move || {
+                let mut got: Vec<i32> = (0..1000).map(|_| q.pop_front_wait()).collect();
+                got.sort();
+                got
+            })
+        };
+
+        for p in producers {
+            p.join().unwrap();
+        }
+        let got = consumer.join().unwrap();
+        let mut expected: Vec<i32> = (0..4).flat_map(|p| (0..250).map(move |i| p * 1000 + i)).collect();
+        expected.sort();
+        assert_eq!(got, expected);
+        assert!(q.is_empty());
+    }
+
+    #[test]
+    fn blocked_push_wakes_after_pop() {
+        let q = Arc::new(SyncDeque::bounded(1));
+        q.push_back(1);
+        let pusher = {
+            let q = Arc::clone(&q);
+            thread::spawn(move || q.push_back(2))
+        };
+        thread::sleep(Duration::from_millis(20));
+        assert_eq!(q.len(), 1);
+        assert_eq!(q.pop_front_wait(), 1);
+        pusher.join().unwrap();
+        assert_eq!(q.pop_front_timeout(Duration::from_secs(1)), Some(2));
+    }
+
+    #[test]
+    fn drain_and_into_inner() {
+        let q: SyncDeque<_> = (1..=3).collect::<List<_>>().into();
+        let drained = q.drain();
+        assert_eq!(drained.len(), 3);
+        assert!(q.is_empty());
+        q.push_back(9);
+        assert_eq!(q.into_inner().into_iter().collect::<Vec<_>>(), vec![9]);
+    }
+}
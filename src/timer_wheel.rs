@@ -0,0 +1,314 @@
+// 分层时间轮(hierarchical timing wheel)，每个桶是一条侵入式链表
+// 定时器对象由调用者持有，内嵌ListLink，挂进桶里不需要额外分配，取消时O(1)摘下(见intrusive_list)
+// 共LEVELS层，每层SLOTS个桶，第k层一个桶覆盖64^k个tick:
+// - 放置: 看到期时间和当前时间从哪一组6位开始不同，就放在哪一层；超出最高层范围的放进overflow桶
+// - 推进一个tick: 先把刚好轮到的高层桶整体拆下来重新放置(cascade)，它们会落到更低的层，
+//   最后把第0层当前桶里的定时器全部取出，它们就是这个tick到期的
+// schedule和cancel都是O(1)，每个定时器在到期前最多被cascade LEVELS次
+
+use std::cell::Cell;
+use std::fmt;
+use std::mem::offset_of;
+use std::pin::Pin;
+use std::ptr::{self, NonNull};
+
+use crate::intrusive_list::{IntrusiveList, Linked, ListLink};
+
+const SLOT_BITS: u32 = 6;
+const SLOTS: usize = 1 << SLOT_BITS;
+const LEVELS: usize = 4;
+// 所有层之外的那个桶
+const OVERFLOW: usize = LEVELS * SLOTS;
+
+// 挂在时间轮上的定时器，item是到期时要处理的数据
+pub struct Timer<T> {
+    link: ListLink,
+    deadline: Cell<u64>,
+    // 所在桶的下标，只在挂在时间轮上时有意义
+    bucket: Cell<usize>,
+    item: T,
+}
+
+impl<T> Timer<T> {
+    pub fn new(item: T) -> Self {
+        Timer {
+            link: ListLink::new(),
+            deadline: Cell::new(0),
+            bucket: Cell::new(0),
+            item,
+        }
+    }
+
+    pub fn item(&self) -> &T {
+        &self.item
+    }
+
+    pub fn is_scheduled(&self) -> bool {
+        self.link.is_linked()
+    }
+
+    // 已经安排时返回到期的tick
+    pub fn deadline(&self) -> Option<u64> {
+        self.is_scheduled().then(|| self.deadline.get())
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Timer<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Timer")
+            .field("deadline", &self.deadline())
+            .field("item", &self.item)
+            .finish()
+    }
+}
+
+unsafe impl<T> Linked for Timer<T> {
+    fn links(node: NonNull<Self>) -> NonNull<ListLink> {
+        unsafe { NonNull::new_unchecked(ptr::addr_of_mut!((*node.as_ptr()).link)) }
+    }
+
+    unsafe fn from_links(link: NonNull<ListLink>) -> NonNull<Self> {
+        unsafe {
+            let base = (link.as_ptr() as *mut u8).sub(offset_of!(Self, link));
+            NonNull::new_unchecked(base as *mut Self)
+        }
+    }
+}
+
+pub struct TimerWheel<'a, T> {
+    buckets: Vec<IntrusiveList<'a, Timer<T>>>,
+    now: u64,
+    len: usize,
+}
+
+impl<'a, T> TimerWheel<'a, T> {
+    pub fn new() -> Self {
+        TimerWheel {
+            buckets: (0..=OVERFLOW).map(|_| IntrusiveList::new()).collect(),
+            now: 0,
+            len: 0,
+        }
+    }
+
+    // 当前的tick
+    pub fn now(&self) -> u64 {
+        self.now
+    }
+
+    // 还没到期的定时器个数
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // 安排timer在after个tick之后到期，after为0时在下一个tick到期
+    // timer已经在这个时间轮上时改为新的到期时间；挂在别的时间轮上时panic
+    pub fn schedule(&mut self, after: u64, timer: Pin<&'a Timer<T>>) {
+        self.cancel(timer);
+        timer.deadline.set(self.now + after.max(1));
+        self.place(timer);
+        self.len += 1;
+    }
+
+    // 取消定时器，它不在这个时间轮上(没安排、已经到期或者属于别的时间轮)时返回false
+    pub fn cancel(&mut self, timer: Pin<&Timer<T>>) -> bool {
+        let removed = timer.is_scheduled() && self.buckets[timer.bucket.get()].remove(timer);
+        if removed {
+            self.len -= 1;
+        }
+        removed
+    }
+
+    // 推进ticks个tick，按到期时间顺序返回这期间到期的定时器
+    pub fn advance(&mut self, ticks: u64) -> Vec<Pin<&'a Timer<T>>> {
+        let mut expired = Vec::new();
+        for _ in 0..ticks {
+            self.tick(&mut expired);
+        }
+        expired
+    }
+
+    fn tick(&mut self, expired: &mut Vec<Pin<&'a Timer<T>>>) {
+        self.now += 1;
+        // 从高到低cascade，高层拆下来的定时器可能还要经过更低的层
+        for level in (1..=LEVELS).rev() {
+            let shift = SLOT_BITS * level as u32;
+            if self.now & ((1 << shift) - 1) != 0 {
+                continue;
+            }
+            let bucket = match level {
+                LEVELS => OVERFLOW,
+                _ => level * SLOTS + (self.now >> shift) as usize % SLOTS,
+            };
+            // 先整桶拆下来再放置，overflow里仍然超出范围的定时器会放回同一个桶
+            let mut moved = Vec::new();
+            while let Some(timer) = self.buckets[bucket].pop_front() {
+                moved.push(timer);
+            }
+            for timer in moved {
+                self.place(timer);
+            }
+        }
+        let bucket = self.now as usize % SLOTS;
+        while let Some(timer) = self.buckets[bucket].pop_front() {
+            self.len -= 1;
+            expired.push(timer);
+        }
+    }
+
+    // 按到期时间和当前时间第一组不同的6位选层
+    fn place(&mut self, timer: Pin<&'a Timer<T>>) {
+        let deadline = timer.deadline.get();
+        let diff = deadline ^ self.now;
+        let level = match diff {
+            0 => 0,
+            _ => ((u64::BITS - 1 - diff.leading_zeros()) / SLOT_BITS) as usize,
+        };
+        let bucket = match level {
+            LEVELS.. => OVERFLOW,
+            _ => level * SLOTS + (deadline >> (SLOT_BITS * level as u32)) as usize % SLOTS,
+        };
+        timer.bucket.set(bucket);
+        self.buckets[bucket].push_back(timer);
+    }
+}
+
+impl<T> Default for TimerWheel<'_, T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> fmt::Debug for TimerWheel<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TimerWheel")
+            .field("now", &self.now)
+            .field("len", &self.len)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::XorShift;
+
+    fn timers(n: u32) -> Vec<Pin<Box<Timer<u32>>>> {
+        (0..n).map(|i| Box::pin(Timer::new(i))).collect()
+    }
+
+    fn items(expired: &[Pin<&Timer<u32>>]) -> Vec<u32> {
+        expired.iter().map(|t| *t.item()).collect()
+    }
+
+    #[test]
+    fn expires_in_deadline_order() {
+        let ts = timers(4);
+        let mut wheel = TimerWheel::new();
+        wheel.schedule(5, ts[0].as_ref());
+        wheel.schedule(2, ts[1].as_ref());
+        wheel.schedule(5, ts[2].as_ref());
+        wheel.schedule(0, ts[3].as_ref());
+        assert_eq!(wheel.len(), 4);
+        assert_eq!(ts[0].deadline(), Some(5));
+        assert_eq!(items(&wheel.advance(1)), vec![3]);
+        assert!(!ts[3].is_scheduled());
+        assert_eq!(items(&wheel.advance(10)), vec![1, 0, 2]);
+        assert_eq!(wheel.now(), 11);
+        assert!(wheel.is_empty());
+    }
+
+    #[test]
+    fn cancel_and_reschedule() {
+        let ts = timers(3);
+        let mut wheel = TimerWheel::new();
+        for t in &ts {
+            wheel.schedule(10, t.as_ref());
+        }
+        assert!(wheel.cancel(ts[1].as_ref()));
+        assert!(!wheel.cancel(ts[1].as_ref()));
+        // 重新安排会替换原来的到期时间
+        wheel.schedule(3, ts[2].as_ref());
+        assert_eq!(wheel.len(), 2);
+        assert_eq!(items(&wheel.advance(3)), vec![2]);
+        assert_eq!(items(&wheel.advance(7)), vec![0]);
+        assert!(!wheel.cancel(ts[0].as_ref()));
+    }
+
+    #[test]
+    fn cascades_through_levels() {
+        let ts = timers(5);
+        let mut wheel = TimerWheel::new();
+        wheel.advance(37);
+        let afters = [63, 64, 4_000, 300_000, 20_000_000];
+        for (t, &after) in ts.iter().zip(&afters) {
+            wheel.schedule(after, t.as_ref());
+        }
+        assert_eq!(ts[4].bucket.get(), OVERFLOW);
+        let mut elapsed = 0;
+        for (i, &after) in afters[..4].iter().enumerate() {
+            let expired = wheel.advance(after - elapsed - 1);
+            assert!(expired.is_empty());
+            assert_eq!(items(&wheel.advance(1)), vec![i as u32]);
+            elapsed = after;
+        }
+        assert!(wheel.cancel(ts[4].as_ref()));
+        assert!(wheel.is_empty());
+    }
+
+    #[test]
+    fn wrong_wheel_is_not_cancelled() {
+        let ts = timers(1);
+        let mut a = TimerWheel::new();
+        let mut b = TimerWheel::new();
+        a.schedule(4, ts[0].as_ref());
+        assert!(!b.cancel(ts[0].as_ref()));
+        assert!(a.cancel(ts[0].as_ref()));
+        assert_eq!(format!("{a:?}"), "TimerWheel { now: 0, len: 0 }");
+    }
+
+    #[test]
+    fn randomized_against_model() {
+        let ts = timers(64);
+        let mut rng = XorShift::new(0x71e);
+        let mut wheel = TimerWheel::new();
+        // 模型: 每个定时器的到期tick
+        let mut model: Vec<Option<u64>> = vec![None; ts.len()];
+        for _ in 0..2000 {
+            let i = (rng.next_u64() as usize) % ts.len();
+            match rng.next_u64() % 4 {
+                0 | 1 => {
+                    let after = rng.next_u64() % 5000;
+                    wheel.schedule(after, ts[i].as_ref());
+                    model[i] = Some(wheel.now() + after.max(1));
+                }
+                2 => assert_eq!(wheel.cancel(ts[i].as_ref()), model[i].take().is_some()),
+                _ => {
+                    let ticks = rng.next_u64() % 300;
+                    let now = wheel.now() + ticks;
+                    let got: Vec<(u64, u32)> = wheel
+                        .advance(ticks)
+                        .iter()
+                        .map(|t| (t.deadline.get(), *t.item()))
+                        .collect();
+                    let mut expect: Vec<(u64, u32)> = Vec::new();
+                    for (id, slot) in model.iter_mut().enumerate() {
+                        if slot.is_some_and(|d| d <= now) {
+                            expect.push((slot.take().unwrap(), id as u32));
+                        }
+                    }
+                    // 按到期tick有序，同一tick内的顺序不作要求
+                    assert!(got.windows(2).all(|w| w[0].0 <= w[1].0));
+                    let mut got = got;
+                    got.sort();
+                    expect.sort();
+                    assert_eq!(got, expect);
+                }
+            }
+            assert_eq!(wheel.len(), model.iter().flatten().count());
+        }
+    }
+}
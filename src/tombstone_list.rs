@@ -0,0 +1,296 @@
+// 延迟删除的链表: remove只把节点标记成墓碑(元素被取走，节点留在原处)，之后由compact统一摘除
+// 这是并发结构(先逻辑删除再物理删除，见harris_list)和"遍历中删除不能打乱位置"场景里的常见做法:
+// - 删除不改链接，其他节点的原始位置(raw位置)在下一次compact之前保持不变
+// - compact一次遍历把所有墓碑摘下并释放，代价摊到多次删除上
+// - 可以设置自动压缩的比例: 墓碑数超过原始长度的这个比例时，删除之后自动compact；设为None则只能手动压缩
+// len是存活元素个数，raw_len包括墓碑
+// 底层是simple_deque_3::List<Option<T>>，None就是墓碑
+
+use std::fmt;
+
+use crate::simple_deque_3::List;
+
+// 默认墓碑超过一半时自动压缩
+const DEFAULT_RATIO: f64 = 0.5;
+
+pub struct TombstoneList<T> {
+    list: List<Option<T>>,
+    live: usize,
+    compact_ratio: Option<f64>,
+    compactions: usize,
+}
+
+impl<T> TombstoneList<T> {
+    pub fn new() -> Self {
+        Self::with_compact_ratio(Some(DEFAULT_RATIO))
+    }
+
+    // ratio必须在(0, 1]之间，None表示关闭自动压缩
+    pub fn with_compact_ratio(ratio: Option<f64>) -> Self {
+        assert!(ratio.is_none_or(|r| r > 0.0 && r <= 1.0), "compact ratio must be in (0, 1]");
+        TombstoneList {
+            list: List::new(),
+            live: 0,
+            compact_ratio: ratio,
+            compactions: 0,
+        }
+    }
+
+    // 存活元素个数
+    pub fn len(&self) -> usize {
+        self.live
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.live == 0
+    }
+
+    // 包括墓碑在内的节点数
+    pub fn raw_len(&self) -> usize {
+        self.list.len()
+    }
+
+    pub fn tombstones(&self) -> usize {
+        self.list.len() - self.live
+    }
+
+    // 已经做过几次压缩(手动和自动都算)
+    pub fn compactions(&self) -> usize {
+        self.compactions
+    }
+
+    pub fn push_back(&mut self, elem: T) {
+        self.list.push_back(Some(elem));
+        self.live += 1;
+    }
+
+    pub fn push_front(&mut self, elem: T) {
+        self.list.push_front(Some(elem));
+        self.live += 1;
+    }
+
+    // 表头的墓碑顺便直接摘掉
+    pub fn pop_front(&mut self) -> Option<T> {
+        while let Some(slot) = self.list.pop_front() {
+            if let Some(elem) = slot {
+                self.live -= 1;
+                return Some(elem);
+            }
+        }
+        None
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        self.iter().next()
+    }
+
+    // 第index个存活元素，O(raw_len)
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.iter().nth(index)
+    }
+
+    // 把第一个满足pred的存活元素标记为墓碑并返回它
+    pub fn remove_first_by<P: FnMut(&T) -> bool>(&mut self, mut pred: P) -> Option<T> {
+        let slot = self.list.iter_mut().find(|slot| slot.as_ref().is_some_and(&mut pred))?;
+        let elem = slot.take();
+        self.live -= 1;
+        self.maybe_compact();
+        elem
+    }
+
+    // 把所有满足pred的存活元素标记为墓碑，返回标记了几个；被删除的元素立即析构
+    pub fn remove_all_by<P: FnMut(&T) -> bool>(&mut self, mut pred: P) -> usize {
+        let mut removed = 0;
+        for slot in self.list.iter_mut() {
+            if slot.as_ref().is_some_and(&mut pred) {
+                *slot = None;
+                removed += 1;
+            }
+        }
+        self.live -= removed;
+        if removed > 0 {
+            self.maybe_compact();
+        }
+        removed
+    }
+
+    // 摘下所有墓碑，返回摘下的个数
+    pub fn compact(&mut self) -> usize {
+        let tombstones = self.tombstones();
+        if tombstones == 0 {
+            return 0;
+        }
+        let mut cursor = self.list.cursor_mut();
+        cursor.move_next();
+        while let Some(slot) = cursor.current() {
+            if slot.is_none() {
+                cursor.remove_current();
+            } else {
+                cursor.move_next();
+            }
+        }
+        self.compactions += 1;
+        tombstones
+    }
+
+    fn maybe_compact(&mut self) {
+        if let Some(ratio) = self.compact_ratio {
+            if self.tombstones() as f64 > ratio * self.list.len() as f64 {
+                self.compact();
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.list.clear();
+        self.live = 0;
+    }
+
+    // 只遍历存活元素
+    pub fn iter(&self) -> impl Iterator<Item = &T> + '_ {
+        self.list.iter().filter_map(Option::as_ref)
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> + '_ {
+        self.list.iter_mut().filter_map(Option::as_mut)
+    }
+
+    // 按原始位置遍历，墓碑是None
+    pub fn raw_iter(&self) -> impl Iterator<Item = Option<&T>> + '_ {
+        self.list.iter().map(Option::as_ref)
+    }
+}
+
+impl<T> Default for TombstoneList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Extend<T> for TombstoneList<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for elem in iter {
+            self.push_back(elem);
+        }
+    }
+}
+
+impl<T> FromIterator<T> for TombstoneList<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = TombstoneList::new();
+        list.extend(iter);
+        list
+    }
+}
+
+// 墓碑显示为"†"
+impl<T: fmt::Debug> fmt::Debug for TombstoneList<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        struct Tombstone;
+        impl fmt::Debug for Tombstone {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("†")
+            }
+        }
+        let mut list = f.debug_list();
+        for slot in self.raw_iter() {
+            match slot {
+                Some(elem) => list.entry(elem),
+                None => list.entry(&Tombstone),
+            };
+        }
+        list.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::XorShift;
+    use std::rc::Rc;
+
+    #[test]
+    fn remove_leaves_tombstones_until_compact() {
+        let mut list = TombstoneList::with_compact_ratio(None);
+        list.extend(1..=5);
+        assert_eq!(list.remove_first_by(|&x| x == 2), Some(2));
+        assert_eq!(list.remove_all_by(|&x| x % 2 == 1 && x > 1), 2);
+        assert_eq!(format!("{:?}", list), "[1, †, †, 4, †]");
+        assert_eq!((list.len(), list.raw_len(), list.tombstones()), (2, 5, 3));
+        assert_eq!(list.get(1), Some(&4));
+        assert_eq!(list.remove_first_by(|&x| x == 2), None);
+        assert_eq!(list.compact(), 3);
+        assert_eq!(list.compact(), 0);
+        assert_eq!(format!("{:?}", list), "[1, 4]");
+        assert_eq!(list.compactions(), 1);
+    }
+
+    #[test]
+    fn automatic_compaction_threshold() {
+        let mut list: TombstoneList<i32> = TombstoneList::with_compact_ratio(Some(0.25));
+        list.extend(0..8);
+        list.remove_first_by(|&x| x == 0);
+        list.remove_first_by(|&x| x == 1);
+        // 2/8没有超过1/4
+        assert_eq!((list.raw_len(), list.compactions()), (8, 0));
+        list.remove_first_by(|&x| x == 5);
+        assert_eq!((list.raw_len(), list.compactions()), (5, 1));
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![2, 3, 4, 6, 7]);
+    }
+
+    #[test]
+    #[should_panic(expected = "compact ratio must be in (0, 1]")]
+    fn invalid_ratio_panics() {
+        TombstoneList::<()>::with_compact_ratio(Some(1.5));
+    }
+
+    #[test]
+    fn pop_front_skips_tombstones_and_drops_eagerly() {
+        let marker = Rc::new(());
+        let mut list = TombstoneList::with_compact_ratio(None);
+        list.extend((0..4).map(|i| (i, Rc::clone(&marker))));
+        list.remove_all_by(|(i, _)| *i < 2);
+        // 元素在标记时就已经析构，不等compact
+        assert_eq!(Rc::strong_count(&marker), 3);
+        assert_eq!(list.pop_front().map(|(i, _)| i), Some(2));
+        assert_eq!(list.raw_len(), 1);
+        assert_eq!(list.front().map(|(i, _)| *i), Some(3));
+        for (i, _) in list.iter_mut() {
+            *i *= 10;
+        }
+        assert_eq!(list.pop_front().map(|(i, _)| i), Some(30));
+        assert_eq!(list.pop_front(), None);
+        assert_eq!(Rc::strong_count(&marker), 1);
+    }
+
+    #[test]
+    fn matches_vec_model() {
+        let mut rng = XorShift::new(703);
+        let mut list = TombstoneList::new();
+        let mut model = Vec::new();
+        for i in 0..3000u64 {
+            match rng.next_u64() % 4 {
+                0 | 1 => {
+                    list.push_back(i);
+                    model.push(i);
+                }
+                2 => {
+                    let key = rng.next_u64() % 8;
+                    let expected = model.iter().position(|x| x % 8 == key).map(|p| model.remove(p));
+                    assert_eq!(list.remove_first_by(|x| x % 8 == key), expected);
+                }
+                _ => {
+                    let key = rng.next_u64() % 16;
+                    let before = model.len();
+                    model.retain(|x| x % 16 != key);
+                    assert_eq!(list.remove_all_by(|x| x % 16 == key), before - model.len());
+                }
+            }
+            assert_eq!(list.len(), model.len());
+            // 默认比例下墓碑不会超过一半
+            assert!(list.tombstones() * 2 <= list.raw_len());
+        }
+        assert!(list.iter().eq(model.iter()));
+        assert!(list.compactions() > 0);
+    }
+}
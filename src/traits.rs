@@ -0,0 +1,314 @@
+// 栈、队列、双端队列的公共trait
+// crate里的链表各自有一套方法名(push/push_front/send……)，这里统一成三个trait，
+// 写一份泛型代码就能跑在所有实现上，测试和基准也可以按表格驱动
+// - Stack: 后进先出，push/pop
+// - Queue: 先进先出，enqueue/dequeue(不叫push/pop，同时实现两者的类型调用时不会混淆)
+// - Deque: 两端都能进出；实现了Deque的类型也都实现Stack(用front一端)和Queue(back进front出)
+// 方法都取&mut self: 无锁结构本来用&self就能操作，这里只是把它们当成普通容器使用
+// 有容量上限的(static_list)、push会返回Err的、按优先级出队的堆都不在这里
+// 标准库的Vec/VecDeque/LinkedList也实现了，作为测试模型和基准里的对照组
+
+use std::collections::{LinkedList, VecDeque};
+
+#[cfg(feature = "unsafe-impls")]
+use crate::arena_list::ArenaList;
+#[cfg(feature = "concurrent")]
+use crate::chase_lev_deque::Worker;
+#[cfg(feature = "persistent")]
+use crate::cow_list::CowList;
+#[cfg(feature = "concurrent")]
+use crate::elimination_stack::EliminationStack;
+#[cfg(feature = "concurrent")]
+use crate::flat_combining::FcQueue;
+#[cfg(feature = "unsafe-impls")]
+use crate::indexable_skip_list::IndexableSkipList;
+#[cfg(feature = "safe")]
+use crate::min_stack::{MinQueue, MinStack};
+#[cfg(feature = "concurrent")]
+use crate::ms_queue::MsQueue;
+#[cfg(feature = "unsafe-impls")]
+use crate::random_list::RandomList;
+#[cfg(feature = "concurrent")]
+use crate::rcu_list::RcuList;
+#[cfg(feature = "concurrent")]
+use crate::reclaim::Reclaim;
+#[cfg(feature = "unsafe-impls")]
+use crate::ring::Ring;
+#[cfg(feature = "concurrent")]
+use crate::seg_queue::SegQueue;
+#[cfg(feature = "concurrent")]
+use crate::shared_stack::SharedStack;
+#[cfg(feature = "unsafe-impls")]
+use crate::simple_deque_3::pinned::PinnedList;
+#[cfg(feature = "trace")]
+use crate::simple_deque_3::trace::TracedList;
+#[cfg(feature = "unsafe-impls")]
+use crate::simple_deque_3::NodeLayout;
+#[cfg(feature = "unsafe-impls")]
+use crate::slab_list::SlabList;
+#[cfg(feature = "unsafe-impls")]
+use crate::small_list::SmallList;
+#[cfg(feature = "concurrent")]
+use crate::sync_deque::SyncDeque;
+#[cfg(feature = "unsafe-impls")]
+use crate::tombstone_list::TombstoneList;
+#[cfg(feature = "concurrent")]
+use crate::treiber_stack::TreiberStack;
+#[cfg(feature = "unsafe-impls")]
+use crate::unrolled_list::UnrolledList;
+#[cfg(feature = "unsafe-impls")]
+use crate::xor_list::XorList;
+#[cfg(feature = "safe")]
+use crate::{simple_deque_1, simple_stack_1, simple_stack_2};
+#[cfg(feature = "unsafe-impls")]
+use crate::{sentinel_list, simple_deque_2, simple_deque_3};
+
+pub trait Stack<T> {
+    fn push(&mut self, elem: T);
+    fn pop(&mut self) -> Option<T>;
+}
+
+pub trait Queue<T> {
+    fn enqueue(&mut self, elem: T);
+    fn dequeue(&mut self) -> Option<T>;
+}
+
+pub trait Deque<T> {
+    fn push_front(&mut self, elem: T);
+    fn push_back(&mut self, elem: T);
+    fn pop_front(&mut self) -> Option<T>;
+    fn pop_back(&mut self) -> Option<T>;
+}
+
+// 下面的宏把trait方法转发到同名或对应的固有方法；<$ty>::method优先解析到固有方法
+// 写法: [泛型参数] 类型 => 入方法, 出方法;
+
+macro_rules! impl_stack {
+    ($($(#[$attr:meta])* [$($gen:tt)*] $ty:ty => $push:ident, $pop:ident;)*) => {$(
+        $(#[$attr])*
+        impl<$($gen)*> Stack<T> for $ty {
+            fn push(&mut self, elem: T) {
+                <$ty>::$push(self, elem);
+            }
+
+            fn pop(&mut self) -> Option<T> {
+                <$ty>::$pop(self)
+            }
+        }
+    )*};
+}
+
+macro_rules! impl_queue {
+    ($($(#[$attr:meta])* [$($gen:tt)*] $ty:ty => $push:ident, $pop:ident;)*) => {$(
+        $(#[$attr])*
+        impl<$($gen)*> Queue<T> for $ty {
+            fn enqueue(&mut self, elem: T) {
+                <$ty>::$push(self, elem);
+            }
+
+            fn dequeue(&mut self) -> Option<T> {
+                <$ty>::$pop(self)
+            }
+        }
+    )*};
+}
+
+// 双端队列同时得到Stack和Queue
+macro_rules! impl_deque {
+    ($($(#[$attr:meta])* [$($gen:tt)*] $ty:ty => $pop_front:ident, $pop_back:ident;)*) => {$(
+        $(#[$attr])*
+        impl<$($gen)*> Deque<T> for $ty {
+            fn push_front(&mut self, elem: T) {
+                <$ty>::push_front(self, elem);
+            }
+
+            fn push_back(&mut self, elem: T) {
+                <$ty>::push_back(self, elem);
+            }
+
+            fn pop_front(&mut self) -> Option<T> {
+                <$ty>::$pop_front(self)
+            }
+
+            fn pop_back(&mut self) -> Option<T> {
+                <$ty>::$pop_back(self)
+            }
+        }
+
+        impl_stack! { $(#[$attr])* [$($gen)*] $ty => push_front, $pop_front; }
+        impl_queue! { $(#[$attr])* [$($gen)*] $ty => push_back, $pop_front; }
+    )*};
+}
+
+impl_deque! {
+    #[cfg(feature = "safe")]
+    [T] simple_deque_1::List<T> => pop_front, pop_back;
+    #[cfg(feature = "unsafe-impls")]
+    [T, L: NodeLayout] simple_deque_3::List<T, L> => pop_front, pop_back;
+    #[cfg(feature = "unsafe-impls")]
+    [T: Unpin, L: NodeLayout] PinnedList<T, L> => pop_front, pop_back;
+    #[cfg(feature = "trace")]
+    [T: std::fmt::Debug, L: NodeLayout] TracedList<T, L> => pop_front, pop_back;
+    #[cfg(feature = "unsafe-impls")]
+    [T] sentinel_list::List<T> => pop_front, pop_back;
+    #[cfg(feature = "unsafe-impls")]
+    [T] XorList<T> => pop_front, pop_back;
+    #[cfg(feature = "unsafe-impls")]
+    [T] ArenaList<T> => pop_front, pop_back;
+    #[cfg(feature = "unsafe-impls")]
+    [T] SlabList<T> => pop_front, pop_back;
+    #[cfg(feature = "unsafe-impls")]
+    [T, const N: usize] SmallList<T, N> => pop_front, pop_back;
+    #[cfg(feature = "unsafe-impls")]
+    [T, const K: usize] UnrolledList<T, K> => pop_front, pop_back;
+    #[cfg(feature = "unsafe-impls")]
+    [T] IndexableSkipList<T> => pop_front, pop_back;
+    // 不等待的版本
+    #[cfg(feature = "concurrent")]
+    [T] SyncDeque<T> => try_pop_front, try_pop_back;
+    [T] VecDeque<T> => pop_front, pop_back;
+    [T] LinkedList<T> => pop_front, pop_back;
+}
+
+impl_stack! {
+    #[cfg(feature = "safe")]
+    [T] simple_stack_2::List<T> => push, pop;
+    #[cfg(feature = "safe")]
+    [T: Ord + Clone] MinStack<T> => push, pop;
+    #[cfg(feature = "unsafe-impls")]
+    [T] Ring<T> => push, pop;
+    #[cfg(feature = "unsafe-impls")]
+    [T] RandomList<T> => push_front, pop_front;
+    #[cfg(feature = "persistent")]
+    [T: Clone] CowList<T> => push_front, pop_front;
+    #[cfg(feature = "concurrent")]
+    [T: Clone + Send + Sync + 'static] RcuList<T> => push_front, pop_front;
+    #[cfg(feature = "concurrent")]
+    [T: Clone + Send + Sync + 'static] SharedStack<T> => push, pop;
+    #[cfg(feature = "concurrent")]
+    [T, R: Reclaim] TreiberStack<T, R> => push, pop;
+    #[cfg(feature = "concurrent")]
+    [T, R: Reclaim] EliminationStack<T, R> => push, pop;
+    // 所有者一端是后进先出
+    #[cfg(feature = "concurrent")]
+    [T] Worker<T> => push, pop;
+    #[cfg(feature = "unsafe-impls")]
+    [T] TombstoneList<T> => push_front, pop_front;
+    [T] Vec<T> => push, pop;
+}
+
+impl_queue! {
+    #[cfg(feature = "unsafe-impls")]
+    [T] simple_deque_2::List<T> => push_back, pop_front;
+    #[cfg(feature = "safe")]
+    [T: Ord + Clone] MinQueue<T> => push, pop;
+    #[cfg(feature = "concurrent")]
+    [T, R: Reclaim] MsQueue<T, R> => push, pop;
+    #[cfg(feature = "concurrent")]
+    [T, R: Reclaim] SegQueue<T, R> => push, pop;
+    #[cfg(feature = "concurrent")]
+    [T] FcQueue<T> => push, pop;
+    #[cfg(feature = "unsafe-impls")]
+    [T] TombstoneList<T> => push_back, pop_front;
+}
+
+// 用bounded限定了容量的simple_stack_1满了之后push会panic，不限容量时和其它栈一样
+#[cfg(feature = "safe")]
+impl<T> Stack<T> for simple_stack_1::List<T> {
+    fn push(&mut self, elem: T) {
+        if simple_stack_1::List::push(self, elem).is_err() {
+            panic!("push on a full simple_stack_1::List");
+        }
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        simple_stack_1::List::pop(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 同一段泛型代码跑在不同的实现上
+    fn drain_stack<S: Stack<i32>>(stack: &mut S) -> Vec<i32> {
+        for i in 0..5 {
+            stack.push(i);
+        }
+        std::iter::from_fn(|| stack.pop()).collect()
+    }
+
+    fn drain_queue<Q: Queue<i32>>(queue: &mut Q) -> Vec<i32> {
+        for i in 0..5 {
+            queue.enqueue(i);
+        }
+        std::iter::from_fn(|| queue.dequeue()).collect()
+    }
+
+    #[test]
+    fn generic_over_stacks() {
+        let lifo = vec![4, 3, 2, 1, 0];
+        assert_eq!(drain_stack(&mut Vec::new()), lifo);
+        #[cfg(feature = "safe")]
+        assert_eq!(drain_stack(&mut simple_stack_1::List::new()), lifo);
+        #[cfg(feature = "safe")]
+        assert_eq!(drain_stack(&mut simple_stack_2::List::new()), lifo);
+        #[cfg(feature = "concurrent")]
+        assert_eq!(drain_stack(&mut TreiberStack::new()), lifo);
+        #[cfg(feature = "unsafe-impls")]
+        assert_eq!(drain_stack(&mut Ring::new()), lifo);
+        #[cfg(feature = "unsafe-impls")]
+        assert_eq!(drain_stack(&mut simple_deque_3::List::new()), lifo);
+        #[cfg(feature = "unsafe-impls")]
+        assert_eq!(drain_stack(&mut UnrolledList::<i32, 2>::default()), lifo);
+    }
+
+    #[test]
+    #[cfg(feature = "safe")]
+    #[should_panic(expected = "push on a full simple_stack_1::List")]
+    fn bounded_simple_stack_panics_when_full() {
+        drain_stack(&mut simple_stack_1::List::bounded(4));
+    }
+
+    #[test]
+    fn generic_over_queues() {
+        let fifo = vec![0, 1, 2, 3, 4];
+        assert_eq!(drain_queue(&mut VecDeque::new()), fifo);
+        #[cfg(feature = "unsafe-impls")]
+        assert_eq!(drain_queue(&mut simple_deque_2::List::new()), fifo);
+        #[cfg(feature = "concurrent")]
+        assert_eq!(drain_queue(&mut MsQueue::new()), fifo);
+        #[cfg(feature = "concurrent")]
+        assert_eq!(drain_queue(&mut SegQueue::new()), fifo);
+        #[cfg(feature = "safe")]
+        assert_eq!(drain_queue(&mut MinQueue::new()), fifo);
+        #[cfg(feature = "unsafe-impls")]
+        assert_eq!(drain_queue(&mut XorList::new()), fifo);
+        #[cfg(feature = "concurrent")]
+        assert_eq!(drain_queue(&mut SyncDeque::new()), fifo);
+    }
+
+    #[cfg(all(feature = "safe", feature = "unsafe-impls"))]
+    #[test]
+    fn deque_through_trait_objects() {
+        // 表格驱动: 不同实现装进同一个Vec里
+        let mut deques: Vec<Box<dyn Deque<i32>>> = vec![
+            Box::new(simple_deque_1::List::new()),
+            Box::new(sentinel_list::List::new()),
+            Box::new(ArenaList::new()),
+            Box::new(SlabList::new()),
+            Box::new(SmallList::<i32, 2>::new()),
+            Box::new(IndexableSkipList::new()),
+            Box::new(PinnedList::new()),
+        ];
+        for deque in &mut deques {
+            deque.push_back(2);
+            deque.push_front(1);
+            deque.push_back(3);
+            assert_eq!(deque.pop_back(), Some(3));
+            assert_eq!(deque.pop_front(), Some(1));
+            assert_eq!(deque.pop_front(), Some(2));
+            assert_eq!(deque.pop_back(), None);
+        }
+    }
+}
@@ -0,0 +1,265 @@
+// Treiber无锁栈
+// 栈顶是一个AtomicPtr，push/pop都是"读栈顶 -> 准备新值 -> CAS替换"的重试循环
+// 并发结构最难的是内存回收: 一个线程pop成功后，别的线程可能还拿着旧栈顶指针在读它的next
+// pop时先通过回收策略R保护栈顶，摘下的节点交给R延迟释放(默认用epoch，也可以选危险指针):
+// 只要还有线程可能持有旧指针，节点就不会被释放，地址也不会被复用，从而同时避免了释放后使用和ABA
+// Drop时已经没有其他线程访问，剩余的节点可以直接释放
+
+use std::marker::PhantomData;
+use std::mem::ManuallyDrop;
+use std::ptr;
+
+use crate::instrument::mutation;
+use crate::reclaim::{Epoch, Reclaim, ReclaimGuard};
+use crate::sync::atomic::{AtomicPtr, Ordering};
+
+pub struct TreiberStack<T, R: Reclaim = Epoch> {
+    head: AtomicPtr<Node<T>>,
+    _boo: PhantomData<(T, R)>,
+}
+
+struct Node<T> {
+    // 元素会被pop的线程用ptr::read取走，节点本身不负责析构它
+    elem: ManuallyDrop<T>,
+    next: *mut Node<T>,
+}
+
+impl<T> TreiberStack<T> {
+    pub fn new() -> Self {
+        Self::empty()
+    }
+}
+
+impl<T, R: Reclaim> TreiberStack<T, R> {
+    // 用指定的回收策略创建，例如TreiberStack::with_reclaim(Hazard)
+    pub fn with_reclaim(_reclaim: R) -> Self {
+        Self::empty()
+    }
+
+    fn empty() -> Self {
+        TreiberStack {
+            head: AtomicPtr::new(ptr::null_mut()),
+            _boo: PhantomData,
+        }
+    }
+
+    pub fn push(&self, elem: T) {
+        let new = Box::into_raw(Box::new(Node {
+            elem: ManuallyDrop::new(elem),
+            next: ptr::null_mut(),
+        }));
+        let mut head = self.head.load(Ordering::Relaxed);
+        loop {
+            // SAFETY: new还没有发布出去，只有当前线程能访问
+            unsafe { (*new).next = head };
+            // Release保证其他线程通过Acquire读到new时，也能看到elem和next的写入
+            match self
+                .head
+                .compare_exchange_weak(head, new, Ordering::Release, Ordering::Relaxed)
+            {
+                Ok(_) => {
+                    mutation!("push");
+                    return;
+                }
+                Err(actual) => head = actual,
+            }
+        }
+    }
+
+    pub fn pop(&self) -> Option<T> {
+        let guard = R::pin();
+        loop {
+            let head = guard.protect(0, &self.head);
+            if head.is_null() {
+                return None;
+            }
+            // SAFETY: head受guard保护，即使被别人摘下也还没有被释放
+            let next = unsafe { (*head).next };
+            if self
+                .head
+                .compare_exchange_weak(head, next, Ordering::Acquire, Ordering::Relaxed)
+                .is_ok()
+            {
+                // CAS成功说明只有当前线程摘下了这个节点，可以独占地取走元素
+                // 节点外壳等没有线程再访问它之后再释放(elem是ManuallyDrop，释放时不会再析构元素)
+                mutation!("pop");
+                unsafe {
+                    let elem = ptr::read(&*(*head).elem);
+                    guard.retire(head);
+                    return Some(elem);
+                }
+            }
+        }
+    }
+
+    // 只尝试一次CAS，失败(有竞争)时把元素还回来；elimination_stack靠它发现竞争
+    pub(crate) fn try_push(&self, elem: T) -> Result<(), T> {
+        let head = self.head.load(Ordering::Relaxed);
+        let new = Box::into_raw(Box::new(Node {
+            elem: ManuallyDrop::new(elem),
+            next: head,
+        }));
+        match self.head.compare_exchange(head, new, Ordering::Release, Ordering::Relaxed) {
+            Ok(_) => Ok(()),
+            // SAFETY: CAS失败，new没有发布出去
+            Err(_) => unsafe { Err(ManuallyDrop::into_inner(Box::from_raw(new).elem)) },
+        }
+    }
+
+    // 同上，Err(())表示CAS失败；Ok(None)表示栈为空
+    pub(crate) fn try_pop(&self) -> Result<Option<T>, ()> {
+        let guard = R::pin();
+        let head = guard.protect(0, &self.head);
+        if head.is_null() {
+            return Ok(None);
+        }
+        let next = unsafe { (*head).next };
+        if self
+            .head
+            .compare_exchange(head, next, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            return Err(());
+        }
+        // 和pop一样独占地取走元素，节点交给回收策略
+        unsafe {
+            let elem = ptr::read(&*(*head).elem);
+            guard.retire(head);
+            Ok(Some(elem))
+        }
+    }
+
+    // 并发情况下结果只代表调用那一刻的状态
+    pub fn is_empty(&self) -> bool {
+        self.head.load(Ordering::Acquire).is_null()
+    }
+}
+
+impl<T, R: Reclaim> Default for TreiberStack<T, R> {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+impl<T, R: Reclaim> Drop for TreiberStack<T, R> {
+    fn drop(&mut self) {
+        // &mut self保证没有并发访问，沿着next释放剩余节点
+        let mut cur = self.head.load(Ordering::Relaxed);
+        while !cur.is_null() {
+            // SAFETY: 栈里的节点都来自Box::into_raw且元素还没有被取走
+            unsafe {
+                let mut node = Box::from_raw(cur);
+                ManuallyDrop::drop(&mut node.elem);
+                cur = node.next;
+            }
+        }
+    }
+}
+
+// 元素会在线程之间转移，所以只要求T: Send
+unsafe impl<T: Send, R: Reclaim> Send for TreiberStack<T, R> {}
+unsafe impl<T: Send, R: Reclaim> Sync for TreiberStack<T, R> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reclaim::Hazard;
+    use std::collections::HashSet;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn basics() {
+        let stack = TreiberStack::new();
+        assert!(stack.is_empty());
+        assert_eq!(stack.pop(), None);
+
+        stack.push(1);
+        stack.push(2);
+        stack.push(3);
+        assert!(!stack.is_empty());
+
+        assert_eq!(stack.pop(), Some(3));
+        assert_eq!(stack.pop(), Some(2));
+        stack.push(4);
+        assert_eq!(stack.pop(), Some(4));
+        assert_eq!(stack.pop(), Some(1));
+        assert_eq!(stack.pop(), None);
+    }
+
+    fn concurrent_push_pop<R: Reclaim>(reclaim: R) {
+        const THREADS: usize = 8;
+        const PER_THREAD: usize = 1000;
+
+        let stack = Arc::new(TreiberStack::with_reclaim(reclaim));
+        let handles: Vec<_> = (0..THREADS)
+            .map(|t| {
+                let stack = Arc::clone(&stack);
+                thread::spawn(move || {
+                    let mut popped = Vec::new();
+                    for i in 0..PER_THREAD {
+                        stack.push(t * PER_THREAD + i);
+                        // 每推两个弹一个，制造push/pop交错
+                        if i % 2 == 1 {
+                            popped.extend(stack.pop());
+                        }
+                    }
+                    popped
+                })
+            })
+            .collect();
+
+        let mut seen = HashSet::new();
+        for h in handles {
+            for x in h.join().unwrap() {
+                assert!(seen.insert(x), "{} popped twice", x);
+            }
+        }
+        while let Some(x) = stack.pop() {
+            assert!(seen.insert(x), "{} popped twice", x);
+        }
+        assert_eq!(seen.len(), THREADS * PER_THREAD);
+    }
+
+    #[test]
+    fn concurrent_push_pop_epoch() {
+        concurrent_push_pop(Epoch);
+    }
+
+    #[test]
+    fn concurrent_push_pop_hazard() {
+        concurrent_push_pop(Hazard);
+    }
+
+    struct DropCounter(Arc<AtomicUsize>);
+
+    impl Drop for DropCounter {
+        fn drop(&mut self) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn elements_dropped_exactly_once() {
+        let drops = Arc::new(AtomicUsize::new(0));
+        let stack = TreiberStack::new();
+        for _ in 0..10 {
+            stack.push(DropCounter(Arc::clone(&drops)));
+        }
+        drop(stack.pop());
+        drop(stack.pop());
+        assert_eq!(drops.load(Ordering::SeqCst), 2);
+        drop(stack);
+        assert_eq!(drops.load(Ordering::SeqCst), 10);
+
+        let stack = TreiberStack::with_reclaim(Hazard);
+        for _ in 0..10 {
+            stack.push(DropCounter(Arc::clone(&drops)));
+        }
+        drop(stack.pop());
+        assert_eq!(drops.load(Ordering::SeqCst), 11);
+        drop(stack);
+        assert_eq!(drops.load(Ordering::SeqCst), 20);
+    }
+}
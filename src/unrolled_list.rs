@@ -0,0 +1,748 @@
+// 展开链表(unrolled linked list): 每个节点存一小段连续的元素(最多K个)，节点之间再双向链接
+// 遍历时大部分时间在节点内部顺序读连续内存，指针跳转和缓存未命中减少到原来的约1/K，
+// 每个元素分摊的指针开销也从两个指针降到2/K个
+// 节点满了插入时对半分裂；删除后节点只剩不到一半(不超过K/2)时尝试和相邻节点合并，避免退化成一元素一节点
+// 按下标访问需要逐个节点跳过，复杂度O(n/K)
+
+use std::fmt;
+use std::iter::FusedIterator;
+use std::marker::PhantomData;
+use std::mem::MaybeUninit;
+use std::ptr::{self, NonNull};
+
+use crate::error::{check_insert, ListError};
+
+type Link<T, const K: usize> = Option<NonNull<Node<T, K>>>;
+
+struct Node<T, const K: usize> {
+    prev: Link<T, K>,
+    next: Link<T, K>,
+    // elems[..len]已初始化
+    len: usize,
+    elems: [MaybeUninit<T>; K],
+}
+
+impl<T, const K: usize> Node<T, K> {
+    fn alloc() -> NonNull<Self> {
+        let node = Box::new(Node {
+            prev: None,
+            next: None,
+            len: 0,
+            elems: [const { MaybeUninit::uninit() }; K],
+        });
+        NonNull::from(Box::leak(node))
+    }
+
+    // 允许i == K(末尾之后的位置)，移动元素时会用到
+    fn slot(&mut self, i: usize) -> *mut T {
+        debug_assert!(i <= K);
+        unsafe { (self.elems.as_mut_ptr() as *mut T).add(i) }
+    }
+
+    fn as_slice(&self) -> &[T] {
+        unsafe { std::slice::from_raw_parts(self.elems.as_ptr() as *const T, self.len) }
+    }
+
+    fn as_mut_slice(&mut self) -> &mut [T] {
+        unsafe { std::slice::from_raw_parts_mut(self.elems.as_mut_ptr() as *mut T, self.len) }
+    }
+
+    // 调用者保证len < K且i <= len
+    unsafe fn insert_at(&mut self, i: usize, elem: T) {
+        // 只取一次基址，同一个操作里的指针都从它派生
+        let base = self.slot(0);
+        ptr::copy(base.add(i), base.add(i + 1), self.len - i);
+        base.add(i).write(elem);
+        self.len += 1;
+    }
+
+    // 调用者保证i < len
+    unsafe fn remove_at(&mut self, i: usize) -> T {
+        let base = self.slot(0);
+        let elem = base.add(i).read();
+        ptr::copy(base.add(i + 1), base.add(i), self.len - i - 1);
+        self.len -= 1;
+        elem
+    }
+}
+
+impl<T, const K: usize> Drop for Node<T, K> {
+    fn drop(&mut self) {
+        unsafe { ptr::drop_in_place(self.as_mut_slice()) };
+    }
+}
+
+pub struct UnrolledList<T, const K: usize = 16> {
+    head: Link<T, K>,
+    tail: Link<T, K>,
+    len: usize,
+    nodes: usize,
+    _boo: PhantomData<Box<Node<T, K>>>,
+}
+
+impl<T> UnrolledList<T> {
+    pub fn new() -> Self {
+        Self::empty()
+    }
+}
+
+impl<T, const K: usize> UnrolledList<T, K> {
+    // 指定每个节点的容量，例如UnrolledList::<u8, 64>::with_chunk()
+    pub fn with_chunk() -> Self {
+        Self::empty()
+    }
+
+    fn empty() -> Self {
+        // K < 2时分裂没有意义
+        const { assert!(K >= 2, "UnrolledList needs at least 2 elements per node") };
+        UnrolledList {
+            head: None,
+            tail: None,
+            len: 0,
+            nodes: 0,
+            _boo: PhantomData,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // 当前的节点数，可以用来观察分裂和合并
+    pub fn node_count(&self) -> usize {
+        self.nodes
+    }
+
+    pub fn push_back(&mut self, elem: T) {
+        let tail = match self.tail {
+            Some(t) if unsafe { (*t.as_ptr()).len } < K => t,
+            _ => self.link_after(self.tail, Node::alloc()),
+        };
+        unsafe {
+            let node = &mut *tail.as_ptr();
+            node.slot(node.len).write(elem);
+            node.len += 1;
+        }
+        self.len += 1;
+    }
+
+    pub fn push_front(&mut self, elem: T) {
+        let head = match self.head {
+            Some(h) if unsafe { (*h.as_ptr()).len } < K => h,
+            _ => self.link_before(self.head, Node::alloc()),
+        };
+        unsafe { (*head.as_ptr()).insert_at(0, elem) };
+        self.len += 1;
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        let head = self.head?;
+        let elem = unsafe { (*head.as_ptr()).remove_at(0) };
+        self.after_remove(head);
+        Some(elem)
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        let tail = self.tail?;
+        let elem = unsafe {
+            let node = &mut *tail.as_ptr();
+            node.remove_at(node.len - 1)
+        };
+        self.after_remove(tail);
+        Some(elem)
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        self.head.map(|h| unsafe { &(*h.as_ptr()).as_slice()[0] })
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        self.tail.map(|t| unsafe { (*t.as_ptr()).as_slice().last().unwrap() })
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        let (node, offset) = self.locate(index)?;
+        unsafe { Some(&(*node.as_ptr()).as_slice()[offset]) }
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        let (node, offset) = self.locate(index)?;
+        unsafe { Some(&mut (*node.as_ptr()).as_mut_slice()[offset]) }
+    }
+
+    // index == len时等同于push_back，超出范围会panic
+    pub fn insert(&mut self, index: usize, elem: T) {
+        assert!(index <= self.len, "insertion index {index} out of bounds (len {})", self.len);
+        if index == self.len {
+            return self.push_back(elem);
+        }
+        let (mut node, mut offset) = self.locate(index).unwrap();
+        unsafe {
+            if (*node.as_ptr()).len == K {
+                // 节点满了: 后一半搬到新节点，再决定插到哪一半
+                let new = self.link_after(Some(node), Node::alloc());
+                let (from, to) = (&mut *node.as_ptr(), &mut *new.as_ptr());
+                let keep = K / 2;
+                ptr::copy_nonoverlapping(from.slot(keep), to.slot(0), K - keep);
+                to.len = K - keep;
+                from.len = keep;
+                if offset > keep {
+                    node = new;
+                    offset -= keep;
+                }
+            }
+            (*node.as_ptr()).insert_at(offset, elem);
+        }
+        self.len += 1;
+    }
+
+    // 超出范围会panic
+    pub fn remove(&mut self, index: usize) -> T {
+        let Some((node, offset)) = self.locate(index) else {
+            panic!("removal index {index} out of bounds (len {})", self.len);
+        };
+        let elem = unsafe { (*node.as_ptr()).remove_at(offset) };
+        self.after_remove(node);
+        elem
+    }
+
+    // insert/remove的不panic版本，越界时返回OutOfRange
+    pub fn try_insert(&mut self, index: usize, elem: T) -> Result<(), ListError> {
+        check_insert(index, self.len)?;
+        self.insert(index, elem);
+        Ok(())
+    }
+
+    pub fn try_remove(&mut self, index: usize) -> Result<T, ListError> {
+        if index >= self.len {
+            return Err(ListError::OutOfRange { index, len: self.len });
+        }
+        Ok(self.remove(index))
+    }
+
+    pub fn clear(&mut self) {
+        let mut cur = self.head.take();
+        while let Some(node) = cur {
+            unsafe {
+                let node = Box::from_raw(node.as_ptr());
+                cur = node.next;
+            }
+        }
+        self.tail = None;
+        self.len = 0;
+        self.nodes = 0;
+    }
+
+    pub fn iter(&self) -> Iter<'_, T, K> {
+        Iter {
+            front: self.head,
+            front_idx: 0,
+            back: self.tail,
+            back_idx: self.tail.map_or(0, |t| unsafe { (*t.as_ptr()).len }),
+            remaining: self.len,
+            _boo: PhantomData,
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T, K> {
+        IterMut {
+            front: self.head,
+            front_idx: 0,
+            back: self.tail,
+            back_idx: self.tail.map_or(0, |t| unsafe { (*t.as_ptr()).len }),
+            remaining: self.len,
+            _boo: PhantomData,
+        }
+    }
+
+    // 找到第index个元素所在的节点和节点内偏移，从离得近的一端开始找
+    fn locate(&self, index: usize) -> Option<(NonNull<Node<T, K>>, usize)> {
+        if index >= self.len {
+            return None;
+        }
+        unsafe {
+            if index < self.len / 2 {
+                let mut node = self.head?;
+                let mut offset = index;
+                while offset >= (*node.as_ptr()).len {
+                    offset -= (*node.as_ptr()).len;
+                    node = (*node.as_ptr()).next?;
+                }
+                Some((node, offset))
+            } else {
+                let mut node = self.tail?;
+                let mut from_back = self.len - 1 - index;
+                while from_back >= (*node.as_ptr()).len {
+                    from_back -= (*node.as_ptr()).len;
+                    node = (*node.as_ptr()).prev?;
+                }
+                Some((node, (*node.as_ptr()).len - 1 - from_back))
+            }
+        }
+    }
+
+    // 删除一个元素后维护节点: 空节点直接摘掉，过空的节点尽量和邻居合并
+    fn after_remove(&mut self, node: NonNull<Node<T, K>>) {
+        self.len -= 1;
+        unsafe {
+            let n = &mut *node.as_ptr();
+            if n.len == 0 {
+                self.unlink(node);
+                return;
+            }
+            if n.len > K / 2 {
+                return;
+            }
+            if let Some(next) = n.next {
+                if n.len + (*next.as_ptr()).len <= K {
+                    self.merge_into(node, next);
+                    return;
+                }
+            }
+            if let Some(prev) = n.prev {
+                if (*prev.as_ptr()).len + n.len <= K {
+                    self.merge_into(prev, node);
+                }
+            }
+        }
+    }
+
+    // 把right的元素全部搬到left末尾并释放right，调用者保证放得下且right紧跟在left之后
+    unsafe fn merge_into(&mut self, left: NonNull<Node<T, K>>, right: NonNull<Node<T, K>>) {
+        let (l, r) = (&mut *left.as_ptr(), &mut *right.as_ptr());
+        ptr::copy_nonoverlapping(r.slot(0), l.slot(l.len), r.len);
+        l.len += r.len;
+        r.len = 0;
+        self.unlink(right);
+    }
+
+    // 把new挂到at之后(at为None时挂到表头)
+    fn link_after(&mut self, at: Link<T, K>, new: NonNull<Node<T, K>>) -> NonNull<Node<T, K>> {
+        unsafe {
+            let next = match at {
+                Some(a) => (*a.as_ptr()).next.replace(new),
+                None => self.head.replace(new),
+            };
+            (*new.as_ptr()).prev = at;
+            (*new.as_ptr()).next = next;
+            match next {
+                Some(n) => (*n.as_ptr()).prev = Some(new),
+                None => self.tail = Some(new),
+            }
+        }
+        self.nodes += 1;
+        new
+    }
+
+    fn link_before(&mut self, at: Link<T, K>, new: NonNull<Node<T, K>>) -> NonNull<Node<T, K>> {
+        let prev = match at {
+            Some(a) => unsafe { (*a.as_ptr()).prev },
+            None => self.tail,
+        };
+        self.link_after(prev, new)
+    }
+
+    // 摘下并释放节点(剩余的元素也会被析构)
+    fn unlink(&mut self, node: NonNull<Node<T, K>>) {
+        unsafe {
+            let node = Box::from_raw(node.as_ptr());
+            match node.prev {
+                Some(p) => (*p.as_ptr()).next = node.next,
+                None => self.head = node.next,
+            }
+            match node.next {
+                Some(n) => (*n.as_ptr()).prev = node.prev,
+                None => self.tail = node.prev,
+            }
+        }
+        self.nodes -= 1;
+    }
+}
+
+impl<T, const K: usize> Drop for UnrolledList<T, K> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+impl<T, const K: usize> Default for UnrolledList<T, K> {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+impl<T: fmt::Debug, const K: usize> fmt::Debug for UnrolledList<T, K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: Clone, const K: usize> Clone for UnrolledList<T, K> {
+    fn clone(&self) -> Self {
+        self.iter().cloned().collect()
+    }
+}
+
+impl<T: PartialEq, const K: usize> PartialEq for UnrolledList<T, K> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Eq, const K: usize> Eq for UnrolledList<T, K> {}
+
+impl<T, const K: usize> Extend<T> for UnrolledList<T, K> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for elem in iter {
+            self.push_back(elem);
+        }
+    }
+}
+
+impl<T, const K: usize> FromIterator<T> for UnrolledList<T, K> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = Self::empty();
+        list.extend(iter);
+        list
+    }
+}
+
+// front_idx是前端下一个要读的位置，back_idx是后端上一次读到的位置(下一个读back_idx - 1)
+pub struct Iter<'a, T, const K: usize> {
+    front: Link<T, K>,
+    front_idx: usize,
+    back: Link<T, K>,
+    back_idx: usize,
+    remaining: usize,
+    _boo: PhantomData<&'a Node<T, K>>,
+}
+
+impl<'a, T, const K: usize> Iterator for Iter<'a, T, K> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        unsafe {
+            let mut node = &*self.front?.as_ptr();
+            if self.front_idx == node.len {
+                self.front = node.next;
+                self.front_idx = 0;
+                node = &*self.front?.as_ptr();
+            }
+            self.front_idx += 1;
+            Some(&node.as_slice()[self.front_idx - 1])
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T, const K: usize> DoubleEndedIterator for Iter<'_, T, K> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        unsafe {
+            let mut node = &*self.back?.as_ptr();
+            if self.back_idx == 0 {
+                self.back = node.prev;
+                node = &*self.back?.as_ptr();
+                self.back_idx = node.len;
+            }
+            self.back_idx -= 1;
+            Some(&node.as_slice()[self.back_idx])
+        }
+    }
+}
+
+impl<T, const K: usize> ExactSizeIterator for Iter<'_, T, K> {}
+impl<T, const K: usize> FusedIterator for Iter<'_, T, K> {}
+
+unsafe fn elem_ptr<T, const K: usize>(node: *mut Node<T, K>, i: usize) -> *mut T {
+    ptr::addr_of_mut!((*node).elems).cast::<T>().add(i)
+}
+
+pub struct IterMut<'a, T, const K: usize> {
+    front: Link<T, K>,
+    front_idx: usize,
+    back: Link<T, K>,
+    back_idx: usize,
+    remaining: usize,
+    _boo: PhantomData<&'a mut Node<T, K>>,
+}
+
+impl<'a, T, const K: usize> Iterator for IterMut<'a, T, K> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<&'a mut T> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        unsafe {
+            let mut node = self.front?.as_ptr();
+            if self.front_idx == (*node).len {
+                self.front = (*node).next;
+                self.front_idx = 0;
+                node = self.front?.as_ptr();
+            }
+            self.front_idx += 1;
+            // 已经交出去的&mut T可能还活着，不能再借用整个节点，直接从字段地址算出元素指针
+            Some(&mut *elem_ptr(node, self.front_idx - 1))
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T, const K: usize> DoubleEndedIterator for IterMut<'_, T, K> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        unsafe {
+            let mut node = self.back?.as_ptr();
+            if self.back_idx == 0 {
+                self.back = (*node).prev;
+                node = self.back?.as_ptr();
+                self.back_idx = (*node).len;
+            }
+            self.back_idx -= 1;
+            Some(&mut *elem_ptr(node, self.back_idx))
+        }
+    }
+}
+
+impl<T, const K: usize> ExactSizeIterator for IterMut<'_, T, K> {}
+impl<T, const K: usize> FusedIterator for IterMut<'_, T, K> {}
+
+pub struct IntoIter<T, const K: usize>(UnrolledList<T, K>);
+
+impl<T, const K: usize> Iterator for IntoIter<T, K> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.0.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.0.len, Some(self.0.len))
+    }
+}
+
+impl<T, const K: usize> DoubleEndedIterator for IntoIter<T, K> {
+    fn next_back(&mut self) -> Option<T> {
+        self.0.pop_back()
+    }
+}
+
+impl<T, const K: usize> ExactSizeIterator for IntoIter<T, K> {}
+
+impl<T, const K: usize> IntoIterator for UnrolledList<T, K> {
+    type Item = T;
+    type IntoIter = IntoIter<T, K>;
+
+    fn into_iter(self) -> IntoIter<T, K> {
+        IntoIter(self)
+    }
+}
+
+impl<'a, T, const K: usize> IntoIterator for &'a UnrolledList<T, K> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T, K>;
+
+    fn into_iter(self) -> Iter<'a, T, K> {
+        self.iter()
+    }
+}
+
+impl<'a, T, const K: usize> IntoIterator for &'a mut UnrolledList<T, K> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T, K>;
+
+    fn into_iter(self) -> IterMut<'a, T, K> {
+        self.iter_mut()
+    }
+}
+
+unsafe impl<T: Send, const K: usize> Send for UnrolledList<T, K> {}
+unsafe impl<T: Sync, const K: usize> Sync for UnrolledList<T, K> {}
+unsafe impl<T: Sync, const K: usize> Send for Iter<'_, T, K> {}
+unsafe impl<T: Sync, const K: usize> Sync for Iter<'_, T, K> {}
+unsafe impl<T: Send, const K: usize> Send for IterMut<'_, T, K> {}
+unsafe impl<T: Sync, const K: usize> Sync for IterMut<'_, T, K> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::XorShift;
+    use std::rc::Rc;
+
+    #[test]
+    fn push_pop_both_ends() {
+        let mut list = UnrolledList::<i32, 4>::with_chunk();
+        for i in 0..10 {
+            list.push_back(i);
+        }
+        assert_eq!(list.node_count(), 3);
+        list.push_front(-1);
+        assert_eq!(list.len(), 11);
+        assert_eq!(list.front(), Some(&-1));
+        assert_eq!(list.back(), Some(&9));
+        assert_eq!(list.pop_back(), Some(9));
+        assert_eq!(list.pop_front(), Some(-1));
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), (0..9).collect::<Vec<_>>());
+        while list.pop_front().is_some() {}
+        assert_eq!(list.node_count(), 0);
+        assert!(list.is_empty());
+    }
+
+    #[test]
+    fn insert_splits_full_node() {
+        let mut list: UnrolledList<i32, 4> = (0..4).collect();
+        assert_eq!(list.node_count(), 1);
+        list.insert(1, 10);
+        assert_eq!(list.node_count(), 2);
+        list.insert(4, 20);
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![0, 10, 1, 2, 20, 3]);
+        list.insert(6, 30);
+        assert_eq!(list.get(6), Some(&30));
+        assert_eq!(list.get(7), None);
+    }
+
+    #[test]
+    fn remove_merges_sparse_nodes() {
+        let mut list: UnrolledList<i32, 4> = (0..16).collect();
+        assert_eq!(list.node_count(), 4);
+        // 每个节点删掉一半，节点变得过空，应该两两合并
+        for i in (0..16).rev().filter(|i| i % 2 == 1) {
+            assert_eq!(list.remove(i), i as i32);
+        }
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![0, 2, 4, 6, 8, 10, 12, 14]);
+        assert_eq!(list.node_count(), 2);
+        // 同时持有所有元素的可变引用
+        let refs: Vec<&mut i32> = list.iter_mut().collect();
+        for x in refs {
+            *x += 1;
+        }
+        assert_eq!(list.into_iter().rev().collect::<Vec<_>>(), vec![15, 13, 11, 9, 7, 5, 3, 1]);
+    }
+
+    #[test]
+    #[should_panic(expected = "out of bounds")]
+    fn insert_out_of_bounds_panics() {
+        let mut list = UnrolledList::new();
+        list.push_back(1);
+        list.insert(3, 2);
+    }
+
+    #[test]
+    fn double_ended_iteration_meets_in_middle() {
+        let list: UnrolledList<i32, 3> = (0..10).collect();
+        let mut it = list.iter();
+        let mut front = Vec::new();
+        let mut back = Vec::new();
+        while let Some(x) = it.next() {
+            front.push(*x);
+            if let Some(y) = it.next_back() {
+                back.push(*y);
+            }
+        }
+        assert_eq!(front, vec![0, 1, 2, 3, 4]);
+        assert_eq!(back, vec![9, 8, 7, 6, 5]);
+    }
+
+    // 随机插入删除，和Vec比较
+    #[test]
+    fn randomized_against_vec() {
+        let mut rng = XorShift::new(2024);
+        let mut list = UnrolledList::<u64, 8>::with_chunk();
+        let mut reference = Vec::new();
+        for step in 0..5000 {
+            let len = reference.len() as u64;
+            match rng.next_u64() % 6 {
+                0 => {
+                    let i = (rng.next_u64() % (len + 1)) as usize;
+                    list.insert(i, step);
+                    reference.insert(i, step);
+                }
+                1 if len > 0 => {
+                    let i = (rng.next_u64() % len) as usize;
+                    assert_eq!(list.remove(i), reference.remove(i));
+                }
+                2 => assert_eq!(list.pop_front(), (!reference.is_empty()).then(|| reference.remove(0))),
+                3 => assert_eq!(list.pop_back(), reference.pop()),
+                4 => {
+                    list.push_front(step);
+                    reference.insert(0, step);
+                }
+                _ => {
+                    list.push_back(step);
+                    reference.push(step);
+                }
+            }
+            assert_eq!(list.len(), reference.len());
+            if step % 250 == 0 {
+                assert!(list.iter().eq(reference.iter()));
+                assert!(list.iter().rev().eq(reference.iter().rev()));
+                // 合并策略保证节点平均不会太空
+                assert!(list.node_count() <= reference.len() / 2 + 2);
+            }
+        }
+    }
+
+    #[test]
+    fn drops_every_element() {
+        let marker = Rc::new(());
+        let mut list = UnrolledList::<Rc<()>, 4>::with_chunk();
+        for _ in 0..20 {
+            list.push_back(Rc::clone(&marker));
+        }
+        drop(list.remove(7));
+        drop(list.pop_front());
+        assert_eq!(Rc::strong_count(&marker), 19);
+        drop(list);
+        assert_eq!(Rc::strong_count(&marker), 1);
+    }
+
+    // 从尾部推入时节点都是满的，每K个元素分配一次
+    #[test]
+    fn one_allocation_per_chunk() {
+        use crate::alloc_counter::{assert_no_leaks, measure};
+        let (list, stats) = measure(|| (0..100).collect::<UnrolledList<i32, 16>>());
+        assert_eq!(stats.allocations, 100usize.div_ceil(16));
+        drop(list);
+        // 中间插入会拆分节点，多出来的节点也都要释放
+        assert_no_leaks(|| {
+            let mut list: UnrolledList<i32, 16> = (0..100).collect();
+            for i in 0..50 {
+                list.insert(i * 2, i as i32);
+            }
+        });
+    }
+
+    #[test]
+    fn try_insert_and_remove() {
+        let mut list = UnrolledList::<i32, 4>::with_chunk();
+        for i in 0..6 {
+            list.try_insert(i, i as i32).unwrap();
+        }
+        assert_eq!(list.try_insert(7, 7), Err(ListError::OutOfRange { index: 7, len: 6 }));
+        assert_eq!(list.try_remove(2), Ok(2));
+        assert_eq!(list.try_remove(5), Err(ListError::OutOfRange { index: 5, len: 5 }));
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![0, 1, 3, 4, 5]);
+    }
+}
@@ -0,0 +1,368 @@
+// 可以回到任意历史版本的链表
+// 每次修改都在simple_stack_3的持久化列表上生成一个新版本，旧版本原样保留:
+// - push_front只在旧版本前面加一个节点，pop_front直接取旧版本的tail，都不复制
+// - insert/remove在位置i修改时复制前i个元素，之后的节点和旧版本共享
+// 版本之间用parent连成一棵树: checkout回到旧版本后再修改，就从那里长出一条新分支
+// diff利用结构共享: 两个版本从某个节点开始共享同一段后缀，只需要比较各自共享点之前的前缀，
+//   判断依据是节点是否是同一个，而不是元素是否相等
+// gc: 当前版本和被pin住的版本，以及它们的所有祖先是可达的；其余版本(被放弃的分支)释放掉，
+//   只被这些版本引用的节点随之释放
+
+use std::fmt;
+
+use crate::error::{check_insert, ListError};
+use crate::simple_stack_3::{Iter, List};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Version(usize);
+
+struct Entry<T> {
+    list: List<T>,
+    len: usize,
+    parent: Option<Version>,
+}
+
+// 从from到to: 先删掉removed(from独有的前缀)，再在前面加上added(to独有的前缀)，后面shared个元素不变
+#[derive(Debug, PartialEq, Eq)]
+pub struct Diff<'a, T> {
+    pub removed: Vec<&'a T>,
+    pub added: Vec<&'a T>,
+    pub shared: usize,
+}
+
+pub struct VersionedList<T> {
+    versions: Vec<Option<Entry<T>>>,
+    current: Version,
+    pinned: Vec<Version>,
+}
+
+impl<T> VersionedList<T> {
+    // 初始版本是空列表
+    pub fn new() -> Self {
+        VersionedList {
+            versions: vec![Some(Entry {
+                list: List::new(),
+                len: 0,
+                parent: None,
+            })],
+            current: Version(0),
+            pinned: Vec::new(),
+        }
+    }
+
+    fn entry(&self, version: Version) -> Option<&Entry<T>> {
+        self.versions.get(version.0)?.as_ref()
+    }
+
+    fn current_entry(&self) -> &Entry<T> {
+        self.entry(self.current).unwrap()
+    }
+
+    // 以当前版本为parent记录一个新版本并切换过去
+    fn commit(&mut self, list: List<T>, len: usize) -> Version {
+        let version = Version(self.versions.len());
+        self.versions.push(Some(Entry {
+            list,
+            len,
+            parent: Some(self.current),
+        }));
+        self.current = version;
+        version
+    }
+
+    pub fn current(&self) -> Version {
+        self.current
+    }
+
+    pub fn len(&self) -> usize {
+        self.current_entry().len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        self.current_entry().list.head()
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        self.current_entry().list.iter()
+    }
+
+    // 版本已经被gc时返回None
+    pub fn iter_at(&self, version: Version) -> Option<Iter<'_, T>> {
+        self.entry(version).map(|entry| entry.list.iter())
+    }
+
+    pub fn parent(&self, version: Version) -> Option<Version> {
+        self.entry(version)?.parent
+    }
+
+    // 还没有被gc的版本数
+    pub fn version_count(&self) -> usize {
+        self.versions.iter().flatten().count()
+    }
+
+    pub fn contains_version(&self, version: Version) -> bool {
+        self.entry(version).is_some()
+    }
+
+    // 切换到某个历史版本，之后的修改从它开始分叉；版本不存在时返回false
+    pub fn checkout(&mut self, version: Version) -> bool {
+        let exists = self.contains_version(version);
+        if exists {
+            self.current = version;
+        }
+        exists
+    }
+
+    pub fn push_front(&mut self, elem: T) -> Version {
+        let entry = self.current_entry();
+        let (list, len) = (entry.list.prepend(elem), entry.len + 1);
+        self.commit(list, len)
+    }
+
+    // 空列表时不产生新版本，返回None
+    pub fn pop_front(&mut self) -> Option<Version> {
+        let entry = self.current_entry();
+        if entry.len == 0 {
+            return None;
+        }
+        let (list, len) = (entry.list.tail(), entry.len - 1);
+        Some(self.commit(list, len))
+    }
+
+    // 任一版本不存在时返回None
+    pub fn diff(&self, from: Version, to: Version) -> Option<Diff<'_, T>> {
+        let (a, b) = (self.entry(from)?, self.entry(to)?);
+        // 先让较长的一边走到和另一边一样长，再同步往后走，直到两边是同一个节点
+        let (mut x, mut y) = (a.list.clone(), b.list.clone());
+        let (mut la, mut lb) = (a.len, b.len);
+        while la > lb {
+            x = x.tail();
+            la -= 1;
+        }
+        while lb > la {
+            y = y.tail();
+            lb -= 1;
+        }
+        while !x.ptr_eq(&y) {
+            x = x.tail();
+            y = y.tail();
+            la -= 1;
+        }
+        Some(Diff {
+            removed: a.list.iter().take(a.len - la).collect(),
+            added: b.list.iter().take(b.len - la).collect(),
+            shared: la,
+        })
+    }
+
+    // pin住的版本和它的祖先不会被gc；版本不存在时返回false
+    pub fn pin(&mut self, version: Version) -> bool {
+        let exists = self.contains_version(version);
+        if exists && !self.pinned.contains(&version) {
+            self.pinned.push(version);
+        }
+        exists
+    }
+
+    pub fn unpin(&mut self, version: Version) {
+        self.pinned.retain(|&v| v != version);
+    }
+
+    // 释放从当前版本和pin住的版本出发都回溯不到的版本，返回释放的个数
+    pub fn gc(&mut self) -> usize {
+        let mut reachable = vec![false; self.versions.len()];
+        for &root in self.pinned.iter().chain([&self.current]) {
+            let mut cur = Some(root);
+            // 遇到已经标记过的版本说明更早的祖先也都标记过了
+            while let Some(version) = cur.filter(|v| !reachable[v.0]) {
+                reachable[version.0] = true;
+                cur = self.parent(version);
+            }
+        }
+        let mut removed = 0;
+        for (slot, reachable) in self.versions.iter_mut().zip(reachable) {
+            if !reachable && slot.take().is_some() {
+                removed += 1;
+            }
+        }
+        removed
+    }
+}
+
+impl<T: Clone> VersionedList<T> {
+    // 复制前index个元素，后面的节点共享
+    fn rebuild(&self, index: usize, suffix: List<T>) -> List<T> {
+        let prefix: Vec<&T> = self.iter().take(index).collect();
+        prefix
+            .into_iter()
+            .rev()
+            .fold(suffix, |list, elem| list.prepend(elem.clone()))
+    }
+
+    // index > len时panic
+    pub fn insert(&mut self, index: usize, elem: T) -> Version {
+        let len = self.len();
+        assert!(index <= len, "insertion index out of bounds");
+        let mut rest = self.current_entry().list.clone();
+        for _ in 0..index {
+            rest = rest.tail();
+        }
+        let list = self.rebuild(index, rest.prepend(elem));
+        self.commit(list, len + 1)
+    }
+
+    // index > len时返回OutOfRange，不生成新版本
+    pub fn try_insert(&mut self, index: usize, elem: T) -> Result<Version, ListError> {
+        check_insert(index, self.len())?;
+        Ok(self.insert(index, elem))
+    }
+
+    // index越界时不产生新版本，返回None
+    pub fn remove(&mut self, index: usize) -> Option<Version> {
+        let len = self.len();
+        if index >= len {
+            return None;
+        }
+        let mut rest = self.current_entry().list.clone();
+        for _ in 0..=index {
+            rest = rest.tail();
+        }
+        let list = self.rebuild(index, rest);
+        Some(self.commit(list, len - 1))
+    }
+}
+
+impl<T> Default for VersionedList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for VersionedList<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("VersionedList")
+            .field("current", &self.current)
+            .field("versions", &self.version_count())
+            .field("list", &DebugList(self))
+            .finish()
+    }
+}
+
+struct DebugList<'a, T>(&'a VersionedList<T>);
+
+impl<T: fmt::Debug> fmt::Debug for DebugList<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.0.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+
+    fn at<T: Clone>(list: &VersionedList<T>, version: Version) -> Vec<T> {
+        list.iter_at(version).unwrap().cloned().collect()
+    }
+
+    #[test]
+    fn every_mutation_is_a_version() {
+        let mut list = VersionedList::new();
+        let v0 = list.current();
+        let v1 = list.push_front(1);
+        let v2 = list.push_front(2);
+        let v3 = list.pop_front().unwrap();
+        assert_eq!(list.pop_front().map(|v| list.parent(v)), Some(Some(v3)));
+        assert_eq!(list.pop_front(), None);
+        assert_eq!(at(&list, v0), Vec::<i32>::new());
+        assert_eq!(at(&list, v1), vec![1]);
+        assert_eq!(at(&list, v2), vec![2, 1]);
+        assert_eq!(at(&list, v3), vec![1]);
+        assert!(list.is_empty());
+        assert!(list.checkout(v2));
+        assert_eq!(list.front(), Some(&2));
+        assert_eq!(list.len(), 2);
+        assert_eq!(list.version_count(), 5);
+    }
+
+    #[test]
+    fn insert_and_remove_share_suffix() {
+        let mut list = VersionedList::new();
+        for x in (1..=5).rev() {
+            list.push_front(x);
+        }
+        let base = list.current();
+        let inserted = list.insert(2, 10);
+        assert_eq!(at(&list, inserted), vec![1, 2, 10, 3, 4, 5]);
+        let diff = list.diff(base, inserted).unwrap();
+        assert_eq!(diff.removed, vec![&1, &2]);
+        assert_eq!(diff.added, vec![&1, &2, &10]);
+        assert_eq!(diff.shared, 3);
+        let removed = list.remove(4).unwrap();
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![1, 2, 10, 3, 5]);
+        assert_eq!(list.diff(inserted, removed).unwrap().shared, 1);
+        assert_eq!(list.remove(5), None);
+        assert_eq!(list.current(), removed);
+        // 同一个版本和自己没有差别
+        let same = list.diff(base, base).unwrap();
+        assert_eq!((same.removed.len(), same.added.len(), same.shared), (0, 0, 5));
+    }
+
+    #[test]
+    #[should_panic(expected = "insertion index out of bounds")]
+    fn insert_past_end_panics() {
+        let mut list = VersionedList::new();
+        list.push_front(1);
+        list.insert(2, 0);
+    }
+
+    #[test]
+    fn checkout_branches_and_diff() {
+        let mut list = VersionedList::new();
+        list.push_front('a');
+        let fork = list.push_front('b');
+        let left = list.push_front('c');
+        assert!(list.checkout(fork));
+        list.pop_front();
+        let right = list.push_front('x');
+        assert_eq!(list.parent(right).and_then(|v| list.parent(v)), Some(fork));
+        let diff = list.diff(left, right).unwrap();
+        assert_eq!(diff, Diff { removed: vec![&'c', &'b'], added: vec![&'x'], shared: 1 });
+        assert_eq!(list.diff(right, left).unwrap().added, vec![&'c', &'b']);
+        assert_eq!(format!("{list:?}"), "VersionedList { current: Version(5), versions: 6, list: ['x', 'a'] }");
+    }
+
+    #[test]
+    fn gc_drops_abandoned_branches() {
+        let shared = Rc::new(0);
+        let mut list = VersionedList::new();
+        let base = list.push_front(Rc::clone(&shared));
+        let dead = list.push_front(Rc::new(1));
+        let dead_elem = Rc::downgrade(list.front().unwrap());
+        let kept = {
+            list.checkout(base);
+            list.push_front(Rc::new(2))
+        };
+        list.checkout(base);
+        let head = list.push_front(Rc::new(3));
+        assert!(list.pin(kept));
+        assert!(!list.pin(Version(99)));
+        // dead这条分支既不是当前版本的祖先也没有被pin
+        assert_eq!(list.gc(), 1);
+        assert!(!list.contains_version(dead));
+        assert!(dead_elem.upgrade().is_none());
+        assert!(!list.checkout(dead));
+        assert!(list.diff(dead, head).is_none());
+        assert!(list.contains_version(Version(0)));
+        // 取消pin后kept也变成不可达
+        list.unpin(kept);
+        assert_eq!(list.gc(), 1);
+        assert_eq!(list.gc(), 0);
+        assert_eq!(list.version_count(), 3);
+        assert_eq!(Rc::strong_count(&shared), 2);
+    }
+}
@@ -0,0 +1,136 @@
+// Graphviz DOT输出: 把链表在内存里真实的指针图画出来
+// 各链表的to_dot()都用这里的Dot拼图，把结果存成.dot文件之后 `dot -Tsvg list.dot -o list.svg` 就能看到
+// - 节点按地址去重: 同一个地址只画一次，持久化链表共享的后缀、双向链表的prev边都会指回已有的节点
+//   遍历时node()返回false说明已经画过，就不用再往下走了，链表里有环也不会死循环
+// - 边的样式区分指针的种类:
+//   Box    实线，独占所有权
+//   Shared 蓝色粗线，Rc共享所有权，节点上标出强引用计数
+//   (不叫Rc: 公开的Edge::Rc会让编译器报错信息里的Rc<T>改写成std::rc::Rc<T>)
+//   Raw    虚线，裸指针/NonNull，不管所有权
+// - 表头(List结构体本身)画成没有边框的文字，从它出发的是head/tail边
+// 元素用Debug格式显示
+
+use std::collections::HashMap;
+use std::fmt::{Debug, Write};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Edge {
+    Box,
+    Shared,
+    Raw,
+}
+
+impl Edge {
+    fn attrs(self) -> &'static str {
+        match self {
+            Edge::Box => "",
+            Edge::Shared => ", color=blue, penwidth=2",
+            Edge::Raw => ", style=dashed",
+        }
+    }
+}
+
+pub struct Dot {
+    body: String,
+    // 节点地址 -> 编号
+    ids: HashMap<usize, usize>,
+    roots: usize,
+}
+
+impl Dot {
+    pub fn new() -> Self {
+        Dot {
+            body: String::new(),
+            ids: HashMap::new(),
+            roots: 0,
+        }
+    }
+
+    // 表头，每次调用都是一个新的
+    pub fn root(&mut self, label: &str) -> String {
+        let name = format!("root{}", self.roots);
+        self.roots += 1;
+        let _ = writeln!(self.body, "    {name} [shape=plaintext, label=\"{}\"];", escape(label));
+        name
+    }
+
+    // 按地址登记一个节点，返回节点名和是否第一次见到；note非空时显示在元素下面一行
+    pub fn node<P: ?Sized>(&mut self, addr: *const P, elem: &dyn Debug, note: &str) -> (String, bool) {
+        let next_id = self.ids.len();
+        let mut new = false;
+        let id = *self.ids.entry(addr as *const () as usize).or_insert_with(|| {
+            new = true;
+            next_id
+        });
+        let name = format!("n{id}");
+        if new {
+            let mut label = escape(&format!("{elem:?}"));
+            if !note.is_empty() {
+                label.push_str("\\n");
+                label.push_str(&escape(note));
+            }
+            let _ = writeln!(self.body, "    {name} [label=\"{label}\"];");
+        }
+        (name, new)
+    }
+
+    pub fn edge(&mut self, from: &str, to: &str, label: &str, kind: Edge) {
+        let _ = writeln!(self.body, "    {from} -> {to} [label=\"{}\"{}];", escape(label), kind.attrs());
+    }
+
+    pub fn finish(self) -> String {
+        format!("digraph list {{\n    rankdir=LR;\n    node [shape=box];\n{}}}\n", self.body)
+    }
+}
+
+impl Default for Dot {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// DOT的双引号字符串里只有"和\需要转义，换行写成\n
+fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nodes_deduplicated_by_address() {
+        let xs = [1, 2];
+        let mut dot = Dot::new();
+        let root = dot.root("List");
+        let (a, new_a) = dot.node(&xs[0], &xs[0], "");
+        let (b, _) = dot.node(&xs[1], &xs[1], "rc=2");
+        let (again, new_again) = dot.node(&xs[0], &xs[0], "");
+        assert!(new_a && !new_again);
+        assert_eq!(a, again);
+        dot.edge(&root, &a, "head", Edge::Box);
+        dot.edge(&a, &b, "next", Edge::Raw);
+        let out = dot.finish();
+        assert_eq!(out.matches("[label=\"1\"]").count(), 1);
+        assert!(out.contains("n1 [label=\"2\\nrc=2\"];"));
+        assert!(out.contains("root0 -> n0 [label=\"head\"];"));
+        assert!(out.contains("n0 -> n1 [label=\"next\", style=dashed];"));
+    }
+
+    #[test]
+    fn labels_escaped() {
+        let s = String::from("say \"hi\"\\");
+        let mut dot = Dot::new();
+        dot.node(&s, &s, "");
+        assert!(dot.finish().contains(r#"[label="\"say \\\"hi\\\"\\\\\""]"#));
+    }
+}
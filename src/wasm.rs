@@ -0,0 +1,96 @@
+// wasm-bindgen导出的双端队列，给浏览器里的链表演示页面用
+// JS里看到的是Deque类，元素是任意JS值，底下就是一条simple_deque_3::List<JsValue>:
+//   const deque = new Deque();
+//   deque.pushBack(1); deque.pushFront("a");
+//   deque.forEach((value, index) => console.log(index, value));
+//   deque.toArray();      // ["a", 1]
+//   deque.popBack();      // 1，空队列返回undefined
+// 用完要调用deque.free()释放Rust这边的内存，JS的垃圾回收管不到
+// 原生目标上连JsValue的销毁都要调用JS运行时，所以这里没有单元测试，队列本身的行为由simple_deque_3的测试覆盖
+
+use js_sys::{Array, Function};
+use wasm_bindgen::prelude::*;
+
+use crate::simple_deque_3::List;
+
+#[wasm_bindgen(js_name = Deque)]
+#[derive(Default)]
+pub struct JsDeque {
+    list: List<JsValue>,
+}
+
+#[wasm_bindgen(js_class = Deque)]
+impl JsDeque {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> JsDeque {
+        JsDeque { list: List::new() }
+    }
+
+    // 按数组顺序从尾部依次放入
+    #[wasm_bindgen(js_name = fromArray)]
+    pub fn from_array(array: &Array) -> JsDeque {
+        JsDeque {
+            list: array.iter().collect(),
+        }
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn length(&self) -> usize {
+        self.list.len()
+    }
+
+    #[wasm_bindgen(js_name = isEmpty)]
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    #[wasm_bindgen(js_name = pushFront)]
+    pub fn push_front(&mut self, value: JsValue) {
+        self.list.push_front(value);
+    }
+
+    #[wasm_bindgen(js_name = pushBack)]
+    pub fn push_back(&mut self, value: JsValue) {
+        self.list.push_back(value);
+    }
+
+    #[wasm_bindgen(js_name = popFront)]
+    pub fn pop_front(&mut self) -> Option<JsValue> {
+        self.list.pop_front()
+    }
+
+    #[wasm_bindgen(js_name = popBack)]
+    pub fn pop_back(&mut self) -> Option<JsValue> {
+        self.list.pop_back()
+    }
+
+    // JsValue是JS堆上对象的句柄，clone只是多一个引用，不会复制对象
+    #[wasm_bindgen(js_name = peekFront)]
+    pub fn peek_front(&self) -> Option<JsValue> {
+        self.list.front().cloned()
+    }
+
+    #[wasm_bindgen(js_name = peekBack)]
+    pub fn peek_back(&self) -> Option<JsValue> {
+        self.list.back().cloned()
+    }
+
+    pub fn clear(&mut self) {
+        self.list.clear();
+    }
+
+    // 从头到尾对每个元素调用callback(value, index)；callback抛出异常时停止遍历并把异常原样抛回JS
+    #[wasm_bindgen(js_name = forEach)]
+    pub fn for_each(&self, callback: &Function) -> Result<(), JsValue> {
+        for (index, value) in self.list.iter().enumerate() {
+            callback.call2(&JsValue::UNDEFINED, value, &JsValue::from(index as u32))?;
+        }
+        Ok(())
+    }
+
+    // 复制成一个新的JS数组，之后修改数组不影响队列
+    #[wasm_bindgen(js_name = toArray)]
+    pub fn to_array(&self) -> Array {
+        self.list.iter().collect()
+    }
+}
@@ -0,0 +1,474 @@
+// 异或链表: 双向链表的经典省内存技巧，每个节点只存一个字段 link = addr(prev) ^ addr(next)
+// 从一端出发时知道上一个节点的地址，就能解出下一个: next = link ^ prev
+// 代价是不能从任意节点出发(必须知道一个邻居)，也没法在中间做O(1)删除，这里只当作unsafe教学示例
+// 顺带得到一个有趣的性质: 整个链表反转只需要交换head和tail
+//
+// 指针和整数之间的转换:
+// 异或之后的整数已经不对应任何一个指针，按Rust的provenance规则无法从它"派生"出合法指针
+// 这里在分配节点时用expose_provenance把地址公开，解码时用with_exposed_provenance_mut取回，
+// 这正是exposed provenance这套API存在的意义: Miri默认模式接受它(会提示int-to-pointer转换)，
+// 但在-Zmiri-strict-provenance下会报错，本模块不能在严格模式下运行
+
+use std::fmt;
+use std::iter::FusedIterator;
+use std::marker::PhantomData;
+use std::ptr;
+
+pub struct XorList<T> {
+    head: *mut Node<T>,
+    tail: *mut Node<T>,
+    len: usize,
+    _boo: PhantomData<Box<Node<T>>>,
+}
+
+struct Node<T> {
+    elem: T,
+    // addr(prev) ^ addr(next)，端点处缺失的邻居记为0
+    link: usize,
+}
+
+fn addr<T>(p: *mut Node<T>) -> usize {
+    p.expose_provenance()
+}
+
+fn from_addr<T>(a: usize) -> *mut Node<T> {
+    ptr::with_exposed_provenance_mut(a)
+}
+
+// push_front/push_back、pop_front/pop_back完全对称，只是两端互换
+// end是要操作的那一端，other是另一端
+unsafe fn push_end<T>(end: &mut *mut Node<T>, other: &mut *mut Node<T>, elem: T) {
+    let new = Box::into_raw(Box::new(Node {
+        elem,
+        link: addr(*end),
+    }));
+    if end.is_null() {
+        *other = new;
+    } else {
+        // 原来的端点: link = neighbour ^ 0，变成 neighbour ^ new
+        (**end).link ^= addr(new);
+    }
+    *end = new;
+}
+
+unsafe fn pop_end<T>(end: &mut *mut Node<T>, other: &mut *mut Node<T>) -> Option<T> {
+    if end.is_null() {
+        return None;
+    }
+    let old = Box::from_raw(*end);
+    // 端点只有一个邻居，link就是它的地址
+    let neighbour = from_addr::<T>(old.link);
+    if neighbour.is_null() {
+        *other = ptr::null_mut();
+    } else {
+        (*neighbour).link ^= addr(*end);
+    }
+    *end = neighbour;
+    Some(old.elem)
+}
+
+impl<T> XorList<T> {
+    pub fn new() -> Self {
+        XorList {
+            head: ptr::null_mut(),
+            tail: ptr::null_mut(),
+            len: 0,
+            _boo: PhantomData,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn push_front(&mut self, elem: T) {
+        unsafe { push_end(&mut self.head, &mut self.tail, elem) };
+        self.len += 1;
+    }
+
+    pub fn push_back(&mut self, elem: T) {
+        unsafe { push_end(&mut self.tail, &mut self.head, elem) };
+        self.len += 1;
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        let elem = unsafe { pop_end(&mut self.head, &mut self.tail) }?;
+        self.len -= 1;
+        Some(elem)
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        let elem = unsafe { pop_end(&mut self.tail, &mut self.head) }?;
+        self.len -= 1;
+        Some(elem)
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        unsafe { self.head.as_ref().map(|n| &n.elem) }
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        unsafe { self.tail.as_ref().map(|n| &n.elem) }
+    }
+
+    pub fn front_mut(&mut self) -> Option<&mut T> {
+        unsafe { self.head.as_mut().map(|n| &mut n.elem) }
+    }
+
+    pub fn back_mut(&mut self) -> Option<&mut T> {
+        unsafe { self.tail.as_mut().map(|n| &mut n.elem) }
+    }
+
+    // 每个节点的link对两个方向是对称的，从tail出发走就是反向遍历，所以交换两端即可
+    pub fn reverse(&mut self) {
+        std::mem::swap(&mut self.head, &mut self.tail);
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            front: self.head,
+            front_prev: 0,
+            back: self.tail,
+            back_next: 0,
+            remaining: self.len,
+            _boo: PhantomData,
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut {
+            front: self.head,
+            front_prev: 0,
+            back: self.tail,
+            back_next: 0,
+            remaining: self.len,
+            _boo: PhantomData,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        while self.pop_front().is_some() {}
+    }
+}
+
+impl<T> Drop for XorList<T> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+impl<T> Default for XorList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for XorList<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: PartialEq> PartialEq for XorList<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Eq> Eq for XorList<T> {}
+
+impl<T: Clone> Clone for XorList<T> {
+    fn clone(&self) -> Self {
+        self.iter().cloned().collect()
+    }
+}
+
+impl<T> Extend<T> for XorList<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for elem in iter {
+            self.push_back(elem);
+        }
+    }
+}
+
+impl<T> FromIterator<T> for XorList<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = Self::new();
+        list.extend(iter);
+        list
+    }
+}
+
+// 两端各自记住走过来的方向上的邻居地址，remaining保证两端相遇后停止
+pub struct Iter<'a, T> {
+    front: *mut Node<T>,
+    front_prev: usize,
+    back: *mut Node<T>,
+    back_next: usize,
+    remaining: usize,
+    _boo: PhantomData<&'a Node<T>>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        unsafe {
+            let node = &*self.front;
+            let next = from_addr(node.link ^ self.front_prev);
+            self.front_prev = addr(self.front);
+            self.front = next;
+            Some(&node.elem)
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T> DoubleEndedIterator for Iter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        unsafe {
+            let node = &*self.back;
+            let prev = from_addr(node.link ^ self.back_next);
+            self.back_next = addr(self.back);
+            self.back = prev;
+            Some(&node.elem)
+        }
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+impl<T> FusedIterator for Iter<'_, T> {}
+
+pub struct IterMut<'a, T> {
+    front: *mut Node<T>,
+    front_prev: usize,
+    back: *mut Node<T>,
+    back_next: usize,
+    remaining: usize,
+    _boo: PhantomData<&'a mut Node<T>>,
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<&'a mut T> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        unsafe {
+            let node = self.front;
+            let next = from_addr((*node).link ^ self.front_prev);
+            self.front_prev = addr(node);
+            self.front = next;
+            Some(&mut (*node).elem)
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T> DoubleEndedIterator for IterMut<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        unsafe {
+            let node = self.back;
+            let prev = from_addr((*node).link ^ self.back_next);
+            self.back_next = addr(node);
+            self.back = prev;
+            Some(&mut (*node).elem)
+        }
+    }
+}
+
+impl<T> ExactSizeIterator for IterMut<'_, T> {}
+impl<T> FusedIterator for IterMut<'_, T> {}
+
+pub struct IntoIter<T>(XorList<T>);
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.0.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.0.len, Some(self.0.len))
+    }
+}
+
+impl<T> DoubleEndedIterator for IntoIter<T> {
+    fn next_back(&mut self) -> Option<T> {
+        self.0.pop_back()
+    }
+}
+
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
+impl<T> IntoIterator for XorList<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter(self)
+    }
+}
+
+impl<'a, T> IntoIterator for &'a XorList<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut XorList<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> IterMut<'a, T> {
+        self.iter_mut()
+    }
+}
+
+unsafe impl<T: Send> Send for XorList<T> {}
+unsafe impl<T: Sync> Sync for XorList<T> {}
+unsafe impl<T: Sync> Send for Iter<'_, T> {}
+unsafe impl<T: Sync> Sync for Iter<'_, T> {}
+unsafe impl<T: Send> Send for IterMut<'_, T> {}
+unsafe impl<T: Sync> Sync for IterMut<'_, T> {}
+
+// Miri说明:
+// - `cargo +nightly miri test xor_list` 在默认模式下通过，每个with_exposed_provenance
+//   处会提示一次int-to-pointer转换，这是预期的
+// - 加上-Zmiri-strict-provenance会在第一次解码地址时报错: 严格模式禁止从整数恢复指针
+// - 写进link的地址必须都经过addr()，如果改用不expose的ptr.addr()，取回的指针没有provenance，
+//   Miri会在第一次解引用时报错；下面的测试覆盖了所有编码和解码路径
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+
+    #[test]
+    fn push_pop_both_ends() {
+        let mut list = XorList::new();
+        assert_eq!(list.pop_front(), None);
+        assert_eq!(list.pop_back(), None);
+        list.push_back(2);
+        list.push_back(3);
+        list.push_front(1);
+        list.push_front(0);
+        assert_eq!(list.len(), 4);
+        assert_eq!(list.front(), Some(&0));
+        assert_eq!(list.back(), Some(&3));
+        assert_eq!(list.pop_back(), Some(3));
+        assert_eq!(list.pop_front(), Some(0));
+        assert_eq!(list.pop_front(), Some(1));
+        assert_eq!(list.pop_front(), Some(2));
+        assert!(list.is_empty());
+        // 清空后两端都应该复位
+        list.push_front(9);
+        assert_eq!(list.back(), Some(&9));
+        assert_eq!(list.pop_back(), Some(9));
+    }
+
+    // 直接检查编码: 中间节点的link是两个邻居地址的异或，端点的link就是唯一邻居的地址
+    #[test]
+    fn link_stores_xor_of_neighbours() {
+        let list: XorList<u8> = (0..3).collect();
+        unsafe {
+            let first = list.head;
+            let last = list.tail;
+            let middle = from_addr::<u8>((*first).link);
+            assert_eq!((*middle).elem, 1);
+            assert_eq!((*middle).link, addr(first) ^ addr(last));
+            assert_eq!((*last).link, addr(middle));
+        }
+    }
+
+    #[test]
+    fn bidirectional_iteration() {
+        let mut list: XorList<i32> = (1..=5).collect();
+        assert_eq!(list.iter().rev().copied().collect::<Vec<_>>(), vec![5, 4, 3, 2, 1]);
+        let mut it = list.iter();
+        assert_eq!(it.next(), Some(&1));
+        assert_eq!(it.next_back(), Some(&5));
+        assert_eq!(it.next(), Some(&2));
+        assert_eq!(it.next_back(), Some(&4));
+        assert_eq!(it.next(), Some(&3));
+        assert_eq!(it.next_back(), None);
+        assert_eq!(it.next(), None);
+
+        for x in list.iter_mut().rev().take(2) {
+            *x *= 10;
+        }
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![1, 2, 3, 40, 50]);
+        assert_eq!(list.into_iter().rev().collect::<Vec<_>>(), vec![50, 40, 3, 2, 1]);
+    }
+
+    #[test]
+    fn reverse_is_constant_time() {
+        let mut list: XorList<i32> = (0..4).collect();
+        list.reverse();
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![3, 2, 1, 0]);
+        list.push_back(-1);
+        list.push_front(4);
+        assert_eq!(list.pop_front(), Some(4));
+        list.reverse();
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![-1, 0, 1, 2, 3]);
+    }
+
+    // 弹出再压入会复用刚释放的地址，编码仍然要正确
+    #[test]
+    fn reused_addresses() {
+        let mut list = XorList::new();
+        for round in 0..50 {
+            list.push_back(round);
+            list.push_front(-round);
+            if round % 3 == 0 {
+                list.pop_back();
+                list.pop_front();
+            }
+        }
+        let forward: Vec<_> = list.iter().copied().collect();
+        let mut backward: Vec<_> = list.iter().rev().copied().collect();
+        backward.reverse();
+        assert_eq!(forward, backward);
+        assert_eq!(forward.len(), list.len());
+    }
+
+    #[test]
+    fn drops_every_element() {
+        let marker = Rc::new(());
+        let mut list = XorList::new();
+        for _ in 0..10 {
+            list.push_back(Rc::clone(&marker));
+        }
+        list.pop_front();
+        assert_eq!(Rc::strong_count(&marker), 10);
+        drop(list);
+        assert_eq!(Rc::strong_count(&marker), 1);
+    }
+}
@@ -0,0 +1,13 @@
+// trybuild编译期测试: 借用、协变、Send/Sync这些性质只有编译器能检查，写成能编译和不能编译的例子固定下来
+// - ui/pass里的必须编译通过(协变、Send/Sync)
+// - ui/fail里的必须编译失败，错误信息和旁边的.stderr一致
+// 升级rustc后错误信息变了，用TRYBUILD=overwrite cargo test --test ui重新生成.stderr，再检查差异
+// 重新生成之后再用--all-features跑一遍: 可选特性引入的公开名字会改变错误信息里类型路径的写法
+
+#[cfg(all(feature = "safe", feature = "persistent", feature = "unsafe-impls", feature = "concurrent"))]
+#[test]
+fn ui() {
+    let t = trybuild::TestCases::new();
+    t.pass("tests/ui/pass/*.rs");
+    t.compile_fail("tests/ui/fail/*.rs");
+}
@@ -0,0 +1,10 @@
+// 游标独占链表，游标还在用时不能再访问链表
+use too_many_linked_list_rs::simple_deque_3::List;
+
+fn main() {
+    let mut list: List<i32> = (0..3).collect();
+    let mut cursor = list.cursor_mut();
+    cursor.move_next();
+    println!("{}", list.len());
+    cursor.remove_current();
+}
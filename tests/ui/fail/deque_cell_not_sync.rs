@@ -0,0 +1,10 @@
+// 元素不是Sync时，链表不能在线程间共享引用
+use std::cell::Cell;
+
+use too_many_linked_list_rs::simple_deque_3::List;
+
+fn assert_sync<T: Sync>() {}
+
+fn main() {
+    assert_sync::<List<Cell<i32>>>();
+}
@@ -0,0 +1,10 @@
+// 元素不是Send时链表也不是
+use std::rc::Rc;
+
+use too_many_linked_list_rs::simple_deque_3::List;
+
+fn main() {
+    let mut list = List::new();
+    list.push_back(Rc::new(1));
+    std::thread::spawn(move || drop(list));
+}
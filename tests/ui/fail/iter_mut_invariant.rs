@@ -0,0 +1,8 @@
+// IterMut能写入元素，所以对T必须不变，否则可以把短命的引用写进装着&'static str的链表
+use too_many_linked_list_rs::simple_deque_3::IterMut;
+
+fn shrink<'i, 'a>(iter: IterMut<'i, &'static str>) -> IterMut<'i, &'a str> {
+    iter
+}
+
+fn main() {}
@@ -0,0 +1,12 @@
+// 迭代器借用链表，不能活得比链表久
+use too_many_linked_list_rs::simple_deque_3::List;
+
+fn main() {
+    let iter = {
+        let list: List<i32> = (0..3).collect();
+        list.iter()
+    };
+    for x in iter {
+        println!("{x}");
+    }
+}
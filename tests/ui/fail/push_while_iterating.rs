@@ -0,0 +1,10 @@
+// 迭代期间不能修改链表
+use too_many_linked_list_rs::simple_stack_2::List;
+
+fn main() {
+    let mut list = List::new();
+    list.push(1);
+    for x in list.iter() {
+        list.push(*x);
+    }
+}
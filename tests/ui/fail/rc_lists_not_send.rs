@@ -0,0 +1,9 @@
+// 内部用Rc共享节点的链表，不管元素是什么都不能跨线程
+use too_many_linked_list_rs::{simple_deque_1, simple_stack_3};
+
+fn assert_send<T: Send>() {}
+
+fn main() {
+    assert_send::<simple_deque_1::List<i32>>();
+    assert_send::<simple_stack_3::List<i32>>();
+}
@@ -0,0 +1,10 @@
+// 无锁栈在线程间共享，元素会被别的线程pop走，所以要求T: Send
+use std::rc::Rc;
+
+use too_many_linked_list_rs::treiber_stack::TreiberStack;
+
+fn assert_sync<T: Sync>() {}
+
+fn main() {
+    assert_sync::<TreiberStack<Rc<i32>>>();
+}
@@ -0,0 +1,11 @@
+// 同一时间只能有一个iter_mut
+use too_many_linked_list_rs::simple_deque_2::List;
+
+fn main() {
+    let mut list = List::new();
+    list.push_back(1);
+    let mut a = list.iter_mut();
+    let mut b = list.iter_mut();
+    *a.next().unwrap() += 1;
+    *b.next().unwrap() += 1;
+}
@@ -0,0 +1,37 @@
+// 这些类型都必须对T(和生命周期)协变: 装着&'static str的容器可以当作装着更短引用的容器使用
+use too_many_linked_list_rs::{simple_deque_3, simple_stack_2, simple_stack_3};
+
+fn deque<'a>(list: simple_deque_3::List<&'static str>) -> simple_deque_3::List<&'a str> {
+    list
+}
+
+fn deque_iter<'i, 'a>(iter: simple_deque_3::Iter<'i, &'static str>) -> simple_deque_3::Iter<'i, &'a str> {
+    iter
+}
+
+fn deque_into_iter<'a>(iter: simple_deque_3::IntoIter<&'static str>) -> simple_deque_3::IntoIter<&'a str> {
+    iter
+}
+
+fn stack<'a>(list: simple_stack_2::List<&'static str>) -> simple_stack_2::List<&'a str> {
+    list
+}
+
+fn stack_iter<'i, 'a>(iter: simple_stack_2::Iter<'i, &'static str>) -> simple_stack_2::Iter<'i, &'a str> {
+    iter
+}
+
+fn persistent<'a>(list: simple_stack_3::List<&'static str>) -> simple_stack_3::List<&'a str> {
+    list
+}
+
+fn main() {
+    let local = String::from("local");
+    let mut list = deque(simple_deque_3::List::new());
+    list.push_back(local.as_str());
+    let _ = deque_iter(simple_deque_3::List::new().iter());
+    let _ = deque_into_iter(simple_deque_3::List::new().into_iter());
+    let _ = stack(simple_stack_2::List::new());
+    let _ = stack_iter(simple_stack_2::List::new().iter());
+    let _ = persistent(simple_stack_3::List::new());
+}
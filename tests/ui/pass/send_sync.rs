@@ -0,0 +1,18 @@
+// 元素是Send/Sync时，容器和拥有所有权的迭代器也是
+use too_many_linked_list_rs::ms_queue::MsQueue;
+use too_many_linked_list_rs::simple_deque_3;
+use too_many_linked_list_rs::treiber_stack::TreiberStack;
+
+fn assert_send<T: Send>() {}
+fn assert_sync<T: Sync>() {}
+
+fn main() {
+    assert_send::<simple_deque_3::List<String>>();
+    assert_sync::<simple_deque_3::List<String>>();
+    assert_send::<simple_deque_3::IntoIter<String>>();
+    assert_send::<TreiberStack<String>>();
+    assert_sync::<TreiberStack<String>>();
+    assert_sync::<MsQueue<String>>();
+    // 无锁结构只要求T: Send就能共享
+    assert_sync::<TreiberStack<std::cell::Cell<i32>>>();
+}